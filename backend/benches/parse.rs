@@ -0,0 +1,70 @@
+use backend::models::hash::ContentHash;
+use backend::models::link::{Link, LinkRef};
+use backend::models::topology::Topology;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Number of links of the large topology, the size of the biggest controllers seen
+const LINKS: usize = 50_000;
+
+/// Returns a TAPI link as reported by the controllers, with its usual attributes
+fn link(index: usize) -> Value {
+    let uuid = |kind: u128| Uuid::from_u128((kind << 64) | index as u128).to_string();
+    json!({
+        "uuid": uuid(1),
+        "name": [{"value-name": "LINK_NAME", "value": format!("LINK-{}", index)}],
+        "direction": "BIDIRECTIONAL",
+        "layer-protocol-name": ["PHOTONIC_MEDIA"],
+        "lifecycle-state": "INSTALLED",
+        "administrative-state": "UNLOCKED",
+        "operational-state": "ENABLED",
+        "cost-characteristic": [
+            {"cost-name": "HOP_COUNT", "cost-value": "1", "cost-algorithm": "STATIC"}
+        ],
+        "latency-characteristic": [
+            {"traffic-property-name": "FIXED_LATENCY", "fixed-latency-characteristic": "120"}
+        ],
+        "node-edge-point": [
+            {
+                "topology-uuid": uuid(2),
+                "node-uuid": uuid(3),
+                "node-edge-point-uuid": uuid(4)
+            },
+            {
+                "topology-uuid": uuid(2),
+                "node-uuid": uuid(5),
+                "node-edge-point-uuid": uuid(6)
+            }
+        ]
+    })
+}
+
+fn parse(c: &mut Criterion) {
+    let single = link(0);
+    c.bench_function("link_from_value", |b| {
+        b.iter(|| Link::from_value(black_box(&single), "10.0.0.1").unwrap())
+    });
+    c.bench_function("link_ref_from_value", |b| {
+        b.iter(|| LinkRef::from_value(black_box(&single)).unwrap())
+    });
+    c.bench_function("content_hash", |b| {
+        b.iter(|| ContentHash::of(black_box(&single)))
+    });
+
+    let topology = json!({
+        "uuid": Uuid::nil().to_string(),
+        "node": [],
+        "link": (0..LINKS).map(link).collect::<Vec<Value>>(),
+    });
+    let mut group = c.benchmark_group("topology");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(LINKS as u64));
+    group.bench_function("from_value_50k_links", |b| {
+        b.iter(|| Topology::from_value(black_box(&topology), "10.0.0.1").unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,7 @@
+// Generates the gRPC services of `proto/device_manager.proto`, with the bundled `protoc`
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    println!("cargo:rerun-if-changed=proto/device_manager.proto");
+    tonic_build::compile_protos("proto/device_manager.proto")?;
+    Ok(())
+}
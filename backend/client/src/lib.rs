@@ -0,0 +1,139 @@
+//! Typed client of the Device Manager gRPC API
+//!
+//! Wraps the services of `proto/device_manager.proto`, so automation tools call
+//! `list_devices`, `get_topology` and `subscribe_events` instead of building requests.
+//! Messages are the types generated in the backend, and events are read back into the
+//! backend [`ChangeEvent`].
+
+use backend::api::grpc::change_event;
+use backend::api::grpc::proto::device_service_client::DeviceServiceClient;
+use backend::api::grpc::proto::event_service_client::EventServiceClient;
+use backend::api::grpc::proto::topology_service_client::TopologyServiceClient;
+use backend::api::grpc::proto::{
+    Device, GetTopologyRequest, ListDevicesRequest, SubscribeRequest, Topology,
+};
+use backend::events::ChangeEvent;
+use backend::{Error, Result};
+
+use tokio_stream::{Stream, StreamExt};
+use tonic::metadata::AsciiMetadataValue;
+use tonic::transport::Channel;
+use tonic::{Request, Status};
+
+/// Connection to the gRPC API of a Device Manager
+#[derive(Clone)]
+pub struct Client {
+    devices: DeviceServiceClient<Channel>,
+    topology: TopologyServiceClient<Channel>,
+    events: EventServiceClient<Channel>,
+    authorization: AsciiMetadataValue, // `Bearer <token>`, sent with every call
+}
+
+impl Client {
+    /// Connects to the API served at `url`, e.g. `http://127.0.0.1:50051`
+    ///
+    /// # Arguments
+    /// - `url`: The address of the API
+    /// - `token`: An API token of the `[api]` section of the server config; calls see
+    ///   the devices and events of its tenant
+    ///
+    /// # Returns
+    /// - `Ok(Client)`: Once connected, the connection being shared by every service
+    /// - `Err(Error)`: If the URL or the token is invalid or nothing answers there
+    pub async fn connect(url: &str, token: &str) -> Result<Self> {
+        let authorization = format!("Bearer {}", token)
+            .parse()
+            .map_err(|_| Error::Invalid("Invalid API token".to_string()))?;
+        let channel = Channel::from_shared(url.to_string())
+            .map_err(|_| Error::Custom(format!("Invalid API url {}", url)))?
+            .connect()
+            .await
+            .map_err(|err| Error::Custom(format!("Failed to connect to {}: {}", url, err)))?;
+        Ok(Client {
+            devices: DeviceServiceClient::new(channel.clone()),
+            topology: TopologyServiceClient::new(channel.clone()),
+            events: EventServiceClient::new(channel),
+            authorization,
+        })
+    }
+
+    /// Wraps a message in a request carrying the token of the client
+    fn request<T>(&self, message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request
+            .metadata_mut()
+            .insert("authorization", self.authorization.clone());
+        request
+    }
+
+    /// Returns the health of every device of the tenant, in registry order
+    pub async fn list_devices(&mut self) -> Result<Vec<Device>> {
+        let request = self.request(ListDevicesRequest {});
+        let response = self.devices.list_devices(request).await.map_err(failed)?;
+        Ok(response.into_inner().devices)
+    }
+
+    /// Returns the last topologies of `host`
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Topology>)`: The topologies of the last collection
+    /// - `Err(Error)`: If `host` was not collected yet or belongs to another tenant, or
+    ///   the request failed
+    pub async fn get_topology(&mut self, host: &str) -> Result<Vec<Topology>> {
+        self.topology_of(host, None).await
+    }
+
+    /// Returns the last topologies of `host` with only their nodes and links in
+    /// `lifecycle_state`, e.g. `PLANNED`
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Topology>)`: The filtered topologies of the last collection
+    /// - `Err(Error)`: If the state is unknown, `host` was not collected yet or belongs
+    ///   to another tenant, or the request failed
+    pub async fn get_topology_in_state(
+        &mut self,
+        host: &str,
+        lifecycle_state: &str,
+    ) -> Result<Vec<Topology>> {
+        self.topology_of(host, Some(lifecycle_state)).await
+    }
+
+    async fn topology_of(
+        &mut self,
+        host: &str,
+        lifecycle_state: Option<&str>,
+    ) -> Result<Vec<Topology>> {
+        let request = self.request(GetTopologyRequest {
+            host: host.to_string(),
+            lifecycle_state: lifecycle_state.map(str::to_string),
+        });
+        let response = self.topology.get_topology(request).await.map_err(failed)?;
+        Ok(response.into_inner().topologies)
+    }
+
+    /// Follows the change events of the tenant published from now on
+    ///
+    /// # Arguments
+    /// - `hosts`: The devices to follow, every device when empty
+    ///
+    /// # Returns
+    /// - `Ok(Stream)`: The events, ending when the server closes the stream
+    /// - `Err(Error)`: If the subscription was refused
+    pub async fn subscribe_events(
+        &mut self,
+        hosts: &[&str],
+    ) -> Result<impl Stream<Item = Result<ChangeEvent>>> {
+        let request = self.request(SubscribeRequest {
+            hosts: hosts.iter().map(|host| host.to_string()).collect(),
+        });
+        let stream = self.events.subscribe(request).await.map_err(failed)?;
+        Ok(stream
+            .into_inner()
+            .map(|message| change_event(&message.map_err(failed)?)))
+    }
+}
+
+/// Maps a failed call, keeping the error code and message of the server
+fn failed(status: Status) -> Error {
+    Error::Custom(status.message().to_string())
+}
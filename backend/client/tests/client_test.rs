@@ -0,0 +1,67 @@
+use backend::api::auth::hash; // Import the hash of the configured API tokens
+use backend::api::grpc::serve; // Import the gRPC API served by the backend
+use backend::setup::app::{App, AppSettings};
+use backend::Error;
+use client::Client; // Import the typed API client
+use std::sync::Arc;
+
+/// # Test: `test_client`
+///
+/// This test verifies that the client lists the devices of a running application with
+/// its token, and reports the errors of the server with their code.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_client() {
+    let directory = std::env::temp_dir().join(format!("client_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let settings = AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        ..Default::default()
+    };
+    std::fs::write(
+        &settings.config,
+        format!(
+            "poll_interval = 60\n\n[[api.tokens]]\ntenant_id = \"default\"\nsha256 = \"{}\"\n",
+            hash("client-token")
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        &settings.devices,
+        r#"[{"host": "127.0.0.1", "auth": {"username": "tapi", "password": "secret"}}]"#,
+    )
+    .unwrap();
+    let app = Arc::new(App::start(&settings).unwrap());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(serve(Arc::clone(&app), listener));
+
+    let mut client = Client::connect(&url, "client-token").await.unwrap();
+    let devices = client.list_devices().await.unwrap();
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].host, "127.0.0.1");
+
+    match client.get_topology("127.0.0.1").await {
+        Err(Error::Custom(msg)) => {
+            assert_eq!(msg, "TOPOLOGY_NOT_FOUND: Not found topology of 127.0.0.1")
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    assert!(client.subscribe_events(&["127.0.0.1"]).await.is_ok());
+
+    let mut intruder = Client::connect(&url, "other-token").await.unwrap();
+    match intruder.list_devices().await {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "UNAUTHORIZED: Invalid API token"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+
+    match Client::connect("not a url", "client-token").await {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "Invalid API url not a url"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but the client connected"),
+    }
+    server.abort();
+}
@@ -0,0 +1,112 @@
+use crate::models::device::Device;
+use crate::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Action run over every device of a tag
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum BulkAction {
+    PausePolling, // Stops collecting the device until it is resumed, lost on restart
+    Resume,       // Collects a paused device again, at once
+    TriggerPoll,  // Collects the device now instead of at its next run
+    HealthCheck,  // Probes the latency of the device next to its schedule
+}
+
+/// A bulk action, the body of `POST /actions/bulk`
+///
+/// ```json
+/// {"tag": "production", "action": "pause-polling"}
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BulkRequest {
+    pub tag: String,
+    pub action: BulkAction,
+}
+
+impl BulkRequest {
+    /// Returns the hosts of the devices tagged with the tag of the request
+    ///
+    /// # Returns
+    /// - `Ok(Vec<String>)`: The hosts, in registry order
+    /// - `Err(Error)`: If no device has the tag
+    pub fn select<'a>(&self, devices: impl IntoIterator<Item = &'a Device>) -> Result<Vec<String>> {
+        let hosts: Vec<String> = devices
+            .into_iter()
+            .filter(|device| device.tags.contains(&self.tag))
+            .map(|device| device.host.to_string())
+            .collect();
+        if hosts.is_empty() {
+            return Err(Error::NotFound(format!(
+                "Not found devices tagged {}",
+                self.tag
+            )));
+        }
+        Ok(hosts)
+    }
+}
+
+/// Outcome of a bulk action on one device
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeviceActionResult {
+    pub host: String,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>, // What the action found, e.g. the latency of a health check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>, // Why the action does not apply to the device
+}
+
+impl DeviceActionResult {
+    /// Returns the result of an action that succeeded on `host`
+    pub fn done(host: &str, result: Value) -> Self {
+        DeviceActionResult {
+            host: host.to_string(),
+            ok: true,
+            result: Some(result),
+            error: None,
+            skipped: None,
+        }
+    }
+
+    /// Returns the result of an action that failed on `host`
+    pub fn failed(host: &str, error: &Error) -> Self {
+        DeviceActionResult {
+            host: host.to_string(),
+            ok: false,
+            result: None,
+            error: Some(error.to_string()),
+            skipped: None,
+        }
+    }
+
+    /// Returns the result of an action that does not apply to `host`
+    ///
+    /// A skipped device is neither ok nor failed.
+    pub fn skipped(host: &str, reason: &str) -> Self {
+        DeviceActionResult {
+            host: host.to_string(),
+            ok: false,
+            result: None,
+            error: None,
+            skipped: Some(reason.to_string()),
+        }
+    }
+}
+
+/// Result of a bulk action job, in the `result` of `GET /jobs/{id}`
+///
+/// The job is done once every device was handled, whether the action succeeded on
+/// each of them or not; `failed` counts the devices it did not succeed on and
+/// `skipped` the devices it does not apply to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BulkReport {
+    pub tag: String,
+    pub action: BulkAction,
+    pub failed: usize,
+    pub skipped: usize,
+    pub devices: Vec<DeviceActionResult>, // In registry order
+}
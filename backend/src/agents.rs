@@ -0,0 +1,296 @@
+use crate::models::topology::Topology;
+use crate::persist;
+use crate::{Error, Result};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Edge agent enrolled as a managed data source
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Agent {
+    pub id: Uuid,
+    pub tenant_id: String, // Customer the agent collects for, every ingest is scoped to it
+    pub name: String,
+    pub enrolled_at: DateTime<Local>,
+    pub last_seen: Option<DateTime<Local>>, // Last accepted ingest
+    #[serde(skip)]
+    secret_hash: [u8; 32],
+}
+
+/// Credentials handed to an agent when it enrolls, shown only once
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct AgentCredentials {
+    pub agent_id: Uuid,
+    pub secret: String,
+}
+
+/// Pending one-time enrollment token
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct EnrollmentToken {
+    tenant_id: String,
+    label: String,
+    expires_at: DateTime<Local>,
+}
+
+/// Registry of edge agents and their enrollment tokens
+///
+/// An operator issues a one-time token; the edge collector redeems it with
+/// [`AgentRegistry::enroll`] and receives its own credentials, which it then uses to
+/// push locally collected topologies through [`AgentRegistry::ingest`]. Only SHA-256
+/// hashes of tokens and secrets are kept. With [`AgentRegistry::open`] the registry is
+/// saved to a JSON file on every change, so agents stay enrolled across restarts.
+#[derive(Default)]
+pub struct AgentRegistry {
+    path: Option<PathBuf>,
+    tokens: HashMap<[u8; 32], EnrollmentToken>,
+    agents: HashMap<Uuid, Agent>,
+}
+
+/// Content of the file of a persisted registry, hashes written as hex
+#[derive(Serialize, Deserialize, Default)]
+struct StoredRegistry {
+    tokens: Vec<StoredToken>,
+    agents: Vec<StoredAgent>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredToken {
+    sha256: String,
+    #[serde(flatten)]
+    token: EnrollmentToken,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredAgent {
+    secret_sha256: String,
+    #[serde(flatten)]
+    agent: Agent,
+}
+
+impl AgentRegistry {
+    /// Creates an empty in-memory registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the registry persisted to `path`, loading the agents already enrolled and
+    /// the tokens not redeemed yet
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let stored: StoredRegistry = persist::read_json(&path, "agents")?.unwrap_or_default();
+
+        let mut tokens = HashMap::new();
+        for stored in stored.tokens {
+            tokens.insert(from_hex(&stored.sha256)?, stored.token);
+        }
+        let mut agents = HashMap::new();
+        for stored in stored.agents {
+            let mut agent = stored.agent;
+            agent.secret_hash = from_hex(&stored.secret_sha256)?;
+            agents.insert(agent.id, agent);
+        }
+
+        Ok(AgentRegistry {
+            path: Some(path),
+            tokens,
+            agents,
+        })
+    }
+
+    /// Issues a one-time enrollment token for an agent of `tenant_id`, valid for `ttl`
+    ///
+    /// # Returns
+    /// - `Ok(String)`: The token, to be handed to the agent
+    /// - `Err(Error)`: If the registry could not be saved
+    pub fn issue_token(&mut self, tenant_id: &str, label: &str, ttl: Duration) -> Result<String> {
+        let now = Local::now();
+        // Tokens nobody redeemed in time would otherwise be kept forever
+        self.tokens.retain(|_, token| token.expires_at > now);
+        let token = random_secret();
+        self.tokens.insert(
+            hash(&token),
+            EnrollmentToken {
+                tenant_id: tenant_id.to_string(),
+                label: label.to_string(),
+                expires_at: now + ttl,
+            },
+        );
+        self.save()?;
+        Ok(token)
+    }
+
+    /// Redeems an enrollment token, registering the agent `name`
+    ///
+    /// The token is consumed whether or not it expired, so it can never be replayed.
+    pub fn enroll(&mut self, token: &str, name: &str) -> Result<AgentCredentials> {
+        let enrollment = self
+            .tokens
+            .remove(&hash(token))
+            .ok_or_else(|| Error::Unauthorized("Invalid enrollment token".to_string()))?;
+        if enrollment.expires_at <= Local::now() {
+            self.save()?;
+            return Err(Error::Unauthorized(format!(
+                "Expired enrollment token {}",
+                enrollment.label
+            )));
+        }
+
+        let secret = random_secret();
+        let agent = Agent {
+            id: Uuid::new_v4(),
+            tenant_id: enrollment.tenant_id,
+            name: name.to_string(),
+            enrolled_at: Local::now(),
+            last_seen: None,
+            secret_hash: hash(&secret),
+        };
+        let credentials = AgentCredentials {
+            agent_id: agent.id,
+            secret,
+        };
+        self.agents.insert(agent.id, agent);
+        self.save()?;
+        Ok(credentials)
+    }
+
+    /// Returns the agents enrolled for `tenant_id`
+    pub fn agents(&self, tenant_id: &str) -> Vec<&Agent> {
+        let mut agents: Vec<&Agent> = self
+            .agents
+            .values()
+            .filter(|agent| agent.tenant_id == tenant_id)
+            .collect();
+        agents.sort_by_key(|agent| agent.enrolled_at);
+        agents
+    }
+
+    /// Revokes an agent; its credentials are rejected from then on
+    ///
+    /// # Returns
+    /// - `Ok(Option<Agent>)`: The revoked agent, none if it was not enrolled
+    /// - `Err(Error)`: If the registry could not be saved
+    pub fn revoke(&mut self, agent_id: &Uuid) -> Result<Option<Agent>> {
+        let agent = self.agents.remove(agent_id);
+        if agent.is_some() {
+            self.save()?;
+        }
+        Ok(agent)
+    }
+
+    /// Checks the credentials of an agent
+    ///
+    /// The returned agent carries the tenant its data must be stored under.
+    pub fn authenticate(&self, agent_id: &Uuid, secret: &str) -> Result<&Agent> {
+        self.agents
+            .get(agent_id)
+            .filter(|agent| agent.secret_hash == hash(secret))
+            .ok_or_else(|| Error::Unauthorized("Invalid agent credentials".to_string()))
+    }
+
+    /// Accepts a topology pushed by an agent for the device at `host`
+    ///
+    /// # Arguments
+    /// - `agent_id`, `secret`: Credentials of the agent
+    /// - `host`: Device the agent collected the topology from
+    /// - `value`: The `tapi-topology:topology` payload
+    ///
+    /// # Returns
+    /// - `Ok((tenant_id, Topology))`: The tenant of the agent and the parsed topology,
+    ///   ready to be stored and diffed under that tenant
+    /// - `Err(Error)`: If the credentials or the payload are invalid, or the registry
+    ///   could not be saved
+    pub fn ingest(
+        &mut self,
+        agent_id: &Uuid,
+        secret: &str,
+        host: &str,
+        value: &Value,
+    ) -> Result<(String, Topology)> {
+        let tenant_id = self.authenticate(agent_id, secret)?.tenant_id.clone();
+        let topology = Topology::from_value(value, host)?;
+
+        if let Some(agent) = self.agents.get_mut(agent_id) {
+            agent.last_seen = Some(Local::now());
+        }
+        self.save()?;
+        Ok((tenant_id, topology))
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            let stored = StoredRegistry {
+                tokens: self
+                    .tokens
+                    .iter()
+                    .map(|(sha256, token)| StoredToken {
+                        sha256: hex(sha256),
+                        token: token.clone(),
+                    })
+                    .collect(),
+                agents: self
+                    .agents
+                    .values()
+                    .map(|agent| StoredAgent {
+                        secret_sha256: hex(&agent.secret_hash),
+                        agent: agent.clone(),
+                    })
+                    .collect(),
+            };
+            persist::write_json(path, &stored, "agents")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the credentials of an agent from the `authorization` header of its requests,
+/// `Basic` with the agent id as user and its secret as password
+///
+/// # Returns
+/// - `Ok((Uuid, String))`: The agent id and the secret
+/// - `Err(Error)`: If the header is missing or malformed
+pub fn basic_credentials(authorization: Option<&str>) -> Result<(Uuid, String)> {
+    let invalid = || Error::Unauthorized("Invalid agent credentials".to_string());
+    let encoded = authorization
+        .and_then(|header| header.strip_prefix("Basic "))
+        .ok_or_else(|| Error::Unauthorized("Missing agent credentials".to_string()))?;
+    let decoded = STANDARD
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .ok_or_else(invalid)?;
+    let (agent_id, secret) = decoded.split_once(':').ok_or_else(invalid)?;
+    let agent_id = Uuid::parse_str(agent_id).map_err(|_| invalid())?;
+    Ok((agent_id, secret.to_string()))
+}
+
+/// Returns a random 244-bit secret as hex
+fn random_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+fn hash(secret: &str) -> [u8; 32] {
+    Sha256::digest(secret.as_bytes()).into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<[u8; 32]> {
+    let invalid = || Error::Custom(format!("Invalid agents file: bad hash {}", hex));
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let mut hash = [0u8; 32];
+    for (index, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(hash)
+}
@@ -0,0 +1,122 @@
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// `Cache-Control` of assets whose file name carries a content hash
+pub const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// `Cache-Control` of `index.html` and unhashed assets, revalidated on every load
+pub const REVALIDATE: &str = "no-cache";
+
+/// Settings of the frontend serving, the `[frontend]` section of `config.toml`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FrontendConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub directory: Option<PathBuf>, // Compiled frontend (`trunk build` output), not served if unset
+}
+
+/// A file to answer a frontend request with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asset {
+    pub path: PathBuf,
+    pub content_type: &'static str,
+    pub cache_control: &'static str,
+}
+
+/// Compiled frontend served by the backend, so small deployments run a single process
+///
+/// Requests for existing files get the file; any other path is a route of the single
+/// page application and gets `index.html`, except missing assets (`/missing.js`) which
+/// stay 404. Hashed assets produced by `trunk` are cached for good,
+/// `index.html` is revalidated so new releases are picked up.
+#[derive(Debug, Clone)]
+pub struct StaticAssets {
+    directory: PathBuf,
+}
+
+impl StaticAssets {
+    /// Serves the files of `directory`
+    pub fn new(directory: impl AsRef<Path>) -> Self {
+        StaticAssets {
+            directory: directory.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Resolves the request path of a `GET` outside the API routes
+    ///
+    /// # Returns
+    /// - `Some(Asset)`: The file to answer with, `index.html` for application routes
+    /// - `None`: If the path escapes the directory or names a missing file
+    pub fn resolve(&self, request_path: &str) -> Option<Asset> {
+        let relative = Path::new(
+            request_path
+                .split(['?', '#'])
+                .next()?
+                .trim_start_matches('/'),
+        );
+        // Only plain names, so `..` cannot reach files outside the directory
+        if !relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+
+        let path = self.directory.join(relative);
+        if path.is_file() {
+            return Some(asset(path));
+        }
+
+        // Routes may contain dots (`/devices/10.0.0.1`), only known asset types are 404
+        match content_type(relative) {
+            Some(_) => None,
+            None => {
+                let index = self.directory.join("index.html");
+                index.is_file().then(|| asset(index))
+            }
+        }
+    }
+}
+
+/// Returns the content type of the known asset types
+fn content_type(path: &Path) -> Option<&'static str> {
+    match path.extension()?.to_str()? {
+        "html" => Some("text/html; charset=utf-8"),
+        "js" => Some("text/javascript; charset=utf-8"),
+        "css" => Some("text/css; charset=utf-8"),
+        "wasm" => Some("application/wasm"),
+        "json" | "map" => Some("application/json"),
+        "svg" => Some("image/svg+xml"),
+        "png" => Some("image/png"),
+        "ico" => Some("image/x-icon"),
+        "woff2" => Some("font/woff2"),
+        _ => None,
+    }
+}
+
+fn asset(path: PathBuf) -> Asset {
+    let content_type = content_type(&path).unwrap_or("application/octet-stream");
+    let cache_control = if is_hashed(&path) {
+        IMMUTABLE
+    } else {
+        REVALIDATE
+    };
+
+    Asset {
+        path,
+        content_type,
+        cache_control,
+    }
+}
+
+/// Whether the file name ends with a content hash, as in `frontend-4c2a81f0e1b3d9a7.js`
+fn is_hashed(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let stem = name.split('.').next().unwrap_or_default();
+    let stem = stem.strip_suffix("_bg").unwrap_or(stem);
+    stem.rsplit_once('-').is_some_and(|(_, hash)| {
+        hash.len() >= 8 && hash.chars().all(|char| char.is_ascii_hexdigit())
+    })
+}
@@ -0,0 +1,95 @@
+use crate::{Error, Result};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Tokens of the API clients, the `[api]` section of `config.toml`
+///
+/// ```toml
+/// [[api.tokens]]
+/// tenant_id = "acme"
+/// name = "ci"
+/// sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+///
+/// [api]
+/// cursor_secret = "change-me"
+/// ```
+///
+/// Only the SHA-256 hash of a token is configured; clients send the token itself as
+/// `authorization: Bearer <token>`. Every request is scoped to the tenant of its token, and
+/// the operations it does are recorded in the audit log under the name of the token.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ApiConfig {
+    #[serde(default)]
+    pub tokens: Vec<ApiToken>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor_secret: Option<String>, // Key of the pagination cursors, random per start if unset
+}
+
+/// Token of an API client
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ApiToken {
+    pub tenant_id: String, // Tenant the requests of the token are scoped to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>, // Actor of the audit entries, `<tenant>:<hash prefix>` if unset
+    pub sha256: String,    // Hex SHA-256 hash of the token
+}
+
+/// Client of an API request, resolved from its token
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caller {
+    pub tenant_id: String, // Tenant the request is scoped to
+    pub actor: String,     // Who the audit log records the operations of the request under
+}
+
+impl ApiToken {
+    /// Returns the actor of the audit entries of the token
+    pub fn actor(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!(
+                "{}:{}",
+                self.tenant_id,
+                &self.sha256[..self.sha256.len().min(8)].to_ascii_lowercase()
+            ),
+        }
+    }
+}
+
+impl ApiConfig {
+    /// Resolves the tenant of a request from its `authorization` header
+    ///
+    /// # Returns
+    /// - `Ok(String)`: The tenant of the token
+    /// - `Err(Error)`: If the header is missing, not a bearer token or an unknown token
+    pub fn tenant(&self, authorization: Option<&str>) -> Result<String> {
+        self.caller(authorization).map(|caller| caller.tenant_id)
+    }
+
+    /// Resolves the tenant and the actor of a request from its `authorization` header
+    ///
+    /// # Returns
+    /// - `Ok(Caller)`: The tenant and the actor of the token
+    /// - `Err(Error)`: If the header is missing, not a bearer token or an unknown token
+    pub fn caller(&self, authorization: Option<&str>) -> Result<Caller> {
+        let token = authorization
+            .and_then(|header| header.strip_prefix("Bearer "))
+            .map(str::trim)
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| Error::Unauthorized("Missing API token".to_string()))?;
+        let hash = hash(token);
+        self.tokens
+            .iter()
+            .find(|known| known.sha256.eq_ignore_ascii_case(&hash))
+            .map(|known| Caller {
+                tenant_id: known.tenant_id.clone(),
+                actor: known.actor(),
+            })
+            .ok_or_else(|| Error::Unauthorized("Invalid API token".to_string()))
+    }
+}
+
+/// Returns the hex SHA-256 hash of a token, as configured in `[api]`
+pub fn hash(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,176 @@
+use super::request::RequestId;
+
+// Import necessary traits for serialization and deserialization
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Stable, machine-readable API error codes
+///
+/// Codes are part of the public API contract: client SDKs and the frontend match on
+/// them, so existing codes must never be renamed or removed. The full list is served at
+/// runtime by `GET /meta/error-codes` (see [`catalog`]).
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// The request body or query parameters are malformed
+    InvalidRequest,
+    /// One or more fields failed validation; `details` lists every problem
+    ValidationFailed,
+    /// The pagination cursor was forged, expired or issued for another query
+    InvalidCursor,
+    /// Missing or invalid credentials
+    Unauthorized,
+    /// No device is registered for the requested host
+    DeviceNotFound,
+    /// The requested topology, node or link does not exist
+    TopologyNotFound,
+    /// A device with the same host (or resolved address) is already registered
+    DeviceConflict,
+    /// The device could not be reached or refused the connection
+    DeviceUnreachable,
+    /// The device answered with a payload that could not be parsed
+    DeviceBadResponse,
+    /// The device did not answer in time
+    DeviceTimeout,
+    /// Unexpected server-side failure
+    Internal,
+    /// The `Range` of a download starts beyond the content
+    RangeNotSatisfiable,
+}
+
+impl ErrorCode {
+    /// Every error code, in a stable order
+    pub const ALL: [ErrorCode; 12] = [
+        ErrorCode::InvalidRequest,
+        ErrorCode::ValidationFailed,
+        ErrorCode::InvalidCursor,
+        ErrorCode::Unauthorized,
+        ErrorCode::DeviceNotFound,
+        ErrorCode::TopologyNotFound,
+        ErrorCode::DeviceConflict,
+        ErrorCode::DeviceUnreachable,
+        ErrorCode::DeviceBadResponse,
+        ErrorCode::DeviceTimeout,
+        ErrorCode::Internal,
+        ErrorCode::RangeNotSatisfiable,
+    ];
+
+    /// Returns the code as sent on the wire (e.g. `DEVICE_NOT_FOUND`)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidRequest => "INVALID_REQUEST",
+            ErrorCode::ValidationFailed => "VALIDATION_FAILED",
+            ErrorCode::InvalidCursor => "INVALID_CURSOR",
+            ErrorCode::Unauthorized => "UNAUTHORIZED",
+            ErrorCode::DeviceNotFound => "DEVICE_NOT_FOUND",
+            ErrorCode::TopologyNotFound => "TOPOLOGY_NOT_FOUND",
+            ErrorCode::DeviceConflict => "DEVICE_CONFLICT",
+            ErrorCode::DeviceUnreachable => "DEVICE_UNREACHABLE",
+            ErrorCode::DeviceBadResponse => "DEVICE_BAD_RESPONSE",
+            ErrorCode::DeviceTimeout => "DEVICE_TIMEOUT",
+            ErrorCode::Internal => "INTERNAL",
+            ErrorCode::RangeNotSatisfiable => "RANGE_NOT_SATISFIABLE",
+        }
+    }
+
+    /// Returns the HTTP status the code is answered with
+    pub fn http_status(&self) -> u16 {
+        match self {
+            ErrorCode::InvalidRequest | ErrorCode::ValidationFailed | ErrorCode::InvalidCursor => {
+                400
+            }
+            ErrorCode::Unauthorized => 401,
+            ErrorCode::DeviceNotFound | ErrorCode::TopologyNotFound => 404,
+            ErrorCode::DeviceConflict => 409,
+            ErrorCode::DeviceUnreachable | ErrorCode::DeviceBadResponse => 502,
+            ErrorCode::DeviceTimeout => 504,
+            ErrorCode::Internal => 500,
+            ErrorCode::RangeNotSatisfiable => 416,
+        }
+    }
+
+    /// Returns the status the code is answered with by the gRPC API
+    pub fn grpc_code(&self) -> tonic::Code {
+        match self {
+            ErrorCode::InvalidRequest | ErrorCode::ValidationFailed | ErrorCode::InvalidCursor => {
+                tonic::Code::InvalidArgument
+            }
+            ErrorCode::Unauthorized => tonic::Code::Unauthenticated,
+            ErrorCode::DeviceNotFound | ErrorCode::TopologyNotFound => tonic::Code::NotFound,
+            ErrorCode::DeviceConflict => tonic::Code::AlreadyExists,
+            ErrorCode::DeviceUnreachable | ErrorCode::DeviceBadResponse => tonic::Code::Unavailable,
+            ErrorCode::DeviceTimeout => tonic::Code::DeadlineExceeded,
+            ErrorCode::Internal => tonic::Code::Internal,
+            ErrorCode::RangeNotSatisfiable => tonic::Code::OutOfRange,
+        }
+    }
+
+    /// Returns a human readable description of the code
+    pub fn description(&self) -> &'static str {
+        match self {
+            ErrorCode::InvalidRequest => "The request body or query parameters are malformed",
+            ErrorCode::ValidationFailed => "One or more fields failed validation",
+            ErrorCode::InvalidCursor => {
+                "The pagination cursor was forged, expired or issued for another query"
+            }
+            ErrorCode::Unauthorized => "Missing or invalid credentials",
+            ErrorCode::DeviceNotFound => "No device is registered for the requested host",
+            ErrorCode::TopologyNotFound => "The requested topology, node or link does not exist",
+            ErrorCode::DeviceConflict => "A device with the same host is already registered",
+            ErrorCode::DeviceUnreachable => "The device could not be reached",
+            ErrorCode::DeviceBadResponse => "The device answered with an unparseable payload",
+            ErrorCode::DeviceTimeout => "The device did not answer in time",
+            ErrorCode::Internal => "Unexpected server-side failure",
+            ErrorCode::RangeNotSatisfiable => "The range of the download starts beyond the content",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Body of every API error response
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String, // Human readable, not meant to be matched on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>, // e.g. the field errors of `VALIDATION_FAILED`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<RequestId>, // Correlates the response with the server logs
+}
+
+impl ErrorBody {
+    /// Creates the body of an error answered to the request `request_id`
+    pub fn new(code: ErrorCode, message: impl Into<String>, request_id: &RequestId) -> Self {
+        ErrorBody {
+            code,
+            message: message.into(),
+            details: None,
+            request_id: Some(request_id.clone()),
+        }
+    }
+}
+
+/// Entry of the error code catalog served by `GET /meta/error-codes`
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ErrorCodeInfo {
+    pub code: ErrorCode,
+    pub http_status: u16,
+    pub description: String,
+}
+
+/// Returns the catalog of every error code
+pub fn catalog() -> Vec<ErrorCodeInfo> {
+    ErrorCode::ALL
+        .iter()
+        .map(|code| ErrorCodeInfo {
+            code: *code,
+            http_status: code.http_status(),
+            description: code.description().to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,162 @@
+use crate::setup::config::Profile;
+
+use serde::{Deserialize, Serialize};
+
+/// Header pairs to add to a response
+pub type Headers = Vec<(&'static str, String)>;
+
+/// Cross-origin settings of the API, the `[cors]` section of `config.toml`
+///
+/// ```toml
+/// [cors]
+/// allowed_origins = ["https://noc.example.com"]
+/// allowed_methods = ["GET", "POST", "DELETE"]
+/// allowed_headers = ["authorization", "content-type"]
+/// allow_credentials = true
+/// ```
+///
+/// Without a `[cors]` section, cross-origin requests are denied in the `production`
+/// profile and allowed from any origin in the `dev` profile.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CorsConfig {
+    #[serde(default)]
+    pub allowed_origins: Vec<String>, // Exact origins, or `*` for any
+    #[serde(default = "default_methods")]
+    pub allowed_methods: Vec<String>,
+    #[serde(default = "default_headers")]
+    pub allowed_headers: Vec<String>, // Request headers, or `*` for any
+    #[serde(default)]
+    pub allow_credentials: bool, // Cookies and `Authorization` sent cross-origin
+    #[serde(default = "default_max_age")]
+    pub max_age: u64, // Seconds a preflight answer is cached by the browser
+}
+
+fn default_methods() -> Vec<String> {
+    ["GET", "POST", "PUT", "PATCH", "DELETE"]
+        .map(str::to_string)
+        .to_vec()
+}
+
+fn default_headers() -> Vec<String> {
+    ["authorization", "content-type", "last-event-id"]
+        .map(str::to_string)
+        .to_vec()
+}
+
+fn default_max_age() -> u64 {
+    600
+}
+
+impl CorsConfig {
+    /// Denies every cross-origin request
+    pub fn deny_all() -> Self {
+        CorsConfig {
+            allowed_origins: vec![],
+            allowed_methods: default_methods(),
+            allowed_headers: default_headers(),
+            allow_credentials: false,
+            max_age: default_max_age(),
+        }
+    }
+
+    /// Allows any origin, method and header, for local development
+    pub fn permissive() -> Self {
+        CorsConfig {
+            allowed_origins: vec!["*".to_string()],
+            allowed_headers: vec!["*".to_string()],
+            allow_credentials: true,
+            ..Self::deny_all()
+        }
+    }
+
+    /// Returns the settings used when `config.toml` has no `[cors]` section
+    pub fn for_profile(profile: Profile) -> Self {
+        match profile {
+            Profile::Production => Self::deny_all(),
+            Profile::Dev => Self::permissive(),
+        }
+    }
+
+    /// Returns whether requests from `origin` are allowed
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(origin))
+    }
+
+    /// Answers a preflight `OPTIONS` request
+    ///
+    /// # Arguments
+    /// - `origin`: The `Origin` header
+    /// - `method`: The `Access-Control-Request-Method` header
+    /// - `headers`: The `Access-Control-Request-Headers` header, if any
+    ///
+    /// # Returns
+    /// - `Some(Headers)`: The headers of the `204` answer
+    /// - `None`: If the request is not allowed; it is answered without CORS headers
+    pub fn preflight(&self, origin: &str, method: &str, headers: Option<&str>) -> Option<Headers> {
+        let requested: Vec<&str> = headers
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|header| !header.is_empty())
+            .collect();
+        let any_header = self.allowed_headers.iter().any(|header| header == "*");
+        if !self.allows_origin(origin)
+            || !self
+                .allowed_methods
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(method))
+            || !(any_header
+                || requested.iter().all(|header| {
+                    self.allowed_headers
+                        .iter()
+                        .any(|allowed| allowed.eq_ignore_ascii_case(header))
+                }))
+        {
+            return None;
+        }
+
+        let mut answer = self.response(origin)?;
+        answer.push((
+            "access-control-allow-methods",
+            self.allowed_methods.join(", "),
+        ));
+        answer.push((
+            "access-control-allow-headers",
+            if any_header {
+                requested.join(", ")
+            } else {
+                self.allowed_headers.join(", ")
+            },
+        ));
+        answer.push(("access-control-max-age", self.max_age.to_string()));
+        Some(answer)
+    }
+
+    /// Returns the CORS headers of the response to a request from `origin`
+    ///
+    /// # Returns
+    /// - `Some(Headers)`: The headers to add to the response
+    /// - `None`: If the origin is not allowed
+    pub fn response(&self, origin: &str) -> Option<Headers> {
+        if !self.allows_origin(origin) {
+            return None;
+        }
+
+        // Browsers reject `*` on credentialed requests, so the origin is echoed back
+        let wildcard = self.allowed_origins.iter().any(|allowed| allowed == "*");
+        let mut headers = if wildcard && !self.allow_credentials {
+            vec![("access-control-allow-origin", "*".to_string())]
+        } else {
+            vec![
+                ("access-control-allow-origin", origin.to_string()),
+                ("vary", "Origin".to_string()),
+            ]
+        };
+        if self.allow_credentials {
+            headers.push(("access-control-allow-credentials", "true".to_string()));
+        }
+        Some(headers)
+    }
+}
@@ -0,0 +1,87 @@
+use super::codes::{ErrorBody, ErrorCode};
+use super::request::RequestId;
+use crate::models::device::FieldError;
+use crate::Error;
+
+use serde_json::{json, Value};
+
+/// An error answered by the API, with its machine-readable code
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub details: Option<Value>,
+}
+
+impl ApiError {
+    /// Creates an error with `code`
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ApiError {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// `VALIDATION_FAILED` listing every problem of a request body
+    pub fn validation(errors: &[FieldError]) -> Self {
+        ApiError {
+            details: Some(json!(errors)),
+            ..Self::new(ErrorCode::ValidationFailed, "Validation failed")
+        }
+    }
+
+    /// `DEVICE_NOT_FOUND` for the device at `host`
+    pub fn device_not_found(host: &str) -> Self {
+        Self::new(
+            ErrorCode::DeviceNotFound,
+            format!("Not found device {}", host),
+        )
+    }
+
+    /// `DEVICE_CONFLICT` for the device at `host`
+    pub fn device_conflict(host: &str) -> Self {
+        Self::new(
+            ErrorCode::DeviceConflict,
+            format!("Device {} is already registered", host),
+        )
+    }
+
+    /// Returns the HTTP status of the response
+    pub fn status(&self) -> u16 {
+        self.code.http_status()
+    }
+
+    /// Returns the body of the response to the request `request_id`
+    pub fn body(&self, request_id: &RequestId) -> ErrorBody {
+        ErrorBody {
+            details: self.details.clone(),
+            ..ErrorBody::new(self.code, self.message.clone(), request_id)
+        }
+    }
+}
+
+impl From<Error> for ApiError {
+    /// Answers a library error with the code of its kind, `INTERNAL` for custom errors
+    fn from(error: Error) -> Self {
+        let code = match &error {
+            Error::NotFound(_) => ErrorCode::DeviceNotFound,
+            Error::TopologyNotFound(_) => ErrorCode::TopologyNotFound,
+            Error::Invalid(_) => ErrorCode::InvalidRequest,
+            Error::InvalidCursor(_) => ErrorCode::InvalidCursor,
+            Error::Unauthorized(_) => ErrorCode::Unauthorized,
+            Error::Unreachable(_) => ErrorCode::DeviceUnreachable,
+            Error::Timeout(_) => ErrorCode::DeviceTimeout,
+            Error::BadResponse(_) => ErrorCode::DeviceBadResponse,
+            Error::RangeNotSatisfiable(_) => ErrorCode::RangeNotSatisfiable,
+            Error::Custom(_) => ErrorCode::Internal,
+        };
+        ApiError::new(code, error.to_string())
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
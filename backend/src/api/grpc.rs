@@ -0,0 +1,354 @@
+use super::error::ApiError;
+use crate::events::{ChangeEvent, ChangeKind};
+use crate::models::lifecycle_state::LifecycleState;
+use crate::models::{link::Link, node::Node, topology::Topology};
+use crate::setup::app::App;
+use crate::{Error, Result};
+
+use std::pin::Pin;
+use std::sync::Arc;
+
+use chrono::{DateTime, Local};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+use tracing::info;
+use uuid::Uuid;
+
+/// Messages and services generated from `proto/device_manager.proto`
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("device_manager.v1");
+}
+
+use proto::device_service_server::{DeviceService, DeviceServiceServer};
+use proto::event_service_server::{EventService, EventServiceServer};
+use proto::topology_service_server::{TopologyService, TopologyServiceServer};
+
+/// Events queued for a gRPC subscriber before it is considered gone
+const STREAM_CAPACITY: usize = 1_000;
+
+/// Serves the gRPC API of `app` on `listener` until the task is dropped
+///
+/// # Returns
+/// - `Err(Error)`: If the server fails
+pub async fn serve(app: Arc<App>, listener: TcpListener) -> Result<()> {
+    if let Ok(address) = listener.local_addr() {
+        info!("gRPC API listening on {}", address);
+    }
+    let api = GrpcApi { app };
+    Server::builder()
+        .add_service(DeviceServiceServer::new(api.clone()))
+        .add_service(TopologyServiceServer::new(api.clone()))
+        .add_service(EventServiceServer::new(api))
+        .serve_with_incoming(TcpListenerStream::new(listener))
+        .await
+        .map_err(|err| Error::Custom(format!("gRPC server failed: {}", err)))
+}
+
+/// Implementation of every service, over the running application
+#[derive(Clone)]
+struct GrpcApi {
+    app: Arc<App>,
+}
+
+impl GrpcApi {
+    /// Resolves the tenant of a call from its `authorization` metadata
+    fn tenant<T>(&self, request: &Request<T>) -> Result<String> {
+        let authorization = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok());
+        self.app.authenticate(authorization)
+    }
+}
+
+#[tonic::async_trait]
+impl DeviceService for GrpcApi {
+    async fn list_devices(
+        &self,
+        request: Request<proto::ListDevicesRequest>,
+    ) -> std::result::Result<Response<proto::ListDevicesResponse>, Status> {
+        let tenant_id = self
+            .tenant(&request)
+            .map_err(|err| status(ApiError::from(err)))?;
+        let devices = self
+            .app
+            .device_health_for_tenant(&tenant_id)
+            .into_iter()
+            .map(|device| proto::Device {
+                host: device.host,
+                address: device.address.map(|address| address.to_string()),
+                collector_state: device.collector.as_ref().map(|task| label(&task.state)),
+                restarts: device.collector.as_ref().map_or(0, |task| task.restarts),
+                last_error: device.collector.and_then(|task| task.last_error),
+                latency_ms: device.latency.and_then(|sample| sample.latency_ms),
+            })
+            .collect();
+        Ok(Response::new(proto::ListDevicesResponse { devices }))
+    }
+
+    async fn get_scheduler(
+        &self,
+        request: Request<proto::GetSchedulerRequest>,
+    ) -> std::result::Result<Response<proto::GetSchedulerResponse>, Status> {
+        let tenant_id = self
+            .tenant(&request)
+            .map_err(|err| status(ApiError::from(err)))?;
+        let scheduler = self.app.scheduler_for_tenant(&tenant_id);
+        let devices = scheduler
+            .devices
+            .into_iter()
+            .map(|device| proto::DeviceSchedule {
+                host: device.host,
+                phase: label(&device.phase),
+                step: device.step.as_ref().map(label),
+                next_run: device.next_run.map(|date| date.to_rfc3339()),
+                last_run: device.last_run.map(|date| date.to_rfc3339()),
+                last_duration_ms: device.last_duration_ms,
+            })
+            .collect();
+        let spools = scheduler
+            .queues
+            .spools
+            .into_iter()
+            .map(|(sink, spool)| proto::QueueDepth {
+                name: format!("spool:{}", sink.as_str()),
+                depth: (spool.in_memory + spool.on_disk) as u64,
+                dropped: spool.dropped,
+            });
+        let queues = scheduler
+            .queues
+            .bus
+            .into_iter()
+            .map(|queue| proto::QueueDepth {
+                name: queue.name,
+                depth: queue.depth,
+                dropped: queue.dropped,
+            })
+            .chain(spools)
+            .collect();
+        Ok(Response::new(proto::GetSchedulerResponse {
+            devices,
+            collectors: scheduler.collectors as u32,
+            busy: scheduler.busy as u32,
+            utilization: scheduler.utilization,
+            queues,
+        }))
+    }
+}
+
+#[tonic::async_trait]
+impl TopologyService for GrpcApi {
+    async fn get_topology(
+        &self,
+        request: Request<proto::GetTopologyRequest>,
+    ) -> std::result::Result<Response<proto::GetTopologyResponse>, Status> {
+        let tenant_id = self
+            .tenant(&request)
+            .map_err(|err| status(ApiError::from(err)))?;
+        let request = request.into_inner();
+        let state: Option<LifecycleState> = request
+            .lifecycle_state
+            .as_deref()
+            .map(|label| parse_label(label, "lifecycle state"))
+            .transpose()
+            .map_err(|err| status(ApiError::from(err)))?;
+        let view = |topology: &Topology| match state {
+            Some(state) => topology_message(&topology.in_state(state)),
+            None => topology_message(topology),
+        };
+        let topologies = self
+            .app
+            .topologies_for_tenant(&tenant_id, &request.host, view)
+            .await
+            .map_err(|err| status(ApiError::from(err)))?;
+        Ok(Response::new(proto::GetTopologyResponse { topologies }))
+    }
+}
+
+type EventStream = Pin<
+    Box<dyn tokio_stream::Stream<Item = std::result::Result<proto::ChangeEvent, Status>> + Send>,
+>;
+
+#[tonic::async_trait]
+impl EventService for GrpcApi {
+    type SubscribeStream = EventStream;
+
+    async fn subscribe(
+        &self,
+        request: Request<proto::SubscribeRequest>,
+    ) -> std::result::Result<Response<EventStream>, Status> {
+        let tenant_id = self
+            .tenant(&request)
+            .map_err(|err| status(ApiError::from(err)))?;
+        let hosts = request.into_inner().hosts;
+        // One queue per stream, dropped with the subscription when the stream ends
+        let mut subscription = self.app.subscribe(&format!("grpc:{}", Uuid::new_v4()));
+        let (sender, receiver) = mpsc::channel(STREAM_CAPACITY);
+        tokio::spawn(async move {
+            loop {
+                let stored = tokio::select! {
+                    stored = subscription.recv() => stored,
+                    // The client hung up, even if no event came since
+                    _ = sender.closed() => break,
+                };
+                let Some(stored) = stored else { break };
+                if stored.tenant_id != tenant_id {
+                    continue;
+                }
+                let event = stored.event;
+                if !hosts.is_empty() && !hosts.contains(&event.host) {
+                    continue;
+                }
+                if sender.send(Ok(event_message(&event))).await.is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+}
+
+/// Answers an API error with the gRPC status of its code
+fn status(error: ApiError) -> Status {
+    Status::new(error.code.grpc_code(), error.to_string())
+}
+
+/// Returns the JSON API name of an enumeration value, e.g. `INSTALLED`
+fn label<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(label)) => label,
+        _ => String::new(),
+    }
+}
+
+/// Parses the JSON API name of an enumeration value, the reverse of [`label`]
+fn parse_label<T: DeserializeOwned>(label: &str, what: &str) -> Result<T> {
+    serde_json::from_value(serde_json::Value::String(label.to_string()))
+        .map_err(|_| Error::Invalid(format!("Invalid {} {}", what, label)))
+}
+
+/// Parses a UUID of a message
+fn parse_uuid(uuid: &str) -> Result<Uuid> {
+    Uuid::parse_str(uuid).map_err(|_| Error::Invalid(format!("Invalid uuid {}", uuid)))
+}
+
+/// Converts a topology into its message
+pub fn topology_message(topology: &Topology) -> proto::Topology {
+    proto::Topology {
+        host: topology.host.clone(),
+        uuid: topology.uuid.to_string(),
+        nodes: topology.nodes.iter().map(node_message).collect(),
+        links: topology.links.iter().map(link_message).collect(),
+    }
+}
+
+/// Converts a node into its message
+fn node_message(node: &Node) -> proto::Node {
+    proto::Node {
+        uuid: node.uuid.to_string(),
+        name: node.name.clone(),
+        lifecycle_state: node.lifecycle_state.as_ref().map(label),
+        owned_node_edge_points: node
+            .owned_node_edge_points
+            .iter()
+            .map(|endpoint| endpoint.uuid.to_string())
+            .collect(),
+    }
+}
+
+/// Converts a link into its message
+fn link_message(link: &Link) -> proto::Link {
+    proto::Link {
+        uuid: link.uuid.to_string(),
+        name: link.name.clone(),
+        lifecycle_state: link.lifecycle_state.as_ref().map(label),
+        direction: link.direction.as_ref().map(label),
+        node_edge_points: link
+            .node_edge_points
+            .iter()
+            .map(|endpoint| proto::NodeEdgePoint {
+                topology_uuid: endpoint.topology_uuid.map(|uuid| uuid.to_string()),
+                node_uuid: endpoint.node_uuid.to_string(),
+                node_edge_point_uuid: endpoint.node_edge_point_uuid.to_string(),
+            })
+            .collect(),
+        raw_uuids: link.raw_uuids.iter().map(|uuid| uuid.to_string()).collect(),
+    }
+}
+
+/// Converts a change event into its message
+pub fn event_message(event: &ChangeEvent) -> proto::ChangeEvent {
+    let mut message = proto::ChangeEvent {
+        host: event.host.clone(),
+        topology_uuid: event.topology_uuid.to_string(),
+        object: label(&event.object),
+        uuid: event.uuid.to_string(),
+        change: String::new(),
+        from: None,
+        to: None,
+        rule: None,
+        detail: None,
+        date: event.date.to_rfc3339(),
+    };
+    message.change = match &event.change {
+        ChangeKind::Added => "added",
+        ChangeKind::Removed => "removed",
+        ChangeKind::Changed => "changed",
+        ChangeKind::LifecycleTransition { from, to } => {
+            message.from = from.as_ref().map(label);
+            message.to = to.as_ref().map(label);
+            "lifecycle_transition"
+        }
+        ChangeKind::Violation { rule, detail } => {
+            message.rule = Some(label(rule));
+            message.detail = Some(detail.clone());
+            "violation"
+        }
+    }
+    .to_string();
+    message
+}
+
+/// Converts a change event message back into the event, for the clients of the API
+///
+/// # Returns
+/// - `Ok(ChangeEvent)`: The event as published on the bus
+/// - `Err(Error)`: If a UUID, date or enumeration value is invalid
+pub fn change_event(message: &proto::ChangeEvent) -> Result<ChangeEvent> {
+    let state = |label: &Option<String>| {
+        label
+            .as_deref()
+            .map(|label| parse_label(label, "lifecycle state"))
+            .transpose()
+    };
+    let change = match message.change.as_str() {
+        "added" => ChangeKind::Added,
+        "removed" => ChangeKind::Removed,
+        "changed" => ChangeKind::Changed,
+        "lifecycle_transition" => ChangeKind::LifecycleTransition {
+            from: state(&message.from)?,
+            to: state(&message.to)?,
+        },
+        "violation" => ChangeKind::Violation {
+            rule: parse_label(message.rule.as_deref().unwrap_or_default(), "rule")?,
+            detail: message.detail.clone().unwrap_or_default(),
+        },
+        change => return Err(Error::Invalid(format!("Invalid change {}", change))),
+    };
+    let date = DateTime::parse_from_rfc3339(&message.date)
+        .map_err(|_| Error::Invalid(format!("Invalid date {}", message.date)))?;
+    Ok(ChangeEvent {
+        host: message.host.clone(),
+        topology_uuid: parse_uuid(&message.topology_uuid)?,
+        object: parse_label(&message.object, "object")?,
+        uuid: parse_uuid(&message.uuid)?,
+        change,
+        date: date.with_timezone(&Local),
+    })
+}
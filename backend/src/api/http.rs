@@ -0,0 +1,936 @@
+use super::assets::StaticAssets;
+use super::codes::{catalog, ErrorCodeInfo};
+use super::error::ApiError;
+use super::request::{RequestId, RequestSpan, REQUEST_ID_HEADER};
+use crate::actions::BulkRequest;
+use crate::agents::{basic_credentials, Agent};
+use crate::api::auth::Caller;
+use crate::audit::{AuditEntry, AuditQuery};
+use crate::diff::TopologyComparison;
+use crate::events::sse::{self, EventStream};
+use crate::events::store::EventPage;
+use crate::export::artifact::ExportChunk;
+use crate::export::csv::links_to_csv;
+use crate::export::graph::GraphFormat;
+use crate::impact::LinkImpact;
+use crate::jobs::Job;
+use crate::latency::LatencySeries;
+use crate::models::connectivity_service::{
+    ConnectivityServiceRequest, CreatedService, DryRun, ServiceStateChange,
+};
+use crate::models::lifecycle_state::LifecycleState;
+use crate::models::link::Link;
+use crate::models::topology::Topology;
+use crate::notifications::history::StoredAlert;
+use crate::retention::Age;
+use crate::search::SearchHit;
+use crate::setup::app::{App, DeviceHealth, Readiness, SchedulerState};
+use crate::sync::{MergeOutcome, Snapshot};
+use crate::validation::Violation;
+use crate::{Error, Result};
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::Uri;
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use chrono::Local;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+use tracing::{info, Instrument};
+use uuid::Uuid;
+
+/// Serves the HTTP API of `app` on `listener` until the task is dropped
+///
+/// Every route but `GET /meta/error-codes`, `GET /ready` and the routes of the edge
+/// agents, authenticated by their own credentials, takes an
+/// `Authorization: Bearer <token>` header with a token of the `[api]` section of the
+/// config, and only sees the devices of the tenant of the token. Mutating routes are
+/// recorded in the audit log under the name of the token.
+///
+/// # Returns
+/// - `Err(Error)`: If the server fails
+pub async fn serve(app: Arc<App>, listener: TcpListener) -> Result<()> {
+    if let Ok(address) = listener.local_addr() {
+        info!("HTTP API listening on {}", address);
+    }
+    axum::serve(listener, router(app))
+        .await
+        .map_err(|err| Error::Custom(format!("HTTP server failed: {}", err)))
+}
+
+/// Routes of the API
+fn router(app: Arc<App>) -> Router {
+    Router::new()
+        .route("/meta/error-codes", get(error_codes))
+        .route("/ready", get(ready))
+        .route("/devices", get(devices))
+        .route("/devices/{host}/topology", get(topology))
+        .route("/devices/{host}/export", post(export_topology))
+        .route(
+            "/devices/{host}/connectivity-services",
+            post(create_connectivity_service),
+        )
+        .route(
+            "/devices/{host}/connectivity-services:validate",
+            post(validate_connectivity_service),
+        )
+        .route(
+            "/devices/{host}/connectivity-services/{uuid}",
+            delete(delete_connectivity_service).patch(change_connectivity_service_state),
+        )
+        .route("/topology/global", get(global_topology))
+        .route("/topology/compare", get(compare_topologies))
+        .route("/topology/{host}/violations", get(violations))
+        .route("/topology/{host}/links", get(links))
+        .route("/topology/{host}/export", get(render_topology))
+        .route("/search", get(search))
+        .route("/links/{uuid}/impact", get(link_impact))
+        .route("/devices/{host}/stats", get(stats))
+        .route("/devices/{host}/latency", get(latency))
+        .route("/metrics", get(metrics))
+        .route("/admin/scheduler", get(scheduler))
+        .route("/actions/bulk", post(bulk))
+        .route("/agents", get(agents))
+        .route("/agents/tokens", post(issue_agent_token))
+        .route("/agents/enroll", post(enroll_agent))
+        .route("/agents/{id}", delete(revoke_agent))
+        .route("/agents/{id}/snapshots", post(ingest_snapshot))
+        .route("/audit", get(audit))
+        .route("/alerts", get(alerts))
+        .route("/jobs", get(jobs))
+        .route("/jobs/{id}", get(job))
+        .route("/exports/{id}", get(export))
+        .route("/export/topology.geojson", get(geojson))
+        .route("/events", get(replay))
+        .route("/events/stream", get(events))
+        .fallback(get(frontend))
+        .layer(middleware::from_fn_with_state(Arc::clone(&app), cors))
+        .layer(middleware::from_fn(trace))
+        .with_state(app)
+}
+
+/// Runs a request in its span, with the id of the `x-request-id` header or a new one
+///
+/// The id is echoed in the `x-request-id` response header and written in the body of
+/// the errors, so a user report can be matched with the logs.
+async fn trace(request: Request, next: Next) -> Response {
+    let request_id = RequestId::from_header(
+        request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok()),
+    );
+    let span = RequestSpan::start(request.method().as_str(), request.uri().path(), &request_id);
+    let mut response = next.run(request).instrument(span.span().clone()).await;
+
+    if let Some(error) = response.extensions_mut().remove::<ApiError>() {
+        // Headers set with the error are kept, e.g. the `Content-Range` of a `416`
+        let headers = std::mem::take(response.headers_mut());
+        response = (response.status(), Json(error.body(&request_id))).into_response();
+        for (name, value) in &headers {
+            if name != header::CONTENT_TYPE && name != header::CONTENT_LENGTH {
+                response.headers_mut().insert(name.clone(), value.clone());
+            }
+        }
+    }
+    if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    span.finish(response.status().as_u16());
+    response
+}
+
+/// Applies the CORS policy of the current config to the requests sent with an `Origin`
+///
+/// Preflight `OPTIONS` requests are answered here with `204`, without CORS headers when
+/// the request is not allowed, so the browser blocks it.
+async fn cors(State(app): State<Arc<App>>, request: Request, next: Next) -> Response {
+    let Some(origin) = request
+        .headers()
+        .get(header::ORIGIN)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+    else {
+        return next.run(request).await;
+    };
+    let policy = app.config().cors();
+    let (method, requested_headers) = {
+        let headers = request.headers();
+        let requested = |name: HeaderName| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        (
+            requested(header::ACCESS_CONTROL_REQUEST_METHOD)
+                .filter(|_| request.method() == Method::OPTIONS),
+            requested(header::ACCESS_CONTROL_REQUEST_HEADERS),
+        )
+    };
+
+    let (mut response, headers) = match method {
+        Some(method) => (
+            StatusCode::NO_CONTENT.into_response(),
+            policy.preflight(&origin, &method, requested_headers.as_deref()),
+        ),
+        None => (next.run(request).await, policy.response(&origin)),
+    };
+    for (name, value) in headers.unwrap_or_default() {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response
+                .headers_mut()
+                .append(HeaderName::from_static(name), value);
+        }
+    }
+    response
+}
+
+/// An error answered with its code and HTTP status; [`trace`] writes its body, with the
+/// id of the request
+struct HttpError(ApiError);
+
+impl From<Error> for HttpError {
+    fn from(error: Error) -> Self {
+        HttpError(ApiError::from(error))
+    }
+}
+
+impl IntoResponse for HttpError {
+    fn into_response(self) -> Response {
+        let status =
+            StatusCode::from_u16(self.0.status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, Extension(self.0)).into_response()
+    }
+}
+
+/// Answers the paths outside the API with the compiled frontend of the `[frontend]`
+/// section of the current config, `404` if none is configured
+async fn frontend(State(app): State<Arc<App>>, uri: Uri) -> Response {
+    let Some(directory) = app.config().frontend.directory else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let Some(asset) = StaticAssets::new(directory).resolve(uri.path()) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    match tokio::fs::read(&asset.path).await {
+        Ok(content) => (
+            [
+                (header::CONTENT_TYPE, asset.content_type),
+                (header::CACHE_CONTROL, asset.cache_control),
+            ],
+            content,
+        )
+            .into_response(),
+        Err(_) => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Events of a `GET /events` page when the request sets no `limit`
+const DEFAULT_EVENT_LIMIT: usize = 100;
+
+/// Most events of a `GET /events` page
+const MAX_EVENT_LIMIT: usize = 1000;
+
+/// Hits of a `GET /search` when the request sets no `limit`
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// Window of `GET /devices/{host}/latency` when the request sets none
+const DEFAULT_LATENCY_WINDOW: &str = "24h";
+
+/// Validity of the tokens of `POST /agents/tokens` when the request sets none
+const DEFAULT_ENROLLMENT_TTL: &str = "24h";
+
+/// Content type of `GET /topology/{host}/links?format=csv`
+const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+
+/// Content type of `GET /export/topology.geojson`
+const GEOJSON_CONTENT_TYPE: &str = "application/geo+json";
+
+/// Content type of the Prometheus text format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Resolves the tenant of a request from its `Authorization` header
+fn tenant(app: &App, headers: &HeaderMap) -> Result<String> {
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    app.authenticate(authorization)
+}
+
+/// Resolves the tenant and the audit actor of a request from its `Authorization` header
+fn caller(app: &App, headers: &HeaderMap) -> Result<Caller> {
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+    app.caller(authorization)
+}
+
+/// Returns the JSON body of a request, a malformed one being `400` like other errors
+fn body<T: DeserializeOwned>(body: std::result::Result<Json<T>, JsonRejection>) -> Result<T> {
+    body.map(|Json(body)| body)
+        .map_err(|rejection| Error::Invalid(format!("Invalid body: {}", rejection.body_text())))
+}
+
+/// `GET /meta/error-codes`: Every error code of the API
+async fn error_codes() -> Json<Vec<ErrorCodeInfo>> {
+    Json(catalog())
+}
+
+/// `GET /ready`: Progress of the warm-load of the stored topologies, `503` until every
+/// stored host is loaded
+async fn ready(State(app): State<Arc<App>>) -> (StatusCode, Json<Readiness>) {
+    let readiness = app.readiness();
+    let status = match readiness.ready {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, Json(readiness))
+}
+
+/// `GET /devices`: Health of the devices of the tenant, in registry order
+async fn devices(
+    State(app): State<Arc<App>>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<DeviceHealth>>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    Ok(Json(app.device_health_for_tenant(&tenant_id)))
+}
+
+/// Filters of `GET /devices/{host}/topology`
+#[derive(Deserialize)]
+struct TopologyQuery {
+    lifecycle_state: Option<String>, // Only the nodes and links in this state, e.g. `INSTALLED`
+}
+
+/// `GET /devices/{host}/topology`: Last topologies of a device of the tenant
+async fn topology(
+    State(app): State<Arc<App>>,
+    Path(host): Path<String>,
+    Query(query): Query<TopologyQuery>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<Topology>>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let state = query
+        .lifecycle_state
+        .map(|state| state.parse::<LifecycleState>())
+        .transpose()?;
+    let view = |topology: &Topology| match state {
+        Some(state) => topology.in_state(state),
+        None => topology.clone(),
+    };
+    Ok(Json(
+        app.topologies_for_tenant(&tenant_id, &host, view).await?,
+    ))
+}
+
+/// Format of `GET /topology/{host}/links`
+#[derive(Deserialize)]
+struct LinksQuery {
+    format: Option<String>, // `csv` or `json`, `json` if unset
+}
+
+/// `GET /topology/{host}/links?format=`: Links of the last topologies of a device of the
+/// tenant, as JSON or as CSV with one row per link
+async fn links(
+    State(app): State<Arc<App>>,
+    Path(host): Path<String>,
+    Query(query): Query<LinksQuery>,
+    headers: HeaderMap,
+) -> std::result::Result<Response, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let format = query.format.unwrap_or_else(|| "json".to_string());
+    if format != "csv" && format != "json" {
+        return Err(Error::Invalid(format!("Invalid links format {}", format)).into());
+    }
+    let links: Vec<Link> = app
+        .topologies_for_tenant(&tenant_id, &host, |topology| topology.links.clone())
+        .await?
+        .into_iter()
+        .flatten()
+        .collect();
+    Ok(match format.as_str() {
+        "csv" => (
+            [(header::CONTENT_TYPE, CSV_CONTENT_TYPE)],
+            links_to_csv(&links),
+        )
+            .into_response(),
+        _ => Json(links).into_response(),
+    })
+}
+
+/// Options of `GET /topology/{host}/export` and `POST /devices/{host}/export`
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>, // `dot`, `graphml` or `json`, `graphml` if unset
+    topology: Option<Uuid>, // The first topology of the device if unset
+}
+
+/// `GET /topology/{host}/export?format=&topology=`: Renders a topology of a device of the
+/// tenant right away, for graphs small enough not to need a job
+async fn render_topology(
+    State(app): State<Arc<App>>,
+    Path(host): Path<String>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> std::result::Result<Response, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let format: GraphFormat = query.format.as_deref().unwrap_or("graphml").parse()?;
+    let graph = app
+        .render_topology(&tenant_id, &host, format, query.topology)
+        .await?;
+    Ok(([(header::CONTENT_TYPE, format.content_type())], graph).into_response())
+}
+
+/// `POST /devices/{host}/export?format=&topology=`: Queues the export of a topology of
+/// a device of the tenant, answered `202 Accepted` with the job; the artifact is then
+/// downloaded from `GET /exports/{id}`
+async fn export_topology(
+    State(app): State<Arc<App>>,
+    Path(host): Path<String>,
+    Query(query): Query<ExportQuery>,
+    headers: HeaderMap,
+) -> std::result::Result<Response, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let format: GraphFormat = query.format.as_deref().unwrap_or("graphml").parse()?;
+    let id = app
+        .export_topology(&tenant_id, &host, format, query.topology)
+        .await?;
+    accepted(&app, &tenant_id, &id)
+}
+
+/// `POST /devices/{host}/connectivity-services`: Provisions a connectivity service on a
+/// device of the tenant, answered `201 Created`
+///
+/// Every invalid field of the body is listed in a `VALIDATION_FAILED` error.
+async fn create_connectivity_service(
+    State(app): State<Arc<App>>,
+    Path(host): Path<String>,
+    headers: HeaderMap,
+    request: std::result::Result<Json<Value>, JsonRejection>,
+) -> std::result::Result<Response, HttpError> {
+    let caller = caller(&app, &headers)?;
+    let value = body(request)?;
+    let errors = ConnectivityServiceRequest::validate(&value);
+    if !errors.is_empty() {
+        return Err(HttpError(ApiError::validation(&errors)));
+    }
+    let request = ConnectivityServiceRequest::from_value(&value)?;
+    let created: CreatedService = app
+        .create_connectivity_service(&caller, &host, &request)
+        .await?;
+    Ok((
+        StatusCode::CREATED,
+        [(
+            header::LOCATION,
+            format!("/devices/{}/connectivity-services/{}", host, created.uuid),
+        )],
+        Json(created),
+    )
+        .into_response())
+}
+
+/// `POST /devices/{host}/connectivity-services:validate`: Checks a provisioning request
+/// against the cached topology of a device of the tenant, without reaching the device
+///
+/// Answered `200 OK` with the violations of the request, invalid fields included.
+async fn validate_connectivity_service(
+    State(app): State<Arc<App>>,
+    Path(host): Path<String>,
+    headers: HeaderMap,
+    request: std::result::Result<Json<Value>, JsonRejection>,
+) -> std::result::Result<Json<DryRun>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let value = body(request)?;
+    Ok(Json(
+        app.validate_connectivity_service(&tenant_id, &host, &value)
+            .await?,
+    ))
+}
+
+/// `DELETE /devices/{host}/connectivity-services/{uuid}`: Deletes a connectivity service
+/// of a device of the tenant, answered `202 Accepted` with the provisioning job
+async fn delete_connectivity_service(
+    State(app): State<Arc<App>>,
+    Path((host, uuid)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> std::result::Result<Response, HttpError> {
+    let caller = caller(&app, &headers)?;
+    let uuid = parse_id(&uuid, "connectivity service")?;
+    let id = app.delete_connectivity_service(&caller, &host, uuid)?;
+    accepted(&app, &caller.tenant_id, &id)
+}
+
+/// `PATCH /devices/{host}/connectivity-services/{uuid}`: Changes the administrative state
+/// of a connectivity service of a device of the tenant, answered `202 Accepted` with the
+/// provisioning job
+async fn change_connectivity_service_state(
+    State(app): State<Arc<App>>,
+    Path((host, uuid)): Path<(String, String)>,
+    headers: HeaderMap,
+    request: std::result::Result<Json<Value>, JsonRejection>,
+) -> std::result::Result<Response, HttpError> {
+    let caller = caller(&app, &headers)?;
+    let uuid = parse_id(&uuid, "connectivity service")?;
+    let change = ServiceStateChange::from_value(&body(request)?)?;
+    let id = app.change_connectivity_service_state(&caller, &host, uuid, change)?;
+    accepted(&app, &caller.tenant_id, &id)
+}
+
+/// `GET /topology/global`: Topologies of the devices of the tenant, joined by the links
+/// stitched between them
+async fn global_topology(
+    State(app): State<Arc<App>>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Value>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    Ok(Json(
+        app.global_topology(&tenant_id, |global| json!(global))
+            .await?,
+    ))
+}
+
+/// `GET /topology/{host}/violations`: Validation rules broken by the last topologies of
+/// a device of the tenant
+async fn violations(
+    State(app): State<Arc<App>>,
+    Path(host): Path<String>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<Violation>>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    Ok(Json(app.violations_for_tenant(&tenant_id, &host)?))
+}
+
+/// Devices of `GET /topology/compare`
+#[derive(Deserialize)]
+struct CompareQuery {
+    left: Option<String>,
+    right: Option<String>,
+}
+
+/// `GET /topology/compare?left=&right=`: Nodes and links reported by only one of two
+/// devices of the tenant, matched by UUID or name
+async fn compare_topologies(
+    State(app): State<Arc<App>>,
+    Query(query): Query<CompareQuery>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<TopologyComparison>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let (Some(left), Some(right)) = (query.left, query.right) else {
+        return Err(Error::Invalid(
+            "Invalid comparison, expected a left and a right device".to_string(),
+        )
+        .into());
+    };
+    Ok(Json(
+        app.compare_for_tenant(&tenant_id, &left, &right).await?,
+    ))
+}
+
+/// Query of `GET /search`
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: Option<String>,    // UUID, name or host/IP, matched in full or in part
+    limit: Option<usize>, // `DEFAULT_SEARCH_LIMIT` if unset
+}
+
+/// `GET /search?q=&limit=`: Devices, nodes, links and edge points of the tenant
+/// matching a query, best matches first
+async fn search(
+    State(app): State<Arc<App>>,
+    Query(query): Query<SearchQuery>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<SearchHit>>, HttpError> {
+    let caller = caller(&app, &headers)?;
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    Ok(Json(app.search_for_tenant(
+        &caller.tenant_id,
+        query.q.as_deref().unwrap_or_default(),
+        limit,
+    )?))
+}
+
+/// `GET /links/{uuid}/impact`: Services of the tenant routed over a link, affected by it
+/// going down
+async fn link_impact(
+    State(app): State<Arc<App>>,
+    Path(uuid): Path<String>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<LinkImpact>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let uuid = parse_id(&uuid, "link")?;
+    Ok(Json(app.link_impact(&tenant_id, &uuid).await?))
+}
+
+/// `GET /devices/{host}/stats`: Last poll cycles of a device of the tenant
+async fn stats(
+    State(app): State<Arc<App>>,
+    Path(host): Path<String>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Value>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    Ok(Json(app.stats_for_tenant(&tenant_id, &host, |stats| {
+        json!(stats)
+    })?))
+}
+
+/// Filters of `GET /devices/{host}/latency`
+#[derive(Deserialize)]
+struct LatencyQuery {
+    window: Option<String>, // Age up to now, e.g. `1h`, `DEFAULT_LATENCY_WINDOW` if unset
+}
+
+/// `GET /devices/{host}/latency?window=`: Latency samples of a device of the tenant
+async fn latency(
+    State(app): State<Arc<App>>,
+    Path(host): Path<String>,
+    Query(query): Query<LatencyQuery>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<LatencySeries>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let window: Age = query
+        .window
+        .as_deref()
+        .unwrap_or(DEFAULT_LATENCY_WINDOW)
+        .parse()?;
+    Ok(Json(app.latency_for_tenant(&tenant_id, &host, window)?))
+}
+
+/// `GET /metrics`: Poll cycles, latency histograms of the devices of the tenant and the
+/// event queues, in the Prometheus text format
+async fn metrics(
+    State(app): State<Arc<App>>,
+    headers: HeaderMap,
+) -> std::result::Result<Response, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    Ok((
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        app.prometheus_for_tenant(&tenant_id),
+    )
+        .into_response())
+}
+
+/// `GET /admin/scheduler`: Schedule of the collectors of the tenant
+async fn scheduler(
+    State(app): State<Arc<App>>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<SchedulerState>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    Ok(Json(app.scheduler_for_tenant(&tenant_id)))
+}
+
+/// `POST /actions/bulk`: Queues a [`BulkRequest`] over the devices of the tenant,
+/// answered `202 Accepted` with the job, followed at `GET /jobs/{id}`
+async fn bulk(
+    State(app): State<Arc<App>>,
+    headers: HeaderMap,
+    request: std::result::Result<Json<BulkRequest>, JsonRejection>,
+) -> std::result::Result<Response, HttpError> {
+    let caller = caller(&app, &headers)?;
+    let id = app.bulk_for_tenant(&caller, &body(request)?)?;
+    accepted(&app, &caller.tenant_id, &id)
+}
+
+/// Answers `202 Accepted` with a job just queued and its location
+fn accepted(app: &App, tenant_id: &str, id: &Uuid) -> std::result::Result<Response, HttpError> {
+    let job = app.job_for_tenant(tenant_id, id)?;
+    Ok((
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, format!("/jobs/{}", id))],
+        Json(job),
+    )
+        .into_response())
+}
+
+/// `GET /agents`: Edge agents enrolled for the tenant, oldest first
+async fn agents(
+    State(app): State<Arc<App>>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<Agent>>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    Ok(Json(app.agents(&tenant_id)))
+}
+
+/// Body of `POST /agents/tokens`
+#[derive(Deserialize)]
+struct AgentTokenRequest {
+    label: String,    // Where the agent runs, e.g. `Madrid edge`
+    ttl: Option<Age>, // Validity of the token, `DEFAULT_ENROLLMENT_TTL` if unset
+}
+
+/// `POST /agents/tokens`: Issues a one-time enrollment token for an edge agent of the
+/// tenant, answered `201 Created` with the token, shown only once
+async fn issue_agent_token(
+    State(app): State<Arc<App>>,
+    headers: HeaderMap,
+    request: std::result::Result<Json<AgentTokenRequest>, JsonRejection>,
+) -> std::result::Result<Response, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let request = body(request)?;
+    let ttl = match request.ttl {
+        Some(ttl) => ttl,
+        None => DEFAULT_ENROLLMENT_TTL.parse()?,
+    };
+    let expires_at = Local::now() + ttl.duration();
+    let token = app.issue_agent_token(&tenant_id, &request.label, ttl.duration())?;
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({ "token": token, "expires_at": expires_at })),
+    )
+        .into_response())
+}
+
+/// Body of `POST /agents/enroll`
+#[derive(Deserialize)]
+struct EnrollRequest {
+    token: String, // Issued by `POST /agents/tokens`
+    name: String,  // Name of the agent, e.g. `edge-mad01`
+}
+
+/// `POST /agents/enroll`: Enrolls an edge agent with its one-time token, answered
+/// `201 Created` with the credentials it pushes its snapshots with
+///
+/// The enrollment token authenticates the request, no API token is needed.
+async fn enroll_agent(
+    State(app): State<Arc<App>>,
+    request: std::result::Result<Json<EnrollRequest>, JsonRejection>,
+) -> std::result::Result<Response, HttpError> {
+    let request = body(request)?;
+    let credentials = app.enroll_agent(&request.token, &request.name)?;
+    Ok((StatusCode::CREATED, Json(credentials)).into_response())
+}
+
+/// `DELETE /agents/{id}`: Revokes an edge agent of the tenant
+async fn revoke_agent(
+    State(app): State<Arc<App>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Agent>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let id = parse_id(&id, "agent")?;
+    Ok(Json(app.revoke_agent(&tenant_id, &id)?))
+}
+
+/// `POST /agents/{id}/snapshots`: Merges a snapshot pushed by an edge agent, answered
+/// with the [`MergeOutcome`]
+///
+/// The agent authenticates with `Basic` credentials, its id and secret. Stale and
+/// duplicate snapshots are answered `200 OK` too, so the agent drops them from its spool.
+async fn ingest_snapshot(
+    State(app): State<Arc<App>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    request: std::result::Result<Json<Snapshot>, JsonRejection>,
+) -> std::result::Result<Json<Value>, HttpError> {
+    let (agent_id, secret) = basic_credentials(
+        headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok()),
+    )?;
+    if parse_id(&id, "agent")? != agent_id {
+        return Err(Error::Unauthorized("Invalid agent credentials".to_string()).into());
+    }
+    let outcome: MergeOutcome = app.ingest_snapshot(&agent_id, &secret, body(request)?)?;
+    Ok(Json(json!({ "outcome": outcome })))
+}
+
+/// `GET /audit?actor=&action=&from=&to=`: Audited operations on the devices of the
+/// tenant, most recent first
+async fn audit(
+    State(app): State<Arc<App>>,
+    Query(query): Query<AuditQuery>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<AuditEntry>>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    Ok(Json(app.audit_for_tenant(&tenant_id, &query)))
+}
+
+/// `GET /alerts`: Last threshold alerts of the tenant, most recent first, those raised
+/// during a maintenance of their device tagged `suppressed`
+async fn alerts(
+    State(app): State<Arc<App>>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<StoredAlert>>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    Ok(Json(app.alerts(&tenant_id)))
+}
+
+/// `GET /jobs`: Jobs of the tenant, most recent first
+async fn jobs(
+    State(app): State<Arc<App>>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Vec<Job>>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    Ok(Json(app.jobs_for_tenant(&tenant_id)))
+}
+
+/// `GET /jobs/{id}`: A job of the tenant, with its result once done
+async fn job(
+    State(app): State<Arc<App>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Job>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let id = parse_id(&id, "job")?;
+    Ok(Json(app.job_for_tenant(&tenant_id, &id)?))
+}
+
+/// `GET /exports/{id}`: Downloads an export of the tenant, resumed with `Range` and
+/// `If-Range`
+///
+/// Answered `206 Partial Content` for a range, `200 OK` with the whole artifact without
+/// one or when `If-Range` is not its etag, and `416` with the size of the artifact when
+/// the range starts beyond it.
+async fn export(
+    State(app): State<Arc<App>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> std::result::Result<Response, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let id = parse_id(&id, "export")?;
+    let header = |name: HeaderName| headers.get(name).and_then(|value| value.to_str().ok());
+    let chunk = match app.export(
+        &tenant_id,
+        &id,
+        header(header::RANGE),
+        header(header::IF_RANGE),
+    ) {
+        Ok(chunk) => chunk,
+        Err(err @ Error::RangeNotSatisfiable(_)) => {
+            let size = app.export_artifact(&tenant_id, &id)?.size;
+            let mut response = HttpError::from(err).into_response();
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", size)) {
+                response.headers_mut().insert(header::CONTENT_RANGE, value);
+            }
+            return Ok(response);
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    let ExportChunk {
+        artifact,
+        range,
+        partial,
+        content,
+    } = chunk;
+    let mut response = Response::new(Body::from(content));
+    *response.status_mut() = match partial {
+        true => StatusCode::PARTIAL_CONTENT,
+        false => StatusCode::OK,
+    };
+    let mut values = vec![
+        (header::CONTENT_TYPE, artifact.media_type.clone()),
+        (header::ACCEPT_RANGES, "bytes".to_string()),
+        (header::ETAG, artifact.etag.clone()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", artifact.name),
+        ),
+    ];
+    if partial {
+        values.push((header::CONTENT_RANGE, range.content_range(artifact.size)));
+    }
+    for (name, value) in values {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+    Ok(response)
+}
+
+/// `GET /export/topology.geojson`: Nodes and links of the tenant placed on their sites,
+/// as a GeoJSON feature collection
+async fn geojson(
+    State(app): State<Arc<App>>,
+    headers: HeaderMap,
+) -> std::result::Result<Response, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    Ok((
+        [(header::CONTENT_TYPE, GEOJSON_CONTENT_TYPE)],
+        Json(app.geojson(&tenant_id).await?),
+    )
+        .into_response())
+}
+
+/// Parses the UUID of a path, e.g. the id of a job
+fn parse_id(id: &str, what: &str) -> Result<Uuid> {
+    Uuid::parse_str(id).map_err(|_| Error::Invalid(format!("Invalid {} id {}", what, id)))
+}
+
+/// Filters of `GET /events`
+#[derive(Deserialize)]
+struct ReplayQuery {
+    since: Option<String>, // RFC 3339 timestamp or `next_cursor` of the previous page
+    limit: Option<usize>,  // Events of the page, up to `MAX_EVENT_LIMIT`
+}
+
+/// `GET /events?since=<timestamp|cursor>&limit=`: Replays the change events of the
+/// tenant, paged with signed cursors
+async fn replay(
+    State(app): State<Arc<App>>,
+    Query(query): Query<ReplayQuery>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<EventPage>, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_EVENT_LIMIT)
+        .clamp(1, MAX_EVENT_LIMIT);
+    Ok(Json(app.replay_events(
+        &tenant_id,
+        query.since.as_deref(),
+        limit,
+    )?))
+}
+
+/// `GET /events/stream`: Change events of the tenant as Server-Sent Events, resumed
+/// after the `Last-Event-ID` header when a client reconnects
+async fn events(
+    State(app): State<Arc<App>>,
+    headers: HeaderMap,
+) -> std::result::Result<Response, HttpError> {
+    let tenant_id = tenant(&app, &headers)?;
+    let last_event_id = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok());
+    let stream = app.event_stream(&tenant_id, last_event_id)?;
+
+    let (sender, receiver) = mpsc::channel(1);
+    tokio::spawn(forward(stream, sender));
+    let body = Body::from_stream(ReceiverStream::new(receiver).map(Ok::<_, Infallible>));
+    Ok((
+        [
+            (header::CONTENT_TYPE, sse::CONTENT_TYPE),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Sends the chunks of `stream` until the client hangs up
+async fn forward(mut stream: EventStream, sender: mpsc::Sender<String>) {
+    loop {
+        let chunk = tokio::select! {
+            chunk = stream.next_chunk() => chunk,
+            _ = sender.closed() => break,
+        };
+        let Ok(chunk) = chunk else { break };
+        if sender.send(chunk).await.is_err() {
+            break;
+        }
+    }
+}
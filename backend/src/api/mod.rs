@@ -0,0 +1,8 @@
+pub mod assets;
+pub mod auth;
+pub mod codes;
+pub mod cors;
+pub mod error;
+pub mod grpc;
+pub mod http;
+pub mod request;
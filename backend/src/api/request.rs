@@ -0,0 +1,96 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::{field, info, info_span, Span};
+use uuid::Uuid;
+
+/// Header carrying the correlation id of a request, in both directions
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Correlation id of an HTTP request
+///
+/// Taken from the `x-request-id` header when the caller (or a proxy in front of the
+/// API) sent a usable one, generated otherwise. It is echoed in the response header,
+/// recorded on every log line of the request and included in error bodies, so a user
+/// report can be matched with the logs.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Longest id accepted from a caller
+    pub const MAX_LENGTH: usize = 128;
+
+    /// Propagates the `x-request-id` header, or generates an id if it is missing or unsafe
+    pub fn from_header(header: Option<&str>) -> Self {
+        match header.map(str::trim) {
+            // Only plain characters, so ids cannot forge log lines or headers
+            Some(id)
+                if !id.is_empty()
+                    && id.len() <= Self::MAX_LENGTH
+                    && id
+                        .chars()
+                        .all(|char| char.is_ascii_alphanumeric() || "-_.:".contains(char)) =>
+            {
+                RequestId(id.to_string())
+            }
+            _ => Self::generate(),
+        }
+    }
+
+    /// Generates a new id
+    pub fn generate() -> Self {
+        RequestId(Uuid::new_v4().to_string())
+    }
+
+    /// Returns the id as sent in the header
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Tracing span of an HTTP request, from its reception to its response
+///
+/// The span carries the method, path and request id; the status and latency are
+/// recorded when the request is finished, with one `Request served` log line.
+pub struct RequestSpan {
+    span: Span,
+    started: Instant,
+}
+
+impl RequestSpan {
+    /// Opens the span of a request; handlers run inside [`RequestSpan::span`]
+    pub fn start(method: &str, path: &str, request_id: &RequestId) -> Self {
+        RequestSpan {
+            span: info_span!(
+                "request",
+                method,
+                path,
+                request_id = %request_id,
+                status = field::Empty,
+                latency_ms = field::Empty,
+            ),
+            started: Instant::now(),
+        }
+    }
+
+    /// Returns the span, to instrument the handler of the request
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Records the response status and latency, and returns the latency
+    pub fn finish(self, status: u16) -> Duration {
+        let latency = self.started.elapsed();
+        self.span.record("status", status);
+        self.span.record("latency_ms", latency.as_millis() as u64);
+        self.span.in_scope(|| info!("Request served"));
+        latency
+    }
+}
@@ -0,0 +1,212 @@
+use crate::setup::config::ConfigChange;
+use crate::{Error, Result};
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Mutating operation recorded in the audit log
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    DeviceAdded,
+    DeviceUpdated,
+    DeviceDeleted,
+    PollTriggered,
+    PollingPaused,
+    PollingResumed,
+    MaintenanceAdded,
+    MaintenanceRemoved,
+    ServiceCreated,
+    ServiceDeleted,
+    ServiceStateChanged,
+    ConfigChanged,
+}
+
+/// Who did what and when
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub id: u64,
+    pub actor: String, // API key name or JWT subject
+    pub action: AuditAction,
+    pub target: String, // Device host, config setting...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>, // Tenant of the target, unset for the whole application
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<Value>, // e.g. the applied patch
+    pub date: DateTime<Local>,
+}
+
+/// Filters of `GET /audit?actor=&action=&from=&to=`
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub from: Option<DateTime<Local>>, // Inclusive
+    pub to: Option<DateTime<Local>>,   // Exclusive
+}
+
+impl AuditQuery {
+    /// Returns `true` when the entry passes every filter
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.actor
+            .as_ref()
+            .is_none_or(|actor| &entry.actor == actor)
+            && self.action.is_none_or(|action| entry.action == action)
+            && self.from.is_none_or(|from| entry.date >= from)
+            && self.to.is_none_or(|to| entry.date < to)
+    }
+}
+
+/// Append-only record of every mutating operation
+///
+/// Entries are never updated or deleted; with [`AuditLog::open`] each one is appended to
+/// a JSON Lines file as it is recorded.
+#[derive(Default)]
+pub struct AuditLog {
+    path: Option<PathBuf>,
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    /// Creates an in-memory audit log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the audit log persisted to `path`, loading the entries already recorded
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = vec![];
+        if path.exists() {
+            let content = fs::read_to_string(&path).map_err(|err| {
+                Error::Custom(format!(
+                    "Failed to read audit log {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                let entry: AuditEntry = serde_json::from_str(line)
+                    .map_err(|err| Error::Custom(format!("Invalid audit log: {}", err)))?;
+                entries.push(entry);
+            }
+        }
+
+        Ok(AuditLog {
+            path: Some(path),
+            entries,
+        })
+    }
+
+    /// Records an operation on the whole application and returns the new entry
+    ///
+    /// # Arguments
+    /// - `actor`: Identity resolved from the API key or JWT of the request
+    /// - `action`: What was done
+    /// - `target`: What it was done to
+    /// - `detail`: Optional payload of the operation
+    pub fn record(
+        &mut self,
+        actor: &str,
+        action: AuditAction,
+        target: &str,
+        detail: Option<Value>,
+    ) -> Result<AuditEntry> {
+        self.append(None, actor, action, target, detail)
+    }
+
+    /// Records an operation on a target of `tenant_id`, e.g. one of its devices, seen
+    /// by the tenant in [`AuditLog::query_tenant`]
+    pub fn record_for(
+        &mut self,
+        tenant_id: &str,
+        actor: &str,
+        action: AuditAction,
+        target: &str,
+        detail: Option<Value>,
+    ) -> Result<AuditEntry> {
+        self.append(Some(tenant_id), actor, action, target, detail)
+    }
+
+    fn append(
+        &mut self,
+        tenant_id: Option<&str>,
+        actor: &str,
+        action: AuditAction,
+        target: &str,
+        detail: Option<Value>,
+    ) -> Result<AuditEntry> {
+        let entry = AuditEntry {
+            id: self.entries.last().map_or(1, |last| last.id + 1),
+            actor: actor.to_string(),
+            action,
+            target: target.to_string(),
+            tenant_id: tenant_id.map(str::to_string),
+            detail,
+            date: Local::now(),
+        };
+
+        if let Some(path) = &self.path {
+            let line = serde_json::to_string(&entry).map_err(Error::custom)?;
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", line))
+                .map_err(|err| {
+                    Error::Custom(format!(
+                        "Failed to write audit log {}: {}",
+                        path.display(),
+                        err
+                    ))
+                })?;
+        }
+
+        self.entries.push(entry.clone());
+        Ok(entry)
+    }
+
+    /// Records the settings applied by a configuration reload, one entry per setting
+    ///
+    /// Meant to be called from the `apply` callback of the config watcher, with the
+    /// config file as actor.
+    pub fn record_config_changes(&mut self, actor: &str, changes: &[ConfigChange]) -> Result<()> {
+        for change in changes {
+            self.record(
+                actor,
+                AuditAction::ConfigChanged,
+                &change.setting,
+                Some(json!({"from": change.from, "to": change.to})),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns the entries matching `query`, most recent first, the body of `GET /audit`
+    pub fn query(&self, query: &AuditQuery) -> Vec<AuditEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|entry| query.matches(entry))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the entries of `tenant_id` matching `query`, most recent first
+    ///
+    /// Operations on the whole application, e.g. config reloads, belong to no tenant.
+    pub fn query_tenant(&self, tenant_id: &str, query: &AuditQuery) -> Vec<AuditEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.tenant_id.as_deref() == Some(tenant_id))
+            .filter(|entry| query.matches(entry))
+            .cloned()
+            .collect()
+    }
+}
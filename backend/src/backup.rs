@@ -0,0 +1,230 @@
+use crate::audit::AuditLog;
+use crate::events::store::EventStore;
+use crate::ingest::ObjectStore;
+use crate::models::device::Device;
+use crate::sync::Snapshot;
+use crate::{Error, Result};
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+
+/// Version of the backup layout and of the files it holds, bumped when either changes
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Name of the manifest, the first entry of every backup
+const MANIFEST: &str = "manifest.json";
+
+/// Files of the application data, the options of `cli backup` and `cli restore`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DataFiles {
+    pub devices: Option<PathBuf>,   // JSON array of the devices
+    pub snapshots: Option<PathBuf>, // JSON Lines of the agent snapshots
+    pub events: Option<PathBuf>,    // Event log
+    pub objects: Option<PathBuf>,   // Journal of the current objects
+    pub audit: Option<PathBuf>,     // Audit log
+}
+
+impl DataFiles {
+    /// Returns the set files, by name in the backup
+    fn entries(&self) -> Vec<(&'static str, &PathBuf)> {
+        [
+            ("devices.json", &self.devices),
+            ("snapshots.jsonl", &self.snapshots),
+            ("events.jsonl", &self.events),
+            ("objects.jsonl", &self.objects),
+            ("audit.jsonl", &self.audit),
+        ]
+        .into_iter()
+        .filter_map(|(name, path)| Some((name, path.as_ref()?)))
+        .collect()
+    }
+}
+
+/// Contents of a backup, stored as `manifest.json`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Manifest {
+    pub schema_version: u32,
+    pub created_at: DateTime<Local>,
+    pub files: Vec<String>, // Names of the data files in the backup
+}
+
+/// Writes the existing `files` to a zstd-compressed tar archive at `out`
+///
+/// Unset and missing files are left out, so a backup of a fresh installation holds
+/// only the devices.
+///
+/// # Returns
+/// - `Ok(Manifest)`: The manifest of the written backup
+/// - `Err(Error)`: If a file cannot be read or the archive cannot be written
+pub fn backup(files: &DataFiles, out: impl AsRef<Path>) -> Result<Manifest> {
+    let out = out.as_ref();
+    let entries: Vec<(&str, &PathBuf)> = files
+        .entries()
+        .into_iter()
+        .filter(|(_, path)| path.exists())
+        .collect();
+    let manifest = Manifest {
+        schema_version: SCHEMA_VERSION,
+        created_at: Local::now(),
+        files: entries.iter().map(|(name, _)| name.to_string()).collect(),
+    };
+
+    let write_error =
+        |err: std::io::Error| Error::Custom(format!("Failed to write {}: {}", out.display(), err));
+    let file = File::create(out).map_err(write_error)?;
+    let encoder = zstd::Encoder::new(file, zstd::DEFAULT_COMPRESSION_LEVEL)
+        .map_err(write_error)?
+        .auto_finish();
+    let mut archive = tar::Builder::new(encoder);
+
+    let content = serde_json::to_vec_pretty(&manifest).map_err(Error::custom)?;
+    append(&mut archive, MANIFEST, &content).map_err(write_error)?;
+    for (name, path) in &entries {
+        let content = fs::read(path)
+            .map_err(|err| Error::Custom(format!("Failed to read {}: {}", path.display(), err)))?;
+        append(&mut archive, name, &content).map_err(write_error)?;
+    }
+    archive.into_inner().map_err(write_error)?;
+
+    info!(
+        "Backed up {} to {}",
+        manifest.files.join(", "),
+        out.display()
+    );
+    Ok(manifest)
+}
+
+/// Restores the backup at `archive` to `files`
+///
+/// Every file of the backup needs a destination. Files are first written next to their
+/// destination and loaded like the application would; only when all of them load are
+/// they moved in place, so a bad backup leaves the current data untouched.
+///
+/// # Returns
+/// - `Ok(Manifest)`: The manifest of the restored backup
+/// - `Err(Error)`: If the backup is unreadable, of another schema version, holds an
+///   invalid file or one without destination
+pub fn restore(archive: impl AsRef<Path>, files: &DataFiles) -> Result<Manifest> {
+    let path = archive.as_ref();
+    let read_error =
+        |err: std::io::Error| Error::Custom(format!("Failed to read {}: {}", path.display(), err));
+    let file = File::open(path).map_err(read_error)?;
+    let decoder = zstd::Decoder::new(file).map_err(read_error)?;
+    let mut archive = tar::Archive::new(decoder);
+
+    let mut contents: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    for entry in archive.entries().map_err(read_error)? {
+        let mut entry = entry.map_err(read_error)?;
+        let name = entry.path().map_err(read_error)?.display().to_string();
+        let mut content = vec![];
+        entry.read_to_end(&mut content).map_err(read_error)?;
+        contents.insert(name, content);
+    }
+
+    let manifest: Manifest = contents
+        .get(MANIFEST)
+        .ok_or_else(|| Error::Custom(format!("Invalid backup {}: no manifest", path.display())))
+        .and_then(|content| {
+            serde_json::from_slice(content)
+                .map_err(|err| Error::Custom(format!("Invalid backup {}: {}", path.display(), err)))
+        })?;
+    if manifest.schema_version != SCHEMA_VERSION {
+        return Err(Error::Custom(format!(
+            "Unsupported backup schema version {}, expected {}",
+            manifest.schema_version, SCHEMA_VERSION
+        )));
+    }
+
+    let destinations: BTreeMap<&str, &PathBuf> = files.entries().into_iter().collect();
+    let mut staged: Vec<(PathBuf, &PathBuf)> = vec![];
+    let result = manifest.files.iter().try_for_each(|name| {
+        let destination = destinations
+            .get(name.as_str())
+            .ok_or_else(|| Error::Custom(format!("Not found destination of {}", name)))?;
+        let content = contents
+            .get(name)
+            .ok_or_else(|| Error::Custom(format!("Invalid backup: {} is missing", name)))?;
+        let mut temporary = destination.as_os_str().to_owned();
+        temporary.push(".restore");
+        let temporary = PathBuf::from(temporary);
+        fs::write(&temporary, content).map_err(|err| {
+            Error::Custom(format!("Failed to write {}: {}", temporary.display(), err))
+        })?;
+        staged.push((temporary.clone(), destination));
+        validate(name, &temporary)
+            .map_err(|err| Error::Custom(format!("Invalid {} in backup: {}", name, err)))
+    });
+    if let Err(err) = result {
+        for (temporary, _) in &staged {
+            let _ = fs::remove_file(temporary);
+        }
+        return Err(err);
+    }
+
+    for (temporary, destination) in staged {
+        fs::rename(&temporary, destination).map_err(|err| {
+            Error::Custom(format!(
+                "Failed to write {}: {}",
+                destination.display(),
+                err
+            ))
+        })?;
+    }
+    info!(
+        "Restored {} from {}, backed up {}",
+        manifest.files.join(", "),
+        path.display(),
+        manifest.created_at
+    );
+    Ok(manifest)
+}
+
+fn append<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    content: &[u8],
+) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o600); // Credentials of the devices are in the backup
+    header.set_mtime(Local::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, name, content)
+}
+
+/// Loads the restored file `name` at `path` the way the application reads it
+fn validate(name: &str, path: &Path) -> Result<()> {
+    match name {
+        "devices.json" => {
+            let content = fs::read(path).map_err(Error::custom)?;
+            let devices: Value = serde_json::from_slice(&content).map_err(Error::custom)?;
+            devices
+                .as_array()
+                .ok_or_else(|| Error::from("Device file must hold an array of devices"))?
+                .iter()
+                .try_for_each(|device| Device::from_value(device).map(|_| ()))
+        }
+        "snapshots.jsonl" => {
+            let content = fs::read_to_string(path).map_err(Error::custom)?;
+            content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .try_for_each(|line| {
+                    serde_json::from_str::<Snapshot>(line)
+                        .map(|_| ())
+                        .map_err(Error::custom)
+                })
+        }
+        "events.jsonl" => EventStore::open(path).map(|_| ()),
+        "objects.jsonl" => ObjectStore::open(path).map(|_| ()),
+        "audit.jsonl" => AuditLog::open(path).map(|_| ()),
+        _ => Err(Error::Custom(format!("Unknown file {}", name))),
+    }
+}
@@ -0,0 +1,299 @@
+use backend::capture::ParseCapture;
+use backend::diff::diff_topologies;
+use backend::models::device::Device;
+use backend::models::parse::{ParseMode, ParseOptions};
+use backend::models::topology::{Topology, TOPOLOGY_CONTEXT_PATH};
+use backend::models::vendor::VendorProfile;
+use backend::notifications::spool::{SpoolConfig, SpoolQueue};
+use backend::payloads::PayloadConfig;
+use backend::southbound::restconf::{Fetched, RestconfClient};
+use backend::southbound::FetchOptions;
+use backend::stats::{CycleStats, StatsStore};
+use backend::sync::Snapshot;
+use backend::{Error, Result};
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use std::{env, fs, process};
+
+use chrono::Local;
+use serde_json::Value;
+use tracing::*;
+use uuid::Uuid;
+
+const USAGE: &str = concat!(
+    "Usage: agent --devices <file> --spool <file> --central <url> --agent-id <uuid> ",
+    "--secret <secret> [--interval <seconds>] [--capture <directory>] ",
+    "[--parse-mode <strict|lenient>] [--stats <file>]"
+);
+
+/// Settings of the edge agent, read from the command line
+struct Settings {
+    devices: String,
+    spool: String,
+    central: String,
+    agent_id: Uuid,
+    secret: String,
+    interval: Duration,
+    capture: Option<String>, // Directory keeping the fragments that fail to parse
+    parse_mode: ParseMode,   // Lenient unless strict parsing is asked for
+    stats: Option<String>,   // File keeping the statistics of the poll cycles
+}
+
+/// Edge agent: polls devices on an isolated management network, keeps the snapshots in
+/// a local spool and pushes them to the central backend whenever it is reachable
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match settings(&args.iter().map(String::as_str).collect::<Vec<&str>>()) {
+        Ok(settings) => run(settings).await,
+        Err(err) => Err(err),
+    };
+
+    if let Err(err) = result {
+        error!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn settings(options: &[&str]) -> Result<Settings> {
+    let mut values: HashMap<&str, &str> = HashMap::new();
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        values.insert(option.trim_start_matches("--"), value);
+    }
+    let required = |name: &str| {
+        values
+            .get(name)
+            .map(|value| value.to_string())
+            .ok_or_else(|| Error::from(USAGE))
+    };
+
+    Ok(Settings {
+        devices: required("devices")?,
+        spool: required("spool")?,
+        central: required("central")?.trim_end_matches('/').to_string(),
+        agent_id: Uuid::parse_str(&required("agent-id")?)
+            .map_err(|_| Error::Invalid("Invalid agent id".to_string()))?,
+        secret: required("secret")?,
+        interval: Duration::from_secs(match values.get("interval") {
+            Some(interval) => interval
+                .parse()
+                .map_err(|_| Error::Invalid(format!("Invalid interval {}", interval)))?,
+            None => 300,
+        }),
+        capture: values.get("capture").map(|directory| directory.to_string()),
+        parse_mode: match values.get("parse-mode") {
+            Some(mode) => mode.parse()?,
+            None => ParseMode::default(),
+        },
+        stats: values.get("stats").map(|path| path.to_string()),
+    })
+}
+
+async fn run(settings: Settings) -> Result<()> {
+    let content = fs::read_to_string(&settings.devices)
+        .map_err(|err| Error::Custom(format!("Failed to read {}: {}", settings.devices, err)))?;
+    let registry: Value = serde_json::from_str(&content)
+        .map_err(|err| Error::Custom(format!("Invalid JSON in {}: {}", settings.devices, err)))?;
+    let devices = registry
+        .as_array()
+        .ok_or_else(|| Error::from("Device file must hold an array of devices"))?
+        .iter()
+        .map(Device::from_value)
+        .collect::<Result<Vec<Device>>>()?;
+
+    let mut spool = SpoolQueue::<Snapshot>::open(&settings.spool, SpoolConfig::default())?;
+    let mut previous: HashMap<String, Vec<Topology>> = HashMap::new();
+    // Kept across cycles so that unchanged topology contexts are not fetched again
+    let mut clients: HashMap<String, RestconfClient> = HashMap::new();
+    let http = reqwest::Client::new();
+    let capture = settings
+        .capture
+        .as_ref()
+        .map(|directory| ParseCapture::open(directory, PayloadConfig::default()))
+        .transpose()?;
+    let mut stats = match &settings.stats {
+        Some(path) => StatsStore::open(path)?,
+        None => StatsStore::new(),
+    };
+    let options = ParseOptions {
+        mode: settings.parse_mode,
+        capture: capture.as_ref(),
+        profile: None,
+        version: None,  // Detected per topology
+        previous: None, // Set per device
+    };
+    let profiles = VendorProfile::builtin();
+
+    loop {
+        for device in devices.iter().filter(|device| device.enabled) {
+            let host = device.host.to_string();
+            let options = ParseOptions {
+                profile: VendorProfile::for_device(device, &profiles),
+                ..options
+            };
+            let client = match clients.get(&host) {
+                Some(client) => client,
+                None => match RestconfClient::new(device.clone()) {
+                    Ok(client) => clients.entry(host.clone()).or_insert(client),
+                    Err(err) => {
+                        warn!("Collection from {} failed: {}", host, err);
+                        continue;
+                    }
+                },
+            };
+            match collect(client, &settings, &options, previous.get(&host)).await {
+                Ok(None) => info!("Topologies of {} unchanged", host),
+                Ok(Some((snapshot, topologies, cycle))) => {
+                    info!(
+                        "Collected {} topologies from {}",
+                        snapshot.topologies.len(),
+                        host
+                    );
+                    if let Err(err) = stats.record(&host, cycle) {
+                        warn!("{}", err);
+                    }
+                    spool.push(snapshot)?;
+                    previous.insert(host, topologies);
+                }
+                Err(err) => {
+                    warn!("Collection from {} failed: {}", host, err);
+                    // The next cycle must fetch and parse the whole context again
+                    clients.remove(&host);
+                }
+            }
+        }
+
+        let synced = sync(&http, &settings, &mut spool).await?;
+        if synced > 0 || !spool.is_empty() {
+            info!("Synced {} snapshots, {} pending", synced, spool.len());
+        }
+
+        tokio::time::sleep(settings.interval).await;
+    }
+}
+
+/// Collects the topologies of a device and the changes since the previous collection
+///
+/// Returns `None` when the topology context is unchanged since the previous collection,
+/// which is then not parsed again.
+async fn collect(
+    client: &RestconfClient,
+    settings: &Settings,
+    options: &ParseOptions<'_>,
+    previous: Option<&Vec<Topology>>,
+) -> Result<Option<(Snapshot, Vec<Topology>, CycleStats)>> {
+    let started = Instant::now();
+    let host = client.device().host.to_string();
+    let context = match client
+        .get_if_changed(TOPOLOGY_CONTEXT_PATH, &FetchOptions::default())
+        .await?
+    {
+        Fetched::Unchanged => return Ok(None),
+        Fetched::Modified(context) => context,
+    };
+    let http_latency = started.elapsed();
+    let mut skipped = 0;
+
+    let values = Topology::list_from_context(&context)?;
+    let options = ParseOptions {
+        previous: previous.map(Vec::as_slice),
+        ..*options
+    };
+    let mut topologies = vec![];
+    for value in &values {
+        // Large topologies keep the thread busy, its other tasks are moved meanwhile
+        let (topology, report) =
+            tokio::task::block_in_place(|| Topology::parse(value, &host, &options))?;
+        for issue in &report.issues {
+            warn!(
+                "Skipped {} {} of {}: {}",
+                issue.object,
+                issue.uuid.as_deref().unwrap_or("without uuid"),
+                host,
+                issue.error
+            );
+        }
+        skipped += report.issues.len();
+        topologies.push(topology);
+    }
+
+    let mut events = vec![];
+    for topology in &topologies {
+        let before = previous
+            .into_iter()
+            .flatten()
+            .find(|before| before.uuid == topology.uuid);
+        if let Some(before) = before {
+            events.extend(diff_topologies(before, topology));
+        }
+    }
+
+    let collected_at = Local::now();
+    let cycle = CycleStats {
+        collected_at,
+        parsed: topologies
+            .iter()
+            .map(|topology| topology.nodes.len() + topology.links.len())
+            .sum(),
+        skipped,
+        duration_ms: started.elapsed().as_millis() as u64,
+        payload_bytes: serde_json::to_vec(&context).map_or(0, |payload| payload.len()),
+        http_latency_ms: http_latency.as_millis() as u64,
+    };
+    let snapshot = Snapshot {
+        agent_id: settings.agent_id,
+        sequence: collected_at.timestamp_micros() as u64,
+        host,
+        collected_at,
+        topologies: values,
+        events,
+    };
+    Ok(Some((snapshot, topologies, cycle)))
+}
+
+/// Pushes spooled snapshots in order until the queue is empty or the backend fails
+async fn sync(
+    http: &reqwest::Client,
+    settings: &Settings,
+    spool: &mut SpoolQueue<Snapshot>,
+) -> Result<usize> {
+    let url = format!(
+        "{}/agents/{}/snapshots",
+        settings.central, settings.agent_id
+    );
+    let mut synced = 0;
+
+    while let Some(snapshot) = spool.peek() {
+        let response = http
+            .post(&url)
+            .basic_auth(settings.agent_id, Some(&settings.secret))
+            .json(snapshot)
+            .send()
+            .await;
+        match response {
+            Ok(response) if response.status().is_success() => {
+                spool.pop()?;
+                synced += 1;
+            }
+            Ok(response) => {
+                warn!("Central backend answered {}", response.status());
+                break;
+            }
+            Err(err) => {
+                debug!("Central backend unreachable: {}", err);
+                break;
+            }
+        }
+    }
+
+    Ok(synced)
+}
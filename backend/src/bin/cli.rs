@@ -1,8 +1,1447 @@
+use backend::api::{grpc, http};
+use backend::audit::{AuditAction, AuditLog};
+use backend::backup::{backup, restore, DataFiles};
+use backend::diff::{compare_topologies, diff_links, LinkChange};
+use backend::discovery::scan::Cidr;
+use backend::discovery::{discover, scan, CandidateStore, ScanConfig, CONTROLLER_ROLE};
+use backend::events::store::EventStore;
+use backend::events::ChangeKind;
+use backend::export::anonymize::{Anonymizer, PseudonymMapping};
+use backend::export::graph::{render, GraphFormat};
+use backend::export::output::{render_output, OutputFormat};
+use backend::ingest::ObjectStore;
+use backend::models::address::Port;
+use backend::models::device::{register, Auth, Device, DeviceFilter, Registration};
+use backend::models::topology::Topology;
+use backend::persist;
+use backend::retention::{Age, RetentionPolicy};
+use backend::schema::{validate_context, TapiVersion};
+use backend::search::SearchIndex;
+use backend::setup::app::{App, AppHooks, AppSettings};
+use backend::setup::config::Config;
+use backend::setup::log_setup::logging_init_stderr;
+use backend::southbound::restconf::RestconfClient;
+use backend::sync::Snapshot;
+use backend::tui::{view, Action, Browser};
+use backend::validation::validate;
+use backend::{Error, Result};
+
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::{env, fs, process};
+
+use chrono::{DateTime, Local};
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use serde_json::{json, Value};
 use tracing::*;
 
+const USAGE: &str = concat!(
+    "Usage: cli [--output table|json|yaml] <command>\n",
+    "  cli topology export --input <file> [--format dot|graphml|json] [--host <host>] [--anonymize <mapping file>]\n",
+    "  cli topology diff <host> --snapshots <file> [--from <date>] [--to <date>] [--json]\n",
+    "  cli topology violations <host> --snapshots <file> [--config <file>]\n",
+    "  cli topology compare <left host> <right host> --snapshots <file>\n",
+    "  cli topology check --input <file> [--tapi 2.1.3|2.4]\n",
+    "  cli device list --input <file> [--tag <tag>]... [--site|--region|--vendor|--role <value>]\n",
+    "  cli device add --input <file> --device <file> [--merge true|false] [--audit <file>]\n",
+    "  cli device set --input <file> --host <host> [--enabled true|false] [--maintenance-until <date>|none] [--audit <file>]\n",
+    "  cli device remove --input <file> --host <host> [--audit <file>]\n",
+    "  cli backup --out <file.tar.zst> --devices <file> [--snapshots <file>] [--events <file>] [--objects <file>] [--audit <file>]\n",
+    "  cli restore <file.tar.zst> --devices <file> [--snapshots <file>] [--events <file>] [--objects <file>] [--audit <file>]\n",
+    "  cli discovery run --devices <file> --candidates <file>\n",
+    "  cli discovery scan --range <cidr>... --devices <file> --candidates <file> [--ports <port,...>] [--rate <probes per second>]\n",
+    "  cli discovery list --candidates <file>\n",
+    "  cli discovery approve <host> --devices <file> --candidates <file> [--auth <file>]\n",
+    "  cli discovery reject <host> --candidates <file>\n",
+    "  cli prune --events <file> --older-than <age, e.g. 30d> [--keep <events per device>]\n",
+    "  cli search <uuid|name|ip> --devices <file> [--objects <file>] [--limit <count>]\n",
+    "  cli serve --config <file> --devices <file> [--events <file>] [--objects <file>] [--candidates <file>] [--latency <file>] [--audit <file>] [--stats <file>] [--custom-objects <file>] [--sites <file>] [--captures <directory>] [--maintenance <file>] [--jobs <file>] [--exports <directory>] [--agents <file>] [--spool <directory>] [--snapshots <file>] [--grpc <address>] [--http <address>]\n",
+    "  cli tui --devices <file> [--snapshots <file>] [--config <file>]\n",
+    "  cli completions bash|zsh|fish"
+);
+
+/// Commands and their options, the source of the shell completions
+const COMMANDS: &[(&str, &[&str])] = &[
+    (
+        "topology export",
+        &["--input", "--format", "--host", "--anonymize"],
+    ),
+    (
+        "topology diff",
+        &["--snapshots", "--from", "--to", "--json"],
+    ),
+    ("topology violations", &["--snapshots", "--config"]),
+    ("topology compare", &["--snapshots"]),
+    ("topology check", &["--input", "--tapi"]),
+    (
+        "device list",
+        &[
+            "--input", "--tag", "--site", "--region", "--vendor", "--role",
+        ],
+    ),
+    ("device add", &["--input", "--device", "--merge", "--audit"]),
+    (
+        "device set",
+        &[
+            "--input",
+            "--host",
+            "--enabled",
+            "--maintenance-until",
+            "--audit",
+        ],
+    ),
+    ("device remove", &["--input", "--host", "--audit"]),
+    (
+        "backup",
+        &[
+            "--out",
+            "--devices",
+            "--snapshots",
+            "--events",
+            "--objects",
+            "--audit",
+        ],
+    ),
+    (
+        "restore",
+        &[
+            "--devices",
+            "--snapshots",
+            "--events",
+            "--objects",
+            "--audit",
+        ],
+    ),
+    ("discovery run", &["--devices", "--candidates"]),
+    (
+        "discovery scan",
+        &["--range", "--devices", "--candidates", "--ports", "--rate"],
+    ),
+    ("discovery list", &["--candidates"]),
+    (
+        "discovery approve",
+        &["--devices", "--candidates", "--auth"],
+    ),
+    ("discovery reject", &["--candidates"]),
+    ("prune", &["--events", "--older-than", "--keep"]),
+    ("search", &["--devices", "--objects", "--limit"]),
+    (
+        "serve",
+        &[
+            "--config",
+            "--devices",
+            "--events",
+            "--objects",
+            "--candidates",
+            "--latency",
+            "--audit",
+            "--stats",
+            "--custom-objects",
+            "--sites",
+            "--captures",
+            "--maintenance",
+            "--jobs",
+            "--exports",
+            "--agents",
+            "--spool",
+            "--snapshots",
+            "--grpc",
+            "--http",
+        ],
+    ),
+    ("tui", &["--devices", "--snapshots", "--config"]),
+    ("completions", &[]),
+];
+
+/// How often `cli tui` reads the snapshot file again and updates the statuses
+const TUI_REFRESH: Duration = Duration::from_secs(5);
+
+/// Shells `cli completions` generates scripts for
+const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
 fn main() {
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::DEBUG) // Capture debug messages
-        .init();
-    debug!("This is a debug message!"); // Log a debug message
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    // Log lines would be drawn over the terminal UI, only its final error is printed
+    let level = match args.first().map(String::as_str) {
+        Some("tui") => Some(tracing::Level::ERROR),
+        Some("serve") => None, // Set up from the config, reloaded with it
+        _ => Some(tracing::Level::DEBUG), // Capture debug messages
+    };
+    if let Some(level) = level {
+        tracing_subscriber::fmt()
+            .with_max_level(level)
+            .with_writer(std::io::stderr) // Keep stdout for command output
+            .init();
+    }
+
+    let result = output_format(&mut args).and_then(|output| {
+        match args.iter().map(String::as_str).collect::<Vec<&str>>()[..] {
+            ["topology", "export", ref options @ ..] => topology_export(options),
+            ["topology", "diff", host, ref options @ ..] => topology_diff(host, options, output),
+            ["topology", "violations", host, ref options @ ..] => {
+                topology_violations(host, options, output)
+            }
+            ["topology", "check", ref options @ ..] => topology_check(options, output),
+            ["topology", "compare", left, right, ref options @ ..] => {
+                topology_compare(left, right, options, output)
+            }
+            ["device", "list", ref options @ ..] => device_list(options, output),
+            ["device", "add", ref options @ ..] => device_add(options, output),
+            ["device", "set", ref options @ ..] => device_set(options, output),
+            ["device", "remove", ref options @ ..] => device_remove(options, output),
+            ["backup", ref options @ ..] => backup_command(options, output),
+            ["restore", archive, ref options @ ..] => restore_command(archive, options, output),
+            ["discovery", "run", ref options @ ..] => discovery_run(options, output),
+            ["discovery", "scan", ref options @ ..] => discovery_scan(options, output),
+            ["discovery", "list", ref options @ ..] => discovery_list(options, output),
+            ["discovery", "approve", host, ref options @ ..] => {
+                discovery_approve(host, options, output)
+            }
+            ["discovery", "reject", host, ref options @ ..] => {
+                discovery_reject(host, options, output)
+            }
+            ["prune", ref options @ ..] => prune(options, output),
+            ["search", query, ref options @ ..] => search(query, options, output),
+            ["serve", ref options @ ..] => serve(options),
+            ["tui", ref options @ ..] => tui(options),
+            ["completions", shell] => completions(shell),
+            _ => Err(Error::from(USAGE)),
+        }
+    });
+
+    if let Err(err) = result {
+        // `serve` may fail before its logging is set up
+        if dispatcher::has_been_set() {
+            error!("{}", err);
+        } else {
+            eprintln!("{}", err);
+        }
+        process::exit(1);
+    }
+}
+
+/// Renders a saved `tapi-topology:topology` payload as a graph on stdout
+///
+/// With `--anonymize`, identifiers are replaced by pseudonyms keyed with the
+/// `ANONYMIZATION_KEY` environment variable and the mapping is kept in the given file.
+fn topology_export(options: &[&str]) -> Result<()> {
+    let mut input = None;
+    let mut format = GraphFormat::Dot;
+    let mut host = "localhost";
+    let mut mapping_file = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--input" => input = Some(*value),
+            "--format" => format = value.parse()?,
+            "--host" => host = value,
+            "--anonymize" => mapping_file = Some(*value),
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let input = input.ok_or_else(|| Error::from(USAGE))?;
+
+    let value = read_json(input)?;
+    let mut topology = Topology::from_value(&value, host)?;
+
+    if let Some(mapping_file) = mapping_file {
+        dotenv::dotenv().ok();
+        let key = env::var("ANONYMIZATION_KEY")
+            .map_err(|_| Error::from("ANONYMIZATION_KEY must be set to anonymize exports"))?;
+        let mut anonymizer = Anonymizer::new(key, PseudonymMapping::load(mapping_file)?);
+        anonymizer.anonymize(&mut topology);
+        anonymizer.mapping().save(mapping_file)?;
+    }
+
+    debug!(
+        "Exporting {} nodes and {} links",
+        topology.nodes.len(),
+        topology.links.len()
+    );
+    print!("{}", render(&topology, format));
+    Ok(())
+}
+
+/// Prints the links added, removed and changed between two snapshots of `host`
+///
+/// Snapshots are read from the history written by `cli serve --snapshots`, or any JSON
+/// Lines file of agent snapshots. `--from` and `--to` select the latest snapshot
+/// collected at or before each RFC 3339 date; by default the last two snapshots are
+/// compared.
+fn topology_diff(host: &str, options: &[&str], mut output: OutputFormat) -> Result<()> {
+    let mut input = None;
+    let mut from = None;
+    let mut to = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        // Shorthand for `--output json`
+        if *option == "--json" {
+            output = OutputFormat::Json;
+            continue;
+        }
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--snapshots" => input = Some(*value),
+            "--from" => from = Some(parse_date(value)?),
+            "--to" => to = Some(parse_date(value)?),
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let input = input.ok_or_else(|| Error::from(USAGE))?;
+
+    let mut snapshots = read_snapshots(input)?;
+    snapshots.retain(|snapshot| snapshot.host == host);
+    snapshots.sort_by_key(|snapshot| snapshot.collected_at);
+
+    // Index of the latest snapshot collected at or before `date`
+    let at = |date: DateTime<Local>| {
+        snapshots
+            .iter()
+            .rposition(|snapshot| snapshot.collected_at <= date)
+            .ok_or_else(|| Error::NotFound(format!("Not found snapshot of {} at {}", host, date)))
+    };
+    let to = match to {
+        Some(date) => at(date)?,
+        None => snapshots
+            .len()
+            .checked_sub(1)
+            .ok_or_else(|| Error::NotFound(format!("Not found snapshot of {}", host)))?,
+    };
+    let from = match from {
+        Some(date) => at(date)?,
+        None => to
+            .checked_sub(1)
+            .ok_or_else(|| Error::NotFound(format!("Not found previous snapshot of {}", host)))?,
+    };
+    let (from, to) = (&snapshots[from], &snapshots[to]);
+
+    let topologies = |snapshot: &Snapshot| -> Result<Vec<Topology>> {
+        snapshot
+            .topologies
+            .iter()
+            .map(|value| Topology::from_value(value, host))
+            .collect()
+    };
+    let diff = diff_links(&topologies(from)?, &topologies(to)?);
+
+    if output != OutputFormat::Table {
+        let summary = json!({
+            "host": host,
+            "from": from.collected_at,
+            "to": to.collected_at,
+            "added": diff.added,
+            "removed": diff.removed,
+            "changed": diff.changed,
+        });
+        print!("{}", render_output(&summary, output)?);
+        return Ok(());
+    }
+
+    // Colored only on a terminal, unless disabled with `NO_COLOR`
+    let color = std::io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let paint = |code: &str, text: String| {
+        if color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text
+        }
+    };
+    println!(
+        "Links of {} from {} to {}",
+        host,
+        from.collected_at.to_rfc3339(),
+        to.collected_at.to_rfc3339()
+    );
+    for (sign, code, changes) in [
+        ("+", "32", &diff.added),
+        ("-", "31", &diff.removed),
+        ("~", "33", &diff.changed),
+    ] {
+        for change in changes {
+            println!("{}", paint(code, format!("{} {}", sign, describe(change))));
+        }
+    }
+    println!(
+        "{} added, {} removed, {} changed",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    );
+    Ok(())
+}
+
+/// Prints the validation rules broken by the latest snapshot of `host`
+///
+/// The latest snapshot of every other device of the file is read too, to find link
+/// UUIDs reported twice. Rules are those of `[validation]` in `--config`, all by default.
+fn topology_violations(host: &str, options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut input = None;
+    let mut config = Config::default();
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--snapshots" => input = Some(*value),
+            "--config" => config = Config::load(value)?,
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let input = input.ok_or_else(|| Error::from(USAGE))?;
+
+    let snapshots = latest_topologies(input)?;
+    if !snapshots.contains_key(host) {
+        return Err(Error::NotFound(format!("Not found snapshot of {}", host)));
+    }
+    let violations = validate(host, &snapshots, &config.validation.rules);
+    let violations = serde_json::to_value(violations).map_err(Error::custom)?;
+    print!("{}", render_output(&violations, output)?);
+    Ok(())
+}
+
+/// Prints the nodes and links reported by only one of two devices
+///
+/// The latest snapshot of each device is read from a JSON Lines file of agent
+/// snapshots. Objects are aligned by UUID, then by name.
+fn topology_compare(left: &str, right: &str, options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut input = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--snapshots" => input = Some(*value),
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let input = input.ok_or_else(|| Error::from(USAGE))?;
+
+    let snapshots = latest_topologies(input)?;
+    let topologies = |host: &str| {
+        snapshots
+            .get(host)
+            .map(Vec::as_slice)
+            .ok_or_else(|| Error::NotFound(format!("Not found snapshot of {}", host)))
+    };
+    let comparison = compare_topologies((left, topologies(left)?), (right, topologies(right)?));
+    let comparison = serde_json::to_value(comparison).map_err(Error::custom)?;
+    print!("{}", render_output(&comparison, output)?);
+    Ok(())
+}
+
+/// Prints where a saved `tapi-topology:topology-context` payload breaks the TAPI schema
+///
+/// The schema of TAPI 2.1.3 is used unless `--tapi` names another release.
+fn topology_check(options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut input = None;
+    let mut version = TapiVersion::V2_1_3;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--input" => input = Some(*value),
+            "--tapi" => version = value.parse()?,
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let input = input.ok_or_else(|| Error::from(USAGE))?;
+
+    let violations = validate_context(&read_json(input)?, version)?;
+    info!("{} schema violations of TAPI {}", violations.len(), version);
+    let violations = serde_json::to_value(violations).map_err(Error::custom)?;
+    print!("{}", render_output(&violations, output)?);
+    Ok(())
+}
+
+/// Describes a link change on one line, e.g. `<uuid> (MAD-BCN) PLANNED -> INSTALLED`
+fn describe(change: &LinkChange) -> String {
+    let mut line = change.uuid.to_string();
+    if let Some(name) = &change.name {
+        line.push_str(&format!(" ({})", name));
+    }
+    if let ChangeKind::LifecycleTransition { from, to } = &change.change {
+        let state = |state| match serde_json::to_value(state) {
+            Ok(Value::String(state)) => state,
+            _ => "none".to_string(),
+        };
+        line.push_str(&format!(" {} -> {}", state(from), state(to)));
+    }
+    line
+}
+
+/// Parses an RFC 3339 date, e.g. `2024-10-01T12:00:00+02:00`
+fn parse_date(value: &str) -> Result<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|date| date.with_timezone(&Local))
+        .map_err(|_| Error::Invalid(format!("Invalid date {}", value)))
+}
+
+/// Lists the devices of a registry file matching the given tags and metadata
+fn device_list(options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut input = None;
+    let mut filter = DeviceFilter::default();
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--input" => input = Some(*value),
+            "--tag" => filter.tags.push(value.to_string()),
+            "--site" | "--region" | "--vendor" | "--role" => {
+                filter.metadata.insert(
+                    option.trim_start_matches("--").to_string(),
+                    value.to_string(),
+                );
+            }
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let input = input.ok_or_else(|| Error::from(USAGE))?;
+
+    let devices = read_devices(input)?;
+
+    let devices: Vec<Value> = filter.apply(&devices).into_iter().map(summary).collect();
+    print!("{}", render_output(&Value::Array(devices), output)?);
+    Ok(())
+}
+
+/// Returns the fields of a device printed by the device commands
+///
+/// Credentials are never printed.
+fn summary(device: &Device) -> Value {
+    json!({
+        "host": device.host,
+        "port": device.port,
+        "tags": device.tags,
+        "metadata": device.metadata,
+        "enabled": device.enabled,
+        "maintenance_until": device.maintenance_until,
+    })
+}
+
+/// Adds a device to a registry file, refusing or merging duplicates
+///
+/// A device whose host resolves to a registered device (same IP, another hostname) is
+/// a duplicate: the existing entry is printed and the command fails, unless
+/// `--merge true` merges the tags and metadata into it.
+fn device_add(options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut input = None;
+    let mut device_file = None;
+    let mut merge = false;
+    let mut audit = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--input" => input = Some(*value),
+            "--device" => device_file = Some(*value),
+            "--audit" => audit = Some(*value),
+            "--merge" => {
+                merge = value
+                    .parse()
+                    .map_err(|_| Error::Invalid(format!("Invalid merge flag {}", value)))?
+            }
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let input = input.ok_or_else(|| Error::from(USAGE))?;
+    let device_file = device_file.ok_or_else(|| Error::from(USAGE))?;
+
+    let mut registry = read_json(input)?;
+    let entries = registry
+        .as_array_mut()
+        .ok_or_else(|| Error::from("Device file must hold an array of devices"))?;
+    let mut devices = entries
+        .iter()
+        .map(Device::from_value)
+        .collect::<Result<Vec<Device>>>()?;
+    let entry = read_json(device_file)?;
+    let device = Device::from_value(&entry)?;
+
+    // Entries are written back as read, only the added or merged one changes
+    let (index, action) = match register(&mut devices, device, merge) {
+        Registration::Added(index) => {
+            entries.push(entry);
+            info!("Device {} added", devices[index].host);
+            (index, AuditAction::DeviceAdded)
+        }
+        Registration::Merged(index) => {
+            entries[index]["tags"] = json!(devices[index].tags);
+            entries[index]["metadata"] = json!(devices[index].metadata);
+            info!("Device merged into {}", devices[index].host);
+            (index, AuditAction::DeviceUpdated)
+        }
+        Registration::Conflict(index) => {
+            print!("{}", render_output(&summary(&devices[index]), output)?);
+            return Err(Error::Custom(format!(
+                "Device {} is already registered as {}, use --merge true to merge them",
+                entry["host"].as_str().unwrap_or_default(),
+                devices[index].host
+            )));
+        }
+    };
+
+    write_registry(input, &registry)?;
+    if let Some(audit) = audit {
+        let detail = json!({"tags": devices[index].tags, "metadata": devices[index].metadata});
+        let device = &devices[index];
+        record_audit(audit, device, action, detail)?;
+    }
+    print!("{}", render_output(&summary(&devices[index]), output)?);
+    Ok(())
+}
+
+/// Appends an operation of the current user on `device` to the audit log at `path`
+fn record_audit(path: &str, device: &Device, action: AuditAction, detail: Value) -> Result<()> {
+    let actor = env::var("USER").unwrap_or_else(|_| "cli".to_string());
+    AuditLog::open(path)?.record_for(
+        &device.tenant_id,
+        &actor,
+        action,
+        &device.host.to_string(),
+        Some(detail),
+    )?;
+    Ok(())
+}
+
+/// Writes the data files of the application to a compressed backup
+fn backup_command(options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut out = None;
+    let mut files = DataFiles::default();
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--out" => out = Some(*value),
+            _ => set_data_file(&mut files, option, value)?,
+        }
+    }
+    let out = out.ok_or_else(|| Error::from(USAGE))?;
+    if files.devices.is_none() {
+        return Err(Error::from(USAGE));
+    }
+
+    let manifest = backup(&files, out)?;
+    let manifest = serde_json::to_value(manifest).map_err(Error::custom)?;
+    print!("{}", render_output(&manifest, output)?);
+    Ok(())
+}
+
+/// Restores a backup over the data files of the application
+///
+/// The service should be stopped first, it would overwrite the restored files.
+fn restore_command(archive: &str, options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut files = DataFiles::default();
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        set_data_file(&mut files, option, value)?;
+    }
+    if files.devices.is_none() {
+        return Err(Error::from(USAGE));
+    }
+
+    let manifest = restore(archive, &files)?;
+    let manifest = serde_json::to_value(manifest).map_err(Error::custom)?;
+    print!("{}", render_output(&manifest, output)?);
+    Ok(())
+}
+
+/// Sets the data file of a `cli backup`/`cli restore` option
+fn set_data_file(files: &mut DataFiles, option: &str, value: &str) -> Result<()> {
+    let path = Some(PathBuf::from(value));
+    match option {
+        "--devices" => files.devices = path,
+        "--snapshots" => files.snapshots = path,
+        "--events" => files.events = path,
+        "--objects" => files.objects = path,
+        "--audit" => files.audit = path,
+        _ => return Err(Error::from(USAGE)),
+    }
+    Ok(())
+}
+
+/// Searches the devices of a registry file and the objects of an object journal
+///
+/// The query is a UUID, a name or a host/IP, matched in full or in part.
+fn search(query: &str, options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut devices = None;
+    let mut objects = None;
+    let mut limit = 50;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--devices" => devices = Some(*value),
+            "--objects" => objects = Some(*value),
+            "--limit" => {
+                limit = value
+                    .parse()
+                    .map_err(|_| Error::Invalid(format!("Invalid limit {}", value)))?
+            }
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let devices = read_devices(devices.ok_or_else(|| Error::from(USAGE))?)?;
+    let objects = match objects {
+        Some(path) => ObjectStore::open(path)?,
+        None => ObjectStore::new(),
+    };
+
+    let hits = SearchIndex::build(&devices, &objects).search(query, limit)?;
+    let hits = serde_json::to_value(hits).map_err(Error::custom)?;
+    print!("{}", render_output(&hits, output)?);
+    Ok(())
+}
+
+/// Queries the controllers of a registry file and proposes the devices they manage
+///
+/// Controllers are the enabled devices with the `role` metadata `controller`. Devices
+/// already registered or proposed are left out; the pending candidates are printed.
+fn discovery_run(options: &[&str], output: OutputFormat) -> Result<()> {
+    let (devices, candidates) = discovery_files(options)?;
+    let devices = read_devices(devices.ok_or_else(|| Error::from(USAGE))?)?;
+    let mut store = CandidateStore::open(candidates)?;
+
+    let runtime = tokio::runtime::Runtime::new().map_err(Error::custom)?;
+    let controllers = devices.iter().filter(|device| {
+        device.enabled && device.metadata.get("role").map(String::as_str) == Some(CONTROLLER_ROLE)
+    });
+    for controller in controllers {
+        let host = controller.host.to_string();
+        let discovered = RestconfClient::new(controller.clone())
+            .and_then(|client| runtime.block_on(discover(&client, &host)));
+        match discovered {
+            Ok(discovered) => {
+                let proposed = store.propose(discovered, &devices)?;
+                info!("{} new candidates from {}", proposed, host);
+            }
+            Err(err) => warn!("Discovery from {} failed: {}", host, err),
+        }
+    }
+
+    print_candidates(&store, output)
+}
+
+/// Scans networks for RESTCONF/TAPI and NETCONF devices and proposes them
+///
+/// Only candidates are recorded; nothing is registered until approved.
+fn discovery_scan(options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut ranges = vec![];
+    let mut devices = None;
+    let mut candidates = None;
+    let mut ports = None;
+    let mut rate = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--range" => ranges.push(value.parse::<Cidr>()?),
+            "--devices" => devices = Some(*value),
+            "--candidates" => candidates = Some(*value),
+            "--ports" => {
+                ports = Some(
+                    value
+                        .split(',')
+                        .map(str::parse::<Port>)
+                        .collect::<Result<Vec<Port>>>()?,
+                )
+            }
+            "--rate" => {
+                rate = Some(
+                    value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|rate| *rate > 0)
+                        .ok_or_else(|| Error::Invalid(format!("Invalid rate {}", value)))?,
+                )
+            }
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let devices = read_devices(devices.ok_or_else(|| Error::from(USAGE))?)?;
+    let mut store = CandidateStore::open(candidates.ok_or_else(|| Error::from(USAGE))?)?;
+    if ranges.is_empty() {
+        return Err(Error::from(USAGE));
+    }
+
+    // The defaults of `[discovery]` for what is not given
+    let mut config: ScanConfig =
+        serde_json::from_value(json!({ "ranges": ranges })).map_err(Error::custom)?;
+    config.ports = ports.unwrap_or(config.ports);
+    config.rate = rate.unwrap_or(config.rate);
+
+    let runtime = tokio::runtime::Runtime::new().map_err(Error::custom)?;
+    let found = runtime.block_on(scan(&config))?;
+    let proposed = store.propose(found, &devices)?;
+    info!("{} new candidates", proposed);
+
+    print_candidates(&store, output)
+}
+
+/// Prints the candidates of a discovery file waiting for approval
+fn discovery_list(options: &[&str], output: OutputFormat) -> Result<()> {
+    let (_, candidates) = discovery_files(options)?;
+    print_candidates(&CandidateStore::open(candidates)?, output)
+}
+
+/// Registers a discovered device in a registry file
+///
+/// Devices found by a scan need `--auth`, a JSON file of credentials like the `auth`
+/// of a device; managed devices default to the credentials of their controller.
+fn discovery_approve(host: &str, options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut input = None;
+    let mut candidates = None;
+    let mut auth = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--devices" => input = Some(*value),
+            "--candidates" => candidates = Some(*value),
+            "--auth" => auth = Some(read_json(value)?),
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let input = input.ok_or_else(|| Error::from(USAGE))?;
+    let mut store = CandidateStore::open(candidates.ok_or_else(|| Error::from(USAGE))?)?;
+    let controller = store
+        .pending()
+        .into_iter()
+        .find(|candidate| candidate.host.as_str().eq_ignore_ascii_case(host))
+        .and_then(|candidate| candidate.controller.clone());
+
+    let mut registry = read_json(input)?;
+    let entries = registry
+        .as_array_mut()
+        .ok_or_else(|| Error::from("Device file must hold an array of devices"))?;
+    let mut devices = entries
+        .iter()
+        .map(Device::from_value)
+        .collect::<Result<Vec<Device>>>()?;
+
+    let index = match store.approve(
+        host,
+        auth.as_ref().map(Auth::from_value).transpose()?,
+        &mut devices,
+    )? {
+        Registration::Added(index) => {
+            // Credentials are written as given, or as those of the controller entry
+            let auth = match auth {
+                Some(auth) => auth,
+                None => entries
+                    .iter()
+                    .find(|entry| entry["host"].as_str() == controller.as_deref())
+                    .map(|entry| entry["auth"].clone())
+                    .unwrap_or_default(),
+            };
+            let mut entry = serde_json::to_value(&devices[index]).map_err(Error::custom)?;
+            entry["auth"] = auth;
+            entries.push(entry);
+            info!("Device {} added", devices[index].host);
+            index
+        }
+        Registration::Merged(index) | Registration::Conflict(index) => {
+            return Err(Error::Custom(format!(
+                "Device {} is already registered as {}",
+                host, devices[index].host
+            )));
+        }
+    };
+
+    write_registry(input, &registry)?;
+    print!("{}", render_output(&summary(&devices[index]), output)?);
+    Ok(())
+}
+
+/// Rejects a discovered device, so it is not proposed again
+fn discovery_reject(host: &str, options: &[&str], output: OutputFormat) -> Result<()> {
+    let (_, candidates) = discovery_files(options)?;
+    let mut store = CandidateStore::open(candidates)?;
+    store.reject(host)?;
+    print_candidates(&store, output)
+}
+
+/// Reads the `--devices` and the required `--candidates` options of `cli discovery`
+fn discovery_files<'a>(options: &[&'a str]) -> Result<(Option<&'a str>, &'a str)> {
+    let mut devices = None;
+    let mut candidates = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--devices" => devices = Some(*value),
+            "--candidates" => candidates = Some(*value),
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    Ok((devices, candidates.ok_or_else(|| Error::from(USAGE))?))
+}
+
+fn print_candidates(store: &CandidateStore, output: OutputFormat) -> Result<()> {
+    let pending = serde_json::to_value(store.pending()).map_err(Error::custom)?;
+    print!("{}", render_output(&pending, output)?);
+    Ok(())
+}
+
+/// Pauses/resumes polling or sets the maintenance window of a device in a registry file
+fn device_set(options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut input = None;
+    let mut host = None;
+    let mut enabled = None;
+    let mut maintenance_until = None; // `Some(None)` ends the maintenance
+    let mut audit = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--input" => input = Some(*value),
+            "--host" => host = Some(*value),
+            "--audit" => audit = Some(*value),
+            "--enabled" => {
+                enabled = Some(
+                    value
+                        .parse()
+                        .map_err(|_| Error::Invalid(format!("Invalid enabled flag {}", value)))?,
+                )
+            }
+            "--maintenance-until" if *value == "none" => maintenance_until = Some(None),
+            "--maintenance-until" => {
+                let until = serde_json::from_value(json!(value))
+                    .map_err(|_| Error::Invalid(format!("Invalid maintenance date {}", value)))?;
+                maintenance_until = Some(Some(until));
+            }
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let input = input.ok_or_else(|| Error::from(USAGE))?;
+    let host = host.ok_or_else(|| Error::from(USAGE))?;
+
+    let mut registry = read_json(input)?;
+    let entry = registry
+        .as_array_mut()
+        .ok_or_else(|| Error::from("Device file must hold an array of devices"))?
+        .iter_mut()
+        .find(|entry| entry.get("host").and_then(Value::as_str) == Some(host))
+        .ok_or_else(|| Error::Custom(format!("Device {} not found", host)))?;
+
+    // Patch the registry entry in place so the rest of it is written back untouched
+    let mut device = Device::from_value(entry)?;
+    let mut detail = json!({});
+    if let Some(enabled) = enabled {
+        device.enabled = enabled;
+        detail["enabled"] = json!(enabled);
+    }
+    if let Some(maintenance_until) = maintenance_until {
+        device.maintenance_until = maintenance_until;
+        detail["maintenance_until"] = json!(maintenance_until);
+    }
+    entry["enabled"] = json!(device.enabled);
+    entry["maintenance_until"] = json!(device.maintenance_until);
+
+    write_registry(input, &registry)?;
+    info!("Device {} updated", host);
+    if let Some(audit) = audit {
+        record_audit(audit, &device, AuditAction::DeviceUpdated, detail)?;
+    }
+    print!("{}", render_output(&summary(&device), output)?);
+    Ok(())
+}
+
+/// Removes a device from a registry file
+fn device_remove(options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut input = None;
+    let mut host = None;
+    let mut audit = None;
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--input" => input = Some(*value),
+            "--host" => host = Some(*value),
+            "--audit" => audit = Some(*value),
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let input = input.ok_or_else(|| Error::from(USAGE))?;
+    let host = host.ok_or_else(|| Error::from(USAGE))?;
+
+    let mut registry = read_json(input)?;
+    let entries = registry
+        .as_array_mut()
+        .ok_or_else(|| Error::from("Device file must hold an array of devices"))?;
+    let index = entries
+        .iter()
+        .position(|entry| entry.get("host").and_then(Value::as_str) == Some(host))
+        .ok_or_else(|| Error::Custom(format!("Device {} not found", host)))?;
+    let device = Device::from_value(&entries.remove(index))?;
+
+    write_registry(input, &registry)?;
+    info!("Device {} removed", host);
+    if let Some(audit) = audit {
+        let detail = json!({"tags": device.tags, "metadata": device.metadata});
+        record_audit(audit, &device, AuditAction::DeviceDeleted, detail)?;
+    }
+    print!("{}", render_output(&summary(&device), output)?);
+    Ok(())
+}
+
+/// Deletes the events of an event log file beyond the given retention limits
+fn prune(options: &[&str], output: OutputFormat) -> Result<()> {
+    let mut events = None;
+    let mut policy = RetentionPolicy::default();
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--events" => events = Some(*value),
+            "--older-than" => policy.max_age = Some(value.parse::<Age>()?),
+            "--keep" => {
+                policy.max_events_per_device = Some(
+                    value
+                        .parse()
+                        .map_err(|_| Error::Invalid(format!("Invalid event count {}", value)))?,
+                )
+            }
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let events = events.ok_or_else(|| Error::from(USAGE))?;
+    if policy == RetentionPolicy::default() {
+        return Err(Error::from(USAGE));
+    }
+
+    let mut store = EventStore::open(events)?;
+    let deleted = store.prune(&policy, chrono::Local::now())?;
+    match output {
+        OutputFormat::Table => println!("Deleted {} events, {} left", deleted, store.len()),
+        output => print!(
+            "{}",
+            render_output(&json!({"deleted": deleted, "left": store.len()}), output)?
+        ),
+    }
+    Ok(())
+}
+
+/// Runs the collectors and notifications of the devices until interrupted
+///
+/// With `--http`, the HTTP API is served on the address, and with `--grpc` the gRPC API
+/// of `proto/device_manager.proto`; both to the clients holding a token of the `[api]`
+/// section of the config. With `--snapshots`, every collection that changed a topology
+/// and every snapshot pushed by an agent is appended to the file, the history read by
+/// `cli topology diff` and the other commands taking `--snapshots`.
+fn serve(options: &[&str]) -> Result<()> {
+    let mut settings = AppSettings::default();
+    let (mut config, mut devices) = (false, false);
+    let (mut grpc_address, mut http_address) = (None, None);
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--config" => (settings.config, config) = (PathBuf::from(value), true),
+            "--devices" => (settings.devices, devices) = (PathBuf::from(value), true),
+            "--events" => settings.events = Some(PathBuf::from(value)),
+            "--objects" => settings.objects = Some(PathBuf::from(value)),
+            "--candidates" => settings.candidates = Some(PathBuf::from(value)),
+            "--latency" => settings.latency = Some(PathBuf::from(value)),
+            "--audit" => settings.audit = Some(PathBuf::from(value)),
+            "--stats" => settings.stats = Some(PathBuf::from(value)),
+            "--custom-objects" => settings.custom_objects = Some(PathBuf::from(value)),
+            "--sites" => settings.sites = Some(PathBuf::from(value)),
+            "--captures" => settings.captures = Some(PathBuf::from(value)),
+            "--maintenance" => settings.maintenance = Some(PathBuf::from(value)),
+            "--jobs" => settings.jobs = Some(PathBuf::from(value)),
+            "--exports" => settings.exports = Some(PathBuf::from(value)),
+            "--agents" => settings.agents = Some(PathBuf::from(value)),
+            "--spool" => settings.spool = Some(PathBuf::from(value)),
+            "--snapshots" => settings.snapshots = Some(PathBuf::from(value)),
+            "--grpc" => grpc_address = Some(*value),
+            "--http" => http_address = Some(*value),
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    if !config || !devices {
+        return Err(Error::from(USAGE));
+    }
+
+    // The log level follows the config; spans are exported if `[telemetry]` says so
+    let startup = Config::load(&settings.config)?;
+    let (log_filter, _telemetry) = logging_init_stderr(&startup.log_level, &startup.telemetry)?;
+
+    let runtime = tokio::runtime::Runtime::new().map_err(Error::custom)?;
+    runtime.block_on(async {
+        let hooks = AppHooks {
+            log_filter: Some(log_filter),
+            ..Default::default()
+        };
+        let app = Arc::new(App::start_with(&settings, hooks)?);
+        let mut servers = vec![];
+        if let Some(address) = http_address {
+            let listener = tokio::net::TcpListener::bind(address)
+                .await
+                .map_err(|err| {
+                    Error::Invalid(format!("Invalid HTTP address {}: {}", address, err))
+                })?;
+            servers.push(tokio::spawn(http::serve(Arc::clone(&app), listener)));
+        }
+        if let Some(address) = grpc_address {
+            let listener = tokio::net::TcpListener::bind(address)
+                .await
+                .map_err(|err| {
+                    Error::Invalid(format!("Invalid gRPC address {}: {}", address, err))
+                })?;
+            servers.push(tokio::spawn(grpc::serve(Arc::clone(&app), listener)));
+        }
+        tokio::signal::ctrl_c().await.map_err(Error::custom)?;
+        for server in servers {
+            server.abort();
+            let _ = server.await;
+        }
+        // Connections still open hold the app, their tasks end with the runtime
+        match Arc::try_unwrap(app) {
+            Ok(app) => app.shutdown(),
+            Err(_) => info!("Device Manager stopped with API connections open"),
+        }
+        Ok(())
+    })
+}
+
+/// Browses the devices of a registry file and their latest snapshots in the terminal
+///
+/// The snapshot file is read again every few seconds when it changes, so the statuses
+/// and topologies follow the collection.
+fn tui(options: &[&str]) -> Result<()> {
+    let mut devices = None;
+    let mut snapshots = None;
+    let mut config = Config::default();
+
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        let value = options
+            .next()
+            .ok_or_else(|| Error::Invalid(format!("Missing value for {}", option)))?;
+        match *option {
+            "--devices" => devices = Some(*value),
+            "--snapshots" => snapshots = Some(*value),
+            "--config" => config = Config::load(value)?,
+            _ => return Err(Error::from(USAGE)),
+        }
+    }
+    let devices = devices.ok_or_else(|| Error::from(USAGE))?;
+
+    let mut browser = Browser::new(read_devices(devices)?, config);
+    // Modification date of the snapshot file when it was last read
+    let mut loaded = None;
+    let mut reload = |browser: &mut Browser, force: bool| -> Result<()> {
+        let Some(path) = snapshots else {
+            return Ok(());
+        };
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if force || modified != loaded {
+            browser.load_snapshots(&read_snapshots(path)?, Local::now());
+            loaded = modified;
+        }
+        Ok(())
+    };
+    reload(&mut browser, true)?;
+
+    let mut terminal = ratatui::init();
+    let mut refreshed = Instant::now();
+    let result = loop {
+        if let Err(err) = terminal.draw(|frame| view::render(frame, &browser)) {
+            break Err(Error::Custom(format!("Failed to draw: {}", err)));
+        }
+        match event::poll(Duration::from_millis(250)) {
+            Ok(true) => match event::read() {
+                Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    match browser.handle(key.code) {
+                        Action::Quit => break Ok(()),
+                        Action::Reload => {
+                            if let Err(err) = reload(&mut browser, true) {
+                                break Err(err);
+                            }
+                        }
+                        Action::None => {}
+                    }
+                }
+                Ok(_) => {}
+                Err(err) => break Err(Error::Custom(format!("Failed to read input: {}", err))),
+            },
+            Ok(false) => {}
+            Err(err) => break Err(Error::Custom(format!("Failed to read input: {}", err))),
+        }
+        if refreshed.elapsed() >= TUI_REFRESH {
+            refreshed = Instant::now();
+            if let Err(err) = reload(&mut browser, false) {
+                break Err(err);
+            }
+            browser.refresh(Local::now());
+        }
+    };
+    ratatui::restore();
+    result
+}
+
+/// Removes the global `--output <format>` option from `args`, wherever it appears
+fn output_format(args: &mut Vec<String>) -> Result<OutputFormat> {
+    let Some(index) = args.iter().position(|arg| arg == "--output") else {
+        return Ok(OutputFormat::default());
+    };
+    let value = args
+        .get(index + 1)
+        .ok_or_else(|| Error::Invalid("Missing value for --output".to_string()))?;
+    let output = value.parse()?;
+    args.drain(index..index + 2);
+    Ok(output)
+}
+
+/// Prints the completion script of `shell` for the commands of [`COMMANDS`]
+fn completions(shell: &str) -> Result<()> {
+    // Commands grouped under a first word, e.g. `topology` -> `export diff`
+    let mut groups: Vec<(&str, Vec<&str>)> = vec![];
+    for (command, _) in COMMANDS {
+        let mut words = command.split(' ');
+        let first = words.next().unwrap_or_default();
+        let index = match groups.iter().position(|(group, _)| *group == first) {
+            Some(index) => index,
+            None => {
+                groups.push((first, vec![]));
+                groups.len() - 1
+            }
+        };
+        groups[index].1.extend(words);
+    }
+    let top: Vec<&str> = groups.iter().map(|(group, _)| *group).collect();
+    let formats = "table json yaml";
+    // Options of a command, with the global flag unless the command takes none
+    let options = |options: &[&str]| match options {
+        [] => String::new(),
+        options => format!("{} --output", options.join(" ")),
+    };
+
+    let mut script = String::new();
+    match shell {
+        "bash" => {
+            script.push_str("_cli() {\n");
+            script.push_str("    local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\" opts\n");
+            script.push_str("    local words=\"${COMP_WORDS[*]:1:COMP_CWORD-1}\"\n");
+            script.push_str("    words=\"${words#--output * }\"\n");
+            script.push_str("    case \"$prev\" in\n");
+            script.push_str(&format!("        --output) opts=\"{}\" ;;\n", formats));
+            script.push_str(&format!(
+                "        completions) opts=\"{}\" ;;\n",
+                SHELLS.join(" ")
+            ));
+            script.push_str("        *)\n            case \"$words\" in\n");
+            for (command, arguments) in COMMANDS {
+                script.push_str(&format!(
+                    "                {}*) opts=\"{}\" ;;\n",
+                    command.replace(' ', "\\ "),
+                    options(arguments)
+                ));
+            }
+            for (group, commands) in groups.iter().filter(|(_, commands)| !commands.is_empty()) {
+                script.push_str(&format!(
+                    "                {}*) opts=\"{}\" ;;\n",
+                    group,
+                    commands.join(" ")
+                ));
+            }
+            script.push_str(&format!(
+                "                *) opts=\"{} --output\" ;;\n",
+                top.join(" ")
+            ));
+            script.push_str("            esac ;;\n    esac\n");
+            script.push_str("    COMPREPLY=($(compgen -W \"$opts\" -- \"$cur\"))\n}\n");
+            script.push_str("complete -o default -F _cli cli\n");
+        }
+        "zsh" => {
+            script.push_str("#compdef cli\n\n_cli() {\n    local -a opts\n");
+            script.push_str("    local line=\"${words[2,CURRENT-1]}\"\n");
+            script.push_str("    line=\"${line#--output * }\"\n");
+            script.push_str("    case \"${words[CURRENT-1]}\" in\n");
+            script.push_str(&format!("        --output) opts=({}) ;;\n", formats));
+            script.push_str(&format!(
+                "        completions) opts=({}) ;;\n",
+                SHELLS.join(" ")
+            ));
+            script.push_str("        *)\n            case \"$line\" in\n");
+            for (command, arguments) in COMMANDS {
+                script.push_str(&format!(
+                    "                \"{}\"*) opts=({}) ;;\n",
+                    command,
+                    options(arguments)
+                ));
+            }
+            for (group, commands) in groups.iter().filter(|(_, commands)| !commands.is_empty()) {
+                script.push_str(&format!(
+                    "                {}*) opts=({}) ;;\n",
+                    group,
+                    commands.join(" ")
+                ));
+            }
+            script.push_str(&format!(
+                "                *) opts=({} --output) ;;\n",
+                top.join(" ")
+            ));
+            script.push_str("            esac ;;\n    esac\n");
+            script.push_str("    compadd -- $opts\n    _files\n}\n\ncompdef _cli cli\n");
+        }
+        "fish" => {
+            script.push_str(&format!(
+                "complete -c cli -l output -x -a \"{}\"\n",
+                formats
+            ));
+            script.push_str(&format!(
+                "complete -c cli -f -n __fish_use_subcommand -a \"{}\"\n",
+                top.join(" ")
+            ));
+            for (group, commands) in groups.iter().filter(|(_, commands)| !commands.is_empty()) {
+                script.push_str(&format!(
+                    "complete -c cli -f -n \"__fish_seen_subcommand_from {}; and not __fish_seen_subcommand_from {}\" -a \"{}\"\n",
+                    group,
+                    commands.join(" "),
+                    commands.join(" ")
+                ));
+            }
+            script.push_str(&format!(
+                "complete -c cli -f -n \"__fish_seen_subcommand_from completions\" -a \"{}\"\n",
+                SHELLS.join(" ")
+            ));
+            for (command, options) in COMMANDS {
+                let condition = command
+                    .split(' ')
+                    .map(|word| format!("__fish_seen_subcommand_from {}", word))
+                    .collect::<Vec<String>>()
+                    .join("; and ");
+                for option in options.iter() {
+                    script.push_str(&format!(
+                        "complete -c cli -n \"{}\" -l {}\n",
+                        condition,
+                        option.trim_start_matches("--")
+                    ));
+                }
+            }
+        }
+        _ => return Err(Error::Invalid(format!("Invalid shell {}", shell))),
+    }
+    print!("{}", script);
+    Ok(())
+}
+
+/// Reads a JSON Lines file of snapshots, e.g. the history of `cli serve --snapshots`
+fn read_snapshots(path: &str) -> Result<Vec<Snapshot>> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| Error::Custom(format!("Failed to read {}: {}", path, err)))?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line)
+                .map_err(|err| Error::Invalid(format!("Invalid snapshot in {}: {}", path, err)))
+        })
+        .collect()
+}
+
+/// Reads the topologies of the latest snapshot of every host of a snapshot file
+fn latest_topologies(path: &str) -> Result<HashMap<String, Vec<Topology>>> {
+    let mut latest: HashMap<String, Snapshot> = HashMap::new();
+    for snapshot in read_snapshots(path)? {
+        if latest
+            .get(&snapshot.host)
+            .is_none_or(|other| other.collected_at <= snapshot.collected_at)
+        {
+            latest.insert(snapshot.host.clone(), snapshot);
+        }
+    }
+    latest
+        .into_iter()
+        .map(|(host, snapshot)| {
+            let topologies = snapshot
+                .topologies
+                .iter()
+                .map(|value| Topology::from_value(value, &host))
+                .collect::<Result<Vec<Topology>>>()?;
+            Ok((host, topologies))
+        })
+        .collect()
+}
+
+/// Reads a registry file holding an array of devices
+fn read_devices(path: &str) -> Result<Vec<Device>> {
+    read_json(path)?
+        .as_array()
+        .ok_or_else(|| Error::from("Device file must hold an array of devices"))?
+        .iter()
+        .map(Device::from_value)
+        .collect()
+}
+
+/// Writes a registry file, pretty printed
+fn write_registry(path: &str, value: &Value) -> Result<()> {
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|err| Error::Custom(format!("Failed to serialize devices: {}", err)))?;
+    persist::write_atomic(path.as_ref(), content.as_bytes(), "devices")
+}
+
+/// Reads a JSON file
+fn read_json(path: &str) -> Result<Value> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| Error::Custom(format!("Failed to read {}: {}", path, err)))?;
+    serde_json::from_str(&content)
+        .map_err(|err| Error::Invalid(format!("Invalid JSON in {}: {}", path, err)))
 }
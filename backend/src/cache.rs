@@ -0,0 +1,111 @@
+use crate::models::topology::Topology;
+use crate::Result;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serde::Serialize;
+
+/// Hit/miss counters of the topology cache
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+pub struct CacheMetrics {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+/// In-memory cache of the latest parsed topologies of each host
+///
+/// A controller may expose several topologies, so every host maps to all of them,
+/// replaced together when the host is collected again. Reads go through
+/// [`TopologyCache::get_or_load`], which falls back to the store on a miss. The
+/// collector calls [`TopologyCache::insert`] or [`TopologyCache::invalidate`] whenever
+/// it stores a new snapshot of a host.
+#[derive(Default)]
+pub struct TopologyCache {
+    topologies: DashMap<String, Arc<[Topology]>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl TopologyCache {
+    /// Creates an empty cache
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached topologies of `host`, without falling back to the store
+    pub fn get(&self, host: &str) -> Option<Arc<[Topology]>> {
+        let topologies = self.topologies.get(host).map(|entry| Arc::clone(&entry));
+        match topologies {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        topologies
+    }
+
+    /// Returns the topologies of `host`, loading them with `load` on a cache miss
+    ///
+    /// `load` returns `Ok(None)` when the store has no topology for the host, in which
+    /// case nothing is cached. Topologies collected while `load` runs are kept over the
+    /// loaded ones.
+    pub async fn get_or_load<F, Fut>(&self, host: &str, load: F) -> Result<Option<Arc<[Topology]>>>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<Option<Vec<Topology>>>>,
+    {
+        if let Some(topologies) = self.get(host) {
+            return Ok(Some(topologies));
+        }
+
+        Ok(load(host.to_string())
+            .await?
+            .map(|topologies| self.insert_if_absent(host, topologies)))
+    }
+
+    /// Caches the topologies of `host` loaded from the store, unless the host was
+    /// collected meanwhile
+    ///
+    /// # Returns
+    /// - `Arc<[Topology]>`: The cached topologies, the collected ones if any
+    pub fn insert_if_absent(&self, host: &str, topologies: Vec<Topology>) -> Arc<[Topology]> {
+        let entry = self
+            .topologies
+            .entry(host.to_string())
+            .or_insert_with(|| topologies.into());
+        Arc::clone(entry.value())
+    }
+
+    /// Caches the freshly collected topologies of `host`, replacing the previous ones
+    pub fn insert(&self, host: &str, topologies: Vec<Topology>) -> Arc<[Topology]> {
+        let topologies: Arc<[Topology]> = topologies.into();
+        self.topologies
+            .insert(host.to_string(), Arc::clone(&topologies));
+        topologies
+    }
+
+    /// Drops the cached topologies of `host`, so the next read goes to the store
+    pub fn invalidate(&self, host: &str) {
+        self.topologies.remove(host);
+    }
+
+    /// Returns the topologies of every cached host, not counted as reads
+    pub fn snapshot(&self) -> HashMap<String, Arc<[Topology]>> {
+        self.topologies
+            .iter()
+            .map(|entry| (entry.key().clone(), Arc::clone(entry.value())))
+            .collect()
+    }
+
+    /// Returns the hit/miss counters and the number of cached hosts
+    pub fn metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.topologies.len(),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use crate::payloads::{PayloadConfig, PayloadStore};
+use crate::{Error, Result};
+
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+use uuid::Uuid;
+
+/// A JSON fragment the collector failed to parse, as saved by [`ParseCapture`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CapturedFragment {
+    pub host: String,                 // Device the fragment was collected from
+    pub object: String,               // Kind of object being parsed, e.g. `node` or `link`
+    pub error: String,                // Parse error, as reported to the caller
+    pub fragment: Value,              // The offending JSON, unchanged
+    pub captured_at: DateTime<Local>, // When the parse failed
+}
+
+/// Debugging mode of the collector keeping the payloads it fails to parse
+///
+/// When a node or link of a controller cannot be parsed, the offending fragment is
+/// saved with the error in a [`PayloadStore`], and the error returned to the caller
+/// is suffixed with the id of the capture (`Invalid link uuid (capture <id>)`), so
+/// nonconforming payloads of a vendor can be reproduced offline.
+pub struct ParseCapture {
+    store: PayloadStore,
+}
+
+impl ParseCapture {
+    /// Opens a capture directory, creating it if needed
+    pub fn open(directory: impl AsRef<Path>, config: PayloadConfig) -> Result<Self> {
+        Ok(ParseCapture {
+            store: PayloadStore::open(directory, config)?,
+        })
+    }
+
+    /// Runs `parse` on `fragment`, capturing the fragment when it fails
+    ///
+    /// # Arguments
+    /// - `host`: Device the fragment was collected from
+    /// - `object`: Kind of object being parsed
+    /// - `fragment`: The JSON handed to `parse`
+    /// - `parse`: The parser, typically a `from_value`
+    ///
+    /// # Returns
+    /// - `Ok(T)`: The parsed object
+    /// - `Err(Error)`: The parse error with the capture id, or the original error alone
+    ///   if the fragment could not be saved
+    pub fn parse<T>(
+        &self,
+        host: &str,
+        object: &str,
+        fragment: &Value,
+        parse: impl FnOnce(&Value) -> Result<T>,
+    ) -> Result<T> {
+        parse(fragment).map_err(|err| {
+            let captured = CapturedFragment {
+                host: host.to_string(),
+                object: object.to_string(),
+                error: err.to_string(),
+                fragment: fragment.clone(),
+                captured_at: Local::now(),
+            };
+            match serde_json::to_value(&captured)
+                .map_err(Error::custom)
+                .and_then(|captured| self.store.save(&captured))
+            {
+                Ok(id) => Error::Custom(format!("{} (capture {})", err, id)),
+                Err(save_err) => {
+                    warn!("Parse failure of {} not captured: {}", host, save_err);
+                    err
+                }
+            }
+        })
+    }
+
+    /// Reads a captured fragment back
+    pub fn load(&self, id: &Uuid) -> Result<CapturedFragment> {
+        let captured = self.store.load(id)?;
+        serde_json::from_value(captured)
+            .map_err(|err| Error::Custom(format!("Corrupted capture {}: {}", id, err)))
+    }
+}
@@ -0,0 +1,157 @@
+use crate::Result;
+
+use std::future::Future;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::time::{timeout_at, Instant};
+use tracing::{instrument, warn};
+
+/// Step of a device collection cycle
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectionStep {
+    Topology,
+    Inventory,
+    Connectivity, // Services and connections, for the impact analysis of links
+    Alarms,
+}
+
+/// Outcome of a single step
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum StepOutcome {
+    Completed,                // The step finished successfully
+    Failed { error: String }, // The step returned an error
+    Aborted,                  // The step was cancelled when the deadline expired
+    Skipped,                  // The deadline expired before the step started
+}
+
+/// Report of a collection cycle
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CycleReport {
+    pub steps: Vec<(CollectionStep, StepOutcome)>, // Outcome of every step, in run order
+    pub partial: bool,     // The deadline expired before every step finished
+    pub elapsed: Duration, // Time spent in the cycle
+}
+
+/// Runs the collection cycles of a device within a time budget
+///
+/// Steps run in order until the deadline expires; the running step is then aborted
+/// and the remaining ones are skipped, so one slow controller cannot hold a worker
+/// slot for longer than its budget. After a partial cycle the scope of the next one is
+/// reordered: skipped steps run first and the aborted step runs last, so every step
+/// keeps getting a chance.
+#[derive(Debug, Clone)]
+pub struct DeviceCycle {
+    budget: Duration,
+    scope: Vec<CollectionStep>,
+}
+
+impl DeviceCycle {
+    /// Default time budget of a full collection cycle
+    pub const DEFAULT_BUDGET: Duration = Duration::from_secs(120);
+
+    /// Creates a cycle running topology, inventory and alarms within `budget`
+    pub fn new(budget: Duration) -> Self {
+        DeviceCycle {
+            budget,
+            scope: vec![
+                CollectionStep::Topology,
+                CollectionStep::Inventory,
+                CollectionStep::Alarms,
+            ],
+        }
+    }
+
+    /// Creates a cycle running only the steps of `scope`, in order, within `budget`
+    pub fn with_scope(budget: Duration, scope: Vec<CollectionStep>) -> Self {
+        DeviceCycle { budget, scope }
+    }
+
+    /// Changes the budget of the next cycles, e.g. after a config reload
+    pub fn set_budget(&mut self, budget: Duration) {
+        self.budget = budget;
+    }
+
+    /// Returns the steps of the next cycle, in run order
+    pub fn scope(&self) -> &[CollectionStep] {
+        &self.scope
+    }
+
+    /// Runs one cycle, calling `collect` for every step in scope
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - Host of the device, used in logs
+    /// * `collect` - Runs a step; its future is dropped if the deadline expires
+    ///
+    /// # Returns
+    ///
+    /// The report of the cycle. The scope of the next cycle is adapted if it was partial.
+    #[instrument(name = "poll", skip_all, fields(host = %host))]
+    pub async fn run<F, Fut>(&mut self, host: &str, mut collect: F) -> CycleReport
+    where
+        F: FnMut(CollectionStep) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let started = Instant::now();
+        let deadline = started + self.budget;
+        let mut steps = Vec::with_capacity(self.scope.len());
+        let mut expired = false;
+
+        for step in self.scope.iter().copied() {
+            if expired || Instant::now() >= deadline {
+                expired = true;
+                steps.push((step, StepOutcome::Skipped));
+                continue;
+            }
+
+            let outcome = match timeout_at(deadline, collect(step)).await {
+                Ok(Ok(())) => StepOutcome::Completed,
+                Ok(Err(err)) => StepOutcome::Failed {
+                    error: err.to_string(),
+                },
+                Err(_) => {
+                    warn!(
+                        "Collection cycle of {} exceeded its {:?} budget during {:?}",
+                        host, self.budget, step
+                    );
+                    expired = true;
+                    StepOutcome::Aborted
+                }
+            };
+            steps.push((step, outcome));
+        }
+
+        if expired {
+            self.scope = next_scope(&steps);
+        }
+
+        CycleReport {
+            steps,
+            partial: expired,
+            elapsed: started.elapsed(),
+        }
+    }
+}
+
+impl Default for DeviceCycle {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_BUDGET)
+    }
+}
+
+/// Orders the next scope: skipped steps, then finished ones, then the aborted one
+fn next_scope(steps: &[(CollectionStep, StepOutcome)]) -> Vec<CollectionStep> {
+    let rank = |outcome: &StepOutcome| match outcome {
+        StepOutcome::Skipped => 0,
+        StepOutcome::Completed | StepOutcome::Failed { .. } => 1,
+        StepOutcome::Aborted => 2,
+    };
+
+    let mut ordered: Vec<&(CollectionStep, StepOutcome)> = steps.iter().collect();
+    // Stable sort keeps the previous order within each rank
+    ordered.sort_by_key(|(_, outcome)| rank(outcome));
+    ordered.into_iter().map(|(step, _)| *step).collect()
+}
@@ -0,0 +1,82 @@
+use crate::models::{
+    equipment::AccessPortRef, node::Node, service_interface_point::ServiceInterfacePoint,
+};
+
+// Import necessary traits for serialization and deserialization
+use serde::{Deserialize, Serialize};
+
+// Import UUID handling utilities from the `uuid` crate
+use uuid::Uuid;
+
+/// Denormalized view of a service endpoint: a service interface point resolved to the
+/// node edge point mapping it and the node owning that node edge point
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ServiceEndpoint {
+    pub host: String,
+    pub service_interface_point_uuid: Uuid,
+    pub service_interface_point_name: Option<String>,
+    pub node_uuid: Uuid,
+    pub node_name: Option<String>,
+    pub node_edge_point_uuid: Uuid,
+    pub layer_protocol_qualifier: Option<String>,
+    pub supporting_access_port: Option<AccessPortRef>,
+}
+
+/// Result of correlating the service interface points of a host with its nodes
+#[derive(Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct Correlation {
+    pub endpoints: Vec<ServiceEndpoint>, // Service interface points resolved to a node edge point
+    pub unmapped: Vec<Uuid>,             // Service interface points no node edge point maps
+}
+
+impl Correlation {
+    /// Joins service interface points to the owned node edge points mapping them
+    ///
+    /// A service interface point mapped by several node edge points produces one endpoint
+    /// per node edge point; one mapped by none is reported in `unmapped`.
+    pub fn build(service_interface_points: &[ServiceInterfacePoint], nodes: &[Node]) -> Self {
+        let mut correlation = Correlation::default();
+
+        for service_interface_point in service_interface_points {
+            let mut mapped = false;
+
+            for node in nodes
+                .iter()
+                .filter(|node| node.host == service_interface_point.host)
+            {
+                for node_edge_point in node.owned_node_edge_points.iter().filter(|nep| {
+                    nep.mapped_service_interface_points
+                        .contains(&service_interface_point.uuid)
+                }) {
+                    mapped = true;
+                    correlation.endpoints.push(ServiceEndpoint {
+                        host: node.host.clone(),
+                        service_interface_point_uuid: service_interface_point.uuid,
+                        service_interface_point_name: service_interface_point.name.clone(),
+                        node_uuid: node.uuid,
+                        node_name: node.name.clone(),
+                        node_edge_point_uuid: node_edge_point.uuid,
+                        layer_protocol_qualifier: node_edge_point.layer_protocol_qualifier.clone(),
+                        supporting_access_port: node_edge_point.supporting_access_port,
+                    });
+                }
+            }
+
+            if !mapped {
+                correlation.unmapped.push(service_interface_point.uuid);
+            }
+        }
+
+        correlation
+    }
+
+    /// Returns the endpoints a service interface point resolves to
+    pub fn resolve(&self, service_interface_point_uuid: &Uuid) -> Vec<&ServiceEndpoint> {
+        self.endpoints
+            .iter()
+            .filter(|endpoint| {
+                endpoint.service_interface_point_uuid == *service_interface_point_uuid
+            })
+            .collect()
+    }
+}
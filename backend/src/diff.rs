@@ -0,0 +1,321 @@
+use crate::events::{ChangeEvent, ChangeKind, ObjectKind};
+use crate::models::hash::ContentHash;
+use crate::models::key::ObjectKey;
+use crate::models::lifecycle_state::LifecycleState;
+use crate::models::topology::Topology;
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::Local;
+use serde::Serialize;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Minimal view of a topology object needed to detect changes
+struct Snapshot {
+    hash: ContentHash,
+    lifecycle_state: Option<LifecycleState>,
+}
+
+/// Indexes the objects of a topology by key
+///
+/// A controller reporting the same UUID twice in one topology is a genuine collision:
+/// the first object is kept and the duplicate is logged instead of silently replacing it.
+fn snapshots(topology: &Topology, object: ObjectKind) -> HashMap<ObjectKey, Snapshot> {
+    let objects: Vec<(Uuid, Snapshot)> = match object {
+        ObjectKind::Node => topology
+            .nodes
+            .iter()
+            .map(|node| {
+                let snapshot = Snapshot {
+                    hash: node.hash,
+                    lifecycle_state: node.lifecycle_state,
+                };
+                (node.uuid, snapshot)
+            })
+            .collect(),
+        ObjectKind::Link => topology
+            .links
+            .iter()
+            .map(|link| {
+                let snapshot = Snapshot {
+                    hash: link.hash,
+                    lifecycle_state: link.lifecycle_state,
+                };
+                (link.uuid, snapshot)
+            })
+            .collect(),
+    };
+
+    let mut index = HashMap::with_capacity(objects.len());
+    for (uuid, snapshot) in objects {
+        match index.entry(topology.key(uuid)) {
+            Entry::Vacant(entry) => {
+                entry.insert(snapshot);
+            }
+            Entry::Occupied(entry) => {
+                warn!(
+                    "Duplicate {:?} key {}, keeping the first one",
+                    object,
+                    entry.key()
+                )
+            }
+        }
+    }
+    index
+}
+
+/// Compares two collections of the same host and returns the resulting change events
+///
+/// Objects are matched by [`ObjectKey`] (host, topology UUID and object UUID), so a
+/// UUID reused by another topology is never mistaken for the same object. A lifecycle
+/// state change is reported as a `LifecycleTransition` rather than a generic `Changed`,
+/// so rollout progress can be tracked on its own.
+pub fn diff_topologies(previous: &Topology, current: &Topology) -> Vec<ChangeEvent> {
+    let mut events = vec![];
+
+    for object in [ObjectKind::Node, ObjectKind::Link] {
+        let before = snapshots(previous, object);
+        let after = snapshots(current, object);
+
+        let mut changes: Vec<(&ObjectKey, ChangeKind)> = vec![];
+        for (key, snapshot) in &after {
+            match before.get(key) {
+                None => changes.push((key, ChangeKind::Added)),
+                Some(old) if old.lifecycle_state != snapshot.lifecycle_state => changes.push((
+                    key,
+                    ChangeKind::LifecycleTransition {
+                        from: old.lifecycle_state,
+                        to: snapshot.lifecycle_state,
+                    },
+                )),
+                Some(old) if old.hash != snapshot.hash => changes.push((key, ChangeKind::Changed)),
+                Some(_) => {}
+            }
+        }
+        for key in before.keys().filter(|key| !after.contains_key(key)) {
+            changes.push((key, ChangeKind::Removed));
+        }
+
+        // Keep the output stable regardless of hash map ordering
+        changes.sort_by(|(left, _), (right, _)| {
+            (left.uuid, left.topology_uuid).cmp(&(right.uuid, right.topology_uuid))
+        });
+
+        let now = Local::now();
+        events.extend(changes.into_iter().map(|(key, change)| ChangeEvent {
+            host: key.host.clone(),
+            topology_uuid: key.topology_uuid,
+            object,
+            uuid: key.uuid,
+            change,
+            date: now,
+        }));
+    }
+
+    events
+}
+
+/// Change of a link between two collections, as listed by `cli topology diff`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LinkChange {
+    pub topology_uuid: Uuid,
+    pub uuid: Uuid,
+    pub name: Option<String>, // Name in the latest collection reporting the link
+    #[serde(flatten)]
+    pub change: ChangeKind,
+}
+
+/// Links added, removed and changed between two collections of a host
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct LinkDiff {
+    pub added: Vec<LinkChange>,
+    pub removed: Vec<LinkChange>,
+    pub changed: Vec<LinkChange>, // Content changes and lifecycle transitions
+}
+
+impl LinkDiff {
+    /// Returns `true` when no link changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares the links of every topology of two collections of the same host
+///
+/// Topologies are matched by UUID; the links of a topology only present in one of the
+/// collections are all reported as added or removed.
+pub fn diff_links(previous: &[Topology], current: &[Topology]) -> LinkDiff {
+    fn find(topologies: &[Topology], uuid: Uuid) -> Option<&Topology> {
+        topologies.iter().find(|topology| topology.uuid == uuid)
+    }
+    let uuids: BTreeSet<Uuid> = previous
+        .iter()
+        .chain(current)
+        .map(|topology| topology.uuid)
+        .collect();
+
+    let mut diff = LinkDiff::default();
+    for uuid in uuids {
+        let (before, after) = (find(previous, uuid), find(current, uuid));
+        let empty = Topology {
+            host: before
+                .or(after)
+                .map(|topology| topology.host.clone())
+                .unwrap_or_default(),
+            uuid,
+            nodes: vec![],
+            links: vec![],
+            site: None,
+        };
+        let (before, after) = (before.unwrap_or(&empty), after.unwrap_or(&empty));
+
+        let events = diff_topologies(before, after);
+        for event in events
+            .into_iter()
+            .filter(|event| event.object == ObjectKind::Link)
+        {
+            let name = after
+                .links
+                .iter()
+                .chain(&before.links)
+                .find(|link| link.uuid == event.uuid)
+                .and_then(|link| link.name.clone());
+            let change = LinkChange {
+                topology_uuid: event.topology_uuid,
+                uuid: event.uuid,
+                name,
+                change: event.change,
+            };
+            match change.change {
+                ChangeKind::Added => diff.added.push(change),
+                ChangeKind::Removed => diff.removed.push(change),
+                _ => diff.changed.push(change),
+            }
+        }
+    }
+    diff
+}
+
+/// An object reported by one side of a comparison only
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct UnmatchedObject {
+    pub object: ObjectKind,
+    pub topology_uuid: Uuid,
+    pub uuid: Uuid,
+    pub name: Option<String>,
+}
+
+/// Objects of two devices aligned with each other, the body of
+/// `GET /topology/compare?left=<host>&right=<host>`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TopologyComparison {
+    pub left: String,
+    pub right: String,
+    pub matched_by_uuid: usize,
+    pub matched_by_name: usize, // Same name, different UUIDs
+    pub only_left: Vec<UnmatchedObject>,
+    pub only_right: Vec<UnmatchedObject>,
+}
+
+impl TopologyComparison {
+    /// Returns `true` when both devices report the same objects
+    pub fn is_empty(&self) -> bool {
+        self.only_left.is_empty() && self.only_right.is_empty()
+    }
+}
+
+/// Compares the topologies reported by two devices, e.g. two controllers of the same
+/// network during a migration
+///
+/// Controllers rarely share topology UUIDs, so nodes and links are aligned across all
+/// the topologies of each side: by UUID first, then by name. A name carried by several
+/// objects of a side is ambiguous and aligns nothing.
+///
+/// # Arguments
+/// - `left`, `right`: The host and last topologies of each device
+///
+/// # Returns
+/// - `TopologyComparison`: The objects found on one side only, by kind and UUID
+pub fn compare_topologies(
+    left: (&str, &[Topology]),
+    right: (&str, &[Topology]),
+) -> TopologyComparison {
+    let mut comparison = TopologyComparison {
+        left: left.0.to_string(),
+        right: right.0.to_string(),
+        matched_by_uuid: 0,
+        matched_by_name: 0,
+        only_left: vec![],
+        only_right: vec![],
+    };
+
+    for object in [ObjectKind::Node, ObjectKind::Link] {
+        let objects = |topologies: &[Topology]| -> Vec<UnmatchedObject> {
+            let mut objects: Vec<UnmatchedObject> = topologies
+                .iter()
+                .flat_map(|topology| {
+                    let named: Vec<(Uuid, Option<String>)> = match object {
+                        ObjectKind::Node => topology
+                            .nodes
+                            .iter()
+                            .map(|node| (node.uuid, node.name.clone()))
+                            .collect(),
+                        ObjectKind::Link => topology
+                            .links
+                            .iter()
+                            .map(|link| (link.uuid, link.name.clone()))
+                            .collect(),
+                    };
+                    named.into_iter().map(|(uuid, name)| UnmatchedObject {
+                        object,
+                        topology_uuid: topology.uuid,
+                        uuid,
+                        name,
+                    })
+                })
+                .collect();
+            objects.sort_by_key(|object| (object.uuid, object.topology_uuid));
+            objects
+        };
+        let (mut only_left, mut only_right) = (objects(left.1), objects(right.1));
+
+        let uuids: BTreeSet<Uuid> = only_right.iter().map(|object| object.uuid).collect();
+        let matched: BTreeSet<Uuid> = only_left
+            .iter()
+            .map(|object| object.uuid)
+            .filter(|uuid| uuids.contains(uuid))
+            .collect();
+        only_left.retain(|object| !matched.contains(&object.uuid));
+        only_right.retain(|object| !matched.contains(&object.uuid));
+        comparison.matched_by_uuid += matched.len();
+
+        // Names carried by a single object of the side
+        let unique = |objects: &[UnmatchedObject]| -> HashMap<String, Uuid> {
+            let mut counts: HashMap<&str, (usize, Uuid)> = HashMap::new();
+            for object in objects {
+                if let Some(name) = &object.name {
+                    counts.entry(name).or_insert((0, object.uuid)).0 += 1;
+                }
+            }
+            counts
+                .into_iter()
+                .filter(|(_, (count, _))| *count == 1)
+                .map(|(name, (_, uuid))| (name.to_string(), uuid))
+                .collect()
+        };
+        let (left_names, right_names) = (unique(&only_left), unique(&only_right));
+        let named: Vec<(Uuid, Uuid)> = left_names
+            .iter()
+            .filter_map(|(name, uuid)| Some((*uuid, *right_names.get(name)?)))
+            .collect();
+        only_left.retain(|object| !named.iter().any(|(uuid, _)| *uuid == object.uuid));
+        only_right.retain(|object| !named.iter().any(|(_, uuid)| *uuid == object.uuid));
+        comparison.matched_by_name += named.len();
+
+        comparison.only_left.extend(only_left);
+        comparison.only_right.extend(only_right);
+    }
+    comparison
+}
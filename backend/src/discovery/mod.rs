@@ -0,0 +1,299 @@
+pub mod scan;
+
+use crate::models::address::{Host, Port};
+use crate::models::device::{register, Auth, Device, Registration};
+use crate::models::equipment::PHYSICAL_CONTEXT_PATH;
+use crate::models::name::Name;
+use crate::persist;
+use crate::southbound::{FetchOptions, SouthboundProtocol};
+use crate::tenant::default_tenant;
+use crate::{Error, Result};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::info;
+use uuid::Uuid;
+
+pub use scan::{scan, Fingerprint, ScanConfig};
+
+/// `role` metadata of the devices whose managed devices are discovered
+pub const CONTROLLER_ROLE: &str = "controller";
+
+/// `value-name`s of the TAPI names carrying the management address of a device
+const ADDRESS_NAMES: &[&str] = &[
+    "IP_ADDRESS",
+    "MANAGEMENT_IP",
+    "MANAGEMENT_ADDRESS",
+    "MGMT_IP",
+];
+
+/// Review state of a discovered device
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CandidateStatus {
+    Pending,  // Waiting for an operator
+    Rejected, // Kept so later discoveries do not propose it again
+}
+
+/// A device managed by a controller or found by a subnet scan, proposed for registration
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub host: Host, // Management address reported by the controller, or scanned
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<Port>, // Port the scan found the device on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub physical_device_uuid: Option<Uuid>, // Device of the controller's physical context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub controller: Option<String>, // Host of the controller that reported it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<Fingerprint>, // What the scan found answering
+    pub discovered_at: DateTime<Local>,
+    pub status: CandidateStatus,
+}
+
+impl Candidate {
+    /// Returns the device registered when the candidate is approved
+    ///
+    /// Managed devices belong to the tenant of their `controller` and are reached through
+    /// its proxy; scanned devices belong to the default tenant.
+    pub fn to_device(&self, auth: Auth, controller: Option<&Device>) -> Device {
+        let mut metadata = HashMap::new();
+        if let Some(controller) = &self.controller {
+            metadata.insert("discovered_by".to_string(), controller.clone());
+        }
+        if let Some(name) = &self.name {
+            metadata.insert("name".to_string(), name.clone());
+        }
+        Device {
+            tenant_id: controller.map_or_else(default_tenant, |device| device.tenant_id.clone()),
+            host: self.host.clone(),
+            port: self.port,
+            auth,
+            tags: vec![],
+            metadata,
+            enabled: true,
+            maintenance_until: None,
+            proxy_url: controller.and_then(|device| device.proxy_url.clone()),
+        }
+    }
+}
+
+/// Reads the managed devices of a controller from its `tapi-equipment:physical-context`
+///
+/// Every physical device reporting a management address among its names (`IP_ADDRESS`,
+/// `MANAGEMENT_IP`...) is a candidate; the others cannot be polled and are skipped.
+///
+/// # Arguments
+/// - `value`: The physical context, with or without its module-qualified wrapper
+/// - `controller`: The host of the controller
+/// - `now`: The discovery date of the candidates
+///
+/// # Returns
+/// - `Ok(Vec<Candidate>)`: The pending candidates
+/// - `Err(Error)`: If a device has no valid UUID
+pub fn candidates_from_value(
+    value: &Value,
+    controller: &str,
+    now: DateTime<Local>,
+) -> Result<Vec<Candidate>> {
+    let value = value
+        .get("tapi-equipment:physical-context")
+        .unwrap_or(value);
+    let mut candidates = vec![];
+    for device in value
+        .get("device")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let physical_device_uuid = Uuid::parse_str(
+            device
+                .get("uuid")
+                .and_then(Value::as_str)
+                .unwrap_or_default(),
+        )
+        .map_err(|_| Error::BadResponse("Not found physical device uuid".to_string()))?;
+        let names = Name::list_from_value(device);
+        let is_address = |name: &&Name| {
+            name.value_name
+                .as_deref()
+                .is_some_and(|value_name| ADDRESS_NAMES.contains(&value_name))
+        };
+        let Some(host) = names
+            .iter()
+            .filter(is_address)
+            .find_map(|name| name.value.parse::<Host>().ok())
+        else {
+            continue;
+        };
+        candidates.push(Candidate {
+            host,
+            port: None,
+            name: names
+                .iter()
+                .find(|name| !is_address(name))
+                .map(|name| name.value.clone()),
+            physical_device_uuid: Some(physical_device_uuid),
+            controller: Some(controller.to_string()),
+            fingerprint: None,
+            discovered_at: now,
+            status: CandidateStatus::Pending,
+        });
+    }
+    Ok(candidates)
+}
+
+/// Queries a controller for the devices it manages
+///
+/// # Returns
+/// - `Ok(Vec<Candidate>)`: The devices with a management address
+/// - `Err(Error)`: If the controller cannot be queried or answers an invalid inventory
+pub async fn discover(
+    client: &impl SouthboundProtocol,
+    controller: &str,
+) -> Result<Vec<Candidate>> {
+    let inventory = client
+        .get(PHYSICAL_CONTEXT_PATH, &FetchOptions::default())
+        .await?;
+    let candidates = candidates_from_value(&inventory, controller, Local::now())?;
+    info!(
+        "Controller {} manages {} devices with a management address",
+        controller,
+        candidates.len()
+    );
+    Ok(candidates)
+}
+
+/// Discovered devices waiting for approval, the data of `GET/POST /discovery/candidates`
+///
+/// With [`CandidateStore::open`] the candidates are saved to a JSON file after every
+/// change. Approved candidates are registered and dropped; rejected ones are kept so
+/// they are not proposed again.
+#[derive(Default)]
+pub struct CandidateStore {
+    path: Option<PathBuf>,
+    candidates: Vec<Candidate>,
+}
+
+impl CandidateStore {
+    /// Creates an in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the store persisted to `path`, loading the candidates already proposed
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let candidates = persist::read_json(&path, "candidates")?.unwrap_or_default();
+        Ok(CandidateStore {
+            path: Some(path),
+            candidates,
+        })
+    }
+
+    /// Returns the candidates waiting for approval
+    pub fn pending(&self) -> Vec<&Candidate> {
+        self.candidates
+            .iter()
+            .filter(|candidate| candidate.status == CandidateStatus::Pending)
+            .collect()
+    }
+
+    /// Adds the discovered candidates that are neither registered nor already proposed
+    ///
+    /// # Returns
+    /// - `Ok(usize)`: The number of new candidates
+    /// - `Err(Error)`: If the store cannot be saved
+    pub fn propose(&mut self, candidates: Vec<Candidate>, devices: &[Device]) -> Result<usize> {
+        let mut proposed = 0;
+        for candidate in candidates {
+            let registered = devices
+                .iter()
+                .any(|device| device.host == candidate.host.as_str());
+            let known = self
+                .candidates
+                .iter()
+                .any(|known| known.host == candidate.host);
+            if !registered && !known {
+                self.candidates.push(candidate);
+                proposed += 1;
+            }
+        }
+        if proposed > 0 {
+            self.save()?;
+        }
+        Ok(proposed)
+    }
+
+    /// Registers the pending candidate `host` in `devices`
+    ///
+    /// # Arguments
+    /// - `auth`: The credentials of the device, by default those of its controller
+    ///
+    /// # Returns
+    /// - `Ok(Registration)`: `Added`, or `Conflict` if it duplicates a registered device,
+    ///   in which case nothing is added and the candidate stays pending
+    /// - `Err(Error)`: If there is no such candidate, its controller is not registered or
+    ///   a scanned candidate is approved without credentials
+    pub fn approve(
+        &mut self,
+        host: &str,
+        auth: Option<Auth>,
+        devices: &mut Vec<Device>,
+    ) -> Result<Registration> {
+        let index = self.find(host)?;
+        let candidate = &self.candidates[index];
+        let controller = candidate
+            .controller
+            .as_ref()
+            .map(|controller| {
+                devices
+                    .iter()
+                    .find(|device| device.host == controller.as_str())
+                    .ok_or_else(|| Error::NotFound(format!("Not found controller {}", controller)))
+            })
+            .transpose()?;
+        let auth = auth
+            .or_else(|| controller.map(|device| device.auth.clone()))
+            .ok_or_else(|| Error::Invalid(format!("Missing credentials for candidate {}", host)))?;
+        let device = candidate.to_device(auth, controller);
+        let registration = register(devices, device, false);
+        if let Registration::Added(_) = registration {
+            self.candidates.remove(index);
+            self.save()?;
+        }
+        Ok(registration)
+    }
+
+    /// Rejects the pending candidate `host`
+    pub fn reject(&mut self, host: &str) -> Result<()> {
+        let index = self.find(host)?;
+        self.candidates[index].status = CandidateStatus::Rejected;
+        self.save()
+    }
+
+    /// Returns the position of the pending candidate `host`
+    fn find(&self, host: &str) -> Result<usize> {
+        self.candidates
+            .iter()
+            .position(|candidate| {
+                candidate.status == CandidateStatus::Pending
+                    && candidate.host.as_str().eq_ignore_ascii_case(host)
+            })
+            .ok_or_else(|| Error::NotFound(format!("Not found candidate {}", host)))
+    }
+
+    /// Writes the candidates to the file of the store, if any
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        persist::write_json(path, &self.candidates, "candidates")
+    }
+}
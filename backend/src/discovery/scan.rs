@@ -0,0 +1,312 @@
+use crate::discovery::{Candidate, CandidateStatus};
+use crate::models::address::{Host, Port};
+use crate::{Error, Result};
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use std::time::Duration;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+use tracing::{debug, info};
+
+/// Largest range a scan accepts, a /16 in IPv4
+pub const MAX_RANGE_ADDRESSES: u128 = 65_536;
+
+/// Time an open port is given to send an SSH banner before HTTP is tried
+const BANNER_WAIT: Duration = Duration::from_millis(300);
+
+/// Settings of the subnet scan, the `[discovery]` section of `config.toml`
+///
+/// Scanning is off unless the section is set. Probes are spread at `rate` per second so
+/// a scan does not look like an attack to the network.
+///
+/// ```toml
+/// [discovery]
+/// ranges = ["10.0.1.0/24", "10.0.2.0/28"]
+/// ports = [443, 8443, 830]
+/// rate = 10
+/// timeout = 2000
+/// interval = 86400
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScanConfig {
+    pub ranges: Vec<Cidr>, // Networks to scan
+    #[serde(default = "default_ports")]
+    pub ports: Vec<Port>, // RESTCONF (443, 8443) and NETCONF (830) ports probed on every host
+    #[serde(default = "default_rate")]
+    pub rate: u32, // Probes started per second
+    #[serde(default = "default_timeout")]
+    pub timeout: u64, // Milliseconds given to a probe
+    #[serde(default = "default_interval")]
+    pub interval: u64, // Seconds between two scans
+}
+
+fn default_ports() -> Vec<Port> {
+    [443, 8443, 830]
+        .into_iter()
+        .filter_map(|port| Port::new(port).ok())
+        .collect()
+}
+
+fn default_rate() -> u32 {
+    10
+}
+
+fn default_timeout() -> u64 {
+    2_000
+}
+
+fn default_interval() -> u64 {
+    86_400
+}
+
+/// What answered on a scanned port, from the least to the most useful
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Fingerprint {
+    Netconf,  // SSH server, presumably NETCONF
+    Restconf, // RESTCONF server, announced by `/.well-known/host-meta`
+    Tapi,     // RESTCONF server with the `tapi-common:context` data tree
+}
+
+/// IPv4 or IPv6 network, e.g. `10.0.1.0/24`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    /// Returns the number of addresses of the network
+    pub fn size(&self) -> u128 {
+        let bits = match self.network {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        1u128
+            .checked_shl(bits - u32::from(self.prefix))
+            .unwrap_or(u128::MAX)
+    }
+
+    /// Returns the host addresses of the network
+    ///
+    /// The network and broadcast addresses of IPv4 networks larger than a /31 are left out.
+    pub fn hosts(&self) -> Vec<IpAddr> {
+        match self.network {
+            IpAddr::V4(network) => {
+                let first = u32::from(network);
+                let last = first + (self.size() - 1) as u32;
+                let (first, last) = if self.prefix < 31 {
+                    (first + 1, last - 1)
+                } else {
+                    (first, last)
+                };
+                (first..=last)
+                    .map(|address| IpAddr::V4(Ipv4Addr::from(address)))
+                    .collect()
+            }
+            IpAddr::V6(network) => {
+                let first = u128::from(network);
+                (0..self.size())
+                    .map(|offset| IpAddr::V6(Ipv6Addr::from(first + offset)))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || Error::Invalid(format!("Invalid network {}", value));
+        let (address, prefix) = value.split_once('/').ok_or_else(invalid)?;
+        let address: IpAddr = address.parse().map_err(|_| invalid())?;
+        let prefix: u8 = prefix.parse().map_err(|_| invalid())?;
+        // Host bits are cleared, `10.0.1.7/24` is `10.0.1.0/24`
+        let network = match address {
+            IpAddr::V4(address) if prefix <= 32 => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(address) & mask))
+            }
+            IpAddr::V6(address) if prefix <= 128 => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(address) & mask))
+            }
+            _ => return Err(invalid()),
+        };
+        let cidr = Cidr { network, prefix };
+        if cidr.size() > MAX_RANGE_ADDRESSES {
+            return Err(Error::Invalid(format!(
+                "Invalid network {}, at most {} addresses are scanned",
+                value, MAX_RANGE_ADDRESSES
+            )));
+        }
+        Ok(cidr)
+    }
+}
+
+impl TryFrom<String> for Cidr {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<Cidr> for String {
+    fn from(cidr: Cidr) -> Self {
+        cidr.to_string()
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
+
+/// Probes the ports of every host of the configured ranges
+///
+/// Probes start at `config.rate` per second and run concurrently. A host answering on
+/// several ports is proposed once, on the port with the most useful [`Fingerprint`].
+///
+/// # Returns
+/// - `Ok(Vec<Candidate>)`: The pending candidates, by address
+/// - `Err(Error)`: If the rate is zero or the HTTP client cannot be built
+pub async fn scan(config: &ScanConfig) -> Result<Vec<Candidate>> {
+    if config.rate == 0 {
+        return Err(Error::Invalid("Invalid scan rate 0".to_string()));
+    }
+    let timeout = Duration::from_millis(config.timeout);
+    // Devices commonly use self-signed certificates, only their presence is checked
+    let client = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .timeout(timeout)
+        .build()
+        .map_err(Error::custom)?;
+
+    let mut pace = tokio::time::interval(Duration::from_secs(1) / config.rate);
+    pace.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut probes = JoinSet::new();
+    for address in config.ranges.iter().flat_map(Cidr::hosts) {
+        for port in &config.ports {
+            pace.tick().await;
+            let target = SocketAddr::new(address, port.get());
+            let client = client.clone();
+            probes.spawn(async move { (target, probe(&client, target, timeout).await) });
+        }
+    }
+
+    let mut found: BTreeMap<IpAddr, (u16, Fingerprint)> = BTreeMap::new();
+    while let Some(result) = probes.join_next().await {
+        let Ok((target, Some(fingerprint))) = result else {
+            continue;
+        };
+        debug!("{} answers as {:?}", target, fingerprint);
+        let best = found
+            .entry(target.ip())
+            .or_insert((target.port(), fingerprint));
+        if fingerprint > best.1 || (fingerprint == best.1 && target.port() < best.0) {
+            *best = (target.port(), fingerprint);
+        }
+    }
+
+    let now = Local::now();
+    let candidates: Vec<Candidate> = found
+        .into_iter()
+        .filter_map(|(address, (port, fingerprint))| {
+            Some(Candidate {
+                host: address.to_string().parse::<Host>().ok()?,
+                port: Port::new(port).ok(),
+                name: None,
+                physical_device_uuid: None,
+                controller: None,
+                fingerprint: Some(fingerprint),
+                discovered_at: now,
+                status: CandidateStatus::Pending,
+            })
+        })
+        .collect();
+    info!(
+        "Scan of {} found {} devices",
+        config
+            .ranges
+            .iter()
+            .map(Cidr::to_string)
+            .collect::<Vec<String>>()
+            .join(", "),
+        candidates.len()
+    );
+    Ok(candidates)
+}
+
+/// Returns what answers on `target`, if anything
+async fn probe(
+    client: &reqwest::Client,
+    target: SocketAddr,
+    timeout: Duration,
+) -> Option<Fingerprint> {
+    let mut stream = tokio::time::timeout(timeout, TcpStream::connect(target))
+        .await
+        .ok()?
+        .ok()?;
+    // SSH servers speak first, HTTP servers wait for the request
+    let mut banner = [0; 4];
+    if let Ok(Ok(4)) = tokio::time::timeout(BANNER_WAIT, stream.read_exact(&mut banner)).await {
+        return (&banner == b"SSH-").then_some(Fingerprint::Netconf);
+    }
+    drop(stream);
+
+    for scheme in ["https", "http"] {
+        let base = format!("{}://{}", scheme, target);
+        let Ok(response) = client
+            .get(format!("{}/.well-known/host-meta", base))
+            .send()
+            .await
+        else {
+            continue;
+        };
+        let host_meta = response.text().await.unwrap_or_default();
+        let root = restconf_root(&host_meta)?;
+        let context = client
+            .get(format!("{}{}/data/tapi-common:context?depth=1", base, root))
+            .header("accept", "application/yang-data+json")
+            .send()
+            .await
+            .ok()?;
+        // A device requiring credentials still shows the tree exists
+        return Some(match context.status().as_u16() {
+            200..=299 | 401 | 403 => Fingerprint::Tapi,
+            _ => Fingerprint::Restconf,
+        });
+    }
+    None
+}
+
+/// Returns the RESTCONF root announced by a `/.well-known/host-meta` document (RFC 8040)
+///
+/// e.g. `/restconf` for `<Link rel='restconf' href='/restconf'/>`
+pub fn restconf_root(host_meta: &str) -> Option<String> {
+    host_meta
+        .split("<Link")
+        .skip(1)
+        .find_map(|link| {
+            let link = link.split('>').next()?;
+            let attribute = |name: &str| {
+                let start = link.find(&format!("{}=", name))? + name.len() + 1;
+                let quote = link[start..].chars().next()?;
+                let value = &link[start + 1..];
+                Some(value[..value.find(quote)?].to_string())
+            };
+            (attribute("rel")? == "restconf").then(|| attribute("href"))?
+        })
+        .map(|root| root.trim_end_matches('/').to_string())
+}
@@ -0,0 +1,92 @@
+use crate::models::{key::ObjectKey, site::Site, topology::Topology};
+use crate::{Error, Result};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Resolves the site of devices and nodes
+///
+/// Implemented by [`SiteMapping`] for file based mappings; other implementations can
+/// query an external inventory or GeoIP service.
+pub trait SiteLookup {
+    /// Returns the site of the device reached at `host`
+    fn site_for_host(&self, host: &str) -> Option<Site>;
+
+    /// Returns the site of a node, looked up by key first and then by name
+    fn site_for_node(&self, key: &ObjectKey, name: Option<&str>) -> Option<Site>;
+}
+
+/// Site mapping loaded from a JSON file
+///
+/// ```json
+/// {
+///     "sites": {"MAD01": {"id": "MAD01", "name": "Madrid", "latitude": 40.41, "longitude": -3.70}},
+///     "hosts": {"10.95.87.21": "MAD01"},
+///     "nodes": {"Barcelona": "BCN01", "62d11f13-db6c-3398-8a83-5fac0b2b7476": "MAD01"}
+/// }
+/// ```
+///
+/// Nodes are keyed by name, by bare UUID, or by `host/topology-uuid/uuid` when two
+/// controllers reuse the same node UUIDs; the composite key wins.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct SiteMapping {
+    #[serde(default)]
+    pub sites: HashMap<String, Site>, // Sites by identifier
+    #[serde(default)]
+    pub hosts: HashMap<String, String>, // Site identifier by device host
+    #[serde(default)]
+    pub nodes: HashMap<String, String>, // Site identifier by node key, UUID or name
+}
+
+impl SiteMapping {
+    /// Loads a site mapping from a JSON file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|err| {
+            Error::Custom(format!(
+                "Failed to read site mapping {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        serde_json::from_str(&content)
+            .map_err(|err| Error::Invalid(format!("Invalid site mapping: {}", err)))
+    }
+
+    /// Returns the site with identifier `id`
+    fn site(&self, id: &String) -> Option<Site> {
+        self.sites.get(id).cloned()
+    }
+}
+
+impl SiteLookup for SiteMapping {
+    fn site_for_host(&self, host: &str) -> Option<Site> {
+        self.hosts.get(host).and_then(|id| self.site(id))
+    }
+
+    fn site_for_node(&self, key: &ObjectKey, name: Option<&str>) -> Option<Site> {
+        self.nodes
+            .get(&key.to_string())
+            .or_else(|| self.nodes.get(&key.uuid.to_string()))
+            .or_else(|| name.and_then(|name| self.nodes.get(name)))
+            .and_then(|id| self.site(id))
+    }
+}
+
+/// Annotates a freshly parsed topology with sites
+///
+/// The device site is looked up by host. Each node gets its own site when mapped,
+/// falling back to the device site, so maps can place every node.
+pub fn enrich(topology: &mut Topology, lookup: &impl SiteLookup) {
+    topology.site = lookup.site_for_host(&topology.host);
+
+    for node in &mut topology.nodes {
+        let key = ObjectKey::new(&topology.host, topology.uuid, node.uuid);
+        node.site = lookup
+            .site_for_node(&key, node.name.as_deref())
+            .or_else(|| topology.site.clone());
+    }
+}
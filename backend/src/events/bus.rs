@@ -0,0 +1,249 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tracing::warn;
+
+/// What happens when a subscriber falls `capacity` events behind
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    #[default]
+    DropOldest, // The oldest undelivered events are discarded, publishing never waits
+    Block, // Publishing waits for the subscriber, which never misses an event
+}
+
+/// Queue counters of a subscriber
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SubscriberMetrics {
+    pub name: String,
+    pub policy: OverflowPolicy,
+    pub depth: u64,    // Events published but not received yet
+    pub received: u64, // Events received since the subscription
+    pub dropped: u64,  // Events discarded by `DropOldest` since the subscription
+}
+
+/// Counters of the bus, the body of its metrics endpoint
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct BusMetrics {
+    pub capacity: usize,
+    pub published: u64,
+    pub subscribers: Vec<SubscriberMetrics>,
+}
+
+/// Name, help text, type and value of an exported series
+type Series = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&SubscriberMetrics) -> u64,
+);
+
+/// Counters shared between a subscription and the bus
+struct Counters {
+    name: String,
+    policy: OverflowPolicy,
+    offset: u64, // Events published before the subscription
+    received: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// Bounded fan-out of events between the collector, the change detector and the sinks
+///
+/// Every subscriber has its own queue of `capacity` events. `DropOldest` subscribers
+/// share a broadcast channel: publishing never waits for them, and one that lags behind
+/// loses its oldest events, so a slow webhook cannot stall polling. `Block` subscribers
+/// have a bounded channel each, and publishing waits until they have room, for consumers
+/// that must see every event such as the event store.
+pub struct EventBus<T> {
+    capacity: usize,
+    lossy: broadcast::Sender<T>,
+    lossless: Mutex<Vec<(Arc<Counters>, mpsc::Sender<T>)>>,
+    subscribers: Mutex<Vec<Arc<Counters>>>,
+    published: AtomicU64,
+}
+
+impl<T: Clone + Send + 'static> EventBus<T> {
+    /// Creates a bus whose subscribers each queue at most `capacity` events
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        EventBus {
+            capacity,
+            lossy: broadcast::channel(capacity).0,
+            lossless: Mutex::new(vec![]),
+            subscribers: Mutex::new(vec![]),
+            published: AtomicU64::new(0),
+        }
+    }
+
+    /// Subscribes to the events published from now on
+    ///
+    /// # Arguments
+    /// - `name`: Name of the subscriber in the metrics, e.g. `webhook`
+    /// - `policy`: What happens when the subscriber falls behind
+    pub fn subscribe(&self, name: &str, policy: OverflowPolicy) -> Subscription<T> {
+        let counters = Arc::new(Counters {
+            name: name.to_string(),
+            policy,
+            offset: self.published.load(Ordering::Relaxed),
+            received: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        });
+        self.subscribers.lock().unwrap().push(Arc::clone(&counters));
+
+        let receiver = match policy {
+            OverflowPolicy::DropOldest => Receiver::Lossy(self.lossy.subscribe()),
+            OverflowPolicy::Block => {
+                let (sender, receiver) = mpsc::channel(self.capacity);
+                self.lossless
+                    .lock()
+                    .unwrap()
+                    .push((Arc::clone(&counters), sender));
+                Receiver::Lossless(receiver)
+            }
+        };
+        Subscription { receiver, counters }
+    }
+
+    /// Publishes `event` to every subscriber
+    ///
+    /// Only waits while a `Block` subscriber has a full queue. Subscribers that were
+    /// dropped are removed.
+    pub async fn publish(&self, event: T) {
+        self.published.fetch_add(1, Ordering::Relaxed);
+        // Fails only when there is no `DropOldest` subscriber
+        let _ = self.lossy.send(event.clone());
+
+        let lossless: Vec<(Arc<Counters>, mpsc::Sender<T>)> = self.lossless.lock().unwrap().clone();
+        let mut closed = vec![];
+        for (counters, sender) in &lossless {
+            if sender.send(event.clone()).await.is_err() {
+                closed.push(Arc::clone(counters));
+            }
+        }
+
+        if !closed.is_empty() {
+            let is_closed = |counters: &Arc<Counters>| {
+                closed.iter().any(|closed| Arc::ptr_eq(closed, counters))
+            };
+            self.lossless
+                .lock()
+                .unwrap()
+                .retain(|(counters, _)| !is_closed(counters));
+            self.subscribers
+                .lock()
+                .unwrap()
+                .retain(|counters| !is_closed(counters));
+        }
+    }
+
+    /// Returns the queue depth and counters of every subscriber
+    pub fn metrics(&self) -> BusMetrics {
+        let published = self.published.load(Ordering::Relaxed);
+        let mut subscribers = self.subscribers.lock().unwrap();
+        // Dropped `DropOldest` subscriptions are only noticed here
+        subscribers.retain(|counters| Arc::strong_count(counters) > 1);
+        let subscribers = subscribers
+            .iter()
+            .map(|counters| {
+                let received = counters.received.load(Ordering::Relaxed);
+                let dropped = counters.dropped.load(Ordering::Relaxed);
+                SubscriberMetrics {
+                    name: counters.name.clone(),
+                    policy: counters.policy,
+                    depth: (published - counters.offset).saturating_sub(received + dropped),
+                    received,
+                    dropped,
+                }
+            })
+            .collect();
+
+        BusMetrics {
+            capacity: self.capacity,
+            published,
+            subscribers,
+        }
+    }
+
+    /// Renders the metrics in the Prometheus text format
+    pub fn prometheus(&self) -> String {
+        let metrics = self.metrics();
+        let mut output = String::new();
+        let _ = writeln!(output, "# HELP event_bus_published_total Events published");
+        let _ = writeln!(output, "# TYPE event_bus_published_total counter");
+        let _ = writeln!(output, "event_bus_published_total {}", metrics.published);
+
+        let series: [Series; 2] = [
+            (
+                "queue_depth",
+                "Events waiting for the subscriber",
+                "gauge",
+                |s| s.depth,
+            ),
+            (
+                "dropped_total",
+                "Events dropped by the overflow policy",
+                "counter",
+                |s| s.dropped,
+            ),
+        ];
+        for (name, help, kind, value) in series {
+            let _ = writeln!(output, "# HELP event_bus_{} {}", name, help);
+            let _ = writeln!(output, "# TYPE event_bus_{} {}", name, kind);
+            for subscriber in &metrics.subscribers {
+                let _ = writeln!(
+                    output,
+                    "event_bus_{}{{subscriber=\"{}\"}} {}",
+                    name,
+                    subscriber.name,
+                    value(subscriber)
+                );
+            }
+        }
+        output
+    }
+}
+
+enum Receiver<T> {
+    Lossy(broadcast::Receiver<T>),
+    Lossless(mpsc::Receiver<T>),
+}
+
+/// Events of the bus, as received by one subscriber
+pub struct Subscription<T> {
+    receiver: Receiver<T>,
+    counters: Arc<Counters>,
+}
+
+impl<T: Clone> Subscription<T> {
+    /// Waits for the next event
+    ///
+    /// # Returns
+    /// - `Some(T)`: The next event the subscriber did not miss
+    /// - `None`: Once the bus is dropped and every queued event was received
+    pub async fn recv(&mut self) -> Option<T> {
+        let event = match &mut self.receiver {
+            Receiver::Lossless(receiver) => receiver.recv().await,
+            Receiver::Lossy(receiver) => loop {
+                match receiver.recv().await {
+                    Ok(event) => break Some(event),
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!(
+                            "Subscriber {} fell behind, {} events dropped",
+                            self.counters.name, missed
+                        );
+                        self.counters.dropped.fetch_add(missed, Ordering::Relaxed);
+                    }
+                    Err(RecvError::Closed) => break None,
+                }
+            },
+        };
+        if event.is_some() {
+            self.counters.received.fetch_add(1, Ordering::Relaxed);
+        }
+        event
+    }
+}
@@ -0,0 +1,46 @@
+pub mod bus;
+pub mod sse;
+pub mod store;
+
+use crate::models::lifecycle_state::LifecycleState;
+use crate::validation::Rule;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Kind of topology object a change event refers to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectKind {
+    Node,
+    Link,
+}
+
+/// What happened to the object
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,   // The object appeared in the topology
+    Removed, // The object disappeared from the topology
+    Changed, // The object content changed (hash mismatch)
+    LifecycleTransition {
+        from: Option<LifecycleState>,
+        to: Option<LifecycleState>,
+    }, // The lifecycle state changed, e.g. PLANNED -> INSTALLED
+    Violation {
+        rule: Rule,
+        detail: String,
+    }, // The object started breaking a validation rule
+}
+
+/// Change detected on a topology object between two collections of a host
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChangeEvent {
+    pub host: String,
+    pub topology_uuid: Uuid, // Topology owning the object, object UUIDs are scoped to it
+    pub object: ObjectKind,
+    pub uuid: Uuid,
+    pub change: ChangeKind,
+    pub date: DateTime<Local>,
+}
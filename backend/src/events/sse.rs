@@ -0,0 +1,116 @@
+use crate::events::store::{EventStore, StoredEvent};
+use crate::{Error, Result};
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Media type of `GET /events/stream`
+pub const CONTENT_TYPE: &str = "text/event-stream";
+
+/// Comment line sent when no event was emitted for a while, keeping proxies from
+/// closing the idle connection
+pub const HEARTBEAT: &str = ": heartbeat\n\n";
+
+/// Default time without events before a heartbeat is sent
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How often the event store is checked for new events
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Formats a stored event as a Server-Sent Events message
+///
+/// The message `id` is the position of the event in the log, so a client reconnecting
+/// with `Last-Event-ID` resumes right after it. The data is the JSON change event.
+pub fn frame(stored: &StoredEvent) -> Result<String> {
+    let data = serde_json::to_string(&stored.event).map_err(Error::custom)?;
+    Ok(format!(
+        "id: {}\nevent: change\ndata: {}\n\n",
+        stored.id, data
+    ))
+}
+
+/// Parses the `Last-Event-ID` header sent by a reconnecting client
+///
+/// # Returns
+/// - `Ok(Some(id))`: The id of the last event the client received
+/// - `Ok(None)`: If the client did not send the header, only new events are streamed
+/// - `Err(Error)`: If the header is not an event id
+pub fn parse_last_event_id(header: Option<&str>) -> Result<Option<u64>> {
+    header
+        .map(|value| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| Error::Invalid(format!("Invalid Last-Event-ID {}", value)))
+        })
+        .transpose()
+}
+
+/// Body of `GET /events/stream`, producing one chunk at a time
+pub struct EventStream {
+    store: Arc<Mutex<EventStore>>,
+    tenant_id: String,
+    last_id: u64,
+    heartbeat: Duration,
+    last_sent: Instant,
+}
+
+impl EventStream {
+    /// Opens a stream on the events of a tenant
+    ///
+    /// # Arguments
+    /// - `store`: The event store new events are appended to
+    /// - `tenant_id`: Tenant resolved from the auth token of the request
+    /// - `last_event_id`: Value of the `Last-Event-ID` header; the events recorded after
+    ///   it are replayed first, without it the stream starts with the next event
+    /// - `heartbeat`: Time without events before a heartbeat is sent
+    pub fn new(
+        store: Arc<Mutex<EventStore>>,
+        tenant_id: &str,
+        last_event_id: Option<&str>,
+        heartbeat: Duration,
+    ) -> Result<Self> {
+        let last_id = match parse_last_event_id(last_event_id)? {
+            Some(id) => id,
+            None => store.lock().unwrap().last_id(),
+        };
+
+        Ok(EventStream {
+            store,
+            tenant_id: tenant_id.to_string(),
+            last_id,
+            heartbeat,
+            last_sent: Instant::now(),
+        })
+    }
+
+    /// Waits for the next chunk: the events recorded since the previous chunk, or a
+    /// heartbeat once the interval elapses without any
+    pub async fn next_chunk(&mut self) -> Result<String> {
+        loop {
+            let chunk = {
+                let store = self.store.lock().unwrap();
+                let mut chunk = String::new();
+                for stored in store.after(&self.tenant_id, self.last_id) {
+                    chunk.push_str(&frame(stored)?);
+                    self.last_id = stored.id;
+                }
+                chunk
+            };
+
+            if !chunk.is_empty() {
+                self.last_sent = Instant::now();
+                return Ok(chunk);
+            }
+            if self.last_sent.elapsed() >= self.heartbeat {
+                self.last_sent = Instant::now();
+                return Ok(HEARTBEAT.to_string());
+            }
+
+            let wait = POLL_INTERVAL.min(self.heartbeat.saturating_sub(self.last_sent.elapsed()));
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
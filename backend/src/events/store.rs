@@ -0,0 +1,279 @@
+use crate::events::ChangeEvent;
+use crate::maintenance::MaintenanceSchedule;
+use crate::pagination::CursorSigner;
+use crate::persist;
+use crate::retention::{retained, RetentionPolicy};
+use crate::tenant::default_tenant;
+use crate::{Error, Result};
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// Filters a replay cursor is bound to, so it cannot be used by another tenant
+#[derive(Serialize)]
+struct ReplayQuery<'a> {
+    tenant_id: &'a str,
+}
+
+/// A change event as recorded in the event log
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StoredEvent {
+    pub id: u64, // Position in the log, strictly increasing
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String, // Customer owning the object the event refers to
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suppressed: bool, // Emitted during a maintenance window of its object
+    #[serde(flatten)]
+    pub event: ChangeEvent,
+}
+
+impl StoredEvent {
+    /// Returns whether the event is sent to the notification channels
+    ///
+    /// Suppressed events are still stored and replayed, but not delivered.
+    pub fn deliverable(&self) -> bool {
+        !self.suppressed
+    }
+}
+
+/// Page of replayed events, the body of `GET /events?since=<timestamp|cursor>`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EventPage {
+    pub events: Vec<StoredEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>, // Resumes after the last event of the page
+    pub has_more: bool, // More events were already available
+}
+
+/// Append-only log of every emitted change event
+///
+/// Consumers that were offline catch up by replaying the log from a timestamp or from
+/// the cursor of their last page instead of losing events. Events are never updated,
+/// and only deleted by [`EventStore::prune`]; with [`EventStore::open`] each one is
+/// appended to a JSON Lines file as it is recorded, and the log is reloaded after a
+/// restart. Reads are scoped to a tenant.
+#[derive(Default)]
+pub struct EventStore {
+    path: Option<PathBuf>,
+    events: Vec<StoredEvent>,
+    last_id: u64, // Survives pruning, so ids are never reused
+}
+
+/// Line of the log file: an event, or the id reached before pruning
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum LogLine {
+    Event(StoredEvent),
+    Pruned { last_id: u64 },
+}
+
+impl EventStore {
+    /// Creates an in-memory event log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the event log persisted to `path`, loading the events already recorded
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut events = vec![];
+        let mut last_id = 0;
+        if path.exists() {
+            let content = fs::read_to_string(&path).map_err(|err| {
+                Error::Custom(format!("Failed to read events {}: {}", path.display(), err))
+            })?;
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                let line: LogLine = serde_json::from_str(line)
+                    .map_err(|err| Error::Custom(format!("Invalid events file: {}", err)))?;
+                match line {
+                    LogLine::Event(event) => {
+                        last_id = last_id.max(event.id);
+                        events.push(event);
+                    }
+                    LogLine::Pruned { last_id: pruned } => last_id = last_id.max(pruned),
+                }
+            }
+        }
+
+        Ok(EventStore {
+            path: Some(path),
+            events,
+            last_id,
+        })
+    }
+
+    /// Returns the number of recorded events
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns `true` when no event was recorded
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Returns the id of the last recorded event, 0 when no event was ever recorded
+    pub fn last_id(&self) -> u64 {
+        self.last_id
+    }
+
+    /// Records an event of `tenant_id` and returns its id
+    pub fn append(&mut self, tenant_id: &str, event: ChangeEvent) -> Result<u64> {
+        self.push(tenant_id, event, false)
+    }
+
+    /// Records an event of `tenant_id`, tagged `suppressed` if it falls in a maintenance
+    /// window of its object, and returns its id
+    pub fn append_scheduled(
+        &mut self,
+        tenant_id: &str,
+        event: ChangeEvent,
+        schedule: &MaintenanceSchedule,
+    ) -> Result<u64> {
+        let suppressed = schedule.suppresses(&event);
+        self.push(tenant_id, event, suppressed)
+    }
+
+    fn push(&mut self, tenant_id: &str, event: ChangeEvent, suppressed: bool) -> Result<u64> {
+        let id = self.last_id() + 1;
+        let stored = StoredEvent {
+            id,
+            tenant_id: tenant_id.to_string(),
+            suppressed,
+            event,
+        };
+
+        if let Some(path) = &self.path {
+            let line = serde_json::to_string(&stored).map_err(Error::custom)?;
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", line))
+                .map_err(|err| {
+                    Error::Custom(format!(
+                        "Failed to write events {}: {}",
+                        path.display(),
+                        err
+                    ))
+                })?;
+        }
+
+        self.events.push(stored);
+        self.last_id = id;
+        Ok(id)
+    }
+
+    /// Returns the event `id`, unless it was pruned
+    pub fn get(&self, id: u64) -> Option<&StoredEvent> {
+        self.events
+            .binary_search_by_key(&id, |stored| stored.id)
+            .ok()
+            .map(|index| &self.events[index])
+    }
+
+    /// Returns the events of `tenant_id` recorded after the event `id`, oldest first
+    pub fn after(&self, tenant_id: &str, id: u64) -> Vec<&StoredEvent> {
+        let start = self.events.partition_point(|stored| stored.id <= id);
+        self.events[start..]
+            .iter()
+            .filter(|stored| stored.tenant_id == tenant_id)
+            .collect()
+    }
+
+    /// Replays the events of a tenant from `since`, returning at most `limit` events
+    ///
+    /// # Arguments
+    /// - `tenant_id`: Tenant resolved from the auth token of the request
+    /// - `since`: An RFC 3339 timestamp, replaying the events dated from that instant,
+    ///   or the `next_cursor` of a previous page. `None` replays the whole log.
+    /// - `limit`: Maximum number of events in the page
+    /// - `signer`: Signer of the pagination cursors
+    ///
+    /// # Returns
+    /// - `Ok(EventPage)`: The page, with a cursor to resume from when it is not empty
+    /// - `Err(Error)`: If `since` is neither a timestamp nor a valid cursor
+    pub fn replay(
+        &self,
+        tenant_id: &str,
+        since: Option<&str>,
+        limit: usize,
+        signer: &CursorSigner,
+    ) -> Result<EventPage> {
+        let query = ReplayQuery { tenant_id };
+        let pending: Vec<&StoredEvent> = match since {
+            None => self.after(tenant_id, 0),
+            Some(since) => match DateTime::parse_from_rfc3339(since) {
+                // Dates come from the collectors and are not guaranteed to be ordered
+                Ok(timestamp) => self
+                    .after(tenant_id, 0)
+                    .into_iter()
+                    .filter(|stored| stored.event.date >= timestamp)
+                    .collect(),
+                Err(_) => self.after(tenant_id, signer.decode::<u64, _>(since, &query)?),
+            },
+        };
+
+        let events: Vec<StoredEvent> = pending
+            .iter()
+            .take(limit)
+            .map(|&stored| stored.clone())
+            .collect();
+        let next_cursor = events
+            .last()
+            .map(|last| signer.encode(&last.id, &query))
+            .transpose()?;
+
+        Ok(EventPage {
+            has_more: pending.len() > events.len(),
+            events,
+            next_cursor,
+        })
+    }
+
+    /// Deletes the events beyond the retention limits and returns how many were deleted
+    ///
+    /// The log file is rewritten to a temporary file first, then renamed over the log,
+    /// so a crash while pruning never loses the events that are kept.
+    pub fn prune(&mut self, policy: &RetentionPolicy, now: DateTime<Local>) -> Result<usize> {
+        let keep = retained(
+            &self.events,
+            |stored| (stored.tenant_id.as_str(), stored.event.host.as_str()),
+            |stored| stored.event.date,
+            policy,
+            now,
+        );
+        if keep.iter().all(|keep| *keep) {
+            return Ok(0);
+        }
+
+        let mut keep = keep.into_iter();
+        let events: Vec<StoredEvent> = self
+            .events
+            .iter()
+            .filter(|_| keep.next().unwrap_or(true))
+            .cloned()
+            .collect();
+
+        if let Some(path) = &self.path {
+            let marker = LogLine::Pruned {
+                last_id: self.last_id,
+            };
+            let mut content = serde_json::to_string(&marker).map_err(Error::custom)?;
+            content.push('\n');
+            for stored in &events {
+                content.push_str(&serde_json::to_string(stored).map_err(Error::custom)?);
+                content.push('\n');
+            }
+            persist::write_atomic(path, content.as_bytes(), "events")?;
+        }
+
+        let deleted = self.events.len() - events.len();
+        self.events = events;
+        Ok(deleted)
+    }
+}
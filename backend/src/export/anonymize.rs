@@ -0,0 +1,114 @@
+use crate::models::{site::Site, topology::Topology};
+use crate::persist;
+use crate::Result;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Pseudonyms issued by an [`Anonymizer`] and the values they replace
+///
+/// Kept locally, next to the key, to de-anonymize the answers of whoever received the
+/// shared export. Never ship it with the export.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct PseudonymMapping {
+    pub pseudonyms: BTreeMap<String, String>, // Original value by pseudonym
+}
+
+impl PseudonymMapping {
+    /// Loads a mapping from a JSON file, or returns an empty one if it does not exist
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(persist::read_json(path.as_ref(), "pseudonym mapping")?.unwrap_or_default())
+    }
+
+    /// Saves the mapping to a JSON file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        persist::write_json(path.as_ref(), self, "pseudonym mapping")
+    }
+
+    /// Returns the original value behind `pseudonym`
+    pub fn original(&self, pseudonym: &str) -> Option<&str> {
+        self.pseudonyms.get(pseudonym).map(String::as_str)
+    }
+}
+
+/// Replaces identifying values of topologies with keyed pseudonyms for shared exports
+///
+/// Hostnames, node names and site annotations become `host-…`, `node-…` and `site-…`
+/// pseudonyms derived with HMAC-SHA256, so the same value always gets the same
+/// pseudonym under a key and the structure of the graph is preserved. UUIDs and
+/// lifecycle states are kept as they do not identify the customer.
+pub struct Anonymizer {
+    key: Vec<u8>,
+    mapping: PseudonymMapping,
+}
+
+impl Anonymizer {
+    /// Creates an anonymizer with the secret `key`, extending an existing `mapping`
+    pub fn new(key: impl AsRef<[u8]>, mapping: PseudonymMapping) -> Self {
+        Anonymizer {
+            key: key.as_ref().to_vec(),
+            mapping,
+        }
+    }
+
+    /// Returns the mapping of every pseudonym issued so far
+    pub fn mapping(&self) -> &PseudonymMapping {
+        &self.mapping
+    }
+
+    /// Returns the pseudonym of `value`, e.g. `host-3f9a1c0b7d2e`
+    pub fn pseudonym(&mut self, kind: &str, value: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(kind.as_bytes());
+        mac.update(b":");
+        mac.update(value.as_bytes());
+        let digest = mac.finalize().into_bytes();
+
+        let hex: String = digest[..6]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let pseudonym = format!("{}-{}", kind, hex);
+        self.mapping
+            .pseudonyms
+            .insert(pseudonym.clone(), value.to_string());
+        pseudonym
+    }
+
+    /// Anonymizes a topology in place
+    pub fn anonymize(&mut self, topology: &mut Topology) {
+        topology.host = self.pseudonym("host", &topology.host);
+        if let Some(site) = &mut topology.site {
+            self.anonymize_site(site);
+        }
+
+        for node in &mut topology.nodes {
+            node.host = topology.host.clone();
+            if let Some(name) = &node.name {
+                node.name = Some(self.pseudonym("node", name));
+            }
+            for name in &mut node.names {
+                name.value = self.pseudonym("node", &name.value);
+            }
+            if let Some(site) = &mut node.site {
+                self.anonymize_site(site);
+            }
+        }
+        for link in &mut topology.links {
+            link.host = topology.host.clone();
+        }
+    }
+
+    fn anonymize_site(&mut self, site: &mut Site) {
+        site.id = self.pseudonym("site", &site.id);
+        if let Some(name) = &site.name {
+            site.name = Some(self.pseudonym("site", name));
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{Error, Result};
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::info;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Settings of the archival sink, the `[archive]` section of `config.toml`
+///
+/// Works with AWS S3 and S3-compatible stores such as MinIO, addressed path-style.
+///
+/// ```toml
+/// [archive]
+/// endpoint = "https://s3.eu-west-1.amazonaws.com"
+/// region = "eu-west-1"
+/// bucket = "noc-archive"
+/// prefix = "device-manager"
+/// access_key_id = "AKIA..."
+/// secret_access_key = "secret"
+/// interval = 86400
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ArchiveConfig {
+    pub endpoint: String, // e.g. `https://s3.amazonaws.com` or `http://minio:9000`
+    #[serde(default = "default_region")]
+    pub region: String,
+    pub bucket: String,
+    #[serde(default)]
+    pub prefix: String, // Prepended to every object key
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    #[serde(default = "default_interval")]
+    pub interval: u64, // Seconds between two archives
+    #[serde(default = "default_level")]
+    pub level: i32, // zstd compression level, 1 (fast) to 22 (small)
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_interval() -> u64 {
+    86_400
+}
+
+fn default_level() -> i32 {
+    zstd::DEFAULT_COMPRESSION_LEVEL
+}
+
+/// Uploads compressed snapshots and reports to an S3 bucket
+///
+/// Objects are JSON documents, zstd compressed, keyed by kind, host and time:
+/// `<prefix>/snapshots/<host>/2024/05/24/20240524T000000Z.json.zst`. Requests are
+/// signed with AWS Signature Version 4, so no SDK is needed.
+pub struct ArchiveSink {
+    config: ArchiveConfig,
+    endpoint: Url,
+    client: reqwest::Client,
+}
+
+impl ArchiveSink {
+    /// Creates the sink of `config`
+    ///
+    /// # Returns
+    /// - `Ok(ArchiveSink)`: Ready to upload
+    /// - `Err(Error)`: If the endpoint is not an HTTP(S) URL or the bucket is empty
+    pub fn new(config: ArchiveConfig) -> Result<Self> {
+        let endpoint = Url::parse(&config.endpoint)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some())
+            .ok_or_else(|| {
+                Error::Invalid(format!("Invalid archive endpoint {}", config.endpoint))
+            })?;
+        if config.bucket.is_empty() || config.bucket.contains('/') {
+            return Err(Error::Custom(format!(
+                "Invalid archive bucket {}",
+                config.bucket
+            )));
+        }
+        Ok(ArchiveSink {
+            config,
+            endpoint,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Returns the key of the `kind` object archived at `at`, for `host` if given
+    pub fn key(&self, kind: &str, host: Option<&str>, at: DateTime<Utc>) -> String {
+        let mut parts: Vec<String> = vec![];
+        let prefix = self.config.prefix.trim_matches('/');
+        if !prefix.is_empty() {
+            parts.push(prefix.to_string());
+        }
+        parts.push(kind.to_string());
+        parts.extend(host.map(str::to_string));
+        parts.push(at.format("%Y/%m/%d").to_string());
+        parts.push(format!("{}.json.zst", at.format("%Y%m%dT%H%M%SZ")));
+        parts.join("/")
+    }
+
+    /// Compresses `value` and uploads it as the `kind` object of `host` at `at`
+    ///
+    /// # Returns
+    /// - `Ok(String)`: The key of the uploaded object
+    /// - `Err(Error)`: If the store cannot be reached or refuses the upload
+    pub async fn archive(
+        &self,
+        kind: &str,
+        host: Option<&str>,
+        value: &Value,
+        at: DateTime<Utc>,
+    ) -> Result<String> {
+        let content = serde_json::to_vec(value).map_err(Error::custom)?;
+        let compressed = zstd::encode_all(content.as_slice(), self.config.level)
+            .map_err(|err| Error::Custom(format!("Failed to compress archive: {}", err)))?;
+        let key = self.key(kind, host, at);
+        let size = compressed.len();
+        self.upload(&key, compressed, at).await?;
+        info!(
+            "Archived {} ({} bytes, {} compressed)",
+            key,
+            content.len(),
+            size
+        );
+        Ok(key)
+    }
+
+    /// Uploads `content` to `key` with a signed `PUT`
+    pub async fn upload(&self, key: &str, content: Vec<u8>, at: DateTime<Utc>) -> Result<()> {
+        let path = format!("/{}/{}", self.config.bucket, uri_encode(key));
+        let url = self
+            .endpoint
+            .join(&path)
+            .map_err(|err| Error::Custom(format!("Invalid archive key {}: {}", key, err)))?;
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let payload_hash = hex(&Sha256::digest(&content));
+
+        let mut headers = BTreeMap::new();
+        headers.insert("host".to_string(), host);
+        headers.insert("x-amz-content-sha256".to_string(), payload_hash.clone());
+        headers.insert(
+            "x-amz-date".to_string(),
+            at.format("%Y%m%dT%H%M%SZ").to_string(),
+        );
+        let authorization =
+            authorization(&self.config, "PUT", url.path(), &headers, &payload_hash, at);
+
+        let response = self
+            .client
+            .put(url)
+            .header("x-amz-content-sha256", &headers["x-amz-content-sha256"])
+            .header("x-amz-date", &headers["x-amz-date"])
+            .header("authorization", authorization)
+            .header("content-type", "application/zstd")
+            .body(content)
+            .send()
+            .await
+            .map_err(|err| Error::Custom(format!("Failed to upload {}: {}", key, err)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            // S3 explains refusals in an XML body, which may be empty
+            let body = response.text().await.unwrap_or_default();
+            let message = format!("Failed to upload {}: {} {}", key, status, body.trim());
+            return Err(Error::Custom(message.trim_end().to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the AWS Signature Version 4 `Authorization` header of a request
+///
+/// # Arguments
+/// - `path`: The URI-encoded path of the request, without query
+/// - `headers`: The signed headers, lowercase, including `host` and `x-amz-date`
+/// - `payload_hash`: The hex SHA-256 of the body, also sent as `x-amz-content-sha256`
+/// - `at`: The time of `x-amz-date`
+pub fn authorization(
+    config: &ArchiveConfig,
+    method: &str,
+    path: &str,
+    headers: &BTreeMap<String, String>,
+    payload_hash: &str,
+    at: DateTime<Utc>,
+) -> String {
+    let date = at.format("%Y%m%d").to_string();
+    let scope = format!("{}/{}/s3/aws4_request", date, config.region);
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers.keys().cloned().collect::<Vec<String>>().join(";");
+    let canonical_request = format!(
+        "{}\n{}\n\n{}\n{}\n{}",
+        method, path, canonical_headers, signed_headers, payload_hash
+    );
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        at.format("%Y%m%dT%H%M%SZ"),
+        scope,
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let key = [date.as_str(), config.region.as_str(), "s3", "aws4_request"]
+        .iter()
+        .fold(
+            format!("AWS4{}", config.secret_access_key).into_bytes(),
+            |key, part| hmac(&key, part.as_bytes()),
+        );
+    let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        config.access_key_id, scope, signed_headers, signature
+    )
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Percent-encodes an object key as S3 expects, keeping the `/` separators
+fn uri_encode(key: &str) -> String {
+    key.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,298 @@
+use crate::jobs::{JobKind, JobQueue, RETENTION_DAYS};
+use crate::persist;
+use crate::tenant::{default_tenant, DEFAULT_TENANT};
+use crate::{Error, Result};
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// An export written once by a job, then served in chunks
+///
+/// The content never changes once written, so a client resuming a download with a
+/// range gets the bytes it is missing of the same file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportArtifact {
+    pub id: Uuid, // Id of the job that wrote it
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String, // Tenant that asked for it, the only one allowed to read it
+    pub name: String, // File name offered to the client, e.g. `topology.graphml`
+    pub media_type: String, // `Content-Type` of the content
+    pub size: u64, // Bytes of the content
+    pub etag: String, // Quoted SHA-256 of the content, compared with `If-Range`
+    pub created_at: DateTime<Local>,
+}
+
+/// Bytes of an artifact asked for with `Range: bytes=...`, bounds included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Parses a `Range` header against an artifact of `size` bytes
+    ///
+    /// Accepts one range, `bytes=start-end`, `bytes=start-` or the suffix `bytes=-length`;
+    /// an end beyond the artifact is cut to its last byte.
+    ///
+    /// # Returns
+    /// - `Ok(ByteRange)`: The bytes to serve
+    /// - `Err(Error)`: If the header is malformed, holds several ranges, or starts beyond
+    ///   the artifact (`416 Range Not Satisfiable`)
+    pub fn parse(header: &str, size: u64) -> Result<Self> {
+        let invalid = || Error::Invalid(format!("Invalid range {}", header));
+        let spec = header.trim().strip_prefix("bytes=").ok_or_else(invalid)?;
+        if spec.contains(',') {
+            return Err(Error::Invalid(format!(
+                "Multiple ranges are not supported: {}",
+                header
+            )));
+        }
+        let (start, end) = spec.split_once('-').ok_or_else(invalid)?;
+        let bound = |value: &str| value.trim().parse::<u64>().map_err(|_| invalid());
+        let range = match (start.trim(), end.trim()) {
+            ("", "") => return Err(invalid()),
+            ("", length) => {
+                let length = bound(length)?;
+                if length == 0 || size == 0 {
+                    return Err(unsatisfiable(header, size));
+                }
+                ByteRange {
+                    start: size.saturating_sub(length),
+                    end: size - 1,
+                }
+            }
+            (start, "") => ByteRange {
+                start: bound(start)?,
+                end: size.saturating_sub(1),
+            },
+            (start, end) => ByteRange {
+                start: bound(start)?,
+                end: bound(end)?.min(size.saturating_sub(1)),
+            },
+        };
+        if range.start >= size || range.start > range.end {
+            return Err(unsatisfiable(header, size));
+        }
+        Ok(range)
+    }
+
+    /// Returns the number of bytes of the range
+    pub fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// Returns whether the range holds no byte, never the case once parsed
+    pub fn is_empty(&self) -> bool {
+        self.end < self.start
+    }
+
+    /// Returns the `Content-Range` header of the range, e.g. `bytes 0-99/1000`
+    pub fn content_range(&self, size: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, size)
+    }
+}
+
+/// Bytes of an artifact, the body of `GET /exports/{id}`
+///
+/// Answered `206 Partial Content` with the `Content-Range` of `range` when `partial`,
+/// `200 OK` with the whole artifact otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportChunk {
+    pub artifact: ExportArtifact,
+    pub range: ByteRange, // Bytes of `content` within the artifact
+    pub partial: bool,    // A range was asked for and honoured
+    pub content: Vec<u8>,
+}
+
+/// Directory of the artifacts written by the export jobs
+///
+/// An export is submitted to a [`JobQueue`]: its job renders the content, writes it
+/// next to its metadata, and reports the [`ExportArtifact`] as its result, so clients
+/// follow it through `GET /jobs/{id}` and download it from `GET /exports/{id}` once
+/// done. Artifacts are forgotten after `RETENTION_DAYS`, like their jobs.
+pub struct ExportStore {
+    directory: PathBuf,
+}
+
+impl ExportStore {
+    /// Opens the artifact directory, created if missing
+    ///
+    /// # Returns
+    /// - `Ok(ExportStore)`: The store
+    /// - `Err(Error)`: If the directory cannot be created
+    pub fn open(directory: impl AsRef<Path>) -> Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory).map_err(|err| {
+            Error::Custom(format!(
+                "Failed to create export directory {}: {}",
+                directory.display(),
+                err
+            ))
+        })?;
+        Ok(ExportStore { directory })
+    }
+
+    /// Queues an export job writing the output of `render` as the artifact `name`
+    ///
+    /// `render` runs on a blocking thread; the id of the job is the id of the artifact.
+    pub fn submit<F>(&self, queue: &JobQueue, name: &str, media_type: &str, render: F) -> Uuid
+    where
+        F: FnOnce() -> Result<Vec<u8>> + Send + 'static,
+    {
+        self.submit_for(queue, DEFAULT_TENANT, name, media_type, render)
+    }
+
+    /// Queues an export job of `tenant_id`, whose job and artifact only the tenant sees
+    pub fn submit_for<F>(
+        &self,
+        queue: &JobQueue,
+        tenant_id: &str,
+        name: &str,
+        media_type: &str,
+        render: F,
+    ) -> Uuid
+    where
+        F: FnOnce() -> Result<Vec<u8>> + Send + 'static,
+    {
+        let directory = self.directory.clone();
+        let (name, media_type) = (name.to_string(), media_type.to_string());
+        let tenant = tenant_id.to_string();
+        queue.submit_for(tenant_id, JobKind::Export, move |progress| async move {
+            let id = progress.id();
+            let writer = move || -> Result<ExportArtifact> {
+                let content = render()?;
+                progress.set(50);
+                let artifact = ExportArtifact {
+                    id,
+                    tenant_id: tenant,
+                    name,
+                    media_type,
+                    size: content.len() as u64,
+                    etag: format!("\"{}\"", hex(&Sha256::digest(&content))),
+                    created_at: Local::now(),
+                };
+                // The metadata is written last, so an artifact is listed once complete
+                persist::write_atomic(&content_path(&directory, &id), &content, "export")?;
+                persist::write_json(&metadata_path(&directory, &id), &artifact, "export")?;
+                Ok(artifact)
+            };
+            let artifact = tokio::task::spawn_blocking(writer)
+                .await
+                .map_err(|err| Error::Custom(format!("Export {} panicked: {}", id, err)))??;
+            serde_json::to_value(artifact)
+                .map_err(|err| Error::Custom(format!("Failed to serialize export: {}", err)))
+        })
+    }
+
+    /// Returns the artifact written by the job `id`
+    ///
+    /// # Returns
+    /// - `Ok(ExportArtifact)`: The artifact
+    /// - `Err(Error)`: If the job did not finish writing it, or it expired
+    pub fn artifact(&self, id: &Uuid) -> Result<ExportArtifact> {
+        persist::read_json(&metadata_path(&self.directory, id), "export")?
+            .ok_or_else(|| Error::NotFound(format!("Not found export {}", id)))
+    }
+
+    /// Reads the bytes of the artifact `id` asked for by a download
+    ///
+    /// # Arguments
+    /// - `range`: The `Range` header, the whole artifact is read without it
+    /// - `if_range`: The `If-Range` header; when it is not the etag of the artifact, the
+    ///   client holds bytes of another content and gets the whole artifact
+    ///
+    /// # Returns
+    /// - `Ok(ExportChunk)`: The bytes and the range they cover
+    /// - `Err(Error)`: If the artifact is unknown, the range invalid or unsatisfiable,
+    ///   or the content cannot be read
+    pub fn read(
+        &self,
+        id: &Uuid,
+        range: Option<&str>,
+        if_range: Option<&str>,
+    ) -> Result<ExportChunk> {
+        let artifact = self.artifact(id)?;
+        let whole = ByteRange {
+            start: 0,
+            end: artifact.size.saturating_sub(1),
+        };
+        let range = match range {
+            Some(_) if if_range.is_some_and(|etag| etag.trim() != artifact.etag) => None,
+            Some(range) => Some(ByteRange::parse(range, artifact.size)?),
+            None => None,
+        };
+        let path = content_path(&self.directory, id);
+        let failed =
+            |err: std::io::Error| Error::Custom(format!("Failed to read export {}: {}", id, err));
+        let mut file = File::open(&path).map_err(failed)?;
+        let read = range.unwrap_or(whole);
+        let mut content = vec![0; artifact.size.min(read.len()) as usize];
+        file.seek(SeekFrom::Start(read.start)).map_err(failed)?;
+        file.read_exact(&mut content).map_err(failed)?;
+        Ok(ExportChunk {
+            artifact,
+            range: read,
+            partial: range.is_some(),
+            content,
+        })
+    }
+
+    /// Deletes the artifacts written more than `RETENTION_DAYS` before `now`
+    ///
+    /// # Returns
+    /// - `Ok(usize)`: The number of artifacts deleted
+    /// - `Err(Error)`: If the directory cannot be listed
+    pub fn prune(&self, now: DateTime<Local>) -> Result<usize> {
+        let entries = fs::read_dir(&self.directory).map_err(|err| {
+            Error::Custom(format!(
+                "Failed to list export directory {}: {}",
+                self.directory.display(),
+                err
+            ))
+        })?;
+        let mut deleted = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|extension| extension != "json") {
+                continue;
+            }
+            let Ok(Some(artifact)) = persist::read_json::<ExportArtifact>(&path, "export") else {
+                continue;
+            };
+            if now - artifact.created_at > chrono::Duration::days(RETENTION_DAYS) {
+                let _ = fs::remove_file(content_path(&self.directory, &artifact.id));
+                let _ = fs::remove_file(&path);
+                deleted += 1;
+            }
+        }
+        Ok(deleted)
+    }
+}
+
+/// Returns the error of a range starting beyond an artifact of `size` bytes
+fn unsatisfiable(header: &str, size: u64) -> Error {
+    Error::RangeNotSatisfiable(format!(
+        "Range not satisfiable: {} of {} bytes",
+        header, size
+    ))
+}
+
+fn content_path(directory: &Path, id: &Uuid) -> PathBuf {
+    directory.join(format!("{}.export", id))
+}
+
+fn metadata_path(directory: &Path, id: &Uuid) -> PathBuf {
+    directory.join(format!("{}.json", id))
+}
+
+/// Lowercase hexadecimal form of a digest
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,123 @@
+use crate::models::{device::Auth, device::Device, link::Link, node::Node};
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Renders links as CSV, one row per link
+///
+/// Endpoints are flattened into `endpoint_N_node_uuid` / `endpoint_N_node_edge_point_uuid`
+/// column pairs, as many as the link with most endpoints needs (at least two). This is
+/// the body of `GET /topology/{host}/links?format=csv`.
+pub fn links_to_csv(links: &[Link]) -> String {
+    let endpoints = links
+        .iter()
+        .map(|link| link.node_edge_points.len())
+        .max()
+        .unwrap_or_default()
+        .max(2);
+
+    let mut header = vec![
+        "host".to_string(),
+        "uuid".to_string(),
+        "lifecycle_state".to_string(),
+    ];
+    for index in 1..=endpoints {
+        header.push(format!("endpoint_{}_node_uuid", index));
+        header.push(format!("endpoint_{}_node_edge_point_uuid", index));
+    }
+    header.push("date".to_string());
+
+    let mut csv = row(&header);
+    for link in links {
+        let mut fields = vec![
+            link.host.clone(),
+            link.uuid.to_string(),
+            text(&link.lifecycle_state),
+        ];
+        for index in 0..endpoints {
+            match link.node_edge_points.get(index) {
+                Some(nep) => {
+                    fields.push(nep.node_uuid.to_string());
+                    fields.push(nep.node_edge_point_uuid.to_string());
+                }
+                None => fields.extend([String::new(), String::new()]),
+            }
+        }
+        fields.push(link.date.to_rfc3339());
+        csv.push_str(&row(&fields));
+    }
+    csv
+}
+
+/// Renders nodes as CSV, one row per node
+pub fn nodes_to_csv(nodes: &[Node]) -> String {
+    let mut csv = row(&[
+        "host",
+        "uuid",
+        "name",
+        "lifecycle_state",
+        "site",
+        "node_edge_points",
+        "date",
+    ]);
+    for node in nodes {
+        csv.push_str(&row(&[
+            node.host.clone(),
+            node.uuid.to_string(),
+            node.name.clone().unwrap_or_default(),
+            text(&node.lifecycle_state),
+            node.site
+                .as_ref()
+                .map(|site| site.id.clone())
+                .unwrap_or_default(),
+            node.owned_node_edge_points.len().to_string(),
+            node.date.to_rfc3339(),
+        ]));
+    }
+    csv
+}
+
+/// Renders devices as CSV, one row per device
+///
+/// Only the authentication method is exported, never the credentials.
+pub fn devices_to_csv(devices: &[Device]) -> String {
+    let mut csv = row(&["host", "port", "auth"]);
+    for device in devices {
+        let auth = match device.auth {
+            Auth::BasicAuth(_) => "basic",
+            Auth::Oauth2(_) => "oauth2",
+            Auth::Custom(_) => "custom",
+            Auth::ApiKey(_) => "api_key",
+            Auth::BearerToken(_) => "bearer_token",
+        };
+        csv.push_str(&row(&[
+            device.host.to_string(),
+            device.port.map(|port| port.to_string()).unwrap_or_default(),
+            auth.to_string(),
+        ]));
+    }
+    csv
+}
+
+/// Returns the serialized text of a value (e.g. `INSTALLED`), or an empty string
+fn text(value: &Option<impl Serialize>) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(text)) => text,
+        _ => String::new(),
+    }
+}
+
+/// Builds a CSV record terminated by CRLF (RFC 4180)
+fn row(fields: &[impl AsRef<str>]) -> String {
+    let fields: Vec<String> = fields.iter().map(|field| escape(field.as_ref())).collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+/// Quotes a field if it contains a separator, quote or line break
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,69 @@
+use crate::models::{site::Site, topology::Topology};
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Renders enriched topologies as a GeoJSON `FeatureCollection`
+///
+/// Nodes with a site become `Point` features and links whose two ends have a site
+/// become `LineString` features. Objects without coordinates are left out, so run the
+/// enrichment stage first. This is the body of `GET /export/topology.geojson`.
+pub fn to_geojson(topologies: &[Topology]) -> Value {
+    let mut features: Vec<Value> = vec![];
+
+    for topology in topologies {
+        let sites: HashMap<Uuid, &Site> = topology
+            .nodes
+            .iter()
+            .filter_map(|node| node.site.as_ref().map(|site| (node.uuid, site)))
+            .collect();
+
+        for node in &topology.nodes {
+            let Some(site) = &node.site else { continue };
+            features.push(json!({
+                "type": "Feature",
+                "geometry": {"type": "Point", "coordinates": coordinates(site)},
+                "properties": {
+                    "kind": "node",
+                    "host": topology.host,
+                    "uuid": node.uuid,
+                    "name": node.name,
+                    "site": site.id,
+                    "lifecycle-state": node.lifecycle_state,
+                }
+            }));
+        }
+
+        for link in &topology.links {
+            // A line needs every endpoint placed on the map
+            let Some(line) = link
+                .node_edge_points
+                .iter()
+                .map(|nep| sites.get(&nep.node_uuid).map(|site| coordinates(site)))
+                .collect::<Option<Vec<[f64; 2]>>>()
+            else {
+                continue;
+            };
+
+            features.push(json!({
+                "type": "Feature",
+                "geometry": {"type": "LineString", "coordinates": line},
+                "properties": {
+                    "kind": "link",
+                    "host": topology.host,
+                    "uuid": link.uuid,
+                    "lifecycle-state": link.lifecycle_state,
+                }
+            }));
+        }
+    }
+
+    json!({"type": "FeatureCollection", "features": features})
+}
+
+/// GeoJSON positions are `[longitude, latitude]`
+fn coordinates(site: &Site) -> [f64; 2] {
+    [site.longitude, site.latitude]
+}
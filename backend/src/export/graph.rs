@@ -0,0 +1,180 @@
+use crate::models::topology::Topology;
+use crate::{Error, Result};
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Output format of the topology graph export
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,     // Graphviz
+    Graphml, // yEd, Gephi...
+    Json,    // Node-link JSON
+}
+
+impl FromStr for GraphFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "dot" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::Graphml),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(Error::Invalid(format!("Invalid export format {}", value))),
+        }
+    }
+}
+
+impl GraphFormat {
+    /// Returns the media type the rendered graph is served with
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "text/vnd.graphviz",
+            GraphFormat::Graphml => "application/graphml+xml",
+            GraphFormat::Json => "application/json",
+        }
+    }
+}
+
+/// An edge of the graph: one link between two nodes
+struct Edge {
+    link: Uuid,
+    source: Uuid,
+    target: Uuid,
+}
+
+/// Splits links into edges
+///
+/// A link joins the node of its first endpoint to the node of every other endpoint,
+/// so point-to-multipoint links are drawn as a star.
+fn edges(topology: &Topology) -> Vec<Edge> {
+    let mut edges = vec![];
+    for link in &topology.links {
+        let Some((first, others)) = link.node_edge_points.split_first() else {
+            continue;
+        };
+        for other in others {
+            edges.push(Edge {
+                link: link.uuid,
+                source: first.node_uuid,
+                target: other.node_uuid,
+            });
+        }
+    }
+    edges
+}
+
+/// Renders the nodes/links graph of a topology
+///
+/// This is the body of `GET /topology/{host}/export?format=dot|graphml|json`, the
+/// artifact of `POST /devices/{host}/export` and the output of `cli topology export`.
+pub fn render(topology: &Topology, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Dot => to_dot(topology),
+        GraphFormat::Graphml => to_graphml(topology),
+        GraphFormat::Json => to_json(topology).to_string(),
+    }
+}
+
+/// Renders the topology as an undirected Graphviz graph
+pub fn to_dot(topology: &Topology) -> String {
+    let mut dot = format!("graph \"{}\" {{\n", escape_dot(&topology.uuid.to_string()));
+
+    for node in &topology.nodes {
+        let label = node.name.clone().unwrap_or_else(|| node.uuid.to_string());
+        let _ = writeln!(
+            dot,
+            "  \"{}\" [label=\"{}\"];",
+            node.uuid,
+            escape_dot(&label)
+        );
+    }
+    for edge in edges(topology) {
+        let _ = writeln!(
+            dot,
+            "  \"{}\" -- \"{}\" [id=\"{}\"];",
+            edge.source, edge.target, edge.link
+        );
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Renders the topology as a GraphML document
+pub fn to_graphml(topology: &Topology) -> String {
+    let mut graphml = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n",
+        "  <key id=\"host\" for=\"graph\" attr.name=\"host\" attr.type=\"string\"/>\n",
+    ));
+    let _ = writeln!(
+        graphml,
+        "  <graph id=\"{}\" edgedefault=\"undirected\">",
+        topology.uuid
+    );
+    let _ = writeln!(
+        graphml,
+        "    <data key=\"host\">{}</data>",
+        escape_xml(&topology.host)
+    );
+
+    for node in &topology.nodes {
+        match &node.name {
+            Some(name) => {
+                let _ = writeln!(
+                    graphml,
+                    "    <node id=\"{}\"><data key=\"name\">{}</data></node>",
+                    node.uuid,
+                    escape_xml(name)
+                );
+            }
+            None => {
+                let _ = writeln!(graphml, "    <node id=\"{}\"/>", node.uuid);
+            }
+        }
+    }
+    for edge in edges(topology) {
+        let _ = writeln!(
+            graphml,
+            "    <edge id=\"{}\" source=\"{}\" target=\"{}\"/>",
+            edge.link, edge.source, edge.target
+        );
+    }
+
+    graphml.push_str("  </graph>\n</graphml>\n");
+    graphml
+}
+
+/// Renders the topology as node-link JSON (`{"nodes": [...], "links": [...]}`)
+pub fn to_json(topology: &Topology) -> Value {
+    let nodes: Vec<Value> = topology
+        .nodes
+        .iter()
+        .map(|node| json!({"id": node.uuid, "name": node.name}))
+        .collect();
+    let links: Vec<Value> = edges(topology)
+        .into_iter()
+        .map(|edge| json!({"id": edge.link, "source": edge.source, "target": edge.target}))
+        .collect();
+
+    json!({"host": topology.host, "uuid": topology.uuid, "nodes": nodes, "links": links})
+}
+
+/// Escapes a Graphviz quoted string
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Escapes XML character data and attribute values
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
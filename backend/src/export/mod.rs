@@ -0,0 +1,7 @@
+pub mod anonymize;
+pub mod archive;
+pub mod artifact;
+pub mod csv;
+pub mod geojson;
+pub mod graph;
+pub mod output;
@@ -0,0 +1,116 @@
+use crate::{Error, Result};
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use serde_json::Value;
+
+/// Format of the CLI command output, chosen with the global `--output` flag
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Table, // Aligned columns for people
+    Json, // Pretty printed JSON for scripts
+    Yaml, // YAML for scripts and config files
+}
+
+impl FromStr for OutputFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "yaml" => Ok(OutputFormat::Yaml),
+            _ => Err(Error::Invalid(format!("Invalid output format {}", value))),
+        }
+    }
+}
+
+/// Renders the result of a command in `format`
+///
+/// As a table, an array of objects gets one row per object and one column per key, in
+/// the order keys are first seen; an object gets one `key  value` row per field.
+/// Nested arrays and objects are printed as compact JSON in their cell.
+///
+/// # Returns
+/// - `Ok(String)`: The rendered output, ending with a newline unless empty
+/// - `Err(Error)`: If `value` cannot be serialized in `format`
+pub fn render_output(value: &Value, format: OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Json => serde_json::to_string_pretty(value)
+            .map(|output| output + "\n")
+            .map_err(|err| Error::Custom(format!("Failed to serialize output: {}", err))),
+        OutputFormat::Yaml => serde_yaml::to_string(value)
+            .map_err(|err| Error::Custom(format!("Failed to serialize output: {}", err))),
+        OutputFormat::Table => Ok(table(value)),
+    }
+}
+
+/// Renders `value` as aligned columns
+fn table(value: &Value) -> String {
+    let rows: Vec<Vec<String>> = match value {
+        Value::Array(items) if items.iter().all(Value::is_object) => {
+            let mut columns: Vec<&str> = vec![];
+            for item in items.iter().filter_map(Value::as_object) {
+                for key in item.keys() {
+                    if !columns.contains(&key.as_str()) {
+                        columns.push(key);
+                    }
+                }
+            }
+            if columns.is_empty() {
+                return String::new();
+            }
+            let header = columns.iter().map(|key| key.to_uppercase()).collect();
+            let body = items.iter().map(|item| {
+                columns
+                    .iter()
+                    .map(|key| cell(item.get(*key).unwrap_or(&Value::Null)))
+                    .collect()
+            });
+            std::iter::once(header).chain(body).collect()
+        }
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(key, value)| vec![key.clone(), cell(value)])
+            .collect(),
+        Value::Array(items) => items.iter().map(|item| vec![cell(item)]).collect(),
+        value => vec![vec![cell(value)]],
+    };
+
+    let mut widths: Vec<usize> = vec![];
+    for row in &rows {
+        for (index, cell) in row.iter().enumerate() {
+            let width = cell.chars().count();
+            match widths.get_mut(index) {
+                Some(max) => *max = (*max).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+
+    let mut output = String::new();
+    for row in rows {
+        let mut line = String::new();
+        for (index, cell) in row.iter().enumerate() {
+            if index + 1 == row.len() {
+                line.push_str(cell);
+            } else {
+                let _ = write!(line, "{:width$}  ", cell, width = widths[index]);
+            }
+        }
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+/// Renders a value in a table cell: strings unquoted, nothing for null
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,152 @@
+use crate::persist;
+use crate::{Error, Result};
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+/// Parser run over every polled payload, in addition to the topology parser
+///
+/// Integrators implement it to pull data the application does not model (e.g. vendor
+/// OTN performance counters) and register it in an [`ExtractorRegistry`]; the objects
+/// it returns are kept in the [`CustomObjectStore`].
+pub trait ModelExtractor: Send + Sync {
+    /// Unique name of the extractor, also the kind of the objects it produces
+    fn name(&self) -> &str;
+
+    /// Extracts objects from the raw payload polled from `host`
+    fn extract(&self, host: &str, payload: &Value) -> Result<Vec<Value>>;
+}
+
+/// An object produced by a [`ModelExtractor`]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CustomObject {
+    pub extractor: String, // Name of the extractor that produced the object
+    pub host: String,      // Device the payload was polled from
+    pub collected_at: DateTime<Local>,
+    pub object: Value,
+}
+
+/// The extractors run by the collector, in registration order
+#[derive(Default)]
+pub struct ExtractorRegistry {
+    extractors: Vec<Box<dyn ModelExtractor>>,
+}
+
+impl ExtractorRegistry {
+    /// Creates a registry without extractors
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an extractor
+    ///
+    /// # Returns
+    /// - `Ok(())`: If the extractor was registered
+    /// - `Err(Error)`: If an extractor with the same name is already registered
+    pub fn register(&mut self, extractor: impl ModelExtractor + 'static) -> Result<()> {
+        if self.names().contains(&extractor.name()) {
+            return Err(Error::Custom(format!(
+                "Duplicated extractor {}",
+                extractor.name()
+            )));
+        }
+        self.extractors.push(Box::new(extractor));
+        Ok(())
+    }
+
+    /// Returns the names of the registered extractors
+    pub fn names(&self) -> Vec<&str> {
+        self.extractors
+            .iter()
+            .map(|extractor| extractor.name())
+            .collect()
+    }
+
+    /// Runs every extractor over a payload and stores their objects
+    ///
+    /// A failing extractor is logged and keeps its previous objects; it never fails the
+    /// poll nor the other extractors.
+    ///
+    /// # Returns
+    /// - `Ok(usize)`: The number of objects stored
+    /// - `Err(Error)`: If the store could not be written
+    pub fn run(&self, host: &str, payload: &Value, store: &mut CustomObjectStore) -> Result<usize> {
+        let collected_at = Local::now();
+        let mut stored = 0;
+        for extractor in &self.extractors {
+            match extractor.extract(host, payload) {
+                Ok(objects) => {
+                    stored += objects.len();
+                    store.replace(extractor.name(), host, collected_at, objects)?;
+                }
+                Err(err) => warn!("Extractor {} failed on {}: {}", extractor.name(), host, err),
+            }
+        }
+        Ok(stored)
+    }
+}
+
+/// Latest objects of every extractor and device, the generic `custom_objects` table
+///
+/// With [`CustomObjectStore::open`] the objects are saved to a JSON file on every
+/// change and reloaded after a restart.
+#[derive(Default)]
+pub struct CustomObjectStore {
+    path: Option<PathBuf>,
+    objects: Vec<CustomObject>,
+}
+
+impl CustomObjectStore {
+    /// Creates an in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the store persisted to `path`, loading the objects already stored
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let objects = persist::read_json(&path, "custom objects")?.unwrap_or_default();
+
+        Ok(CustomObjectStore {
+            path: Some(path),
+            objects,
+        })
+    }
+
+    /// Replaces the objects of `extractor` for `host`
+    pub fn replace(
+        &mut self,
+        extractor: &str,
+        host: &str,
+        collected_at: DateTime<Local>,
+        objects: Vec<Value>,
+    ) -> Result<()> {
+        self.objects
+            .retain(|object| object.extractor != extractor || object.host != host);
+        self.objects
+            .extend(objects.into_iter().map(|object| CustomObject {
+                extractor: extractor.to_string(),
+                host: host.to_string(),
+                collected_at,
+                object,
+            }));
+
+        if let Some(path) = &self.path {
+            persist::write_json(path, &self.objects, "custom objects")?;
+        }
+        Ok(())
+    }
+
+    /// Returns the objects of `extractor`, optionally restricted to one host
+    pub fn query(&self, extractor: &str, host: Option<&str>) -> Vec<&CustomObject> {
+        self.objects
+            .iter()
+            .filter(|object| object.extractor == extractor)
+            .filter(|object| host.is_none_or(|host| object.host == host))
+            .collect()
+    }
+}
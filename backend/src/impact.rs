@@ -0,0 +1,106 @@
+use crate::models::{
+    connection::{Connection, ConnectivityService},
+    link::Link,
+};
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A connectivity service whose route goes through a link
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ImpactedService {
+    pub host: String,
+    pub uuid: Uuid,
+    pub name: Option<String>,
+    pub connections: Vec<Uuid>, // Connections of the service routed over the link
+}
+
+/// Services affected by a link going down, the body of `GET /links/{uuid}/impact`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LinkImpact {
+    pub link: Uuid,
+    pub services: Vec<ImpactedService>,
+}
+
+impl LinkImpact {
+    /// Finds the services whose routes traverse `link`
+    ///
+    /// A connection traverses the link when one of its routes has connection end points
+    /// on the node edge points at both ends of the link. The connections of a service are
+    /// followed down through their lower connections, so a service is reported even when
+    /// only a lower layer connection is routed over the link.
+    ///
+    /// # Arguments
+    /// - `link`: The link to analyse
+    /// - `services`: The connectivity services of the device
+    /// - `connections`: The connections of the device, of every layer
+    pub fn analyse(
+        link: &Link,
+        services: &[ConnectivityService],
+        connections: &[Connection],
+    ) -> Self {
+        let by_uuid: HashMap<Uuid, &Connection> = connections
+            .iter()
+            .map(|connection| (connection.uuid, connection))
+            .collect();
+        let ends: Vec<Uuid> = link
+            .node_edge_points
+            .iter()
+            .map(|node_edge_point| node_edge_point.node_edge_point_uuid)
+            .collect();
+
+        let services = services
+            .iter()
+            .filter_map(|service| {
+                let traversing: Vec<Uuid> = service
+                    .connections
+                    .iter()
+                    .filter(|uuid| traverses(uuid, &ends, &by_uuid, &mut HashSet::new()))
+                    .copied()
+                    .collect();
+                (!traversing.is_empty()).then(|| ImpactedService {
+                    host: service.host.clone(),
+                    uuid: service.uuid,
+                    name: service.name.clone(),
+                    connections: traversing,
+                })
+            })
+            .collect();
+
+        LinkImpact {
+            link: link.uuid,
+            services,
+        }
+    }
+}
+
+/// Whether connection `uuid`, or one of its lower connections, is routed over `ends`
+fn traverses(
+    uuid: &Uuid,
+    ends: &[Uuid],
+    connections: &HashMap<Uuid, &Connection>,
+    visited: &mut HashSet<Uuid>,
+) -> bool {
+    // Guards against inconsistent payloads where connections support each other
+    if !visited.insert(*uuid) {
+        return false;
+    }
+    let Some(connection) = connections.get(uuid) else {
+        return false;
+    };
+
+    connection.routes.iter().any(|route| {
+        !ends.is_empty()
+            && ends.iter().all(|end| {
+                route
+                    .connection_end_points
+                    .iter()
+                    .any(|end_point| end_point.node_edge_point_uuid == *end)
+            })
+    }) || connection
+        .lower_connections
+        .iter()
+        .any(|lower| traverses(lower, ends, connections, visited))
+}
@@ -0,0 +1,384 @@
+use crate::events::ObjectKind;
+use crate::models::hash::ContentHash;
+use crate::models::key::ObjectKey;
+use crate::models::link::Link;
+use crate::models::node::Node;
+use crate::models::topology::Topology;
+use crate::persist;
+use crate::{Error, Result};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+use uuid::Uuid;
+
+/// A node or link as stored, with the content hash it is compared on
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StoredObject {
+    pub kind: ObjectKind,
+    pub hash: ContentHash, // Hash of the controller payload of the object
+    pub object: Value,     // The serialized `Node` or `Link`
+}
+
+/// Key of a stored object
+///
+/// TAPI UUIDs are only unique per object class, so a node and a link of a topology may
+/// share one; the kind keeps them apart.
+pub type StoredKey = (ObjectKind, ObjectKey);
+
+/// Writes bringing the stored objects of a host in line with a new collection
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Delta {
+    pub inserts: Vec<StoredKey>, // Objects not stored yet
+    pub updates: Vec<StoredKey>, // Stored objects whose hash changed
+    pub deletes: Vec<StoredKey>, // Stored objects no longer reported
+    pub unchanged: usize,        // Stored objects with the same hash, not written
+}
+
+impl Delta {
+    /// Returns `true` when the collection requires no write
+    pub fn is_empty(&self) -> bool {
+        self.inserts.is_empty() && self.updates.is_empty() && self.deletes.is_empty()
+    }
+
+    /// Returns the number of objects to write or delete
+    pub fn writes(&self) -> usize {
+        self.inserts.len() + self.updates.len() + self.deletes.len()
+    }
+}
+
+/// Line of the journal file
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum JournalLine {
+    Upsert {
+        key: ObjectKey,
+        #[serde(flatten)]
+        object: StoredObject,
+    },
+    Delete {
+        key: ObjectKey,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        kind: Option<ObjectKind>, // Absent from older journals, whose deletes apply to both kinds
+    },
+}
+
+impl JournalLine {
+    /// Applies the line to `objects`
+    fn apply(self, objects: &mut HashMap<StoredKey, StoredObject>) {
+        match self {
+            JournalLine::Upsert { key, object } => {
+                objects.insert((object.kind, key), object);
+            }
+            JournalLine::Delete {
+                key,
+                kind: Some(kind),
+            } => {
+                objects.remove(&(kind, key));
+            }
+            JournalLine::Delete { key, kind: None } => {
+                objects.remove(&(ObjectKind::Node, key.clone()));
+                objects.remove(&(ObjectKind::Link, key));
+            }
+        }
+    }
+}
+
+/// Current nodes and links of every host, updated by delta ingestion
+///
+/// A stable topology reports mostly the same objects every poll, so rewriting all of
+/// them would make every poll cost as much as the first one. [`ObjectStore::ingest`]
+/// compares the hash of each collected object with the stored one and only writes the
+/// inserted, updated and deleted objects. With [`ObjectStore::open`] the writes are
+/// appended to a JSON Lines journal, replayed after a restart and rewritten by
+/// [`ObjectStore::compact`].
+#[derive(Default)]
+pub struct ObjectStore {
+    path: Option<PathBuf>,
+    objects: HashMap<StoredKey, StoredObject>,
+}
+
+impl ObjectStore {
+    /// Creates an in-memory object store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the object store persisted to `path`, replaying its journal
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut objects = HashMap::new();
+        if path.exists() {
+            let content = fs::read_to_string(&path).map_err(|err| {
+                Error::Custom(format!(
+                    "Failed to read objects {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                let line: JournalLine = serde_json::from_str(line)
+                    .map_err(|err| Error::Custom(format!("Invalid objects file: {}", err)))?;
+                line.apply(&mut objects);
+            }
+        }
+
+        Ok(ObjectStore {
+            path: Some(path),
+            objects,
+        })
+    }
+
+    /// Returns the number of stored objects
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Returns `true` when no object is stored
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Returns the stored object of `kind` with `key`
+    pub fn get(&self, kind: ObjectKind, key: &ObjectKey) -> Option<&StoredObject> {
+        self.objects.get(&(kind, key.clone()))
+    }
+
+    /// Returns the hosts having stored objects, sorted
+    pub fn hosts(&self) -> Vec<&str> {
+        let hosts: BTreeSet<&str> = self
+            .objects
+            .keys()
+            .map(|(_, key)| key.host.as_str())
+            .collect();
+        hosts.into_iter().collect()
+    }
+
+    /// Returns the stored objects collected from `host`
+    pub fn objects_of<'a>(
+        &'a self,
+        host: &'a str,
+    ) -> impl Iterator<Item = (&'a ObjectKey, &'a StoredObject)> + 'a {
+        self.objects
+            .iter()
+            .filter(move |((_, key), _)| key.host == host)
+            .map(|((_, key), object)| (key, object))
+    }
+
+    /// Rebuilds the topologies of `host` from its stored nodes and links
+    ///
+    /// Objects come back sorted by UUID, without the site of the enrichment stage.
+    ///
+    /// # Returns
+    /// - `Ok(Some(Vec<Topology>))`: The topologies, sorted by UUID
+    /// - `Ok(None)`: If no object of `host` is stored
+    /// - `Err(Error)`: If a stored object is not a valid node or link
+    pub fn topologies(&self, host: &str) -> Result<Option<Vec<Topology>>> {
+        let mut objects: Vec<(&StoredKey, &StoredObject)> = self
+            .objects
+            .iter()
+            .filter(|((_, key), _)| key.host == host)
+            .collect();
+        if objects.is_empty() {
+            return Ok(None);
+        }
+        objects.sort_by(|(left, _), (right, _)| left.1.cmp(&right.1));
+
+        let mut topologies: BTreeMap<Uuid, Topology> = BTreeMap::new();
+        for ((kind, key), stored) in objects {
+            let topology = topologies
+                .entry(key.topology_uuid)
+                .or_insert_with(|| Topology {
+                    host: host.to_string(),
+                    uuid: key.topology_uuid,
+                    nodes: vec![],
+                    links: vec![],
+                    site: None,
+                });
+            let invalid = |err: serde_json::Error| {
+                Error::Custom(format!("Invalid stored object {}: {}", key, err))
+            };
+            match kind {
+                ObjectKind::Node => topology
+                    .nodes
+                    .push(Node::deserialize(&stored.object).map_err(invalid)?),
+                ObjectKind::Link => topology
+                    .links
+                    .push(Link::deserialize(&stored.object).map_err(invalid)?),
+            }
+        }
+        Ok(Some(topologies.into_values().collect()))
+    }
+
+    /// Returns the writes needed to store the topologies collected from `host`
+    ///
+    /// Stored objects of `host` that are in none of `topologies` are deleted, including
+    /// those of topologies the device no longer reports.
+    pub fn delta(&self, host: &str, topologies: &[Topology]) -> Delta {
+        self.plan(host, &index(topologies))
+    }
+
+    fn plan(&self, host: &str, current: &HashMap<StoredKey, ObjectRef>) -> Delta {
+        let mut delta = Delta::default();
+        for (key, object) in current {
+            match self.objects.get(key) {
+                None => delta.inserts.push(key.clone()),
+                Some(stored) if stored.hash != object.hash() => delta.updates.push(key.clone()),
+                Some(_) => delta.unchanged += 1,
+            }
+        }
+        delta.deletes = self
+            .objects
+            .keys()
+            .filter(|stored| stored.1.host == host && !current.contains_key(*stored))
+            .cloned()
+            .collect();
+
+        // Sorted so that the journal and the reports do not depend on the hash order
+        delta.inserts.sort();
+        delta.updates.sort();
+        delta.deletes.sort();
+        delta
+    }
+
+    /// Stores the topologies collected from `host`, writing only what changed
+    ///
+    /// # Returns
+    /// - `Ok(Delta)`: The writes that were applied
+    /// - `Err(Error)`: If an object could not be serialized or the journal written
+    pub fn ingest(&mut self, host: &str, topologies: &[Topology]) -> Result<Delta> {
+        let current = index(topologies);
+        let delta = self.plan(host, &current);
+        if delta.is_empty() {
+            return Ok(delta);
+        }
+
+        let mut lines = Vec::with_capacity(delta.writes());
+        for stored in delta.inserts.iter().chain(&delta.updates) {
+            lines.push(JournalLine::Upsert {
+                key: stored.1.clone(),
+                object: current[stored].stored()?,
+            });
+        }
+        for (kind, key) in &delta.deletes {
+            lines.push(JournalLine::Delete {
+                key: key.clone(),
+                kind: Some(*kind),
+            });
+        }
+
+        self.append(&lines)?;
+        for line in lines {
+            line.apply(&mut self.objects);
+        }
+        Ok(delta)
+    }
+
+    /// Rewrites the journal with one line per stored object
+    pub fn compact(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut content = String::new();
+        let mut keys: Vec<&StoredKey> = self.objects.keys().collect();
+        keys.sort();
+        for stored in keys {
+            let line = JournalLine::Upsert {
+                key: stored.1.clone(),
+                object: self.objects[stored].clone(),
+            };
+            content.push_str(&serde_json::to_string(&line).map_err(Error::custom)?);
+            content.push('\n');
+        }
+
+        persist::write_atomic(path, content.as_bytes(), "objects")
+    }
+
+    /// Appends `lines` to the journal, if the store is persisted
+    fn append(&self, lines: &[JournalLine]) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut content = String::new();
+        for line in lines {
+            content.push_str(&serde_json::to_string(line).map_err(Error::custom)?);
+            content.push('\n');
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|err| {
+                Error::Custom(format!(
+                    "Failed to write objects {}: {}",
+                    path.display(),
+                    err
+                ))
+            })
+    }
+}
+
+/// A collected node or link
+#[derive(Clone, Copy)]
+enum ObjectRef<'a> {
+    Node(&'a Node),
+    Link(&'a Link),
+}
+
+impl ObjectRef<'_> {
+    fn kind(&self) -> ObjectKind {
+        match self {
+            ObjectRef::Node(_) => ObjectKind::Node,
+            ObjectRef::Link(_) => ObjectKind::Link,
+        }
+    }
+
+    fn hash(&self) -> ContentHash {
+        match self {
+            ObjectRef::Node(node) => node.hash,
+            ObjectRef::Link(link) => link.hash,
+        }
+    }
+
+    fn stored(&self) -> Result<StoredObject> {
+        let object = match self {
+            ObjectRef::Node(node) => serde_json::to_value(node),
+            ObjectRef::Link(link) => serde_json::to_value(link),
+        };
+        Ok(StoredObject {
+            kind: self.kind(),
+            hash: self.hash(),
+            object: object.map_err(Error::custom)?,
+        })
+    }
+}
+
+/// Indexes the objects of `topologies` by kind and key, keeping the first of duplicates
+fn index(topologies: &[Topology]) -> HashMap<StoredKey, ObjectRef<'_>> {
+    let mut index = HashMap::new();
+    for topology in topologies {
+        let nodes = topology
+            .nodes
+            .iter()
+            .map(|node| (node.uuid, ObjectRef::Node(node)));
+        let links = topology
+            .links
+            .iter()
+            .map(|link| (link.uuid, ObjectRef::Link(link)));
+        for (uuid, object) in nodes.chain(links) {
+            let key = (object.kind(), topology.key(uuid));
+            if index.contains_key(&key) {
+                warn!("Duplicate {:?} key {}, keeping the first one", key.0, key.1);
+                continue;
+            }
+            index.insert(key, object);
+        }
+    }
+    index
+}
@@ -0,0 +1,329 @@
+use crate::persist;
+use crate::tenant::{default_tenant, DEFAULT_TENANT};
+use crate::Result;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use tracing::warn;
+use uuid::Uuid;
+
+/// Finished jobs are forgotten this many days after they ended
+pub const RETENTION_DAYS: i64 = 7;
+
+/// Finished jobs kept at most, the oldest ones are forgotten first
+pub const KEPT_FINISHED: usize = 1_000;
+
+/// How long changes are gathered before the job table is saved
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Kind of long-running operation
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Poll,
+    Export,
+    Import,
+    Provisioning, // Service change awaiting the device confirmation
+    Bulk,         // Action run over the devices of a tag
+}
+
+/// Status of a job
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,  // Waiting for a worker
+    Running, // Picked up by a worker
+    Failed,  // Finished with an error
+    Done,    // Finished successfully, `result` holds the payload
+}
+
+/// A job and its outcome, the payload of `GET /jobs/{id}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: JobKind,
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String, // Tenant of the request that queued the job
+    pub status: JobStatus,
+    pub progress: u8, // Percentage, 0 to 100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: DateTime<Local>,
+    pub updated_at: DateTime<Local>,
+}
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<Value>> + Send>>;
+type JobTask = Box<dyn FnOnce(JobProgress) -> JobFuture + Send>;
+
+/// Handle given to a running job to report its progress
+#[derive(Clone)]
+pub struct JobProgress {
+    id: Uuid,
+    state: Arc<JobState>,
+}
+
+impl JobProgress {
+    /// Returns the id of the job, e.g. to name what it produces
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Sets the progress percentage of the job, capped at 100
+    pub fn set(&self, progress: u8) {
+        self.state
+            .update(&self.id, |job| job.progress = progress.min(100));
+    }
+}
+
+/// Job table shared by the queue and its workers
+struct JobState {
+    jobs: Mutex<HashMap<Uuid, Job>>,
+    path: Option<PathBuf>,
+    changed: Notify, // Wakes the writer of a persistent queue
+}
+
+impl JobState {
+    fn update(&self, id: &Uuid, change: impl FnOnce(&mut Job)) {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(id) {
+            change(job);
+            job.updated_at = Local::now();
+        }
+        if self.path.is_some() {
+            self.changed.notify_one();
+        }
+    }
+
+    /// Writes the job table to the file of the queue, if any
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        // Copied so the table is not locked while writing
+        let jobs = self.jobs.lock().unwrap().clone();
+        save(path, &jobs)
+    }
+}
+
+/// Async job queue for operations that must not block HTTP requests
+///
+/// Jobs are submitted with [`JobQueue::submit_for`] and run by a fixed pool of workers;
+/// clients follow the jobs of their tenant through `GET /jobs` and `GET /jobs/{id}`. With
+/// [`JobQueue::persistent`], the job table is saved to a JSON file so statuses survive
+/// restarts; jobs interrupted by a restart are reported as failed. Changes are saved in
+/// the background, at most every `SAVE_DELAY`, so frequent progress updates cost one
+/// write. Finished jobs are forgotten after `RETENTION_DAYS`, or beyond `KEPT_FINISHED`.
+pub struct JobQueue {
+    state: Arc<JobState>,
+    sender: mpsc::UnboundedSender<(Uuid, JobTask)>,
+    workers: Vec<JoinHandle<()>>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl JobQueue {
+    /// Creates an in-memory queue run by `workers` workers
+    pub fn new(workers: usize) -> Self {
+        Self::start(workers, HashMap::new(), None)
+    }
+
+    /// Creates a queue whose job table is persisted to `path`
+    pub fn persistent(workers: usize, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut jobs: HashMap<Uuid, Job> = persist::read_json(&path, "jobs")?.unwrap_or_default();
+
+        // Their task was lost with the previous process
+        for job in jobs.values_mut() {
+            if matches!(job.status, JobStatus::Queued | JobStatus::Running) {
+                job.status = JobStatus::Failed;
+                job.error = Some("Interrupted by a restart".to_string());
+                job.updated_at = Local::now();
+            }
+        }
+
+        prune(&mut jobs, Local::now());
+
+        Ok(Self::start(workers, jobs, Some(path)))
+    }
+
+    fn start(workers: usize, jobs: HashMap<Uuid, Job>, path: Option<PathBuf>) -> Self {
+        let persistent = path.is_some();
+        let state = Arc::new(JobState {
+            jobs: Mutex::new(jobs),
+            path,
+            changed: Notify::new(),
+        });
+        let (sender, receiver) = mpsc::unbounded_channel::<(Uuid, JobTask)>();
+        let receiver = Arc::new(tokio::sync::Mutex::new(receiver));
+
+        let workers = (0..workers.max(1))
+            .map(|_| {
+                let state = Arc::clone(&state);
+                let receiver = Arc::clone(&receiver);
+                tokio::spawn(async move {
+                    loop {
+                        let Some((id, task)) = receiver.lock().await.recv().await else {
+                            return;
+                        };
+                        state.update(&id, |job| job.status = JobStatus::Running);
+
+                        let progress = JobProgress {
+                            id,
+                            state: Arc::clone(&state),
+                        };
+                        // Run in its own task so a panicking job does not kill the worker
+                        let outcome = tokio::spawn(task(progress)).await;
+                        state.update(&id, |job| match outcome {
+                            Ok(Ok(result)) => {
+                                job.status = JobStatus::Done;
+                                job.progress = 100;
+                                job.result = Some(result);
+                            }
+                            Ok(Err(err)) => {
+                                job.status = JobStatus::Failed;
+                                job.error = Some(err.to_string());
+                            }
+                            Err(err) => {
+                                job.status = JobStatus::Failed;
+                                job.error = Some(format!("Job panicked: {}", err));
+                            }
+                        });
+                    }
+                })
+            })
+            .collect();
+
+        let writer = persistent.then(|| tokio::spawn(write_behind(Arc::downgrade(&state))));
+        JobQueue {
+            state,
+            sender,
+            workers,
+            writer,
+        }
+    }
+
+    /// Queues a job of the default tenant and returns its id
+    pub fn submit<F, Fut>(&self, kind: JobKind, task: F) -> Uuid
+    where
+        F: FnOnce(JobProgress) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        self.submit_for(DEFAULT_TENANT, kind, task)
+    }
+
+    /// Queues a job of `tenant_id` and returns its id, answered with `202 Accepted` by
+    /// the route starting the operation, e.g. `POST /actions/bulk`
+    pub fn submit_for<F, Fut>(&self, tenant_id: &str, kind: JobKind, task: F) -> Uuid
+    where
+        F: FnOnce(JobProgress) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        let now = Local::now();
+        let id = Uuid::new_v4();
+        let job = Job {
+            id,
+            kind,
+            tenant_id: tenant_id.to_string(),
+            status: JobStatus::Queued,
+            progress: 0,
+            result: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        };
+        {
+            let mut jobs = self.state.jobs.lock().unwrap();
+            prune(&mut jobs, now);
+            jobs.insert(id, job);
+        }
+        self.state.update(&id, |_| {});
+
+        let task: JobTask = Box::new(move |progress| Box::pin(task(progress)));
+        if self.sender.send((id, task)).is_err() {
+            self.state.update(&id, |job| {
+                job.status = JobStatus::Failed;
+                job.error = Some("Job queue is shut down".to_string());
+            });
+        }
+        id
+    }
+
+    /// Returns a job
+    pub fn get(&self, id: &Uuid) -> Option<Job> {
+        self.state.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    /// Returns every job, most recent first
+    pub fn list(&self) -> Vec<Job> {
+        let mut jobs: Vec<Job> = self.state.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+        jobs
+    }
+
+    /// Stops the workers, then saves the job table; queued jobs are left as they are
+    pub fn shutdown(self) {
+        for worker in self.workers.iter().chain(&self.writer) {
+            worker.abort();
+        }
+        if let Err(err) = self.state.save() {
+            warn!("{}", err);
+        }
+    }
+}
+
+/// Saves the job table of a persistent queue after its changes, until the queue is gone
+async fn write_behind(state: Weak<JobState>) {
+    loop {
+        let Some(changed) = state.upgrade() else {
+            return;
+        };
+        changed.changed.notified().await;
+        drop(changed);
+        // Changes made meanwhile are saved by the same write
+        tokio::time::sleep(SAVE_DELAY).await;
+
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        let saved = tokio::task::spawn_blocking(move || state.save()).await;
+        match saved {
+            Ok(Err(err)) => warn!("{}", err),
+            Err(err) => warn!("Failed to save jobs: {}", err),
+            Ok(Ok(())) => {}
+        }
+    }
+}
+
+/// Forgets the finished jobs beyond `RETENTION_DAYS` and `KEPT_FINISHED`
+fn prune(jobs: &mut HashMap<Uuid, Job>, now: DateTime<Local>) {
+    let oldest = now - chrono::Duration::days(RETENTION_DAYS);
+    let finished = |job: &Job| matches!(job.status, JobStatus::Done | JobStatus::Failed);
+    jobs.retain(|_, job| !finished(job) || job.updated_at >= oldest);
+
+    let mut ended: Vec<(DateTime<Local>, Uuid)> = jobs
+        .values()
+        .filter(|job| finished(job))
+        .map(|job| (job.updated_at, job.id))
+        .collect();
+    if ended.len() > KEPT_FINISHED {
+        ended.sort();
+        for (_, id) in &ended[..ended.len() - KEPT_FINISHED] {
+            jobs.remove(id);
+        }
+    }
+}
+
+/// Writes the job table to `path`
+fn save(path: &Path, jobs: &HashMap<Uuid, Job>) -> Result<()> {
+    persist::write_json(path, jobs, "jobs")
+}
@@ -0,0 +1,369 @@
+use crate::models::device::Device;
+use crate::persist;
+use crate::retention::Age;
+use crate::southbound::dns::CachedResolver;
+use crate::{Error, Result};
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local};
+use reqwest::Proxy;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpStream;
+
+/// Upper bounds of the buckets of the latency histograms, in seconds
+pub const BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// Days of samples kept per device
+pub const RETENTION_DAYS: i64 = 7;
+
+/// Time given to a probe before the device is deemed unreachable
+pub const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Port of the devices registered without one, that of HTTPS
+const DEFAULT_PORT: u16 = 443;
+
+/// How the latency of a device is measured
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LatencyProbe {
+    #[default]
+    Tcp, // Time to open a TCP connection, the network round trip
+    Http, // Time to the first byte of an HTTP answer, including TLS and the device itself
+}
+
+/// Settings of the latency monitoring, the `[latency]` section of `config.toml`
+///
+/// ```toml
+/// [latency]
+/// probe = "http"
+/// interval = 60
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LatencyConfig {
+    #[serde(default)]
+    pub probe: LatencyProbe,
+    #[serde(default = "default_interval")]
+    pub interval: u64, // Seconds between two probes of a device
+}
+
+fn default_interval() -> u64 {
+    60
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        LatencyConfig {
+            probe: LatencyProbe::default(),
+            interval: default_interval(),
+        }
+    }
+}
+
+/// A latency measurement of a device
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LatencySample {
+    pub at: DateTime<Local>,
+    pub probe: LatencyProbe,
+    pub latency_ms: Option<f64>, // `None` when the device did not answer in time
+}
+
+/// Line of the latency file
+#[derive(Serialize, Deserialize)]
+struct LogLine {
+    host: String,
+    #[serde(flatten)]
+    sample: LatencySample,
+}
+
+/// Latency of a device over a window, the body of `GET /devices/{host}/latency?window=24h`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LatencySeries {
+    pub host: String,
+    pub from: DateTime<Local>,
+    pub samples: Vec<LatencySample>, // Oldest first
+    pub failures: usize,             // Samples without answer
+    pub min_ms: Option<f64>,
+    pub avg_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
+
+/// Prometheus histogram of the latencies of a device
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; BUCKETS.len()], // Samples up to each bound, not cumulated
+    sum: f64,                      // Seconds
+    count: u64,
+}
+
+/// Latency time series of every device
+///
+/// Samples older than [`RETENTION_DAYS`] are dropped. With [`LatencyStore::open`] every
+/// sample is appended to a JSON Lines file, compacted when it is reopened. Answered
+/// probes also feed per-device histograms, exported by [`LatencyStore::prometheus`]
+/// and counted since the store was opened.
+#[derive(Default)]
+pub struct LatencyStore {
+    path: Option<PathBuf>,
+    series: BTreeMap<String, Vec<LatencySample>>,
+    histograms: BTreeMap<String, Histogram>,
+}
+
+impl LatencyStore {
+    /// Creates an in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the store persisted to `path`, loading the samples still retained
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut store = LatencyStore {
+            path: Some(path.clone()),
+            ..Default::default()
+        };
+        if !path.exists() {
+            return Ok(store);
+        }
+
+        let content = fs::read_to_string(&path).map_err(|err| {
+            Error::Custom(format!(
+                "Failed to read latency {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        let oldest = Local::now() - chrono::Duration::days(RETENTION_DAYS);
+        let mut expired = false;
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let line: LogLine = serde_json::from_str(line)
+                .map_err(|err| Error::Custom(format!("Invalid latency file: {}", err)))?;
+            if line.sample.at < oldest {
+                expired = true;
+            } else {
+                store.series.entry(line.host).or_default().push(line.sample);
+            }
+        }
+        if expired {
+            store.compact()?;
+        }
+        Ok(store)
+    }
+
+    /// Records a sample of `host`, dropping its samples beyond the retention
+    pub fn record(&mut self, host: &str, sample: LatencySample) -> Result<()> {
+        if let Some(path) = &self.path {
+            let line = serde_json::to_string(&LogLine {
+                host: host.to_string(),
+                sample: sample.clone(),
+            })
+            .map_err(Error::custom)?;
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| writeln!(file, "{}", line))
+                .map_err(|err| {
+                    Error::Custom(format!(
+                        "Failed to write latency {}: {}",
+                        path.display(),
+                        err
+                    ))
+                })?;
+        }
+
+        if let Some(latency_ms) = sample.latency_ms {
+            let seconds = latency_ms / 1000.0;
+            let histogram = self.histograms.entry(host.to_string()).or_default();
+            if let Some(bucket) = BUCKETS.iter().position(|bound| seconds <= *bound) {
+                histogram.buckets[bucket] += 1;
+            }
+            histogram.sum += seconds;
+            histogram.count += 1;
+        }
+
+        let oldest = sample.at - chrono::Duration::days(RETENTION_DAYS);
+        let samples = self.series.entry(host.to_string()).or_default();
+        samples.push(sample);
+        samples.retain(|sample| sample.at >= oldest);
+        Ok(())
+    }
+
+    /// Returns the last sample of `host`
+    pub fn last(&self, host: &str) -> Option<&LatencySample> {
+        self.series.get(host).and_then(|samples| samples.last())
+    }
+
+    /// Returns the samples of `host` taken over the `window` before `now`, with their
+    /// statistics
+    ///
+    /// # Returns
+    /// - `Ok(LatencySeries)`: The samples, possibly none in a short window
+    /// - `Err(Error)`: If `host` was never measured
+    pub fn series(&self, host: &str, window: Age, now: DateTime<Local>) -> Result<LatencySeries> {
+        let samples = self
+            .series
+            .get(host)
+            .ok_or_else(|| Error::NotFound(format!("Not found latency of {}", host)))?;
+        let from = now - window.duration();
+        let samples: Vec<LatencySample> = samples
+            .iter()
+            .filter(|sample| sample.at >= from && sample.at <= now)
+            .cloned()
+            .collect();
+
+        let mut latencies: Vec<f64> = samples
+            .iter()
+            .filter_map(|sample| sample.latency_ms)
+            .collect();
+        latencies.sort_by(f64::total_cmp);
+        // Nearest-rank percentile
+        let p95 = (!latencies.is_empty())
+            .then(|| latencies[(latencies.len() * 95).div_ceil(100).max(1) - 1]);
+        Ok(LatencySeries {
+            host: host.to_string(),
+            from,
+            failures: samples.len() - latencies.len(),
+            min_ms: latencies.first().copied(),
+            avg_ms: (!latencies.is_empty())
+                .then(|| latencies.iter().sum::<f64>() / latencies.len() as f64),
+            p95_ms: p95,
+            max_ms: latencies.last().copied(),
+            samples,
+        })
+    }
+
+    /// Renders the latency histogram of every device in the Prometheus text format
+    pub fn prometheus(&self) -> String {
+        self.prometheus_of(|_| true)
+    }
+
+    /// Renders the latency histogram of the devices whose host passes `include`
+    pub fn prometheus_of(&self, include: impl Fn(&str) -> bool) -> String {
+        let mut output = String::new();
+        let _ = writeln!(
+            output,
+            "# HELP device_latency_seconds Round-trip latency of the device"
+        );
+        let _ = writeln!(output, "# TYPE device_latency_seconds histogram");
+        for (host, histogram) in self.histograms.iter().filter(|(host, _)| include(host)) {
+            let mut cumulated = 0;
+            for (bound, count) in BUCKETS.iter().zip(histogram.buckets) {
+                cumulated += count;
+                let _ = writeln!(
+                    output,
+                    "device_latency_seconds_bucket{{host=\"{}\",le=\"{}\"}} {}",
+                    host, bound, cumulated
+                );
+            }
+            let _ = writeln!(
+                output,
+                "device_latency_seconds_bucket{{host=\"{}\",le=\"+Inf\"}} {}",
+                host, histogram.count
+            );
+            let _ = writeln!(
+                output,
+                "device_latency_seconds_sum{{host=\"{}\"}} {}",
+                host, histogram.sum
+            );
+            let _ = writeln!(
+                output,
+                "device_latency_seconds_count{{host=\"{}\"}} {}",
+                host, histogram.count
+            );
+        }
+        output
+    }
+
+    /// Writes the retained samples over the latency file
+    fn compact(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut content = String::new();
+        for (host, samples) in &self.series {
+            for sample in samples {
+                let line = serde_json::to_string(&LogLine {
+                    host: host.clone(),
+                    sample: sample.clone(),
+                })
+                .map_err(Error::custom)?;
+                content.push_str(&line);
+                content.push('\n');
+            }
+        }
+        persist::write_atomic(path, content.as_bytes(), "latency")
+    }
+}
+
+/// Measures the latency of `device`
+///
+/// A TCP connect to a device behind a proxy would measure the proxy, so those devices
+/// are always measured over HTTP, through their proxy.
+///
+/// # Arguments
+/// - `base_url`: The URL the device is polled at, e.g. `https://10.0.0.1:8443`
+pub async fn measure(
+    device: &Device,
+    base_url: &str,
+    probe: LatencyProbe,
+    resolver: &CachedResolver,
+) -> LatencySample {
+    let at = Local::now();
+    let probe = match device.proxy_url {
+        Some(_) => LatencyProbe::Http,
+        None => probe,
+    };
+    let started = Instant::now();
+    let answered = match probe {
+        LatencyProbe::Tcp => connect(device, resolver).await,
+        LatencyProbe::Http => first_byte(device, base_url).await,
+    };
+    LatencySample {
+        at,
+        probe,
+        latency_ms: answered.then(|| started.elapsed().as_secs_f64() * 1000.0),
+    }
+}
+
+/// Returns whether a TCP connection to the device opens in time
+async fn connect(device: &Device, resolver: &CachedResolver) -> bool {
+    let Ok(addresses) = resolver.resolve(device.host.as_str()).await else {
+        return false;
+    };
+    let Some(address) = addresses.first() else {
+        return false;
+    };
+    let port = device.port.map_or(DEFAULT_PORT, |port| port.get());
+    let connection = TcpStream::connect(SocketAddr::new(*address, port));
+    matches!(
+        tokio::time::timeout(PROBE_TIMEOUT, connection).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Returns whether the device answers an HTTP request in time, whatever the status
+async fn first_byte(device: &Device, base_url: &str) -> bool {
+    let mut builder = reqwest::Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        // Only the time to answer matters, not who answers
+        .danger_accept_invalid_certs(true);
+    if let Some(proxy_url) = &device.proxy_url {
+        match Proxy::all(proxy_url.as_str()) {
+            Ok(proxy) => builder = builder.proxy(proxy),
+            Err(_) => return false,
+        }
+    }
+    let Ok(client) = builder.build() else {
+        return false;
+    };
+    // `send` resolves once the status line and headers are read
+    client.head(base_url).send().await.is_ok()
+}
@@ -1,17 +1,86 @@
+pub mod actions;
+pub mod agents;
+pub mod api;
+pub mod audit;
+pub mod backup;
+pub mod cache;
+pub mod capture;
+pub mod collector;
+pub mod correlation;
+pub mod diff;
+pub mod discovery;
+pub mod enrichment;
+pub mod events;
+pub mod export;
+pub mod extractors;
+pub mod impact;
+pub mod ingest;
+pub mod jobs;
+pub mod latency;
+pub mod maintenance;
 pub mod models;
+pub mod normalization;
+pub mod notifications;
+pub mod pagination;
+pub mod payloads;
+pub mod persist;
+pub mod provisioning;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod retention;
+pub mod schema;
+pub mod search;
 pub mod setup;
+pub mod southbound;
+pub mod stats;
+pub mod stitching;
+pub mod supervisor;
+pub mod sync;
+pub mod tenant;
+pub mod testsupport;
+pub mod tui;
+pub mod validation;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+/// Errors of the library, by kind so callers can tell them apart
+///
+/// Every variant carries the message shown to users; the kind decides how the APIs
+/// answer it (see [`api::error::ApiError`]).
 #[derive(Debug)]
 pub enum Error {
-    Custom(String),
+    Custom(String),              // Any other failure, internal for the APIs
+    NotFound(String),            // No device or data is known for what was asked
+    TopologyNotFound(String),    // No topology, node or link is known for what was asked
+    Invalid(String),             // The input of the caller is malformed
+    InvalidCursor(String),       // A pagination cursor is forged, expired or for another query
+    Unauthorized(String),        // Missing or invalid credentials
+    Unreachable(String),         // The device could not be reached
+    Timeout(String),             // The device did not answer in time
+    BadResponse(String),         // The device answered with a payload that cannot be used
+    RangeNotSatisfiable(String), // A download range starts beyond the content
 }
 
 impl Error {
     pub fn custom(value: impl std::fmt::Display) -> Self {
         Self::Custom(value.to_string())
     }
+
+    /// Returns the message of the error, whatever its kind
+    pub fn message(&self) -> &str {
+        match self {
+            Error::Custom(message)
+            | Error::NotFound(message)
+            | Error::TopologyNotFound(message)
+            | Error::Invalid(message)
+            | Error::InvalidCursor(message)
+            | Error::Unauthorized(message)
+            | Error::Unreachable(message)
+            | Error::Timeout(message)
+            | Error::BadResponse(message)
+            | Error::RangeNotSatisfiable(message) => message,
+        }
+    }
 }
 
 impl From<&str> for Error {
@@ -19,3 +88,11 @@ impl From<&str> for Error {
         Self::Custom(value.to_string())
     }
 }
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for Error {}
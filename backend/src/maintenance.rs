@@ -0,0 +1,163 @@
+use crate::events::{ChangeEvent, ObjectKind};
+use crate::notifications::Alert;
+use crate::persist;
+use crate::{Error, Result};
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Object a maintenance window applies to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MaintenanceTarget {
+    Device { host: String },           // Every object of the device
+    Link { host: String, uuid: Uuid }, // A single link
+}
+
+/// A scheduled maintenance, the body of `POST /maintenance`
+///
+/// ```json
+/// {
+///     "target": {"kind": "link", "host": "10.0.0.1", "uuid": "14219539-..."},
+///     "start": "2026-03-01T01:00:00+01:00",
+///     "end": "2026-03-01T05:00:00+01:00",
+///     "reason": "Fiber repair MAD-BCN"
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MaintenanceWindow {
+    #[serde(default = "Uuid::new_v4")]
+    pub id: Uuid,
+    pub target: MaintenanceTarget,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>, // Exclusive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl MaintenanceWindow {
+    /// Returns whether the window is open at `at`
+    pub fn is_active(&self, at: DateTime<Local>) -> bool {
+        self.start <= at && at < self.end
+    }
+
+    /// Returns whether the window covers the object of `event` when it was emitted
+    pub fn covers(&self, event: &ChangeEvent) -> bool {
+        if !self.is_active(event.date) {
+            return false;
+        }
+        match &self.target {
+            MaintenanceTarget::Device { host } => *host == event.host,
+            MaintenanceTarget::Link { host, uuid } => {
+                *host == event.host && event.object == ObjectKind::Link && *uuid == event.uuid
+            }
+        }
+    }
+
+    /// Returns whether the window covers the device of `alert` when it was raised
+    ///
+    /// A link window only covers the topology changes of its link.
+    pub fn covers_alert(&self, alert: &Alert) -> bool {
+        match alert {
+            Alert::TopologyChange { event } => self.covers(event),
+            _ => {
+                self.is_active(alert.date())
+                    && matches!(&self.target, MaintenanceTarget::Device { host } if host == alert.host())
+            }
+        }
+    }
+}
+
+/// Maintenance windows defined by the operators
+///
+/// Events emitted during a window of their object are stored tagged `suppressed` (see
+/// [`crate::events::store::EventStore::append_scheduled`]) and not delivered to the
+/// notification channels, so planned work does not page anyone; so are the alerts
+/// raised during a window of their device. With
+/// [`MaintenanceSchedule::open`] the windows are saved to a JSON file on every change.
+#[derive(Default)]
+pub struct MaintenanceSchedule {
+    path: Option<PathBuf>,
+    windows: Vec<MaintenanceWindow>,
+}
+
+impl MaintenanceSchedule {
+    /// Creates an in-memory schedule
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the schedule persisted to `path`, loading the windows already defined
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let windows = persist::read_json(&path, "maintenance windows")?.unwrap_or_default();
+
+        Ok(MaintenanceSchedule {
+            path: Some(path),
+            windows,
+        })
+    }
+
+    /// Adds a window and returns its id
+    ///
+    /// # Returns
+    /// - `Ok(Uuid)`: The id of the window
+    /// - `Err(Error)`: If the window ends before it starts, or the schedule could not be saved
+    pub fn add(&mut self, window: MaintenanceWindow) -> Result<Uuid> {
+        if window.end <= window.start {
+            return Err(Error::Invalid(
+                "Invalid maintenance window: end must be after start".to_string(),
+            ));
+        }
+        let id = window.id;
+        self.windows.push(window);
+        self.save()?;
+        Ok(id)
+    }
+
+    /// Removes a window, ending the maintenance early or cancelling it
+    pub fn remove(&mut self, id: &Uuid) -> Result<()> {
+        let count = self.windows.len();
+        self.windows.retain(|window| window.id != *id);
+        if self.windows.len() == count {
+            return Err(Error::NotFound(format!(
+                "Not found maintenance window {}",
+                id
+            )));
+        }
+        self.save()
+    }
+
+    /// Returns every window, in the order they were added
+    pub fn list(&self) -> &[MaintenanceWindow] {
+        &self.windows
+    }
+
+    /// Returns the windows open at `at`
+    pub fn active(&self, at: DateTime<Local>) -> Vec<&MaintenanceWindow> {
+        self.windows
+            .iter()
+            .filter(|window| window.is_active(at))
+            .collect()
+    }
+
+    /// Returns whether `event` was emitted during a maintenance window of its object
+    pub fn suppresses(&self, event: &ChangeEvent) -> bool {
+        self.windows.iter().any(|window| window.covers(event))
+    }
+
+    /// Returns whether `alert` was raised during a maintenance window of its device
+    pub fn suppresses_alert(&self, alert: &Alert) -> bool {
+        self.windows.iter().any(|window| window.covers_alert(alert))
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            persist::write_json(path, &self.windows, "maintenance windows")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+use crate::Error; // Import custom error handling type `Error` from the crate
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::Value;
+
+// Import UUID handling utilities from the `uuid` crate
+use uuid::Uuid;
+
+/// Parses a mandatory UUID field of a device payload, failing with `message` when missing
+/// or invalid
+pub(crate) fn parse_uuid(value: &Value, field: &str, message: &str) -> Result<Uuid, Error> {
+    Uuid::parse_str(value.get(field).and_then(Value::as_str).unwrap_or_default())
+        .map_err(|_| Error::BadResponse(message.to_string()))
+}
+
+/// Extracts the first value of a TAPI `name` list
+pub(crate) fn name_from_value(value: &Value) -> Option<String> {
+    value
+        .get("name")
+        .and_then(Value::as_array)
+        .and_then(|names| names.first())
+        .and_then(|name| name.get("value"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
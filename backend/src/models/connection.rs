@@ -0,0 +1,197 @@
+use super::common::{name_from_value, parse_uuid}; // Import shared TAPI parsing helpers
+use crate::Error; // Import custom error handling type `Error` from the crate
+
+// Import necessary traits for serialization and deserialization
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::Value;
+
+// Import UUID handling utilities from the `uuid` crate
+use uuid::Uuid;
+
+/// Represents a TAPI connection: the cross-connections of a service at one layer
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Connection {
+    pub host: String,
+    pub uuid: Uuid,           // UUID of the connection
+    pub name: Option<String>, // Human readable name, if reported
+    pub routes: Vec<Route>,   // Paths of the connection through the topology
+    #[serde(rename(serialize = "lower-connection", deserialize = "lower-connection"))]
+    pub lower_connections: Vec<Uuid>, // Connections of the lower layer supporting this one
+}
+
+/// A route of a connection, as the ordered connection end points it goes through
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Route {
+    #[serde(rename(serialize = "local-id", deserialize = "local-id"))]
+    pub local_id: Option<String>,
+    #[serde(rename(
+        serialize = "connection-end-point",
+        deserialize = "connection-end-point"
+    ))]
+    pub connection_end_points: Vec<ConnectionEndPointRef>,
+}
+
+/// Reference to a connection end point, and the node edge point it belongs to
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConnectionEndPointRef {
+    #[serde(rename(serialize = "node-uuid", deserialize = "node-uuid"))]
+    pub node_uuid: Uuid,
+    #[serde(rename(
+        serialize = "node-edge-point-uuid",
+        deserialize = "node-edge-point-uuid"
+    ))]
+    pub node_edge_point_uuid: Uuid,
+    #[serde(rename(
+        serialize = "connection-end-point-uuid",
+        deserialize = "connection-end-point-uuid"
+    ))]
+    pub connection_end_point_uuid: Uuid,
+}
+
+/// Represents a TAPI connectivity service as reported by the device
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConnectivityService {
+    pub host: String,
+    pub uuid: Uuid,             // UUID of the service
+    pub name: Option<String>,   // Human readable name, if reported
+    pub connections: Vec<Uuid>, // Top connections realizing the service
+}
+
+/// Represents the `tapi-connectivity:connectivity-context` of a host: its services and
+/// the connections realizing them, collected for the impact analysis of its links
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConnectivityContext {
+    pub host: String,                       // Host the context was collected from
+    pub services: Vec<ConnectivityService>, // Connectivity services of the device
+    pub connections: Vec<Connection>,       // Connections of the device, of every layer
+}
+
+impl ConnectivityContext {
+    /// Creates a ConnectivityContext instance from a JSON `Value` and host
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the `connectivity-context` JSON `Value`, with or without
+    ///   the `tapi-connectivity:connectivity-context` wrapper
+    /// - `host`: The host the context was collected from
+    ///
+    /// # Returns
+    /// - `Ok(ConnectivityContext)`: If the deserialization is successful
+    /// - `Err(Error)`: If a service or connection has missing or invalid fields
+    pub fn from_value(value: &Value, host: &str) -> Result<Self, Error> {
+        // Unwrap the module-qualified container when the whole RESTCONF response is given
+        let value = value
+            .get("tapi-connectivity:connectivity-context")
+            .unwrap_or(value);
+
+        Ok(ConnectivityContext {
+            host: host.to_string(),
+            services: list(value, "connectivity-service")
+                .map(|service| ConnectivityService::from_value(service, host))
+                .collect::<Result<Vec<ConnectivityService>, Error>>()?,
+            connections: list(value, "connection")
+                .map(|connection| Connection::from_value(connection, host))
+                .collect::<Result<Vec<Connection>, Error>>()?,
+        })
+    }
+}
+
+impl Connection {
+    /// Creates a Connection instance from a JSON `Value` and host
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to deserialize from
+    /// - `host`: The host the connection was collected from
+    ///
+    /// # Returns
+    /// - `Ok(Connection)`: If the deserialization is successful
+    /// - `Err(Error)`: If required fields are missing or invalid
+    pub fn from_value(value: &Value, host: &str) -> Result<Self, Error> {
+        let uuid = parse_uuid(value, "uuid", "Not found connection uuid")?;
+
+        let mut routes = vec![];
+        for route in list(value, "route") {
+            let mut connection_end_points = vec![];
+            for end_point in list(route, "connection-end-point") {
+                connection_end_points.push(ConnectionEndPointRef {
+                    node_uuid: parse_uuid(end_point, "node-uuid", "Not found node uuid")?,
+                    node_edge_point_uuid: parse_uuid(
+                        end_point,
+                        "node-edge-point-uuid",
+                        "Not found node edge point uuid",
+                    )?,
+                    connection_end_point_uuid: parse_uuid(
+                        end_point,
+                        "connection-end-point-uuid",
+                        "Not found connection end point uuid",
+                    )?,
+                });
+            }
+            routes.push(Route {
+                local_id: route
+                    .get("local-id")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                connection_end_points,
+            });
+        }
+
+        let mut lower_connections = vec![];
+        for lower in list(value, "lower-connection") {
+            lower_connections.push(parse_uuid(
+                lower,
+                "connection-uuid",
+                "Not found lower connection uuid",
+            )?);
+        }
+
+        Ok(Connection {
+            host: host.to_string(),
+            uuid,
+            name: name_from_value(value),
+            routes,
+            lower_connections,
+        })
+    }
+}
+
+impl ConnectivityService {
+    /// Creates a ConnectivityService instance from a JSON `Value` and host
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to deserialize from
+    /// - `host`: The host the service was collected from
+    ///
+    /// # Returns
+    /// - `Ok(ConnectivityService)`: If the deserialization is successful
+    /// - `Err(Error)`: If required fields are missing or invalid
+    pub fn from_value(value: &Value, host: &str) -> Result<Self, Error> {
+        let uuid = parse_uuid(value, "uuid", "Not found connectivity service uuid")?;
+
+        let mut connections = vec![];
+        for connection in list(value, "connection") {
+            connections.push(parse_uuid(
+                connection,
+                "connection-uuid",
+                "Not found connection uuid",
+            )?);
+        }
+
+        Ok(ConnectivityService {
+            host: host.to_string(),
+            uuid,
+            name: name_from_value(value),
+            connections,
+        })
+    }
+}
+
+/// Returns the entries of an optional TAPI list
+fn list<'a>(value: &'a Value, field: &str) -> impl Iterator<Item = &'a Value> {
+    value
+        .get(field)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+}
@@ -0,0 +1,402 @@
+use super::device::FieldError; // Import the field errors answered as `VALIDATION_FAILED` details
+use super::node::OwnedNodeEdgePoint; // Import the endpoints services terminate on
+use super::topology::Topology; // Import the cached topology requests are checked against
+use crate::Error; // Import custom error handling type `Error` from the crate
+
+// Import serialization and deserialization traits from `serde`
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::{json, Value};
+
+// Import UUID handling utilities from the `uuid` crate
+use uuid::Uuid;
+
+/// RESTCONF path of the TAPI connectivity context, where services are created
+pub const CONNECTIVITY_CONTEXT_PATH: &str =
+    "/restconf/data/tapi-common:context/tapi-connectivity:connectivity-context";
+
+/// Layer protocols a service can be requested on
+pub const LAYER_PROTOCOLS: [&str; 4] = ["DSR", "ETH", "ODU", "PHOTONIC_MEDIA"];
+
+/// Units of a requested capacity
+pub const CAPACITY_UNITS: [&str; 10] = [
+    "TB", "TBPS", "GB", "GBPS", "MB", "MBPS", "KB", "KBPS", "GHz", "MHz",
+];
+
+/// Body of `POST /devices/{host}/connectivity-services`
+///
+/// ```json
+/// {
+///     "name": "MAD-BCN 100G",
+///     "layer_protocol_name": "DSR",
+///     "layer_protocol_qualifier": "DIGITAL_SIGNAL_TYPE_100_GigE",
+///     "end_points": [
+///         {"service_interface_point": "5a1d4c0e-..."},
+///         {"service_interface_point": "9e0f2b7a-..."}
+///     ],
+///     "requested_capacity": {"value": 100, "unit": "GBPS"}
+/// }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConnectivityServiceRequest {
+    #[serde(default = "Uuid::new_v4")]
+    pub uuid: Uuid, // Generated when the client does not choose it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub layer_protocol_name: String, // One of `LAYER_PROTOCOLS`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer_protocol_qualifier: Option<String>, // e.g. `DIGITAL_SIGNAL_TYPE_100_GigE`
+    pub end_points: Vec<ServiceEndPoint>, // At least two, on distinct SIPs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub requested_capacity: Option<Capacity>,
+}
+
+/// An end point of a requested service
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ServiceEndPoint {
+    pub service_interface_point: Uuid, // SIP the service terminates on
+}
+
+/// Capacity requested for a service
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Capacity {
+    pub value: u64,
+    pub unit: String, // One of `CAPACITY_UNITS`
+}
+
+impl Capacity {
+    /// Returns the capacity in Gbps, if it is a bit rate
+    pub fn gbps(&self) -> Option<f64> {
+        let factor = match self.unit.as_str() {
+            "TBPS" => 1000.0,
+            "GBPS" => 1.0,
+            "MBPS" => 0.001,
+            "KBPS" => 0.000001,
+            _ => return None,
+        };
+        Some(self.value as f64 * factor)
+    }
+}
+
+/// Strips the YANG module prefix of an identity, as in `tapi-dsr:DIGITAL_SIGNAL_TYPE_GigE`
+pub(crate) fn bare(identity: &str) -> &str {
+    identity.rsplit(':').next().unwrap_or(identity)
+}
+
+/// Returns the layer protocol of a `layer-protocol-qualifier`, if it is a known one
+pub(crate) fn qualifier_layer(qualifier: &str) -> Option<&'static str> {
+    [
+        ("DIGITAL_SIGNAL_TYPE_", "DSR"),
+        ("ETH_", "ETH"),
+        ("ODU_TYPE_", "ODU"),
+        ("PHOTONIC_LAYER_QUALIFIER_", "PHOTONIC_MEDIA"),
+    ]
+    .into_iter()
+    .find(|(prefix, _)| qualifier.starts_with(prefix))
+    .map(|(_, layer)| layer)
+}
+
+/// Returns the rate of a node edge point in Gbps, from its qualifier
+fn rate_gbps(node_edge_point: &OwnedNodeEdgePoint) -> Option<f64> {
+    match bare(node_edge_point.layer_protocol_qualifier.as_deref()?) {
+        "DIGITAL_SIGNAL_TYPE_GigE" => Some(1.0),
+        "DIGITAL_SIGNAL_TYPE_10_GigE_LAN" | "DIGITAL_SIGNAL_TYPE_10_GigE_WAN" => Some(10.0),
+        "DIGITAL_SIGNAL_TYPE_40_GigE" => Some(40.0),
+        "DIGITAL_SIGNAL_TYPE_100_GigE" => Some(100.0),
+        "DIGITAL_SIGNAL_TYPE_400_GigE" => Some(400.0),
+        "ODU_TYPE_ODU0" => Some(1.25),
+        "ODU_TYPE_ODU1" => Some(2.5),
+        "ODU_TYPE_ODU2" | "ODU_TYPE_ODU2E" => Some(10.0),
+        "ODU_TYPE_ODU3" => Some(40.0),
+        "ODU_TYPE_ODU4" => Some(100.0),
+        _ => None,
+    }
+}
+
+/// Answer of `POST /devices/{host}/connectivity-services`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CreatedService {
+    pub uuid: Uuid,
+}
+
+/// Answer of `POST /devices/{host}/connectivity-services:validate`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DryRun {
+    pub valid: bool,
+    pub violations: Vec<FieldError>, // Empty if the device would be asked to create the service
+}
+
+/// TAPI `administrative-state` of a service
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AdministrativeState {
+    Locked,   // Administratively disabled, traffic is not carried
+    Unlocked, // In service
+}
+
+/// Body of `PATCH /devices/{host}/connectivity-services/{uuid}`
+///
+/// ```json
+/// {"administrative_state": "LOCKED"}
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ServiceStateChange {
+    pub administrative_state: AdministrativeState,
+}
+
+/// Returns the RESTCONF path of the connectivity service `uuid`
+pub fn service_path(uuid: &Uuid) -> String {
+    format!(
+        "{}/connectivity-service={}",
+        CONNECTIVITY_CONTEXT_PATH, uuid
+    )
+}
+
+impl ServiceStateChange {
+    /// Creates a ServiceStateChange instance from a request body
+    ///
+    /// # Returns
+    /// - `Ok(ServiceStateChange)`: If the body holds a known administrative state
+    /// - `Err(Error)`: Otherwise
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        serde_json::from_value(value.clone())
+            .map_err(|_| Error::Invalid("Invalid administrative state".to_string()))
+    }
+
+    /// Returns the `tapi-connectivity:connectivity-service` payload merged into service `uuid`
+    pub fn to_tapi(&self, uuid: &Uuid) -> Value {
+        json!({
+            "tapi-connectivity:connectivity-service": [{
+                "uuid": uuid,
+                "administrative-state": self.administrative_state
+            }]
+        })
+    }
+}
+
+impl ConnectivityServiceRequest {
+    /// Checks every field of a provisioning request
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the request body
+    ///
+    /// # Returns
+    /// - The list of problems, empty if the request is valid
+    pub fn validate(value: &Value) -> Vec<FieldError> {
+        let mut errors = vec![];
+
+        if let Some(uuid) = value.get("uuid") {
+            if uuid
+                .as_str()
+                .and_then(|uuid| Uuid::parse_str(uuid).ok())
+                .is_none()
+            {
+                errors.push(FieldError::new("uuid", "Uuid must be a valid UUID"));
+            }
+        }
+
+        match value.get("layer_protocol_name").map(Value::as_str) {
+            None => errors.push(FieldError::new(
+                "layer_protocol_name",
+                "Layer protocol name not found",
+            )),
+            Some(name) if !name.is_some_and(|name| LAYER_PROTOCOLS.contains(&name)) => {
+                errors.push(FieldError::new(
+                    "layer_protocol_name",
+                    "Layer protocol name must be DSR, ETH, ODU or PHOTONIC_MEDIA",
+                ))
+            }
+            Some(_) => {}
+        }
+
+        match value.get("end_points").map(Value::as_array) {
+            None => errors.push(FieldError::new("end_points", "End points not found")),
+            Some(None) => errors.push(FieldError::new("end_points", "End points must be a list")),
+            Some(Some(end_points)) => {
+                if end_points.len() < 2 {
+                    errors.push(FieldError::new(
+                        "end_points",
+                        "A service needs at least two end points",
+                    ));
+                }
+                let mut seen = vec![];
+                for (index, end_point) in end_points.iter().enumerate() {
+                    let field = format!("end_points.{}.service_interface_point", index);
+                    match end_point
+                        .get("service_interface_point")
+                        .and_then(Value::as_str)
+                        .and_then(|uuid| Uuid::parse_str(uuid).ok())
+                    {
+                        None => errors.push(FieldError::new(
+                            &field,
+                            "Service interface point must be a valid UUID",
+                        )),
+                        Some(uuid) if seen.contains(&uuid) => errors.push(FieldError::new(
+                            &field,
+                            "Service interface point is used by another end point",
+                        )),
+                        Some(uuid) => seen.push(uuid),
+                    }
+                }
+            }
+        }
+
+        if let Some(capacity) = value.get("requested_capacity") {
+            if capacity
+                .get("value")
+                .and_then(Value::as_u64)
+                .is_none_or(|value| value == 0)
+            {
+                errors.push(FieldError::new(
+                    "requested_capacity.value",
+                    "Capacity must be a positive integer",
+                ));
+            }
+            if !capacity
+                .get("unit")
+                .and_then(Value::as_str)
+                .is_some_and(|unit| CAPACITY_UNITS.contains(&unit))
+            {
+                errors.push(FieldError::new(
+                    "requested_capacity.unit",
+                    "Capacity unit not valid",
+                ));
+            }
+        }
+
+        errors
+    }
+
+    /// Creates a ConnectivityServiceRequest instance from a request body
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to deserialize from
+    ///
+    /// # Returns
+    /// - `Ok(ConnectivityServiceRequest)`: If the request is valid
+    /// - `Err(Error)`: Listing the problems found by [`ConnectivityServiceRequest::validate`]
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        let errors = Self::validate(value);
+        if !errors.is_empty() {
+            let problems: Vec<String> = errors
+                .iter()
+                .map(|error| format!("{}: {}", error.field, error.message))
+                .collect();
+            return Err(Error::Invalid(format!(
+                "Invalid connectivity service request: {}",
+                problems.join("; ")
+            )));
+        }
+
+        serde_json::from_value(value.clone())
+            .map_err(|err| Error::Invalid(format!("Invalid connectivity service request: {}", err)))
+    }
+
+    /// Checks the request against the topology of the device, without reaching it
+    ///
+    /// Every end point must be a SIP mapped by a node edge point of the topology, on the
+    /// requested layer (as told by the qualifier of the node edge point), and the
+    /// requested capacity must not exceed the rate of the end points.
+    ///
+    /// # Returns
+    /// - The list of violations, empty if the request fits the topology
+    pub fn check_topology(&self, topology: &Topology) -> Vec<FieldError> {
+        let mut errors = vec![];
+        let requested_gbps = self.requested_capacity.as_ref().and_then(Capacity::gbps);
+
+        for (index, end_point) in self.end_points.iter().enumerate() {
+            let field = format!("end_points.{}.service_interface_point", index);
+            let Some(node_edge_point) = topology
+                .nodes
+                .iter()
+                .flat_map(|node| &node.owned_node_edge_points)
+                .find(|node_edge_point| {
+                    node_edge_point
+                        .mapped_service_interface_points
+                        .contains(&end_point.service_interface_point)
+                })
+            else {
+                errors.push(FieldError::new(
+                    &field,
+                    "Service interface point not found in the topology",
+                ));
+                continue;
+            };
+
+            let Some(qualifier) = node_edge_point
+                .layer_protocol_qualifier
+                .as_deref()
+                .map(bare)
+            else {
+                continue;
+            };
+            if qualifier_layer(qualifier).is_some_and(|layer| layer != self.layer_protocol_name) {
+                errors.push(FieldError::new(
+                    &field,
+                    &format!(
+                        "Service interface point is on a {} end point, not {}",
+                        qualifier, self.layer_protocol_name
+                    ),
+                ));
+            } else if self
+                .layer_protocol_qualifier
+                .as_deref()
+                .is_some_and(|requested| bare(requested) != qualifier)
+            {
+                errors.push(FieldError::new(
+                    &field,
+                    &format!("Service interface point is on a {} end point", qualifier),
+                ));
+            }
+
+            if let (Some(requested), Some(rate)) = (requested_gbps, rate_gbps(node_edge_point)) {
+                if requested > rate {
+                    errors.push(FieldError::new(
+                        "requested_capacity.value",
+                        &format!("Capacity exceeds the {} Gbps of end point {}", rate, index),
+                    ));
+                }
+            }
+        }
+
+        errors
+    }
+
+    /// Returns the `tapi-connectivity:connectivity-service` payload creating the service
+    pub fn to_tapi(&self) -> Value {
+        let end_points: Vec<Value> = self
+            .end_points
+            .iter()
+            .enumerate()
+            .map(|(index, end_point)| {
+                let mut tapi = json!({
+                    "local-id": (index + 1).to_string(),
+                    "layer-protocol-name": self.layer_protocol_name,
+                    "service-interface-point": {
+                        "service-interface-point-uuid": end_point.service_interface_point
+                    }
+                });
+                if let Some(qualifier) = &self.layer_protocol_qualifier {
+                    tapi["layer-protocol-qualifier"] = json!(qualifier);
+                }
+                tapi
+            })
+            .collect();
+
+        let mut service = json!({
+            "uuid": self.uuid,
+            "end-point": end_points,
+        });
+        if let Some(name) = &self.name {
+            service["name"] = json!([{"value-name": "SERVICE_NAME", "value": name}]);
+        }
+        if let Some(capacity) = &self.requested_capacity {
+            service["requested-capacity"] = json!({
+                "total-size": {"value": capacity.value, "unit": capacity.unit}
+            });
+        }
+
+        json!({ "tapi-connectivity:connectivity-service": [service] })
+    }
+}
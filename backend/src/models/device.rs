@@ -1,15 +1,37 @@
+use super::address::{Host, Port, ProxyUrl};
+use crate::tenant::default_tenant; // Import the tenant of devices registered without one
 use crate::Error; // Import custom error handling type `Error` from the crate
 
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
 // Import necessary traits for serialization and deserialization
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 /// Represents a Device with host, port, and authentication type
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Device {
-    pub host: String,      // Host name or IP address of the device
-    pub port: Option<i64>, // Optional port number
-    pub auth: Auth,        // Authentication method (enum)
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String, // Customer owning the device, resolved from the auth token
+    pub host: Host, // Host name or IP address of the device
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<Port>, // Optional port number
+    pub auth: Auth, // Authentication method (enum)
+    #[serde(default)]
+    pub tags: Vec<String>, // Free-form labels, e.g. `production`
+    #[serde(default)]
+    pub metadata: HashMap<String, String>, // Key-value annotations: site, region, vendor, role...
+    #[serde(default = "enabled_default")]
+    pub enabled: bool, // Whether the device is polled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance_until: Option<DateTime<Local>>, // Alerts are suppressed until this date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<ProxyUrl>, // Proxy the device is reached through, e.g. in a DMZ
+}
+
+fn enabled_default() -> bool {
+    true
 }
 
 impl Device {
@@ -22,37 +44,376 @@ impl Device {
     /// - `Ok(Device)`: If the deserialization is successful
     /// - `Err(Error)`: If required fields are missing or invalid
     pub fn from_value(value: &Value) -> Result<Self, Error> {
-        // Extract the host field from the JSON
-        let host_value = value
+        // Extract and validate the host field from the JSON
+        let host_value: Host = value
             .get("host")
             .and_then(Value::as_str)
-            .ok_or_else(|| Error::from("Host not found"))?;
+            .ok_or_else(|| Error::Invalid("Host not found".to_string()))?
+            .parse()?;
 
-        // Extract the optional port field from the JSON
-        let port_value = value.get("port").and_then(Value::as_i64);
+        // Extract and validate the optional port field from the JSON
+        let port_value = match value.get("port") {
+            Some(Value::Null) | None => None,
+            Some(port) => {
+                Some(Port::try_from(port.as_i64().ok_or_else(|| {
+                    Error::Invalid(format!("Invalid port {}", port))
+                })?)?)
+            }
+        };
 
         // Extract and deserialize the authentication field (which is of enum type Auth)
         let auth_value = Auth::from_value(
             value
                 .get("auth")
-                .ok_or_else(|| Error::from("Auth body not found"))?,
+                .ok_or_else(|| Error::Invalid("Auth body not found".to_string()))?,
         )?;
 
+        // Extract the optional tenant, devices belong to the default tenant otherwise
+        let tenant_id_value = match value.get("tenant_id") {
+            Some(tenant_id) => tenant_id
+                .as_str()
+                .filter(|tenant_id| !tenant_id.trim().is_empty())
+                .ok_or_else(|| Error::Invalid("Invalid device tenant".to_string()))?
+                .to_string(),
+            None => default_tenant(),
+        };
+
+        // Extract the optional tags, which must all be strings
+        let tags_value = match value.get("tags") {
+            Some(tags) => serde_json::from_value(tags.clone())
+                .map_err(|_| Error::Invalid("Invalid device tags".to_string()))?,
+            None => vec![],
+        };
+
+        // Extract the optional metadata, which must map strings to strings
+        let metadata_value = match value.get("metadata") {
+            Some(metadata) => serde_json::from_value(metadata.clone())
+                .map_err(|_| Error::Invalid("Invalid device metadata".to_string()))?,
+            None => HashMap::new(),
+        };
+
+        // Extract the optional polling flag, devices are polled by default
+        let enabled_value = match value.get("enabled") {
+            Some(enabled) => enabled
+                .as_bool()
+                .ok_or_else(|| Error::Invalid("Invalid device enabled flag".to_string()))?,
+            None => true,
+        };
+
+        // Extract the optional end of the maintenance window (RFC 3339)
+        let maintenance_until_value = match value.get("maintenance_until") {
+            Some(Value::Null) | None => None,
+            Some(date) => Some(
+                serde_json::from_value(date.clone())
+                    .map_err(|_| Error::Invalid("Invalid device maintenance date".to_string()))?,
+            ),
+        };
+
+        // Extract the optional proxy, the default proxy of the config is used otherwise
+        let proxy_url_value = match value.get("proxy_url") {
+            Some(Value::Null) | None => None,
+            Some(proxy_url) => Some(
+                proxy_url
+                    .as_str()
+                    .ok_or_else(|| {
+                        Error::Invalid("Invalid proxy URL, expected a string".to_string())
+                    })?
+                    .parse()?,
+            ),
+        };
+
         // Return a Device instance
         Ok(Device {
-            host: host_value.to_string(),
+            tenant_id: tenant_id_value,
+            host: host_value,
             port: port_value,
             auth: auth_value,
+            tags: tags_value,
+            metadata: metadata_value,
+            enabled: enabled_value,
+            maintenance_until: maintenance_until_value,
+            proxy_url: proxy_url_value,
         })
     }
+
+    /// Returns whether the device is under maintenance at `now`
+    pub fn in_maintenance(&self, now: DateTime<Local>) -> bool {
+        self.maintenance_until.is_some_and(|until| now < until)
+    }
+
+    /// Returns whether change and health alerts of the device must be sent at `now`
+    ///
+    /// Alerts are suppressed while the device is disabled or under maintenance.
+    pub fn alerts_enabled(&self, now: DateTime<Local>) -> bool {
+        self.enabled && !self.in_maintenance(now)
+    }
+
+    /// Returns whether `other` is the same device registered under another name
+    ///
+    /// Devices of the same tenant and port are the same when their hosts are equal,
+    /// ignoring case, or resolve to a common address (e.g. a hostname and its IP).
+    pub fn same_device(&self, other: &Device) -> bool {
+        if self.tenant_id != other.tenant_id || self.port != other.port {
+            return false;
+        }
+        if self.host.as_str().eq_ignore_ascii_case(other.host.as_str()) {
+            return true;
+        }
+        let addresses = self.host.resolve();
+        other
+            .host
+            .resolve()
+            .iter()
+            .any(|address| addresses.contains(address))
+    }
+
+    /// Merges the tags and metadata of a duplicate registration into the device
+    ///
+    /// New tags are appended and new metadata keys added; existing metadata values
+    /// are kept, so a merge never changes what the device is already known as.
+    pub fn merge(&mut self, other: &Device) {
+        for tag in &other.tags {
+            if !self.tags.contains(tag) {
+                self.tags.push(tag.clone());
+            }
+        }
+        for (key, value) in &other.metadata {
+            self.metadata
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+}
+
+/// Outcome of [`register`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registration {
+    Added(usize),    // Index of the new device
+    Merged(usize),   // Index of the device the registration was merged into
+    Conflict(usize), // Index of the device already registered, nothing changed
+}
+
+/// Adds `device` to `devices` unless it duplicates a registered device
+///
+/// A duplicate (see [`Device::same_device`]) is merged into the registered device when
+/// `merge` is set, and reported as a conflict otherwise: `cli device add` refuses it,
+/// printing the existing entry, unless run with `--merge true`.
+pub fn register(devices: &mut Vec<Device>, device: Device, merge: bool) -> Registration {
+    match devices.iter().position(|known| known.same_device(&device)) {
+        Some(index) if merge => {
+            devices[index].merge(&device);
+            Registration::Merged(index)
+        }
+        Some(index) => Registration::Conflict(index),
+        None => {
+            devices.push(device);
+            Registration::Added(devices.len() - 1)
+        }
+    }
+}
+
+/// A problem found in a field of a device registration
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,   // Path of the field, e.g. `auth.username`
+    pub message: String, // What is wrong with it
+}
+
+impl FieldError {
+    pub(crate) fn new(field: &str, message: &str) -> Self {
+        FieldError {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Device {
+    /// Checks every field of a device registration
+    ///
+    /// Unlike [`Device::from_value`], which stops at the first missing field, this
+    /// collects every problem so API clients can show all form errors at once (the
+    /// `details` of a `VALIDATION_FAILED` answer).
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to validate
+    ///
+    /// # Returns
+    /// - The list of problems, empty if the registration is valid
+    pub fn validate(value: &Value) -> Vec<FieldError> {
+        let mut errors = vec![];
+
+        match value.get("host").map(Value::as_str) {
+            None => errors.push(FieldError::new("host", "Host not found")),
+            Some(None) => errors.push(FieldError::new("host", "Host must be a string")),
+            Some(Some(host)) if host.parse::<Host>().is_err() => errors.push(FieldError::new(
+                "host",
+                "Host must be an IP address or a hostname",
+            )),
+            Some(Some(_)) => {}
+        }
+
+        if let Some(port) = value.get("port").filter(|port| !port.is_null()) {
+            if port
+                .as_i64()
+                .and_then(|port| Port::try_from(port).ok())
+                .is_none()
+            {
+                errors.push(FieldError::new("port", "Port must be between 1 and 65535"));
+            }
+        }
+
+        match value.get("auth").map(Value::as_object) {
+            None => errors.push(FieldError::new("auth", "Auth body not found")),
+            Some(None) => errors.push(FieldError::new("auth", "Auth body not valid")),
+            Some(Some(auth)) => {
+                let required: &[&str] = if auth.contains_key("grant_type") {
+                    &["username", "password", "grant_type", "auth_url"]
+                } else if auth.contains_key("auth_body") {
+                    &["auth_url"]
+                } else if auth.contains_key("key") {
+                    &["key"]
+                } else if auth.contains_key("token") {
+                    &["token"]
+                } else if auth.contains_key("username") || auth.contains_key("password") {
+                    &["username", "password"]
+                } else {
+                    errors.push(FieldError::new(
+                        "auth",
+                        "Not recognizable authentication type",
+                    ));
+                    &[]
+                };
+
+                for field in required {
+                    let message = match auth.get(*field).map(Value::as_str) {
+                        None => "Field not found",
+                        Some(None) => "Field must be a string",
+                        Some(Some(text)) if text.trim().is_empty() && *field != "password" => {
+                            "Field must not be empty"
+                        }
+                        Some(Some(username))
+                            if *field == "username" && username.starts_with('-') =>
+                        {
+                            // It would be read as an option by `ssh` (NETCONF)
+                            "Username must not start with '-'"
+                        }
+                        Some(Some(url)) if *field == "auth_url" && !valid_auth_url(url) => {
+                            "Authentication URL must be an absolute path or an http(s) URL"
+                        }
+                        Some(Some(_)) => continue,
+                    };
+                    errors.push(FieldError::new(&format!("auth.{}", field), message));
+                }
+            }
+        }
+
+        if let Some(tenant_id) = value.get("tenant_id") {
+            if tenant_id
+                .as_str()
+                .is_none_or(|tenant_id| tenant_id.trim().is_empty())
+            {
+                errors.push(FieldError::new("tenant_id", "Invalid device tenant"));
+            }
+        }
+        match value.get("proxy_url") {
+            Some(Value::Null) | None => {}
+            Some(Value::String(proxy_url)) => {
+                if let Err(err) = proxy_url.parse::<ProxyUrl>() {
+                    errors.push(FieldError::new("proxy_url", &err.to_string()));
+                }
+            }
+            Some(_) => errors.push(FieldError::new("proxy_url", "Proxy URL must be a string")),
+        }
+        if let Some(tags) = value.get("tags") {
+            if serde_json::from_value::<Vec<String>>(tags.clone()).is_err() {
+                errors.push(FieldError::new("tags", "Invalid device tags"));
+            }
+        }
+        if let Some(metadata) = value.get("metadata") {
+            if serde_json::from_value::<HashMap<String, String>>(metadata.clone()).is_err() {
+                errors.push(FieldError::new("metadata", "Invalid device metadata"));
+            }
+        }
+
+        errors
+    }
+
+    /// Validates a device registration and creates the Device if it has no problems
+    ///
+    /// # Returns
+    /// - `Ok(Device)`: If every field is valid
+    /// - `Err(Vec<FieldError>)`: Every problem found
+    pub fn from_value_validated(value: &Value) -> Result<Self, Vec<FieldError>> {
+        let errors = Self::validate(value);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Self::from_value(value).map_err(|err| vec![FieldError::new("", &err.to_string())])
+    }
+}
+
+/// Returns whether `url` is a path on the device (`/oauth/token`) or an absolute http(s) URL
+fn valid_auth_url(url: &str) -> bool {
+    if url.chars().any(char::is_whitespace) {
+        return false;
+    }
+    if url.starts_with('/') {
+        return true;
+    }
+    reqwest::Url::parse(url)
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some())
+}
+
+/// Filter on device tags and metadata used by the device list
+///
+/// A device matches when it has every tag and every metadata entry of the filter.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct DeviceFilter {
+    #[serde(default)]
+    pub tags: Vec<String>, // Tags the device must have
+    #[serde(default)]
+    pub metadata: HashMap<String, String>, // Metadata entries the device must have
+}
+
+impl DeviceFilter {
+    /// Returns whether `device` matches the filter
+    pub fn matches(&self, device: &Device) -> bool {
+        self.tags.iter().all(|tag| device.tags.contains(tag))
+            && self
+                .metadata
+                .iter()
+                .all(|(key, value)| device.metadata.get(key) == Some(value))
+    }
+
+    /// Returns the devices matching the filter
+    pub fn apply<'a>(&self, devices: &'a [Device]) -> Vec<&'a Device> {
+        devices
+            .iter()
+            .filter(|device| self.matches(device))
+            .collect()
+    }
+
+    /// Returns the devices of `tenant_id` matching the filter
+    ///
+    /// Requests on behalf of a tenant must go through this, so they never see the
+    /// devices of another customer.
+    pub fn apply_for_tenant<'a>(&self, tenant_id: &str, devices: &'a [Device]) -> Vec<&'a Device> {
+        devices
+            .iter()
+            .filter(|device| device.tenant_id == tenant_id && self.matches(device))
+            .collect()
+    }
 }
 
 /// Enum representing the different authentication methods
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum Auth {
-    BasicAuth(BasicAuth), // Basic Authentication
-    Oauth2(Oauth2),       // OAuth2 Authentication
-    Custom(CustomAuth),   // Custom Authentication
+    BasicAuth(BasicAuth),     // Basic Authentication
+    Oauth2(Oauth2),           // OAuth2 Authentication
+    Custom(CustomAuth),       // Custom Authentication
+    ApiKey(ApiKey),           // Static key sent in a header, e.g. `X-API-Key`
+    BearerToken(BearerToken), // Static bearer token
 }
 
 impl Auth {
@@ -68,30 +429,40 @@ impl Auth {
         // Extract the object (hash map) from the JSON value to inspect the fields
         let value_object = value
             .as_object()
-            .ok_or_else(|| Error::from("Auth body not valid"))?;
+            .ok_or_else(|| Error::Invalid("Auth body not valid".to_string()))?;
 
         // Determine the correct Auth variant based on the fields present in the object
         if value_object.contains_key("grant_type") {
             // OAuth2 authentication
-            let auth = Oauth2::from_value(&value)?;
+            let auth = Oauth2::from_value(value)?;
             Ok(Auth::Oauth2(auth))
         } else if value_object.contains_key("auth_body") {
             // Custom authentication
-            let auth = CustomAuth::from_value(&value)?;
+            let auth = CustomAuth::from_value(value)?;
             Ok(Auth::Custom(auth))
+        } else if value_object.contains_key("key") {
+            // API key authentication
+            let auth = ApiKey::from_value(value)?;
+            Ok(Auth::ApiKey(auth))
+        } else if value_object.contains_key("token") {
+            // Static bearer token authentication
+            let auth = BearerToken::from_value(value)?;
+            Ok(Auth::BearerToken(auth))
         } else if value_object.contains_key("username") && value_object.contains_key("password") {
             // Basic authentication
-            let auth = BasicAuth::from_value(&value)?;
+            let auth = BasicAuth::from_value(value)?;
             Ok(Auth::BasicAuth(auth))
         } else {
             // If no recognizable fields, return an error
-            Err(Error::from("Not recognizable authentication type"))
+            Err(Error::Invalid(
+                "Not recognizable authentication type".to_string(),
+            ))
         }
     }
 }
 
 /// Represents Basic Authentication with username and password
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct BasicAuth {
     pub username: String, // Username for authentication
     pub password: String, // Password for authentication
@@ -110,11 +481,15 @@ impl BasicAuth {
         let username_value = value
             .get("username")
             .and_then(Value::as_str)
-            .ok_or_else(|| Error::from("Username for Basic authentication not found"))?;
+            .ok_or_else(|| {
+                Error::Invalid("Username for Basic authentication not found".to_string())
+            })?;
         let password_value = value
             .get("password")
             .and_then(Value::as_str)
-            .ok_or_else(|| Error::from("Password for Basic authentication not found"))?;
+            .ok_or_else(|| {
+                Error::Invalid("Password for Basic authentication not found".to_string())
+            })?;
 
         Ok(BasicAuth {
             username: username_value.to_string(),
@@ -124,7 +499,7 @@ impl BasicAuth {
 }
 
 /// Represents OAuth2 Authentication with additional fields for grant type and authentication URL
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Oauth2 {
     pub username: String,   // Username for OAuth2 authentication
     pub password: String,   // Password for OAuth2 authentication
@@ -145,19 +520,28 @@ impl Oauth2 {
         let username_value = value
             .get("username")
             .and_then(Value::as_str)
-            .ok_or_else(|| Error::from("Username for OAuth2 authentication not found"))?;
+            .ok_or_else(|| {
+                Error::Invalid("Username for OAuth2 authentication not found".to_string())
+            })?;
         let password_value = value
             .get("password")
             .and_then(Value::as_str)
-            .ok_or_else(|| Error::from("Password for OAuth2 authentication not found"))?;
-        let grant_type_value = value
-            .get("grant_type")
-            .and_then(Value::as_str)
-            .ok_or_else(|| Error::from("Grant type for OAuth2 authentication not found"))?;
+            .ok_or_else(|| {
+                Error::Invalid("Password for OAuth2 authentication not found".to_string())
+            })?;
+        let grant_type_value =
+            value
+                .get("grant_type")
+                .and_then(Value::as_str)
+                .ok_or_else(|| {
+                    Error::Invalid("Grant type for OAuth2 authentication not found".to_string())
+                })?;
         let auth_url_value = value
             .get("auth_url")
             .and_then(Value::as_str)
-            .ok_or_else(|| Error::from("Authentication URL for OAuth2 authentication not found"))?;
+            .ok_or_else(|| {
+                Error::Invalid("Authentication URL for OAuth2 authentication not found".to_string())
+            })?;
 
         Ok(Oauth2 {
             username: username_value.to_string(),
@@ -169,10 +553,14 @@ impl Oauth2 {
 }
 
 /// Represents Custom Authentication with an arbitrary body and authentication URL
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct CustomAuth {
     pub auth_body: Value, // A JSON object containing custom authentication data
     pub auth_url: String, // URL for custom authentication
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_pointer: Option<String>, // JSON pointer to the token in the login answer, e.g. `/data/token`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_header: Option<String>, // Header carrying the token (`Authorization: Bearer` if unset)
 }
 
 impl CustomAuth {
@@ -186,16 +574,91 @@ impl CustomAuth {
     /// - `Err(Error)`: If required fields are missing
     pub fn from_value(value: &Value) -> Result<CustomAuth, Error> {
         let auth_body_value = value.get("auth_body").ok_or_else(|| {
-            Error::from("Authentication body for Custom authentication not found")
+            Error::Invalid("Authentication body for Custom authentication not found".to_string())
         })?;
         let auth_url_value = value
             .get("auth_url")
             .and_then(Value::as_str)
-            .ok_or_else(|| Error::from("Authentication URL for Custom authentication not found"))?;
+            .ok_or_else(|| {
+                Error::Invalid("Authentication URL for Custom authentication not found".to_string())
+            })?;
+
+        // Without a token pointer, the session cookies of the login answer are used
+        let token_pointer_value = value
+            .get("token_pointer")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let token_header_value = value
+            .get("token_header")
+            .and_then(Value::as_str)
+            .map(str::to_string);
 
         Ok(CustomAuth {
             auth_body: auth_body_value.clone(),
             auth_url: auth_url_value.to_string(),
+            token_pointer: token_pointer_value,
+            token_header: token_header_value,
+        })
+    }
+}
+
+/// Represents API key authentication: a static key sent in a request header
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct ApiKey {
+    pub header_name: String, // Header carrying the key (defaults to `X-API-Key`)
+    pub key: String,         // The API key
+}
+
+impl ApiKey {
+    /// Header used when the device does not name one
+    pub const DEFAULT_HEADER: &'static str = "X-API-Key";
+
+    /// Creates an ApiKey instance from a JSON `Value`
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to deserialize from
+    ///
+    /// # Returns
+    /// - `Ok(ApiKey)`: If deserialization is successful
+    /// - `Err(Error)`: If the key is missing
+    pub fn from_value(value: &Value) -> Result<ApiKey, Error> {
+        let header_name_value = value
+            .get("header_name")
+            .and_then(Value::as_str)
+            .unwrap_or(Self::DEFAULT_HEADER);
+        let key_value = value.get("key").and_then(Value::as_str).ok_or_else(|| {
+            Error::Invalid("Key for API key authentication not found".to_string())
+        })?;
+
+        Ok(ApiKey {
+            header_name: header_name_value.to_string(),
+            key: key_value.to_string(),
+        })
+    }
+}
+
+/// Represents authentication with a static bearer token
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct BearerToken {
+    pub token: String, // Token sent as `Authorization: Bearer <token>`
+}
+
+impl BearerToken {
+    /// Creates a BearerToken instance from a JSON `Value`
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to deserialize from
+    ///
+    /// # Returns
+    /// - `Ok(BearerToken)`: If deserialization is successful
+    /// - `Err(Error)`: If the token is missing
+    pub fn from_value(value: &Value) -> Result<BearerToken, Error> {
+        let token_value = value.get("token").and_then(Value::as_str).ok_or_else(|| {
+            Error::Invalid("Token for Bearer authentication not found".to_string())
+        })?;
+
+        Ok(BearerToken {
+            token: token_value.to_string(),
         })
     }
 }
@@ -0,0 +1,35 @@
+use crate::Error; // Import custom error handling type `Error` from the crate
+
+// Import necessary traits for serialization and deserialization
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::Value;
+
+/// TAPI `direction` of a link, whether traffic flows both ways or from the first
+/// endpoint to the last
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Direction {
+    Bidirectional,      // A single link carries both directions
+    Unidirectional,     // Paired with a link in the opposite direction
+    UndefinedOrUnknown, // Not known to the controller
+}
+
+impl Direction {
+    /// Parses the optional `direction` field of a TAPI object
+    ///
+    /// # Returns
+    /// - `Ok(Some(Direction))`: If the field is present and valid
+    /// - `Ok(None)`: If the object does not report a direction
+    /// - `Err(Error)`: If the field holds an unknown value
+    pub fn from_value(value: &Value) -> Result<Option<Self>, Error> {
+        value
+            .get("direction")
+            .map(|direction| {
+                Direction::deserialize(direction)
+                    .map_err(|_| Error::BadResponse("Invalid direction".to_string()))
+            })
+            .transpose()
+    }
+}
@@ -0,0 +1,254 @@
+use super::common::{name_from_value, parse_uuid}; // Import shared TAPI parsing helpers
+use crate::Error; // Import custom error handling type `Error` from the crate
+
+// Import necessary traits for serialization and deserialization
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::Value;
+
+// Import UUID handling utilities from the `uuid` crate
+use uuid::Uuid;
+
+/// RESTCONF path of the TAPI physical context holding the equipment inventory
+pub const PHYSICAL_CONTEXT_PATH: &str =
+    "/restconf/data/tapi-common:context/tapi-equipment:physical-context";
+
+/// Represents the equipment inventory (`tapi-equipment:physical-context`) of a host
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PhysicalContext {
+    pub host: String,                 // Host the inventory was collected from
+    pub devices: Vec<PhysicalDevice>, // Physical devices (network elements) in the context
+}
+
+impl PhysicalContext {
+    /// Creates a PhysicalContext instance from a JSON `Value` and host
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the `physical-context` JSON `Value`, with or without the
+    ///   `tapi-equipment:physical-context` wrapper
+    /// - `host`: The host the inventory was collected from
+    ///
+    /// # Returns
+    /// - `Ok(PhysicalContext)`: If the deserialization is successful
+    /// - `Err(Error)`: If required fields are missing or invalid
+    pub fn from_value(value: &Value, host: &str) -> Result<Self, Error> {
+        // Unwrap the module-qualified container when the whole RESTCONF response is given
+        let value = value
+            .get("tapi-equipment:physical-context")
+            .unwrap_or(value);
+
+        // A context without devices is valid, it simply has no inventory
+        let devices = match value.get("device") {
+            Some(devices_value) => devices_value
+                .as_array()
+                .ok_or_else(|| Error::BadResponse("Not found physical devices list".to_string()))?
+                .iter()
+                .map(PhysicalDevice::from_value)
+                .collect::<Result<Vec<PhysicalDevice>, Error>>()?,
+            None => vec![],
+        };
+
+        Ok(PhysicalContext {
+            host: host.to_string(),
+            devices,
+        })
+    }
+
+    /// Returns the equipment behind an access port, which is what a node edge point
+    /// references through `tapi-equipment:supporting-access-port`
+    pub fn equipment_for(&self, access_port: &AccessPortRef) -> Vec<&Equipment> {
+        self.devices
+            .iter()
+            .filter(|device| device.uuid == access_port.device_uuid)
+            .flat_map(|device| {
+                device
+                    .access_ports
+                    .iter()
+                    .filter(|port| port.uuid == access_port.access_port_uuid)
+                    .flat_map(|port| port.equipment_uuids.iter())
+                    .filter_map(|uuid| device.equipment.iter().find(|eq| eq.uuid == *uuid))
+            })
+            .collect()
+    }
+}
+
+/// Represents a physical device (network element) with its equipment and access ports
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PhysicalDevice {
+    pub uuid: Uuid,                // UUID of the device
+    pub name: Option<String>,      // Human readable name, if reported
+    pub equipment: Vec<Equipment>, // Chassis, cards, pluggables...
+    #[serde(rename(serialize = "access-port", deserialize = "access-port"))]
+    pub access_ports: Vec<AccessPort>, // Access ports exposed by the device
+}
+
+impl PhysicalDevice {
+    /// Creates a PhysicalDevice instance from a JSON `Value`
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to deserialize from
+    ///
+    /// # Returns
+    /// - `Ok(PhysicalDevice)`: If the deserialization is successful
+    /// - `Err(Error)`: If required fields are missing or invalid
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        let uuid = parse_uuid(value, "uuid", "Not found physical device uuid")?;
+
+        let equipment = value
+            .get("equipment")
+            .and_then(Value::as_array)
+            .map(|equipment| {
+                equipment
+                    .iter()
+                    .map(Equipment::from_value)
+                    .collect::<Result<Vec<Equipment>, Error>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        let access_ports = value
+            .get("access-port")
+            .and_then(Value::as_array)
+            .map(|access_ports| {
+                access_ports
+                    .iter()
+                    .map(AccessPort::from_value)
+                    .collect::<Result<Vec<AccessPort>, Error>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(PhysicalDevice {
+            uuid,
+            name: name_from_value(value),
+            equipment,
+            access_ports,
+        })
+    }
+}
+
+/// Represents a piece of equipment (chassis, card, port module...) with its identifiers
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Equipment {
+    pub uuid: Uuid,               // UUID of the equipment
+    pub name: Option<String>,     // Human readable name, if reported
+    pub category: Option<String>, // Equipment category (e.g. SUBRACK, CIRCUIT_PACK)
+    #[serde(rename(serialize = "serial-number", deserialize = "serial-number"))]
+    pub serial_number: Option<String>, // Serial number of the actual equipment
+    #[serde(rename(serialize = "part-number", deserialize = "part-number"))]
+    pub part_number: Option<String>, // Part number (equipment type identifier)
+    pub manufacturer: Option<String>, // Manufacturer name
+}
+
+impl Equipment {
+    /// Creates an Equipment instance from a JSON `Value`
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to deserialize from
+    ///
+    /// # Returns
+    /// - `Ok(Equipment)`: If the deserialization is successful
+    /// - `Err(Error)`: If required fields are missing or invalid
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        let uuid = parse_uuid(value, "uuid", "Not found equipment uuid")?;
+
+        // Identifiers are split between the actual and the expected equipment
+        let actual = value.get("actual-equipment");
+        let actual_properties = actual.and_then(|actual| actual.get("common-actual-properties"));
+        let equipment_properties =
+            actual.and_then(|actual| actual.get("common-equipment-properties"));
+
+        let string_field = |object: Option<&Value>, field: &str| {
+            object
+                .and_then(|object| object.get(field))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+
+        Ok(Equipment {
+            uuid,
+            name: name_from_value(value),
+            category: string_field(Some(value), "category"),
+            serial_number: string_field(actual_properties, "serial-number"),
+            part_number: string_field(equipment_properties, "equipment-type-identifier"),
+            manufacturer: string_field(equipment_properties, "manufacturer-name"),
+        })
+    }
+}
+
+/// Represents an access port and the equipment its connector pins land on
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct AccessPort {
+    pub uuid: Uuid, // UUID of the access port
+    #[serde(rename(serialize = "equipment-uuid", deserialize = "equipment-uuid"))]
+    pub equipment_uuids: Vec<Uuid>, // Equipment referenced by the connector pins
+}
+
+impl AccessPort {
+    /// Creates an AccessPort instance from a JSON `Value`
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to deserialize from
+    ///
+    /// # Returns
+    /// - `Ok(AccessPort)`: If the deserialization is successful
+    /// - `Err(Error)`: If required fields are missing or invalid
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        let uuid = parse_uuid(value, "uuid", "Not found access port uuid")?;
+
+        let mut equipment_uuids: Vec<Uuid> = vec![];
+        for pin in value
+            .get("connector-pin")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let equipment_uuid = parse_uuid(pin, "equipment-uuid", "Not found equipment uuid")?;
+            // Several pins usually land on the same equipment
+            if !equipment_uuids.contains(&equipment_uuid) {
+                equipment_uuids.push(equipment_uuid);
+            }
+        }
+
+        Ok(AccessPort {
+            uuid,
+            equipment_uuids,
+        })
+    }
+}
+
+/// Reference from a node edge point to the access port supporting it
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
+pub struct AccessPortRef {
+    #[serde(rename(serialize = "device-uuid", deserialize = "device-uuid"))]
+    pub device_uuid: Uuid, // UUID of the physical device
+    #[serde(rename(serialize = "access-port-uuid", deserialize = "access-port-uuid"))]
+    pub access_port_uuid: Uuid, // UUID of the access port within the device
+}
+
+impl AccessPortRef {
+    /// Creates an AccessPortRef from an owned node edge point `Value`
+    ///
+    /// # Returns
+    /// - `Ok(Some(AccessPortRef))`: If the node edge point is supported by an access port
+    /// - `Ok(None)`: If the node edge point has no `tapi-equipment:supporting-access-port`
+    /// - `Err(Error)`: If the reference is present but malformed
+    pub fn from_node_edge_point(value: &Value) -> Result<Option<Self>, Error> {
+        let Some(access_port) = value
+            .get("tapi-equipment:supporting-access-port")
+            .and_then(|supporting| supporting.get("access-port"))
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(AccessPortRef {
+            device_uuid: parse_uuid(access_port, "device-uuid", "Not found device uuid")?,
+            access_port_uuid: parse_uuid(
+                access_port,
+                "access-port-uuid",
+                "Not found access port uuid",
+            )?,
+        }))
+    }
+}
@@ -0,0 +1,182 @@
+use std::cell::RefCell;
+use std::fmt;
+
+// Import serialization and deserialization traits from `serde`
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::Value;
+
+// Import the SHA-256 implementation used as stable hash function
+use sha2::{Digest, Sha256};
+
+/// Hash identifying the content of a topology object, used to detect changes
+///
+/// The value is the first 64 bits of the SHA-256 digest of the canonical payload. Unlike
+/// `DefaultHasher`, the result does not depend on the Rust version or the process, so
+/// hashes persisted by an older release stay comparable after an upgrade.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(transparent)] // Stored as a plain number, like the former `u64` hashes
+pub struct ContentHash(u64);
+
+impl ContentHash {
+    /// Hashes a JSON value independently of the order of its object keys
+    ///
+    /// Controllers are free to reorder keys between two answers, so hashing the raw
+    /// serialization would report identical objects as changed. The value is serialized
+    /// compactly with the keys of every object sorted, and that canonical form is hashed.
+    pub fn of(value: &Value) -> Self {
+        // The canonical form is streamed into the hasher instead of being built first
+        let digest = CHUNK.with_borrow_mut(|chunk| {
+            chunk.clear();
+            let mut canonical = Canonical {
+                hasher: Sha256::new(),
+                chunk,
+            };
+            canonical.value(value);
+            canonical.finish()
+        });
+
+        let mut truncated = [0u8; 8];
+        truncated.copy_from_slice(&digest[..8]);
+        ContentHash(u64::from_be_bytes(truncated))
+    }
+
+    /// Returns the hash as a number
+    pub fn get(&self) -> u64 {
+        self.0
+    }
+}
+
+impl From<u64> for ContentHash {
+    fn from(value: u64) -> Self {
+        ContentHash(value)
+    }
+}
+
+impl fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Bytes of the canonical form gathered before they are hashed
+///
+/// The form is written a few bytes at a time (`{`, `,`, a key...); handing each piece to
+/// SHA-256 costs more than hashing it, so pieces are gathered into whole chunks first.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Objects sorted on the stack, larger ones in a vector
+const STACK_ENTRIES: usize = 16;
+
+thread_local! {
+    /// Chunk of the canonical form being hashed, reused so hashing allocates nothing
+    static CHUNK: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Writer of the canonical form of a JSON value: sorted keys, no whitespace
+struct Canonical<'a> {
+    hasher: Sha256,
+    chunk: &'a mut Vec<u8>, // Bytes not hashed yet
+}
+
+impl Canonical<'_> {
+    fn write(&mut self, bytes: &[u8]) {
+        if self.chunk.len() + bytes.len() > CHUNK_SIZE {
+            self.hasher.update(&self.chunk[..]);
+            self.chunk.clear();
+            // Long strings are hashed as they are rather than copied
+            if bytes.len() > CHUNK_SIZE {
+                self.hasher.update(bytes);
+                return;
+            }
+        }
+        self.chunk.extend_from_slice(bytes);
+    }
+
+    fn finish(self) -> [u8; 32] {
+        let Canonical { mut hasher, chunk } = self;
+        hasher.update(&chunk[..]);
+        hasher.finalize().into()
+    }
+
+    fn value(&mut self, value: &Value) {
+        match value {
+            Value::Object(object) => {
+                self.write(b"{");
+                // Controllers usually send sorted keys, sorting is only needed otherwise
+                if object.keys().is_sorted() {
+                    self.entries(object.iter());
+                } else if object.len() <= STACK_ENTRIES {
+                    let mut entries = [None; STACK_ENTRIES];
+                    for (slot, entry) in entries.iter_mut().zip(object.iter()) {
+                        *slot = Some(entry);
+                    }
+                    let entries = &mut entries[..object.len()];
+                    entries.sort_unstable_by_key(|entry| entry.map(|(key, _)| key));
+                    self.entries(entries.iter().flatten().copied());
+                } else {
+                    let mut entries: Vec<(&String, &Value)> = object.iter().collect();
+                    entries.sort_unstable_by_key(|(key, _)| *key);
+                    self.entries(entries.into_iter());
+                }
+                self.write(b"}");
+            }
+            Value::Array(items) => {
+                self.write(b"[");
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        self.write(b",");
+                    }
+                    self.value(item);
+                }
+                self.write(b"]");
+            }
+            Value::String(string) => self.string(string),
+            Value::Null => self.write(b"null"),
+            Value::Bool(true) => self.write(b"true"),
+            Value::Bool(false) => self.write(b"false"),
+            // Numbers have a single compact serialization
+            Value::Number(number) => {
+                let _ = serde_json::to_writer(&mut *self, number);
+            }
+        }
+    }
+
+    fn entries<'v>(&mut self, entries: impl Iterator<Item = (&'v String, &'v Value)>) {
+        for (index, (key, value)) in entries.enumerate() {
+            if index > 0 {
+                self.write(b",");
+            }
+            self.string(key);
+            self.write(b":");
+            self.value(value);
+        }
+    }
+
+    /// Writes a JSON string literal, escaped like `serde_json` does
+    fn string(&mut self, string: &str) {
+        // Every byte is checked without branching, which the compiler vectorizes
+        let escaped = string.bytes().fold(false, |escaped, byte| {
+            escaped | (byte < 0x20) | (byte == b'"') | (byte == b'\\')
+        });
+        if escaped {
+            let _ = serde_json::to_writer(&mut *self, string);
+        } else {
+            self.write(b"\"");
+            self.write(string.as_bytes());
+            self.write(b"\"");
+        }
+    }
+}
+
+impl std::io::Write for Canonical<'_> {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        Canonical::write(self, bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
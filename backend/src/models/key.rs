@@ -0,0 +1,79 @@
+use crate::Error; // Import custom error handling type `Error` from the crate
+
+use std::fmt;
+use std::str::FromStr;
+
+// Import necessary traits for serialization and deserialization
+use serde::{Deserialize, Serialize};
+
+// Import UUID handling utilities from the `uuid` crate
+use uuid::Uuid;
+
+/// Composite key of a node or link
+///
+/// TAPI UUIDs are only unique within a topology, and two controllers may well report
+/// the same node UUIDs, so objects are stored and looked up by device host, topology
+/// UUID and object UUID. The storage form is `host/topology-uuid/uuid`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectKey {
+    pub host: String,        // Device the topology was collected from
+    pub topology_uuid: Uuid, // Topology owning the object
+    pub uuid: Uuid,          // UUID of the object within the topology
+}
+
+impl ObjectKey {
+    /// Creates the key of object `uuid` in topology `topology_uuid` of `host`
+    pub fn new(host: &str, topology_uuid: Uuid, uuid: Uuid) -> Self {
+        ObjectKey {
+            host: host.to_string(),
+            topology_uuid,
+            uuid,
+        }
+    }
+
+    /// Reads a stored key, migrating legacy keys made of the bare object UUID
+    ///
+    /// # Arguments
+    /// - `key`: The stored key, either `host/topology-uuid/uuid` or a bare UUID
+    /// - `host`: Host the legacy key was stored under
+    /// - `topology_uuid`: Topology the legacy key belongs to
+    ///
+    /// # Returns
+    /// - `Ok(ObjectKey)`: If the key is valid in either form
+    /// - `Err(Error)`: If the key is malformed
+    pub fn migrate(key: &str, host: &str, topology_uuid: Uuid) -> Result<Self, Error> {
+        match Uuid::parse_str(key) {
+            Ok(uuid) => Ok(ObjectKey::new(host, topology_uuid, uuid)),
+            Err(_) => key.parse(),
+        }
+    }
+}
+
+impl fmt::Display for ObjectKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.host, self.topology_uuid, self.uuid)
+    }
+}
+
+impl FromStr for ObjectKey {
+    type Err = Error;
+
+    fn from_str(key: &str) -> Result<Self, Error> {
+        let invalid = || Error::Invalid(format!("Invalid object key {}", key));
+
+        // Hosts never contain `/`, but split from the right to stay strict about UUIDs
+        let mut parts = key.rsplitn(3, '/');
+        let uuid = parts.next().ok_or_else(invalid)?;
+        let topology_uuid = parts.next().ok_or_else(invalid)?;
+        let host = parts
+            .next()
+            .filter(|host| !host.is_empty())
+            .ok_or_else(invalid)?;
+
+        Ok(ObjectKey {
+            host: host.to_string(),
+            topology_uuid: Uuid::parse_str(topology_uuid).map_err(|_| invalid())?,
+            uuid: Uuid::parse_str(uuid).map_err(|_| invalid())?,
+        })
+    }
+}
@@ -0,0 +1,49 @@
+use crate::Error; // Import custom error handling type `Error` from the crate
+
+use std::str::FromStr;
+
+// Import necessary traits for serialization and deserialization
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::Value;
+
+/// TAPI `lifecycle-state` of a topology object, used by planners to track rollouts
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LifecycleState {
+    Planned,            // Designed but not deployed
+    PotentialAvailable, // Can be deployed and is not used by any potential service
+    PotentialBusy,      // Can be deployed and is used by a potential service
+    Installed,          // Deployed and operable
+    PendingRemoval,     // Deployed but scheduled for removal
+}
+
+impl LifecycleState {
+    /// Parses the optional `lifecycle-state` field of a TAPI object
+    ///
+    /// # Returns
+    /// - `Ok(Some(LifecycleState))`: If the field is present and valid
+    /// - `Ok(None)`: If the object does not report a lifecycle state
+    /// - `Err(Error)`: If the field holds an unknown value
+    pub fn from_value(value: &Value) -> Result<Option<Self>, Error> {
+        value
+            .get("lifecycle-state")
+            .map(|state| {
+                // Deserialized from the borrowed value, without copying it
+                LifecycleState::deserialize(state)
+                    .map_err(|_| Error::BadResponse("Invalid lifecycle state".to_string()))
+            })
+            .transpose()
+    }
+}
+
+impl FromStr for LifecycleState {
+    type Err = Error;
+
+    /// Parses the TAPI name of a state, e.g. `INSTALLED`, as given in API filters
+    fn from_str(state: &str) -> Result<Self, Error> {
+        serde_json::from_value(Value::String(state.to_string()))
+            .map_err(|_| Error::Invalid(format!("Invalid lifecycle state {}", state)))
+    }
+}
@@ -1,8 +1,12 @@
-use super::node_edge_point::NodeEdgePoint;
+use super::common::name_from_value; // Import the TAPI name parsing helper
+use super::direction::Direction; // Import the TAPI forwarding direction
+use super::hash::ContentHash; // Import the stable content hash
+use super::lifecycle_state::LifecycleState; // Import the `LifecycleState` enum from a sibling module
+use super::name::Name; // Import the TAPI name list entry
+use super::node_edge_point::{NodeEdgePoint, NodeEdgePointRef};
 use crate::Error; // Import custom error handling type `Error` from the crate // Import the `NodeEdgePoint` struct from a sibling module
 
-// Import necessary traits for hashing
-use std::hash::{DefaultHasher, Hash, Hasher};
+use std::borrow::Cow;
 
 // Import date and time utilities from the `chrono` crate
 use chrono::{DateTime, Local};
@@ -11,54 +15,130 @@ use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
 
 // Import JSON utilities for working with `serde_json`
-// `Value` is used for dynamic JSON parsing, `to_string` is used for serialization
-use serde_json::{to_string, Value};
+// `Value` is used for dynamic JSON parsing, `json!` to rebuild payloads
+use serde_json::{json, Map, Value};
 
 // Import UUID handling utilities from the `uuid` crate
 use uuid::Uuid;
 
 // Define the `Link` struct with relevant fields, and make it serializable, deserializable, and comparable
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Link {
     pub host: String,
     #[serde(rename(serialize = "node-edge-point", deserialize = "node-edge-point"))]
     // Rename field for (de)serialization
     pub node_edge_points: Vec<NodeEdgePoint>, // A vector of node-edge points
-    pub uuid: Uuid,            // A UUID for identifying the link
-    pub hash: u64,             // A hash for identifying changes in the link object
+    pub uuid: Uuid, // A UUID for identifying the link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>, // Human readable name, if reported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub names: Vec<Name>, // Every entry of the TAPI `name` list, circuit IDs and labels included
+    #[serde(
+        rename(serialize = "lifecycle-state", deserialize = "lifecycle-state"),
+        default,
+        skip_serializing_if = "Option::is_none"
+    )] // Only serialized when the controller reports it
+    pub lifecycle_state: Option<LifecycleState>, // Deployment state of the link
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Direction>, // Only set when the controller reports it
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub extensions: Map<String, Value>, // Vendor fields extracted by the device's `VendorProfile`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub raw_uuids: Vec<Uuid>, // Unidirectional links merged into this one by the normalization
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<LinkWarning>, // Oddities of the payload, accepted but worth checking
+    pub hash: ContentHash, // A hash for identifying changes in the link object
     pub date: DateTime<Local>, // Timestamp for when the link was created or last modified
 }
 
+/// Oddity of a link payload that does not prevent parsing it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkWarning {
+    SelfReferential, // Two endpoints name the same node edge point of the same node
+}
+
+impl LinkWarning {
+    /// Returns the warnings of a link with the given endpoints
+    pub fn of(node_edge_points: &[NodeEdgePoint]) -> Vec<LinkWarning> {
+        let mut warnings = vec![];
+        let self_referential = node_edge_points.iter().enumerate().any(|(index, a)| {
+            node_edge_points[index + 1..].iter().any(|b| {
+                a.node_uuid == b.node_uuid && a.node_edge_point_uuid == b.node_edge_point_uuid
+            })
+        });
+        if self_referential {
+            warnings.push(LinkWarning::SelfReferential);
+        }
+        warnings
+    }
+}
+
 impl Link {
+    /// Starts building a `Link` in code instead of parsing it from a controller payload
+    ///
+    /// # Returns
+    /// - `LinkBuilder`: An empty builder, `uuid` must be set before calling `build`
+    pub fn builder() -> LinkBuilder {
+        LinkBuilder::default()
+    }
+
+    /// Returns whether `other` is the unidirectional link carrying the traffic of this
+    /// one back, its endpoints being those of this link in reverse order
+    pub fn is_reversed_by(&self, other: &Link) -> bool {
+        let endpoints = |link: &Link| -> Vec<(Uuid, Uuid)> {
+            link.node_edge_points
+                .iter()
+                .map(|endpoint| (endpoint.node_uuid, endpoint.node_edge_point_uuid))
+                .collect()
+        };
+        let mut reversed = endpoints(self);
+        reversed.reverse();
+        other.uuid != self.uuid
+            && self.direction == Some(Direction::Unidirectional)
+            && other.direction == Some(Direction::Unidirectional)
+            && endpoints(other) == reversed
+    }
+
     /// Creates a Link instance from a JSON `Value` and host
     ///
     /// # Arguments
     /// - `value`: A reference to the JSON `Value` to deserialize from
-    /// - `host`: A reference to the host `static str`
+    /// - `host`: The host the link was collected from
     ///
     /// # Returns
     /// - `Ok(Device)`: If the deserialization is successful
     /// - `Err(Error)`: If required fields are missing or invalid
-    pub fn from_value(value: &Value, host: &'static str) -> Result<Self, Error> {
+    pub fn from_value(value: &Value, host: &str) -> Result<Self, Error> {
+        Self::from_value_at(value, host, Local::now())
+    }
+
+    /// Creates a Link instance collected at `date`, shared by every object of a topology
+    pub(crate) fn from_value_at(
+        value: &Value,
+        host: &str,
+        date: DateTime<Local>,
+    ) -> Result<Self, Error> {
         let host = host.to_string();
 
         // Parse the UUID from the input `Value`
         let uuid: Uuid = Uuid::parse_str(
-            &value
+            value
                 .get("uuid") // Try to get the `uuid` field
                 .and_then(Value::as_str) // Ensure it's a string
                 .unwrap_or_default(), // Default to an empty string if not found
         )
-        .map_err(|_| Error::from("Not found link uuid"))?; // Return an error if parsing fails
+        .map_err(|_| Error::BadResponse("Not found link uuid".to_string()))?; // Return an error if parsing fails
 
         // Get the array of node-edge points from the JSON `Value`
         let node_edge_points_array: &Vec<Value> = value
             .get("node-edge-point") // Try to get `node-edge-point` field
             .and_then(Value::as_array) // Ensure it's an array
-            .ok_or_else(|| Error::from("Not found node edge points list"))?; // Return error if not found
+            .ok_or_else(|| Error::BadResponse("Not found node edge points list".to_string()))?; // Return error if not found
 
         // Initialize an empty vector to store parsed node-edge points
-        let mut node_edge_points: Vec<NodeEdgePoint> = vec![];
+        let mut node_edge_points: Vec<NodeEdgePoint> =
+            Vec::with_capacity(node_edge_points_array.len());
 
         // Iterate over the array of node-edge points and try to parse each into a `NodeEdgePoint`
         for node_edge_point in node_edge_points_array {
@@ -72,20 +152,237 @@ impl Link {
             }
         }
 
-        // Create a new hasher instance
-        let mut hasher = DefaultHasher::new();
-        // Hash the string representation of the entire `value` (JSON structure)
-        to_string(&value).unwrap().hash(&mut hasher);
-        // Get the current timestamp using `chrono::Local`
-        let now = Local::now();
+        // Parse the optional lifecycle state (PLANNED, INSTALLED...)
+        let lifecycle_state = LifecycleState::from_value(value)?;
+        let direction = Direction::from_value(value)?;
+
+        // Hash the entire `value` (JSON structure), ignoring the order of its keys
+        let hash = ContentHash::of(value);
 
         // Return a new `Link` object populated with the parsed data
+        let warnings = LinkWarning::of(&node_edge_points);
+
+        Ok(Link {
+            host,
+            raw_uuids: vec![], // Set when unidirectional links are paired
+            warnings,          // Self-referential endpoints, if any
+            node_edge_points,  // Parsed node-edge points
+            uuid,              // Parsed UUID
+            name: name_from_value(value).filter(|name| !name.is_empty()), // Controllers often send ""
+            names: Name::list_from_value(value),
+            lifecycle_state,        // Parsed lifecycle state, if any
+            direction,              // Parsed direction, if any
+            extensions: Map::new(), // Filled in by the vendor profile of the device
+            hash,                   // The calculated hash value
+            date,                   // The collection timestamp
+        })
+    }
+}
+
+/// Borrowed view of a link payload, for the ingestion path
+///
+/// Deserializing it from the collected `Value` copies no string and parses no UUID, so a
+/// poll can compare the content hash of every link with the stored one and only build
+/// the owned `Link` of the links that changed.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct LinkRef<'a> {
+    pub uuid: &'a str,
+    #[serde(rename = "node-edge-point", borrow)]
+    pub node_edge_points: Vec<NodeEdgePointRef<'a>>,
+    #[serde(default, borrow)]
+    pub name: Vec<NameRef<'a>>, // TAPI names, the first one being the link name
+    #[serde(rename = "lifecycle-state", default)]
+    pub lifecycle_state: Option<LifecycleState>,
+    #[serde(default)]
+    pub direction: Option<Direction>,
+}
+
+/// A TAPI `name` entry
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct NameRef<'a> {
+    #[serde(rename = "value-name", default, borrow)]
+    pub value_name: Option<Cow<'a, str>>,
+    #[serde(default, borrow)]
+    pub value: Cow<'a, str>, // Only copied when read from JSON text with escapes
+}
+
+impl<'a> LinkRef<'a> {
+    /// Deserializes the view of a link payload
+    ///
+    /// # Returns
+    /// - `Ok(LinkRef)`: Borrowing the strings of `value`
+    /// - `Err(Error)`: If a required field is missing or has the wrong type
+    pub fn from_value(value: &'a Value) -> Result<Self, Error> {
+        LinkRef::deserialize(value)
+            .map_err(|err| Error::BadResponse(format!("Invalid link: {}", err)))
+    }
+
+    /// Returns the link name, if reported and not empty
+    pub fn name(&self) -> Option<&str> {
+        self.name
+            .first()
+            .map(|name| name.value.as_ref())
+            .filter(|name| !name.is_empty())
+    }
+
+    /// Converts the view into an owned `Link`, when it has to be persisted
+    ///
+    /// # Arguments
+    /// - `host`: The host the link was collected from
+    /// - `hash`: The `ContentHash` of the payload the view was read from
+    /// - `date`: The collection timestamp
+    ///
+    /// # Returns
+    /// - `Ok(Link)`: Equal to the result of `Link::from_value` on the same payload
+    /// - `Err(Error)`: If a UUID is invalid
+    pub fn to_link(
+        &self,
+        host: &str,
+        hash: ContentHash,
+        date: DateTime<Local>,
+    ) -> Result<Link, Error> {
+        let uuid = Uuid::parse_str(self.uuid)
+            .map_err(|_| Error::BadResponse("Not found link uuid".to_string()))?;
+        let node_edge_points = self
+            .node_edge_points
+            .iter()
+            .map(NodeEdgePointRef::to_node_edge_point)
+            .collect::<Result<Vec<NodeEdgePoint>, Error>>()?;
+
+        Ok(Link {
+            host: host.to_string(),
+            raw_uuids: vec![],
+            warnings: LinkWarning::of(&node_edge_points),
+            node_edge_points,
+            uuid,
+            name: self.name().map(str::to_string),
+            names: self
+                .name
+                .iter()
+                .filter(|name| !name.value.is_empty())
+                .map(|name| Name {
+                    value_name: name.value_name.as_deref().map(str::to_string),
+                    value: name.value.to_string(),
+                })
+                .collect(),
+            lifecycle_state: self.lifecycle_state,
+            direction: self.direction,
+            extensions: Map::new(), // Filled in by the vendor profile of the device
+            hash,
+            date,
+        })
+    }
+}
+
+/// Builder of `Link` objects, computing the hash and date like `Link::from_value` does
+#[derive(Debug, Default)]
+pub struct LinkBuilder {
+    host: String,
+    uuid: Option<Uuid>,
+    name: Option<String>,
+    topology_uuid: Option<Uuid>,
+    node_edge_points: Vec<NodeEdgePoint>,
+    lifecycle_state: Option<LifecycleState>,
+    direction: Option<Direction>,
+}
+
+impl LinkBuilder {
+    /// Sets the host the link belongs to
+    pub fn host(mut self, host: &str) -> Self {
+        self.host = host.to_string();
+        self
+    }
+
+    /// Sets the UUID of the link
+    pub fn uuid(mut self, uuid: Uuid) -> Self {
+        self.uuid = Some(uuid);
+        self
+    }
+
+    /// Sets the name of the link
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    /// Sets the topology owning the endpoint nodes, applied to every endpoint
+    pub fn topology(mut self, topology_uuid: Uuid) -> Self {
+        self.topology_uuid = Some(topology_uuid);
+        self
+    }
+
+    /// Adds an endpoint of the link, in order
+    ///
+    /// # Arguments
+    /// - `node_uuid`: The node the endpoint belongs to
+    /// - `node_edge_point_uuid`: The node edge point the link is attached to
+    pub fn endpoint(mut self, node_uuid: Uuid, node_edge_point_uuid: Uuid) -> Self {
+        self.node_edge_points.push(NodeEdgePoint {
+            node_edge_point_uuid,
+            node_uuid,
+            topology_uuid: None,
+        });
+        self
+    }
+
+    /// Sets the lifecycle state of the link
+    pub fn lifecycle_state(mut self, lifecycle_state: LifecycleState) -> Self {
+        self.lifecycle_state = Some(lifecycle_state);
+        self
+    }
+
+    /// Sets the direction of the link
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Builds the `Link`
+    ///
+    /// The hash is computed from the TAPI payload equivalent to the link, so a link built
+    /// here and the same minimal payload parsed with `Link::from_value` hash alike.
+    ///
+    /// # Returns
+    /// - `Ok(Link)`: The link, dated now
+    /// - `Err(Error)`: If the UUID was not set
+    pub fn build(self) -> Result<Link, Error> {
+        let uuid = self
+            .uuid
+            .ok_or_else(|| Error::Invalid("Not found link uuid".to_string()))?;
+
+        let mut node_edge_points = self.node_edge_points;
+        for node_edge_point in &mut node_edge_points {
+            node_edge_point.topology_uuid = self.topology_uuid;
+        }
+
+        // Rebuild the payload the controller would have sent
+        let mut value = json!({
+            "node-edge-point": node_edge_points,
+            "uuid": uuid,
+        });
+        if let Some(name) = &self.name {
+            value["name"] = json!([{"value-name": "LINK_NAME", "value": name}]);
+        }
+        if let Some(lifecycle_state) = &self.lifecycle_state {
+            value["lifecycle-state"] = json!(lifecycle_state);
+        }
+        if let Some(direction) = &self.direction {
+            value["direction"] = json!(direction);
+        }
+
         Ok(Link {
-            host: host,
-            node_edge_points: node_edge_points, // Parsed node-edge points
-            uuid: uuid,                         // Parsed UUID
-            hash: hasher.finish(),              // The calculated hash value
-            date: now,                          // The current timestamp
+            host: self.host,
+            raw_uuids: vec![],
+            warnings: LinkWarning::of(&node_edge_points),
+            node_edge_points,
+            uuid,
+            names: Name::list_from_value(&value),
+            name: self.name,
+            lifecycle_state: self.lifecycle_state,
+            direction: self.direction,
+            extensions: Map::new(),
+            hash: ContentHash::of(&value),
+            date: Local::now(),
         })
     }
 }
@@ -1,3 +1,19 @@
+pub mod address;
+mod common;
+pub mod connection;
+pub mod connectivity_service;
 pub mod device;
+pub mod direction;
+pub mod equipment;
+pub mod hash;
+pub mod key;
+pub mod lifecycle_state;
 pub mod link;
+pub mod name;
+pub mod node;
 pub mod node_edge_point;
+pub mod parse;
+pub mod service_interface_point;
+pub mod site;
+pub mod topology;
+pub mod vendor;
@@ -0,0 +1,45 @@
+// Import necessary traits for serialization and deserialization
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::Value;
+
+/// An entry of a TAPI `name` list, e.g. `{"value-name": "CIRCUIT_ID", "value": "MAD-BCN-100G-01"}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Name {
+    #[serde(
+        rename(serialize = "value-name", deserialize = "value-name"),
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub value_name: Option<String>, // Kind of name (LINK_NAME, USER_LABEL...), if reported
+    pub value: String,
+}
+
+impl Name {
+    /// Parses every entry of the `name` list of a TAPI object
+    ///
+    /// Entries without a value or with an empty one, which controllers often send, are
+    /// skipped, as are lists of the wrong type.
+    pub fn list_from_value(value: &Value) -> Vec<Name> {
+        value
+            .get("name")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|name| {
+                let value = name.get("value").and_then(Value::as_str)?;
+                if value.is_empty() {
+                    return None;
+                }
+                Some(Name {
+                    value_name: name
+                        .get("value-name")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    value: value.to_string(),
+                })
+            })
+            .collect()
+    }
+}
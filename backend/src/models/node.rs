@@ -0,0 +1,159 @@
+use super::common::{name_from_value, parse_uuid}; // Import shared TAPI parsing helpers
+use super::equipment::AccessPortRef; // Import the access port reference used for inventory linkage
+use super::hash::ContentHash; // Import the stable content hash
+use super::lifecycle_state::LifecycleState; // Import the `LifecycleState` enum from a sibling module
+use super::name::Name; // Import the TAPI name list entry
+use super::site::Site; // Import the `Site` struct from a sibling module
+use crate::Error; // Import custom error handling type `Error` from the crate
+
+// Import date and time utilities from the `chrono` crate
+use chrono::{DateTime, Local};
+
+// Import serialization and deserialization traits from `serde`
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for working with `serde_json`
+use serde_json::{Map, Value};
+
+// Import UUID handling utilities from the `uuid` crate
+use uuid::Uuid;
+
+// Define the `Node` struct with relevant fields, and make it serializable, deserializable, and comparable
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Node {
+    pub host: String,
+    pub uuid: Uuid,           // A UUID for identifying the node
+    pub name: Option<String>, // Human readable name, if reported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub names: Vec<Name>, // Every entry of the TAPI `name` list
+    #[serde(
+        rename(serialize = "lifecycle-state", deserialize = "lifecycle-state"),
+        default,
+        skip_serializing_if = "Option::is_none"
+    )] // Only serialized when the controller reports it
+    pub lifecycle_state: Option<LifecycleState>, // Deployment state of the node
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site: Option<Site>, // Site annotation added by the enrichment stage
+    #[serde(rename(
+        serialize = "owned-node-edge-point",
+        deserialize = "owned-node-edge-point"
+    ))]
+    // Rename field for (de)serialization
+    pub owned_node_edge_points: Vec<OwnedNodeEdgePoint>, // Node edge points owned by the node
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub extensions: Map<String, Value>, // Vendor fields extracted by the device's `VendorProfile`
+    pub hash: ContentHash,    // A hash for identifying changes in the node object
+    pub date: DateTime<Local>, // Timestamp for when the node was created or last modified
+}
+
+impl Node {
+    /// Creates a Node instance from a JSON `Value` and host
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to deserialize from
+    /// - `host`: The host the node was collected from
+    ///
+    /// # Returns
+    /// - `Ok(Node)`: If the deserialization is successful
+    /// - `Err(Error)`: If required fields are missing or invalid
+    pub fn from_value(value: &Value, host: &str) -> Result<Self, Error> {
+        Self::from_value_at(value, host, Local::now())
+    }
+
+    /// Creates a Node instance collected at `date`, shared by every object of a topology
+    pub(crate) fn from_value_at(
+        value: &Value,
+        host: &str,
+        date: DateTime<Local>,
+    ) -> Result<Self, Error> {
+        let uuid = parse_uuid(value, "uuid", "Not found node uuid")?;
+
+        // Nodes without owned node edge points are valid (e.g. abstract nodes)
+        let owned_node_edge_points = value
+            .get("owned-node-edge-point")
+            .and_then(Value::as_array)
+            .map(|node_edge_points| {
+                node_edge_points
+                    .iter()
+                    .map(OwnedNodeEdgePoint::from_value)
+                    .collect::<Result<Vec<OwnedNodeEdgePoint>, Error>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Node {
+            host: host.to_string(),
+            uuid,
+            name: name_from_value(value),
+            names: Name::list_from_value(value),
+            lifecycle_state: LifecycleState::from_value(value)?,
+            site: None,
+            owned_node_edge_points,
+            extensions: Map::new(), // Filled in by the vendor profile of the device
+            hash: ContentHash::of(value), // Independent of the key order of the payload
+            date,
+        })
+    }
+}
+
+/// Represents a node edge point as owned by its node, with the attributes needed to
+/// correlate it with services and hardware
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OwnedNodeEdgePoint {
+    pub uuid: Uuid, // UUID of the node edge point
+    #[serde(rename(
+        serialize = "layer-protocol-qualifier",
+        deserialize = "layer-protocol-qualifier"
+    ))]
+    pub layer_protocol_qualifier: Option<String>, // Digital signal type of the endpoint
+    #[serde(rename(
+        serialize = "mapped-service-interface-point",
+        deserialize = "mapped-service-interface-point"
+    ))]
+    pub mapped_service_interface_points: Vec<Uuid>, // SIPs supported by the node edge point
+    #[serde(rename(
+        serialize = "supporting-access-port",
+        deserialize = "supporting-access-port"
+    ))]
+    pub supporting_access_port: Option<AccessPortRef>, // Hardware behind the node edge point
+}
+
+impl OwnedNodeEdgePoint {
+    /// Creates an OwnedNodeEdgePoint instance from a JSON `Value`
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to deserialize from
+    ///
+    /// # Returns
+    /// - `Ok(OwnedNodeEdgePoint)`: If the deserialization is successful
+    /// - `Err(Error)`: If required fields are missing or invalid
+    pub fn from_value(value: &Value) -> Result<Self, Error> {
+        let uuid = parse_uuid(value, "uuid", "Not found node edge point uuid")?;
+
+        let layer_protocol_qualifier = value
+            .get("layer-protocol-qualifier")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        let mut mapped_service_interface_points: Vec<Uuid> = vec![];
+        for mapped in value
+            .get("mapped-service-interface-point")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            mapped_service_interface_points.push(parse_uuid(
+                mapped,
+                "service-interface-point-uuid",
+                "Not found service interface point uuid",
+            )?);
+        }
+
+        Ok(OwnedNodeEdgePoint {
+            uuid,
+            layer_protocol_qualifier,
+            mapped_service_interface_points,
+            supporting_access_port: AccessPortRef::from_node_edge_point(value)?,
+        })
+    }
+}
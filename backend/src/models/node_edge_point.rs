@@ -10,7 +10,7 @@ use serde_json::Value;
 use uuid::Uuid;
 
 // Define the `NodeEdgePoint` struct with relevant fields, and make it serializable, deserializable, and comparable
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct NodeEdgePoint {
     #[serde(rename(
         serialize = "node-edge-point-uuid",
@@ -20,6 +20,12 @@ pub struct NodeEdgePoint {
     #[serde(rename(serialize = "node-uuid", deserialize = "node-uuid"))]
     // Rename field for (de)serialization
     pub node_uuid: Uuid, // UUID for the node
+    #[serde(
+        rename(serialize = "topology-uuid", deserialize = "topology-uuid"),
+        default,
+        skip_serializing_if = "Option::is_none"
+    )] // Only serialized when the controller reports it
+    pub topology_uuid: Option<Uuid>, // UUID for the topology owning the node
 }
 
 impl NodeEdgePoint {
@@ -28,26 +34,69 @@ impl NodeEdgePoint {
     pub fn from_value(value: &Value) -> Result<Self, Error> {
         // Parse the node edge point UUID from the input `Value`
         let node_edge_point_uuid: Uuid = Uuid::parse_str(
-            &value
+            value
                 .get("node-edge-point-uuid") // Try to get the `node-edge-point-uuid` field
                 .and_then(Value::as_str) // Ensure it's a string
                 .unwrap_or_default(), // Default to an empty string if not found
         )
-        .map_err(|_| Error::from("Not found node edge point uuid"))?; // Return an error if parsing fails
+        .map_err(|_| Error::BadResponse("Not found node edge point uuid".to_string()))?; // Return an error if parsing fails
 
         // Parse the node UUID from the input `Value`
         let node_uuid: Uuid = Uuid::parse_str(
-            &value
+            value
                 .get("node-uuid") // Try to get the `node-uuid` field
                 .and_then(Value::as_str) // Ensure it's a string
                 .unwrap_or_default(), // Default to an empty string if not found
         )
-        .map_err(|_| Error::from("Not found node uuid"))?; // Return an error if parsing fails
+        .map_err(|_| Error::BadResponse("Not found node uuid".to_string()))?; // Return an error if parsing fails
+
+        // Parse the optional topology UUID; when present it must be valid
+        let topology_uuid: Option<Uuid> = value
+            .get("topology-uuid") // Try to get the `topology-uuid` field
+            .and_then(Value::as_str) // Ensure it's a string
+            .map(Uuid::parse_str) // Parse it only if it exists
+            .transpose()
+            .map_err(|_| Error::BadResponse("Invalid topology uuid".to_string()))?; // Return an error if parsing fails
 
         // Return a new `NodeEdgePoint` object populated with the parsed data
         Ok(NodeEdgePoint {
-            node_edge_point_uuid: node_edge_point_uuid, // Parsed node edge point UUID
-            node_uuid: node_uuid,                       // Parsed node UUID
+            node_edge_point_uuid, // Parsed node edge point UUID
+            node_uuid,            // Parsed node UUID
+            topology_uuid,        // Parsed topology UUID, if any
+        })
+    }
+}
+
+/// Borrowed view of a node edge point payload, its UUIDs left unparsed
+///
+/// Used on the ingestion path, see [`crate::models::link::LinkRef`].
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct NodeEdgePointRef<'a> {
+    #[serde(rename = "node-edge-point-uuid")]
+    pub node_edge_point_uuid: &'a str,
+    #[serde(rename = "node-uuid")]
+    pub node_uuid: &'a str,
+    #[serde(rename = "topology-uuid", default)]
+    pub topology_uuid: Option<&'a str>,
+}
+
+impl NodeEdgePointRef<'_> {
+    /// Converts the view into an owned `NodeEdgePoint`, parsing its UUIDs
+    ///
+    /// # Returns
+    /// - `Ok(NodeEdgePoint)`: If every UUID is valid
+    /// - `Err(Error)`: With the same messages as `NodeEdgePoint::from_value`
+    pub fn to_node_edge_point(&self) -> Result<NodeEdgePoint, Error> {
+        Ok(NodeEdgePoint {
+            node_edge_point_uuid: Uuid::parse_str(self.node_edge_point_uuid)
+                .map_err(|_| Error::BadResponse("Not found node edge point uuid".to_string()))?,
+            node_uuid: Uuid::parse_str(self.node_uuid)
+                .map_err(|_| Error::BadResponse("Not found node uuid".to_string()))?,
+            topology_uuid: self
+                .topology_uuid
+                .map(Uuid::parse_str)
+                .transpose()
+                .map_err(|_| Error::BadResponse("Invalid topology uuid".to_string()))?,
         })
     }
 }
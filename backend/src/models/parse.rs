@@ -0,0 +1,115 @@
+use super::topology::Topology; // Import the topologies of the previous collection
+use super::vendor::VendorProfile; // Import the vendor fields to extract
+use crate::capture::ParseCapture; // Import the capture of the fragments failing to parse
+use crate::schema::TapiVersion; // Import the TAPI releases
+use crate::Error; // Import custom error handling type `Error` from the crate
+
+// Import serialization and deserialization traits from `serde`
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::Value;
+
+use rayon::prelude::*;
+
+use std::str::FromStr;
+
+/// Size from which the nodes and links of a topology are parsed in parallel
+pub const PARALLEL_THRESHOLD: usize = 1_000;
+
+/// How a topology handles nodes and links that fail to parse
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ParseMode {
+    Strict, // The first bad entry fails the whole topology
+    #[default]
+    Lenient, // Bad entries are skipped and reported
+}
+
+impl FromStr for ParseMode {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Error> {
+        match value {
+            "strict" => Ok(ParseMode::Strict),
+            "lenient" => Ok(ParseMode::Lenient),
+            _ => Err(Error::Invalid(format!("Invalid parse mode {}", value))),
+        }
+    }
+}
+
+/// Settings of a topology parse
+#[derive(Default, Clone, Copy)]
+pub struct ParseOptions<'a> {
+    pub mode: ParseMode,
+    pub capture: Option<&'a ParseCapture>, // Where to save the fragments failing to parse
+    pub profile: Option<&'a VendorProfile>, // Vendor fields to extract into `extensions`
+    pub version: Option<TapiVersion>,      // TAPI release of the payload, detected when unset
+    pub previous: Option<&'a [Topology]>, // Last topologies of the host, unchanged links being reused
+}
+
+/// An entry skipped by a lenient parse
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ParseIssue {
+    pub object: String, // Kind of the entry, `node` or `link`
+    pub index: usize,   // Position of the entry in its list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>, // Raw uuid of the entry, when it has one
+    pub error: String,
+}
+
+/// Entries skipped while parsing a topology, empty after a strict parse
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ParseReport {
+    pub issues: Vec<ParseIssue>,
+}
+
+impl ParseReport {
+    /// Returns `true` when no entry was skipped
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Parses every entry of `entries`, skipping or failing on bad ones according to `options`
+    ///
+    /// Lists of at least `PARALLEL_THRESHOLD` entries are parsed on the rayon thread pool.
+    /// The results are then handled in list order, so the reported indexes, the error of a
+    /// strict parse and the captured fragments are the same as with a sequential parse.
+    pub(crate) fn parse_list<T: Send>(
+        &mut self,
+        object: &str,
+        host: &str,
+        entries: &[Value],
+        options: &ParseOptions,
+        parse: impl Fn(&Value) -> Result<T, Error> + Sync,
+    ) -> Result<Vec<T>, Error> {
+        let results: Vec<Result<T, Error>> = if entries.len() >= PARALLEL_THRESHOLD {
+            entries.par_iter().map(&parse).collect()
+        } else {
+            entries.iter().map(&parse).collect()
+        };
+
+        let mut parsed = Vec::with_capacity(entries.len());
+        for (index, (entry, result)) in entries.iter().zip(results).enumerate() {
+            // Fragments are captured here rather than by the workers, in list order
+            let result = match (result, options.capture) {
+                (Err(err), Some(capture)) => capture.parse(host, object, entry, |_| Err(err)),
+                (result, _) => result,
+            };
+            match (result, options.mode) {
+                (Ok(value), _) => parsed.push(value),
+                (Err(err), ParseMode::Strict) => return Err(err),
+                (Err(err), ParseMode::Lenient) => self.issues.push(ParseIssue {
+                    object: object.to_string(),
+                    index,
+                    uuid: entry
+                        .get("uuid")
+                        .and_then(Value::as_str)
+                        .map(str::to_string),
+                    error: err.to_string(),
+                }),
+            }
+        }
+        Ok(parsed)
+    }
+}
@@ -0,0 +1,48 @@
+use super::common::{name_from_value, parse_uuid}; // Import shared TAPI parsing helpers
+use crate::Error; // Import custom error handling type `Error` from the crate
+
+// Import necessary traits for serialization and deserialization
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::Value;
+
+// Import UUID handling utilities from the `uuid` crate
+use uuid::Uuid;
+
+/// Represents a TAPI service interface point, the endpoint services are requested on
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ServiceInterfacePoint {
+    pub host: String,
+    pub uuid: Uuid,           // UUID of the service interface point
+    pub name: Option<String>, // Human readable name, if reported
+    #[serde(rename(serialize = "layer-protocol-name", deserialize = "layer-protocol-name"))]
+    pub layer_protocol_name: Option<String>, // Layer protocol (e.g. DSR, ODU, PHOTONIC_MEDIA)
+}
+
+impl ServiceInterfacePoint {
+    /// Creates a ServiceInterfacePoint instance from a JSON `Value` and host
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the JSON `Value` to deserialize from
+    /// - `host`: The host the service interface point was collected from
+    ///
+    /// # Returns
+    /// - `Ok(ServiceInterfacePoint)`: If the deserialization is successful
+    /// - `Err(Error)`: If required fields are missing or invalid
+    pub fn from_value(value: &Value, host: &str) -> Result<Self, Error> {
+        let uuid = parse_uuid(value, "uuid", "Not found service interface point uuid")?;
+
+        let layer_protocol_name = value
+            .get("layer-protocol-name")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        Ok(ServiceInterfacePoint {
+            host: host.to_string(),
+            uuid,
+            name: name_from_value(value),
+            layer_protocol_name,
+        })
+    }
+}
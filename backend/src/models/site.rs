@@ -0,0 +1,11 @@
+// Import necessary traits for serialization and deserialization
+use serde::{Deserialize, Serialize};
+
+/// Physical site (point of presence) a device or node is located at
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Site {
+    pub id: String,           // Site identifier (e.g. MAD01)
+    pub name: Option<String>, // Human readable name
+    pub latitude: f64,        // WGS84 latitude in degrees
+    pub longitude: f64,       // WGS84 longitude in degrees
+}
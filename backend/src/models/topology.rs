@@ -0,0 +1,245 @@
+use super::common::parse_uuid; // Import shared TAPI parsing helpers
+use super::connectivity_service::{bare, qualifier_layer};
+use super::hash::ContentHash;
+use super::key::ObjectKey;
+use super::lifecycle_state::LifecycleState;
+use super::link::{Link, LinkRef};
+use super::node::Node;
+use super::parse::{ParseMode, ParseOptions, ParseReport};
+use super::site::Site;
+use crate::schema::adapter; // Import the adapters of the TAPI releases
+use crate::Error; // Import custom error handling type `Error` from the crate
+
+use std::collections::HashMap;
+
+// Import date and time utilities from the `chrono` crate
+use chrono::{DateTime, Local};
+
+// Import serialization and deserialization traits from `serde`
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::{Map, Value};
+
+// Import UUID handling utilities from the `uuid` crate
+use uuid::Uuid;
+
+/// RESTCONF path of the TAPI topology context holding the topologies of a device
+pub const TOPOLOGY_CONTEXT_PATH: &str =
+    "/restconf/data/tapi-common:context/tapi-topology:topology-context";
+
+/// Represents a parsed `tapi-topology:topology` of a host: its nodes and links
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Topology {
+    pub host: String,
+    pub uuid: Uuid,       // A UUID for identifying the topology
+    pub nodes: Vec<Node>, // Nodes of the topology
+    pub links: Vec<Link>, // Links between the nodes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site: Option<Site>, // Site of the device, added by the enrichment stage
+}
+
+impl Topology {
+    /// Creates a Topology instance from a JSON `Value` and host
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the topology JSON `Value`
+    /// - `host`: The host the topology was collected from
+    ///
+    /// # Returns
+    /// - `Ok(Topology)`: If the deserialization is successful
+    /// - `Err(Error)`: If required fields are missing or invalid
+    pub fn from_value(value: &Value, host: &str) -> Result<Self, Error> {
+        let options = ParseOptions {
+            mode: ParseMode::Strict,
+            ..Default::default()
+        };
+        Self::parse(value, host, &options).map(|(topology, _)| topology)
+    }
+
+    /// Creates a Topology instance with the given parse settings
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the topology JSON `Value`
+    /// - `host`: The host the topology was collected from
+    /// - `options`: Whether bad nodes and links fail the topology or are skipped, where
+    ///   to save them, the vendor fields to extract and the TAPI release of the payload
+    ///
+    /// # Returns
+    /// - `Ok((Topology, ParseReport))`: The topology and the entries that were skipped
+    /// - `Err(Error)`: If the topology uuid is missing, or on the first bad entry in
+    ///   strict mode, with the id of the capture when the fragment was saved
+    pub fn parse(
+        value: &Value,
+        host: &str,
+        options: &ParseOptions,
+    ) -> Result<(Self, ParseReport), Error> {
+        let version = options.version.unwrap_or_else(|| adapter::detect(value));
+        let value = adapter::normalize(value, version);
+        let value = value.as_ref();
+        let uuid = parse_uuid(value, "uuid", "Not found topology uuid")?;
+        let mut report = ParseReport::default();
+        // Reading the clock per object is a large part of the parse of big topologies
+        let date = Local::now();
+
+        // A topology may legitimately report no nodes or no links
+        let nodes = match value.get("node").and_then(Value::as_array) {
+            Some(nodes) => report.parse_list("node", host, nodes, options, |value| {
+                let mut node = Node::from_value_at(value, host, date)?;
+                if let Some(profile) = options.profile {
+                    node.extensions = profile.node_extensions(value);
+                }
+                Ok(node)
+            })?,
+            None => vec![],
+        };
+
+        // Links of the previous collection of this topology, paired ones matching no payload
+        let previous: HashMap<Uuid, &Link> = options
+            .previous
+            .unwrap_or_default()
+            .iter()
+            .filter(|topology| topology.uuid == uuid && topology.host == host)
+            .flat_map(|topology| &topology.links)
+            .filter(|link| link.raw_uuids.is_empty())
+            .map(|link| (link.uuid, link))
+            .collect();
+        let links = match value.get("link").and_then(Value::as_array) {
+            Some(links) => report.parse_list("link", host, links, options, |value| {
+                let mut link = ingest_link(value, host, date, &previous)?;
+                link.extensions = match options.profile {
+                    Some(profile) => profile.link_extensions(value),
+                    None => Map::new(),
+                };
+                Ok(link)
+            })?,
+            None => vec![],
+        };
+
+        let topology = Topology {
+            host: host.to_string(),
+            uuid,
+            nodes,
+            links,
+            site: None,
+        };
+        Ok((topology, report))
+    }
+
+    /// Extracts the raw topologies of a `tapi-topology:topology-context` JSON `Value`
+    ///
+    /// # Arguments
+    /// - `value`: A reference to the topology context, with or without the
+    ///   `tapi-topology:topology-context` wrapper
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Value>)`: The topologies, to be parsed with [`Topology::from_value`]
+    /// - `Err(Error)`: If the topology list is malformed
+    pub fn list_from_context(value: &Value) -> Result<Vec<Value>, Error> {
+        // Unwrap the module-qualified container when the whole RESTCONF response is given
+        let value = value.get("tapi-topology:topology-context").unwrap_or(value);
+
+        match value.get("topology") {
+            Some(topologies) => Ok(topologies
+                .as_array()
+                .ok_or_else(|| Error::BadResponse("Not found topology list".to_string()))?
+                .clone()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Returns the storage key of object `uuid` of this topology
+    pub fn key(&self, uuid: Uuid) -> ObjectKey {
+        ObjectKey::new(&self.host, self.uuid, uuid)
+    }
+
+    /// Returns the nodes in the given lifecycle state
+    pub fn nodes_in_state(&self, state: LifecycleState) -> Vec<&Node> {
+        self.nodes
+            .iter()
+            .filter(|node| node.lifecycle_state == Some(state))
+            .collect()
+    }
+
+    /// Returns the links in the given lifecycle state
+    pub fn links_in_state(&self, state: LifecycleState) -> Vec<&Link> {
+        self.links
+            .iter()
+            .filter(|link| link.lifecycle_state == Some(state))
+            .collect()
+    }
+
+    /// Returns a copy of the topology with only its nodes and links in `state`
+    pub fn in_state(&self, state: LifecycleState) -> Topology {
+        Topology {
+            host: self.host.clone(),
+            uuid: self.uuid,
+            nodes: self.nodes_in_state(state).into_iter().cloned().collect(),
+            links: self.links_in_state(state).into_iter().cloned().collect(),
+            site: self.site.clone(),
+        }
+    }
+
+    /// Returns the layer protocol of the node or link `uuid`, one of `LAYER_PROTOCOLS`
+    ///
+    /// The layer is told by the `layer-protocol-qualifier` of the node edge points of a
+    /// node, or of the node edge points a link joins; `None` if no qualifier is known.
+    pub fn layer_protocol(&self, uuid: &Uuid) -> Option<&'static str> {
+        let qualifiers: Vec<&str> = match self.nodes.iter().find(|node| node.uuid == *uuid) {
+            Some(node) => node
+                .owned_node_edge_points
+                .iter()
+                .filter_map(|node_edge_point| node_edge_point.layer_protocol_qualifier.as_deref())
+                .collect(),
+            None => self
+                .links
+                .iter()
+                .find(|link| link.uuid == *uuid)?
+                .node_edge_points
+                .iter()
+                .filter_map(|end| {
+                    self.nodes
+                        .iter()
+                        .find(|node| node.uuid == end.node_uuid)?
+                        .owned_node_edge_points
+                        .iter()
+                        .find(|node_edge_point| node_edge_point.uuid == end.node_edge_point_uuid)?
+                        .layer_protocol_qualifier
+                        .as_deref()
+                })
+                .collect(),
+        };
+        qualifiers
+            .into_iter()
+            .find_map(|qualifier| qualifier_layer(bare(qualifier)))
+    }
+}
+
+/// Builds the link of a payload, reusing the previous link when its content is unchanged
+///
+/// The payload is read through the borrowed [`LinkRef`] view: only its content hash is
+/// computed for a link seen unchanged, the owned link being built for new and changed
+/// links only. Payloads the view rejects go through [`Link::from_value_at`], which
+/// reports why.
+fn ingest_link(
+    value: &Value,
+    host: &str,
+    date: DateTime<Local>,
+    previous: &HashMap<Uuid, &Link>,
+) -> Result<Link, Error> {
+    let Ok(view) = LinkRef::from_value(value) else {
+        return Link::from_value_at(value, host, date);
+    };
+    let hash = ContentHash::of(value);
+    let unchanged = Uuid::parse_str(view.uuid)
+        .ok()
+        .and_then(|uuid| previous.get(&uuid))
+        .filter(|link| link.hash == hash);
+    match unchanged {
+        Some(link) => Ok(Link {
+            date,
+            ..(*link).clone()
+        }),
+        None => view.to_link(host, hash, date),
+    }
+}
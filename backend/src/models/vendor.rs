@@ -0,0 +1,93 @@
+use super::device::Device; // Import the `Device` struct, whose `vendor` metadata selects the profile
+
+// Import serialization and deserialization traits from `serde`
+use serde::{Deserialize, Serialize};
+
+// Import JSON utilities for dynamic JSON value parsing
+use serde_json::{Map, Value};
+
+/// A vendor field copied into the `extensions` of a node or link
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldMapping {
+    pub source: String, // Module-qualified key in the TAPI payload
+    pub name: String,   // Key in `extensions`
+}
+
+impl FieldMapping {
+    /// Creates a mapping of the payload key `source` to the extension `name`
+    pub fn new(source: &str, name: &str) -> Self {
+        FieldMapping {
+            source: source.to_string(),
+            name: name.to_string(),
+        }
+    }
+}
+
+/// Vendor-specific fields to extract from the nodes and links of a device
+///
+/// Vendors add their own fields under extension namespaces (e.g.
+/// `tapi-ciena-link-extensions`). A profile lists the ones worth keeping; they are
+/// copied, unchanged, into the generic `extensions` map of `Node`/`Link`. The profile
+/// of a device is selected by the `vendor` entry of its metadata.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct VendorProfile {
+    pub vendor: String, // Matched case-insensitively against the device `vendor` metadata
+    #[serde(default)]
+    pub node: Vec<FieldMapping>, // Fields extracted from the nodes
+    #[serde(default)]
+    pub link: Vec<FieldMapping>, // Fields extracted from the links
+}
+
+impl VendorProfile {
+    /// Returns the profiles shipped with the application
+    pub fn builtin() -> Vec<VendorProfile> {
+        vec![VendorProfile {
+            vendor: "ciena".to_string(),
+            node: vec![],
+            link: vec![
+                FieldMapping::new(
+                    "tapi-ciena-link-extensions:layer-protocol-qualifier",
+                    "layer-protocol-qualifier",
+                ),
+                FieldMapping::new(
+                    "tapi-ciena-link-extensions:signal-content-type",
+                    "signal-content-type",
+                ),
+            ],
+        }]
+    }
+
+    /// Selects the profile of a device among `profiles`
+    ///
+    /// # Returns
+    /// - `Some(&VendorProfile)`: The profile of the device `vendor` metadata
+    /// - `None`: If the device has no vendor, or no profile matches it
+    pub fn for_device<'a>(device: &Device, profiles: &'a [VendorProfile]) -> Option<&'a Self> {
+        let vendor = device.metadata.get("vendor")?;
+        profiles
+            .iter()
+            .find(|profile| profile.vendor.eq_ignore_ascii_case(vendor))
+    }
+
+    /// Extracts the node extensions of a raw `tapi-topology:node`
+    pub fn node_extensions(&self, value: &Value) -> Map<String, Value> {
+        extract(&self.node, value)
+    }
+
+    /// Extracts the link extensions of a raw `tapi-topology:link`
+    pub fn link_extensions(&self, value: &Value) -> Map<String, Value> {
+        extract(&self.link, value)
+    }
+}
+
+/// Copies the mapped fields present in `value`
+fn extract(mappings: &[FieldMapping], value: &Value) -> Map<String, Value> {
+    mappings
+        .iter()
+        .filter_map(|mapping| {
+            value
+                .get(&mapping.source)
+                .map(|field| (mapping.name.clone(), field.clone()))
+        })
+        .collect()
+}
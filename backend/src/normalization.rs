@@ -0,0 +1,58 @@
+use crate::models::direction::Direction;
+use crate::models::hash::ContentHash;
+use crate::models::{link::Link, topology::Topology};
+
+use serde_json::json;
+
+/// Merges the unidirectional link pairs of `topology` into bidirectional links
+///
+/// Controllers often report a physical link as two unidirectional links, A→B and B→A.
+/// Each pair becomes one link with the UUID of the smaller raw UUID and both raw UUIDs
+/// in `raw_uuids`. Its hash combines the hashes of both sides, so a change on either
+/// side is still detected. Links without a mate are kept as reported.
+///
+/// # Returns
+/// - `usize`: The number of pairs merged
+pub fn pair_links(topology: &mut Topology) -> usize {
+    let links = std::mem::take(&mut topology.links);
+    let mut unpaired: Vec<Link> = vec![];
+    let mut merged = 0;
+    for link in links {
+        match unpaired.iter().position(|mate| link.is_reversed_by(mate)) {
+            Some(index) => {
+                let mate = unpaired.remove(index);
+                topology.links.push(merge(mate, link));
+                merged += 1;
+            }
+            None => unpaired.push(link),
+        }
+    }
+    topology.links.extend(unpaired);
+    merged
+}
+
+/// Merges two mates into the bidirectional link they stand for
+fn merge(a: Link, b: Link) -> Link {
+    let (mut first, second) = if a.uuid <= b.uuid { (a, b) } else { (b, a) };
+    first.raw_uuids = vec![first.uuid, second.uuid];
+    first.direction = Some(Direction::Bidirectional);
+    first.hash = ContentHash::of(&json!([first.hash.get(), second.hash.get()]));
+    if first.name.is_none() {
+        first.name = second.name;
+    }
+    for name in second.names {
+        if !first.names.contains(&name) {
+            first.names.push(name);
+        }
+    }
+    first.lifecycle_state = first.lifecycle_state.or(second.lifecycle_state);
+    for (key, value) in second.extensions {
+        first.extensions.entry(key).or_insert(value);
+    }
+    for warning in second.warnings {
+        if !first.warnings.contains(&warning) {
+            first.warnings.push(warning);
+        }
+    }
+    first
+}
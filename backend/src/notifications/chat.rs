@@ -0,0 +1,260 @@
+use super::templates::NotificationTemplates;
+use super::thresholds::{Threshold, ThresholdState};
+use super::{Alert, Channel, Severity};
+use crate::{Error, Result};
+
+use reqwest::{Client, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+/// Chat service an incoming webhook belongs to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatKind {
+    Slack, // Block Kit messages
+    Teams, // Office 365 connector cards
+}
+
+/// A chat channel, one `[[notifications.chat]]` entry of `config.toml`
+///
+/// ```toml
+/// [[notifications.chat]]
+/// name = "noc-production"
+/// kind = "slack"
+/// webhook_url = "https://hooks.slack.com/services/T000/B000/XXXX"
+/// device_tags = ["production"]
+/// min_severity = "warning"
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChatChannelConfig {
+    pub name: String, // Used in the logs and errors, the webhook URL being a secret
+    pub kind: ChatKind,
+    pub webhook_url: String,
+    #[serde(default)]
+    pub device_tags: Vec<String>, // Only devices with one of these tags, every device if empty
+    #[serde(default = "default_min_severity")]
+    pub min_severity: Severity, // Less severe alerts are not posted
+}
+
+fn default_min_severity() -> Severity {
+    Severity::Info
+}
+
+impl ChatChannelConfig {
+    /// Returns whether `alert` of a device tagged `device_tags` is posted to the channel
+    pub fn accepts(&self, alert: &Alert, device_tags: &[String]) -> bool {
+        alert.severity() >= self.min_severity
+            && (self.device_tags.is_empty()
+                || self.device_tags.iter().any(|tag| device_tags.contains(tag)))
+    }
+}
+
+/// Slack and Teams channels: posts alerts to their incoming webhooks
+pub struct ChatNotifier {
+    channels: Vec<ChatChannelConfig>,
+    templates: NotificationTemplates,
+    client: Client,
+}
+
+impl ChatNotifier {
+    /// Creates the notifier, topology changes being formatted with the `slack` and
+    /// `teams` templates
+    ///
+    /// # Returns
+    /// - `Ok(ChatNotifier)`: If every webhook URL is valid
+    /// - `Err(Error)`: Otherwise
+    pub fn new(channels: Vec<ChatChannelConfig>, templates: NotificationTemplates) -> Result<Self> {
+        for channel in &channels {
+            Url::parse(&channel.webhook_url).map_err(|err| {
+                Error::Custom(format!(
+                    "Invalid webhook URL of chat channel {}: {}",
+                    channel.name, err
+                ))
+            })?;
+        }
+
+        Ok(ChatNotifier {
+            channels,
+            templates,
+            client: Client::new(),
+        })
+    }
+
+    /// Returns the channels of `kinds` that `alert` of a device tagged `device_tags` is
+    /// posted to
+    pub fn routes(
+        &self,
+        alert: &Alert,
+        device_tags: &[String],
+        kinds: &[ChatKind],
+    ) -> Vec<&ChatChannelConfig> {
+        self.channels
+            .iter()
+            .filter(|channel| kinds.contains(&channel.kind) && channel.accepts(alert, device_tags))
+            .collect()
+    }
+
+    /// Posts `alert` to every channel routing it
+    ///
+    /// A failing channel does not prevent the others from being notified.
+    ///
+    /// # Arguments
+    /// - `alert`: The alert to notify
+    /// - `device_tags`: The tags of the device the alert is about
+    /// - `kinds`: The chat services the alert was routed to
+    ///
+    /// # Returns
+    /// - `Ok(Vec<String>)`: The names of the notified channels
+    /// - `Err(Error)`: If any channel refused the message or could not be reached
+    pub async fn notify(
+        &self,
+        alert: &Alert,
+        device_tags: &[String],
+        kinds: &[ChatKind],
+    ) -> Result<Vec<String>> {
+        let mut notified = vec![];
+        let mut failures = vec![];
+        for channel in self.routes(alert, device_tags, kinds) {
+            match self.post(channel, alert).await {
+                Ok(()) => notified.push(channel.name.clone()),
+                Err(err) => {
+                    warn!("{}", err);
+                    failures.push(err.to_string());
+                }
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(Error::Custom(format!(
+                "Failed to notify chat channels: {}",
+                failures.join("; ")
+            )));
+        }
+        info!(
+            "Alert on {} posted to {} chat channels",
+            alert.host(),
+            notified.len()
+        );
+        Ok(notified)
+    }
+
+    /// Returns the message of `alert` in the format of `kind`
+    pub fn message(&self, kind: ChatKind, alert: &Alert) -> Result<Value> {
+        let title = title(alert);
+        match kind {
+            ChatKind::Slack => {
+                let text = self.text(Channel::Slack, alert)?;
+                Ok(json!({
+                    "text": title, // Fallback of notifications and old clients
+                    "blocks": [
+                        {"type": "header", "text": {"type": "plain_text", "text": title}},
+                        {"type": "section", "text": {"type": "mrkdwn", "text": text}},
+                        {
+                            "type": "context",
+                            "elements": [{
+                                "type": "mrkdwn",
+                                "text": format!("Severity: *{}* | Device Manager", alert.severity().as_str()),
+                            }],
+                        },
+                    ],
+                }))
+            }
+            ChatKind::Teams => {
+                let text = self.text(Channel::Teams, alert)?;
+                Ok(json!({
+                    "@type": "MessageCard",
+                    "@context": "https://schema.org/extensions",
+                    "themeColor": color(alert.severity()),
+                    "summary": title,
+                    "title": title,
+                    "text": text,
+                }))
+            }
+        }
+    }
+
+    /// Returns the markdown body of `alert`, in the dialect of `channel`
+    fn text(&self, channel: Channel, alert: &Alert) -> Result<String> {
+        let bold = |value: &str| match channel {
+            Channel::Teams => format!("**{}**", value),
+            _ => format!("*{}*", value),
+        };
+        match alert {
+            Alert::DeviceDown { error, since, .. } => Ok(format!(
+                "Unreachable since {}: {}",
+                bold(&since.format("%Y-%m-%d %H:%M:%S").to_string()),
+                error
+            )),
+            Alert::TopologyChange { event } => self.templates.render(channel, event),
+            Alert::Alarm {
+                resource,
+                description,
+                raised_at,
+                ..
+            } => Ok(format!(
+                "{}: {} (raised at {})",
+                bold(resource),
+                description,
+                raised_at.format("%Y-%m-%d %H:%M:%S")
+            )),
+            Alert::Threshold { detail, at, .. } => Ok(format!(
+                "{}: {}",
+                bold(&at.format("%Y-%m-%d %H:%M:%S").to_string()),
+                detail
+            )),
+        }
+    }
+
+    async fn post(&self, channel: &ChatChannelConfig, alert: &Alert) -> Result<()> {
+        let message = self.message(channel.kind, alert)?;
+        let response = self
+            .client
+            .post(&channel.webhook_url)
+            .json(&message)
+            .send()
+            .await
+            .map_err(|err| Error::Custom(format!("Failed to post to {}: {}", channel.name, err)))?;
+        if !response.status().is_success() {
+            return Err(Error::Custom(format!(
+                "Failed to post to {}: {}",
+                channel.name,
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the one-line summary of `alert`
+pub(super) fn title(alert: &Alert) -> String {
+    match alert {
+        Alert::DeviceDown { host, .. } => format!("Device {} down", host),
+        Alert::TopologyChange { event } => format!("Topology change on {}", event.host),
+        Alert::Alarm { host, .. } => format!("Alarm on {}", host),
+        Alert::Threshold {
+            host,
+            threshold,
+            state,
+            ..
+        } => {
+            let condition = match threshold {
+                Threshold::FailedPolls => "Polls failing",
+                Threshold::Latency => "High latency",
+            };
+            match state {
+                ThresholdState::Raised => format!("{} on {}", condition, host),
+                ThresholdState::Cleared => format!("{} on {} cleared", condition, host),
+            }
+        }
+    }
+}
+
+/// Returns the Teams card color of `severity`
+fn color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "1976D2",
+        Severity::Warning => "F9A825",
+        Severity::Critical => "D32F2F",
+    }
+}
@@ -0,0 +1,339 @@
+use super::templates::{self, NotificationTemplates};
+use super::{chat, Alert, Channel, Severity};
+use crate::events::ChangeEvent;
+use crate::{Error, Result};
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Local};
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Settings of the email channel, the `[notifications.email]` section of `config.toml`
+///
+/// ```toml
+/// [notifications.email]
+/// smtp_host = "smtp.example.com"
+/// username = "noc"
+/// password = "secret"
+/// from = "Device Manager <noc@example.com>"
+/// to = ["oncall@example.com"]
+/// min_severity = "critical"
+/// device_tags = ["production"]
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    #[serde(default = "default_port")]
+    pub smtp_port: u16,
+    #[serde(default = "default_starttls")]
+    pub starttls: bool, // Plain SMTP when disabled, only for local relays
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    #[serde(default = "default_min_severity")]
+    pub min_severity: Severity, // Less severe events are not emailed
+    #[serde(default)]
+    pub device_tags: Vec<String>, // Only devices with one of these tags, every device if empty
+    #[serde(default = "default_subject")]
+    pub subject: String, // Tera template, rendered like the email body
+    #[serde(default)]
+    pub digest: DigestConfig,
+}
+
+/// Rate limiting of the email channel
+///
+/// Each device may send `max_immediate` emails per `window`; further events of the
+/// window are grouped in a single digest sent when the window closes, so a flapping
+/// device sends a handful of emails instead of hundreds.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DigestConfig {
+    #[serde(default = "default_window")]
+    pub window: u64, // Seconds
+    #[serde(default = "default_max_immediate")]
+    pub max_immediate: usize,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        DigestConfig {
+            window: default_window(),
+            max_immediate: default_max_immediate(),
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    587
+}
+
+fn default_starttls() -> bool {
+    true
+}
+
+fn default_min_severity() -> Severity {
+    Severity::Critical
+}
+
+fn default_subject() -> String {
+    "[Device Manager] {{ host }}: {{ object }} {{ change.type }}".to_string()
+}
+
+fn default_window() -> u64 {
+    600
+}
+
+fn default_max_immediate() -> usize {
+    3
+}
+
+/// What the email channel did with an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    Filtered, // Below the severity or outside the device tags
+    Sent,     // Emailed on its own
+    Digested, // Held for the digest of its device
+}
+
+/// Emails of a device in the current rate limiting window
+struct DeviceWindow {
+    started: DateTime<Local>,
+    sent: usize,
+    pending: Vec<ChangeEvent>, // Events of the digest
+}
+
+/// Email channel: sends critical events over SMTP
+pub struct EmailNotifier {
+    config: EmailConfig,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    templates: NotificationTemplates,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    windows: HashMap<String, DeviceWindow>,
+}
+
+impl EmailNotifier {
+    /// Creates the channel, the body of the emails being the `email` template
+    ///
+    /// # Returns
+    /// - `Ok(EmailNotifier)`: If the addresses and the subject template are valid
+    /// - `Err(Error)`: Otherwise
+    pub fn new(config: EmailConfig, templates: NotificationTemplates) -> Result<Self> {
+        let mailbox = |address: &str| {
+            address.parse::<Mailbox>().map_err(|err| {
+                Error::Invalid(format!("Invalid email address {}: {}", address, err))
+            })
+        };
+        let from = mailbox(&config.from)?;
+        let to = config
+            .to
+            .iter()
+            .map(|address| mailbox(address))
+            .collect::<Result<Vec<Mailbox>>>()?;
+        if to.is_empty() {
+            return Err(Error::Invalid(
+                "Invalid email config: no recipient".to_string(),
+            ));
+        }
+        templates::validate(&config.subject)?;
+
+        let mut transport = if config.starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+                .map_err(|err| Error::Invalid(format!("Invalid SMTP host: {}", err)))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+        }
+        .port(config.smtp_port);
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            let credentials = Credentials::new(username.clone(), password.clone());
+            transport = transport.credentials(credentials);
+        }
+
+        Ok(EmailNotifier {
+            from,
+            to,
+            templates,
+            transport: transport.build(),
+            windows: HashMap::new(),
+            config,
+        })
+    }
+
+    /// Returns whether `event` of a device tagged `device_tags` is worth an email
+    pub fn accepts(&self, event: &ChangeEvent, device_tags: &[String]) -> bool {
+        self.accepts_severity(Severity::of(event), device_tags)
+    }
+
+    fn accepts_severity(&self, severity: Severity, device_tags: &[String]) -> bool {
+        severity >= self.config.min_severity
+            && (self.config.device_tags.is_empty()
+                || self
+                    .config
+                    .device_tags
+                    .iter()
+                    .any(|tag| device_tags.contains(tag)))
+    }
+
+    /// Emails `event`, or holds it for the digest if its device already sent too many
+    ///
+    /// # Arguments
+    /// - `event`: The event to notify
+    /// - `device_tags`: The tags of the device the event comes from
+    ///
+    /// # Returns
+    /// - `Ok(Delivery)`: What was done with the event
+    /// - `Err(Error)`: If the SMTP server refused the email or could not be reached
+    pub async fn notify(
+        &mut self,
+        event: &ChangeEvent,
+        device_tags: &[String],
+    ) -> Result<Delivery> {
+        if !self.accepts(event, device_tags) {
+            return Ok(Delivery::Filtered);
+        }
+
+        let now = Local::now();
+        let window = Duration::seconds(self.config.digest.window as i64);
+        let expired = self
+            .windows
+            .get(&event.host)
+            .is_some_and(|device| now - device.started >= window);
+        if expired {
+            self.send_digest(&event.host).await?;
+        }
+
+        let device = self
+            .windows
+            .entry(event.host.clone())
+            .or_insert_with(|| DeviceWindow {
+                started: now,
+                sent: 0,
+                pending: vec![],
+            });
+        if device.sent >= self.config.digest.max_immediate {
+            device.pending.push(event.clone());
+            return Ok(Delivery::Digested);
+        }
+        device.sent += 1;
+
+        let subject = templates::render_source(&self.config.subject, event)?;
+        let body = self.templates.render(Channel::Email, event)?;
+        self.send(subject, body).await?;
+        Ok(Delivery::Sent)
+    }
+
+    /// Emails `alert`, topology changes going through [`EmailNotifier::notify`]
+    ///
+    /// The other alerts are sent on their own when at or above the severity: a device or
+    /// a threshold only raises them once per state change, so they are not digested.
+    ///
+    /// # Returns
+    /// - `Ok(Delivery)`: What was done with the alert
+    /// - `Err(Error)`: If the SMTP server refused the email or could not be reached
+    pub async fn notify_alert(
+        &mut self,
+        alert: &Alert,
+        device_tags: &[String],
+    ) -> Result<Delivery> {
+        let body = match alert {
+            Alert::TopologyChange { event } => return self.notify(event, device_tags).await,
+            _ if !self.accepts_severity(alert.severity(), device_tags) => {
+                return Ok(Delivery::Filtered)
+            }
+            Alert::DeviceDown { error, since, .. } => format!(
+                "Unreachable since {}: {}",
+                since.format("%Y-%m-%d %H:%M:%S"),
+                error
+            ),
+            Alert::Alarm {
+                resource,
+                description,
+                raised_at,
+                ..
+            } => format!(
+                "{}: {} (raised at {})",
+                resource,
+                description,
+                raised_at.format("%Y-%m-%d %H:%M:%S")
+            ),
+            Alert::Threshold { detail, at, .. } => {
+                format!("{}: {}", at.format("%Y-%m-%d %H:%M:%S"), detail)
+            }
+        };
+        let subject = format!("[Device Manager] {}", chat::title(alert));
+        self.send(subject, body).await?;
+        Ok(Delivery::Sent)
+    }
+
+    /// Sends the digests of the windows closed at `now`, and returns how many were sent
+    ///
+    /// Called periodically, so held events are not delayed until the next event of their
+    /// device.
+    pub async fn flush(&mut self, now: DateTime<Local>) -> Result<usize> {
+        let window = Duration::seconds(self.config.digest.window as i64);
+        let closed: Vec<String> = self
+            .windows
+            .iter()
+            .filter(|(_, device)| now - device.started >= window)
+            .map(|(host, _)| host.clone())
+            .collect();
+
+        let mut sent = 0;
+        for host in closed {
+            if self.send_digest(&host).await? {
+                sent += 1;
+            }
+        }
+        Ok(sent)
+    }
+
+    /// Closes the window of `host`, emailing its held events if any
+    async fn send_digest(&mut self, host: &str) -> Result<bool> {
+        let Some(device) = self.windows.remove(host) else {
+            return Ok(false);
+        };
+        if device.pending.is_empty() {
+            return Ok(false);
+        }
+
+        let lines = device
+            .pending
+            .iter()
+            .map(|event| self.templates.render(Channel::Email, event))
+            .collect::<Result<Vec<String>>>()?;
+        let subject = format!(
+            "[Device Manager] {}: {} more events since {}",
+            host,
+            lines.len(),
+            device.started.format("%Y-%m-%d %H:%M:%S")
+        );
+        self.send(subject, lines.join("\n")).await?;
+        info!("Email digest of {} events sent for {}", lines.len(), host);
+        Ok(true)
+    }
+
+    async fn send(&self, subject: String, body: String) -> Result<()> {
+        let mut message = Message::builder()
+            .from(self.from.clone())
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+        let message = message
+            .body(body)
+            .map_err(|err| Error::Invalid(format!("Invalid email: {}", err)))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|err| Error::Custom(format!("Failed to send email: {}", err)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use super::Alert;
+use crate::tenant::default_tenant;
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+/// Alerts kept by the history, the oldest being dropped beyond it
+pub const HISTORY_CAPACITY: usize = 1_000;
+
+/// An alert as recorded in the history
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StoredAlert {
+    #[serde(default = "default_tenant")]
+    pub tenant_id: String, // Customer owning the device the alert is about
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub suppressed: bool, // Raised while its device was under maintenance
+    #[serde(flatten)]
+    pub alert: Alert,
+}
+
+/// Last alerts raised by the thresholds, kept in memory
+///
+/// Like the events of a maintenance window, alerts raised while their device is under
+/// maintenance are recorded tagged `suppressed` and not delivered to the notification
+/// channels.
+#[derive(Default)]
+pub struct AlertHistory {
+    alerts: VecDeque<StoredAlert>,
+}
+
+impl AlertHistory {
+    /// Creates an empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an alert of `tenant_id`, dropping the oldest one beyond
+    /// [`HISTORY_CAPACITY`]
+    pub fn record(&mut self, tenant_id: &str, alert: Alert, suppressed: bool) {
+        if self.alerts.len() == HISTORY_CAPACITY {
+            self.alerts.pop_front();
+        }
+        self.alerts.push_back(StoredAlert {
+            tenant_id: tenant_id.to_string(),
+            suppressed,
+            alert,
+        });
+    }
+
+    /// Returns the alerts of `tenant_id`, most recent first
+    pub fn list(&self, tenant_id: &str) -> Vec<&StoredAlert> {
+        self.alerts
+            .iter()
+            .rev()
+            .filter(|stored| stored.tenant_id == tenant_id)
+            .collect()
+    }
+}
@@ -0,0 +1,165 @@
+pub mod chat;
+pub mod email;
+pub mod history;
+pub mod routing;
+pub mod spool;
+pub mod templates;
+pub mod thresholds;
+
+use crate::events::{ChangeEvent, ChangeKind};
+use chat::ChatChannelConfig;
+use email::EmailConfig;
+use thresholds::{Threshold, ThresholdConfig, ThresholdState};
+
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// Settings of the notification channels, the `[notifications]` section of `config.toml`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct NotificationsConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<EmailConfig>, // No emails are sent if unset
+    #[serde(default)]
+    pub chat: Vec<ChatChannelConfig>, // Slack and Teams incoming webhooks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing: Option<PathBuf>, // YAML routing rules, see `routing::RoutingRules`
+    #[serde(default)]
+    pub thresholds: ThresholdConfig, // Poll failures and latency raising alerts
+}
+
+/// Channel a notification is delivered through
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Webhook,
+    Email,
+    Slack,
+    Teams,
+}
+
+impl Channel {
+    /// Every channel, in a stable order
+    pub const ALL: [Channel; 4] = [
+        Channel::Webhook,
+        Channel::Email,
+        Channel::Slack,
+        Channel::Teams,
+    ];
+
+    /// Returns the channel name, also used as its template name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Channel::Webhook => "webhook",
+            Channel::Email => "email",
+            Channel::Slack => "slack",
+            Channel::Teams => "teams",
+        }
+    }
+}
+
+/// Severity of a notification, used to decide which channels are worth notifying
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,     // Objects added or changed
+    Warning,  // Lifecycle transitions, usually planned work, and rule violations
+    Critical, // Objects gone from the topology
+}
+
+impl Severity {
+    /// Returns the severity of `event`
+    pub fn of(event: &ChangeEvent) -> Self {
+        match event.change {
+            ChangeKind::Added | ChangeKind::Changed => Severity::Info,
+            ChangeKind::LifecycleTransition { .. } | ChangeKind::Violation { .. } => {
+                Severity::Warning
+            }
+            ChangeKind::Removed => Severity::Critical,
+        }
+    }
+
+    /// Returns the severity name, as written in the config
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+/// Something worth telling the operators, as sent to the chat channels
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Alert {
+    DeviceDown {
+        host: String,
+        error: String, // Why the last poll failed
+        since: DateTime<Local>,
+    },
+    TopologyChange {
+        event: ChangeEvent,
+    },
+    Alarm {
+        host: String,
+        severity: Severity,
+        resource: String, // Object the alarm is raised on, e.g. a port name
+        description: String,
+        raised_at: DateTime<Local>,
+    },
+    Threshold {
+        host: String,
+        threshold: Threshold,
+        state: ThresholdState,
+        detail: String, // e.g. `3 consecutive polls failed, last: ...`
+        at: DateTime<Local>,
+    },
+}
+
+impl Alert {
+    /// Returns the device the alert is about
+    pub fn host(&self) -> &str {
+        match self {
+            Alert::DeviceDown { host, .. }
+            | Alert::Alarm { host, .. }
+            | Alert::Threshold { host, .. } => host,
+            Alert::TopologyChange { event } => &event.host,
+        }
+    }
+
+    /// Returns when the alert was raised
+    pub fn date(&self) -> DateTime<Local> {
+        match self {
+            Alert::DeviceDown { since, .. } => *since,
+            Alert::TopologyChange { event } => event.date,
+            Alert::Alarm { raised_at, .. } => *raised_at,
+            Alert::Threshold { at, .. } => *at,
+        }
+    }
+
+    /// Returns the severity of the alert, a device down being always critical
+    ///
+    /// Failing polls are critical and slow devices a warning; their clear alerts are
+    /// informational.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Alert::DeviceDown { .. } => Severity::Critical,
+            Alert::Threshold {
+                state: ThresholdState::Cleared,
+                ..
+            } => Severity::Info,
+            Alert::Threshold {
+                threshold: Threshold::FailedPolls,
+                ..
+            } => Severity::Critical,
+            Alert::Threshold {
+                threshold: Threshold::Latency,
+                ..
+            } => Severity::Warning,
+            Alert::TopologyChange { event } => Severity::of(event),
+            Alert::Alarm { severity, .. } => *severity,
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use super::{Alert, Severity};
+use crate::models::connectivity_service::LAYER_PROTOCOLS;
+use crate::setup::watch::{self, FileWatcher};
+use crate::{Error, Result};
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Destination of a notification
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Sink {
+    Webhook,
+    Email,
+    Slack,
+    Teams,
+    Kafka,
+}
+
+/// Sinks the app delivers to, every one of them without routing rules
+pub const DELIVERED_SINKS: [Sink; 3] = [Sink::Email, Sink::Slack, Sink::Teams];
+
+impl Sink {
+    /// Returns the name of the sink, as written in the rules
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Sink::Webhook => "webhook",
+            Sink::Email => "email",
+            Sink::Slack => "slack",
+            Sink::Teams => "teams",
+            Sink::Kafka => "kafka",
+        }
+    }
+
+    /// Returns whether the app has a delivery path for the sink
+    pub fn is_delivered(self) -> bool {
+        DELIVERED_SINKS.contains(&self)
+    }
+}
+
+/// Type of an alert, as matched by the rules
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    DeviceDown,
+    TopologyChange,
+    Alarm,
+    Threshold,
+}
+
+impl EventType {
+    /// Returns the type of `alert`
+    pub fn of(alert: &Alert) -> Self {
+        match alert {
+            Alert::DeviceDown { .. } => EventType::DeviceDown,
+            Alert::TopologyChange { .. } => EventType::TopologyChange,
+            Alert::Alarm { .. } => EventType::Alarm,
+            Alert::Threshold { .. } => EventType::Threshold,
+        }
+    }
+}
+
+/// Conditions of a rule, every one of them must hold; an empty list matches anything
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct RuleMatch {
+    pub event_types: Vec<EventType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_severity: Option<Severity>,
+    pub device_tags: Vec<String>,     // The device must have one of them
+    pub layer_protocols: Vec<String>, // One of `LAYER_PROTOCOLS`
+}
+
+/// A routing rule: the sinks receiving the events it matches
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RoutingRule {
+    pub name: String,
+    #[serde(default, rename = "match")]
+    pub conditions: RuleMatch,
+    pub sinks: Vec<Sink>,
+    #[serde(default)]
+    pub stop: bool, // Later rules are not evaluated when this one matches
+}
+
+/// What the rules know about an event besides the alert itself
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoutingContext<'a> {
+    pub device_tags: &'a [String],
+    pub layer_protocol: Option<&'a str>, // Layer of the object, e.g. `PHOTONIC_MEDIA`
+}
+
+impl RoutingRule {
+    /// Returns whether the rule matches `alert`
+    pub fn matches(&self, alert: &Alert, context: &RoutingContext) -> bool {
+        let conditions = &self.conditions;
+        (conditions.event_types.is_empty()
+            || conditions.event_types.contains(&EventType::of(alert)))
+            && conditions
+                .min_severity
+                .is_none_or(|severity| alert.severity() >= severity)
+            && (conditions.device_tags.is_empty()
+                || conditions
+                    .device_tags
+                    .iter()
+                    .any(|tag| context.device_tags.contains(tag)))
+            && (conditions.layer_protocols.is_empty()
+                || context.layer_protocol.is_some_and(|layer| {
+                    conditions
+                        .layer_protocols
+                        .iter()
+                        .any(|protocol| protocol == layer)
+                }))
+    }
+}
+
+/// The routing rules file, e.g. `routing.yaml`
+///
+/// ```yaml
+/// default: [email]
+/// rules:
+///   - name: optical-outages
+///     match:
+///       min_severity: critical
+///       device_tags: [production]
+///       layer_protocols: [PHOTONIC_MEDIA]
+///     sinks: [email, slack]
+///     stop: true
+///   - name: everything-to-teams
+///     sinks: [teams]
+/// ```
+///
+/// Rules are evaluated in order and the sinks of every matching rule are combined,
+/// until a matching rule has `stop` set. Events no rule matches go to `default`.
+/// Only the sinks the app delivers to (email, slack and teams) are accepted.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RoutingRules {
+    #[serde(default)]
+    pub default: Vec<Sink>,
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+}
+
+impl RoutingRules {
+    /// Parses and validates the YAML rules
+    ///
+    /// # Returns
+    /// - `Ok(RoutingRules)`: If the rules are valid
+    /// - `Err(Error)`: If the YAML is malformed or a rule is invalid
+    pub fn parse(content: &str) -> Result<Self> {
+        let rules: RoutingRules = serde_yaml::from_str(content)
+            .map_err(|err| Error::Invalid(format!("Invalid routing rules: {}", err)))?;
+
+        if let Some(sink) = rules.default.iter().find(|sink| !sink.is_delivered()) {
+            return Err(Error::Invalid(format!(
+                "Invalid routing rules: no delivery for default sink {:?}",
+                sink
+            )));
+        }
+        for rule in &rules.rules {
+            if rule.sinks.is_empty() {
+                return Err(Error::Invalid(format!(
+                    "Invalid routing rule {}: no sink",
+                    rule.name
+                )));
+            }
+            if let Some(sink) = rule.sinks.iter().find(|sink| !sink.is_delivered()) {
+                return Err(Error::Invalid(format!(
+                    "Invalid routing rule {}: no delivery for sink {:?}",
+                    rule.name, sink
+                )));
+            }
+            if let Some(layer) = rule
+                .conditions
+                .layer_protocols
+                .iter()
+                .find(|layer| !LAYER_PROTOCOLS.contains(&layer.as_str()))
+            {
+                return Err(Error::Invalid(format!(
+                    "Invalid routing rule {}: unknown layer protocol {}",
+                    rule.name, layer
+                )));
+            }
+        }
+        Ok(rules)
+    }
+
+    /// Reads and parses the rules file at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|err| {
+            Error::Custom(format!(
+                "Failed to read routing rules {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        Self::parse(&content)
+    }
+
+    /// Returns the sinks receiving `alert`
+    pub fn sinks(&self, alert: &Alert, context: &RoutingContext) -> BTreeSet<Sink> {
+        let mut sinks = BTreeSet::new();
+        let mut matched = false;
+        for rule in &self.rules {
+            if rule.matches(alert, context) {
+                matched = true;
+                sinks.extend(rule.sinks.iter().copied());
+                if rule.stop {
+                    break;
+                }
+            }
+        }
+
+        if !matched {
+            sinks.extend(self.default.iter().copied());
+        }
+        sinks
+    }
+}
+
+/// State shared between the watcher handle and the file system notifications
+struct Shared {
+    path: PathBuf,
+    rules: RwLock<RoutingRules>,
+}
+
+impl Shared {
+    fn reload(&self) -> Result<()> {
+        self.update(&watch::read(&self.path, "routing rules")?)
+    }
+
+    fn update(&self, content: &str) -> Result<()> {
+        let rules = RoutingRules::parse(content)?;
+        info!(
+            "Routing rules reloaded from {}: {} rules",
+            self.path.display(),
+            rules.rules.len()
+        );
+        *self.rules.write().unwrap() = rules;
+        Ok(())
+    }
+}
+
+/// Keeps the routing rules in sync with their file without restarting the service
+///
+/// Every valid new version of the file replaces the rules, an invalid one being logged
+/// and the previous rules kept.
+pub struct RoutingWatcher {
+    shared: Arc<Shared>,
+    _watcher: FileWatcher,
+}
+
+impl RoutingWatcher {
+    /// Loads the rules at `path` and starts watching them
+    pub fn start(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let shared = Arc::new(Shared {
+            rules: RwLock::new(RoutingRules::load(&path)?),
+            path: path.clone(),
+        });
+
+        let watcher = {
+            let shared = Arc::clone(&shared);
+            FileWatcher::start(&path, "routing rules", move |content| {
+                shared.update(content)
+            })?
+        };
+
+        Ok(RoutingWatcher {
+            shared,
+            _watcher: watcher,
+        })
+    }
+
+    /// Returns the rules currently applied
+    pub fn current(&self) -> RoutingRules {
+        self.shared.rules.read().unwrap().clone()
+    }
+
+    /// Returns the sinks receiving `alert` with the rules currently applied
+    pub fn sinks(&self, alert: &Alert, context: &RoutingContext) -> BTreeSet<Sink> {
+        self.shared.rules.read().unwrap().sinks(alert, context)
+    }
+
+    /// Reloads the file now
+    pub fn reload(&self) -> Result<()> {
+        self.shared.reload()
+    }
+}
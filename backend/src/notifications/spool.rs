@@ -0,0 +1,242 @@
+use crate::persist;
+use crate::{Error, Result};
+
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// What to do with a new event when both the memory buffer and the disk spool are full
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DropPolicy {
+    #[default]
+    DropOldest, // Discard the oldest undelivered event to make room
+    DropNewest, // Discard the incoming event
+}
+
+/// Limits of a spool queue
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SpoolConfig {
+    pub memory_capacity: usize, // Events held in memory before spilling to disk
+    pub disk_capacity: usize,   // Events held on disk before the drop policy applies
+    #[serde(default)]
+    pub drop_policy: DropPolicy,
+}
+
+impl Default for SpoolConfig {
+    fn default() -> Self {
+        SpoolConfig {
+            memory_capacity: 1_000,
+            disk_capacity: 100_000,
+            drop_policy: DropPolicy::default(),
+        }
+    }
+}
+
+/// Counters of a spool queue
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct SpoolMetrics {
+    pub in_memory: usize, // Events waiting in memory
+    pub on_disk: usize,   // Events waiting on disk
+    pub spilled: u64,     // Events written to disk since startup
+    pub dropped: u64,     // Events discarded by the drop policy since startup
+    pub delivered: u64,   // Events delivered since startup
+}
+
+/// FIFO queue of undelivered notifications that spills to disk
+///
+/// Events are kept in memory up to `memory_capacity`; once the buffer is full, new
+/// events are appended to a JSON Lines file so slow or unavailable targets neither
+/// exhaust memory nor lose events. Every event on disk is newer than every event in
+/// memory, so delivery order is preserved. The spool file is reloaded by
+/// [`SpoolQueue::open`] after a restart.
+pub struct SpoolQueue<T> {
+    config: SpoolConfig,
+    path: PathBuf,
+    memory: VecDeque<T>,
+    on_disk: usize,
+    metrics: SpoolMetrics,
+}
+
+impl<T: Serialize + DeserializeOwned> SpoolQueue<T> {
+    /// Opens the queue spooling to `path`, picking up events left by a previous run
+    pub fn open(path: impl AsRef<Path>, config: SpoolConfig) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let on_disk = if path.exists() {
+            read_spool::<T>(&path)?.len()
+        } else {
+            0
+        };
+
+        let mut queue = SpoolQueue {
+            config,
+            path,
+            memory: VecDeque::new(),
+            on_disk,
+            metrics: SpoolMetrics::default(),
+        };
+        queue.refill()?;
+        Ok(queue)
+    }
+
+    /// Returns the number of undelivered events
+    pub fn len(&self) -> usize {
+        self.memory.len() + self.on_disk
+    }
+
+    /// Returns whether every event was delivered
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the queue counters
+    pub fn metrics(&self) -> SpoolMetrics {
+        SpoolMetrics {
+            in_memory: self.memory.len(),
+            on_disk: self.on_disk,
+            ..self.metrics.clone()
+        }
+    }
+
+    /// Queues an event for delivery
+    pub fn push(&mut self, event: T) -> Result<()> {
+        if self.on_disk == 0 && self.memory.len() < self.config.memory_capacity {
+            self.memory.push_back(event);
+            return Ok(());
+        }
+
+        if self.on_disk >= self.config.disk_capacity {
+            self.metrics.dropped += 1;
+            match self.config.drop_policy {
+                DropPolicy::DropNewest => {
+                    warn!("Notification spool is full, dropping the newest event");
+                    return Ok(());
+                }
+                DropPolicy::DropOldest => {
+                    warn!("Notification spool is full, dropping the oldest event");
+                    let mut spooled = read_spool::<T>(&self.path)?;
+                    if self.memory.pop_front().is_some() {
+                        // Move the oldest spooled event up to keep memory before disk
+                        self.memory.extend(spooled.pop_front());
+                    } else {
+                        spooled.pop_front();
+                    }
+                    self.write_spool(&spooled)?;
+
+                    if self.on_disk == 0 && self.memory.len() < self.config.memory_capacity {
+                        self.memory.push_back(event);
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        self.append(&event)?;
+        self.metrics.spilled += 1;
+        Ok(())
+    }
+
+    /// Returns the oldest undelivered event without removing it
+    pub fn peek(&self) -> Option<&T> {
+        self.memory.front()
+    }
+
+    /// Removes the oldest event once it has been delivered
+    pub fn pop(&mut self) -> Result<Option<T>> {
+        let event = self.memory.pop_front();
+        if event.is_some() {
+            self.metrics.delivered += 1;
+        }
+        self.refill()?;
+        Ok(event)
+    }
+
+    /// Delivers queued events in order until the queue is empty or delivery fails
+    ///
+    /// Called when the target recovers. Returns the number of delivered events; the
+    /// event that failed stays at the head of the queue.
+    pub fn drain(&mut self, mut deliver: impl FnMut(&T) -> Result<()>) -> Result<usize> {
+        let mut delivered = 0;
+        while let Some(event) = self.peek() {
+            if let Err(err) = deliver(event) {
+                warn!("Notification delivery failed, keeping the event: {}", err);
+                break;
+            }
+            self.pop()?;
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
+    /// Moves spooled events back to memory once the memory buffer is empty
+    fn refill(&mut self) -> Result<()> {
+        if !self.memory.is_empty() || self.on_disk == 0 {
+            return Ok(());
+        }
+
+        let mut spooled = read_spool::<T>(&self.path)?;
+        let batch = spooled.len().min(self.config.memory_capacity.max(1));
+        self.memory.extend(spooled.drain(..batch));
+        self.write_spool(&spooled)
+    }
+
+    /// Appends an event to the spool file
+    fn append(&mut self, event: &T) -> Result<()> {
+        let line = serde_json::to_string(event)
+            .map_err(|err| Error::Custom(format!("Failed to serialize event: {}", err)))?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| spool_error(&self.path, err))?;
+        writeln!(file, "{}", line).map_err(|err| spool_error(&self.path, err))?;
+        self.on_disk += 1;
+        Ok(())
+    }
+
+    /// Rewrites the spool file with `events`
+    fn write_spool(&mut self, events: &VecDeque<T>) -> Result<()> {
+        let mut content = String::new();
+        for event in events {
+            let line = serde_json::to_string(event)
+                .map_err(|err| Error::Custom(format!("Failed to serialize event: {}", err)))?;
+            content.push_str(&line);
+            content.push('\n');
+        }
+        persist::write_atomic(&self.path, content.as_bytes(), "notification spool")?;
+        self.on_disk = events.len();
+        Ok(())
+    }
+}
+
+/// Reads every event of a spool file
+fn read_spool<T: DeserializeOwned>(path: &Path) -> Result<VecDeque<T>> {
+    let content = fs::read_to_string(path).map_err(|err| spool_error(path, err))?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|err| {
+                Error::Custom(format!(
+                    "Invalid event in spool {}: {}",
+                    path.display(),
+                    err
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Maps a spool file failure to an error
+fn spool_error(path: &Path, err: std::io::Error) -> Error {
+    Error::Custom(format!(
+        "Notification spool {} failed: {}",
+        path.display(),
+        err
+    ))
+}
@@ -0,0 +1,118 @@
+use super::Channel;
+use crate::events::{ChangeEvent, ChangeKind, ObjectKind};
+use crate::models::lifecycle_state::LifecycleState;
+use crate::{Error, Result};
+
+use std::collections::HashMap;
+use std::error::Error as _;
+
+use chrono::Local;
+use tera::{Context, Tera};
+use uuid::Uuid;
+
+const DEFAULT_WEBHOOK: &str = r#"{"host": "{{ host }}", "object": "{{ object }}", "uuid": "{{ uuid }}", "change": "{{ change.type }}", "date": "{{ date }}"}"#;
+const DEFAULT_EMAIL: &str =
+    "Device {{ host }}: {{ object }} {{ uuid }} {{ change.type }} at {{ date }}";
+const DEFAULT_SLACK: &str =
+    ":satellite_antenna: *{{ host }}* {{ object }} `{{ uuid }}` {{ change.type }}";
+const DEFAULT_TEAMS: &str = "**{{ host }}** {{ object }} `{{ uuid }}` {{ change.type }}";
+
+/// Message bodies of the notification channels, written as Tera templates
+///
+/// Templates render a [`ChangeEvent`]: `host`, `object`, `uuid`, `date`, and `change`
+/// with its `type` (plus `from`/`to` for lifecycle transitions, `rule`/`detail` for
+/// violations). Every channel starts with a built-in template that operators can
+/// override from the config or the DB.
+pub struct NotificationTemplates {
+    tera: Tera,
+}
+
+impl Default for NotificationTemplates {
+    fn default() -> Self {
+        let mut tera = Tera::default();
+        // Messages are not HTML: never escape the rendered values
+        tera.autoescape_on(vec![]);
+        tera.add_raw_templates([
+            (Channel::Webhook.as_str(), DEFAULT_WEBHOOK),
+            (Channel::Email.as_str(), DEFAULT_EMAIL),
+            (Channel::Slack.as_str(), DEFAULT_SLACK),
+            (Channel::Teams.as_str(), DEFAULT_TEAMS),
+        ])
+        .expect("Built-in notification templates are valid");
+
+        NotificationTemplates { tera }
+    }
+}
+
+impl NotificationTemplates {
+    /// Creates the templates, overriding the built-in ones with `overrides`
+    pub fn new(overrides: &HashMap<Channel, String>) -> Result<Self> {
+        let mut templates = Self::default();
+        for (channel, source) in overrides {
+            templates.set(*channel, source)?;
+        }
+        Ok(templates)
+    }
+
+    /// Replaces the template of `channel`, keeping the previous one if `source` is invalid
+    pub fn set(&mut self, channel: Channel, source: &str) -> Result<()> {
+        validate(source)?;
+        self.tera
+            .add_raw_template(channel.as_str(), source)
+            .map_err(template_error)
+    }
+
+    /// Renders the message of `channel` for `event`
+    pub fn render(&self, channel: Channel, event: &ChangeEvent) -> Result<String> {
+        let context = Context::from_serialize(event).map_err(template_error)?;
+        self.tera
+            .render(channel.as_str(), &context)
+            .map_err(template_error)
+    }
+}
+
+/// Checks that `source` is a valid template that renders the sample event
+///
+/// This is the body of `POST /notifications/templates/validate`.
+pub fn validate(source: &str) -> Result<()> {
+    preview(source).map(|_| ())
+}
+
+/// Renders `source` with a sample event, for the template preview
+pub fn preview(source: &str) -> Result<String> {
+    render_source(source, &sample_event())
+}
+
+/// Renders the template `source` for `event`, without registering it
+pub fn render_source(source: &str, event: &ChangeEvent) -> Result<String> {
+    let context = Context::from_serialize(event).map_err(template_error)?;
+    Tera::one_off(source, &context, false).map_err(template_error)
+}
+
+/// Event used to preview templates
+///
+/// A lifecycle transition, so templates may use every field an event can carry.
+pub fn sample_event() -> ChangeEvent {
+    ChangeEvent {
+        host: "10.95.87.21".to_string(),
+        topology_uuid: Uuid::nil(),
+        object: ObjectKind::Link,
+        uuid: Uuid::nil(),
+        change: ChangeKind::LifecycleTransition {
+            from: Some(LifecycleState::Planned),
+            to: Some(LifecycleState::Installed),
+        },
+        date: Local::now(),
+    }
+}
+
+/// Maps a Tera failure to an error, keeping the underlying cause (e.g. the parse error)
+fn template_error(err: tera::Error) -> Error {
+    let mut message = format!("Invalid template: {}", err);
+    let mut source = err.source();
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    Error::Custom(message)
+}
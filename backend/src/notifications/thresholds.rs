@@ -0,0 +1,179 @@
+use super::Alert;
+use crate::latency::LatencySample;
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+
+/// Thresholds raising alerts, the `[notifications.thresholds]` section of `config.toml`
+///
+/// Every threshold is off unless set.
+///
+/// ```toml
+/// [notifications.thresholds]
+/// failed_polls = 3
+/// latency_ms = 500
+/// latency_minutes = 5
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ThresholdConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_polls: Option<u32>, // Consecutive failed polls raising an alert
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<f64>, // Latency raising an alert once exceeded long enough
+    #[serde(default = "default_latency_minutes")]
+    pub latency_minutes: i64, // How long the latency must stay above `latency_ms`
+}
+
+fn default_latency_minutes() -> i64 {
+    5
+}
+
+impl Default for ThresholdConfig {
+    fn default() -> Self {
+        ThresholdConfig {
+            failed_polls: None,
+            latency_ms: None,
+            latency_minutes: default_latency_minutes(),
+        }
+    }
+}
+
+/// Condition watched by a threshold
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Threshold {
+    FailedPolls,
+    Latency,
+}
+
+/// Whether a threshold alert starts or ends
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ThresholdState {
+    Raised,
+    Cleared,
+}
+
+/// Tracks the polls and latencies of the devices against the configured thresholds
+///
+/// An alert is raised once when a condition starts holding, and cleared once when it
+/// recovers, so a device staying down or slow is not notified at every poll.
+#[derive(Debug, Default)]
+pub struct ThresholdMonitor {
+    failures: HashMap<String, u32>, // Consecutive failed polls by host
+    slow_since: HashMap<String, DateTime<Local>>, // First sample of the current slow period
+    raised: HashSet<(String, Threshold)>,
+}
+
+impl ThresholdMonitor {
+    /// Creates a monitor with no device in breach
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a failed poll of `host`
+    ///
+    /// # Returns
+    /// - `Some(Alert)`: Raised when the failures reach `failed_polls`
+    /// - `None`: Otherwise
+    pub fn poll_failed(
+        &mut self,
+        host: &str,
+        error: &str,
+        config: &ThresholdConfig,
+        now: DateTime<Local>,
+    ) -> Option<Alert> {
+        let failures = self.failures.entry(host.to_string()).or_default();
+        *failures += 1;
+        let limit = config.failed_polls?;
+        if *failures < limit {
+            return None;
+        }
+        let detail = format!("{} consecutive polls failed, last: {}", failures, error);
+        self.raise(host, Threshold::FailedPolls, detail, now)
+    }
+
+    /// Records a successful poll of `host`, clearing its failed polls alert if raised
+    pub fn poll_succeeded(&mut self, host: &str, now: DateTime<Local>) -> Option<Alert> {
+        let failures = self.failures.remove(host).unwrap_or_default();
+        let detail = format!("Polled again after {} failures", failures);
+        self.clear(host, Threshold::FailedPolls, detail, now)
+    }
+
+    /// Records a latency sample of `host`
+    ///
+    /// Unanswered probes leave the state as is, an unreachable device being reported by
+    /// its failed polls.
+    ///
+    /// # Returns
+    /// - `Some(Alert)`: Raised when the latency stayed above `latency_ms` for
+    ///   `latency_minutes`, cleared when it falls back below
+    /// - `None`: Otherwise
+    pub fn latency(
+        &mut self,
+        host: &str,
+        sample: &LatencySample,
+        config: &ThresholdConfig,
+    ) -> Option<Alert> {
+        let latency_ms = sample.latency_ms?;
+        let Some(limit) = config.latency_ms.filter(|limit| latency_ms > *limit) else {
+            self.slow_since.remove(host);
+            let detail = format!("Latency back to {:.0} ms", latency_ms);
+            return self.clear(host, Threshold::Latency, detail, sample.at);
+        };
+        let since = *self.slow_since.entry(host.to_string()).or_insert(sample.at);
+        if sample.at - since < Duration::minutes(config.latency_minutes) {
+            return None;
+        }
+        let detail = format!(
+            "Latency {:.0} ms above {:.0} ms since {}",
+            latency_ms,
+            limit,
+            since.format("%Y-%m-%d %H:%M:%S")
+        );
+        self.raise(host, Threshold::Latency, detail, sample.at)
+    }
+
+    /// Returns whether the `threshold` alert of `host` is raised
+    pub fn is_raised(&self, host: &str, threshold: Threshold) -> bool {
+        self.raised.contains(&(host.to_string(), threshold))
+    }
+
+    fn raise(
+        &mut self,
+        host: &str,
+        threshold: Threshold,
+        detail: String,
+        at: DateTime<Local>,
+    ) -> Option<Alert> {
+        self.raised
+            .insert((host.to_string(), threshold))
+            .then(|| Alert::Threshold {
+                host: host.to_string(),
+                threshold,
+                state: ThresholdState::Raised,
+                detail,
+                at,
+            })
+    }
+
+    fn clear(
+        &mut self,
+        host: &str,
+        threshold: Threshold,
+        detail: String,
+        at: DateTime<Local>,
+    ) -> Option<Alert> {
+        self.raised
+            .remove(&(host.to_string(), threshold))
+            .then(|| Alert::Threshold {
+                host: host.to_string(),
+                threshold,
+                state: ThresholdState::Cleared,
+                detail,
+                at,
+            })
+    }
+}
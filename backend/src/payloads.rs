@@ -0,0 +1,99 @@
+use crate::persist;
+use crate::{Error, Result};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Settings of the raw payload store, the `[payloads]` section of `config.toml`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PayloadConfig {
+    #[serde(default = "compress_default")]
+    pub compress: bool, // Store new payloads zstd compressed
+    #[serde(default = "level_default")]
+    pub level: i32, // zstd compression level, 1 (fast) to 22 (small)
+}
+
+fn compress_default() -> bool {
+    true
+}
+
+fn level_default() -> i32 {
+    zstd::DEFAULT_COMPRESSION_LEVEL
+}
+
+impl Default for PayloadConfig {
+    fn default() -> Self {
+        PayloadConfig {
+            compress: compress_default(),
+            level: level_default(),
+        }
+    }
+}
+
+/// Directory of raw TAPI responses kept for audit and debugging
+///
+/// Raw contexts of big networks are tens of MB each, so payloads are written zstd
+/// compressed (`<id>.json.zst`) unless compression is switched off (`<id>.json`).
+/// Reading is transparent: a payload is found and decoded whatever the setting was
+/// when it was stored.
+pub struct PayloadStore {
+    directory: PathBuf,
+    config: PayloadConfig,
+}
+
+impl PayloadStore {
+    /// Opens the store in `directory`, creating it if needed
+    pub fn open(directory: impl AsRef<Path>, config: PayloadConfig) -> Result<Self> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory).map_err(|err| {
+            Error::Custom(format!(
+                "Failed to create payload directory {}: {}",
+                directory.display(),
+                err
+            ))
+        })?;
+        Ok(PayloadStore { directory, config })
+    }
+
+    /// Stores a payload and returns its id
+    pub fn save(&self, payload: &Value) -> Result<Uuid> {
+        let id = Uuid::new_v4();
+        let content = serde_json::to_vec(payload).map_err(Error::custom)?;
+
+        let (path, content) = if self.config.compress {
+            let compressed = zstd::encode_all(content.as_slice(), self.config.level)
+                .map_err(|err| Error::Custom(format!("Failed to compress payload: {}", err)))?;
+            (self.path(&id, true), compressed)
+        } else {
+            (self.path(&id, false), content)
+        };
+
+        persist::write_atomic(&path, &content, "payload")?;
+        Ok(id)
+    }
+
+    /// Reads a stored payload back
+    pub fn load(&self, id: &Uuid) -> Result<Value> {
+        let compressed = self.path(id, true);
+        let content = if compressed.exists() {
+            let content = fs::read(&compressed).map_err(Error::custom)?;
+            zstd::decode_all(content.as_slice())
+                .map_err(|err| Error::Custom(format!("Corrupted payload {}: {}", id, err)))?
+        } else {
+            fs::read(self.path(id, false))
+                .map_err(|_| Error::NotFound(format!("Not found payload {}", id)))?
+        };
+
+        serde_json::from_slice(&content)
+            .map_err(|err| Error::Custom(format!("Corrupted payload {}: {}", id, err)))
+    }
+
+    fn path(&self, id: &Uuid, compressed: bool) -> PathBuf {
+        let extension = if compressed { "json.zst" } else { "json" };
+        self.directory.join(format!("{}.{}", id, extension))
+    }
+}
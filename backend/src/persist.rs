@@ -0,0 +1,73 @@
+use crate::{Error, Result};
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Reads the JSON file a store is persisted to
+///
+/// # Arguments
+/// - `path`: The file of the store
+/// - `what`: What the file holds, for the error messages (e.g. `jobs`)
+///
+/// # Returns
+/// - `Ok(Some(T))`: The content of the file
+/// - `Ok(None)`: If the file does not exist yet
+/// - `Err(Error)`: If the file cannot be read or is not valid JSON
+pub fn read_json<T: DeserializeOwned>(path: &Path, what: &str) -> Result<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path).map_err(|err| {
+        Error::Custom(format!(
+            "Failed to read {} {}: {}",
+            what,
+            path.display(),
+            err
+        ))
+    })?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|err| Error::Custom(format!("Invalid {} file {}: {}", what, path.display(), err)))
+}
+
+/// Writes `value` as the JSON file of a store, see [`write_atomic`]
+pub fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T, what: &str) -> Result<()> {
+    let content = serde_json::to_vec(value)
+        .map_err(|err| Error::Custom(format!("Failed to serialize {}: {}", what, err)))?;
+    write_atomic(path, &content, what)
+}
+
+/// Replaces the file at `path` with `content`
+///
+/// The content is written and synced to a temporary file next to `path`, then renamed
+/// over it, so a crash leaves either the previous file or the new one, never a
+/// truncated file the store would fail to open on startup.
+pub fn write_atomic(path: &Path, content: &[u8], what: &str) -> Result<()> {
+    let temporary = temporary(path);
+    File::create(&temporary)
+        .and_then(|mut file| {
+            file.write_all(content)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temporary, path))
+        .map_err(|err| {
+            let _ = fs::remove_file(&temporary);
+            Error::Custom(format!(
+                "Failed to write {} {}: {}",
+                what,
+                path.display(),
+                err
+            ))
+        })
+}
+
+/// Returns the temporary file `path` is written to before the rename, `<path>.tmp`
+fn temporary(path: &Path) -> PathBuf {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    PathBuf::from(temporary)
+}
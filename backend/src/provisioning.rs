@@ -0,0 +1,253 @@
+use crate::jobs::{JobKind, JobProgress, JobQueue};
+use crate::models::connectivity_service::{
+    service_path, AdministrativeState, ConnectivityServiceRequest, CreatedService, DryRun,
+    ServiceStateChange, CONNECTIVITY_CONTEXT_PATH,
+};
+use crate::models::device::FieldError;
+use crate::models::topology::Topology;
+use crate::southbound::restconf::RestconfClient;
+use crate::{Error, Result};
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use tracing::info;
+use uuid::Uuid;
+
+/// How a service change is confirmed: devices apply deletions and state changes
+/// asynchronously, so the service is read back until it reflects the change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Confirmation {
+    pub interval: Duration, // Wait between two reads of the service
+    pub attempts: u32,      // Reads before giving up
+}
+
+impl Default for Confirmation {
+    fn default() -> Self {
+        Confirmation {
+            interval: Duration::from_secs(2),
+            attempts: 30,
+        }
+    }
+}
+
+/// Checks a provisioning request without touching the device, for
+/// `POST /devices/{host}/connectivity-services:validate`
+///
+/// # Arguments
+/// - `value`: The request body, as it would be sent to `POST /devices/{host}/connectivity-services`
+/// - `topology`: The cached topology of the device
+///
+/// # Returns
+/// - The violations of the request fields, or else of the request against the topology
+pub fn dry_run(value: &Value, topology: &Topology) -> DryRun {
+    let mut violations = ConnectivityServiceRequest::validate(value);
+    if violations.is_empty() {
+        match ConnectivityServiceRequest::from_value(value) {
+            Ok(request) => violations = request.check_topology(topology),
+            Err(err) => violations.push(FieldError::new("", &err.to_string())),
+        }
+    }
+
+    DryRun {
+        valid: violations.is_empty(),
+        violations,
+    }
+}
+
+/// Creates a connectivity service on a device, for `POST /devices/{host}/connectivity-services`
+///
+/// The request is expected to be validated already (see
+/// [`ConnectivityServiceRequest::from_value`]); it is forwarded as is to the TAPI
+/// connectivity context of the device.
+///
+/// # Returns
+/// - `Ok(CreatedService)`: The uuid of the service, as reported by the device when it
+///   answers with the created service, or the uuid of the request otherwise
+/// - `Err(Error)`: If the device refused the service or could not be reached
+pub async fn create_connectivity_service(
+    client: &RestconfClient,
+    request: &ConnectivityServiceRequest,
+) -> Result<CreatedService> {
+    let answer = client
+        .post(CONNECTIVITY_CONTEXT_PATH, &request.to_tapi())
+        .await?;
+
+    let uuid = answer
+        .as_ref()
+        .and_then(|answer| {
+            answer
+                .pointer("/tapi-connectivity:connectivity-service/0/uuid")
+                .or_else(|| answer.get("uuid"))
+        })
+        .and_then(Value::as_str)
+        .and_then(|uuid| Uuid::parse_str(uuid).ok())
+        .unwrap_or(request.uuid);
+
+    info!(
+        "Connectivity service {} created on {}",
+        uuid,
+        client.device().host
+    );
+    Ok(CreatedService { uuid })
+}
+
+/// Deletes a connectivity service, for `DELETE /devices/{host}/connectivity-services/{uuid}`
+///
+/// The deletion runs as a [`JobKind::Provisioning`] job of `tenant_id`, answered with
+/// `202 Accepted`; the job is done once the device no longer reports the service.
+///
+/// # Returns
+/// - The id of the job
+pub fn delete_connectivity_service(
+    jobs: &JobQueue,
+    tenant_id: &str,
+    client: Arc<RestconfClient>,
+    uuid: Uuid,
+    confirmation: Confirmation,
+) -> Uuid {
+    jobs.submit_for(
+        tenant_id,
+        JobKind::Provisioning,
+        move |progress| async move { delete_service(&client, uuid, confirmation, &progress).await },
+    )
+}
+
+/// Body of a [`delete_connectivity_service`] job
+///
+/// # Returns
+/// - `Ok(Value)`: The job result, once the deletion is confirmed
+/// - `Err(Error)`: If the device refused the deletion, could not be reached or did not
+///   confirm it in time
+pub(crate) async fn delete_service(
+    client: &RestconfClient,
+    uuid: Uuid,
+    confirmation: Confirmation,
+    progress: &JobProgress,
+) -> Result<Value> {
+    let path = service_path(&uuid);
+    client.delete(&path).await?;
+    progress.set(10);
+
+    let deleted = confirm(progress, confirmation, || async {
+        Ok(client.find(&path).await?.is_none())
+    })
+    .await?;
+    if !deleted {
+        return Err(Error::Timeout(format!(
+            "Device timeout confirming deletion of connectivity service {}",
+            uuid
+        )));
+    }
+
+    info!(
+        "Connectivity service {} deleted on {}",
+        uuid,
+        client.device().host
+    );
+    Ok(json!({ "uuid": uuid, "deleted": true }))
+}
+
+/// Changes the administrative state of a connectivity service, for
+/// `PATCH /devices/{host}/connectivity-services/{uuid}`
+///
+/// The change runs as a [`JobKind::Provisioning`] job of `tenant_id`, answered with
+/// `202 Accepted`; the job is done once the device reports the requested state.
+///
+/// # Returns
+/// - The id of the job
+pub fn change_connectivity_service_state(
+    jobs: &JobQueue,
+    tenant_id: &str,
+    client: Arc<RestconfClient>,
+    uuid: Uuid,
+    change: ServiceStateChange,
+    confirmation: Confirmation,
+) -> Uuid {
+    jobs.submit_for(
+        tenant_id,
+        JobKind::Provisioning,
+        move |progress| async move {
+            change_service_state(&client, uuid, change, confirmation, &progress).await
+        },
+    )
+}
+
+/// Body of a [`change_connectivity_service_state`] job
+///
+/// # Returns
+/// - `Ok(Value)`: The job result, once the device reports the requested state
+/// - `Err(Error)`: If the device refused the change, could not be reached or did not
+///   confirm it in time
+pub(crate) async fn change_service_state(
+    client: &RestconfClient,
+    uuid: Uuid,
+    change: ServiceStateChange,
+    confirmation: Confirmation,
+    progress: &JobProgress,
+) -> Result<Value> {
+    let path = service_path(&uuid);
+    client.patch(&path, &change.to_tapi(&uuid)).await?;
+    progress.set(10);
+
+    let requested = change.administrative_state;
+    let applied = confirm(progress, confirmation, || async {
+        let service = client
+            .find(&path)
+            .await?
+            .ok_or_else(|| Error::NotFound(format!("Not found connectivity service {}", uuid)))?;
+        Ok(administrative_state(&service) == Some(requested))
+    })
+    .await?;
+    if !applied {
+        return Err(Error::Timeout(format!(
+            "Device timeout confirming administrative state of connectivity service {}",
+            uuid
+        )));
+    }
+
+    info!(
+        "Connectivity service {} set {:?} on {}",
+        uuid,
+        requested,
+        client.device().host
+    );
+    Ok(json!({ "uuid": uuid, "administrative_state": requested }))
+}
+
+/// Reads the `administrative-state` of a service as answered by the device
+fn administrative_state(service: &Value) -> Option<AdministrativeState> {
+    let state = service
+        .pointer("/tapi-connectivity:connectivity-service/0/administrative-state")
+        .or_else(|| service.get("administrative-state"))?;
+    serde_json::from_value(state.clone()).ok()
+}
+
+/// Runs `check` until it confirms the change, reporting the attempts as job progress
+///
+/// # Returns
+/// - `Ok(bool)`: Whether the change was confirmed before the attempts ran out
+/// - `Err(Error)`: If `check` failed
+async fn confirm<F, Fut>(
+    progress: &JobProgress,
+    confirmation: Confirmation,
+    check: F,
+) -> Result<bool>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    let attempts = confirmation.attempts.max(1);
+    for attempt in 1..=attempts {
+        if check().await? {
+            return Ok(true);
+        }
+        progress.set((10 + 89 * attempt / attempts) as u8);
+        if attempt < attempts {
+            tokio::time::sleep(confirmation.interval).await;
+        }
+    }
+    Ok(false)
+}
@@ -0,0 +1,405 @@
+use crate::diff::{compare_topologies, diff_topologies};
+use crate::models::parse::{ParseMode, ParseOptions, ParseReport};
+use crate::models::{device::Device, link::Link, node::Node, topology::Topology};
+use crate::normalization::pair_links;
+use crate::Error;
+
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+/// Python module `device_manager`, built with the `python` feature
+///
+/// Payloads are given as the `dict`s `json.load` returns, and objects are returned the
+/// same way by `to_dict`, so notebooks parse and diff topologies exactly like the
+/// collectors do.
+#[pymodule]
+#[pyo3(name = "device_manager")]
+fn device_manager(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyDevice>()?;
+    module.add_class::<PyNode>()?;
+    module.add_class::<PyLink>()?;
+    module.add_class::<PyTopology>()?;
+    module.add_function(wrap_pyfunction!(diff, module)?)?;
+    module.add_function(wrap_pyfunction!(compare, module)?)?;
+    Ok(())
+}
+
+/// A device of the registry, `Device(payload)`
+#[pyclass(name = "Device", module = "device_manager")]
+pub struct PyDevice {
+    inner: Device,
+}
+
+#[pymethods]
+impl PyDevice {
+    #[new]
+    fn new(payload: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let inner = Device::from_value(&from_python(payload)?).map_err(value_error)?;
+        Ok(PyDevice { inner })
+    }
+
+    #[getter]
+    fn host(&self) -> String {
+        self.inner.host.to_string()
+    }
+
+    #[getter]
+    fn port(&self) -> Option<u16> {
+        self.inner.port.map(|port| port.get())
+    }
+
+    #[getter]
+    fn enabled(&self) -> bool {
+        self.inner.enabled
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        dict(py, &self.inner)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Device({})", self.inner.host)
+    }
+}
+
+/// A node of a topology, `Node(payload, host)`
+#[pyclass(name = "Node", module = "device_manager")]
+pub struct PyNode {
+    inner: Node,
+}
+
+#[pymethods]
+impl PyNode {
+    #[new]
+    fn new(payload: &Bound<'_, PyAny>, host: &str) -> PyResult<Self> {
+        let inner = Node::from_value(&from_python(payload)?, host).map_err(value_error)?;
+        Ok(PyNode { inner })
+    }
+
+    #[getter]
+    fn uuid(&self) -> String {
+        self.inner.uuid.to_string()
+    }
+
+    #[getter]
+    fn name(&self) -> Option<String> {
+        self.inner.name.clone()
+    }
+
+    #[getter]
+    fn host(&self) -> String {
+        self.inner.host.clone()
+    }
+
+    #[getter]
+    fn lifecycle_state(&self) -> Option<String> {
+        self.inner.lifecycle_state.as_ref().map(label)
+    }
+
+    #[getter]
+    fn owned_node_edge_points(&self) -> Vec<String> {
+        self.inner
+            .owned_node_edge_points
+            .iter()
+            .map(|endpoint| endpoint.uuid.to_string())
+            .collect()
+    }
+
+    #[getter]
+    fn hash(&self) -> String {
+        self.inner.hash.to_string()
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        dict(py, &self.inner)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Node({})", self.inner.uuid)
+    }
+}
+
+/// A link of a topology, `Link(payload, host)`
+#[pyclass(name = "Link", module = "device_manager")]
+pub struct PyLink {
+    inner: Link,
+}
+
+#[pymethods]
+impl PyLink {
+    #[new]
+    fn new(payload: &Bound<'_, PyAny>, host: &str) -> PyResult<Self> {
+        let inner = Link::from_value(&from_python(payload)?, host).map_err(value_error)?;
+        Ok(PyLink { inner })
+    }
+
+    #[getter]
+    fn uuid(&self) -> String {
+        self.inner.uuid.to_string()
+    }
+
+    #[getter]
+    fn name(&self) -> Option<String> {
+        self.inner.name.clone()
+    }
+
+    #[getter]
+    fn host(&self) -> String {
+        self.inner.host.clone()
+    }
+
+    #[getter]
+    fn lifecycle_state(&self) -> Option<String> {
+        self.inner.lifecycle_state.as_ref().map(label)
+    }
+
+    #[getter]
+    fn direction(&self) -> Option<String> {
+        self.inner.direction.as_ref().map(label)
+    }
+
+    /// `(node_uuid, node_edge_point_uuid)` of every endpoint, in order
+    #[getter]
+    fn node_edge_points(&self) -> Vec<(String, String)> {
+        self.inner
+            .node_edge_points
+            .iter()
+            .map(|endpoint| {
+                (
+                    endpoint.node_uuid.to_string(),
+                    endpoint.node_edge_point_uuid.to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[getter]
+    fn hash(&self) -> String {
+        self.inner.hash.to_string()
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        dict(py, &self.inner)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Link({})", self.inner.uuid)
+    }
+}
+
+/// A topology of a device, `Topology(payload, host, mode="lenient")`
+///
+/// Parsed like the collectors do: with the default `lenient` mode bad nodes and links
+/// are skipped and listed in `issues`, with `strict` the first one raises `ValueError`.
+#[pyclass(name = "Topology", module = "device_manager")]
+pub struct PyTopology {
+    inner: Topology,
+    report: ParseReport,
+}
+
+impl PyTopology {
+    /// Parses a topology with the parse mode named `mode`
+    fn parse(value: &Value, host: &str, mode: &str) -> PyResult<Self> {
+        let options = ParseOptions {
+            mode: mode.parse::<ParseMode>().map_err(value_error)?,
+            ..Default::default()
+        };
+        let (inner, report) = Topology::parse(value, host, &options).map_err(value_error)?;
+        Ok(PyTopology { inner, report })
+    }
+}
+
+#[pymethods]
+impl PyTopology {
+    #[new]
+    #[pyo3(signature = (payload, host, mode = "lenient"))]
+    fn new(payload: &Bound<'_, PyAny>, host: &str, mode: &str) -> PyResult<Self> {
+        Self::parse(&from_python(payload)?, host, mode)
+    }
+
+    /// Parses every topology of a `tapi-topology:topology-context` payload
+    #[staticmethod]
+    #[pyo3(signature = (payload, host, mode = "lenient"))]
+    fn from_context(payload: &Bound<'_, PyAny>, host: &str, mode: &str) -> PyResult<Vec<Self>> {
+        Topology::list_from_context(&from_python(payload)?)
+            .map_err(value_error)?
+            .iter()
+            .map(|value| Self::parse(value, host, mode))
+            .collect()
+    }
+
+    /// Nodes and links skipped by a lenient parse, as `dict`s
+    #[getter]
+    fn issues<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        dict(py, &self.report.issues)
+    }
+
+    #[getter]
+    fn uuid(&self) -> String {
+        self.inner.uuid.to_string()
+    }
+
+    #[getter]
+    fn host(&self) -> String {
+        self.inner.host.clone()
+    }
+
+    #[getter]
+    fn nodes(&self) -> Vec<PyNode> {
+        self.inner
+            .nodes
+            .iter()
+            .map(|node| PyNode {
+                inner: node.clone(),
+            })
+            .collect()
+    }
+
+    #[getter]
+    fn links(&self) -> Vec<PyLink> {
+        self.inner
+            .links
+            .iter()
+            .map(|link| PyLink {
+                inner: link.clone(),
+            })
+            .collect()
+    }
+
+    /// Merges the unidirectional link pairs, returning the number of pairs merged
+    fn pair_links(&mut self) -> usize {
+        pair_links(&mut self.inner)
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        dict(py, &self.inner)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Topology({})", self.inner.uuid)
+    }
+}
+
+/// Returns the change events between two collections of a topology, as `dict`s
+#[pyfunction]
+fn diff<'py>(
+    py: Python<'py>,
+    before: PyRef<'_, PyTopology>,
+    after: PyRef<'_, PyTopology>,
+) -> PyResult<Bound<'py, PyAny>> {
+    dict(py, &diff_topologies(&before.inner, &after.inner))
+}
+
+/// Compares the topologies of two devices, as a `dict`
+#[pyfunction]
+fn compare<'py>(
+    py: Python<'py>,
+    left: (String, Vec<PyRef<'_, PyTopology>>),
+    right: (String, Vec<PyRef<'_, PyTopology>>),
+) -> PyResult<Bound<'py, PyAny>> {
+    let topologies = |side: &[PyRef<'_, PyTopology>]| -> Vec<Topology> {
+        side.iter().map(|topology| topology.inner.clone()).collect()
+    };
+    let (left_topologies, right_topologies) = (topologies(&left.1), topologies(&right.1));
+    dict(
+        py,
+        &compare_topologies((&left.0, &left_topologies), (&right.0, &right_topologies)),
+    )
+}
+
+/// Maps a parse error to `ValueError`
+fn value_error(err: Error) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// Returns the JSON API name of an enumeration value, e.g. `INSTALLED`
+fn label<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(Value::String(label)) => label,
+        _ => String::new(),
+    }
+}
+
+/// Returns `value` as the Python object `json.loads` would build from its JSON
+fn dict<'py, T: Serialize>(py: Python<'py>, value: &T) -> PyResult<Bound<'py, PyAny>> {
+    let value =
+        serde_json::to_value(value).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    to_python(py, &value)
+}
+
+/// Converts a JSON value into a Python object
+fn to_python<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(flag) => PyBool::new(py, *flag).to_owned().into_any(),
+        Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+            (Some(integer), _) => integer.into_pyobject(py)?.into_any(),
+            (None, Some(integer)) => integer.into_pyobject(py)?.into_any(),
+            _ => PyFloat::new(py, number.as_f64().unwrap_or_default()).into_any(),
+        },
+        Value::String(text) => PyString::new(py, text).into_any(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(to_python(py, item)?)?;
+            }
+            list.into_any()
+        }
+        Value::Object(members) => {
+            let dict = PyDict::new(py);
+            for (name, member) in members {
+                dict.set_item(name, to_python(py, member)?)?;
+            }
+            dict.into_any()
+        }
+    })
+}
+
+/// Converts a Python object built by `json.loads` into a JSON value
+fn from_python(object: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if object.is_none() {
+        return Ok(Value::Null);
+    }
+    // `bool` is a subclass of `int`, so it is checked first
+    if let Ok(flag) = object.downcast::<PyBool>() {
+        return Ok(Value::Bool(flag.is_true()));
+    }
+    if object.is_instance_of::<PyInt>() {
+        return match object.extract::<i64>() {
+            Ok(integer) => Ok(Value::from(integer)),
+            Err(_) => Ok(Value::from(object.extract::<u64>()?)),
+        };
+    }
+    if let Ok(float) = object.downcast::<PyFloat>() {
+        return Number::from_f64(float.value())
+            .map(Value::Number)
+            .ok_or_else(|| PyValueError::new_err("Invalid number, not finite"));
+    }
+    if let Ok(text) = object.downcast::<PyString>() {
+        return Ok(Value::String(text.to_str()?.to_string()));
+    }
+    if let Ok(dict) = object.downcast::<PyDict>() {
+        let mut members = Map::new();
+        for (name, member) in dict.iter() {
+            let name = name
+                .downcast::<PyString>()
+                .map_err(|_| PyTypeError::new_err("Invalid key, expected str"))?;
+            members.insert(name.to_str()?.to_string(), from_python(&member)?);
+        }
+        return Ok(Value::Object(members));
+    }
+    if object.is_instance_of::<PyList>() || object.is_instance_of::<PyTuple>() {
+        return object
+            .try_iter()?
+            .map(|item| from_python(&item?))
+            .collect::<PyResult<Vec<Value>>>()
+            .map(Value::Array);
+    }
+    Err(PyTypeError::new_err(format!(
+        "Invalid value of type {}",
+        object.get_type().name()?
+    )))
+}
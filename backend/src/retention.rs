@@ -0,0 +1,167 @@
+use crate::events::store::EventStore;
+use crate::sync::SnapshotStore;
+use crate::{Error, Result};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+/// Age written with a unit suffix: `90s`, `15m`, `12h`, `30d` or `2w`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct Age(Duration);
+
+impl Age {
+    /// Returns the age as a duration
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+}
+
+impl FromStr for Age {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || Error::Invalid(format!("Invalid age {}", value));
+        let split = value.len().checked_sub(1).ok_or_else(invalid)?;
+        let (amount, unit) = value.split_at_checked(split).ok_or_else(invalid)?;
+        let amount: i64 = amount.parse().map_err(|_| invalid())?;
+        if amount <= 0 {
+            return Err(invalid());
+        }
+
+        let duration = match unit {
+            "s" => Duration::try_seconds(amount),
+            "m" => Duration::try_minutes(amount),
+            "h" => Duration::try_hours(amount),
+            "d" => Duration::try_days(amount),
+            "w" => Duration::try_weeks(amount),
+            _ => None,
+        };
+        duration.map(Age).ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for Age {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<Age> for String {
+    fn from(age: Age) -> Self {
+        age.to_string()
+    }
+}
+
+impl fmt::Display for Age {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let seconds = self.0.num_seconds();
+        // Largest unit the age is a whole number of
+        let (amount, unit) = [(604_800, "w"), (86_400, "d"), (3_600, "h"), (60, "m")]
+            .into_iter()
+            .find(|(size, _)| seconds % size == 0)
+            .map_or((seconds, "s"), |(size, unit)| (seconds / size, unit));
+        write!(f, "{}{}", amount, unit)
+    }
+}
+
+/// Limits of the snapshot and event history, the `[retention]` section of `config.toml`
+///
+/// Without limits nothing is pruned.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RetentionPolicy {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<Age>, // Snapshots and events older than this are deleted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_events_per_device: Option<usize>, // Only the most recent events of a device are kept
+}
+
+impl RetentionPolicy {
+    /// Returns the date before which records are deleted, if any
+    pub fn cutoff(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        self.max_age.map(|age| now - age.duration())
+    }
+}
+
+/// Records deleted by a pruning run
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct PruneReport {
+    pub snapshots: usize,
+    pub events: usize,
+}
+
+/// Applies the retention policy to the snapshot store and the event log
+pub fn prune(
+    events: &mut EventStore,
+    snapshots: &mut SnapshotStore,
+    policy: &RetentionPolicy,
+    now: DateTime<Local>,
+) -> Result<PruneReport> {
+    let mut report = snapshots.prune(policy, now);
+    report.events += events.prune(policy, now)?;
+    Ok(report)
+}
+
+/// Starts the scheduled pruning task, running every `every`
+pub fn spawn_pruning(
+    events: Arc<Mutex<EventStore>>,
+    snapshots: Arc<Mutex<SnapshotStore>>,
+    policy: RetentionPolicy,
+    every: std::time::Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        loop {
+            interval.tick().await;
+            let result = prune(
+                &mut events.lock().unwrap(),
+                &mut snapshots.lock().unwrap(),
+                &policy,
+                Local::now(),
+            );
+            match result {
+                Ok(report) => info!(
+                    "Pruned {} snapshots and {} events",
+                    report.snapshots, report.events
+                ),
+                Err(err) => warn!("Pruning failed: {}", err),
+            }
+        }
+    })
+}
+
+/// Decides which records of a history to keep, in the order given (oldest first)
+///
+/// A record is kept when it is recent enough and among the `max_events_per_device`
+/// most recent records of its device, identified by `device`.
+pub(crate) fn retained<T>(
+    records: &[T],
+    device: impl Fn(&T) -> (&str, &str),
+    date: impl Fn(&T) -> DateTime<Local>,
+    policy: &RetentionPolicy,
+    now: DateTime<Local>,
+) -> Vec<bool> {
+    let cutoff = policy.cutoff(now);
+    let mut counts: HashMap<(&str, &str), usize> = HashMap::new();
+    let mut keep = vec![true; records.len()];
+
+    for (index, record) in records.iter().enumerate().rev() {
+        let count = counts.entry(device(record)).or_default();
+        *count += 1;
+        if cutoff.is_some_and(|cutoff| date(record) < cutoff)
+            || policy.max_events_per_device.is_some_and(|max| *count > max)
+        {
+            keep[index] = false;
+        }
+    }
+    keep
+}
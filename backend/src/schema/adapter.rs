@@ -0,0 +1,109 @@
+use super::TapiVersion;
+
+use std::borrow::Cow;
+
+use serde_json::Value;
+
+/// Prefix of the `layer-protocol-name` identities of TAPI 2.4
+const LAYER_PROTOCOL_NAME_PREFIX: &str = "LAYER_PROTOCOL_NAME_TYPE_";
+
+/// Returns the TAPI release a topology payload was written in
+///
+/// Since TAPI 2.4 `layer-protocol-name` is an identity, reported qualified by its module
+/// (`tapi-common:LAYER_PROTOCOL_NAME_TYPE_ETH` for `ETH`). A topology without any such
+/// value is read as TAPI 2.1.3, both releases being alike for it.
+pub fn detect(topology: &Value) -> TapiVersion {
+    let mut qualified = false;
+    visit_layer_protocols(topology, &mut |name| qualified |= name.contains(':'));
+    if qualified {
+        TapiVersion::V2_4
+    } else {
+        TapiVersion::V2_1_3
+    }
+}
+
+/// Rewrites a topology payload of `version` into the TAPI 2.1.3 shape the parsers read
+///
+/// Payloads of a device moving to another release keep their content hashes, so the
+/// upgrade is not reported as a change of every node and link.
+///
+/// # Arguments
+/// - `topology`: A reference to the topology JSON `Value`
+/// - `version`: The release of the payload, see [`detect`]
+///
+/// # Returns
+/// - `Cow<Value>`: The payload itself for TAPI 2.1.3, a rewritten copy otherwise
+pub fn normalize(topology: &Value, version: TapiVersion) -> Cow<'_, Value> {
+    match version {
+        TapiVersion::V2_1_3 => Cow::Borrowed(topology),
+        TapiVersion::V2_4 => {
+            let mut topology = topology.clone();
+            visit_layer_protocols_mut(&mut topology, &mut |name| {
+                let unqualified = name.rsplit(':').next().unwrap_or_default();
+                *name = unqualified
+                    .strip_prefix(LAYER_PROTOCOL_NAME_PREFIX)
+                    .unwrap_or(unqualified)
+                    .to_string();
+            });
+            Cow::Owned(topology)
+        }
+    }
+}
+
+/// Calls `visit` with every `layer-protocol-name` of the topology
+fn visit_layer_protocols(topology: &Value, visit: &mut impl FnMut(&str)) {
+    let mut read = |object: &Value| match object.get("layer-protocol-name") {
+        Some(Value::String(name)) => visit(name),
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).for_each(&mut *visit),
+        _ => {}
+    };
+    read(topology);
+    for node in list(topology, "node") {
+        read(node);
+        for endpoint in list(node, "owned-node-edge-point") {
+            read(endpoint);
+        }
+    }
+    for link in list(topology, "link") {
+        read(link);
+    }
+}
+
+/// Calls `visit` with every `layer-protocol-name` of the topology, to rewrite it
+fn visit_layer_protocols_mut(topology: &mut Value, visit: &mut impl FnMut(&mut String)) {
+    let mut rewrite = |object: &mut Value| match object.get_mut("layer-protocol-name") {
+        Some(Value::String(name)) => visit(name),
+        Some(Value::Array(names)) => names.iter_mut().for_each(|name| {
+            if let Value::String(name) = name {
+                visit(name)
+            }
+        }),
+        _ => {}
+    };
+    rewrite(topology);
+    for node in list_mut(topology, "node") {
+        rewrite(node);
+        for endpoint in list_mut(node, "owned-node-edge-point") {
+            rewrite(endpoint);
+        }
+    }
+    for link in list_mut(topology, "link") {
+        rewrite(link);
+    }
+}
+
+/// Returns the entries of the list `key` of `value`, none if it is not a list
+fn list_mut<'a>(value: &'a mut Value, key: &str) -> std::slice::IterMut<'a, Value> {
+    match value.get_mut(key) {
+        Some(Value::Array(list)) => list.iter_mut(),
+        _ => [].iter_mut(),
+    }
+}
+
+/// Returns the entries of the list `key` of `value`, none if it is not a list
+fn list<'a>(value: &'a Value, key: &str) -> std::slice::Iter<'a, Value> {
+    match value.get(key) {
+        Some(Value::Array(list)) => list.iter(),
+        _ => [].iter(),
+    }
+}
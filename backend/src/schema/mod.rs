@@ -0,0 +1,233 @@
+pub mod adapter;
+
+use crate::{Error, Result};
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Schema of `tapi-topology:topology` in TAPI 2.1.3
+const TAPI_2_1_3: &str = include_str!("tapi-2.1.3.json");
+
+/// Schema of `tapi-topology:topology` in TAPI 2.4
+const TAPI_2_4: &str = include_str!("tapi-2.4.json");
+
+/// TAPI release a bundled schema is derived from
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapiVersion {
+    #[serde(rename = "2.1.3")]
+    V2_1_3,
+    #[serde(rename = "2.4")]
+    V2_4,
+}
+
+impl TapiVersion {
+    /// Returns the JSON schema of a topology in this release
+    ///
+    /// Schemas are JSON Schema documents derived from the YANG modules, limited to the
+    /// fields the backend reads. Vendor augmentations are allowed anywhere.
+    pub fn schema(&self) -> Result<Value> {
+        let source = match self {
+            TapiVersion::V2_1_3 => TAPI_2_1_3,
+            TapiVersion::V2_4 => TAPI_2_4,
+        };
+        serde_json::from_str(source)
+            .map_err(|err| Error::Custom(format!("Invalid schema of TAPI {}: {}", self, err)))
+    }
+}
+
+impl FromStr for TapiVersion {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "2.1.3" => Ok(TapiVersion::V2_1_3),
+            "2.4" => Ok(TapiVersion::V2_4),
+            _ => Err(Error::Invalid(format!("Invalid TAPI version {}", value))),
+        }
+    }
+}
+
+impl fmt::Display for TapiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TapiVersion::V2_1_3 => write!(f, "2.1.3"),
+            TapiVersion::V2_4 => write!(f, "2.4"),
+        }
+    }
+}
+
+/// A value of a payload not conforming to the schema
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+    pub pointer: String, // JSON pointer (RFC 6901) of the value in the payload
+    pub message: String,
+}
+
+/// Validates a `tapi-topology:topology-context` payload against the schema of `version`
+///
+/// # Arguments
+/// - `value`: The topology context, with or without the `tapi-topology:topology-context`
+///   wrapper, as given to `Topology::list_from_context`
+///
+/// # Returns
+/// - `Ok(Vec<SchemaViolation>)`: Every violation, in document order, none if conforming
+/// - `Err(Error)`: If the bundled schema cannot be read
+pub fn validate_context(value: &Value, version: TapiVersion) -> Result<Vec<SchemaViolation>> {
+    let schema = version.schema()?;
+    let (value, mut pointer) = match value.get("tapi-topology:topology-context") {
+        Some(context) => (context, "/tapi-topology:topology-context".to_string()),
+        None => (value, String::new()),
+    };
+    let mut violations = vec![];
+    let Some(topologies) = value.get("topology").and_then(Value::as_array) else {
+        violations.push(SchemaViolation {
+            pointer,
+            message: "Missing topology list".to_string(),
+        });
+        return Ok(violations);
+    };
+    pointer.push_str("/topology");
+    for (index, topology) in topologies.iter().enumerate() {
+        let pointer = format!("{}/{}", pointer, index);
+        check(topology, &schema, &schema, &pointer, &mut violations);
+    }
+    Ok(violations)
+}
+
+/// Checks `value` against the schema `node`, resolving references in `root`
+///
+/// Supports the keywords of the bundled schemas: `$ref` to `#/definitions`, `type`,
+/// `enum`, `required`, `properties`, `items`, `minItems` and the `uuid` format.
+fn check(
+    value: &Value,
+    node: &Value,
+    root: &Value,
+    pointer: &str,
+    violations: &mut Vec<SchemaViolation>,
+) {
+    if let Some(reference) = node.get("$ref").and_then(Value::as_str) {
+        match reference
+            .strip_prefix("#/definitions/")
+            .and_then(|name| root.get("definitions")?.get(name))
+        {
+            Some(definition) => check(value, definition, root, pointer, violations),
+            None => report(
+                violations,
+                pointer,
+                format!("Not found schema definition {}", reference),
+            ),
+        }
+        return;
+    }
+
+    if let Some(expected) = node.get("type").and_then(Value::as_str) {
+        let found = type_name(value);
+        let matches = found == expected || (expected == "number" && found == "integer");
+        if !matches {
+            report(
+                violations,
+                pointer,
+                format!("Expected {}, found {}", expected, found),
+            );
+            return;
+        }
+    }
+
+    if let Some(allowed) = node.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            report(
+                violations,
+                pointer,
+                format!(
+                    "Invalid value {}, expected one of {}",
+                    value,
+                    allowed.join(", ")
+                ),
+            );
+            return;
+        }
+    }
+
+    if node.get("format").and_then(Value::as_str) == Some("uuid") {
+        if let Some(text) = value.as_str() {
+            if Uuid::parse_str(text).is_err() {
+                report(violations, pointer, format!("Invalid uuid {}", value));
+            }
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for name in node
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !object.contains_key(name) {
+                report(
+                    violations,
+                    pointer,
+                    format!("Missing required property {}", name),
+                );
+            }
+        }
+        if let Some(properties) = node.get("properties").and_then(Value::as_object) {
+            for (name, property) in object {
+                if let Some(schema) = properties.get(name) {
+                    let pointer = format!("{}/{}", pointer, escape(name));
+                    check(property, schema, root, &pointer, violations);
+                }
+            }
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        if let Some(minimum) = node.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < minimum {
+                report(
+                    violations,
+                    pointer,
+                    format!("Expected at least {} items, found {}", minimum, items.len()),
+                );
+            }
+        }
+        if let Some(schema) = node.get("items") {
+            for (index, item) in items.iter().enumerate() {
+                let pointer = format!("{}/{}", pointer, index);
+                check(item, schema, root, &pointer, violations);
+            }
+        }
+    }
+}
+
+/// Records a violation of the value at `pointer`
+fn report(violations: &mut Vec<SchemaViolation>, pointer: &str, message: String) {
+    violations.push(SchemaViolation {
+        pointer: pointer.to_string(),
+        message,
+    });
+}
+
+/// Returns the JSON Schema type of `value`
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Escapes a property name as a JSON pointer token
+fn escape(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
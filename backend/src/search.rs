@@ -0,0 +1,354 @@
+use crate::events::ObjectKind;
+use crate::ingest::ObjectStore;
+use crate::models::device::Device;
+use crate::models::service_interface_point::ServiceInterfacePoint;
+use crate::{Error, Result};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Type of the object a search hit points to
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum HitKind {
+    Device,
+    Node,
+    Link,
+    NodeEdgePoint,
+    ServiceInterfacePoint,
+}
+
+/// An object matching a search, with the device owning it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub kind: HitKind,
+    pub host: String, // Device the object was collected from, or the device itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topology_uuid: Option<Uuid>, // Topology of a node, link or node edge point
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<Uuid>, // None for devices
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_uuid: Option<Uuid>, // Node owning a node edge point
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>, // Other TAPI names and text extensions (circuit IDs...)
+}
+
+impl SearchHit {
+    /// Returns the terms the hit is found by in full, lowercase: its UUID, names and host
+    fn terms(&self) -> Vec<String> {
+        let mut terms: Vec<String> = self.uuid.iter().map(Uuid::to_string).collect();
+        terms.extend(
+            self.name
+                .iter()
+                .chain(&self.labels)
+                .map(|name| name.to_lowercase()),
+        );
+        if self.kind == HitKind::Device {
+            terms.push(self.host.to_lowercase());
+        }
+        terms
+    }
+
+    /// Returns the words of the names, labels and host of the hit, for full-text search
+    fn tokens(&self) -> HashSet<String> {
+        let mut tokens: HashSet<String> = self
+            .name
+            .iter()
+            .chain(&self.labels)
+            .flat_map(|name| tokenize(name))
+            .collect();
+        if self.kind == HitKind::Device {
+            tokens.extend(tokenize(&self.host));
+        }
+        tokens
+    }
+}
+
+/// Splits `text` into lowercase words, on any character that is not a letter or digit
+///
+/// `MAD-BCN-100G-01` gives `mad`, `bcn`, `100g` and `01`.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Term or word -> host -> positions of the hits of the host having it
+type Postings = HashMap<String, HashMap<String, BTreeSet<usize>>>;
+
+/// Index of the devices and collected objects, the backend of `GET /search`
+///
+/// Hits are grouped by device so a collection only reindexes its own device. Full
+/// UUIDs, names, labels and hosts are looked up in a term index, and every word of the
+/// names and labels in a full-text index, so a circuit name resolves to its links
+/// without scanning the objects. Queries matching neither fall back to a substring scan.
+/// Hits are ranked exact matches first, then those starting with the query.
+#[derive(Default)]
+pub struct SearchIndex {
+    hosts: BTreeMap<String, Vec<SearchHit>>, // Hits of every device
+    terms: Postings,                         // Exact terms
+    words: Postings,                         // Words of the names and labels
+}
+
+impl SearchIndex {
+    /// Creates an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Indexes `devices` and the objects of `store`, including those of unknown hosts
+    pub fn build(devices: &[Device], store: &ObjectStore) -> Self {
+        let mut index = Self::new();
+        for device in devices {
+            index.update(device.host.as_str(), Some(device), store, &[]);
+        }
+        for host in store.hosts() {
+            if !devices.iter().any(|device| device.host == host) {
+                index.update(host, None, store, &[]);
+            }
+        }
+        index
+    }
+
+    /// Replaces the hits of `host` with the device, its stored objects and `sips`
+    pub fn update(
+        &mut self,
+        host: &str,
+        device: Option<&Device>,
+        store: &ObjectStore,
+        sips: &[ServiceInterfacePoint],
+    ) {
+        let mut hits = vec![];
+        if let Some(device) = device {
+            hits.push(SearchHit {
+                kind: HitKind::Device,
+                host: host.to_string(),
+                topology_uuid: None,
+                uuid: None,
+                node_uuid: None,
+                name: device.metadata.get("name").cloned(),
+                labels: vec![],
+            });
+        }
+        for (key, object) in store.objects_of(host) {
+            let name = object.object["name"].as_str().map(str::to_string);
+            let labels = labels(&object.object, name.as_deref());
+            match object.kind {
+                ObjectKind::Link => hits.push(SearchHit {
+                    kind: HitKind::Link,
+                    host: host.to_string(),
+                    topology_uuid: Some(key.topology_uuid),
+                    uuid: Some(key.uuid),
+                    node_uuid: None,
+                    name,
+                    labels,
+                }),
+                ObjectKind::Node => {
+                    let node_edge_points = object.object["owned-node-edge-point"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|point| point["uuid"].as_str()?.parse().ok());
+                    for uuid in node_edge_points {
+                        hits.push(SearchHit {
+                            kind: HitKind::NodeEdgePoint,
+                            host: host.to_string(),
+                            topology_uuid: Some(key.topology_uuid),
+                            uuid: Some(uuid),
+                            node_uuid: Some(key.uuid),
+                            name: None,
+                            labels: vec![],
+                        });
+                    }
+                    hits.push(SearchHit {
+                        kind: HitKind::Node,
+                        host: host.to_string(),
+                        topology_uuid: Some(key.topology_uuid),
+                        uuid: Some(key.uuid),
+                        node_uuid: None,
+                        name,
+                        labels,
+                    });
+                }
+            }
+        }
+        hits.extend(sips.iter().map(|sip| SearchHit {
+            kind: HitKind::ServiceInterfacePoint,
+            host: host.to_string(),
+            topology_uuid: None,
+            uuid: Some(sip.uuid),
+            node_uuid: None,
+            name: sip.name.clone(),
+            labels: vec![],
+        }));
+        self.replace(host, hits);
+    }
+
+    /// Drops every hit of `host`
+    pub fn remove(&mut self, host: &str) {
+        self.replace(host, vec![]);
+    }
+
+    /// Returns the number of indexed hits
+    pub fn len(&self) -> usize {
+        self.hosts.values().map(Vec::len).sum()
+    }
+
+    /// Returns `true` when nothing is indexed
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns up to `limit` hits matching `query`: a UUID, a name or a device host/IP
+    ///
+    /// # Returns
+    /// - `Ok(Vec<SearchHit>)`: The hits, best matches first, then by host and kind
+    /// - `Err(Error)`: If the query is empty
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Err(Error::Invalid(
+                "Invalid search query, expected a UUID, name or IP".to_string(),
+            ));
+        }
+
+        // Exact matches make the other ones noise, as for a full UUID
+        let mut hits: Vec<(u8, &SearchHit)> = self
+            .lookup(&self.terms, std::slice::from_ref(&query))
+            .into_iter()
+            .map(|hit| (0, hit))
+            .collect();
+        if hits.is_empty() {
+            hits = self
+                .lookup(&self.words, &tokenize(&query))
+                .into_iter()
+                .map(|hit| (partial_rank(hit, &query).unwrap_or(2), hit))
+                .collect();
+        }
+        if hits.is_empty() {
+            hits = self
+                .hosts
+                .values()
+                .flatten()
+                .filter_map(|hit| Some((partial_rank(hit, &query)?, hit)))
+                .collect();
+        }
+
+        hits.sort_by(|(left_rank, left), (right_rank, right)| {
+            (left_rank, &left.host, left.kind, left.uuid).cmp(&(
+                right_rank,
+                &right.host,
+                right.kind,
+                right.uuid,
+            ))
+        });
+        Ok(hits
+            .into_iter()
+            .take(limit)
+            .map(|(_, hit)| hit.clone())
+            .collect())
+    }
+
+    /// Returns the hits having every one of `keys` in `postings`
+    fn lookup(&self, postings: &Postings, keys: &[String]) -> Vec<&SearchHit> {
+        let Some((first, others)) = keys.split_first() else {
+            return vec![];
+        };
+        let mut hits = vec![];
+        for (host, positions) in postings.get(first).into_iter().flatten() {
+            let positions = positions.iter().filter(|position| {
+                others.iter().all(|key| {
+                    postings
+                        .get(key)
+                        .and_then(|hosts| hosts.get(host))
+                        .is_some_and(|others| others.contains(position))
+                })
+            });
+            hits.extend(positions.map(|position| &self.hosts[host][*position]));
+        }
+        hits
+    }
+
+    /// Replaces the hits of `host` and their postings
+    fn replace(&mut self, host: &str, hits: Vec<SearchHit>) {
+        for hit in self.hosts.remove(host).into_iter().flatten() {
+            unpost(&mut self.terms, hit.terms(), host);
+            unpost(&mut self.words, hit.tokens(), host);
+        }
+        if hits.is_empty() {
+            return;
+        }
+        for (position, hit) in hits.iter().enumerate() {
+            for term in hit.terms() {
+                post(&mut self.terms, term, host, position);
+            }
+            for word in hit.tokens() {
+                post(&mut self.words, word, host, position);
+            }
+        }
+        self.hosts.insert(host.to_string(), hits);
+    }
+}
+
+/// Returns 1 when a term of `hit` starts with `query`, 2 when one contains it
+fn partial_rank(hit: &SearchHit, query: &str) -> Option<u8> {
+    hit.terms()
+        .iter()
+        .filter_map(|term| {
+            if term.starts_with(query) {
+                Some(1)
+            } else if term.contains(query) {
+                Some(2)
+            } else {
+                None
+            }
+        })
+        .min()
+}
+
+/// Returns the names of a stored object other than `name`, and its text extensions
+fn labels(object: &Value, name: Option<&str>) -> Vec<String> {
+    let names = object["names"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry["value"].as_str());
+    let extensions = object["extensions"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(_, value)| value.as_str());
+    let mut labels: Vec<String> = vec![];
+    for label in names.chain(extensions) {
+        if Some(label) != name && !labels.iter().any(|known| known == label) {
+            labels.push(label.to_string());
+        }
+    }
+    labels
+}
+
+fn post(postings: &mut Postings, key: String, host: &str, position: usize) {
+    postings
+        .entry(key)
+        .or_default()
+        .entry(host.to_string())
+        .or_default()
+        .insert(position);
+}
+
+fn unpost(postings: &mut Postings, keys: impl IntoIterator<Item = String>, host: &str) {
+    for key in keys {
+        if let Some(hosts) = postings.get_mut(&key) {
+            hosts.remove(host);
+            if hosts.is_empty() {
+                postings.remove(&key);
+            }
+        }
+    }
+}
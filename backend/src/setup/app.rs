@@ -0,0 +1,2589 @@
+use crate::actions::{BulkAction, BulkReport, BulkRequest, DeviceActionResult};
+use crate::agents::{Agent, AgentCredentials, AgentRegistry};
+use crate::api::auth::Caller;
+use crate::audit::{AuditAction, AuditEntry, AuditLog, AuditQuery};
+use crate::cache::{CacheMetrics, TopologyCache};
+use crate::capture::ParseCapture;
+use crate::collector::{CollectionStep, CycleReport, DeviceCycle, StepOutcome};
+use crate::diff::{compare_topologies, diff_topologies, TopologyComparison};
+use crate::discovery::{scan, Candidate, CandidateStore};
+use crate::enrichment::{enrich, SiteMapping};
+use crate::events::bus::{EventBus, OverflowPolicy, SubscriberMetrics, Subscription};
+use crate::events::sse::{EventStream, HEARTBEAT_INTERVAL};
+use crate::events::store::{EventPage, EventStore, StoredEvent};
+use crate::events::{ChangeEvent, ChangeKind, ObjectKind};
+use crate::export::archive::ArchiveSink;
+use crate::export::artifact::{ExportArtifact, ExportChunk, ExportStore};
+use crate::export::geojson::to_geojson;
+use crate::export::graph::{render, GraphFormat};
+use crate::extractors::{CustomObject, CustomObjectStore, ExtractorRegistry};
+use crate::impact::LinkImpact;
+use crate::ingest::ObjectStore;
+use crate::jobs::{Job, JobKind, JobQueue};
+use crate::latency::{measure, LatencySample, LatencySeries, LatencyStore};
+use crate::maintenance::{MaintenanceSchedule, MaintenanceTarget, MaintenanceWindow};
+use crate::models::connection::ConnectivityContext;
+use crate::models::connectivity_service::{
+    ConnectivityServiceRequest, CreatedService, DryRun, ServiceStateChange,
+    CONNECTIVITY_CONTEXT_PATH,
+};
+use crate::models::device::Device;
+use crate::models::equipment::{PhysicalContext, PHYSICAL_CONTEXT_PATH};
+use crate::models::parse::ParseOptions;
+use crate::models::topology::{Topology, TOPOLOGY_CONTEXT_PATH};
+use crate::models::vendor::VendorProfile;
+use crate::normalization::pair_links;
+use crate::notifications::chat::{ChatKind, ChatNotifier};
+use crate::notifications::email::EmailNotifier;
+use crate::notifications::history::{AlertHistory, StoredAlert};
+use crate::notifications::routing::{RoutingContext, RoutingWatcher, Sink, DELIVERED_SINKS};
+use crate::notifications::spool::{SpoolConfig, SpoolMetrics, SpoolQueue};
+use crate::notifications::templates::NotificationTemplates;
+use crate::notifications::thresholds::ThresholdMonitor;
+use crate::notifications::Alert;
+use crate::pagination::CursorSigner;
+use crate::provisioning::{
+    change_service_state, create_connectivity_service, delete_service, dry_run, Confirmation,
+};
+use crate::retention::{self, Age};
+use crate::schema::validate_context;
+use crate::search::{SearchHit, SearchIndex};
+use crate::setup::config::{Config, ConfigChange, ConfigWatcher};
+use crate::setup::log_setup::LogFilterHandle;
+use crate::southbound::dns::CachedResolver;
+use crate::southbound::netconf::NetconfClient;
+use crate::southbound::restconf::{Fetched, RestconfClient};
+use crate::southbound::tunnel::SshTunnel;
+use crate::southbound::FetchOptions;
+use crate::stats::{CycleStats, DeviceStats, StatsStore};
+use crate::stitching::GlobalTopology;
+use crate::supervisor::{RestartPolicy, Supervisor, TaskHealth};
+use crate::sync::{MergeOutcome, Snapshot, SnapshotLog, SnapshotStore, LOCAL_AGENT};
+use crate::tenant::DEFAULT_TENANT;
+use crate::validation::{validate, Violation};
+use crate::{Error, Result};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, Notify};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+/// Events queued for each subscriber of the event bus
+const BUS_CAPACITY: usize = 10_000;
+
+/// How often email digests and retention are checked
+const HOUSEKEEPING_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Time between two rewrites of the object journal, which grows with every write
+const COMPACTION_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Workers of the job queue running the bulk actions
+const JOB_WORKERS: usize = 2;
+
+/// Lifetime of the cursors of the event replays
+const CURSOR_TTL_HOURS: i64 = 24;
+
+/// Actor of the operations called on the application itself rather than through the API
+pub const LOCAL_ACTOR: &str = "local";
+
+/// Files the application is started with, the options of `cli serve`
+#[derive(Debug, Clone, Default)]
+pub struct AppSettings {
+    pub config: PathBuf,                 // `config.toml`, reloaded when it changes
+    pub devices: PathBuf,                // JSON array of the devices to poll
+    pub events: Option<PathBuf>,         // Event log, kept in memory if unset
+    pub objects: Option<PathBuf>,        // Journal of the current objects, kept in memory if unset
+    pub candidates: Option<PathBuf>,     // Discovered devices, kept in memory if unset
+    pub latency: Option<PathBuf>,        // Latency samples, kept in memory if unset
+    pub audit: Option<PathBuf>,          // Audit log of the config reloads, kept in memory if unset
+    pub stats: Option<PathBuf>,          // Statistics of the poll cycles, kept in memory if unset
+    pub custom_objects: Option<PathBuf>, // Objects of the extractors, kept in memory if unset
+    pub sites: Option<PathBuf>,          // Site mapping of the devices and nodes, no sites if unset
+    pub captures: Option<PathBuf>, // Directory of the fragments failing to parse, none kept if unset
+    pub maintenance: Option<PathBuf>, // Maintenance windows, kept in memory if unset
+    pub jobs: Option<PathBuf>,     // Job table, kept in memory if unset
+    pub exports: Option<PathBuf>,  // Directory of the export artifacts, a temporary one if unset
+    pub agents: Option<PathBuf>,   // Edge agents and enrollment tokens, kept in memory if unset
+    pub spool: Option<PathBuf>, // Directory of the undelivered notifications, `spool` next to the config if unset
+    pub snapshots: Option<PathBuf>, // Snapshot history of the polls and agents, none kept if unset
+}
+
+/// What the embedding program hands to the application besides its files
+#[derive(Default)]
+pub struct AppHooks {
+    pub log_filter: Option<LogFilterHandle>, // Applies the `log_level` of reloaded configs
+    pub extractors: ExtractorRegistry,       // Run over every collected topology context
+}
+
+/// Health of a device of the registry
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DeviceHealth {
+    pub host: String,
+    pub address: Option<IpAddr>, // Address being polled, once the host is resolved
+    pub collector: Option<TaskHealth>, // Collector task, none for disabled devices
+    pub latency: Option<LatencySample>, // Last latency probe
+}
+
+/// What the collector of a device is doing
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollectorPhase {
+    Starting,   // Opening its tunnel and client, also after a restart
+    Collecting, // Running a cycle, `step` is the step being collected
+    Waiting,    // Sleeping until `next_run`
+    Paused,     // Paused by a bulk action until resumed
+    Disabled,   // Disabled in the registry or the config
+}
+
+/// Schedule of a device, an entry of `GET /admin/scheduler`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DeviceSchedule {
+    pub host: String,
+    pub phase: CollectorPhase,
+    pub step: Option<CollectionStep>,      // Step being collected
+    pub next_run: Option<DateTime<Local>>, // Start of the next cycle, while waiting
+    pub last_run: Option<DateTime<Local>>, // Start of the last cycle
+    pub last_duration_ms: Option<u64>,     // Duration of the last cycle
+}
+
+/// State of the collectors, the payload of `GET /admin/scheduler`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SchedulerState {
+    pub devices: Vec<DeviceSchedule>, // In registry order
+    pub collectors: usize,            // Collector tasks, one per enabled device
+    pub busy: usize,                  // Collectors running a cycle
+    pub utilization: f64,             // `busy` over `collectors`, 0 without collectors
+    pub queues: SchedulerQueues,
+}
+
+/// Queues between the collectors and the notification sinks
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct SchedulerQueues {
+    pub bus: Vec<SubscriberMetrics>, // Event bus queues, the notifications among them
+    pub spools: BTreeMap<Sink, SpoolMetrics>, // Failed deliveries waiting for their sink
+}
+
+/// Progress of the warm-load of the stored topologies, the payload of `GET /ready`
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Readiness {
+    pub ready: bool,     // Every stored host is in the topology cache
+    pub hydrated: usize, // Hosts loaded into the cache so far
+    pub hosts: usize,    // Hosts with stored objects at startup
+}
+
+/// State shared by the tasks of the application
+struct Shared {
+    config: ConfigWatcher,
+    routing: Option<RoutingWatcher>,
+    devices: Vec<Device>,
+    resolver: CachedResolver,    // DNS cache shared by the collectors
+    topologies: TopologyCache,   // Last topologies of every host
+    hydration: Mutex<Readiness>, // Hosts of the object store loaded into `topologies`
+    inventory: Mutex<HashMap<String, PhysicalContext>>, // Last equipment inventory of every host
+    connectivity: Mutex<HashMap<String, ConnectivityContext>>, // Last services of every host
+    objects: Mutex<ObjectStore>,
+    search: Mutex<SearchIndex>, // Devices and stored objects, updated after every collection
+    events: Arc<Mutex<EventStore>>, // Shared with the event streams of the API
+    cursors: CursorSigner,      // Signs the cursors of the event replays
+    exports: ExportStore,       // Artifacts written by the export jobs
+    maintenance: Mutex<MaintenanceSchedule>, // Windows whose events are not delivered
+    agents: Mutex<AgentRegistry>, // Edge agents pushing the topologies they collect
+    spools: Mutex<BTreeMap<Sink, SpoolQueue<Alert>>>, // Failed deliveries, one queue per sink
+    snapshots: Mutex<SnapshotStore>, // Latest snapshot of every host pushed by the agents
+    snapshot_log: Option<Mutex<SnapshotLog>>, // History of the collected and pushed snapshots
+    bus: EventBus<StoredEvent>,
+    archive: Option<ArchiveSink>, // Set when `[archive]` is configured at startup
+    candidates: Mutex<CandidateStore>, // Devices found by the subnet scan
+    latency: Mutex<LatencyStore>,
+    thresholds: Mutex<ThresholdMonitor>, // Poll failures and slow periods of the devices
+    alerts: mpsc::UnboundedSender<Alert>, // Threshold alerts, delivered with the events
+    alert_history: Mutex<AlertHistory>,  // Last threshold alerts, suppressed ones among them
+    violations: Mutex<HashMap<String, Vec<Violation>>>, // Rules broken by the last topologies
+    audit: Arc<Mutex<AuditLog>>, // Shared with the config watcher, which records the reloads
+    profiles: Vec<VendorProfile>, // Vendor fields extracted from the nodes and links
+    capture: Option<ParseCapture>,
+    sites: Option<SiteMapping>,
+    stats: Mutex<StatsStore>,
+    extractors: ExtractorRegistry,
+    custom_objects: Mutex<CustomObjectStore>,
+    schedule: Mutex<HashMap<String, DeviceSchedule>>, // Updated by the collectors
+    paused: Mutex<BTreeSet<String>>, // Hosts paused by a bulk action, until resumed
+    wakeups: HashMap<String, Notify>, // Ends the wait of a collector before its next run
+}
+
+/// The whole backend: collectors, change detection, storage and notifications
+///
+/// Every enabled device gets a collector task polling its topology context and its
+/// equipment inventory at the interval of the config, each collection cycle bounded by
+/// the cycle budget of the device. Topologies are parsed with the vendor profile of the
+/// device, annotated with their sites and handed to the extractors; the statistics of
+/// every cycle are kept. Changes are written to the object store and the event log,
+/// then published on the event bus, from which the notification task delivers those
+/// outside the maintenance windows.
+/// Tasks run under a [`Supervisor`], which restarts them with backoff when they fail.
+pub struct App {
+    shared: Arc<Shared>,
+    supervisor: Supervisor,
+    jobs: JobQueue, // Bulk actions
+}
+
+impl App {
+    /// Loads the config and the devices, and starts every task
+    ///
+    /// # Returns
+    /// - `Ok(App)`: The running application
+    /// - `Err(Error)`: If a file is missing or invalid, or a notifier misconfigured
+    pub fn start(settings: &AppSettings) -> Result<Self> {
+        Self::start_with(settings, AppHooks::default())
+    }
+
+    /// Starts the application like [`App::start`], with the log filter the `log_level`
+    /// of every new version of the config is applied to, and the extractors to run
+    ///
+    /// # Returns
+    /// - `Ok(App)`: The running application
+    /// - `Err(Error)`: If a file is missing or invalid, or a notifier misconfigured
+    pub fn start_with(settings: &AppSettings, hooks: AppHooks) -> Result<Self> {
+        let AppHooks {
+            log_filter,
+            extractors,
+        } = hooks;
+        let audit = Arc::new(Mutex::new(match &settings.audit {
+            Some(path) => AuditLog::open(path)?,
+            None => AuditLog::new(),
+        }));
+        let config = {
+            let audit = Arc::clone(&audit);
+            let actor = settings.config.display().to_string();
+            ConfigWatcher::start(&settings.config, move |config, changes| {
+                apply_config(config, changes, log_filter.as_ref());
+                if let Err(err) = audit.lock().unwrap().record_config_changes(&actor, changes) {
+                    warn!("{}", err);
+                }
+            })?
+        };
+        let current = config.current();
+        // Without a configured secret, the cursors issued before a restart are refused
+        let cursors = CursorSigner::new(
+            current
+                .api
+                .cursor_secret
+                .clone()
+                .unwrap_or_else(|| format!("{}{}", Uuid::new_v4(), Uuid::new_v4())),
+            chrono::Duration::hours(CURSOR_TTL_HOURS),
+        );
+        let routing = current
+            .notifications
+            .routing
+            .as_ref()
+            .map(RoutingWatcher::start)
+            .transpose()?;
+        let devices = read_devices(&settings.devices)?;
+        let objects = match &settings.objects {
+            Some(path) => ObjectStore::open(path)?,
+            None => ObjectStore::new(),
+        };
+        let events = match &settings.events {
+            Some(path) => EventStore::open(path)?,
+            None => EventStore::new(),
+        };
+
+        let maintenance = match &settings.maintenance {
+            Some(path) => MaintenanceSchedule::open(path)?,
+            None => MaintenanceSchedule::new(),
+        };
+
+        let agents = match &settings.agents {
+            Some(path) => AgentRegistry::open(path)?,
+            None => AgentRegistry::new(),
+        };
+
+        let candidates = match &settings.candidates {
+            Some(path) => CandidateStore::open(path)?,
+            None => CandidateStore::new(),
+        };
+
+        let latency = match &settings.latency {
+            Some(path) => LatencyStore::open(path)?,
+            None => LatencyStore::new(),
+        };
+
+        let stats = match &settings.stats {
+            Some(path) => StatsStore::open(path)?,
+            None => StatsStore::new(),
+        };
+        let custom_objects = match &settings.custom_objects {
+            Some(path) => CustomObjectStore::open(path)?,
+            None => CustomObjectStore::new(),
+        };
+        let exports = ExportStore::open(settings.exports.clone().unwrap_or_else(|| {
+            std::env::temp_dir().join(format!("device_manager_exports_{}", std::process::id()))
+        }))?;
+        // Kept across restarts, so the deliveries spooled before one are replayed
+        let spools = open_spools(
+            &settings
+                .spool
+                .clone()
+                .unwrap_or_else(|| settings.config.with_file_name("spool")),
+        )?;
+        let jobs = match &settings.jobs {
+            Some(path) => JobQueue::persistent(JOB_WORKERS, path)?,
+            None => JobQueue::new(JOB_WORKERS),
+        };
+        let sites = settings
+            .sites
+            .as_ref()
+            .map(SiteMapping::from_file)
+            .transpose()?;
+        // Read at startup like `[archive]`, a new compression applies after a restart
+        let capture = settings
+            .captures
+            .as_ref()
+            .map(|directory| ParseCapture::open(directory, current.payloads.clone()))
+            .transpose()?;
+
+        let search = SearchIndex::build(&devices, &objects);
+        let stored_hosts = objects.hosts().len();
+        let schedule = devices
+            .iter()
+            .map(|device| {
+                let host = device.host.to_string();
+                let phase = match device.enabled {
+                    true => CollectorPhase::Starting,
+                    false => CollectorPhase::Disabled,
+                };
+                let schedule = DeviceSchedule {
+                    host: host.clone(),
+                    phase,
+                    step: None,
+                    next_run: None,
+                    last_run: None,
+                    last_duration_ms: None,
+                };
+                (host, schedule)
+            })
+            .collect();
+        let wakeups = devices
+            .iter()
+            .map(|device| (device.host.to_string(), Notify::new()))
+            .collect();
+        let (alerts, alert_receiver) = mpsc::unbounded_channel();
+        let sink = current.archive.clone().map(ArchiveSink::new).transpose()?;
+
+        let shared = Arc::new(Shared {
+            config,
+            routing,
+            devices,
+            resolver: CachedResolver::new(),
+            topologies: TopologyCache::new(),
+            hydration: Mutex::new(Readiness {
+                ready: stored_hosts == 0,
+                hydrated: 0,
+                hosts: stored_hosts,
+            }),
+            inventory: Mutex::new(HashMap::new()),
+            connectivity: Mutex::new(HashMap::new()),
+            objects: Mutex::new(objects),
+            search: Mutex::new(search),
+            events: Arc::new(Mutex::new(events)),
+            cursors,
+            exports,
+            maintenance: Mutex::new(maintenance),
+            agents: Mutex::new(agents),
+            spools: Mutex::new(spools),
+            snapshots: Mutex::new(SnapshotStore::new()),
+            snapshot_log: settings
+                .snapshots
+                .as_ref()
+                .map(|path| Mutex::new(SnapshotLog::open(path))),
+            bus: EventBus::new(BUS_CAPACITY),
+            archive: sink,
+            candidates: Mutex::new(candidates),
+            latency: Mutex::new(latency),
+            thresholds: Mutex::new(ThresholdMonitor::new()),
+            alerts,
+            alert_history: Mutex::new(AlertHistory::new()),
+            violations: Mutex::new(HashMap::new()),
+            audit,
+            profiles: VendorProfile::builtin(),
+            capture,
+            sites,
+            stats: Mutex::new(stats),
+            extractors,
+            custom_objects: Mutex::new(custom_objects),
+            schedule: Mutex::new(schedule),
+            paused: Mutex::new(BTreeSet::new()),
+            wakeups,
+        });
+        let mut supervisor = Supervisor::new();
+
+        // Subscribed before the collectors start, so no event is missed
+        let subscription = Arc::new(tokio::sync::Mutex::new(
+            shared
+                .bus
+                .subscribe("notifications", OverflowPolicy::DropOldest),
+        ));
+        let alert_receiver = Arc::new(tokio::sync::Mutex::new(alert_receiver));
+        let chat = Arc::new(ChatNotifier::new(
+            current.notifications.chat.clone(),
+            NotificationTemplates::default(),
+        )?);
+        let email = current
+            .notifications
+            .email
+            .clone()
+            .map(|email| EmailNotifier::new(email, NotificationTemplates::default()))
+            .transpose()?
+            .map(|email| Arc::new(tokio::sync::Mutex::new(email)));
+        {
+            let shared = Arc::clone(&shared);
+            supervisor.spawn("notifications", RestartPolicy::default(), move || {
+                notify(
+                    Arc::clone(&shared),
+                    Arc::clone(&subscription),
+                    Arc::clone(&alert_receiver),
+                    Arc::clone(&chat),
+                    email.clone(),
+                )
+            });
+        }
+
+        for device in shared.devices.iter().filter(|device| device.enabled) {
+            let shared = Arc::clone(&shared);
+            let device = device.clone();
+            let name = format!("collector:{}", device.host);
+            supervisor.spawn(&name, RestartPolicy::default(), move || {
+                collect(Arc::clone(&shared), device.clone())
+            });
+        }
+
+        {
+            let shared = Arc::clone(&shared);
+            supervisor.spawn("retention", RestartPolicy::default(), move || {
+                prune(Arc::clone(&shared))
+            });
+        }
+
+        // Reads are served from the store until their host is hydrated
+        if stored_hosts > 0 {
+            let shared = Arc::clone(&shared);
+            supervisor.spawn("hydration", RestartPolicy::default(), move || {
+                hydrate(Arc::clone(&shared))
+            });
+        }
+
+        // An in-memory store has no journal to compact
+        if settings.objects.is_some() {
+            let shared = Arc::clone(&shared);
+            supervisor.spawn("compaction", RestartPolicy::default(), move || {
+                compact(Arc::clone(&shared))
+            });
+        }
+
+        if shared.archive.is_some() {
+            let shared = Arc::clone(&shared);
+            supervisor.spawn("archive", RestartPolicy::default(), move || {
+                archive(Arc::clone(&shared))
+            });
+        }
+
+        {
+            let shared = Arc::clone(&shared);
+            supervisor.spawn("latency", RestartPolicy::default(), move || {
+                probe_latency(Arc::clone(&shared))
+            });
+        }
+
+        if current.discovery.is_some() {
+            let shared = Arc::clone(&shared);
+            supervisor.spawn("discovery", RestartPolicy::default(), move || {
+                discover(Arc::clone(&shared))
+            });
+        }
+
+        info!("Device Manager started: {} devices", shared.devices.len());
+        Ok(App {
+            shared,
+            supervisor,
+            jobs,
+        })
+    }
+
+    /// Returns the health of every task, sorted by name
+    pub fn health(&self) -> Vec<TaskHealth> {
+        self.supervisor.health()
+    }
+
+    /// Returns the configuration currently applied, reloaded when `config.toml` changes
+    pub fn config(&self) -> Config {
+        self.shared.config.current()
+    }
+
+    /// Resolves the tenant of an API request from its `authorization` header, with the
+    /// tokens of the `[api]` section of the current config
+    ///
+    /// # Returns
+    /// - `Ok(String)`: The tenant the request is scoped to
+    /// - `Err(Error)`: If the token is missing or unknown
+    pub fn authenticate(&self, authorization: Option<&str>) -> Result<String> {
+        self.shared.config.current().api.tenant(authorization)
+    }
+
+    /// Resolves the tenant and the audit actor of an API request from its
+    /// `authorization` header
+    ///
+    /// # Returns
+    /// - `Ok(Caller)`: The tenant the request is scoped to and who it is audited as
+    /// - `Err(Error)`: If the token is missing or unknown
+    pub fn caller(&self, authorization: Option<&str>) -> Result<Caller> {
+        self.shared.config.current().api.caller(authorization)
+    }
+
+    /// Returns the health of every device, with the address its host resolved to
+    pub fn device_health(&self) -> Vec<DeviceHealth> {
+        self.health_of(None)
+    }
+
+    /// Returns the health of the devices of `tenant_id`
+    pub fn device_health_for_tenant(&self, tenant_id: &str) -> Vec<DeviceHealth> {
+        self.health_of(Some(tenant_id))
+    }
+
+    fn health_of(&self, tenant_id: Option<&str>) -> Vec<DeviceHealth> {
+        let tasks = self.supervisor.health();
+        let latency = self.shared.latency.lock().unwrap();
+        self.tenant_devices(tenant_id)
+            .map(|device| {
+                let name = format!("collector:{}", device.host);
+                DeviceHealth {
+                    host: device.host.to_string(),
+                    address: self.shared.resolver.address(device.host.as_str()),
+                    collector: tasks.iter().find(|task| task.name == name).cloned(),
+                    latency: latency.last(device.host.as_str()).cloned(),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns when every device was and will be collected, and how busy the
+    /// collectors and the event queues are
+    pub fn scheduler(&self) -> SchedulerState {
+        self.scheduler_of(None)
+    }
+
+    /// Returns the schedule of the devices of `tenant_id`
+    ///
+    /// `collectors`, `busy` and `utilization` only count the collectors of the tenant;
+    /// the event queues are shared by every tenant and carry no device data.
+    pub fn scheduler_for_tenant(&self, tenant_id: &str) -> SchedulerState {
+        self.scheduler_of(Some(tenant_id))
+    }
+
+    fn scheduler_of(&self, tenant_id: Option<&str>) -> SchedulerState {
+        let schedule = self.shared.schedule.lock().unwrap();
+        let devices: Vec<DeviceSchedule> = self
+            .tenant_devices(tenant_id)
+            .filter_map(|device| schedule.get(device.host.as_str()).cloned())
+            .collect();
+        let collectors = self
+            .tenant_devices(tenant_id)
+            .filter(|device| device.enabled)
+            .count();
+        let busy = devices
+            .iter()
+            .filter(|device| device.phase == CollectorPhase::Collecting)
+            .count();
+        SchedulerState {
+            devices,
+            collectors,
+            busy,
+            utilization: match collectors {
+                0 => 0.0,
+                collectors => busy as f64 / collectors as f64,
+            },
+            queues: SchedulerQueues {
+                bus: self.shared.bus.metrics().subscribers,
+                spools: self
+                    .shared
+                    .spools
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|(sink, spool)| (*sink, spool.metrics()))
+                    .collect(),
+            },
+        }
+    }
+
+    /// Queues a bulk action over every device tagged with the tag of `request`
+    ///
+    /// The action runs as a [`JobKind::Bulk`] job, answered with `202 Accepted`; its
+    /// result is a [`BulkReport`] with the outcome on every device. Each pause, resume
+    /// and triggered poll is audited under [`LOCAL_ACTOR`].
+    ///
+    /// # Returns
+    /// - `Ok(Uuid)`: The id of the job
+    /// - `Err(Error)`: If no device has the tag
+    pub fn bulk(&self, request: &BulkRequest) -> Result<Uuid> {
+        self.bulk_of(None, LOCAL_ACTOR, request)
+    }
+
+    /// Queues a bulk action of `caller` over the devices of its tenant tagged with the
+    /// tag of `request`, each action audited under the actor of `caller`
+    ///
+    /// # Returns
+    /// - `Ok(Uuid)`: The id of the job, visible to the tenant
+    /// - `Err(Error)`: If no device of the tenant has the tag
+    pub fn bulk_for_tenant(&self, caller: &Caller, request: &BulkRequest) -> Result<Uuid> {
+        self.bulk_of(Some(&caller.tenant_id), &caller.actor, request)
+    }
+
+    fn bulk_of(&self, tenant_id: Option<&str>, actor: &str, request: &BulkRequest) -> Result<Uuid> {
+        let hosts = request.select(self.tenant_devices(tenant_id))?;
+        let shared = Arc::clone(&self.shared);
+        let request = request.clone();
+        let actor = actor.to_string();
+        let tenant_id = tenant_id.unwrap_or(DEFAULT_TENANT);
+        Ok(self
+            .jobs
+            .submit_for(tenant_id, JobKind::Bulk, move |progress| async move {
+                let mut devices = Vec::with_capacity(hosts.len());
+                for (index, host) in hosts.iter().enumerate() {
+                    devices.push(run_action(&shared, &actor, host, request.action).await);
+                    progress.set(((index + 1) * 100 / hosts.len()) as u8);
+                }
+                info!(
+                    "Bulk action {:?} run on {} devices tagged {}",
+                    request.action,
+                    devices.len(),
+                    request.tag
+                );
+                let report = BulkReport {
+                    tag: request.tag,
+                    action: request.action,
+                    failed: devices
+                        .iter()
+                        .filter(|device| !device.ok && device.skipped.is_none())
+                        .count(),
+                    skipped: devices
+                        .iter()
+                        .filter(|device| device.skipped.is_some())
+                        .count(),
+                    devices,
+                };
+                serde_json::to_value(report).map_err(|err| {
+                    Error::Custom(format!("Failed to serialize bulk report: {}", err))
+                })
+            }))
+    }
+
+    /// Queues an export of a topology of `host` of `tenant_id`, rendered in `format`
+    ///
+    /// The job reports the [`ExportArtifact`], downloaded with [`App::export`] once done.
+    ///
+    /// # Arguments
+    /// - `topology`: The topology to render, the first one of the device if unset
+    ///
+    /// # Returns
+    /// - `Ok(Uuid)`: The id of the job, and of the artifact
+    /// - `Err(Error)`: If `host` was not collected yet, belongs to another tenant or has
+    ///   no such topology
+    pub async fn export_topology(
+        &self,
+        tenant_id: &str,
+        host: &str,
+        format: GraphFormat,
+        topology: Option<Uuid>,
+    ) -> Result<Uuid> {
+        let topology = self.tenant_topology(tenant_id, host, topology).await?;
+        let extension = match format {
+            GraphFormat::Dot => "dot",
+            GraphFormat::Graphml => "graphml",
+            GraphFormat::Json => "json",
+        };
+        Ok(self.shared.exports.submit_for(
+            &self.jobs,
+            tenant_id,
+            &format!("{}.{}", topology.uuid, extension),
+            format.content_type(),
+            move || Ok(render(&topology, format).into_bytes()),
+        ))
+    }
+
+    /// Renders a topology of `host` of `tenant_id` in `format` right away, for
+    /// `GET /topology/{host}/export`
+    ///
+    /// # Arguments
+    /// - `topology`: The topology to render, the first one of the device if unset
+    ///
+    /// # Returns
+    /// - `Ok(String)`: The rendered graph
+    /// - `Err(Error)`: If `host` was not collected yet, belongs to another tenant or has
+    ///   no such topology
+    pub async fn render_topology(
+        &self,
+        tenant_id: &str,
+        host: &str,
+        format: GraphFormat,
+        topology: Option<Uuid>,
+    ) -> Result<String> {
+        let topology = self.tenant_topology(tenant_id, host, topology).await?;
+        Ok(render(&topology, format))
+    }
+
+    /// Returns the topology `uuid` of `host` of `tenant_id`, its first one if unset
+    async fn tenant_topology(
+        &self,
+        tenant_id: &str,
+        host: &str,
+        uuid: Option<Uuid>,
+    ) -> Result<Topology> {
+        self.topologies_for_tenant(tenant_id, host, Topology::clone)
+            .await?
+            .into_iter()
+            .find(|topology| uuid.is_none_or(|uuid| topology.uuid == uuid))
+            .ok_or_else(|| {
+                Error::TopologyNotFound(format!(
+                    "Not found topology {} of {}",
+                    uuid.unwrap_or_default(),
+                    host
+                ))
+            })
+    }
+
+    /// Reads the bytes of the export `id` of `tenant_id` asked for by a download
+    ///
+    /// # Arguments
+    /// - `range`: The `Range` header, the whole artifact is read without it
+    /// - `if_range`: The `If-Range` header, the whole artifact is read if it changed
+    ///
+    /// # Returns
+    /// - `Ok(ExportChunk)`: The bytes and the range they cover
+    /// - `Err(Error)`: If the export is not written yet, expired or belongs to another
+    ///   tenant, or the range is invalid or unsatisfiable
+    pub fn export(
+        &self,
+        tenant_id: &str,
+        id: &Uuid,
+        range: Option<&str>,
+        if_range: Option<&str>,
+    ) -> Result<ExportChunk> {
+        self.export_artifact(tenant_id, id)?;
+        self.shared.exports.read(id, range, if_range)
+    }
+
+    /// Returns the export `id` of `tenant_id`, without its content
+    ///
+    /// # Returns
+    /// - `Ok(ExportArtifact)`: The artifact
+    /// - `Err(Error)`: If the export is not written yet, expired or belongs to another
+    ///   tenant, answered alike
+    pub fn export_artifact(&self, tenant_id: &str, id: &Uuid) -> Result<ExportArtifact> {
+        Some(self.shared.exports.artifact(id)?)
+            .filter(|artifact| artifact.tenant_id == tenant_id)
+            .ok_or_else(|| Error::NotFound(format!("Not found export {}", id)))
+    }
+
+    /// Creates a connectivity service on the device at `host` of the tenant of `caller`,
+    /// audited under its actor once the device accepted it
+    ///
+    /// # Returns
+    /// - `Ok(CreatedService)`: The uuid of the created service
+    /// - `Err(Error)`: If `host` belongs to another tenant, cannot be provisioned, or
+    ///   the device refused the service or could not be reached
+    pub async fn create_connectivity_service(
+        &self,
+        caller: &Caller,
+        host: &str,
+        request: &ConnectivityServiceRequest,
+    ) -> Result<CreatedService> {
+        let client = self.provisioning_client(&caller.tenant_id, host)?;
+        let created = create_connectivity_service(&client, request).await?;
+        audit(
+            &self.shared,
+            Some(&caller.tenant_id),
+            &caller.actor,
+            AuditAction::ServiceCreated,
+            host,
+            Some(json!({ "uuid": created.uuid, "request": request.to_tapi() })),
+        );
+        Ok(created)
+    }
+
+    /// Checks a provisioning request against the cached topologies of the device at
+    /// `host` of `tenant_id`, without reaching the device
+    ///
+    /// The nodes of every topology of the device are checked together, as the end points
+    /// of a service may be found in any of them.
+    ///
+    /// # Returns
+    /// - `Ok(DryRun)`: The violations of the request, empty if the device would be asked
+    ///   to create the service
+    /// - `Err(Error)`: If `host` was not collected yet or belongs to another tenant
+    pub async fn validate_connectivity_service(
+        &self,
+        tenant_id: &str,
+        host: &str,
+        value: &Value,
+    ) -> Result<DryRun> {
+        let mut topologies = self
+            .topologies_for_tenant(tenant_id, host, Topology::clone)
+            .await?
+            .into_iter();
+        let Some(mut topology) = topologies.next() else {
+            return Err(Error::TopologyNotFound(format!(
+                "Not found topology of {}",
+                host
+            )));
+        };
+        for other in topologies {
+            topology.nodes.extend(other.nodes);
+            topology.links.extend(other.links);
+        }
+        Ok(dry_run(value, &topology))
+    }
+
+    /// Queues the deletion of the connectivity service `uuid` on the device at `host` of
+    /// the caller, audited once the device confirms it
+    ///
+    /// # Returns
+    /// - `Ok(Uuid)`: The id of the job, followed through `GET /jobs/{id}`
+    /// - `Err(Error)`: If the device is unknown to the tenant or cannot be provisioned
+    pub fn delete_connectivity_service(
+        &self,
+        caller: &Caller,
+        host: &str,
+        uuid: Uuid,
+    ) -> Result<Uuid> {
+        let client = self.provisioning_client(&caller.tenant_id, host)?;
+        let shared = Arc::clone(&self.shared);
+        let owner = caller.clone();
+        let host = host.to_string();
+        Ok(self.jobs.submit_for(
+            &caller.tenant_id,
+            JobKind::Provisioning,
+            move |progress| async move {
+                let result =
+                    delete_service(&client, uuid, Confirmation::default(), &progress).await?;
+                audit(
+                    &shared,
+                    Some(&owner.tenant_id),
+                    &owner.actor,
+                    AuditAction::ServiceDeleted,
+                    &host,
+                    Some(json!({ "uuid": uuid })),
+                );
+                Ok(result)
+            },
+        ))
+    }
+
+    /// Queues a change of the administrative state of the connectivity service `uuid` on
+    /// the device at `host` of the caller, audited once the device reports the new state
+    ///
+    /// # Returns
+    /// - `Ok(Uuid)`: The id of the job, followed through `GET /jobs/{id}`
+    /// - `Err(Error)`: If the device is unknown to the tenant or cannot be provisioned
+    pub fn change_connectivity_service_state(
+        &self,
+        caller: &Caller,
+        host: &str,
+        uuid: Uuid,
+        change: ServiceStateChange,
+    ) -> Result<Uuid> {
+        let client = self.provisioning_client(&caller.tenant_id, host)?;
+        let shared = Arc::clone(&self.shared);
+        let owner = caller.clone();
+        let host = host.to_string();
+        Ok(self.jobs.submit_for(
+            &caller.tenant_id,
+            JobKind::Provisioning,
+            move |progress| async move {
+                let result =
+                    change_service_state(&client, uuid, change, Confirmation::default(), &progress)
+                        .await?;
+                audit(
+                    &shared,
+                    Some(&owner.tenant_id),
+                    &owner.actor,
+                    AuditAction::ServiceStateChanged,
+                    &host,
+                    Some(json!({ "uuid": uuid, "administrative_state": change.administrative_state })),
+                );
+                Ok(result)
+            },
+        ))
+    }
+
+    /// Returns a RESTCONF client to provision the device at `host` of `tenant_id`
+    ///
+    /// Devices reached over NETCONF or through a jump host are only collected.
+    fn provisioning_client(&self, tenant_id: &str, host: &str) -> Result<RestconfClient> {
+        let device = self
+            .tenant_devices(Some(tenant_id))
+            .find(|device| device.host == host)
+            .ok_or_else(|| Error::NotFound(format!("Not found device {}", host)))?;
+        let config = self.config();
+        if config.netconf(host).is_some() || config.jump_host(host).is_some() {
+            return Err(Error::Invalid(format!(
+                "Device {} is not reached over RESTCONF directly, it cannot be provisioned",
+                host
+            )));
+        }
+        let mut device = device.clone();
+        device.proxy_url = config.proxy_url(&device);
+        RestconfClient::with_resolver(device, self.shared.resolver.clone())
+    }
+
+    /// Returns a job of the application, e.g. a bulk action
+    pub fn job(&self, id: &Uuid) -> Option<Job> {
+        self.jobs.get(id)
+    }
+
+    /// Returns the job `id` if it was queued by `tenant_id`
+    ///
+    /// # Returns
+    /// - `Ok(Job)`: The job and its outcome
+    /// - `Err(Error)`: If no job has the id, or it belongs to another tenant
+    pub fn job_for_tenant(&self, tenant_id: &str, id: &Uuid) -> Result<Job> {
+        self.jobs
+            .get(id)
+            .filter(|job| job.tenant_id == tenant_id)
+            .ok_or_else(|| Error::NotFound(format!("Not found job {}", id)))
+    }
+
+    /// Returns the jobs queued by `tenant_id`, most recent first
+    pub fn jobs_for_tenant(&self, tenant_id: &str) -> Vec<Job> {
+        self.jobs
+            .list()
+            .into_iter()
+            .filter(|job| job.tenant_id == tenant_id)
+            .collect()
+    }
+
+    /// Returns up to `limit` devices, nodes, links and edge points matching `query`
+    ///
+    /// # Returns
+    /// - `Ok(Vec<SearchHit>)`: The hits with their device, best matches first
+    /// - `Err(Error)`: If the query is empty
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.shared.search.lock().unwrap().search(query, limit)
+    }
+
+    /// Returns up to `limit` hits of `query` on the devices of `tenant_id`
+    ///
+    /// # Returns
+    /// - `Ok(Vec<SearchHit>)`: The hits with their device, best matches first
+    /// - `Err(Error)`: If the query is empty
+    pub fn search_for_tenant(
+        &self,
+        tenant_id: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        Ok(self
+            .search(query, usize::MAX)?
+            .into_iter()
+            .filter(|hit| self.owns(tenant_id, &hit.host))
+            .take(limit)
+            .collect())
+    }
+
+    /// Uploads the last snapshot of every device and a report to the archive now
+    ///
+    /// # Returns
+    /// - `Ok(Vec<String>)`: The keys of the uploaded objects
+    /// - `Err(Error)`: If no archive is configured or an upload fails
+    pub async fn archive(&self) -> Result<Vec<String>> {
+        archive_all(&self.shared, Utc::now()).await
+    }
+
+    /// Returns the latency samples of `host` over the `window` up to now
+    ///
+    /// # Returns
+    /// - `Ok(LatencySeries)`: The samples and their statistics
+    /// - `Err(Error)`: If `host` was never probed
+    pub fn latency(&self, host: &str, window: Age) -> Result<LatencySeries> {
+        self.shared
+            .latency
+            .lock()
+            .unwrap()
+            .series(host, window, Local::now())
+    }
+
+    /// Returns the latency of `host` over the `window` if it belongs to `tenant_id`
+    ///
+    /// # Returns
+    /// - `Ok(LatencySeries)`: The samples and their statistics
+    /// - `Err(Error)`: If `host` was never probed or belongs to another tenant
+    pub fn latency_for_tenant(
+        &self,
+        tenant_id: &str,
+        host: &str,
+        window: Age,
+    ) -> Result<LatencySeries> {
+        if !self.owns(tenant_id, host) {
+            return Err(Error::NotFound(format!("Not found latency of {}", host)));
+        }
+        self.latency(host, window)
+    }
+
+    /// Returns the validation rules broken by the last topologies of `host`
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Violation>)`: The violations, none if the topologies are consistent
+    /// - `Err(Error)`: If `host` was not collected yet
+    pub fn violations(&self, host: &str) -> Result<Vec<Violation>> {
+        self.shared
+            .violations
+            .lock()
+            .unwrap()
+            .get(host)
+            .cloned()
+            .ok_or_else(|| Error::TopologyNotFound(format!("Not found topology of {}", host)))
+    }
+
+    /// Returns the validation rules broken by the last topologies of `host` if it is a
+    /// device of `tenant_id`
+    ///
+    /// # Returns
+    /// - `Ok(Vec<Violation>)`: The violations, none if the topologies are consistent
+    /// - `Err(Error)`: If `host` was not collected yet or belongs to another tenant
+    pub fn violations_for_tenant(&self, tenant_id: &str, host: &str) -> Result<Vec<Violation>> {
+        if !self.owns(tenant_id, host) {
+            return Err(Error::TopologyNotFound(format!(
+                "Not found topology of {}",
+                host
+            )));
+        }
+        self.violations(host)
+    }
+
+    /// Compares the last topologies of two devices
+    ///
+    /// # Returns
+    /// - `Ok(TopologyComparison)`: The nodes and links reported by one device only
+    /// - `Err(Error)`: If either device was not collected yet
+    pub async fn compare(&self, left: &str, right: &str) -> Result<TopologyComparison> {
+        let left_topologies = self.topologies(left, Topology::clone).await?;
+        let right_topologies = self.topologies(right, Topology::clone).await?;
+        Ok(compare_topologies(
+            (left, &left_topologies),
+            (right, &right_topologies),
+        ))
+    }
+
+    /// Compares the last topologies of two devices of `tenant_id`
+    ///
+    /// # Returns
+    /// - `Ok(TopologyComparison)`: The nodes and links reported by one device only
+    /// - `Err(Error)`: If either device was not collected yet or belongs to another tenant
+    pub async fn compare_for_tenant(
+        &self,
+        tenant_id: &str,
+        left: &str,
+        right: &str,
+    ) -> Result<TopologyComparison> {
+        let left_topologies = self
+            .topologies_for_tenant(tenant_id, left, Topology::clone)
+            .await?;
+        let right_topologies = self
+            .topologies_for_tenant(tenant_id, right, Topology::clone)
+            .await?;
+        Ok(compare_topologies(
+            (left, &left_topologies),
+            (right, &right_topologies),
+        ))
+    }
+
+    /// Returns how far the topologies of the object store are loaded into the cache
+    ///
+    /// The API answers before the warm-load ends, reading the hosts not loaded yet
+    /// from the store.
+    pub fn readiness(&self) -> Readiness {
+        *self.shared.hydration.lock().unwrap()
+    }
+
+    /// Returns the hit/miss counters of the topology cache
+    pub fn cache_metrics(&self) -> CacheMetrics {
+        self.shared.topologies.metrics()
+    }
+
+    /// Renders the metrics of the application in the Prometheus text format
+    pub fn metrics(&self) -> String {
+        self.shared.latency.lock().unwrap().prometheus()
+    }
+
+    /// Returns the discovered devices waiting for approval
+    pub fn candidates(&self) -> Vec<Candidate> {
+        self.shared
+            .candidates
+            .lock()
+            .unwrap()
+            .pending()
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the topologies of the devices of `tenant_id` stitched into one, converted
+    /// by `view`
+    ///
+    /// Devices not collected yet are left out; links are stitched as configured in the
+    /// `[stitching]` section, with the devices of the tenant only.
+    ///
+    /// # Returns
+    /// - `Ok(T)`: The converted global topology
+    /// - `Err(Error)`: If the stored objects of a device are invalid
+    pub async fn global_topology<T>(
+        &self,
+        tenant_id: &str,
+        view: impl Fn(&GlobalTopology) -> T,
+    ) -> Result<T> {
+        let mut cached = vec![];
+        for device in self.tenant_devices(Some(tenant_id)) {
+            if let Some(topologies) = cached_topologies(&self.shared, device.host.as_str()).await? {
+                cached.push(topologies);
+            }
+        }
+        let topologies: Vec<&Topology> = cached
+            .iter()
+            .flat_map(|topologies| topologies.iter())
+            .collect();
+        Ok(view(&GlobalTopology::stitch(
+            &topologies,
+            &self.config().stitching,
+        )))
+    }
+
+    /// Returns the last topologies of the devices of `tenant_id` as a GeoJSON feature
+    /// collection, for `GET /export/topology.geojson`
+    ///
+    /// Devices not collected yet are left out, like their objects without a site.
+    ///
+    /// # Returns
+    /// - `Ok(Value)`: The `FeatureCollection`
+    /// - `Err(Error)`: If the stored objects of a device are invalid
+    pub async fn geojson(&self, tenant_id: &str) -> Result<Value> {
+        let mut topologies = vec![];
+        for device in self.tenant_devices(Some(tenant_id)) {
+            if let Some(cached) = cached_topologies(&self.shared, device.host.as_str()).await? {
+                topologies.extend(cached.iter().cloned());
+            }
+        }
+        Ok(to_geojson(&topologies))
+    }
+
+    /// Returns the last topologies of `host`, each converted by `view`
+    ///
+    /// Topologies missing from the cache, e.g. after a restart, are read from the object
+    /// store and cached.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<T>)`: The converted topologies
+    /// - `Err(Error)`: If `host` was never collected, or its stored objects are invalid
+    pub async fn topologies<T>(&self, host: &str, view: impl Fn(&Topology) -> T) -> Result<Vec<T>> {
+        cached_topologies(&self.shared, host)
+            .await?
+            .map(|topologies| topologies.iter().map(view).collect())
+            .ok_or_else(|| Error::TopologyNotFound(format!("Not found topology of {}", host)))
+    }
+
+    /// Returns the last topologies of `host` if it is a device of `tenant_id`, each
+    /// converted by `view`
+    ///
+    /// # Returns
+    /// - `Ok(Vec<T>)`: The converted topologies
+    /// - `Err(Error)`: If `host` was not collected yet or belongs to another tenant,
+    ///   answered alike so the hosts of other tenants are not disclosed
+    pub async fn topologies_for_tenant<T>(
+        &self,
+        tenant_id: &str,
+        host: &str,
+        view: impl Fn(&Topology) -> T,
+    ) -> Result<Vec<T>> {
+        if !self.owns(tenant_id, host) {
+            return Err(Error::TopologyNotFound(format!(
+                "Not found topology of {}",
+                host
+            )));
+        }
+        self.topologies(host, view).await
+    }
+
+    /// Returns whether the device at `host` belongs to `tenant_id`
+    fn owns(&self, tenant_id: &str, host: &str) -> bool {
+        self.tenant_devices(Some(tenant_id))
+            .any(|device| device.host == host)
+    }
+
+    /// Returns the devices of the registry, only those of `tenant_id` when set
+    fn tenant_devices<'a>(
+        &'a self,
+        tenant_id: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a Device> + 'a {
+        self.shared
+            .devices
+            .iter()
+            .filter(move |device| tenant_id.is_none_or(|tenant_id| device.tenant_id == tenant_id))
+    }
+
+    /// Returns the last equipment inventory of `host`, converted by `view`
+    ///
+    /// # Returns
+    /// - `Ok(T)`: The converted inventory
+    /// - `Err(Error)`: If the inventory of `host` was not collected yet
+    pub fn inventory<T>(&self, host: &str, view: impl Fn(&PhysicalContext) -> T) -> Result<T> {
+        self.shared
+            .inventory
+            .lock()
+            .unwrap()
+            .get(host)
+            .map(view)
+            .ok_or_else(|| Error::NotFound(format!("Not found inventory of {}", host)))
+    }
+
+    /// Finds the services of the tenant routed over the link `uuid`, for
+    /// `GET /links/{uuid}/impact`
+    ///
+    /// The link is looked up in the topologies of the devices of `tenant_id`, and analysed
+    /// against the last connectivity services collected from its device.
+    ///
+    /// # Returns
+    /// - `Ok(LinkImpact)`: The services affected by the link going down
+    /// - `Err(Error)`: If no device of the tenant reports the link, or the services of its
+    ///   device were not collected yet
+    pub async fn link_impact(&self, tenant_id: &str, uuid: &Uuid) -> Result<LinkImpact> {
+        for device in self.tenant_devices(Some(tenant_id)) {
+            let Some(topologies) = cached_topologies(&self.shared, device.host.as_str()).await?
+            else {
+                continue;
+            };
+            let Some(link) = topologies
+                .iter()
+                .flat_map(|topology| &topology.links)
+                .find(|link| link.uuid == *uuid)
+            else {
+                continue;
+            };
+            return self
+                .shared
+                .connectivity
+                .lock()
+                .unwrap()
+                .get(&link.host)
+                .map(|context| LinkImpact::analyse(link, &context.services, &context.connections))
+                .ok_or_else(|| {
+                    Error::TopologyNotFound(format!(
+                        "Not found connectivity services of {}",
+                        link.host
+                    ))
+                });
+        }
+        Err(Error::TopologyNotFound(format!("Not found link {}", uuid)))
+    }
+
+    /// Returns the statistics of the last poll cycles of `host`, converted by `view`
+    ///
+    /// # Returns
+    /// - `Ok(T)`: The converted statistics
+    /// - `Err(Error)`: If no cycle of `host` completed yet
+    pub fn stats<T>(&self, host: &str, view: impl Fn(DeviceStats) -> T) -> Result<T> {
+        self.shared.stats.lock().unwrap().device(host).map(view)
+    }
+
+    /// Returns the statistics of `host` if it belongs to `tenant_id`
+    ///
+    /// # Returns
+    /// - `Ok(T)`: The converted statistics
+    /// - `Err(Error)`: If no cycle of `host` completed yet or it belongs to another
+    ///   tenant, answered alike
+    pub fn stats_for_tenant<T>(
+        &self,
+        tenant_id: &str,
+        host: &str,
+        view: impl Fn(DeviceStats) -> T,
+    ) -> Result<T> {
+        if !self.owns(tenant_id, host) {
+            return Err(Error::NotFound(format!("Not found stats of {}", host)));
+        }
+        self.stats(host, view)
+    }
+
+    /// Returns the last poll cycle of every device in the Prometheus text format
+    pub fn prometheus(&self) -> String {
+        self.shared.stats.lock().unwrap().prometheus()
+    }
+
+    /// Returns the metrics of the devices of `tenant_id` in the Prometheus text format:
+    /// their last poll cycle and latency histogram, and the event queues, which are
+    /// shared by every tenant and carry no device data
+    pub fn prometheus_for_tenant(&self, tenant_id: &str) -> String {
+        let include = |host: &str| self.owns(tenant_id, host);
+        let mut output = self.shared.stats.lock().unwrap().prometheus_of(include);
+        output.push_str(&self.shared.latency.lock().unwrap().prometheus_of(include));
+        output.push_str(&self.shared.bus.prometheus());
+        output
+    }
+
+    /// Returns the objects of `extractor`, optionally restricted to one host
+    pub fn custom_objects(&self, extractor: &str, host: Option<&str>) -> Vec<CustomObject> {
+        self.shared
+            .custom_objects
+            .lock()
+            .unwrap()
+            .query(extractor, host)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Subscribes to the change events published from now on, as recorded in the log
+    ///
+    /// A subscriber falling behind loses its oldest events rather than slowing polling.
+    pub fn subscribe(&self, name: &str) -> Subscription<StoredEvent> {
+        self.shared.bus.subscribe(name, OverflowPolicy::DropOldest)
+    }
+
+    /// Returns the maintenance windows
+    pub fn maintenance(&self) -> Vec<MaintenanceWindow> {
+        self.shared.maintenance.lock().unwrap().list().to_vec()
+    }
+
+    /// Adds a maintenance window, whose events are stored but not delivered, audited under
+    /// [`LOCAL_ACTOR`]
+    ///
+    /// # Returns
+    /// - `Ok(Uuid)`: The id of the window
+    /// - `Err(Error)`: If the window ends before it starts, or could not be saved
+    pub fn add_maintenance(&self, window: MaintenanceWindow) -> Result<Uuid> {
+        let detail = json!(window);
+        let tenant_id = self.maintenance_tenant(&window);
+        let id = self.shared.maintenance.lock().unwrap().add(window)?;
+        audit(
+            &self.shared,
+            tenant_id.as_deref(),
+            LOCAL_ACTOR,
+            AuditAction::MaintenanceAdded,
+            &id.to_string(),
+            Some(detail),
+        );
+        Ok(id)
+    }
+
+    /// Removes the maintenance window `id`, audited under [`LOCAL_ACTOR`]
+    ///
+    /// # Returns
+    /// - `Ok(())`: If the window was removed
+    /// - `Err(Error)`: If no window has the id, or the schedule could not be saved
+    pub fn remove_maintenance(&self, id: &Uuid) -> Result<()> {
+        let mut maintenance = self.shared.maintenance.lock().unwrap();
+        let window = maintenance
+            .list()
+            .iter()
+            .find(|window| &window.id == id)
+            .cloned();
+        maintenance.remove(id)?;
+        drop(maintenance);
+        audit(
+            &self.shared,
+            window
+                .as_ref()
+                .and_then(|window| self.maintenance_tenant(window))
+                .as_deref(),
+            LOCAL_ACTOR,
+            AuditAction::MaintenanceRemoved,
+            &id.to_string(),
+            window.map(|window| json!(window)),
+        );
+        Ok(())
+    }
+
+    /// Returns the edge agents enrolled for `tenant_id`, oldest first
+    pub fn agents(&self, tenant_id: &str) -> Vec<Agent> {
+        self.shared
+            .agents
+            .lock()
+            .unwrap()
+            .agents(tenant_id)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Issues a one-time enrollment token for an edge agent of `tenant_id`, valid for `ttl`
+    ///
+    /// # Returns
+    /// - `Ok(String)`: The token, only its hash is kept
+    /// - `Err(Error)`: If the registry could not be saved
+    pub fn issue_agent_token(
+        &self,
+        tenant_id: &str,
+        label: &str,
+        ttl: chrono::Duration,
+    ) -> Result<String> {
+        self.shared
+            .agents
+            .lock()
+            .unwrap()
+            .issue_token(tenant_id, label, ttl)
+    }
+
+    /// Enrolls the edge agent `name` with a token issued by [`App::issue_agent_token`]
+    ///
+    /// # Returns
+    /// - `Ok(AgentCredentials)`: The credentials the agent pushes its snapshots with
+    /// - `Err(Error)`: If the token is unknown, expired or already redeemed, or the
+    ///   registry could not be saved
+    pub fn enroll_agent(&self, token: &str, name: &str) -> Result<AgentCredentials> {
+        self.shared.agents.lock().unwrap().enroll(token, name)
+    }
+
+    /// Revokes the edge agent `agent_id` of `tenant_id`
+    ///
+    /// # Returns
+    /// - `Ok(Agent)`: The revoked agent, whose credentials are rejected from then on
+    /// - `Err(Error)`: If no agent of the tenant has the id, or the registry could not
+    ///   be saved
+    pub fn revoke_agent(&self, tenant_id: &str, agent_id: &Uuid) -> Result<Agent> {
+        let mut agents = self.shared.agents.lock().unwrap();
+        if !agents
+            .agents(tenant_id)
+            .iter()
+            .any(|agent| agent.id == *agent_id)
+        {
+            return Err(Error::NotFound(format!("Not found agent {}", agent_id)));
+        }
+        agents
+            .revoke(agent_id)?
+            .ok_or_else(|| Error::NotFound(format!("Not found agent {}", agent_id)))
+    }
+
+    /// Accepts a topology pushed by an edge agent for the device at `host`, see
+    /// [`AgentRegistry::ingest`]
+    ///
+    /// # Returns
+    /// - `Ok((tenant_id, Topology))`: The tenant of the agent and the parsed topology
+    /// - `Err(Error)`: If the credentials or the payload are invalid
+    pub fn ingest_agent_topology(
+        &self,
+        agent_id: &Uuid,
+        secret: &str,
+        host: &str,
+        value: &Value,
+    ) -> Result<(String, Topology)> {
+        self.shared
+            .agents
+            .lock()
+            .unwrap()
+            .ingest(agent_id, secret, host, value)
+    }
+
+    /// Merges a snapshot pushed by the edge agent `agent_id`, under the tenant of the
+    /// agent, for `POST /agents/{id}/snapshots`
+    ///
+    /// Every topology of the snapshot is parsed before the merge, so a snapshot the
+    /// backend could not read is refused as a whole.
+    ///
+    /// # Returns
+    /// - `Ok(MergeOutcome)`: Whether the snapshot is now the latest of its host
+    /// - `Err(Error)`: If the credentials are invalid, the snapshot belongs to another
+    ///   agent or holds an invalid topology
+    pub fn ingest_snapshot(
+        &self,
+        agent_id: &Uuid,
+        secret: &str,
+        snapshot: Snapshot,
+    ) -> Result<MergeOutcome> {
+        let tenant_id = {
+            let mut agents = self.shared.agents.lock().unwrap();
+            let tenant_id = agents.authenticate(agent_id, secret)?.tenant_id.clone();
+            if snapshot.agent_id != *agent_id {
+                return Err(Error::Invalid(format!(
+                    "Invalid snapshot: collected by agent {}, not {}",
+                    snapshot.agent_id, agent_id
+                )));
+            }
+            for value in &snapshot.topologies {
+                // A payload the agent could not parse either is the agent's fault
+                agents
+                    .ingest(agent_id, secret, &snapshot.host, value)
+                    .map_err(|err| match err {
+                        Error::BadResponse(message) => {
+                            Error::Invalid(format!("Invalid snapshot: {}", message))
+                        }
+                        err => err,
+                    })?;
+            }
+            tenant_id
+        };
+        let host = snapshot.host.clone();
+        let history = self.shared.snapshot_log.is_some().then(|| snapshot.clone());
+        let outcome = self
+            .shared
+            .snapshots
+            .lock()
+            .unwrap()
+            .merge(&tenant_id, snapshot);
+        // Retries are already in the history
+        if let Some(snapshot) = history.filter(|_| outcome != MergeOutcome::Duplicate) {
+            record_snapshot(&self.shared, &snapshot);
+        }
+        debug!(
+            "Snapshot of {} from agent {}: {:?}",
+            host, agent_id, outcome
+        );
+        Ok(outcome)
+    }
+
+    /// Returns the tenant of the device a maintenance window applies to
+    fn maintenance_tenant(&self, window: &MaintenanceWindow) -> Option<String> {
+        let host = match &window.target {
+            MaintenanceTarget::Device { host } | MaintenanceTarget::Link { host, .. } => host,
+        };
+        self.shared
+            .devices
+            .iter()
+            .find(|device| device.host.as_str() == host)
+            .map(|device| device.tenant_id.clone())
+    }
+
+    /// Opens a Server-Sent Events stream on the events of `tenant_id`
+    ///
+    /// # Arguments
+    /// - `tenant_id`: Tenant of the API request
+    /// - `last_event_id`: The `Last-Event-ID` header; the events recorded after it are
+    ///   replayed first, without it the stream starts with the next event
+    ///
+    /// # Returns
+    /// - `Ok(EventStream)`: The stream, sending a heartbeat when idle
+    /// - `Err(Error)`: If `last_event_id` is not an event id
+    pub fn event_stream(
+        &self,
+        tenant_id: &str,
+        last_event_id: Option<&str>,
+    ) -> Result<EventStream> {
+        EventStream::new(
+            Arc::clone(&self.shared.events),
+            tenant_id,
+            last_event_id,
+            HEARTBEAT_INTERVAL,
+        )
+    }
+
+    /// Replays the events of `tenant_id` from `since`, at most `limit` of them
+    ///
+    /// # Arguments
+    /// - `since`: An RFC 3339 timestamp, or the `next_cursor` of a previous page
+    ///
+    /// # Returns
+    /// - `Ok(EventPage)`: The page, with a signed cursor to resume from
+    /// - `Err(Error)`: If `since` is neither a timestamp nor a valid cursor of the tenant
+    pub fn replay_events(
+        &self,
+        tenant_id: &str,
+        since: Option<&str>,
+        limit: usize,
+    ) -> Result<EventPage> {
+        self.shared
+            .events
+            .lock()
+            .unwrap()
+            .replay(tenant_id, since, limit, &self.shared.cursors)
+    }
+
+    /// Returns the number of events recorded since the event log was created
+    pub fn events(&self) -> usize {
+        self.shared.events.lock().unwrap().len()
+    }
+
+    /// Returns the last threshold alerts of `tenant_id`, most recent first, those raised
+    /// during a maintenance of their device tagged `suppressed`
+    pub fn alerts(&self, tenant_id: &str) -> Vec<StoredAlert> {
+        self.shared
+            .alert_history
+            .lock()
+            .unwrap()
+            .list(tenant_id)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the audit entries matching `query`, most recent first
+    pub fn audit(&self, query: &AuditQuery) -> Vec<AuditEntry> {
+        self.shared.audit.lock().unwrap().query(query)
+    }
+
+    /// Returns the audit entries of `tenant_id` matching `query`, most recent first
+    pub fn audit_for_tenant(&self, tenant_id: &str, query: &AuditQuery) -> Vec<AuditEntry> {
+        self.shared
+            .audit
+            .lock()
+            .unwrap()
+            .query_tenant(tenant_id, query)
+    }
+
+    /// Stops every task
+    pub fn shutdown(self) {
+        self.supervisor.shutdown();
+        self.jobs.shutdown();
+        info!("Device Manager stopped");
+    }
+}
+
+/// Applies the settings of a reloaded config that are not read on every cycle
+fn apply_config(config: &Config, changes: &[ConfigChange], log_filter: Option<&LogFilterHandle>) {
+    let log_level = changes.iter().any(|change| change.setting == "log_level");
+    if let (true, Some(log_filter)) = (log_level, log_filter) {
+        // An invalid level keeps the previous one
+        if let Err(err) = log_filter.set(&config.log_level) {
+            warn!("{}", err);
+        }
+    }
+}
+
+/// Reads the JSON array of devices at `path`
+fn read_devices(path: &PathBuf) -> Result<Vec<Device>> {
+    let content = fs::read_to_string(path)
+        .map_err(|err| Error::Custom(format!("Failed to read {}: {}", path.display(), err)))?;
+    let registry: Value = serde_json::from_str(&content)
+        .map_err(|err| Error::Custom(format!("Invalid JSON in {}: {}", path.display(), err)))?;
+    registry
+        .as_array()
+        .ok_or_else(|| Error::from("Device file must hold an array of devices"))?
+        .iter()
+        .map(Device::from_value)
+        .collect()
+}
+
+/// Archives the snapshots and a report at the interval of `[archive]`, until stopped
+async fn archive(shared: Arc<Shared>) -> Result<()> {
+    loop {
+        let interval = shared
+            .config
+            .current()
+            .archive
+            .map_or(HOUSEKEEPING_INTERVAL, |archive| {
+                Duration::from_secs(archive.interval)
+            });
+        tokio::time::sleep(interval).await;
+        // A failed upload is retried at the next interval, the store may be down a while
+        if let Err(err) = archive_all(&shared, Utc::now()).await {
+            warn!("{}", err);
+        }
+    }
+}
+
+/// Measures the latency of `device` at the URL it is polled at
+///
+/// The device is reached over HTTPS, directly or through its proxy.
+async fn probe(shared: &Shared, config: &Config, device: &Device) -> LatencySample {
+    let mut device = device.clone();
+    device.proxy_url = config.proxy_url(&device);
+    let base_url = match device.port {
+        Some(port) => format!("https://{}:{}", device.host.url_host(), port),
+        None => format!("https://{}", device.host.url_host()),
+    };
+    measure(&device, &base_url, config.latency.probe, &shared.resolver).await
+}
+
+/// Probes the latency of every enabled device at the interval of `[latency]`
+///
+/// Devices behind a jump host are left out, their tunnel belongs to the collector,
+/// and so are NETCONF devices, which are not reached over HTTPS.
+async fn probe_latency(shared: Arc<Shared>) -> Result<()> {
+    loop {
+        let config = shared.config.current();
+        for device in &shared.devices {
+            let host = device.host.as_str();
+            let direct = config.jump_host(host).is_none() && config.netconf(host).is_none();
+            if !config.enabled(host, device.enabled) || !direct {
+                continue;
+            }
+            let sample = probe(&shared, &config, device).await;
+            if sample.latency_ms.is_none() {
+                warn!("No answer from {} in time", host);
+            }
+            let alert = shared.thresholds.lock().unwrap().latency(
+                host,
+                &sample,
+                &config.notifications.thresholds,
+            );
+            if let Some(alert) = alert {
+                let _ = shared.alerts.send(alert);
+            }
+            shared.latency.lock().unwrap().record(host, sample)?;
+        }
+        tokio::time::sleep(Duration::from_secs(config.latency.interval)).await;
+    }
+}
+
+/// Scans the ranges of `[discovery]` at its interval, until stopped
+///
+/// Found devices are proposed as candidates; a scan disabled by a reload is resumed
+/// when the section is set again.
+async fn discover(shared: Arc<Shared>) -> Result<()> {
+    loop {
+        let Some(config) = shared.config.current().discovery else {
+            tokio::time::sleep(HOUSEKEEPING_INTERVAL).await;
+            continue;
+        };
+        let found = scan(&config).await?;
+        let proposed = shared
+            .candidates
+            .lock()
+            .unwrap()
+            .propose(found, &shared.devices)?;
+        info!("{} new devices to approve", proposed);
+        tokio::time::sleep(Duration::from_secs(config.interval)).await;
+    }
+}
+
+/// Uploads the last topologies of every device, then a report of the archive
+async fn archive_all(shared: &Shared, at: DateTime<Utc>) -> Result<Vec<String>> {
+    let sink = shared
+        .archive
+        .as_ref()
+        .ok_or_else(|| Error::NotFound("Not found archive settings".to_string()))?;
+    // Serialized up front, the lock is not held across uploads
+    let (snapshots, devices): (Vec<(String, Value)>, Vec<Value>) = shared
+        .topologies
+        .snapshot()
+        .iter()
+        .map(|(host, topologies)| {
+            let device = json!({
+                "host": host,
+                "nodes": topologies.iter().map(|topology| topology.nodes.len()).sum::<usize>(),
+                "links": topologies.iter().map(|topology| topology.links.len()).sum::<usize>(),
+            });
+            ((host.clone(), json!(topologies.as_ref())), device)
+        })
+        .unzip();
+
+    let mut keys = vec![];
+    for (host, topologies) in &snapshots {
+        keys.push(
+            sink.archive("snapshots", Some(host), topologies, at)
+                .await?,
+        );
+    }
+    let report = json!({
+        "archived_at": at,
+        "devices": devices,
+        "objects": shared.objects.lock().unwrap().len(),
+        "events": shared.events.lock().unwrap().len(),
+    });
+    keys.push(sink.archive("reports", None, &report, at).await?);
+    Ok(keys)
+}
+
+/// Driver a collector reads its device with
+enum Southbound {
+    Restconf(RestconfClient),
+    Netconf(NetconfClient), // `[devices."<host>".netconf]` is set
+}
+
+impl Southbound {
+    /// Fetches the resource at `path` unless it is unchanged since the previous fetch
+    async fn get_if_changed(&self, path: &str) -> Result<Fetched> {
+        match self {
+            Southbound::Restconf(client) => {
+                client.get_if_changed(path, &FetchOptions::default()).await
+            }
+            Southbound::Netconf(client) => client.get_if_changed(path).await,
+        }
+    }
+}
+
+/// Polls `device` forever; an error ends the task, which the supervisor restarts
+///
+/// Every poll is a [`DeviceCycle`] collecting the topology, then the inventory and the
+/// connectivity services, within the cycle budget of the device. Only a topology that
+/// could not be collected fails the poll, the others being optional on many controllers.
+async fn collect(shared: Arc<Shared>, mut device: Device) -> Result<()> {
+    let host = device.host.to_string();
+    reschedule(&shared, &host, |schedule| {
+        schedule.phase = CollectorPhase::Starting;
+        schedule.step = None;
+        schedule.next_run = None;
+    });
+    let config = shared.config.current();
+    device.proxy_url = config.proxy_url(&device);
+    // NETCONF devices are reached directly, over their own SSH session
+    let netconf = config.netconf(&host).cloned();
+    // The tunnel lives as long as the task; a restart opens a new one
+    let mut tunnel = match (config.jump_host(&host), &netconf) {
+        (Some(jump), None) => {
+            let port = device.port.map_or(443, |port| port.get());
+            Some(SshTunnel::open(jump, device.host.as_str(), port).await?)
+        }
+        _ => None,
+    };
+    let client = match (&netconf, &tunnel) {
+        (Some(options), _) => {
+            Southbound::Netconf(NetconfClient::connect(device.clone(), options).await?)
+        }
+        (None, Some(tunnel)) => Southbound::Restconf(RestconfClient::with_tunnel(
+            device.clone(),
+            shared.resolver.clone(),
+            tunnel.local_address(),
+        )?),
+        (None, None) => Southbound::Restconf(RestconfClient::with_resolver(
+            device.clone(),
+            shared.resolver.clone(),
+        )?),
+    };
+    let mut cycle = DeviceCycle::with_scope(
+        config.cycle_budget(&host),
+        vec![
+            CollectionStep::Topology,
+            CollectionStep::Inventory,
+            CollectionStep::Connectivity,
+        ],
+    );
+    loop {
+        let config = shared.config.current();
+        let paused = shared.paused.lock().unwrap().contains(&host);
+        if config.enabled(&host, device.enabled) && !paused {
+            if tunnel.as_mut().is_some_and(|tunnel| !tunnel.is_open()) {
+                return Err(Error::Unreachable(format!(
+                    "Device unreachable: SSH tunnel of {} closed",
+                    host
+                )));
+            }
+            cycle.set_budget(config.cycle_budget(&host));
+            let started = Local::now();
+            let report = cycle
+                .run(&host, |step| {
+                    let (shared, client, host) = (&shared, &client, host.as_str());
+                    reschedule(shared, host, |schedule| {
+                        schedule.phase = CollectorPhase::Collecting;
+                        schedule.step = Some(step);
+                        schedule.next_run = None;
+                    });
+                    async move {
+                        match step {
+                            CollectionStep::Topology => poll(shared, client, host).await,
+                            CollectionStep::Inventory => inventory(shared, client, host).await,
+                            CollectionStep::Connectivity => {
+                                connectivity(shared, client, host).await
+                            }
+                            CollectionStep::Alarms => Ok(()),
+                        }
+                    }
+                })
+                .await;
+            let interval = config.poll_interval(&host);
+            reschedule(&shared, &host, |schedule| {
+                schedule.phase = CollectorPhase::Waiting;
+                schedule.step = None;
+                schedule.last_run = Some(started);
+                schedule.last_duration_ms = Some(report.elapsed.as_millis() as u64);
+                schedule.next_run = chrono::Duration::from_std(interval)
+                    .ok()
+                    .map(|interval| Local::now() + interval);
+            });
+            let result = cycle_result(&host, &report);
+            let thresholds = &config.notifications.thresholds;
+            let alert = {
+                let mut monitor = shared.thresholds.lock().unwrap();
+                match &result {
+                    Ok(()) => monitor.poll_succeeded(&host, Local::now()),
+                    Err(err) => {
+                        monitor.poll_failed(&host, &err.to_string(), thresholds, Local::now())
+                    }
+                }
+            };
+            if let Some(alert) = alert {
+                let _ = shared.alerts.send(alert);
+            }
+            result?;
+        } else {
+            reschedule(&shared, &host, |schedule| {
+                schedule.phase = match paused {
+                    true => CollectorPhase::Paused,
+                    false => CollectorPhase::Disabled,
+                };
+                schedule.step = None;
+                schedule.next_run = None;
+            });
+        }
+        // Bulk actions wake the collector to poll now, or to notice a pause or a resume
+        match shared.wakeups.get(&host) {
+            Some(wakeup) => {
+                tokio::select! {
+                    _ = tokio::time::sleep(config.poll_interval(&host)) => {}
+                    _ = wakeup.notified() => {}
+                }
+            }
+            None => tokio::time::sleep(config.poll_interval(&host)).await,
+        }
+    }
+}
+
+/// Records a mutating operation of `actor`, a failure to write the audit log only being
+/// logged so the operation itself is not undone
+fn audit(
+    shared: &Shared,
+    tenant_id: Option<&str>,
+    actor: &str,
+    action: AuditAction,
+    target: &str,
+    detail: Option<Value>,
+) {
+    let mut log = shared.audit.lock().unwrap();
+    let recorded = match tenant_id {
+        Some(tenant_id) => log.record_for(tenant_id, actor, action, target, detail),
+        None => log.record(actor, action, target, detail),
+    };
+    if let Err(err) = recorded {
+        warn!("{}", err);
+    }
+}
+
+/// Runs a bulk action on the device `host`
+async fn run_action(
+    shared: &Shared,
+    actor: &str,
+    host: &str,
+    action: BulkAction,
+) -> DeviceActionResult {
+    let config = shared.config.current();
+    let Some(device) = shared
+        .devices
+        .iter()
+        .find(|device| device.host.as_str() == host)
+    else {
+        return DeviceActionResult::failed(
+            host,
+            &Error::NotFound(format!("Not found device {}", host)),
+        );
+    };
+    let wake = || {
+        if let Some(wakeup) = shared.wakeups.get(host) {
+            wakeup.notify_one();
+        }
+    };
+    match action {
+        BulkAction::PausePolling => {
+            shared.paused.lock().unwrap().insert(host.to_string());
+            wake();
+            audit(
+                shared,
+                Some(&device.tenant_id),
+                actor,
+                AuditAction::PollingPaused,
+                host,
+                None,
+            );
+            DeviceActionResult::done(host, json!({ "paused": true }))
+        }
+        BulkAction::Resume => {
+            let resumed = shared.paused.lock().unwrap().remove(host);
+            wake();
+            audit(
+                shared,
+                Some(&device.tenant_id),
+                actor,
+                AuditAction::PollingResumed,
+                host,
+                None,
+            );
+            DeviceActionResult::done(host, json!({ "paused": false, "resumed": resumed }))
+        }
+        BulkAction::TriggerPoll => {
+            if !config.enabled(host, device.enabled) {
+                let error = Error::Invalid(format!("Polling of {} is disabled", host));
+                return DeviceActionResult::failed(host, &error);
+            }
+            if shared.paused.lock().unwrap().contains(host) {
+                let error = Error::Invalid(format!("Polling of {} is paused", host));
+                return DeviceActionResult::failed(host, &error);
+            }
+            wake();
+            audit(
+                shared,
+                Some(&device.tenant_id),
+                actor,
+                AuditAction::PollTriggered,
+                host,
+                None,
+            );
+            DeviceActionResult::done(host, json!({ "triggered": true }))
+        }
+        BulkAction::HealthCheck => {
+            let schedule = shared.schedule.lock().unwrap().get(host).cloned();
+            // Not probed over HTTPS, like in `probe_latency`
+            if config.jump_host(host).is_some() {
+                return DeviceActionResult::skipped(host, "Reached through a jump host");
+            }
+            if config.netconf(host).is_some() {
+                return DeviceActionResult::skipped(host, "Reached over NETCONF");
+            }
+            match probe(shared, &config, device).await.latency_ms {
+                Some(latency_ms) => DeviceActionResult::done(
+                    host,
+                    json!({ "latency_ms": latency_ms, "schedule": schedule }),
+                ),
+                None => DeviceActionResult::failed(
+                    host,
+                    &Error::Timeout(format!("Device timeout: no answer from {} in time", host)),
+                ),
+            }
+        }
+    }
+}
+
+/// Updates the schedule of `host` reported by [`App::scheduler`]
+fn reschedule(shared: &Shared, host: &str, change: impl FnOnce(&mut DeviceSchedule)) {
+    if let Some(schedule) = shared.schedule.lock().unwrap().get_mut(host) {
+        change(schedule);
+    }
+}
+
+/// Returns the result of a collection cycle, the error of its topology step if any
+///
+/// Steps beyond the topology are logged when they fail, without failing the cycle.
+fn cycle_result(host: &str, report: &CycleReport) -> Result<()> {
+    let mut result = Ok(());
+    for (step, outcome) in &report.steps {
+        let error = match outcome {
+            StepOutcome::Completed => continue,
+            StepOutcome::Failed { error } => Error::Custom(error.clone()),
+            StepOutcome::Aborted | StepOutcome::Skipped => Error::Timeout(format!(
+                "Device timeout: {:?} of {} not collected within the cycle budget",
+                step, host
+            )),
+        };
+        match step {
+            CollectionStep::Topology => result = Err(error),
+            _ => debug!("{:?} of {} not collected: {}", step, host, error),
+        }
+    }
+    result
+}
+
+/// Returns the last topologies of `host`, read from the object store on a cache miss
+///
+/// The cache is empty after a restart, while the journal of the store already holds
+/// the objects of the previous run.
+async fn cached_topologies(shared: &Shared, host: &str) -> Result<Option<Arc<[Topology]>>> {
+    shared
+        .topologies
+        .get_or_load(host, |host| async move { stored_topologies(shared, &host) })
+        .await
+}
+
+/// Rebuilds the topologies of `host` from the object store, annotated with their sites
+fn stored_topologies(shared: &Shared, host: &str) -> Result<Option<Vec<Topology>>> {
+    let topologies = shared.objects.lock().unwrap().topologies(host)?;
+    Ok(topologies.map(|mut topologies| {
+        if let Some(sites) = &shared.sites {
+            for topology in &mut topologies {
+                enrich(topology, sites);
+            }
+        }
+        topologies
+    }))
+}
+
+/// Collects the equipment inventory of `host`, kept for the API
+async fn inventory(shared: &Shared, client: &Southbound, host: &str) -> Result<()> {
+    let context = match client.get_if_changed(PHYSICAL_CONTEXT_PATH).await? {
+        Fetched::Unchanged => return Ok(()),
+        Fetched::Modified(context) => context,
+    };
+    let inventory = PhysicalContext::from_value(&context, host)?;
+    shared
+        .inventory
+        .lock()
+        .unwrap()
+        .insert(host.to_string(), inventory);
+    Ok(())
+}
+
+/// Collects the connectivity services and connections of `host`, kept for the impact
+/// analysis of its links
+async fn connectivity(shared: &Shared, client: &Southbound, host: &str) -> Result<()> {
+    let context = match client.get_if_changed(CONNECTIVITY_CONTEXT_PATH).await? {
+        Fetched::Unchanged => return Ok(()),
+        Fetched::Modified(context) => context,
+    };
+    let connectivity = ConnectivityContext::from_value(&context, host)?;
+    shared
+        .connectivity
+        .lock()
+        .unwrap()
+        .insert(host.to_string(), connectivity);
+    Ok(())
+}
+
+/// Collects the topologies of `host`, then stores and publishes the changes
+async fn poll(shared: &Shared, client: &Southbound, host: &str) -> Result<()> {
+    let started = Instant::now();
+    let context = match client.get_if_changed(TOPOLOGY_CONTEXT_PATH).await? {
+        Fetched::Unchanged => return Ok(()),
+        Fetched::Modified(context) => context,
+    };
+    let http_latency = started.elapsed();
+
+    let config = shared.config.current();
+    let pair = config.pair_links(host);
+    // A store that cannot be read only costs the change events of this collection
+    let previous = cached_topologies(shared, host).await.unwrap_or_else(|err| {
+        warn!("{}", err);
+        None
+    });
+    let device = shared.devices.iter().find(|device| device.host == host);
+    let options = ParseOptions {
+        capture: shared.capture.as_ref(),
+        profile: device.and_then(|device| VendorProfile::for_device(device, &shared.profiles)),
+        version: config.tapi_version(host),
+        previous: previous.as_deref(),
+        ..Default::default()
+    };
+    let mut skipped = 0;
+    let (payloads, topologies) = tokio::task::block_in_place(|| {
+        // Reported only, a nonconformant payload is still parsed as far as possible
+        if let Some(version) = config.validation.schema {
+            for violation in validate_context(&context, version)? {
+                warn!(
+                    "Payload of {} breaks TAPI {} at {}: {}",
+                    host, version, violation.pointer, violation.message
+                );
+            }
+        }
+        let payloads = Topology::list_from_context(&context)?;
+        let topologies = payloads
+            .iter()
+            .map(|value| {
+                let (mut topology, report) = Topology::parse(value, host, &options)?;
+                if pair {
+                    pair_links(&mut topology);
+                }
+                if let Some(sites) = &shared.sites {
+                    enrich(&mut topology, sites);
+                }
+                for issue in &report.issues {
+                    warn!(
+                        "Skipped {} {} of {}: {}",
+                        issue.object, issue.index, host, issue.error
+                    );
+                }
+                skipped += report.issues.len();
+                Ok(topology)
+            })
+            .collect::<Result<Vec<Topology>>>()?;
+        Ok::<_, Error>((payloads, topologies))
+    })?;
+    // Extractors only read the payload, their failures never fail the poll
+    tokio::task::block_in_place(|| {
+        let mut custom_objects = shared.custom_objects.lock().unwrap();
+        if let Err(err) = shared.extractors.run(host, &context, &mut custom_objects) {
+            warn!("{}", err);
+        }
+    });
+    let parsed = topologies
+        .iter()
+        .map(|topology| topology.nodes.len() + topology.links.len())
+        .sum();
+
+    let mut events: Vec<ChangeEvent> = {
+        topologies
+            .iter()
+            .flat_map(|topology| {
+                previous
+                    .iter()
+                    .flat_map(|previous| previous.iter())
+                    .find(|before| before.uuid == topology.uuid)
+                    .map(|before| diff_topologies(before, topology))
+                    .unwrap_or_default()
+            })
+            .collect()
+    };
+
+    let delta = {
+        let mut objects = shared.objects.lock().unwrap();
+        let delta = objects.ingest(host, &topologies)?;
+        shared
+            .search
+            .lock()
+            .unwrap()
+            .update(host, device, &objects, &[]);
+        delta
+    };
+    shared.topologies.insert(host, topologies);
+    let violations = validate(
+        host,
+        &shared.topologies.snapshot(),
+        &config.validation.rules,
+    );
+    // Only violations the previous collection did not show are reported as events
+    let reported = shared
+        .violations
+        .lock()
+        .unwrap()
+        .insert(host.to_string(), violations.clone())
+        .unwrap_or_default();
+    let date = Local::now();
+    events.extend(
+        violations
+            .into_iter()
+            .filter(|violation| !reported.contains(violation))
+            .map(|violation| ChangeEvent {
+                host: violation.host,
+                topology_uuid: violation.topology_uuid,
+                object: ObjectKind::Link,
+                uuid: violation.link_uuid,
+                change: ChangeKind::Violation {
+                    rule: violation.rule,
+                    detail: violation.detail,
+                },
+                date,
+            }),
+    );
+    let tenant_id = device
+        .map(|device| device.tenant_id.clone())
+        .unwrap_or_default();
+    // Events of a maintenance window are stored tagged `suppressed`, and not delivered
+    let stored = {
+        let maintenance = shared.maintenance.lock().unwrap();
+        let mut store = shared.events.lock().unwrap();
+        let mut stored = Vec::with_capacity(events.len());
+        for event in &events {
+            let id = store.append_scheduled(&tenant_id, event.clone(), &maintenance)?;
+            stored.extend(store.get(id).cloned());
+        }
+        stored
+    };
+    info!(
+        "Collected {}: {} objects written, {} events",
+        host,
+        delta.writes(),
+        events.len()
+    );
+    if shared.snapshot_log.is_some() {
+        let snapshot = Snapshot {
+            agent_id: LOCAL_AGENT,
+            sequence: date.timestamp_micros() as u64,
+            host: host.to_string(),
+            collected_at: date,
+            topologies: payloads,
+            events,
+        };
+        record_snapshot(shared, &snapshot);
+    }
+    let cycle = CycleStats {
+        collected_at: date,
+        parsed,
+        skipped,
+        duration_ms: started.elapsed().as_millis() as u64,
+        payload_bytes: serde_json::to_vec(&context).map_or(0, |payload| payload.len()),
+        http_latency_ms: http_latency.as_millis() as u64,
+    };
+    if let Err(err) = shared.stats.lock().unwrap().record(host, cycle) {
+        warn!("{}", err);
+    }
+
+    for event in stored {
+        shared.bus.publish(event).await;
+    }
+    Ok(())
+}
+
+/// Appends `snapshot` to the snapshot history, if one is kept
+///
+/// A history that cannot be written only costs the past snapshots, never the collection.
+fn record_snapshot(shared: &Shared, snapshot: &Snapshot) {
+    if let Some(log) = &shared.snapshot_log {
+        if let Err(err) = log.lock().unwrap().append(snapshot) {
+            warn!("{}", err);
+        }
+    }
+}
+
+/// Delivers the published events and the threshold alerts to the notification channels
+///
+/// An alert a sink fails to take is spooled for it, and later alerts of the sink queue
+/// behind it; the spools are drained before every delivery and on every housekeeping
+/// tick, so a recovered sink gets its alerts in order.
+async fn notify(
+    shared: Arc<Shared>,
+    subscription: Arc<tokio::sync::Mutex<Subscription<StoredEvent>>>,
+    alerts: Arc<tokio::sync::Mutex<mpsc::UnboundedReceiver<Alert>>>,
+    chat: Arc<ChatNotifier>,
+    email: Option<Arc<tokio::sync::Mutex<EmailNotifier>>>,
+) -> Result<()> {
+    let mut subscription = subscription.lock().await;
+    let mut alerts = alerts.lock().await;
+    let mut housekeeping = tokio::time::interval(HOUSEKEEPING_INTERVAL);
+    loop {
+        let alert = tokio::select! {
+            event = subscription.recv() => match event {
+                // Recorded during a maintenance window of its object
+                Some(event) if !event.deliverable() => continue,
+                Some(event) => Alert::TopologyChange { event: event.event },
+                None => return Ok(()),
+            },
+            alert = alerts.recv() => match alert {
+                // Raised during a maintenance window of its device
+                Some(alert) if !record_alert(&shared, &alert) => continue,
+                Some(alert) => alert,
+                None => return Ok(()),
+            },
+            _ = housekeeping.tick() => {
+                if let Some(email) = &email {
+                    if let Err(err) = email.lock().await.flush(Local::now()).await {
+                        warn!("{}", err);
+                    }
+                }
+                for sink in DELIVERED_SINKS {
+                    drain_spool(&shared, &chat, email.as_deref(), sink).await;
+                }
+                continue;
+            }
+        };
+
+        let device = shared
+            .devices
+            .iter()
+            .find(|device| device.host == alert.host());
+        if device.is_some_and(|device| !device.alerts_enabled(Local::now())) {
+            continue;
+        }
+        let tags = device_tags(&shared, alert.host());
+
+        // Without routing rules, every channel filters the events on its own settings
+        let sinks: BTreeSet<Sink> = match &shared.routing {
+            Some(routing) => routing.sinks(
+                &alert,
+                &RoutingContext {
+                    device_tags: &tags,
+                    layer_protocol: layer_protocol(&shared, &alert),
+                },
+            ),
+            None => DELIVERED_SINKS.into(),
+        };
+        for sink in sinks {
+            let pending = shared
+                .spools
+                .lock()
+                .unwrap()
+                .get(&sink)
+                .is_some_and(|spool| !spool.is_empty());
+            if pending {
+                spool(&shared, sink, alert.clone());
+                drain_spool(&shared, &chat, email.as_deref(), sink).await;
+            } else if let Err(err) = deliver(&chat, email.as_deref(), sink, &alert, &tags).await {
+                warn!("{}", err);
+                spool(&shared, sink, alert.clone());
+            }
+        }
+    }
+}
+
+/// Records a threshold alert, tagged `suppressed` while its device is under maintenance,
+/// and returns whether it is delivered
+fn record_alert(shared: &Shared, alert: &Alert) -> bool {
+    let device = shared
+        .devices
+        .iter()
+        .find(|device| device.host == alert.host());
+    let suppressed = device.is_some_and(|device| !device.alerts_enabled(alert.date()))
+        || shared.maintenance.lock().unwrap().suppresses_alert(alert);
+    let tenant_id = device
+        .map(|device| device.tenant_id.as_str())
+        .unwrap_or_default();
+    shared
+        .alert_history
+        .lock()
+        .unwrap()
+        .record(tenant_id, alert.clone(), suppressed);
+    !suppressed
+}
+
+/// Returns the tags of the device of `host`, none for an unknown host
+fn device_tags(shared: &Shared, host: &str) -> Vec<String> {
+    shared
+        .devices
+        .iter()
+        .find(|device| device.host == host)
+        .map(|device| device.tags.clone())
+        .unwrap_or_default()
+}
+
+/// Delivers `alert` to the channels of `sink`, a sink without configured channels taking
+/// every alert
+async fn deliver(
+    chat: &ChatNotifier,
+    email: Option<&tokio::sync::Mutex<EmailNotifier>>,
+    sink: Sink,
+    alert: &Alert,
+    tags: &[String],
+) -> Result<()> {
+    match (sink, email) {
+        (Sink::Slack, _) => chat.notify(alert, tags, &[ChatKind::Slack]).await.map(drop),
+        (Sink::Teams, _) => chat.notify(alert, tags, &[ChatKind::Teams]).await.map(drop),
+        (Sink::Email, Some(email)) => email.lock().await.notify_alert(alert, tags).await.map(drop),
+        (Sink::Email, None) => Ok(()),
+        (Sink::Webhook | Sink::Kafka, _) => Err(Error::Invalid(format!(
+            "No delivery for sink {}",
+            sink.as_str()
+        ))),
+    }
+}
+
+/// Queues `alert` until `sink` takes it
+fn spool(shared: &Shared, sink: Sink, alert: Alert) {
+    if let Some(spool) = shared.spools.lock().unwrap().get_mut(&sink) {
+        if let Err(err) = spool.push(alert) {
+            warn!("{}", err);
+        }
+    }
+}
+
+/// Delivers the spooled alerts of `sink` in order, until its spool is empty or a
+/// delivery fails, the failed alert staying at the head of the spool
+async fn drain_spool(
+    shared: &Shared,
+    chat: &ChatNotifier,
+    email: Option<&tokio::sync::Mutex<EmailNotifier>>,
+    sink: Sink,
+) {
+    let mut delivered = 0;
+    loop {
+        let alert = match shared.spools.lock().unwrap().get(&sink) {
+            Some(spool) => spool.peek().cloned(),
+            None => None,
+        };
+        let Some(alert) = alert else {
+            break;
+        };
+        let tags = device_tags(shared, alert.host());
+        if let Err(err) = deliver(chat, email, sink, &alert, &tags).await {
+            warn!("Spooled alerts kept for {}: {}", sink.as_str(), err);
+            break;
+        }
+        if let Some(spool) = shared.spools.lock().unwrap().get_mut(&sink) {
+            if let Err(err) = spool.pop() {
+                warn!("{}", err);
+                break;
+            }
+        }
+        delivered += 1;
+    }
+    if delivered > 0 {
+        info!(
+            "Delivered {} spooled alerts to {}",
+            delivered,
+            sink.as_str()
+        );
+    }
+}
+
+/// Opens the spool of every delivered sink in `directory`, picking up the alerts left
+/// by a previous run
+fn open_spools(directory: &Path) -> Result<BTreeMap<Sink, SpoolQueue<Alert>>> {
+    fs::create_dir_all(directory).map_err(|err| {
+        Error::Custom(format!(
+            "Failed to create notification spool {}: {}",
+            directory.display(),
+            err
+        ))
+    })?;
+    DELIVERED_SINKS
+        .into_iter()
+        .map(|sink| {
+            let path = directory.join(format!("{}.jsonl", sink.as_str()));
+            Ok((sink, SpoolQueue::open(path, SpoolConfig::default())?))
+        })
+        .collect()
+}
+
+/// Returns the layer protocol of the node or link a topology change is about, as told
+/// by the cached topology of its host
+fn layer_protocol(shared: &Shared, alert: &Alert) -> Option<&'static str> {
+    let Alert::TopologyChange { event } = alert else {
+        return None;
+    };
+    shared
+        .topologies
+        .get(&event.host)?
+        .iter()
+        .find(|topology| topology.uuid == event.topology_uuid)?
+        .layer_protocol(&event.uuid)
+}
+
+/// Loads the topologies of every host of the object store into the cache, one host at
+/// a time, then ends
+///
+/// Hosts collected or read meanwhile are already cached and keep their topologies.
+async fn hydrate(shared: Arc<Shared>) -> Result<()> {
+    let hosts: Vec<String> = shared
+        .objects
+        .lock()
+        .unwrap()
+        .hosts()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    for (index, host) in hosts.iter().enumerate() {
+        if let Some(topologies) = tokio::task::block_in_place(|| stored_topologies(&shared, host))?
+        {
+            shared.topologies.insert_if_absent(host, topologies);
+        }
+        *shared.hydration.lock().unwrap() = Readiness {
+            ready: index + 1 == hosts.len(),
+            hydrated: index + 1,
+            hosts: hosts.len(),
+        };
+        // Leaves the runtime to the API between two hosts
+        tokio::task::yield_now().await;
+    }
+    info!("Hydrated the topologies of {} hosts", hosts.len());
+    Ok(())
+}
+
+/// Rewrites the object journal at `COMPACTION_INTERVAL`, one line per stored object
+async fn compact(shared: Arc<Shared>) -> Result<()> {
+    loop {
+        tokio::time::sleep(COMPACTION_INTERVAL).await;
+        tokio::task::block_in_place(|| shared.objects.lock().unwrap().compact())?;
+    }
+}
+
+/// Prunes the event log and the agent snapshots according to the retention settings
+/// of the config, and the expired export artifacts
+async fn prune(shared: Arc<Shared>) -> Result<()> {
+    loop {
+        let policy = shared.config.current().retention;
+        let report = retention::prune(
+            &mut shared.events.lock().unwrap(),
+            &mut shared.snapshots.lock().unwrap(),
+            &policy,
+            Local::now(),
+        )?;
+        if report.events > 0 || report.snapshots > 0 {
+            info!(
+                "Deleted {} events and {} snapshots beyond retention",
+                report.events, report.snapshots
+            );
+        }
+        // Artifacts expire with the jobs that wrote them
+        let deleted = tokio::task::block_in_place(|| shared.exports.prune(Local::now()))?;
+        if deleted > 0 {
+            info!("Deleted {} expired exports", deleted);
+        }
+        tokio::time::sleep(HOUSEKEEPING_INTERVAL).await;
+    }
+}
@@ -0,0 +1,630 @@
+use crate::api::assets::FrontendConfig;
+use crate::api::auth::ApiConfig;
+use crate::api::cors::CorsConfig;
+use crate::collector::DeviceCycle;
+use crate::discovery::ScanConfig;
+use crate::export::archive::ArchiveConfig;
+use crate::latency::LatencyConfig;
+use crate::models::address::ProxyUrl;
+use crate::models::device::Device;
+use crate::notifications::NotificationsConfig;
+use crate::payloads::PayloadConfig;
+use crate::retention::RetentionPolicy;
+use crate::schema::TapiVersion;
+use crate::setup::log_setup::TelemetryConfig;
+use crate::setup::watch::{self, FileWatcher};
+use crate::southbound::netconf::NetconfOptions;
+use crate::southbound::tunnel::JumpHost;
+use crate::stitching::StitchingConfig;
+use crate::validation::ValidationConfig;
+use crate::{Error, Result};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Default polling interval of the devices, in seconds
+pub const DEFAULT_POLL_INTERVAL: u64 = 300;
+
+/// Runtime settings read from `config.toml`
+///
+/// ```toml
+/// profile = "production"
+/// log_level = "info,backend::southbound=debug"
+/// poll_interval = 300
+/// proxy_url = "socks5://bastion.example.com:1080"
+///
+/// [devices."10.0.0.1"]
+/// enabled = false
+///
+/// [devices."10.0.0.2"]
+/// poll_interval = 60
+/// cycle_budget = 120
+/// pair_links = true
+/// tapi = "2.4"
+///
+/// [devices."10.0.0.3".jump_host]
+/// host = "bastion.example.com"
+/// user = "ops"
+///
+/// [devices."10.0.0.4".netconf]
+/// port = 830
+///
+/// [retention]
+/// max_age = "30d"
+/// max_events_per_device = 10000
+///
+/// [payloads]
+/// compress = true
+///
+/// [frontend]
+/// directory = "frontend/dist"
+///
+/// [telemetry]
+/// otlp_endpoint = "http://tempo:4318"
+///
+/// [[api.tokens]]
+/// tenant_id = "acme"
+/// sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Config {
+    #[serde(default)]
+    pub profile: Profile, // Selects the defaults of the unset settings
+    #[serde(default = "default_log_level")]
+    pub log_level: String, // `EnvFilter` directives
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: u64, // Seconds between two collections of a device
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<ProxyUrl>, // Proxy of the devices without their own
+    #[serde(default)]
+    pub devices: BTreeMap<String, DeviceConfig>, // Overrides by device host
+    #[serde(default)]
+    pub retention: RetentionPolicy, // Pruning of snapshots and events
+    #[serde(default)]
+    pub payloads: PayloadConfig, // Storage of raw TAPI responses
+    #[serde(default)]
+    pub frontend: FrontendConfig, // Compiled frontend served with the API
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cors: Option<CorsConfig>, // Defaults to the CORS policy of the profile
+    #[serde(default)]
+    pub telemetry: TelemetryConfig, // Export of the spans, read at startup
+    #[serde(default)]
+    pub stitching: StitchingConfig, // Matching of the links shared by two devices
+    #[serde(default)]
+    pub notifications: NotificationsConfig, // Channels change events are sent through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<ArchiveConfig>, // Upload of snapshots and reports to S3, off if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discovery: Option<ScanConfig>, // Subnet scan proposing devices, off if unset
+    #[serde(default)]
+    pub latency: LatencyConfig, // Probes of the round-trip latency of the devices
+    #[serde(default)]
+    pub validation: ValidationConfig, // Rules checked on every collected snapshot
+    #[serde(default)]
+    pub api: ApiConfig, // Tokens of the API clients and their tenants
+}
+
+/// Deployment profile
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    #[default]
+    Production, // Safe defaults: cross-origin requests denied
+    Dev, // Convenient defaults for local development
+}
+
+/// Settings overriding the global ones for a single device
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct DeviceConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>, // Overrides the enabled flag of the registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poll_interval: Option<u64>, // Seconds, overrides the global interval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cycle_budget: Option<u64>, // Seconds a whole collection cycle may take
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jump_host: Option<JumpHost>, // Bastion the device is only reachable through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pair_links: Option<bool>, // Merges unidirectional link pairs, off by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tapi: Option<TapiVersion>, // TAPI release of the device, detected when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub netconf: Option<NetconfOptions>, // Collects the device over NETCONF instead of RESTCONF
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_poll_interval() -> u64 {
+    DEFAULT_POLL_INTERVAL
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            profile: Profile::default(),
+            log_level: default_log_level(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            proxy_url: None,
+            devices: BTreeMap::new(),
+            retention: RetentionPolicy::default(),
+            payloads: PayloadConfig::default(),
+            frontend: FrontendConfig::default(),
+            cors: None,
+            telemetry: TelemetryConfig::default(),
+            stitching: StitchingConfig::default(),
+            notifications: NotificationsConfig::default(),
+            archive: None,
+            discovery: None,
+            latency: LatencyConfig::default(),
+            validation: ValidationConfig::default(),
+            api: ApiConfig::default(),
+        }
+    }
+}
+
+/// A setting whose value changed between two versions of the configuration
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    pub setting: String,      // e.g. `log_level`, `devices.10.0.0.1.enabled`
+    pub from: Option<String>, // `None` when the setting was not set
+    pub to: Option<String>,   // `None` when the setting was removed
+}
+
+impl Config {
+    /// Parses a configuration from its TOML content
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Config = toml::from_str(content)
+            .map_err(|err| Error::Invalid(format!("Invalid config: {}", err)))?;
+        if config.poll_interval == 0
+            || config
+                .devices
+                .values()
+                .any(|device| device.poll_interval == Some(0))
+        {
+            return Err(Error::Invalid(
+                "Invalid config: poll_interval must be positive".to_string(),
+            ));
+        }
+        if config
+            .archive
+            .as_ref()
+            .is_some_and(|archive| archive.interval == 0)
+        {
+            return Err(Error::Invalid(
+                "Invalid config: archive.interval must be positive".to_string(),
+            ));
+        }
+        let thresholds = &config.notifications.thresholds;
+        if thresholds.failed_polls == Some(0)
+            || thresholds.latency_ms.is_some_and(|latency| latency <= 0.0)
+            || thresholds.latency_minutes < 0
+        {
+            return Err(Error::Invalid(
+                "Invalid config: notifications.thresholds must be positive".to_string(),
+            ));
+        }
+        if config
+            .devices
+            .values()
+            .any(|device| device.cycle_budget == Some(0))
+        {
+            return Err(Error::Invalid(
+                "Invalid config: cycle_budget must be positive".to_string(),
+            ));
+        }
+        if config.latency.interval == 0 {
+            return Err(Error::Invalid(
+                "Invalid config: latency.interval must be positive".to_string(),
+            ));
+        }
+        if let Some(discovery) = &config.discovery {
+            if discovery.rate == 0 || discovery.interval == 0 {
+                return Err(Error::Invalid(
+                    "Invalid config: discovery.rate and discovery.interval must be positive"
+                        .to_string(),
+                ));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Reads the configuration file at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|err| {
+            Error::Custom(format!("Failed to read config {}: {}", path.display(), err))
+        })?;
+        Self::parse(&content)
+    }
+
+    /// Returns the polling interval of a device
+    pub fn poll_interval(&self, host: &str) -> Duration {
+        let seconds = self
+            .devices
+            .get(host)
+            .and_then(|device| device.poll_interval)
+            .unwrap_or(self.poll_interval);
+        Duration::from_secs(seconds)
+    }
+
+    /// Returns the time budget of a collection cycle of a device
+    pub fn cycle_budget(&self, host: &str) -> Duration {
+        self.devices
+            .get(host)
+            .and_then(|device| device.cycle_budget)
+            .map_or(DeviceCycle::DEFAULT_BUDGET, Duration::from_secs)
+    }
+
+    /// Returns whether a device is polled, `registry_enabled` being its registry flag
+    pub fn enabled(&self, host: &str, registry_enabled: bool) -> bool {
+        self.devices
+            .get(host)
+            .and_then(|device| device.enabled)
+            .unwrap_or(registry_enabled)
+    }
+
+    /// Returns the jump host a device is reached through, if any
+    pub fn jump_host(&self, host: &str) -> Option<&JumpHost> {
+        self.devices
+            .get(host)
+            .and_then(|device| device.jump_host.as_ref())
+    }
+
+    /// Returns the NETCONF options of a device collected over NETCONF
+    pub fn netconf(&self, host: &str) -> Option<&NetconfOptions> {
+        self.devices
+            .get(host)
+            .and_then(|device| device.netconf.as_ref())
+    }
+
+    /// Returns whether the unidirectional link pairs of a device are merged
+    pub fn pair_links(&self, host: &str) -> bool {
+        self.devices
+            .get(host)
+            .and_then(|device| device.pair_links)
+            .unwrap_or(false)
+    }
+
+    /// Returns the TAPI release set for a device, `None` to detect it from its payloads
+    pub fn tapi_version(&self, host: &str) -> Option<TapiVersion> {
+        self.devices.get(host).and_then(|device| device.tapi)
+    }
+
+    /// Returns the proxy `device` is reached through: its own, or else the default one
+    pub fn proxy_url(&self, device: &Device) -> Option<ProxyUrl> {
+        device.proxy_url.clone().or_else(|| self.proxy_url.clone())
+    }
+
+    /// Returns the CORS policy of the API, the profile default if none is configured
+    pub fn cors(&self) -> CorsConfig {
+        self.cors
+            .clone()
+            .unwrap_or_else(|| CorsConfig::for_profile(self.profile))
+    }
+
+    /// Lists the settings that differ from `previous`
+    pub fn changes(&self, previous: &Config) -> Vec<ConfigChange> {
+        let mut changes = vec![];
+        let mut compare = |setting: String, from: Option<String>, to: Option<String>| {
+            if from != to {
+                changes.push(ConfigChange { setting, from, to });
+            }
+        };
+
+        compare(
+            "profile".to_string(),
+            Some(format!("{:?}", previous.profile).to_lowercase()),
+            Some(format!("{:?}", self.profile).to_lowercase()),
+        );
+        compare(
+            "log_level".to_string(),
+            Some(previous.log_level.clone()),
+            Some(self.log_level.clone()),
+        );
+        compare(
+            "poll_interval".to_string(),
+            Some(previous.poll_interval.to_string()),
+            Some(self.poll_interval.to_string()),
+        );
+
+        // Displayed with the password masked
+        compare(
+            "proxy_url".to_string(),
+            previous.proxy_url.as_ref().map(ToString::to_string),
+            self.proxy_url.as_ref().map(ToString::to_string),
+        );
+
+        compare(
+            "retention.max_age".to_string(),
+            previous.retention.max_age.map(|age| age.to_string()),
+            self.retention.max_age.map(|age| age.to_string()),
+        );
+        compare(
+            "retention.max_events_per_device".to_string(),
+            previous
+                .retention
+                .max_events_per_device
+                .map(|max| max.to_string()),
+            self.retention
+                .max_events_per_device
+                .map(|max| max.to_string()),
+        );
+
+        compare(
+            "payloads.compress".to_string(),
+            Some(previous.payloads.compress.to_string()),
+            Some(self.payloads.compress.to_string()),
+        );
+        compare(
+            "payloads.level".to_string(),
+            Some(previous.payloads.level.to_string()),
+            Some(self.payloads.level.to_string()),
+        );
+
+        compare(
+            "frontend.directory".to_string(),
+            previous
+                .frontend
+                .directory
+                .as_ref()
+                .map(|directory| directory.display().to_string()),
+            self.frontend
+                .directory
+                .as_ref()
+                .map(|directory| directory.display().to_string()),
+        );
+
+        compare(
+            "telemetry.otlp_endpoint".to_string(),
+            previous.telemetry.otlp_endpoint.clone(),
+            self.telemetry.otlp_endpoint.clone(),
+        );
+
+        let cors = |config: &Config| serde_json::to_string(&config.cors()).ok();
+        compare("cors".to_string(), cors(previous), cors(self));
+        let stitching = |config: &Config| serde_json::to_string(&config.stitching).ok();
+        compare(
+            "stitching".to_string(),
+            stitching(previous),
+            stitching(self),
+        );
+        // Secrets never reach the audit log
+        let email = |config: &Config| {
+            config.notifications.email.clone().map(|mut email| {
+                email.password = email.password.map(|_| "********".to_string());
+                serde_json::to_string(&email).unwrap_or_default()
+            })
+        };
+        compare(
+            "notifications.email".to_string(),
+            email(previous),
+            email(self),
+        );
+        let chat = |config: &Config| {
+            let channels: Vec<_> = config
+                .notifications
+                .chat
+                .iter()
+                .cloned()
+                .map(|mut channel| {
+                    channel.webhook_url = "********".to_string();
+                    channel
+                })
+                .collect();
+            serde_json::to_string(&channels).ok()
+        };
+        compare("notifications.chat".to_string(), chat(previous), chat(self));
+        let archive = |config: &Config| {
+            config.archive.clone().map(|mut archive| {
+                archive.secret_access_key = "********".to_string();
+                serde_json::to_string(&archive).unwrap_or_default()
+            })
+        };
+        compare("archive".to_string(), archive(previous), archive(self));
+        let discovery = |config: &Config| {
+            config
+                .discovery
+                .as_ref()
+                .and_then(|discovery| serde_json::to_string(discovery).ok())
+        };
+        compare(
+            "discovery".to_string(),
+            discovery(previous),
+            discovery(self),
+        );
+        let thresholds =
+            |config: &Config| serde_json::to_string(&config.notifications.thresholds).ok();
+        compare(
+            "notifications.thresholds".to_string(),
+            thresholds(previous),
+            thresholds(self),
+        );
+        let latency = |config: &Config| serde_json::to_string(&config.latency).ok();
+        compare("latency".to_string(), latency(previous), latency(self));
+        let validation = |config: &Config| serde_json::to_string(&config.validation).ok();
+        compare(
+            "validation".to_string(),
+            validation(previous),
+            validation(self),
+        );
+        // Tenants with the first characters of their hashes, enough to tell a rotation
+        let tokens = |config: &Config| {
+            let tokens: Vec<String> = config
+                .api
+                .tokens
+                .iter()
+                .map(|token| {
+                    let prefix: String = token.sha256.chars().take(8).collect();
+                    format!("{}:{}", token.tenant_id, prefix)
+                })
+                .collect();
+            Some(tokens.join(","))
+        };
+        compare("api.tokens".to_string(), tokens(previous), tokens(self));
+        compare(
+            "notifications.routing".to_string(),
+            previous
+                .notifications
+                .routing
+                .as_ref()
+                .map(|path| path.display().to_string()),
+            self.notifications
+                .routing
+                .as_ref()
+                .map(|path| path.display().to_string()),
+        );
+
+        let hosts: std::collections::BTreeSet<&String> =
+            previous.devices.keys().chain(self.devices.keys()).collect();
+        for host in hosts {
+            let before = previous.devices.get(host).cloned().unwrap_or_default();
+            let after = self.devices.get(host).cloned().unwrap_or_default();
+            compare(
+                format!("devices.{}.enabled", host),
+                before.enabled.map(|enabled| enabled.to_string()),
+                after.enabled.map(|enabled| enabled.to_string()),
+            );
+            compare(
+                format!("devices.{}.poll_interval", host),
+                before.poll_interval.map(|seconds| seconds.to_string()),
+                after.poll_interval.map(|seconds| seconds.to_string()),
+            );
+            compare(
+                format!("devices.{}.cycle_budget", host),
+                before.cycle_budget.map(|seconds| seconds.to_string()),
+                after.cycle_budget.map(|seconds| seconds.to_string()),
+            );
+            let jump_host = |device: &DeviceConfig| {
+                device
+                    .jump_host
+                    .as_ref()
+                    .map(|jump| format!("{}@{}:{}", jump.user, jump.host, jump.port))
+            };
+            compare(
+                format!("devices.{}.jump_host", host),
+                jump_host(&before),
+                jump_host(&after),
+            );
+            compare(
+                format!("devices.{}.pair_links", host),
+                before.pair_links.map(|pair| pair.to_string()),
+                after.pair_links.map(|pair| pair.to_string()),
+            );
+            compare(
+                format!("devices.{}.tapi", host),
+                before.tapi.map(|version| version.to_string()),
+                after.tapi.map(|version| version.to_string()),
+            );
+            compare(
+                format!("devices.{}.netconf", host),
+                before
+                    .netconf
+                    .as_ref()
+                    .map(|netconf| netconf.port.to_string()),
+                after
+                    .netconf
+                    .as_ref()
+                    .map(|netconf| netconf.port.to_string()),
+            );
+        }
+
+        changes
+    }
+}
+
+type ApplyCallback = Box<dyn Fn(&Config, &[ConfigChange]) + Send + Sync>;
+
+/// State shared between the watcher handle and the file system notifications
+struct Shared {
+    path: PathBuf,
+    config: RwLock<Config>,
+    apply: ApplyCallback,
+}
+
+impl Shared {
+    fn reload(&self) -> Result<Vec<ConfigChange>> {
+        self.update(&watch::read(&self.path, "config")?)
+    }
+
+    fn update(&self, content: &str) -> Result<Vec<ConfigChange>> {
+        let config = Config::parse(content)?;
+        let changes = {
+            let mut current = self.config.write().unwrap();
+            let changes = config.changes(&current);
+            *current = config.clone();
+            changes
+        };
+
+        if !changes.is_empty() {
+            for change in &changes {
+                info!(
+                    target: "audit",
+                    setting = %change.setting,
+                    from = ?change.from,
+                    to = ?change.to,
+                    "Config change applied"
+                );
+            }
+            // Called without the lock held, so the callback may read the configuration
+            (self.apply)(&config, &changes);
+        }
+        Ok(changes)
+    }
+}
+
+/// Keeps the configuration in sync with `config.toml` without restarting the service
+///
+/// The file is watched for changes; every valid new version replaces the current
+/// configuration, which pollers read through [`ConfigWatcher::current`] on each cycle,
+/// and is passed with the list of changed settings to the `apply` callback (e.g. to
+/// update the tracing `EnvFilter`). Every applied change is logged on the `audit`
+/// target. An invalid file is logged and the previous configuration kept.
+pub struct ConfigWatcher {
+    shared: Arc<Shared>,
+    _watcher: FileWatcher,
+}
+
+impl ConfigWatcher {
+    /// Loads the configuration at `path` and starts watching it
+    pub fn start<F>(path: impl AsRef<Path>, apply: F) -> Result<Self>
+    where
+        F: Fn(&Config, &[ConfigChange]) + Send + Sync + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let shared = Arc::new(Shared {
+            config: RwLock::new(Config::load(&path)?),
+            path: path.clone(),
+            apply: Box::new(apply),
+        });
+
+        let watcher = {
+            let shared = Arc::clone(&shared);
+            FileWatcher::start(&path, "config", move |content| {
+                shared.update(content).map(|_| ())
+            })?
+        };
+
+        Ok(ConfigWatcher {
+            shared,
+            _watcher: watcher,
+        })
+    }
+
+    /// Returns the configuration currently applied
+    pub fn current(&self) -> Config {
+        self.shared.config.read().unwrap().clone()
+    }
+
+    /// Reloads the file now, returning the settings that changed
+    pub fn reload(&self) -> Result<Vec<ConfigChange>> {
+        self.shared.reload()
+    }
+}
@@ -1,8 +1,24 @@
 use crate::Error;
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use serde::{Deserialize, Serialize};
 use tracing_appender::{
     non_blocking::WorkerGuard,
     rolling::{RollingFileAppender, Rotation},
 };
+use tracing_subscriber::{
+    layer::{Layered, SubscriberExt},
+    reload,
+    util::SubscriberInitExt,
+    EnvFilter, Layer, Registry,
+};
+
+/// Path of the OTLP/HTTP trace endpoint of a collector
+const OTLP_TRACES_PATH: &str = "/v1/traces";
+
+/// Subscriber the log output is layered on: the registry behind the reloadable filter
+type Filtered = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
 
 /// Initializes the logging system with a rolling file appender and non-blocking logging.
 ///
@@ -52,3 +68,199 @@ pub fn logging_init_setup(filename_prefix: &str) -> Result<WorkerGuard, Error> {
     // Return the guard, which ensures that logging continues in the background.
     Ok(guard)
 }
+
+/// Changes the `EnvFilter` of the running logging system
+pub struct LogFilterHandle {
+    reload: Box<dyn Fn(EnvFilter) -> Result<(), Error> + Send + Sync>,
+}
+
+impl LogFilterHandle {
+    /// Applies new filter directives, e.g. `info,backend::southbound=debug`
+    ///
+    /// # Returns
+    ///
+    /// An error if the directives are invalid; the previous filter is then kept.
+    pub fn set(&self, directives: &str) -> Result<(), Error> {
+        (self.reload)(parse_filter(directives)?)
+    }
+}
+
+/// Settings of the trace export, the `[telemetry]` section of `config.toml`
+///
+/// ```toml
+/// [telemetry]
+/// otlp_endpoint = "http://tempo:4318"
+/// service_name = "device-manager"
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TelemetryConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otlp_endpoint: Option<String>, // OTLP/HTTP collector (Jaeger, Tempo...), no export if unset
+    #[serde(default = "default_service_name")]
+    pub service_name: String, // `service.name` of the exported spans
+}
+
+fn default_service_name() -> String {
+    "device-manager-backend".to_string()
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        TelemetryConfig {
+            otlp_endpoint: None,
+            service_name: default_service_name(),
+        }
+    }
+}
+
+/// Flushes the spans not exported yet when dropped
+pub struct TelemetryGuard {
+    provider: SdkTracerProvider,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Err(err) = self.provider.shutdown() {
+            eprintln!("Failed to flush the exported spans: {}", err);
+        }
+    }
+}
+
+/// Initializes the logging system like [`logging_init_setup`], filtered by `directives`
+/// that can be changed at runtime through the returned [`LogFilterHandle`].
+///
+/// This is used with the configuration hot-reload, so the log level of `config.toml`
+/// applies without restarting the service.
+pub fn logging_init_reloadable(
+    filename_prefix: &str,
+    directives: &str,
+) -> Result<(WorkerGuard, LogFilterHandle), Error> {
+    let (guard, handle, _) =
+        logging_init_telemetry(filename_prefix, directives, &TelemetryConfig::default())?;
+    Ok((guard, handle))
+}
+
+/// Initializes the logging system like [`logging_init_reloadable`], also exporting the
+/// spans (HTTP requests, collector polls, southbound requests) to an OTLP collector
+/// when `telemetry` has an endpoint.
+///
+/// # Returns
+///
+/// The guards of the log file and of the span export, which must both be retained,
+/// and the handle changing the log level.
+pub fn logging_init_telemetry(
+    filename_prefix: &str,
+    directives: &str,
+    telemetry: &TelemetryConfig,
+) -> Result<(WorkerGuard, LogFilterHandle, Option<TelemetryGuard>), Error> {
+    let filter = parse_filter(directives)?;
+    let provider = telemetry
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| tracer_provider(endpoint, &telemetry.service_name))
+        .transpose()?;
+
+    let file_appender = RollingFileAppender::builder()
+        .rotation(Rotation::HOURLY)
+        .filename_prefix(filename_prefix)
+        .build("./logs")
+        .map_err(|err| Error::Custom(format!("Failed to initialize log file: {}", err)))?;
+    let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
+
+    let output = tracing_subscriber::fmt::layer()
+        .with_writer(non_blocking)
+        .json()
+        .boxed();
+    let handle = install(output, filter, provider.as_ref())?;
+    Ok((
+        guard,
+        handle,
+        provider.map(|provider| TelemetryGuard { provider }),
+    ))
+}
+
+/// Initializes the logging system on stderr, filtered by `directives` that can be
+/// changed at runtime, and exporting the spans like [`logging_init_telemetry`]
+///
+/// This is used by `cli serve`, whose logs are read in the terminal or collected by
+/// the service manager.
+///
+/// # Returns
+///
+/// The handle changing the log level, and the guard of the span export, which must be
+/// retained.
+pub fn logging_init_stderr(
+    directives: &str,
+    telemetry: &TelemetryConfig,
+) -> Result<(LogFilterHandle, Option<TelemetryGuard>), Error> {
+    let filter = parse_filter(directives)?;
+    let provider = telemetry
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| tracer_provider(endpoint, &telemetry.service_name))
+        .transpose()?;
+
+    let output = tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .boxed();
+    let handle = install(output, filter, provider.as_ref())?;
+    Ok((handle, provider.map(|provider| TelemetryGuard { provider })))
+}
+
+/// Parses `EnvFilter` directives
+fn parse_filter(directives: &str) -> Result<EnvFilter, Error> {
+    EnvFilter::try_new(directives)
+        .map_err(|err| Error::Invalid(format!("Invalid log level {}: {}", directives, err)))
+}
+
+/// Installs `output` as the global subscriber behind a reloadable `filter`, next to
+/// the span export of `provider` if any
+fn install(
+    output: Box<dyn Layer<Filtered> + Send + Sync>,
+    filter: EnvFilter,
+    provider: Option<&SdkTracerProvider>,
+) -> Result<LogFilterHandle, Error> {
+    let (filter, handle) = reload::Layer::new(filter);
+    let otel = provider
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer("backend")));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(output)
+        .with(otel)
+        .try_init()
+        .map_err(|err| Error::Custom(format!("Failed to initialize logging: {}", err)))?;
+
+    let reload = move |filter: EnvFilter| {
+        handle
+            .reload(filter)
+            .map_err(|err| Error::Custom(format!("Failed to change log level: {}", err)))
+    };
+    Ok(LogFilterHandle {
+        reload: Box::new(reload),
+    })
+}
+
+/// Creates the tracer provider exporting spans to the OTLP/HTTP collector at `endpoint`
+fn tracer_provider(endpoint: &str, service_name: &str) -> Result<SdkTracerProvider, Error> {
+    let endpoint = endpoint.trim_end_matches('/');
+    let endpoint = if endpoint.ends_with(OTLP_TRACES_PATH) {
+        endpoint.to_string()
+    } else {
+        format!("{}{}", endpoint, OTLP_TRACES_PATH)
+    };
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(&endpoint)
+        .build()
+        .map_err(|err| Error::Invalid(format!("Invalid OTLP endpoint {}: {}", endpoint, err)))?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            Resource::builder()
+                .with_service_name(service_name.to_string())
+                .build(),
+        )
+        .build())
+}
@@ -1 +1,4 @@
-pub mod log_setup;
+pub mod app;
+pub mod config;
+pub mod log_setup;
+pub mod watch;
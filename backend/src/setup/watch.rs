@@ -0,0 +1,195 @@
+use crate::{Error, Result};
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::warn;
+
+/// Time given to a write of a watched file to complete before it is read
+const SETTLE_DELAY: Duration = Duration::from_millis(100);
+
+type Reload = Arc<dyn Fn(&str) -> Result<()> + Send + Sync>;
+
+/// A file registered on the shared watcher
+struct WatchedFile {
+    path: PathBuf,      // As given by the caller, for the messages
+    resolved: PathBuf,  // In the canonical directory, as reported by the events
+    what: &'static str, // What the file holds, e.g. `config`
+    reload: Reload,
+}
+
+/// The notify watcher shared by every [`FileWatcher`] of the process
+///
+/// The watched files are kept apart from the watcher: the event handler only locks
+/// them, so (un)watching a directory never waits on a running reload.
+struct Registry {
+    watcher: Mutex<(RecommendedWatcher, HashMap<PathBuf, usize>)>, // With the watchers of each directory
+    files: Arc<Mutex<HashMap<u64, WatchedFile>>>,
+    next_id: AtomicU64,
+}
+
+impl Registry {
+    /// Returns the registry, starting the watcher on first use
+    fn get() -> Result<&'static Registry> {
+        static REGISTRY: OnceLock<Registry> = OnceLock::new();
+        if let Some(registry) = REGISTRY.get() {
+            return Ok(registry);
+        }
+        let files: Arc<Mutex<HashMap<u64, WatchedFile>>> = Arc::default();
+        let watcher = {
+            let files = Arc::clone(&files);
+            notify::recommended_watcher(move |event| dispatch(&files, event))
+                .map_err(Error::custom)?
+        };
+        // A concurrent first use may have won, its watcher is then the shared one
+        Ok(REGISTRY.get_or_init(|| Registry {
+            watcher: Mutex::new((watcher, HashMap::new())),
+            files,
+            next_id: AtomicU64::new(1),
+        }))
+    }
+}
+
+/// Reloads the watched files changed by `event`
+fn dispatch(files: &Mutex<HashMap<u64, WatchedFile>>, event: notify::Result<Event>) {
+    let event = match event {
+        Ok(event) if event.kind.is_modify() || event.kind.is_create() => event,
+        Ok(_) => return,
+        Err(err) => {
+            warn!("Watch error on {:?}: {}", err.paths, err);
+            return;
+        }
+    };
+    let changed: Vec<(PathBuf, &'static str, Reload)> = files
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|file| event.paths.contains(&file.resolved))
+        .map(|file| (file.path.clone(), file.what, Arc::clone(&file.reload)))
+        .collect();
+    if changed.is_empty() {
+        return;
+    }
+
+    // Let the write complete before reading the files
+    std::thread::sleep(SETTLE_DELAY);
+    for (path, what, reload) in changed {
+        if let Err(err) = read(&path, what).and_then(|content| reload(&content)) {
+            warn!("{} not reloaded: {}", path.display(), err);
+        }
+    }
+}
+
+/// Calls a reload function with the new content of a file after each of its writes
+///
+/// Editors often replace the file rather than write it in place, so its directory is
+/// watched. Every file watcher of the process shares a single notify watcher, each
+/// directory being watched once. A reload that fails is logged, the caller keeping its
+/// previous state. The file stops being watched when the [`FileWatcher`] is dropped.
+pub struct FileWatcher {
+    id: u64,
+    directory: PathBuf, // Canonical directory of the file
+}
+
+impl FileWatcher {
+    /// Starts watching the file at `path`
+    ///
+    /// # Arguments
+    /// - `path`: The watched file
+    /// - `what`: What the file holds, for the messages (e.g. `config`)
+    /// - `reload`: Called with the content of the file after each write
+    pub fn start<F>(path: impl AsRef<Path>, what: &'static str, reload: F) -> Result<Self>
+    where
+        F: Fn(&str) -> Result<()> + Send + Sync + 'static,
+    {
+        let path = path.as_ref().to_path_buf();
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let directory = fs::canonicalize(&directory).map_err(|err| {
+            Error::Custom(format!(
+                "Failed to watch {} {}: {}",
+                what,
+                path.display(),
+                err
+            ))
+        })?;
+        let name = path
+            .file_name()
+            .ok_or_else(|| Error::Custom(format!("Invalid {} file {}", what, path.display())))?;
+
+        let registry = Registry::get()?;
+        {
+            let mut watcher = registry.watcher.lock().unwrap();
+            let (watcher, directories) = &mut *watcher;
+            if !directories.contains_key(&directory) {
+                watcher
+                    .watch(&directory, RecursiveMode::NonRecursive)
+                    .map_err(Error::custom)?;
+            }
+            *directories.entry(directory.clone()).or_default() += 1;
+        }
+
+        let id = registry.next_id.fetch_add(1, Ordering::Relaxed);
+        registry.files.lock().unwrap().insert(
+            id,
+            WatchedFile {
+                resolved: directory.join(name),
+                path,
+                what,
+                reload: Arc::new(reload),
+            },
+        );
+        Ok(FileWatcher { id, directory })
+    }
+}
+
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        let Some(registry) = Registry::get().ok() else {
+            return;
+        };
+        registry.files.lock().unwrap().remove(&self.id);
+
+        let mut watcher = registry.watcher.lock().unwrap();
+        let (watcher, directories) = &mut *watcher;
+        if let Some(count) = directories.get_mut(&self.directory) {
+            *count -= 1;
+            if *count == 0 {
+                directories.remove(&self.directory);
+                let _ = watcher.unwatch(&self.directory);
+            }
+        }
+    }
+}
+
+/// Reads a watched file
+///
+/// # Returns
+/// - `Ok(String)`: The content of the file
+/// - `Err(Error)`: If the file cannot be read or is empty, most editors truncating the
+///   file before writing it back
+pub fn read(path: &Path, what: &str) -> Result<String> {
+    let content = fs::read_to_string(path).map_err(|err| {
+        Error::Custom(format!(
+            "Failed to read {} {}: {}",
+            what,
+            path.display(),
+            err
+        ))
+    })?;
+    if content.trim().is_empty() {
+        return Err(Error::Custom(format!(
+            "Empty {} file {}",
+            what,
+            path.display()
+        )));
+    }
+    Ok(content)
+}
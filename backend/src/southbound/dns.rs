@@ -0,0 +1,138 @@
+use crate::{Error, Result};
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use hickory_resolver::config::{ResolverConfig, ResolverOpts};
+use hickory_resolver::TokioAsyncResolver;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use tracing::{debug, warn};
+
+/// Addresses of a host, cached until their DNS TTL expires
+#[derive(Debug, Clone)]
+struct Resolved {
+    addresses: Vec<IpAddr>,
+    valid_until: Instant,
+}
+
+/// DNS resolver of the device clients, caching answers for their TTL
+///
+/// Every collector resolves its device at each poll; answers are kept until their TTL
+/// expires so a poll does not wait on DNS, and the last address of every host is kept
+/// so health reports can show which address is actually polled. IP literals, including
+/// IPv6 ones, are used as they are. Clones share the cache.
+#[derive(Clone)]
+pub struct CachedResolver {
+    resolver: Arc<TokioAsyncResolver>,
+    cache: Arc<Mutex<HashMap<String, Resolved>>>,
+    min_ttl: Duration, // Floor of the cache duration, for answers with a TTL of 0
+}
+
+impl CachedResolver {
+    /// Creates a resolver using the system DNS configuration (`/etc/resolv.conf`)
+    ///
+    /// Falls back to the default public resolvers when the system configuration
+    /// cannot be read.
+    pub fn new() -> Self {
+        let (config, options) =
+            hickory_resolver::system_conf::read_system_conf().unwrap_or_else(|err| {
+                warn!("Using default DNS resolvers: {}", err);
+                (ResolverConfig::default(), ResolverOpts::default())
+            });
+        Self::with_config(config, options)
+    }
+
+    /// Creates a resolver querying the name servers of `config`
+    pub fn with_config(config: ResolverConfig, options: ResolverOpts) -> Self {
+        CachedResolver {
+            resolver: Arc::new(TokioAsyncResolver::tokio(config, options)),
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            min_ttl: Duration::from_secs(1),
+        }
+    }
+
+    /// Resolves `host`, from the cache while its TTL has not expired
+    ///
+    /// # Returns
+    /// - `Ok(Vec<IpAddr>)`: The addresses of the host, the address itself for an IP
+    /// - `Err(Error)`: If the name does not resolve
+    pub async fn resolve(&self, host: &str) -> Result<Vec<IpAddr>> {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(address) = host.parse::<IpAddr>() {
+            return Ok(vec![address]);
+        }
+
+        let now = Instant::now();
+        if let Some(resolved) = self.cache.lock().unwrap().get(host) {
+            if resolved.valid_until > now {
+                return Ok(resolved.addresses.clone());
+            }
+        }
+
+        let lookup =
+            self.resolver.lookup_ip(host).await.map_err(|err| {
+                Error::Unreachable(format!("Device unreachable: {}: {}", host, err))
+            })?;
+        let addresses: Vec<IpAddr> = lookup.iter().collect();
+        if addresses.is_empty() {
+            return Err(Error::Unreachable(format!(
+                "Device unreachable: {} has no address",
+                host
+            )));
+        }
+        let valid_until = lookup.valid_until().max(now + self.min_ttl);
+        debug!(
+            "Resolved {} to {:?} for {:?}",
+            host,
+            addresses,
+            valid_until - now
+        );
+        self.cache.lock().unwrap().insert(
+            host.to_string(),
+            Resolved {
+                addresses: addresses.clone(),
+                valid_until,
+            },
+        );
+        Ok(addresses)
+    }
+
+    /// Returns the address `host` was last resolved to, the one connections go to
+    ///
+    /// The address is kept after its TTL expires, until the host is resolved again.
+    pub fn address(&self, host: &str) -> Option<IpAddr> {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(address) = host.parse::<IpAddr>() {
+            return Some(address);
+        }
+        self.cache
+            .lock()
+            .unwrap()
+            .get(host)
+            .and_then(|resolved| resolved.addresses.first().copied())
+    }
+}
+
+impl Default for CachedResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolve for CachedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addresses = resolver.resolve(name.as_str()).await?;
+            // The port is replaced by the one of the URL
+            let addrs: Addrs = Box::new(
+                addresses
+                    .into_iter()
+                    .map(|address| SocketAddr::new(address, 0)),
+            );
+            Ok(addrs)
+        })
+    }
+}
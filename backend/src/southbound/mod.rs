@@ -0,0 +1,29 @@
+pub mod dns;
+pub mod netconf;
+pub mod restconf;
+pub mod snmp;
+pub mod tunnel;
+pub mod xml;
+
+use crate::Result;
+
+use std::future::Future;
+
+use serde_json::Value;
+
+/// Query options understood by southbound drivers when fetching a resource
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FetchOptions {
+    pub depth: Option<u32>, // Limits how many levels of the subtree are returned
+    pub fields: Option<String>, // Selects the fields to return (RESTCONF `fields` syntax)
+}
+
+/// Protocol used to collect data from a device (plain HTTP/TAPI, RESTCONF, NETCONF...)
+///
+/// Drivers return the resource as JSON so the models can parse it regardless of the
+/// protocol and encoding the device speaks.
+pub trait SouthboundProtocol {
+    /// Fetches the resource at `path` from the device
+    fn get(&self, path: &str, options: &FetchOptions)
+        -> impl Future<Output = Result<Value>> + Send;
+}
@@ -0,0 +1,559 @@
+use super::restconf::Fetched;
+use super::xml::to_json;
+use super::{FetchOptions, SouthboundProtocol};
+use crate::models::device::{Auth, Device};
+use crate::models::hash::ContentHash;
+use crate::{Error, Result};
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use quick_xml::escape::escape;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tokio::io::{
+    join, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, Join,
+};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use tracing::{debug, info_span, Instrument};
+
+/// Port of the NETCONF SSH subsystem (RFC 6242)
+pub const NETCONF_PORT: u16 = 830;
+
+/// Namespace of the NETCONF protocol messages
+const BASE_NAMESPACE: &str = "urn:ietf:params:xml:ns:netconf:base:1.0";
+
+/// Capabilities of the two framings, `]]>]]>` delimited and chunked
+const BASE_1_0: &str = "urn:ietf:params:netconf:base:1.0";
+const BASE_1_1: &str = "urn:ietf:params:netconf:base:1.1";
+
+/// Delimiter of the messages of a NETCONF 1.0 session
+const END_OF_MESSAGE: &[u8] = b"]]>]]>";
+
+/// How long the device is given to answer the hello and every request
+const REPLY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// SSH options of a device managed over NETCONF, `[devices."<host>".netconf]` in
+/// `config.toml`
+///
+/// ```toml
+/// [devices."10.0.0.1".netconf]
+/// port = 830
+/// identity_file = "/etc/device-manager/id_ed25519"
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NetconfOptions {
+    #[serde(default = "default_netconf_port")]
+    pub port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<PathBuf>, // Private key, the SSH agent and defaults otherwise
+}
+
+fn default_netconf_port() -> u16 {
+    NETCONF_PORT
+}
+
+impl Default for NetconfOptions {
+    fn default() -> Self {
+        NetconfOptions {
+            port: NETCONF_PORT,
+            identity_file: None,
+        }
+    }
+}
+
+impl NetconfOptions {
+    /// Returns the `ssh` arguments opening the `netconf` subsystem of `device`
+    ///
+    /// The user is the username of the basic auth of the device. `ssh` never prompts
+    /// (`BatchMode`), so the password is not used: the device must accept the key.
+    ///
+    /// # Returns
+    /// - `Ok(Vec<String>)`: The arguments
+    /// - `Err(Error)`: If the device has no basic auth to take the user from, or the
+    ///   user or host would be read as an option
+    pub fn ssh_args(&self, device: &Device) -> Result<Vec<String>> {
+        let Auth::BasicAuth(auth) = &device.auth else {
+            return Err(Error::Invalid(format!(
+                "NETCONF of {} needs a basic auth username",
+                device.host
+            )));
+        };
+        // A destination starting with `-` would be read as an option, e.g. `-oProxyCommand`
+        if auth.username.starts_with('-') || device.host.to_string().starts_with('-') {
+            return Err(Error::Invalid(format!(
+                "NETCONF of {} has a user or host starting with '-'",
+                device.host
+            )));
+        }
+        let mut args = vec![
+            "-o".to_string(),
+            "BatchMode=yes".to_string(),
+            "-o".to_string(),
+            "ServerAliveInterval=30".to_string(),
+            "-p".to_string(),
+            self.port.to_string(),
+        ];
+        if let Some(identity_file) = &self.identity_file {
+            args.push("-i".to_string());
+            args.push(identity_file.display().to_string());
+        }
+        // Options end at `--`, so the subsystem flag comes before it
+        args.push("-s".to_string());
+        args.push("--".to_string());
+        args.push(format!("{}@{}", auth.username, device.host));
+        args.push("netconf".to_string());
+        Ok(args)
+    }
+}
+
+/// Framing of the messages of a session, chosen from the capabilities of the hellos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    EndOfMessage, // NETCONF 1.0, messages end with `]]>]]>`
+    Chunked,      // NETCONF 1.1, both peers announced `base:1.1`
+}
+
+/// A NETCONF session over a byte stream, once the hellos are exchanged
+pub struct NetconfSession<S> {
+    stream: BufReader<S>,
+    framing: Framing,
+    next_id: u64, // `message-id` of the next request
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> NetconfSession<S> {
+    /// Sends the hello of the client and reads the hello of the device
+    ///
+    /// # Returns
+    /// - `Ok(NetconfSession)`: The session, chunked if both peers speak NETCONF 1.1
+    /// - `Err(Error)`: If the stream fails or the device hello is invalid
+    pub async fn open(stream: S) -> Result<Self> {
+        let mut session = NetconfSession {
+            stream: BufReader::new(stream),
+            framing: Framing::EndOfMessage,
+            next_id: 1,
+        };
+        let hello = format!(
+            "<hello xmlns=\"{}\"><capabilities><capability>{}</capability>\
+             <capability>{}</capability></capabilities></hello>",
+            BASE_NAMESPACE, BASE_1_0, BASE_1_1
+        );
+        session.write(&hello).await?;
+        let hello = to_json(&session.read().await?)?;
+        let capabilities = root(&hello)
+            .and_then(|hello| hello.get("capabilities"))
+            .and_then(|capabilities| capabilities.get("capability"))
+            .ok_or_else(|| Error::BadResponse("NETCONF hello without capabilities".into()))?;
+        let capabilities = match capabilities {
+            Value::Array(capabilities) => capabilities.iter().collect(),
+            capability => vec![capability],
+        };
+        if capabilities
+            .iter()
+            .any(|capability| *capability == BASE_1_1)
+        {
+            session.framing = Framing::Chunked;
+        }
+        Ok(session)
+    }
+
+    /// Returns the framing of the session
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Sends the request `operation` and reads its reply
+    ///
+    /// # Arguments
+    /// - `operation`: The XML of the operation, e.g. `<get/>`
+    ///
+    /// # Returns
+    /// - `Ok(Value)`: The `data` of the reply in the JSON encoding, an empty object when
+    ///   the reply has none
+    /// - `Err(Error)`: If the stream fails, the reply is invalid or holds `rpc-error`s
+    pub async fn rpc(&mut self, operation: &str) -> Result<Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = format!(
+            "<rpc message-id=\"{}\" xmlns=\"{}\">{}</rpc>",
+            id, BASE_NAMESPACE, operation
+        );
+        self.write(&request).await?;
+        let reply = to_json(&self.read().await?)?;
+        let reply = root(&reply)
+            .ok_or_else(|| Error::BadResponse("NETCONF reply without rpc-reply".into()))?;
+        if let Some(errors) = reply.get("rpc-error") {
+            return Err(rpc_error(errors));
+        }
+        match reply.get("data") {
+            Some(Value::Object(data)) => Ok(Value::Object(data.clone())),
+            _ => Ok(Value::Object(Map::new())),
+        }
+    }
+
+    /// Asks the device to end the session
+    pub async fn close(&mut self) -> Result<()> {
+        self.rpc("<close-session/>").await.map(|_| ())
+    }
+
+    /// Writes a message in the framing of the session
+    async fn write(&mut self, message: &str) -> Result<()> {
+        let framed = match self.framing {
+            Framing::EndOfMessage => format!("{}]]>]]>", message),
+            Framing::Chunked => format!("\n#{}\n{}\n##\n", message.len(), message),
+        };
+        let stream = self.stream.get_mut();
+        stream.write_all(framed.as_bytes()).await.map_err(closed)?;
+        stream.flush().await.map_err(closed)
+    }
+
+    /// Reads a message in the framing of the session
+    async fn read(&mut self) -> Result<String> {
+        let message = match tokio::time::timeout(REPLY_TIMEOUT, self.read_message()).await {
+            Ok(message) => message?,
+            Err(_) => {
+                return Err(Error::Timeout(
+                    "Device timeout: no NETCONF reply in time".to_string(),
+                ))
+            }
+        };
+        String::from_utf8(message)
+            .map_err(|_| Error::BadResponse("NETCONF message is not UTF-8".to_string()))
+    }
+
+    async fn read_message(&mut self) -> Result<Vec<u8>> {
+        let mut message = vec![];
+        match self.framing {
+            Framing::EndOfMessage => {
+                while !message.ends_with(END_OF_MESSAGE) {
+                    if self
+                        .stream
+                        .read_until(b'>', &mut message)
+                        .await
+                        .map_err(closed)?
+                        == 0
+                    {
+                        return Err(end_of_stream());
+                    }
+                }
+                message.truncate(message.len() - END_OF_MESSAGE.len());
+            }
+            Framing::Chunked => loop {
+                // Every chunk starts with `\n#<size>\n`, the message ends with `\n##\n`
+                let mut header = vec![];
+                for _ in 0..2 {
+                    if self
+                        .stream
+                        .read_until(b'\n', &mut header)
+                        .await
+                        .map_err(closed)?
+                        == 0
+                    {
+                        return Err(end_of_stream());
+                    }
+                }
+                let size = match header.strip_prefix(b"\n#") {
+                    Some(b"#\n") => break,
+                    Some(size) => std::str::from_utf8(size)
+                        .ok()
+                        .and_then(|size| size.trim_end().parse::<usize>().ok()),
+                    None => None,
+                };
+                let size = size.ok_or_else(|| {
+                    Error::BadResponse("NETCONF message with an invalid chunk".to_string())
+                })?;
+                let start = message.len();
+                message.resize(start + size, 0);
+                self.stream
+                    .read_exact(&mut message[start..])
+                    .await
+                    .map_err(closed)?;
+            },
+        }
+        Ok(message)
+    }
+}
+
+/// Stream of the `ssh` process running the `netconf` subsystem
+pub type SshStream = Join<ChildStdout, ChildStdin>;
+
+/// NETCONF driver, reading the datastores of a device over SSH
+///
+/// Resources are asked for by their RESTCONF path, e.g. [`TOPOLOGY_CONTEXT_PATH`],
+/// which is turned into a subtree filter; the reply is converted from XML into the
+/// JSON encoding a RESTCONF device would answer, so the models parse it unchanged.
+/// Requests of a client share its session and are sent one at a time.
+///
+/// [`TOPOLOGY_CONTEXT_PATH`]: crate::models::topology::TOPOLOGY_CONTEXT_PATH
+pub struct NetconfClient<S = SshStream> {
+    device: Device,
+    session: Mutex<NetconfSession<S>>,
+    hashes: Mutex<HashMap<String, ContentHash>>, // Last content of every path, by `get_if_changed`
+    _ssh: Option<Child>,                         // The `ssh` process, killed with the client
+}
+
+impl NetconfClient<SshStream> {
+    /// Opens a NETCONF session with `device` through the system `ssh` client
+    ///
+    /// # Returns
+    /// - `Ok(NetconfClient)`: The client, once the hellos are exchanged
+    /// - `Err(Error)`: If `ssh` cannot be started or the device does not answer
+    pub async fn connect(device: Device, options: &NetconfOptions) -> Result<Self> {
+        let mut ssh = Command::new("ssh")
+            .args(options.ssh_args(&device)?)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| Error::Custom(format!("Failed to start ssh: {}", err)))?;
+        let (Some(stdout), Some(stdin)) = (ssh.stdout.take(), ssh.stdin.take()) else {
+            return Err(Error::Custom("Failed to start ssh: no stdio".to_string()));
+        };
+        let session = NetconfSession::open(join(stdout, stdin))
+            .await
+            .map_err(|err| {
+                Error::Unreachable(format!(
+                    "Device unreachable: NETCONF session with {} failed: {}",
+                    device.host, err
+                ))
+            })?;
+        debug!("NETCONF session with {} opened", device.host);
+        Ok(NetconfClient {
+            device,
+            session: Mutex::new(session),
+            hashes: Mutex::new(HashMap::new()),
+            _ssh: Some(ssh),
+        })
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> NetconfClient<S> {
+    /// Opens a NETCONF session with `device` over an established stream
+    pub async fn over(device: Device, stream: S) -> Result<Self> {
+        Ok(NetconfClient {
+            device,
+            session: Mutex::new(NetconfSession::open(stream).await?),
+            hashes: Mutex::new(HashMap::new()),
+            _ssh: None,
+        })
+    }
+
+    /// Returns the device the client reads
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Reads the resource at the RESTCONF `path` from the running configuration
+    ///
+    /// # Returns
+    /// - `Ok(Value)`: The resource, as RESTCONF would answer it
+    /// - `Err(Error)`: If the path is not supported, the request fails or the device
+    ///   does not hold the resource
+    pub async fn get_config(&self, path: &str) -> Result<Value> {
+        let filter = subtree_filter(path)?;
+        let operation = format!(
+            "<get-config><source><running/></source>{}</get-config>",
+            filter
+        );
+        self.fetch(path, &operation).await
+    }
+
+    /// Reads the resource at `path` like [`SouthboundProtocol::get`], unless its content
+    /// is the same as on the previous call
+    ///
+    /// NETCONF has no conditional read, so the whole resource is always transferred and
+    /// its content hash compared with the previous one.
+    ///
+    /// # Returns
+    /// - `Ok(Fetched::Modified(Value))`: On the first call, or if the resource changed
+    /// - `Ok(Fetched::Unchanged)`: If the resource is the same as on the previous call
+    /// - `Err(Error)`: If the request fails or the device does not hold the resource
+    pub async fn get_if_changed(&self, path: &str) -> Result<Fetched> {
+        let value = self.get(path, &FetchOptions::default()).await?;
+        let hash = ContentHash::of(&value);
+        match self.hashes.lock().await.insert(path.to_string(), hash) {
+            Some(previous) if previous == hash => Ok(Fetched::Unchanged),
+            _ => Ok(Fetched::Modified(value)),
+        }
+    }
+
+    async fn fetch(&self, path: &str, operation: &str) -> Result<Value> {
+        let span = info_span!("southbound", host = %self.device.host, path);
+        async {
+            let data = self.session.lock().await.rpc(operation).await?;
+            select(&data, path)
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> SouthboundProtocol for NetconfClient<S> {
+    /// Reads the resource at `path`, configuration and state, with `<get>`
+    ///
+    /// Subtree filters select whole subtrees, so `options` are not applied.
+    async fn get(&self, path: &str, _options: &FetchOptions) -> Result<Value> {
+        let operation = format!("<get>{}</get>", subtree_filter(path)?);
+        self.fetch(path, &operation).await
+    }
+}
+
+/// A segment of a RESTCONF data path, e.g. `tapi-topology:topology=<uuid>`
+struct Segment<'a> {
+    module: Option<&'a str>,
+    name: &'a str,
+    key: Option<&'a str>, // `uuid` of a list entry, the key of the TAPI lists
+}
+
+/// Splits a RESTCONF data path into its segments
+fn segments(path: &str) -> Result<Vec<Segment<'_>>> {
+    let data = path
+        .strip_prefix("/restconf/data/")
+        .filter(|data| !data.is_empty())
+        .ok_or_else(|| Error::Invalid(format!("Invalid NETCONF path {}", path)))?;
+    Ok(data
+        .trim_end_matches('/')
+        .split('/')
+        .map(|segment| {
+            let (name, key) = match segment.split_once('=') {
+                Some((name, key)) => (name, Some(key)),
+                None => (segment, None),
+            };
+            match name.split_once(':') {
+                Some((module, name)) => Segment {
+                    module: Some(module),
+                    name,
+                    key,
+                },
+                None => Segment {
+                    module: None,
+                    name,
+                    key,
+                },
+            }
+        })
+        .collect())
+}
+
+/// Returns the subtree filter selecting the resource at the RESTCONF `path`
+///
+/// Modules are mapped to the namespaces of their family: `tapi-*` to
+/// `urn:onf:otcc:yang:<module>`, `ietf-*` to `urn:ietf:params:xml:ns:yang:<module>`.
+///
+/// # Returns
+/// - `Ok(String)`: The `<filter>` element
+/// - `Err(Error)`: If the path is not a data path or names an unknown module
+pub fn subtree_filter(path: &str) -> Result<String> {
+    let segments = segments(path)?;
+    let mut filter = String::from("<filter type=\"subtree\">");
+    for segment in &segments {
+        filter.push('<');
+        filter.push_str(segment.name);
+        if let Some(module) = segment.module {
+            filter.push_str(&format!(" xmlns=\"{}\"", namespace(module)?));
+        }
+        filter.push('>');
+        if let Some(key) = segment.key {
+            filter.push_str(&format!("<uuid>{}</uuid>", escape(key)));
+        }
+    }
+    for segment in segments.iter().rev() {
+        filter.push_str(&format!("</{}>", segment.name));
+    }
+    filter.push_str("</filter>");
+    Ok(filter)
+}
+
+/// Returns the resource at the RESTCONF `path` within the `data` of a reply
+///
+/// # Returns
+/// - `Ok(Value)`: The resource under its qualified name, a list entry within an array
+/// - `Err(Error)`: If the device does not hold it
+fn select(data: &Value, path: &str) -> Result<Value> {
+    let not_found = || Error::NotFound(format!("Not found {} on the device", path));
+    let mut value = data;
+    let mut module = "";
+    let mut name = String::new();
+    for segment in segments(path)? {
+        let inherited = module;
+        module = segment.module.unwrap_or(module);
+        // Members are qualified when their module differs from the one of their parent
+        let qualified = format!("{}:{}", module, segment.name);
+        let member = match module == inherited {
+            true => value.get(segment.name),
+            false => value.get(&qualified),
+        };
+        value = member.ok_or_else(not_found)?;
+        if let Some(key) = segment.key {
+            value = value
+                .as_array()
+                .and_then(|entries| entries.iter().find(|entry| entry["uuid"] == key))
+                .ok_or_else(not_found)?;
+        }
+        name = qualified;
+    }
+    let value = match segments(path)?.last().and_then(|segment| segment.key) {
+        Some(_) => Value::Array(vec![value.clone()]),
+        None => value.clone(),
+    };
+    let mut resource = Map::new();
+    resource.insert(name, value);
+    Ok(Value::Object(resource))
+}
+
+/// Returns the namespace of a YANG module
+fn namespace(module: &str) -> Result<String> {
+    if module.starts_with("tapi-") {
+        Ok(format!("urn:onf:otcc:yang:{}", module))
+    } else if module.starts_with("ietf-") {
+        Ok(format!("urn:ietf:params:xml:ns:yang:{}", module))
+    } else {
+        Err(Error::Invalid(format!(
+            "Unknown namespace of YANG module {}",
+            module
+        )))
+    }
+}
+
+/// Returns the content of the single top-level element of a converted message
+fn root(message: &Value) -> Option<&Value> {
+    message.as_object()?.values().next()
+}
+
+/// Converts the `rpc-error`s of a reply into an error
+fn rpc_error(errors: &Value) -> Error {
+    let errors = match errors {
+        Value::Array(errors) => errors.iter().collect(),
+        error => vec![error],
+    };
+    let messages: Vec<String> = errors
+        .iter()
+        .map(|error| {
+            let tag = error
+                .get("error-tag")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown");
+            match error.get("error-message").and_then(Value::as_str) {
+                Some(message) => format!("{}: {}", tag, message),
+                None => tag.to_string(),
+            }
+        })
+        .collect();
+    Error::BadResponse(format!("NETCONF error: {}", messages.join("; ")))
+}
+
+/// Maps a failure of the session stream
+fn closed(err: std::io::Error) -> Error {
+    Error::Unreachable(format!(
+        "Device unreachable: NETCONF session failed: {}",
+        err
+    ))
+}
+
+fn end_of_stream() -> Error {
+    Error::Unreachable("Device unreachable: NETCONF session closed".to_string())
+}
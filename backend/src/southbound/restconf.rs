@@ -0,0 +1,601 @@
+use super::dns::CachedResolver;
+use super::xml::{self, YANG_DATA_XML};
+use super::{FetchOptions, SouthboundProtocol};
+use crate::models::device::{Auth, CustomAuth, Device};
+use crate::models::hash::ContentHash;
+use crate::{Error, Result};
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::header::{
+    HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE, COOKIE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+    LAST_MODIFIED, SET_COOKIE,
+};
+use reqwest::{Client, Method, Proxy, RequestBuilder, Response, StatusCode, Url};
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+use tracing::{info_span, Instrument};
+
+/// Media type of RESTCONF JSON payloads
+pub const YANG_DATA_JSON: &str = "application/yang-data+json";
+
+/// Media types accepted in answers, XML for the devices that only speak it
+const ACCEPTED: &str = "application/yang-data+json, application/yang-data+xml;q=0.5";
+
+/// Time given to the TCP and TLS handshakes with a device
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default time given to a request, answer body included
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// RESTCONF driver: fetches resources as `application/yang-data+json`, forwarding
+/// `depth`/`fields` query parameters and turning RESTCONF error envelopes into errors
+///
+/// Devices answering `application/yang-data+xml` are read too, their payloads being
+/// converted to the JSON encoding the models parse. Every request is bounded by a
+/// timeout, so a controller that stops answering fails with [`Error::Timeout`].
+pub struct RestconfClient {
+    device: Device,
+    base_url: String,
+    http: Client,
+    timeout: Duration,        // Of every request, `REQUEST_TIMEOUT` unless set
+    resolver: CachedResolver, // Resolves the device host, shared by the clients of the app
+    session: Mutex<Option<Session>>, // OAuth2 or custom login session, fetched lazily
+    validators: Mutex<HashMap<(String, FetchOptions), Validators>>, // Of `get_if_changed`
+}
+
+/// Answer of a conditional fetch
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fetched {
+    Modified(Value), // The resource changed since the previous fetch, or is fetched first
+    Unchanged,       // The device answered `304`, or sent the same content again
+}
+
+/// What identifies the last version of a resource fetched with `get_if_changed`
+#[derive(Debug, Clone, Default)]
+struct Validators {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    hash: Option<ContentHash>, // Used when the device sends neither header
+}
+
+/// Credentials obtained by logging in, reused until the device answers 401
+#[derive(Debug, Clone)]
+enum Session {
+    Bearer(String),                         // `Authorization: Bearer <token>`
+    Header { name: String, value: String }, // Token in a custom header
+    Cookie(String),                         // Session cookies, as a `Cookie` header value
+}
+
+impl Session {
+    /// Adds the session credentials to a request
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match self {
+            Session::Bearer(token) => request.bearer_auth(token),
+            Session::Header { name, value } => request.header(name, value),
+            Session::Cookie(cookies) => request.header(COOKIE, cookies),
+        }
+    }
+}
+
+impl RestconfClient {
+    /// Creates a client reaching the device over HTTPS at its host and port
+    pub fn new(device: Device) -> Result<Self> {
+        Self::with_resolver(device, CachedResolver::new())
+    }
+
+    /// Creates a client reaching the device over HTTPS, resolving its host with `resolver`
+    ///
+    /// Clients sharing a resolver share its DNS cache.
+    pub fn with_resolver(device: Device, resolver: CachedResolver) -> Result<Self> {
+        // IPv6 literals are bracketed in the URL
+        let base_url = match device.port {
+            Some(port) => format!("https://{}:{}", device.host.url_host(), port),
+            None => format!("https://{}", device.host.url_host()),
+        };
+        Self::build(device, &base_url, resolver, None)
+    }
+
+    /// Creates a client reaching the device over HTTPS through the local end of a tunnel
+    ///
+    /// A device named by a hostname is still addressed by that name, so its certificate
+    /// is checked against it, while connections go to `local`.
+    pub fn with_tunnel(
+        device: Device,
+        resolver: CachedResolver,
+        local: SocketAddr,
+    ) -> Result<Self> {
+        if device.host.as_str().parse::<IpAddr>().is_ok() {
+            let base_url = format!("https://{}", local);
+            return Self::build(device, &base_url, resolver, None);
+        }
+        let base_url = format!("https://{}:{}", device.host, local.port());
+        Self::build(device, &base_url, resolver, Some(local))
+    }
+
+    /// Creates a client reaching the device at `base_url` (e.g. `http://127.0.0.1:8080`)
+    pub fn with_base_url(device: Device, base_url: &str) -> Result<Self> {
+        Self::build(device, base_url, CachedResolver::new(), None)
+    }
+
+    /// Creates a client reaching the device at `base_url` through `resolver`, and its
+    /// proxy if it has one; with `tunnel`, the device host is resolved to it instead
+    fn build(
+        device: Device,
+        base_url: &str,
+        resolver: CachedResolver,
+        tunnel: Option<SocketAddr>,
+    ) -> Result<Self> {
+        let mut builder = Client::builder()
+            .dns_resolver(Arc::new(resolver.clone()))
+            .connect_timeout(CONNECT_TIMEOUT);
+        if let Some(tunnel) = tunnel {
+            builder = builder.resolve(device.host.as_str(), tunnel);
+        }
+        if let Some(proxy_url) = &device.proxy_url {
+            // Credentials in the URL are sent to the proxy (Basic or SOCKS5 login)
+            let proxy = Proxy::all(proxy_url.as_str()).map_err(|err| {
+                Error::Invalid(format!("Invalid proxy URL {}: {}", proxy_url, err))
+            })?;
+            builder = builder.proxy(proxy);
+        }
+        let http = builder
+            .build()
+            .map_err(|err| Error::Custom(format!("Failed to build HTTP client: {}", err)))?;
+
+        Ok(RestconfClient {
+            device,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http,
+            timeout: REQUEST_TIMEOUT,
+            resolver,
+            session: Mutex::new(None),
+            validators: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Sets the time given to every request, answer body included
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Returns the device this client collects from
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Returns the address the device was last resolved to, the one being polled
+    ///
+    /// # Returns
+    /// - `Some(IpAddr)`: The address, or the host itself if it is an IP address
+    /// - `None`: If the host has not been resolved yet
+    pub fn address(&self) -> Option<IpAddr> {
+        let url = Url::parse(&self.base_url).ok()?;
+        self.resolver.address(url.host_str()?)
+    }
+
+    /// Builds the absolute URL of `path`, which may already be an absolute URL
+    pub fn url(&self, path: &str) -> String {
+        if path.starts_with("http://") || path.starts_with("https://") {
+            return path.to_string();
+        }
+        format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+    }
+
+    /// Creates the resource `body` under `path`
+    ///
+    /// # Returns
+    /// - `Ok(Some(Value))`: The body of the answer, if the device sent one
+    /// - `Ok(None)`: If the device answered without a body, as RESTCONF does on `201`
+    /// - `Err(Error)`: If the device refused the request or could not be reached
+    pub async fn post(&self, path: &str, body: &Value) -> Result<Option<Value>> {
+        self.write(Method::POST, path, Some(body)).await
+    }
+
+    /// Merges `body` into the resource at `path`
+    ///
+    /// # Returns
+    /// - `Ok(Some(Value))`: The body of the answer, if the device sent one
+    /// - `Ok(None)`: If the device answered without a body, as RESTCONF does on `204`
+    /// - `Err(Error)`: If the device refused the request or could not be reached
+    pub async fn patch(&self, path: &str, body: &Value) -> Result<Option<Value>> {
+        self.write(Method::PATCH, path, Some(body)).await
+    }
+
+    /// Deletes the resource at `path`
+    pub async fn delete(&self, path: &str) -> Result<()> {
+        self.write(Method::DELETE, path, None).await.map(|_| ())
+    }
+
+    /// Fetches the resource at `path`, if it exists
+    ///
+    /// # Returns
+    /// - `Ok(Some(Value))`: The resource
+    /// - `Ok(None)`: If the device answered `404`
+    /// - `Err(Error)`: If the device answered another error or could not be reached
+    pub async fn find(&self, path: &str) -> Result<Option<Value>> {
+        let span = info_span!("southbound", host = %self.device.host, path);
+        async {
+            let response = self
+                .send(
+                    Method::GET,
+                    path,
+                    &FetchOptions::default(),
+                    None,
+                    &HeaderMap::new(),
+                )
+                .await?;
+            if response.status() == StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            json_body(response).await.map(Some)
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Fetches the resource at `path` unless it is unchanged since the previous call
+    ///
+    /// The `ETag` and `Last-Modified` of the previous answer are sent back as
+    /// `If-None-Match` and `If-Modified-Since`, so devices supporting them answer `304`
+    /// without a body. For the others, the content hash of the answer is compared with
+    /// the previous one, so the caller can still skip parsing an unchanged resource.
+    ///
+    /// # Returns
+    /// - `Ok(Fetched::Modified(Value))`: On the first call, or if the resource changed
+    /// - `Ok(Fetched::Unchanged)`: If the resource is the same as on the previous call
+    /// - `Err(Error)`: If the device answered an error or could not be reached
+    pub async fn get_if_changed(&self, path: &str, options: &FetchOptions) -> Result<Fetched> {
+        let span = info_span!("southbound", host = %self.device.host, path);
+        async {
+            let key = (path.to_string(), options.clone());
+            let previous = self.validators.lock().await.get(&key).cloned();
+
+            let mut headers = HeaderMap::new();
+            if let Some(previous) = &previous {
+                if let Some(etag) = &previous.etag {
+                    headers.insert(IF_NONE_MATCH, etag.clone());
+                }
+                if let Some(last_modified) = &previous.last_modified {
+                    headers.insert(IF_MODIFIED_SINCE, last_modified.clone());
+                }
+            }
+
+            let response = self
+                .send(Method::GET, path, options, None, &headers)
+                .await?;
+            if response.status() == StatusCode::NOT_MODIFIED && previous.is_some() {
+                return Ok(Fetched::Unchanged);
+            }
+
+            let etag = response.headers().get(ETAG).cloned();
+            let last_modified = response.headers().get(LAST_MODIFIED).cloned();
+            let value = json_body(response).await?;
+            let hash = ContentHash::of(&value);
+            self.validators.lock().await.insert(
+                key,
+                Validators {
+                    etag,
+                    last_modified,
+                    hash: Some(hash),
+                },
+            );
+
+            match previous.and_then(|previous| previous.hash) {
+                Some(previous) if previous == hash => Ok(Fetched::Unchanged),
+                _ => Ok(Fetched::Modified(value)),
+            }
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Sends a configuration change, whose answer may have no body
+    async fn write(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&Value>,
+    ) -> Result<Option<Value>> {
+        let span = info_span!("southbound", host = %self.device.host, path, method = %method);
+        async {
+            let response = self
+                .send(
+                    method.clone(),
+                    path,
+                    &FetchOptions::default(),
+                    body,
+                    &HeaderMap::new(),
+                )
+                .await?;
+            optional_json_body(response).await
+        }
+        .instrument(span)
+        .await
+    }
+
+    /// Sends a request, logging in again once if the device answers 401
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        options: &FetchOptions,
+        body: Option<&Value>,
+        headers: &HeaderMap,
+    ) -> Result<Response> {
+        let response = self
+            .request(method.clone(), path, options, body, headers)
+            .await?
+            .send()
+            .await;
+        let response = response.map_err(unreachable)?;
+
+        if response.status() == StatusCode::UNAUTHORIZED && self.session.lock().await.is_some() {
+            // The session expired: drop it and log in again
+            *self.session.lock().await = None;
+            return self
+                .request(method, path, options, body, headers)
+                .await?
+                .send()
+                .await
+                .map_err(unreachable);
+        }
+
+        Ok(response)
+    }
+
+    /// Builds an authenticated request for `path`, with `body` as its JSON payload
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        options: &FetchOptions,
+        body: Option<&Value>,
+        headers: &HeaderMap,
+    ) -> Result<RequestBuilder> {
+        let mut request = self
+            .http
+            .request(method, self.url(path))
+            .timeout(self.timeout)
+            .header(ACCEPT, ACCEPTED)
+            .headers(headers.clone());
+        if let Some(body) = body {
+            request = request
+                .header(CONTENT_TYPE, YANG_DATA_JSON)
+                .body(body.to_string());
+        }
+
+        if let Some(depth) = options.depth {
+            request = request.query(&[("depth", depth.to_string())]);
+        }
+        if let Some(fields) = &options.fields {
+            request = request.query(&[("fields", fields)]);
+        }
+
+        match &self.device.auth {
+            Auth::BasicAuth(basic) => {
+                Ok(request.basic_auth(&basic.username, Some(&basic.password)))
+            }
+            Auth::Oauth2(_) | Auth::Custom(_) => Ok(self.session().await?.authorize(request)),
+            Auth::ApiKey(api_key) => Ok(request.header(&api_key.header_name, &api_key.key)),
+            Auth::BearerToken(bearer) => Ok(request.bearer_auth(&bearer.token)),
+        }
+    }
+
+    /// Returns the cached login session, logging in if needed
+    async fn session(&self) -> Result<Session> {
+        let mut session = self.session.lock().await;
+        if let Some(session) = session.as_ref() {
+            return Ok(session.clone());
+        }
+
+        let new_session = match &self.device.auth {
+            Auth::Oauth2(_) => Session::Bearer(self.oauth2_token().await?),
+            Auth::Custom(custom) => self.custom_login(custom).await?,
+            _ => return Err(Error::from("Device does not use session authentication")),
+        };
+        *session = Some(new_session.clone());
+        Ok(new_session)
+    }
+
+    /// Requests an OAuth2 access token
+    async fn oauth2_token(&self) -> Result<String> {
+        let Auth::Oauth2(oauth2) = &self.device.auth else {
+            return Err(Error::from("Device does not use OAuth2 authentication"));
+        };
+
+        let response = self
+            .http
+            .post(self.url(&oauth2.auth_url))
+            .timeout(self.timeout)
+            .basic_auth(&oauth2.username, Some(&oauth2.password))
+            .json(&json!({ "grant_type": oauth2.grant_type }))
+            .send()
+            .await
+            .map_err(unreachable)?;
+        let body = json_body(response).await?;
+
+        Ok(body
+            .get("access_token")
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                Error::BadResponse("Access token not found in OAuth2 response".to_string())
+            })?
+            .to_string())
+    }
+
+    /// Logs in by POSTing `auth_body` to `auth_url`
+    ///
+    /// The session is the token at `token_pointer` in the answer if configured, or else
+    /// the cookies the device set.
+    async fn custom_login(&self, custom: &CustomAuth) -> Result<Session> {
+        let response = self
+            .http
+            .post(self.url(&custom.auth_url))
+            .timeout(self.timeout)
+            .json(&custom.auth_body)
+            .send()
+            .await
+            .map_err(unreachable)?;
+
+        let cookies: Vec<String> = response
+            .headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|cookie| cookie.to_str().ok())
+            .filter_map(|cookie| cookie.split(';').next())
+            .map(|cookie| cookie.trim().to_string())
+            .collect();
+
+        let Some(pointer) = &custom.token_pointer else {
+            if !response.status().is_success() {
+                json_body(response).await?;
+            }
+            if cookies.is_empty() {
+                return Err(Error::BadResponse(
+                    "Session cookie not found in login response".to_string(),
+                ));
+            }
+            return Ok(Session::Cookie(cookies.join("; ")));
+        };
+
+        let body = json_body(response).await?;
+        let token = body
+            .pointer(pointer)
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                Error::BadResponse(format!("Token not found at {} in login response", pointer))
+            })?
+            .to_string();
+
+        Ok(match &custom.token_header {
+            Some(name) => Session::Header {
+                name: name.clone(),
+                value: token,
+            },
+            None => Session::Bearer(token),
+        })
+    }
+}
+
+impl SouthboundProtocol for RestconfClient {
+    async fn get(&self, path: &str, options: &FetchOptions) -> Result<Value> {
+        let span = info_span!("southbound", host = %self.device.host, path);
+        async {
+            let response = self
+                .send(Method::GET, path, options, None, &HeaderMap::new())
+                .await?;
+            json_body(response).await
+        }
+        .instrument(span)
+        .await
+    }
+}
+
+/// Reads a JSON or XML response body, turning error statuses into errors
+async fn json_body(response: Response) -> Result<Value> {
+    let status = response.status();
+    let xml = is_xml(&response);
+    let content = response.bytes().await.map_err(unreachable)?;
+    let body = decode(&content, xml);
+
+    if !status.is_success() {
+        return Err(body.as_ref().and_then(restconf_error).unwrap_or_else(|| {
+            Error::BadResponse(format!("Device answered with status {}", status))
+        }));
+    }
+
+    body.ok_or_else(|| invalid_body(xml))
+}
+
+/// Reads a JSON or XML response body that may be empty, turning error statuses into errors
+async fn optional_json_body(response: Response) -> Result<Option<Value>> {
+    let status = response.status();
+    let xml = is_xml(&response);
+    let content = response.bytes().await.map_err(unreachable)?;
+    let body = decode(&content, xml);
+
+    if !status.is_success() {
+        return Err(body.as_ref().and_then(restconf_error).unwrap_or_else(|| {
+            Error::BadResponse(format!("Device answered with status {}", status))
+        }));
+    }
+    if content.iter().all(u8::is_ascii_whitespace) {
+        return Ok(None);
+    }
+
+    body.map(Some).ok_or_else(|| invalid_body(xml))
+}
+
+/// Returns whether the device answered XML rather than JSON
+fn is_xml(response: &Response) -> bool {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| {
+            content_type.starts_with(YANG_DATA_XML) || content_type.contains("/xml")
+        })
+}
+
+/// Decodes a response body into the JSON encoding, `None` if it is malformed
+fn decode(content: &[u8], xml: bool) -> Option<Value> {
+    if xml {
+        std::str::from_utf8(content)
+            .ok()
+            .and_then(|content| xml::to_json(content).ok())
+    } else {
+        serde_json::from_slice(content).ok()
+    }
+}
+
+/// Error of a malformed response body
+fn invalid_body(xml: bool) -> Error {
+    if xml {
+        Error::BadResponse("Device answered with an invalid XML body".to_string())
+    } else {
+        Error::BadResponse("Device answered with an invalid JSON body".to_string())
+    }
+}
+
+/// Converts a RESTCONF error envelope (`ietf-restconf:errors`) into an error
+///
+/// Returns `None` if `value` is not an error envelope.
+pub fn restconf_error(value: &Value) -> Option<Error> {
+    let errors = value
+        .get("ietf-restconf:errors")?
+        .get("error")?
+        .as_array()?;
+
+    let messages: Vec<String> = errors
+        .iter()
+        .map(|error| {
+            let tag = error
+                .get("error-tag")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown");
+            match error.get("error-message").and_then(Value::as_str) {
+                Some(message) => format!("{}: {}", tag, message),
+                None => tag.to_string(),
+            }
+        })
+        .collect();
+
+    Some(Error::BadResponse(format!(
+        "RESTCONF error: {}",
+        messages.join("; ")
+    )))
+}
+
+/// Maps a transport failure to an error
+fn unreachable(err: reqwest::Error) -> Error {
+    if err.is_timeout() {
+        Error::Timeout(format!("Device timeout: {}", err))
+    } else {
+        Error::Unreachable(format!("Device unreachable: {}", err))
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{Error, Result};
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use snmp2::{v3, AsyncSession, Oid, Value};
+
+/// Default SNMP agent port
+pub const SNMP_PORT: u16 = 161;
+
+const SYS_DESCR: &[u64] = &[1, 3, 6, 1, 2, 1, 1, 1, 0]; // SNMPv2-MIB::sysDescr.0
+const SYS_NAME: &[u64] = &[1, 3, 6, 1, 2, 1, 1, 5, 0]; // SNMPv2-MIB::sysName.0
+const IF_NUMBER: &[u64] = &[1, 3, 6, 1, 2, 1, 2, 1, 0]; // IF-MIB::ifNumber.0 (ifTable rows)
+
+/// Credentials used to reach an SNMP agent
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "version", rename_all = "lowercase")]
+pub enum SnmpCredentials {
+    V2c {
+        community: String,
+    },
+    V3 {
+        username: String,
+        auth_password: String,
+        #[serde(default)]
+        auth_protocol: SnmpAuthProtocol,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        privacy_password: Option<String>, // Enables AES-128 encryption when set
+    },
+}
+
+/// SNMPv3 authentication protocol
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SnmpAuthProtocol {
+    Md5,
+    #[default]
+    Sha1,
+    Sha256,
+}
+
+/// Basic information reported by an SNMP agent
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SnmpInfo {
+    pub sys_name: Option<String>,     // sysName.0
+    pub sys_descr: Option<String>,    // sysDescr.0
+    pub interface_count: Option<i64>, // ifNumber.0
+}
+
+impl SnmpInfo {
+    /// Returns the information as device metadata entries (`snmp.sys_name`...)
+    pub fn metadata(&self) -> BTreeMap<String, String> {
+        let mut metadata = BTreeMap::new();
+        if let Some(sys_name) = &self.sys_name {
+            metadata.insert("snmp.sys_name".to_string(), sys_name.clone());
+        }
+        if let Some(sys_descr) = &self.sys_descr {
+            metadata.insert("snmp.sys_descr".to_string(), sys_descr.clone());
+        }
+        if let Some(interface_count) = self.interface_count {
+            metadata.insert(
+                "snmp.interface_count".to_string(),
+                interface_count.to_string(),
+            );
+        }
+        metadata
+    }
+}
+
+/// Probes an SNMP agent, fetching sysName, sysDescr and the number of interfaces
+///
+/// # Arguments
+///
+/// * `host` - Address of the agent
+/// * `port` - UDP port of the agent, usually [`SNMP_PORT`]
+/// * `credentials` - SNMP v2c community or v3 user
+/// * `timeout` - Time allowed for the whole probe
+///
+/// # Returns
+///
+/// The information reported by the agent, or an error if it did not answer in time.
+pub async fn probe(
+    host: &str,
+    port: u16,
+    credentials: &SnmpCredentials,
+    timeout: Duration,
+) -> Result<SnmpInfo> {
+    tokio::time::timeout(timeout, probe_agent(host, port, credentials))
+        .await
+        .map_err(|_| Error::Timeout(format!("Device timeout: no SNMP answer from {}", host)))?
+}
+
+async fn probe_agent(host: &str, port: u16, credentials: &SnmpCredentials) -> Result<SnmpInfo> {
+    let destination = format!("{}:{}", host, port);
+
+    let mut session = match credentials {
+        SnmpCredentials::V2c { community } => {
+            AsyncSession::new_v2c(destination.as_str(), community.as_bytes(), 0)
+                .await
+                .map_err(unreachable)?
+        }
+        SnmpCredentials::V3 {
+            username,
+            auth_password,
+            auth_protocol,
+            privacy_password,
+        } => {
+            let auth = match privacy_password {
+                Some(privacy_password) => v3::Auth::AuthPriv {
+                    cipher: v3::Cipher::Aes128,
+                    privacy_password: privacy_password.as_bytes().to_vec(),
+                },
+                None => v3::Auth::AuthNoPriv,
+            };
+            let security = v3::Security::new(username.as_bytes(), auth_password.as_bytes())
+                .with_auth(auth)
+                .with_auth_protocol(match auth_protocol {
+                    SnmpAuthProtocol::Md5 => v3::AuthProtocol::Md5,
+                    SnmpAuthProtocol::Sha1 => v3::AuthProtocol::Sha1,
+                    SnmpAuthProtocol::Sha256 => v3::AuthProtocol::Sha256,
+                });
+
+            let mut session = AsyncSession::new_v3(destination.as_str(), 0, security)
+                .await
+                .map_err(unreachable)?;
+            // Discovers the engine ID and boots of the agent
+            session.init().await.map_err(snmp_error)?;
+            session
+        }
+    };
+
+    let sys_descr = oid(SYS_DESCR)?;
+    let sys_name = oid(SYS_NAME)?;
+    let if_number = oid(IF_NUMBER)?;
+
+    let pdu = session
+        .get_many(&[&sys_descr, &sys_name, &if_number])
+        .await
+        .map_err(snmp_error)?;
+
+    let mut info = SnmpInfo::default();
+    for (name, value) in pdu.varbinds {
+        match value {
+            Value::OctetString(bytes) if name == sys_name => {
+                info.sys_name = Some(String::from_utf8_lossy(bytes).to_string());
+            }
+            Value::OctetString(bytes) if name == sys_descr => {
+                info.sys_descr = Some(String::from_utf8_lossy(bytes).to_string());
+            }
+            Value::Integer(count) if name == if_number => info.interface_count = Some(count),
+            _ => {} // noSuchObject or unexpected type: leave the field empty
+        }
+    }
+
+    Ok(info)
+}
+
+fn oid(components: &[u64]) -> Result<Oid<'static>> {
+    Oid::from(components).map_err(|_| Error::from("Invalid OID"))
+}
+
+/// Maps a socket failure to an error
+fn unreachable(err: std::io::Error) -> Error {
+    Error::Unreachable(format!("Device unreachable: {}", err))
+}
+
+/// Maps an SNMP protocol failure to an error
+fn snmp_error(err: snmp2::Error) -> Error {
+    Error::Custom(format!("SNMP error: {}", err))
+}
@@ -0,0 +1,294 @@
+use crate::{Error, Result};
+
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{copy_bidirectional, join, AsyncBufReadExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Instant};
+use tracing::{debug, info, warn};
+
+/// How long the master session is given to come up after `ssh` starts
+const OPEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Delay between two checks of a starting master session
+const OPEN_POLL: Duration = Duration::from_millis(100);
+
+/// Bastion a device is reached through, `[devices."<host>".jump_host]` in `config.toml`
+///
+/// ```toml
+/// [devices."10.0.0.1".jump_host]
+/// host = "bastion.example.com"
+/// user = "ops"
+/// identity_file = "/etc/device-manager/id_ed25519"
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JumpHost {
+    pub host: String,
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    pub user: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<PathBuf>, // Private key, the SSH agent and defaults otherwise
+}
+
+fn default_ssh_port() -> u16 {
+    22
+}
+
+impl JumpHost {
+    /// Returns the `ssh` arguments of the master session, listening on `control_path`
+    ///
+    /// The session never prompts (`BatchMode`), so a missing key fails instead of
+    /// hanging. It forwards nothing itself: each connection to the device is a channel
+    /// opened through it by the arguments of [`JumpHost::forward_args`].
+    ///
+    /// # Returns
+    /// - `Ok(Vec<String>)`: The arguments
+    /// - `Err(Error)`: If the user or host would be read as an option
+    pub fn ssh_args(&self, control_path: &Path) -> Result<Vec<String>> {
+        let mut args = vec![
+            "-N".to_string(),
+            "-M".to_string(),
+            "-S".to_string(),
+            control_path.display().to_string(),
+            "-o".to_string(),
+            "BatchMode=yes".to_string(),
+            "-o".to_string(),
+            "ServerAliveInterval=30".to_string(),
+        ];
+        args.extend(self.destination()?);
+        Ok(args)
+    }
+
+    /// Returns the `ssh` arguments connecting stdin and stdout to `target:target_port`,
+    /// through the master session at `control_path`
+    ///
+    /// # Returns
+    /// - `Ok(Vec<String>)`: The arguments
+    /// - `Err(Error)`: If the user or host would be read as an option
+    pub fn forward_args(
+        &self,
+        control_path: &Path,
+        target: &str,
+        target_port: u16,
+    ) -> Result<Vec<String>> {
+        // IPv6 targets are bracketed in the forward specification
+        let target = match target.parse::<std::net::Ipv6Addr>() {
+            Ok(_) => format!("[{}]", target),
+            Err(_) => target.to_string(),
+        };
+        let mut args = vec![
+            "-S".to_string(),
+            control_path.display().to_string(),
+            "-o".to_string(),
+            "ControlMaster=no".to_string(),
+            "-o".to_string(),
+            "BatchMode=yes".to_string(),
+            "-W".to_string(),
+            format!("{}:{}", target, target_port),
+        ];
+        args.extend(self.destination()?);
+        Ok(args)
+    }
+
+    /// Returns the port, identity and `user@host` arguments of the jump host, the
+    /// destination after the `--` ending the options
+    fn destination(&self) -> Result<Vec<String>> {
+        // A destination starting with `-` would be read as an option, e.g. `-oProxyCommand`
+        if self.user.starts_with('-') || self.host.starts_with('-') {
+            return Err(Error::Invalid(format!(
+                "Jump host {} has a user or host starting with '-'",
+                self.host
+            )));
+        }
+        let mut args = vec!["-p".to_string(), self.port.to_string()];
+        if let Some(identity_file) = &self.identity_file {
+            args.push("-i".to_string());
+            args.push(identity_file.display().to_string());
+        }
+        args.push("--".to_string());
+        args.push(format!("{}@{}", self.user, self.host));
+        Ok(args)
+    }
+}
+
+/// Tunnel to a device through its jump host
+///
+/// The tunnel runs the system `ssh` client rather than an embedded SSH implementation,
+/// so the usual `~/.ssh/config`, agent and `known_hosts` apply. A master session is
+/// kept open to the jump host; the tunnel listens on a local port it binds itself, and
+/// relays every accepted connection over a channel of the master (`ssh -W`). The
+/// session and the listener are closed when the tunnel is dropped.
+pub struct SshTunnel {
+    local_address: SocketAddr,
+    master: Child,
+    relay: JoinHandle<()>, // Accepts the local connections
+    control: PathBuf,      // Private directory of the master socket
+}
+
+impl SshTunnel {
+    /// Opens a tunnel from a local port to `target:target_port` through `jump`
+    ///
+    /// # Returns
+    /// - `Ok(SshTunnel)`: Once the master session is up
+    /// - `Err(Error)`: If `ssh` cannot be started, exits, or the session does not come up
+    pub async fn open(jump: &JumpHost, target: &str, target_port: u16) -> Result<Self> {
+        let control = control_directory()?;
+        let tunnel = Self::start(jump, target, target_port, &control).await;
+        if tunnel.is_err() {
+            let _ = std::fs::remove_dir_all(&control);
+        }
+        tunnel
+    }
+
+    async fn start(
+        jump: &JumpHost,
+        target: &str,
+        target_port: u16,
+        control: &Path,
+    ) -> Result<Self> {
+        let socket = control.join("master");
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|err| Error::Custom(format!("Failed to bind a tunnel port: {}", err)))?;
+        let local_address = listener.local_addr().map_err(Error::custom)?;
+        let forward = jump.forward_args(&socket, target, target_port)?;
+        let mut master = Command::new("ssh")
+            .args(jump.ssh_args(&socket)?)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|err| Error::Custom(format!("Failed to start ssh: {}", err)))?;
+        // Drained for the whole session, a full pipe would block `ssh`
+        let stderr = master.stderr.take().map(|stderr| {
+            let host = jump.host.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                let mut last = String::new();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    debug!("ssh {}: {}", host, line);
+                    last = line;
+                }
+                last
+            })
+        });
+        debug!(
+            "Opening SSH tunnel to {}:{} through {}",
+            target, target_port, jump.host
+        );
+
+        let deadline = Instant::now() + OPEN_TIMEOUT;
+        loop {
+            if let Some(status) = master.try_wait().map_err(Error::custom)? {
+                let message = match stderr {
+                    Some(stderr) => stderr.await.unwrap_or_default(),
+                    None => String::new(),
+                };
+                return Err(Error::Unreachable(format!(
+                    "Device unreachable: SSH tunnel through {} exited with {}: {}",
+                    jump.host,
+                    status,
+                    message.trim()
+                )));
+            }
+            if tokio::fs::try_exists(&socket).await.unwrap_or(false) {
+                break;
+            }
+            if Instant::now() > deadline {
+                return Err(Error::Timeout(format!(
+                    "Device timeout: SSH tunnel through {} not ready after {:?}",
+                    jump.host, OPEN_TIMEOUT
+                )));
+            }
+            sleep(OPEN_POLL).await;
+        }
+
+        let relay = tokio::spawn(async move {
+            while let Ok((connection, _)) = listener.accept().await {
+                tokio::spawn(relay(connection, forward.clone()));
+            }
+        });
+        info!(
+            "SSH tunnel to {}:{} through {} listening on {}",
+            target, target_port, jump.host, local_address
+        );
+        Ok(SshTunnel {
+            local_address,
+            master,
+            relay,
+            control: control.to_path_buf(),
+        })
+    }
+
+    /// Returns the local end of the tunnel
+    pub fn local_address(&self) -> SocketAddr {
+        self.local_address
+    }
+
+    /// Returns whether the master `ssh` session is still running
+    pub fn is_open(&mut self) -> bool {
+        matches!(self.master.try_wait(), Ok(None))
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        self.relay.abort();
+        let _ = self.master.start_kill();
+        let _ = std::fs::remove_dir_all(&self.control);
+    }
+}
+
+/// Relays a local connection to the device over a channel of the master session
+async fn relay(mut connection: TcpStream, forward: Vec<String>) {
+    let channel = Command::new("ssh")
+        .args(&forward)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+    let mut channel = match channel {
+        Ok(channel) => channel,
+        Err(err) => {
+            warn!("Failed to start ssh: {}", err);
+            return;
+        }
+    };
+    let (Some(stdout), Some(stdin)) = (channel.stdout.take(), channel.stdin.take()) else {
+        return;
+    };
+    if let Err(err) = copy_bidirectional(&mut connection, &mut join(stdout, stdin)).await {
+        debug!("SSH channel closed: {}", err);
+    }
+}
+
+/// Creates a directory only the current user can read, for the master socket
+fn control_directory() -> Result<PathBuf> {
+    static COUNT: AtomicU64 = AtomicU64::new(0);
+    let directory = std::env::temp_dir().join(format!(
+        "device-manager-ssh-{}-{}",
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&directory).map_err(|err| {
+        Error::Custom(format!(
+            "Failed to create tunnel directory {}: {}",
+            directory.display(),
+            err
+        ))
+    })?;
+    Ok(directory)
+}
@@ -0,0 +1,186 @@
+use crate::{Error, Result};
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::ResolveResult;
+use quick_xml::NsReader;
+use serde_json::{Map, Value};
+
+/// Media type of RESTCONF XML payloads
+pub const YANG_DATA_XML: &str = "application/yang-data+xml";
+
+/// Converts a RESTCONF XML payload into its `application/yang-data+json` encoding
+///
+/// XML does not tell a list of one entry from a container, so the lists the models read
+/// are known by name and always become arrays, as do repeated elements. Members in
+/// another namespace than their parent are qualified by the module the namespace ends
+/// with (`urn:onf:otcc:yang:tapi-topology` for `tapi-topology`), like the JSON encoding
+/// does. Leaves are read as strings.
+///
+/// # Arguments
+/// - `xml`: The XML document, a single top-level element
+///
+/// # Returns
+/// - `Ok(Value)`: The payload as the device would have sent it in JSON
+/// - `Err(Error)`: If the document is not well-formed XML
+pub fn to_json(xml: &str) -> Result<Value> {
+    let mut reader = NsReader::from_str(xml);
+    let mut stack: Vec<Element> = vec![];
+    loop {
+        let (namespace, event) = reader.read_resolved_event().map_err(invalid)?;
+        match event {
+            Event::Start(start) => {
+                let element = Element::open(&start, &namespace, stack.last())?;
+                stack.push(element);
+            }
+            Event::Empty(start) => {
+                let element = Element::open(&start, &namespace, stack.last())?;
+                if let Some(root) = close(&mut stack, element) {
+                    return Ok(root);
+                }
+            }
+            Event::End(_) => {
+                let element = stack.pop().ok_or_else(|| {
+                    Error::BadResponse("Invalid XML payload: unexpected end tag".to_string())
+                })?;
+                if let Some(root) = close(&mut stack, element) {
+                    return Ok(root);
+                }
+            }
+            Event::Text(text) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text.unescape().map_err(invalid)?);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::Eof => {
+                return Err(Error::BadResponse(
+                    "Invalid XML payload: no root element".to_string(),
+                ))
+            }
+            _ => {}
+        }
+    }
+}
+
+/// An element being read, with the members read so far
+struct Element {
+    local: String,     // Name without its module
+    name: String,      // Name as a JSON member, qualified if needed
+    namespace: String, // Namespace URI, empty if unbound
+    members: Vec<(String, Value)>,
+    text: String,
+}
+
+impl Element {
+    /// Starts reading an element, child of `parent`
+    fn open(
+        start: &BytesStart,
+        namespace: &ResolveResult,
+        parent: Option<&Element>,
+    ) -> Result<Self> {
+        let local = String::from_utf8_lossy(start.local_name().as_ref()).to_string();
+        let namespace = match namespace {
+            ResolveResult::Bound(namespace) => {
+                String::from_utf8_lossy(namespace.as_ref()).to_string()
+            }
+            ResolveResult::Unbound => String::new(),
+            ResolveResult::Unknown(prefix) => {
+                return Err(Error::BadResponse(format!(
+                    "Invalid XML payload: unknown prefix {}",
+                    String::from_utf8_lossy(prefix)
+                )))
+            }
+        };
+        let inherited = parent.map(|parent| parent.namespace == namespace);
+        let name = match (inherited, module(&namespace)) {
+            (Some(true), _) | (_, None) => local.clone(),
+            (_, Some(module)) => format!("{}:{}", module, local),
+        };
+        Ok(Element {
+            local,
+            name,
+            namespace,
+            members: vec![],
+            text: String::new(),
+        })
+    }
+
+    /// Returns the JSON value of the element, once every member is read
+    fn value(self) -> Value {
+        if self.members.is_empty() {
+            return Value::String(self.text.trim().to_string());
+        }
+        let mut object = Map::new();
+        for (name, value) in self.members {
+            let local = name.rsplit(':').next().unwrap_or_default();
+            match object.get_mut(&name) {
+                Some(Value::Array(entries)) => entries.push(value),
+                Some(single) => *single = Value::Array(vec![single.take(), value]),
+                None if is_list(&self.local, local) => {
+                    object.insert(name, Value::Array(vec![value]));
+                }
+                None => {
+                    object.insert(name, value);
+                }
+            }
+        }
+        Value::Object(object)
+    }
+}
+
+/// Adds a finished element to its parent, returning the document if it was the root
+fn close(stack: &mut [Element], element: Element) -> Option<Value> {
+    let name = element.name.clone();
+    match stack.last_mut() {
+        Some(parent) => {
+            parent.members.push((name, element.value()));
+            None
+        }
+        None => {
+            let mut root = Map::new();
+            root.insert(name, element.value());
+            Some(Value::Object(root))
+        }
+    }
+}
+
+/// Returns whether the member `name` of `parent` is a YANG list or leaf-list
+fn is_list(parent: &str, name: &str) -> bool {
+    match (parent, name) {
+        (
+            _,
+            "topology"
+            | "node"
+            | "link"
+            | "node-edge-point"
+            | "owned-node-edge-point"
+            | "name"
+            | "mapped-service-interface-point"
+            | "device"
+            | "equipment"
+            | "access-port"
+            | "connector-pin"
+            | "error",
+        ) => true,
+        // A leaf of node edge points, but a leaf-list of topologies, nodes and links
+        ("topology" | "node" | "link", "layer-protocol-name") => true,
+        _ => false,
+    }
+}
+
+/// Returns the YANG module a namespace URI names, its last segment
+fn module(namespace: &str) -> Option<&str> {
+    namespace
+        .rsplit([':', '/'])
+        .next()
+        .filter(|module| !module.is_empty())
+}
+
+/// Maps a parse error of the XML reader
+fn invalid(err: impl std::fmt::Display) -> Error {
+    Error::BadResponse(format!("Invalid XML payload: {}", err))
+}
@@ -0,0 +1,124 @@
+use crate::persist;
+use crate::{Error, Result};
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+/// Poll cycles kept per device
+pub const HISTORY: usize = 100;
+
+/// Statistics of one poll cycle of a device
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CycleStats {
+    pub collected_at: DateTime<Local>,
+    pub parsed: usize,        // Nodes and links parsed
+    pub skipped: usize,       // Nodes and links skipped by a lenient parse
+    pub duration_ms: u64,     // Whole cycle: request, parse and diff
+    pub payload_bytes: usize, // Size of the JSON payload of the device
+    pub http_latency_ms: u64, // Time until the device answered
+}
+
+/// Name, help text and value of an exported gauge
+type Gauge = (&'static str, &'static str, fn(&CycleStats) -> f64);
+
+/// Poll history of a device, the body of `GET /devices/{host}/stats`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DeviceStats<'a> {
+    pub host: &'a str,
+    pub cycles: &'a [CycleStats], // Oldest first, at most `HISTORY`
+}
+
+/// Per-device statistics of the last poll cycles
+///
+/// Used to spot slow or degrading controllers: a growing latency or payload, or
+/// entries starting to be skipped. With [`StatsStore::open`] the statistics are saved
+/// to a JSON file after every cycle. The latest cycle of every device is exported as
+/// Prometheus gauges by [`StatsStore::prometheus`].
+#[derive(Default)]
+pub struct StatsStore {
+    path: Option<PathBuf>,
+    devices: BTreeMap<String, Vec<CycleStats>>,
+}
+
+impl StatsStore {
+    /// Creates an in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the store persisted to `path`, loading the statistics already recorded
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let devices = persist::read_json(&path, "stats")?.unwrap_or_default();
+
+        Ok(StatsStore {
+            path: Some(path),
+            devices,
+        })
+    }
+
+    /// Records a cycle of `host`, dropping its oldest cycle beyond `HISTORY`
+    pub fn record(&mut self, host: &str, stats: CycleStats) -> Result<()> {
+        let cycles = self.devices.entry(host.to_string()).or_default();
+        cycles.push(stats);
+        if cycles.len() > HISTORY {
+            cycles.drain(..cycles.len() - HISTORY);
+        }
+
+        if let Some(path) = &self.path {
+            persist::write_json(path, &self.devices, "stats")?;
+        }
+        Ok(())
+    }
+
+    /// Returns the poll history of `host`
+    pub fn device<'a>(&'a self, host: &'a str) -> Result<DeviceStats<'a>> {
+        let cycles = self
+            .devices
+            .get(host)
+            .ok_or_else(|| Error::NotFound(format!("Not found stats of {}", host)))?;
+        Ok(DeviceStats { host, cycles })
+    }
+
+    /// Renders the latest cycle of every device in the Prometheus text format
+    pub fn prometheus(&self) -> String {
+        self.prometheus_of(|_| true)
+    }
+
+    /// Renders the latest cycle of the devices whose host passes `include`
+    pub fn prometheus_of(&self, include: impl Fn(&str) -> bool) -> String {
+        let gauges: [Gauge; 5] = [
+            ("objects_parsed", "Nodes and links parsed", |stats| {
+                stats.parsed as f64
+            }),
+            ("objects_skipped", "Nodes and links skipped", |stats| {
+                stats.skipped as f64
+            }),
+            ("duration_seconds", "Duration of the poll cycle", |stats| {
+                stats.duration_ms as f64 / 1000.0
+            }),
+            ("payload_bytes", "Size of the device payload", |stats| {
+                stats.payload_bytes as f64
+            }),
+            ("http_latency_seconds", "Latency of the device", |stats| {
+                stats.http_latency_ms as f64 / 1000.0
+            }),
+        ];
+
+        let mut output = String::new();
+        for (name, help, value) in gauges {
+            let _ = writeln!(output, "# HELP poll_{} {} in the last cycle", name, help);
+            let _ = writeln!(output, "# TYPE poll_{} gauge", name);
+            for (host, cycles) in self.devices.iter().filter(|(host, _)| include(host)) {
+                if let Some(last) = cycles.last() {
+                    let _ = writeln!(output, "poll_{}{{host=\"{}\"}} {}", name, host, value(last));
+                }
+            }
+        }
+        output
+    }
+}
@@ -0,0 +1,175 @@
+use crate::models::{link::Link, topology::Topology};
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Settings of the inter-device link stitching, the `[stitching]` section of `config.toml`
+///
+/// ```toml
+/// [stitching]
+/// labels = ["circuit-id"]
+///
+/// [[stitching.mappings]]
+/// a = { host = "10.0.0.1", link = "14219539-208b-35f5-b7cf-35a58e083490" }
+/// b = { host = "10.0.0.2", link = "0f5e7c5a-2b1d-4c8e-9a3f-6d4b2c1e0a9f" }
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct StitchingConfig {
+    #[serde(default)]
+    pub labels: Vec<String>, // Link extensions identifying a physical link on both sides
+    #[serde(default)]
+    pub mappings: Vec<LinkMapping>, // Pairs stitched regardless of names and labels
+}
+
+/// A link as reported by one device
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LinkEnd {
+    pub host: String,
+    pub link: Uuid,
+}
+
+/// Two sides of the same physical link, configured by the operator
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LinkMapping {
+    pub a: LinkEnd,
+    pub b: LinkEnd,
+}
+
+/// How the two sides of a stitched link were matched, by decreasing priority
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StitchMatch {
+    Mapping,       // Configured in `[[stitching.mappings]]`
+    Label(String), // Same value of the named extension
+    Name,          // Same link name
+}
+
+/// A physical link reported by two devices, joining their topologies
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StitchedLink {
+    pub a: LinkEnd,
+    pub b: LinkEnd,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub matched_by: StitchMatch,
+}
+
+/// Aggregated multi-domain topology, the body of `GET /topology/global`
+#[derive(Serialize, Debug)]
+pub struct GlobalTopology<'a> {
+    pub domains: Vec<&'a Topology>, // Topology of every device, as collected
+    pub stitched: Vec<StitchedLink>, // Links joining two domains
+}
+
+impl<'a> GlobalTopology<'a> {
+    /// Stitches the topologies of the registered devices
+    ///
+    /// Links of different hosts are paired by configured mappings first, then by the
+    /// configured labels and finally by name. A link is stitched at most once, and a name
+    /// or label value shared by several links of the same host is ambiguous and ignored.
+    pub fn stitch(topologies: &[&'a Topology], config: &StitchingConfig) -> Self {
+        let links: Vec<&Link> = topologies
+            .iter()
+            .flat_map(|topology| &topology.links)
+            .collect();
+        let mut stitched: Vec<StitchedLink> = vec![];
+        let mut used: HashSet<LinkEnd> = HashSet::new();
+
+        for mapping in &config.mappings {
+            let find = |end: &LinkEnd| {
+                links
+                    .iter()
+                    .find(|link| link.host == end.host && link.uuid == end.link)
+            };
+            if let (Some(a), Some(b)) = (find(&mapping.a), find(&mapping.b)) {
+                if a.host != b.host && !used.contains(&mapping.a) && !used.contains(&mapping.b) {
+                    used.insert(mapping.a.clone());
+                    used.insert(mapping.b.clone());
+                    stitched.push(StitchedLink {
+                        a: mapping.a.clone(),
+                        b: mapping.b.clone(),
+                        name: a.name.clone().or_else(|| b.name.clone()),
+                        matched_by: StitchMatch::Mapping,
+                    });
+                }
+            }
+        }
+
+        for label in &config.labels {
+            let key = |link: &Link| {
+                link.extensions
+                    .get(label)
+                    .filter(|value| !value.is_null())
+                    .map(Value::to_string)
+            };
+            pair(
+                &links,
+                key,
+                StitchMatch::Label(label.clone()),
+                &mut used,
+                &mut stitched,
+            );
+        }
+        pair(
+            &links,
+            |link: &Link| link.name.clone(),
+            StitchMatch::Name,
+            &mut used,
+            &mut stitched,
+        );
+
+        GlobalTopology {
+            domains: topologies.to_vec(),
+            stitched,
+        }
+    }
+}
+
+/// Stitches the unused links whose `key` is shared by exactly two links of different hosts
+fn pair(
+    links: &[&Link],
+    key: impl Fn(&Link) -> Option<String>,
+    matched_by: StitchMatch,
+    used: &mut HashSet<LinkEnd>,
+    stitched: &mut Vec<StitchedLink>,
+) {
+    let candidates: Vec<(&Link, String)> = links
+        .iter()
+        .filter(|link| !used.contains(&end(link)))
+        .filter_map(|link| key(link).map(|key| (*link, key)))
+        .collect();
+
+    for (index, (a, key_a)) in candidates.iter().enumerate() {
+        let same: Vec<&(&Link, String)> = candidates
+            .iter()
+            .filter(|(_, key_b)| key_b == key_a)
+            .collect();
+        // Ambiguous keys are skipped, and each pair is stitched from its first link
+        if same.len() != 2 || !std::ptr::eq(same[0], &candidates[index]) {
+            continue;
+        }
+        let b = same[1].0;
+        if a.host == b.host {
+            continue;
+        }
+
+        used.insert(end(a));
+        used.insert(end(b));
+        stitched.push(StitchedLink {
+            a: end(a),
+            b: end(b),
+            name: a.name.clone().or_else(|| b.name.clone()),
+            matched_by: matched_by.clone(),
+        });
+    }
+}
+
+fn end(link: &Link) -> LinkEnd {
+    LinkEnd {
+        host: link.host.clone(),
+        link: link.uuid,
+    }
+}
@@ -0,0 +1,166 @@
+use crate::Result;
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+
+/// Decides how a supervised task is restarted after it fails or panics
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    pub initial_backoff: Duration, // Delay before the first restart
+    pub max_backoff: Duration,     // Upper bound for the exponential backoff
+    pub max_restarts: Option<u32>, // Give up after this many restarts (`None` = never)
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_restarts: None,
+        }
+    }
+}
+
+/// Lifecycle state of a supervised task
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,    // The task is currently executing
+    Restarting, // The task failed and is waiting for its backoff to elapse
+    Finished,   // The task returned successfully and will not be restarted
+    Failed,     // The task exhausted its restart budget
+}
+
+/// Health snapshot of a supervised task, the payload of `GET /internal/tasks`
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TaskHealth {
+    pub name: String,
+    pub state: TaskState,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    pub last_restart: Option<DateTime<Local>>,
+}
+
+/// Owns background task handles and restarts failed tasks with backoff
+#[derive(Default)]
+pub struct Supervisor {
+    health: Arc<Mutex<HashMap<String, TaskHealth>>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Supervisor {
+    /// Creates a supervisor without tasks
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawns a supervised task
+    ///
+    /// `factory` is called to start the task and again on every restart. A task returning
+    /// `Ok(())` is considered finished; an error or a panic triggers a restart according
+    /// to `policy`.
+    pub fn spawn<F, Fut>(&mut self, name: &str, policy: RestartPolicy, factory: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let name = name.to_string();
+        let health = Arc::clone(&self.health);
+
+        health.lock().unwrap().insert(
+            name.clone(),
+            TaskHealth {
+                name: name.clone(),
+                state: TaskState::Running,
+                restarts: 0,
+                last_error: None,
+                last_restart: None,
+            },
+        );
+
+        let handle = tokio::spawn(async move {
+            let mut backoff = policy.initial_backoff;
+
+            loop {
+                // Run the task on its own so a panic is caught by the join handle
+                let mut task = AbortOnDrop(tokio::spawn(factory()));
+                let failure = match (&mut task.0).await {
+                    Ok(Ok(())) => {
+                        update(&health, &name, |task| task.state = TaskState::Finished);
+                        return;
+                    }
+                    Ok(Err(err)) => err.to_string(),
+                    Err(join_err) => format!("Task panicked: {}", join_err),
+                };
+
+                let restarts = health.lock().unwrap()[&name].restarts;
+                if policy.max_restarts.is_some_and(|max| restarts >= max) {
+                    error!(task = %name, error = %failure, "Task failed, restart budget exhausted");
+                    update(&health, &name, |task| {
+                        task.state = TaskState::Failed;
+                        task.last_error = Some(failure);
+                    });
+                    return;
+                }
+
+                warn!(task = %name, error = %failure, ?backoff, "Task failed, restarting");
+                update(&health, &name, |task| {
+                    task.state = TaskState::Restarting;
+                    task.last_error = Some(failure);
+                });
+
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(policy.max_backoff);
+
+                update(&health, &name, |task| {
+                    task.state = TaskState::Running;
+                    task.restarts += 1;
+                    task.last_restart = Some(Local::now());
+                });
+            }
+        });
+
+        self.handles.push(handle);
+    }
+
+    /// Returns the health of every supervised task, sorted by name
+    pub fn health(&self) -> Vec<TaskHealth> {
+        let mut tasks: Vec<TaskHealth> = self.health.lock().unwrap().values().cloned().collect();
+        tasks.sort_by(|a, b| a.name.cmp(&b.name));
+        tasks
+    }
+
+    /// Stops every supervised task
+    pub fn shutdown(self) {
+        for handle in self.handles {
+            handle.abort();
+        }
+    }
+}
+
+/// Aborts the wrapped task when dropped, so shutting down the supervisor stops it too
+struct AbortOnDrop(JoinHandle<Result<()>>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Applies `change` to the health entry of task `name`
+fn update(
+    health: &Mutex<HashMap<String, TaskHealth>>,
+    name: &str,
+    change: impl FnOnce(&mut TaskHealth),
+) {
+    if let Some(task) = health.lock().unwrap().get_mut(name) {
+        change(task);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::events::ChangeEvent;
+use crate::retention::{retained, PruneReport, RetentionPolicy};
+use crate::{Error, Result};
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Collection result pushed by an edge agent to the central backend
+///
+/// Agents queue snapshots locally while the central backend is unreachable and push
+/// them in order once connectivity is back, possibly long after collection.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub agent_id: Uuid,
+    pub sequence: u64, // Increases with every snapshot of the agent
+    pub host: String,  // Device the topologies were collected from
+    pub collected_at: DateTime<Local>,
+    pub topologies: Vec<Value>, // Raw `tapi-topology:topology` payloads
+    #[serde(default)]
+    pub events: Vec<ChangeEvent>, // Changes detected by the agent since its previous snapshot
+}
+
+/// What the central backend did with a pushed snapshot
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeOutcome {
+    Applied,   // The snapshot is now the latest of its host
+    Stale,     // A more recent snapshot of the host is already known; only events were kept
+    Duplicate, // The snapshot was already received (agent retry)
+}
+
+/// Agent id of the snapshots collected by the backend itself
+pub const LOCAL_AGENT: Uuid = Uuid::nil();
+
+/// History of the snapshots of every host, appended to a JSON Lines file
+///
+/// The file is what `cli topology diff`, `compare` and `violations`, `cli tui` and
+/// `cli backup` read with `--snapshots`. Lines are never rewritten, so the file grows
+/// with every collection that changed a topology.
+pub struct SnapshotLog {
+    path: PathBuf,
+}
+
+impl SnapshotLog {
+    /// Creates the history appended to `path`, kept when it already exists
+    pub fn open(path: impl AsRef<Path>) -> Self {
+        SnapshotLog {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Appends `snapshot` to the history
+    pub fn append(&self, snapshot: &Snapshot) -> Result<()> {
+        let line = serde_json::to_string(snapshot).map_err(Error::custom)?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|err| {
+                Error::Custom(format!(
+                    "Failed to write snapshots {}: {}",
+                    self.path.display(),
+                    err
+                ))
+            })
+    }
+}
+
+/// Server side merge of agent snapshots
+///
+/// Snapshots and events are stored under the tenant of the agent that pushed them, and
+/// every read is scoped to a tenant. Each host of a tenant is a last-writer-wins register ordered by collection time (ties broken by
+/// agent id and sequence), so snapshots can arrive late, twice or from several agents
+/// in any order and every replica converges on the same latest snapshot. Change events
+/// are history and are kept unless the snapshot is a duplicate.
+#[derive(Default)]
+pub struct SnapshotStore {
+    latest: HashMap<(String, String), Snapshot>, // By tenant and host
+    received: HashMap<Uuid, Vec<u64>>,
+    events: Vec<(String, ChangeEvent)>, // With their tenant
+}
+
+impl SnapshotStore {
+    /// Creates an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merges a snapshot pushed by an agent of `tenant_id`
+    pub fn merge(&mut self, tenant_id: &str, snapshot: Snapshot) -> MergeOutcome {
+        let received = self.received.entry(snapshot.agent_id).or_default();
+        if received.contains(&snapshot.sequence) {
+            return MergeOutcome::Duplicate;
+        }
+        received.push(snapshot.sequence);
+        self.events.extend(
+            snapshot
+                .events
+                .iter()
+                .map(|event| (tenant_id.to_string(), event.clone())),
+        );
+
+        let order =
+            |snapshot: &Snapshot| (snapshot.collected_at, snapshot.agent_id, snapshot.sequence);
+        let key = (tenant_id.to_string(), snapshot.host.clone());
+        match self.latest.get(&key) {
+            Some(latest) if order(latest) > order(&snapshot) => MergeOutcome::Stale,
+            _ => {
+                self.latest.insert(key, snapshot);
+                MergeOutcome::Applied
+            }
+        }
+    }
+
+    /// Returns the latest snapshot of `host` for `tenant_id`
+    pub fn latest(&self, tenant_id: &str, host: &str) -> Option<&Snapshot> {
+        self.latest.get(&(tenant_id.to_string(), host.to_string()))
+    }
+
+    /// Returns the change events received from the agents of `tenant_id`
+    pub fn events(&self, tenant_id: &str) -> Vec<&ChangeEvent> {
+        self.events
+            .iter()
+            .filter(|(tenant, _)| tenant == tenant_id)
+            .map(|(_, event)| event)
+            .collect()
+    }
+
+    /// Deletes the snapshots of devices not heard of within the retention age and the
+    /// events beyond the retention limits
+    pub fn prune(&mut self, policy: &RetentionPolicy, now: DateTime<Local>) -> PruneReport {
+        let before = self.latest.len();
+        if let Some(cutoff) = policy.cutoff(now) {
+            self.latest
+                .retain(|_, snapshot| snapshot.collected_at >= cutoff);
+        }
+
+        let keep = retained(
+            &self.events,
+            |(tenant, event)| (tenant.as_str(), event.host.as_str()),
+            |(_, event)| event.date,
+            policy,
+            now,
+        );
+        let mut keep = keep.into_iter();
+        let events = self.events.len();
+        self.events.retain(|_| keep.next().unwrap_or(true));
+
+        PruneReport {
+            snapshots: before - self.latest.len(),
+            events: events - self.events.len(),
+        }
+    }
+}
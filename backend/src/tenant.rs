@@ -0,0 +1,8 @@
+/// Tenant of single-customer deployments, also given to records written before
+/// multi-tenancy, so existing registries and logs keep loading
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Serde default of the `tenant_id` fields
+pub fn default_tenant() -> String {
+    DEFAULT_TENANT.to_string()
+}
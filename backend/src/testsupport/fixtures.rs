@@ -0,0 +1,203 @@
+use crate::models::parse::{ParseMode, ParseOptions};
+use crate::models::topology::Topology;
+use crate::models::vendor::VendorProfile;
+use crate::persist;
+use crate::{Error, Result};
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+/// Host the fixtures are parsed for, so the output does not depend on the machine
+const FIXTURE_HOST: &str = "fixture";
+
+/// A vendor payload sample, e.g. `tests/fixtures/ciena/otn_topology.json`
+///
+/// The first directory below the fixture root names the vendor, whose built-in
+/// [`VendorProfile`] is applied when parsing, if there is one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixture {
+    pub name: String, // Path below the fixture root without `.json`, e.g. `ciena/otn_topology`
+    pub vendor: Option<String>, // First directory of the name
+    pub value: Value,
+}
+
+/// Loads every `*.json` file below `root`, sorted by name
+///
+/// # Returns
+/// - `Ok(Vec<Fixture>)`: The fixtures, an empty list if `root` does not exist
+/// - `Err(Error)`: If a file cannot be read or is not valid JSON
+pub fn load_fixtures(root: impl AsRef<Path>) -> Result<Vec<Fixture>> {
+    let root = root.as_ref();
+    let mut paths = vec![];
+    if root.exists() {
+        collect_json(root, &mut paths)?;
+    }
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let content = fs::read_to_string(&path).map_err(|err| {
+                Error::Custom(format!(
+                    "Failed to read fixture {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+            let value = serde_json::from_str(&content).map_err(|err| {
+                Error::Custom(format!(
+                    "Invalid JSON in fixture {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+            let name = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .with_extension("")
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<String>>()
+                .join("/");
+            let vendor = name.split_once('/').map(|(vendor, _)| vendor.to_string());
+            Ok(Fixture {
+                name,
+                vendor,
+                value,
+            })
+        })
+        .collect()
+}
+
+/// Adds the JSON files below `directory` to `paths`
+fn collect_json(directory: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(directory).map_err(|err| {
+        Error::Custom(format!(
+            "Failed to read fixtures {}: {}",
+            directory.display(),
+            err
+        ))
+    })?;
+    for entry in entries {
+        let path = entry.map_err(Error::custom)?.path();
+        if path.is_dir() {
+            collect_json(&path, paths)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Parses a fixture and returns its normalized output
+///
+/// The fixture holds a `tapi-common:context`, a topology context or a single topology.
+/// Topologies are parsed leniently, so the skipped entries are part of the output. The
+/// output leaves out the collection dates and sorts nodes and links by UUID, so it only
+/// changes when the parsers do.
+pub fn parse_fixture(fixture: &Fixture) -> Result<Value> {
+    let profiles = VendorProfile::builtin();
+    let profile = fixture.vendor.as_ref().and_then(|vendor| {
+        profiles
+            .iter()
+            .find(|profile| profile.vendor.eq_ignore_ascii_case(vendor))
+    });
+    let options = ParseOptions {
+        mode: ParseMode::Lenient,
+        profile,
+        ..Default::default()
+    };
+
+    let context = fixture
+        .value
+        .get("tapi-common:context")
+        .unwrap_or(&fixture.value);
+    let topologies = match context.get("tapi-topology:topology-context") {
+        Some(_) => Topology::list_from_context(context)?,
+        None if context.get("topology").is_some() => Topology::list_from_context(context)?,
+        None => vec![context.clone()],
+    };
+
+    let mut output = vec![];
+    for value in &topologies {
+        let (topology, report) = Topology::parse(value, FIXTURE_HOST, &options)?;
+        let mut topology = serde_json::to_value(&topology).map_err(Error::custom)?;
+        for list in ["nodes", "links"] {
+            if let Some(Value::Array(objects)) = topology.get_mut(list) {
+                for object in objects.iter_mut() {
+                    if let Some(object) = object.as_object_mut() {
+                        object.remove("date");
+                    }
+                }
+                objects.sort_by(|left, right| {
+                    left["uuid"].to_string().cmp(&right["uuid"].to_string())
+                });
+            }
+        }
+        output.push(json!({"topology": topology, "issues": report.issues}));
+    }
+    Ok(Value::Array(output))
+}
+
+/// Compares `actual` with the golden file `<golden root>/<fixture name>.json`
+///
+/// With `UPDATE_GOLDEN=1` in the environment, the golden file is written instead, so
+/// a new sample only needs to be dropped in the fixture directory and reviewed.
+///
+/// # Returns
+/// - `Ok(())`: If the output matches, or the golden file was written
+/// - `Err(Error)`: If the golden file is missing or differs, with the first difference
+pub fn check_golden(
+    golden_root: impl AsRef<Path>,
+    fixture: &Fixture,
+    actual: &Value,
+) -> Result<()> {
+    let path = golden_root.as_ref().join(format!("{}.json", fixture.name));
+    let rendered = serde_json::to_string_pretty(actual).map_err(Error::custom)? + "\n";
+
+    if env::var("UPDATE_GOLDEN").is_ok_and(|update| update == "1") {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| {
+                Error::Custom(format!(
+                    "Failed to write golden file {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+        }
+        return persist::write_atomic(&path, rendered.as_bytes(), "golden file");
+    }
+
+    let expected = fs::read_to_string(&path).map_err(|_| {
+        Error::Custom(format!(
+            "Not found golden file {} of fixture {}, run with UPDATE_GOLDEN=1 to create it",
+            path.display(),
+            fixture.name
+        ))
+    })?;
+    if expected == rendered {
+        return Ok(());
+    }
+
+    let (line, (expected, actual)) = expected
+        .lines()
+        .chain(std::iter::repeat(""))
+        .zip(rendered.lines().chain(std::iter::repeat("")))
+        .enumerate()
+        .find(|(_, (expected, actual))| expected != actual)
+        .unwrap_or((0, ("", "")));
+    Err(Error::Custom(format!(
+        "Fixture {} differs from {} at line {}: expected `{}`, got `{}`; run with UPDATE_GOLDEN=1 to accept the new output",
+        fixture.name,
+        path.display(),
+        line + 1,
+        expected.trim(),
+        actual.trim()
+    )))
+}
@@ -0,0 +1,391 @@
+pub mod fixtures;
+
+use crate::{Error, Result};
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// Root of the RESTCONF data tree served by the mock device
+const DATA_ROOT: &str = "/restconf/data/";
+
+/// Document announcing the RESTCONF root
+const HOST_META_PATH: &str = "/.well-known/host-meta";
+
+/// Authentication the mock device requires
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockAuth {
+    None, // Every request is accepted
+    Basic {
+        username: String,
+        password: String,
+    },
+    Oauth2 {
+        token_path: String, // e.g. `/oauth/token`, the `auth_url` of the device
+        username: String,   // Client credentials, sent with Basic Authentication
+        password: String,
+        token: String, // Access token handed out
+    },
+    Custom {
+        login_path: String, // e.g. `/api/login`, the `auth_url` of the device
+        body: Value,        // Login body expected, the `auth_body` of the device
+        token: String,      // Returned as `{"token": ...}` and as the `session` cookie
+    },
+}
+
+/// Misbehavior injected into the answers of the mock device
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    Latency(Duration), // Every answer is delayed
+    ServerErrors(u32), // The next data requests fail with `500 Internal Server Error`
+    Malformed(String), // The field at this JSON pointer of the context is replaced with `false`
+}
+
+/// A request received by the mock device
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,                   // Path and query string
+    pub headers: Vec<(String, String)>, // Names in lowercase
+    pub body: String,
+}
+
+impl MockRequest {
+    /// Returns the value of header `name`, case insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.headers
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// State shared between the mock device and its connections
+struct State {
+    context: Value,
+    auth: MockAuth,
+    latency: Duration,
+    server_errors: u32,
+    requests: Vec<MockRequest>,
+}
+
+/// TAPI device serving a canned context over RESTCONF, for integration tests
+///
+/// The context is the content of `tapi-common:context`; any path below
+/// `/restconf/data/tapi-common:context` is answered with the matching subtree, e.g.
+/// [`TOPOLOGY_CONTEXT_PATH`](crate::models::topology::TOPOLOGY_CONTEXT_PATH). Requests
+/// must pass the configured [`MockAuth`], and [`Fault`]s make the device slow, failing
+/// or malformed, so the client and the collector can be tested without hardware.
+pub struct MockDevice {
+    address: SocketAddr,
+    state: Arc<Mutex<State>>,
+    task: JoinHandle<()>,
+}
+
+impl MockDevice {
+    /// Starts a mock device on a free local port
+    ///
+    /// # Arguments
+    /// - `context`: Content of `tapi-common:context`
+    /// - `auth`: Authentication required on data requests
+    pub async fn start(context: Value, auth: MockAuth) -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|err| Error::Custom(format!("Failed to bind mock device: {}", err)))?;
+        let address = listener.local_addr().map_err(Error::custom)?;
+        let state = Arc::new(Mutex::new(State {
+            context,
+            auth,
+            latency: Duration::ZERO,
+            server_errors: 0,
+            requests: vec![],
+        }));
+
+        let shared = Arc::clone(&state);
+        let task = tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(serve(socket, Arc::clone(&shared)));
+            }
+        });
+        Ok(MockDevice {
+            address,
+            state,
+            task,
+        })
+    }
+
+    /// Returns the base URL of the device, for `RestconfClient::with_base_url`
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Returns the address the device listens on
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Injects a fault into the following answers
+    pub fn inject(&self, fault: Fault) {
+        let mut state = self.state.lock().unwrap();
+        match fault {
+            Fault::Latency(latency) => state.latency = latency,
+            Fault::ServerErrors(count) => state.server_errors += count,
+            Fault::Malformed(pointer) => {
+                if let Some(field) = state.context.pointer_mut(&pointer) {
+                    // No TAPI field is a boolean, so any parser must reject it
+                    *field = Value::Bool(false);
+                }
+            }
+        }
+    }
+
+    /// Replaces the served context, e.g. to simulate a topology change
+    pub fn set_context(&self, context: Value) {
+        self.state.lock().unwrap().context = context;
+    }
+
+    /// Returns the requests received so far
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockDevice {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Answers the requests of a connection until it is closed
+async fn serve(mut socket: TcpStream, state: Arc<Mutex<State>>) {
+    let mut buffer = vec![];
+    while let Some(request) = read_request(&mut socket, &mut buffer).await {
+        let latency = state.lock().unwrap().latency;
+        tokio::time::sleep(latency).await;
+
+        let (status, headers, body) = {
+            let mut state = state.lock().unwrap();
+            state.requests.push(request.clone());
+            answer(&mut state, &request)
+        };
+        let mut response = format!(
+            "HTTP/1.1 {}\r\ncontent-type: application/yang-data+json\r\ncontent-length: {}\r\n",
+            status,
+            body.len()
+        );
+        for (name, value) in headers {
+            response.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        response.push_str("\r\n");
+        response.push_str(&body);
+        if socket.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Reads the next HTTP/1.1 request of the connection
+///
+/// Returns `None` once the connection is closed or sends something else than HTTP.
+async fn read_request(socket: &mut TcpStream, buffer: &mut Vec<u8>) -> Option<MockRequest> {
+    let mut chunk = [0; 4096];
+    let end = loop {
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end;
+        }
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length: usize = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let start = end + 4;
+    while buffer.len() < start + length {
+        let read = socket.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    let body = String::from_utf8_lossy(&buffer[start..start + length]).to_string();
+    buffer.drain(..start + length);
+
+    Some(MockRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+type Answer = (&'static str, Vec<(&'static str, String)>, String);
+
+/// Answers a request: logins, then authenticated data requests
+fn answer(state: &mut State, request: &MockRequest) -> Answer {
+    let path = request.path.split('?').next().unwrap_or_default();
+
+    match (&state.auth, request.method.as_str()) {
+        (
+            MockAuth::Oauth2 {
+                token_path,
+                username,
+                password,
+                token,
+            },
+            "POST",
+        ) if path == token_path => {
+            return if request.header("authorization") == Some(&basic(username, password)) {
+                let body = json!({"access_token": token, "token_type": "Bearer"});
+                ("200 OK", vec![], body.to_string())
+            } else {
+                error(
+                    "401 Unauthorized",
+                    "access-denied",
+                    "Invalid client credentials",
+                )
+            };
+        }
+        (
+            MockAuth::Custom {
+                login_path,
+                body,
+                token,
+            },
+            "POST",
+        ) if path == login_path => {
+            return if serde_json::from_str::<Value>(&request.body).ok().as_ref() == Some(body) {
+                let cookie = format!("session={}; Path=/; HttpOnly", token);
+                let body = json!({"token": token});
+                ("200 OK", vec![("set-cookie", cookie)], body.to_string())
+            } else {
+                error("401 Unauthorized", "access-denied", "Invalid login")
+            };
+        }
+        _ => {}
+    }
+
+    // RESTCONF root discovery (RFC 8040), answered to anyone
+    if request.method == "GET" && path == HOST_META_PATH {
+        let body = "<XRD xmlns='http://docs.oasis-open.org/ns/xri/xrd-1.0'>\n  <Link rel='restconf' href='/restconf'/>\n</XRD>";
+        return ("200 OK", vec![], body.to_string());
+    }
+    if !authorized(&state.auth, request) {
+        return error(
+            "401 Unauthorized",
+            "access-denied",
+            "Authentication required",
+        );
+    }
+    if request.method != "GET" {
+        return error(
+            "405 Method Not Allowed",
+            "operation-not-supported",
+            "Read only device",
+        );
+    }
+    if state.server_errors > 0 {
+        state.server_errors -= 1;
+        return ("500 Internal Server Error", vec![], String::new());
+    }
+
+    match path
+        .strip_prefix(DATA_ROOT)
+        .and_then(|path| subtree(&state.context, path))
+    {
+        Some(value) => ("200 OK", vec![], value.to_string()),
+        None => error("404 Not Found", "invalid-value", "Uri keypath not found"),
+    }
+}
+
+/// Returns whether `request` carries the credentials `auth` requires
+fn authorized(auth: &MockAuth, request: &MockRequest) -> bool {
+    let authorization = request.header("authorization");
+    match auth {
+        MockAuth::None => true,
+        MockAuth::Basic { username, password } => authorization == Some(&basic(username, password)),
+        MockAuth::Oauth2 { token, .. } => authorization == Some(&format!("Bearer {}", token)),
+        MockAuth::Custom { token, .. } => {
+            let cookie = format!("session={}", token);
+            authorization == Some(&format!("Bearer {}", token))
+                || request
+                    .header("cookie")
+                    .is_some_and(|cookies| cookies.split("; ").any(|value| value == cookie))
+        }
+    }
+}
+
+/// Returns the `Authorization` header value of Basic Authentication
+fn basic(username: &str, password: &str) -> String {
+    format!(
+        "Basic {}",
+        STANDARD.encode(format!("{}:{}", username, password))
+    )
+}
+
+/// Returns the RESTCONF answer for a data path below `/restconf/data/`
+///
+/// The path starts with `tapi-common:context`; every further segment is a field of the
+/// previous one, or `name=key` for the element of list `name` with UUID `key`. The
+/// target is wrapped in its last segment name, as RESTCONF does.
+fn subtree(context: &Value, path: &str) -> Option<Value> {
+    let mut segments: VecDeque<&str> = path.trim_end_matches('/').split('/').collect();
+    if segments.pop_front()? != "tapi-common:context" {
+        return None;
+    }
+
+    let mut name = "tapi-common:context";
+    let mut value = context;
+    for segment in segments {
+        value = match segment.split_once('=') {
+            Some((list, key)) => field(value, list)?
+                .as_array()?
+                .iter()
+                .find(|element| element.get("uuid").and_then(Value::as_str) == Some(key))?,
+            None => field(value, segment)?,
+        };
+        name = segment.split('=').next()?;
+    }
+    Some(json!({ name: value }))
+}
+
+/// Returns field `name` of an object, matching it with or without its module prefix
+fn field<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    value.get(name).or_else(|| {
+        let (_, local) = name.split_once(':')?;
+        value.get(local)
+    })
+}
+
+/// Returns a RESTCONF error envelope answer
+fn error(status: &'static str, tag: &str, message: &str) -> Answer {
+    let body = json!({
+        "ietf-restconf:errors": {
+            "error": [{"error-type": "protocol", "error-tag": tag, "error-message": message}]
+        }
+    });
+    (status, vec![], body.to_string())
+}
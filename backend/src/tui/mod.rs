@@ -0,0 +1,335 @@
+pub mod view;
+
+use crate::models::device::Device;
+use crate::models::topology::Topology;
+use crate::setup::config::Config;
+use crate::sync::Snapshot;
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
+use ratatui::crossterm::event::KeyCode;
+use tracing::warn;
+
+/// Health of a device as shown in the device panel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceStatus {
+    Healthy,     // Collected within three poll intervals
+    Stale,       // Last collection older than three poll intervals
+    NoData,      // Never collected
+    Maintenance, // Under maintenance, alerts suppressed
+    Disabled,    // Not polled
+}
+
+/// A device of the registry with its latest collection
+pub struct DeviceEntry {
+    pub device: Device,
+    pub status: DeviceStatus,
+    pub collected_at: Option<DateTime<Local>>,
+    pub topologies: Vec<Topology>,
+}
+
+/// Panel receiving the keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Panel {
+    Devices, // The device list
+    Objects, // The links or nodes of the selected device
+}
+
+/// Objects listed in the drill-down of a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObjectTab {
+    Links,
+    Nodes,
+}
+
+/// What the event loop must do after a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    None,
+    Reload, // Read the snapshots again
+    Quit,
+}
+
+/// A row of the drill-down table
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectRow {
+    pub uuid: String,
+    pub name: String,
+    pub lifecycle_state: String,
+    pub detail: String, // Endpoints of a link, edge point count of a node
+}
+
+/// State of `cli tui`: devices, selection, drill-down and search
+///
+/// The state is independent of the terminal: keys are applied with [`Browser::handle`]
+/// and [`view::render`] draws the current state, so navigation can be tested without
+/// a terminal.
+pub struct Browser {
+    pub devices: Vec<DeviceEntry>,
+    pub panel: Panel,
+    pub tab: ObjectTab,
+    pub search: String,  // Filters the focused panel, case insensitive
+    pub searching: bool, // Keys are typed into the search
+    selected: usize,     // Index in the filtered device list
+    selected_object: usize,
+    config: Config, // Poll intervals, to tell stale devices
+}
+
+impl Browser {
+    /// Creates a browser listing `devices`, none collected yet
+    pub fn new(devices: Vec<Device>, config: Config) -> Self {
+        let devices = devices
+            .into_iter()
+            .map(|device| DeviceEntry {
+                device,
+                status: DeviceStatus::NoData,
+                collected_at: None,
+                topologies: vec![],
+            })
+            .collect();
+        let mut browser = Browser {
+            devices,
+            panel: Panel::Devices,
+            tab: ObjectTab::Links,
+            search: String::new(),
+            searching: false,
+            selected: 0,
+            selected_object: 0,
+            config,
+        };
+        browser.refresh(Local::now());
+        browser
+    }
+
+    /// Keeps the latest snapshot of every device and updates the statuses
+    ///
+    /// Topologies that fail to parse are skipped with a warning.
+    pub fn load_snapshots(&mut self, snapshots: &[Snapshot], now: DateTime<Local>) {
+        let mut latest: HashMap<&str, &Snapshot> = HashMap::new();
+        for snapshot in snapshots {
+            match latest.get(snapshot.host.as_str()) {
+                Some(known) if known.collected_at >= snapshot.collected_at => {}
+                _ => {
+                    latest.insert(&snapshot.host, snapshot);
+                }
+            }
+        }
+
+        for entry in &mut self.devices {
+            let host = entry.device.host.to_string();
+            let Some(snapshot) = latest.get(host.as_str()) else {
+                continue;
+            };
+            if entry.collected_at == Some(snapshot.collected_at) {
+                continue;
+            }
+            entry.collected_at = Some(snapshot.collected_at);
+            entry.topologies = snapshot
+                .topologies
+                .iter()
+                .filter_map(|value| match Topology::from_value(value, &host) {
+                    Ok(topology) => Some(topology),
+                    Err(err) => {
+                        warn!("Skipped topology of {}: {}", host, err);
+                        None
+                    }
+                })
+                .collect();
+        }
+        self.refresh(now);
+    }
+
+    /// Updates the statuses, which depend on the time of the last collection
+    pub fn refresh(&mut self, now: DateTime<Local>) {
+        for entry in &mut self.devices {
+            let host = entry.device.host.to_string();
+            entry.status = if !self.config.enabled(&host, entry.device.enabled) {
+                DeviceStatus::Disabled
+            } else if entry.device.in_maintenance(now) {
+                DeviceStatus::Maintenance
+            } else {
+                match entry.collected_at {
+                    None => DeviceStatus::NoData,
+                    Some(collected_at) => {
+                        let stale_after = self.config.poll_interval(&host) * 3;
+                        match (now - collected_at).to_std() {
+                            Ok(age) if age > stale_after => DeviceStatus::Stale,
+                            _ => DeviceStatus::Healthy,
+                        }
+                    }
+                }
+            };
+        }
+    }
+
+    /// Returns the devices matching the search of the device panel
+    pub fn visible_devices(&self) -> Vec<&DeviceEntry> {
+        let search = match self.panel {
+            Panel::Devices => self.search.to_lowercase(),
+            Panel::Objects => String::new(),
+        };
+        self.devices
+            .iter()
+            .filter(|entry| {
+                search.is_empty()
+                    || entry
+                        .device
+                        .host
+                        .to_string()
+                        .to_lowercase()
+                        .contains(&search)
+                    || entry
+                        .device
+                        .tags
+                        .iter()
+                        .chain(entry.device.metadata.values())
+                        .any(|value| value.to_lowercase().contains(&search))
+            })
+            .collect()
+    }
+
+    /// Returns the selected device, if any matches the search
+    pub fn selected(&self) -> Option<&DeviceEntry> {
+        self.visible_devices().get(self.selected).copied()
+    }
+
+    /// Returns the index of the selected row of `panel`
+    pub fn selected_index(&self, panel: Panel) -> usize {
+        match panel {
+            Panel::Devices => self.selected,
+            Panel::Objects => self.selected_object,
+        }
+    }
+
+    /// Returns the rows of the drill-down matching the search of the object panel
+    pub fn rows(&self) -> Vec<ObjectRow> {
+        let Some(entry) = self.selected() else {
+            return vec![];
+        };
+        let state = |state: &Option<_>| match serde_json::to_value(state) {
+            Ok(serde_json::Value::String(state)) => state,
+            _ => String::new(),
+        };
+        let rows = entry
+            .topologies
+            .iter()
+            .flat_map(|topology| -> Vec<ObjectRow> {
+                match self.tab {
+                    ObjectTab::Links => topology
+                        .links
+                        .iter()
+                        .map(|link| ObjectRow {
+                            uuid: link.uuid.to_string(),
+                            name: link.name.clone().unwrap_or_default(),
+                            lifecycle_state: state(&link.lifecycle_state),
+                            detail: link
+                                .node_edge_points
+                                .iter()
+                                .map(|point| point.node_uuid.to_string())
+                                .collect::<Vec<String>>()
+                                .join(" <-> "),
+                        })
+                        .collect(),
+                    ObjectTab::Nodes => topology
+                        .nodes
+                        .iter()
+                        .map(|node| ObjectRow {
+                            uuid: node.uuid.to_string(),
+                            name: node.name.clone().unwrap_or_default(),
+                            lifecycle_state: state(&node.lifecycle_state),
+                            detail: format!("{} edge points", node.owned_node_edge_points.len()),
+                        })
+                        .collect(),
+                }
+            });
+
+        let search = match self.panel {
+            Panel::Objects => self.search.to_lowercase(),
+            Panel::Devices => String::new(),
+        };
+        rows.filter(|row| {
+            search.is_empty()
+                || row.uuid.contains(&search)
+                || row.name.to_lowercase().contains(&search)
+        })
+        .collect()
+    }
+
+    /// Applies a key
+    pub fn handle(&mut self, key: KeyCode) -> Action {
+        if self.searching {
+            match key {
+                KeyCode::Char(character) => self.search.push(character),
+                KeyCode::Backspace => {
+                    self.search.pop();
+                }
+                KeyCode::Esc => {
+                    self.search.clear();
+                    self.searching = false;
+                }
+                KeyCode::Enter => self.searching = false,
+                _ => {}
+            }
+            self.clamp();
+            return Action::None;
+        }
+
+        match key {
+            KeyCode::Char('q') => return Action::Quit,
+            KeyCode::Char('r') => return Action::Reload,
+            KeyCode::Char('/') => {
+                self.searching = true;
+                self.search.clear();
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l')
+                if self.panel == Panel::Devices && self.selected().is_some() =>
+            {
+                // The device search is kept as the selection, the drill-down starts unfiltered
+                let host = self.selected().map(|entry| entry.device.host.to_string());
+                self.search.clear();
+                self.selected = self
+                    .visible_devices()
+                    .iter()
+                    .position(|entry| Some(entry.device.host.to_string()) == host)
+                    .unwrap_or_default();
+                self.panel = Panel::Objects;
+                self.selected_object = 0;
+            }
+            KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') if self.panel == Panel::Objects => {
+                self.panel = Panel::Devices;
+                self.search.clear();
+            }
+            KeyCode::Esc => self.search.clear(),
+            KeyCode::Tab if self.panel == Panel::Objects => {
+                self.tab = match self.tab {
+                    ObjectTab::Links => ObjectTab::Nodes,
+                    ObjectTab::Nodes => ObjectTab::Links,
+                };
+                self.selected_object = 0;
+            }
+            _ => {}
+        }
+        self.clamp();
+        Action::None
+    }
+
+    /// Moves the selection of the focused panel by `step` rows
+    fn move_selection(&mut self, step: isize) {
+        let selected = match self.panel {
+            Panel::Devices => &mut self.selected,
+            Panel::Objects => &mut self.selected_object,
+        };
+        *selected = selected.saturating_add_signed(step);
+    }
+
+    /// Keeps the selections within the filtered rows
+    fn clamp(&mut self) {
+        let devices = self.visible_devices().len();
+        self.selected = self.selected.min(devices.saturating_sub(1));
+        let rows = self.rows().len();
+        self.selected_object = self.selected_object.min(rows.saturating_sub(1));
+    }
+}
@@ -0,0 +1,183 @@
+use super::{Browser, DeviceStatus, ObjectTab, Panel};
+
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{
+    Block, Borders, List, ListItem, ListState, Paragraph, Row, Table, TableState, Tabs,
+};
+use ratatui::Frame;
+
+/// Keys listed in the footer
+const HELP: &str = "↑/↓ move  enter open  esc back  tab links/nodes  / search  r reload  q quit";
+
+impl DeviceStatus {
+    /// Returns the label and color of the status
+    fn label(&self) -> (&'static str, Color) {
+        match self {
+            DeviceStatus::Healthy => ("healthy", Color::Green),
+            DeviceStatus::Stale => ("stale", Color::Yellow),
+            DeviceStatus::NoData => ("no data", Color::Red),
+            DeviceStatus::Maintenance => ("maintenance", Color::Blue),
+            DeviceStatus::Disabled => ("disabled", Color::DarkGray),
+        }
+    }
+}
+
+/// Draws the device panel, the drill-down of the selected device and the footer
+pub fn render(frame: &mut Frame, browser: &Browser) {
+    let [main, footer] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(1)]).areas(frame.area());
+    let [devices, objects] =
+        Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)]).areas(main);
+    let focused = |panel: Panel| {
+        if browser.panel == panel {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        }
+    };
+    let highlight = Style::default().add_modifier(Modifier::REVERSED);
+
+    // Device panel
+    let items: Vec<ListItem> = browser
+        .visible_devices()
+        .iter()
+        .map(|entry| {
+            let (label, color) = entry.status.label();
+            ListItem::new(Line::from(vec![
+                Span::styled("● ", Style::default().fg(color)),
+                Span::raw(entry.device.host.to_string()),
+                Span::styled(format!("  {}", label), Style::default().fg(color)),
+            ]))
+        })
+        .collect();
+    let mut state =
+        ListState::default().with_selected(Some(browser.selected_index(Panel::Devices)));
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(focused(Panel::Devices))
+                .title(format!(" Devices ({}) ", browser.devices.len())),
+        )
+        .highlight_style(highlight);
+    frame.render_stateful_widget(list, devices, &mut state);
+
+    // Drill-down of the selected device
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(focused(Panel::Objects));
+    let Some(entry) = browser.selected() else {
+        frame.render_widget(
+            Paragraph::new("No device matches the search").block(block),
+            objects,
+        );
+        render_footer(frame, footer, browser);
+        return;
+    };
+    let inner = block.inner(objects);
+    frame.render_widget(block.title(format!(" {} ", entry.device.host)), objects);
+    let [summary, tabs, table] = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Length(1),
+        Constraint::Min(1),
+    ])
+    .areas(inner);
+
+    let (label, color) = entry.status.label();
+    let collected_at = entry
+        .collected_at
+        .map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "never".to_string());
+    let (nodes, links) = entry
+        .topologies
+        .iter()
+        .fold((0, 0), |(nodes, links), topology| {
+            (nodes + topology.nodes.len(), links + topology.links.len())
+        });
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(vec![
+                Span::raw("Status: "),
+                Span::styled(label, Style::default().fg(color)),
+                Span::raw(format!("   Last collection: {}", collected_at)),
+            ]),
+            Line::from(format!(
+                "{} topologies, {} nodes, {} links   Tags: {}",
+                entry.topologies.len(),
+                nodes,
+                links,
+                entry.device.tags.join(", ")
+            )),
+        ]),
+        summary,
+    );
+
+    let selected_tab = match browser.tab {
+        ObjectTab::Links => 0,
+        ObjectTab::Nodes => 1,
+    };
+    frame.render_widget(
+        Tabs::new(["Links", "Nodes"])
+            .select(selected_tab)
+            .highlight_style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        tabs,
+    );
+
+    let rows = browser.rows();
+    let detail = match browser.tab {
+        ObjectTab::Links => "ENDPOINT NODES",
+        ObjectTab::Nodes => "EDGE POINTS",
+    };
+    let table_rows: Vec<Row> = rows
+        .into_iter()
+        .map(|row| Row::new([row.uuid, row.name, row.lifecycle_state, row.detail]))
+        .collect();
+    let mut state = TableState::default();
+    if browser.panel == Panel::Objects {
+        state.select(Some(browser.selected_index(Panel::Objects)));
+    }
+    frame.render_stateful_widget(
+        Table::new(
+            table_rows,
+            [
+                Constraint::Length(36),
+                Constraint::Percentage(25),
+                Constraint::Length(20),
+                Constraint::Min(10),
+            ],
+        )
+        .header(
+            Row::new(["UUID", "NAME", "LIFECYCLE", detail])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(highlight),
+        table,
+        &mut state,
+    );
+
+    render_footer(frame, footer, browser);
+}
+
+/// Draws the search prompt while searching, the key help otherwise
+fn render_footer(frame: &mut Frame, area: ratatui::layout::Rect, browser: &Browser) {
+    let line = if browser.searching {
+        Line::from(format!("/{}", browser.search))
+    } else if !browser.search.is_empty() {
+        Line::from(format!(
+            "Search: {}   (esc to clear)   {}",
+            browser.search, HELP
+        ))
+    } else {
+        Line::from(HELP)
+    };
+    frame.render_widget(
+        Paragraph::new(line).style(Style::default().fg(Color::Gray)),
+        area,
+    );
+}
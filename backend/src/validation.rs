@@ -0,0 +1,163 @@
+use crate::models::direction::Direction;
+use crate::models::{link::Link, topology::Topology};
+use crate::schema::TapiVersion;
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Consistency check run on every collected snapshot
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    UnknownNode,   // A link endpoint names a node missing from its topology
+    MissingMate,   // A unidirectional link has no link in the opposite direction
+    DuplicateLink, // The link UUID is also reported by another device
+}
+
+impl Rule {
+    /// Every rule, in a stable order
+    pub const ALL: [Rule; 3] = [Rule::UnknownNode, Rule::MissingMate, Rule::DuplicateLink];
+}
+
+/// Settings of the topology validation, the `[validation]` section of `config.toml`
+///
+/// Every rule runs unless the list is set. Raw payloads are only checked against the
+/// bundled TAPI schema when `schema` names a release.
+///
+/// ```toml
+/// [validation]
+/// rules = ["unknown_node", "missing_mate"]
+/// schema = "2.1.3"
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ValidationConfig {
+    #[serde(default = "default_rules")]
+    pub rules: Vec<Rule>, // Rules run on every snapshot, none disables the validation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<TapiVersion>, // TAPI release the payloads are checked against
+}
+
+fn default_rules() -> Vec<Rule> {
+    Rule::ALL.to_vec()
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        ValidationConfig {
+            rules: default_rules(),
+            schema: None,
+        }
+    }
+}
+
+/// A link breaking a rule, an entry of `GET /topology/{host}/violations`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub rule: Rule,
+    pub host: String,
+    pub topology_uuid: Uuid,
+    pub link_uuid: Uuid,
+    pub detail: String,
+}
+
+/// Runs `rules` over the last topologies of `host`
+///
+/// # Arguments
+/// - `host`: The device whose topologies are checked
+/// - `snapshots`: The last topologies of every device, `host` included, to find link
+///   UUIDs reported twice
+/// - `rules`: The rules to run
+///
+/// # Returns
+/// - `Vec<Violation>`: The violations, by topology and link, none if `host` was never
+///   collected
+pub fn validate<T: AsRef<[Topology]>>(
+    host: &str,
+    snapshots: &HashMap<String, T>,
+    rules: &[Rule],
+) -> Vec<Violation> {
+    let Some(topologies) = snapshots.get(host).map(AsRef::as_ref) else {
+        return vec![];
+    };
+    let nodes: HashMap<Uuid, HashSet<Uuid>> = topologies
+        .iter()
+        .map(|topology| {
+            let nodes = topology.nodes.iter().map(|node| node.uuid).collect();
+            (topology.uuid, nodes)
+        })
+        .collect();
+    let links: Vec<&Link> = topologies
+        .iter()
+        .flat_map(|topology| &topology.links)
+        .collect();
+    // Devices reporting each link UUID, the checked host left out
+    let mut elsewhere: HashMap<Uuid, Vec<&str>> = HashMap::new();
+    if rules.contains(&Rule::DuplicateLink) {
+        for (other, topologies) in snapshots.iter().filter(|(other, _)| *other != host) {
+            for link in topologies
+                .as_ref()
+                .iter()
+                .flat_map(|topology| &topology.links)
+            {
+                elsewhere.entry(link.uuid).or_default().push(other);
+            }
+        }
+    }
+
+    let mut violations = vec![];
+    for topology in topologies {
+        for link in &topology.links {
+            let mut violation = |rule: Rule, detail: String| {
+                violations.push(Violation {
+                    rule,
+                    host: host.to_string(),
+                    topology_uuid: topology.uuid,
+                    link_uuid: link.uuid,
+                    detail,
+                })
+            };
+
+            if rules.contains(&Rule::UnknownNode) {
+                for endpoint in &link.node_edge_points {
+                    let owner = endpoint.topology_uuid.unwrap_or(topology.uuid);
+                    // Endpoints in topologies of other devices are the stitching's concern
+                    let Some(known) = nodes.get(&owner) else {
+                        continue;
+                    };
+                    if !known.contains(&endpoint.node_uuid) {
+                        violation(
+                            Rule::UnknownNode,
+                            format!(
+                                "Not found node {} in topology {}",
+                                endpoint.node_uuid, owner
+                            ),
+                        );
+                    }
+                }
+            }
+
+            if rules.contains(&Rule::MissingMate)
+                && link.direction == Some(Direction::Unidirectional)
+                && !links.iter().any(|mate| link.is_reversed_by(mate))
+            {
+                violation(
+                    Rule::MissingMate,
+                    "Not found link in the opposite direction".to_string(),
+                );
+            }
+
+            if let Some(hosts) = elsewhere.get(&link.uuid) {
+                let mut hosts = hosts.clone();
+                hosts.sort();
+                hosts.dedup();
+                violation(
+                    Rule::DuplicateLink,
+                    format!("Link uuid also reported by {}", hosts.join(", ")),
+                );
+            }
+        }
+    }
+    violations
+}
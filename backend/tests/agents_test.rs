@@ -0,0 +1,307 @@
+use backend::agents::AgentRegistry; // Import the edge agent registry
+use backend::sync::Snapshot;
+use backend::tenant::DEFAULT_TENANT;
+use backend::Error; // Import the custom error type from the backend module
+use chrono::{Duration, Local};
+use serde_json::json;
+use uuid::Uuid;
+
+/// # Test: `test_agent_enrollment`
+///
+/// This test verifies that an enrollment token registers an agent only once, and that
+/// the agent can then push topologies with its credentials.
+#[test]
+fn test_agent_enrollment() {
+    let mut registry = AgentRegistry::new();
+    let token = registry
+        .issue_token(DEFAULT_TENANT, "Madrid edge", Duration::minutes(10))
+        .unwrap();
+
+    let credentials = registry.enroll(&token, "edge-mad01").unwrap();
+    match registry.enroll(&token, "edge-mad01") {
+        Err(Error::Unauthorized(msg)) => assert_eq!(msg, "Invalid enrollment token".to_string()),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but got Ok"),
+    }
+
+    let value = json!({"uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb", "node": [{"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"}]});
+    let (tenant_id, topology) = registry
+        .ingest(
+            &credentials.agent_id,
+            &credentials.secret,
+            "10.95.87.21",
+            &value,
+        )
+        .unwrap();
+    assert_eq!(tenant_id, DEFAULT_TENANT);
+    assert_eq!(topology.host, "10.95.87.21");
+    assert!(registry.agents(DEFAULT_TENANT)[0].last_seen.is_some());
+
+    assert!(registry
+        .ingest(&credentials.agent_id, "wrong-secret", "10.95.87.21", &value)
+        .is_err());
+    assert!(registry.revoke(&credentials.agent_id).unwrap().is_some());
+    assert!(registry
+        .authenticate(&credentials.agent_id, &credentials.secret)
+        .is_err());
+}
+
+/// # Test: `test_expired_enrollment_token`
+///
+/// This test checks that an expired enrollment token is rejected.
+#[test]
+fn test_expired_enrollment_token() {
+    let mut registry = AgentRegistry::new();
+    let token = registry
+        .issue_token(DEFAULT_TENANT, "Barcelona edge", Duration::seconds(-1))
+        .unwrap();
+
+    match registry.enroll(&token, "edge-bcn01") {
+        Err(Error::Unauthorized(msg)) => {
+            assert_eq!(msg, "Expired enrollment token Barcelona edge".to_string())
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but got Ok"),
+    }
+    assert!(registry.agents(DEFAULT_TENANT).is_empty());
+}
+
+/// # Test: `test_persisted_agent_registry`
+///
+/// This test verifies that enrolled agents, their credentials and pending tokens survive
+/// a reopening of the registry file, and that redeemed or revoked ones do not.
+#[test]
+fn test_persisted_agent_registry() {
+    let path = std::env::temp_dir().join(format!("agents_test_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut registry = AgentRegistry::open(&path).unwrap();
+    let redeemed = registry
+        .issue_token("acme", "Madrid edge", Duration::minutes(10))
+        .unwrap();
+    let pending = registry
+        .issue_token("acme", "Barcelona edge", Duration::minutes(10))
+        .unwrap();
+    let credentials = registry.enroll(&redeemed, "edge-mad01").unwrap();
+    let token = registry
+        .issue_token("acme", "Valencia edge", Duration::minutes(10))
+        .unwrap();
+    let revoked = registry.enroll(&token, "edge-vlc01").unwrap();
+    registry.revoke(&revoked.agent_id).unwrap();
+    drop(registry);
+    // Only hashes are written
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(!content.contains(&credentials.secret) && !content.contains(&pending));
+
+    let mut registry = AgentRegistry::open(&path).unwrap();
+    assert_eq!(registry.agents("acme").len(), 1);
+    assert_eq!(registry.agents("acme")[0].name, "edge-mad01");
+    assert!(registry
+        .authenticate(&credentials.agent_id, &credentials.secret)
+        .is_ok());
+    assert!(registry
+        .authenticate(&revoked.agent_id, &revoked.secret)
+        .is_err());
+    assert!(registry.enroll(&redeemed, "edge-mad02").is_err());
+    registry.enroll(&pending, "edge-bcn01").unwrap();
+    assert_eq!(AgentRegistry::open(&path).unwrap().agents("acme").len(), 2);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// A `cli serve` process answering HTTP on a free local port, killed when dropped
+struct Server {
+    child: std::process::Child,
+    url: String,
+}
+
+impl Server {
+    /// Starts `cli serve` over the files of `directory` and waits until it answers
+    async fn start(directory: &std::path::Path) -> Self {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let child = std::process::Command::new(env!("CARGO_BIN_EXE_cli"))
+            .arg("serve")
+            .arg("--config")
+            .arg(directory.join("config.toml"))
+            .arg("--devices")
+            .arg(directory.join("devices.json"))
+            .arg("--agents")
+            .arg(directory.join("agents.json"))
+            .arg("--snapshots")
+            .arg(directory.join("snapshots.jsonl"))
+            .arg("--http")
+            .arg(format!("127.0.0.1:{}", port))
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let server = Server {
+            child,
+            url: format!("http://127.0.0.1:{}", port),
+        };
+        for _ in 0..200 {
+            if reqwest::get(format!("{}/ready", server.url)).await.is_ok() {
+                return server;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        panic!("cli serve did not start");
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// # Test: `test_agent_snapshots_served`
+///
+/// This test verifies against `cli serve` that an agent enrolled over HTTP pushes its
+/// snapshots to `POST /agents/{id}/snapshots`, that retries and late snapshots are
+/// accepted without replacing the latest one, that bad credentials and unreadable
+/// topologies are refused, and that the agent stays enrolled across a restart. Merged
+/// snapshots are appended to the history, which `cli topology diff` reads.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_agent_snapshots_served() {
+    let directory = std::env::temp_dir().join(format!("agents_served_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(
+        directory.join("config.toml"),
+        format!(
+            "[[api.tokens]]\ntenant_id = \"acme\"\nname = \"ops\"\nsha256 = \"{}\"\n",
+            backend::api::auth::hash("ops-token")
+        ),
+    )
+    .unwrap();
+    std::fs::write(directory.join("devices.json"), "[]").unwrap();
+    let client = reqwest::Client::new();
+
+    let server = Server::start(&directory).await;
+    let token: serde_json::Value = client
+        .post(format!("{}/agents/tokens", server.url))
+        .bearer_auth("ops-token")
+        .json(&json!({"label": "Madrid edge"}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let credentials: serde_json::Value = client
+        .post(format!("{}/agents/enroll", server.url))
+        .json(&json!({"token": token["token"], "name": "edge-mad01"}))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let agent_id = credentials["agent_id"].as_str().unwrap().to_string();
+    let secret = credentials["secret"].as_str().unwrap().to_string();
+
+    let snapshot = |sequence: u64, age: i64, topology: serde_json::Value| Snapshot {
+        agent_id: agent_id.parse().unwrap(),
+        sequence,
+        host: "10.95.87.21".to_string(),
+        collected_at: Local::now() - Duration::minutes(age),
+        topologies: vec![topology],
+        events: vec![],
+    };
+    let topology = json!({"uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb"});
+    let push = |url: &str, secret: &str, snapshot: &Snapshot| {
+        client
+            .post(format!("{}/agents/{}/snapshots", url, agent_id))
+            .basic_auth(&agent_id, Some(secret))
+            .json(snapshot)
+            .send()
+    };
+
+    let latest = snapshot(2, 0, topology.clone());
+    for (snapshot, outcome) in [
+        (&latest, "applied"),
+        (&latest, "duplicate"),
+        (&snapshot(1, 5, topology.clone()), "stale"),
+    ] {
+        let response = push(&server.url, &secret, snapshot).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["outcome"], outcome);
+    }
+    let response = push(
+        &server.url,
+        "wrong-secret",
+        &snapshot(3, 0, topology.clone()),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 401);
+    let response = push(&server.url, &secret, &snapshot(3, 0, json!({"node": []})))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let agents: Vec<serde_json::Value> = client
+        .get(format!("{}/agents", server.url))
+        .bearer_auth("ops-token")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(agents[0]["last_seen"].is_string());
+    drop(server);
+
+    // Retries and refused snapshots are not in the history
+    let history = directory.join("snapshots.jsonl");
+    let sequences = || -> Vec<u64> {
+        std::fs::read_to_string(&history)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<Snapshot>(line).unwrap().sequence)
+            .collect()
+    };
+    assert_eq!(sequences(), [2, 1]);
+
+    let server = Server::start(&directory).await;
+    let linked = json!({
+        "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+        "node": [{"uuid": Uuid::from_u128(2)}, {"uuid": Uuid::from_u128(3)}],
+        "link": [{
+            "uuid": Uuid::from_u128(4),
+            "node-edge-point": [
+                {"node-uuid": Uuid::from_u128(2), "node-edge-point-uuid": Uuid::from_u128(5)},
+                {"node-uuid": Uuid::from_u128(3), "node-edge-point-uuid": Uuid::from_u128(6)}
+            ]
+        }]
+    });
+    let response = push(&server.url, &secret, &snapshot(4, 0, linked))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    drop(server);
+    assert_eq!(sequences(), [2, 1, 4]);
+
+    let diff = std::process::Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(["topology", "diff", "10.95.87.21", "--json", "--snapshots"])
+        .arg(&history)
+        .output()
+        .unwrap();
+    assert!(
+        diff.status.success(),
+        "{}",
+        String::from_utf8_lossy(&diff.stderr)
+    );
+    let diff: serde_json::Value = serde_json::from_slice(&diff.stdout).unwrap();
+    assert_eq!(diff["added"].as_array().unwrap().len(), 1);
+    assert_eq!(diff["removed"], json!([]));
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
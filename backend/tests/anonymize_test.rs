@@ -0,0 +1,68 @@
+use backend::enrichment::{enrich, SiteMapping}; // Import the enrichment stage
+use backend::export::anonymize::{Anonymizer, PseudonymMapping}; // Import the anonymizer
+use backend::models::{site::Site, topology::Topology}; // Import the topology models
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Parses a topology with two named nodes, placed at a site
+fn topology() -> Topology {
+    let value = json!({
+        "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+        "node": [
+            {"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "name": [{"value-name": "NODE_NAME", "value": "ACME-Madrid-core"}]},
+            {"uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7", "name": [{"value-name": "NODE_NAME", "value": "ACME-Madrid-edge"}]}
+        ]
+    });
+    let mut topology = Topology::from_value(&value, "core.acme.example").unwrap();
+    let mapping = SiteMapping {
+        sites: HashMap::from([(
+            "MAD01".to_string(),
+            Site {
+                id: "MAD01".to_string(),
+                name: Some("ACME Madrid".to_string()),
+                latitude: 40.41,
+                longitude: -3.70,
+            },
+        )]),
+        hosts: HashMap::from([("core.acme.example".to_string(), "MAD01".to_string())]),
+        nodes: HashMap::new(),
+    };
+    enrich(&mut topology, &mapping);
+    topology
+}
+
+/// # Test: `test_anonymize_topology`
+///
+/// This test verifies that hostnames, names and sites are replaced by consistent
+/// pseudonyms, that the structure is preserved, and that the mapping recovers them.
+#[test]
+fn test_anonymize_topology() {
+    let mut anonymizer = Anonymizer::new("secret-key", PseudonymMapping::default());
+    let mut first = topology();
+    anonymizer.anonymize(&mut first);
+
+    let serialized = serde_json::to_string(&first).unwrap();
+    assert!(!serialized.contains("ACME"));
+    assert!(!serialized.contains("acme"));
+    assert!(first.host.starts_with("host-"));
+    assert_eq!(first.nodes.len(), 2);
+    assert_eq!(first.nodes[0].site.as_ref().unwrap().latitude, 40.41);
+
+    // The same key gives the same pseudonyms
+    let mut second = topology();
+    Anonymizer::new("secret-key", PseudonymMapping::default()).anonymize(&mut second);
+    assert_eq!(first.host, second.host);
+    assert_eq!(first.nodes[1].name, second.nodes[1].name);
+
+    // Another key gives other pseudonyms
+    let mut third = topology();
+    Anonymizer::new("other-key", PseudonymMapping::default()).anonymize(&mut third);
+    assert_ne!(first.host, third.host);
+
+    let mapping = anonymizer.mapping();
+    assert_eq!(mapping.original(&first.host), Some("core.acme.example"));
+    assert_eq!(
+        mapping.original(first.nodes[0].name.as_ref().unwrap()),
+        Some("ACME-Madrid-core")
+    );
+}
@@ -0,0 +1,115 @@
+use backend::api::codes::ErrorCode;
+use backend::api::error::ApiError; // Import the API error responses
+use backend::api::request::RequestId;
+use backend::models::address::Host;
+use backend::models::device::Device;
+use backend::models::link::Link;
+use backend::Error;
+use serde_json::{json, to_value};
+
+/// # Test: `test_api_error_mapping`
+///
+/// This test verifies that library errors are answered with the code and HTTP status of
+/// their kind instead of a generic 500, whatever their message.
+#[test]
+fn test_api_error_mapping() {
+    let cases = [
+        (
+            Error::InvalidCursor("Invalid cursor signature".to_string()),
+            ErrorCode::InvalidCursor,
+            400,
+        ),
+        (
+            Error::Unauthorized("Invalid agent credentials".to_string()),
+            ErrorCode::Unauthorized,
+            401,
+        ),
+        (
+            Error::NotFound("Not found stats of 10.0.0.1".to_string()),
+            ErrorCode::DeviceNotFound,
+            404,
+        ),
+        (
+            Error::TopologyNotFound("Not found topology of 10.0.0.1".to_string()),
+            ErrorCode::TopologyNotFound,
+            404,
+        ),
+        (
+            Error::Unreachable("Device unreachable: connection refused".to_string()),
+            ErrorCode::DeviceUnreachable,
+            502,
+        ),
+        (
+            Error::BadResponse("Device answered with status 500".to_string()),
+            ErrorCode::DeviceBadResponse,
+            502,
+        ),
+        (
+            Error::Timeout("Device timeout: no SNMP answer from 10.0.0.1".to_string()),
+            ErrorCode::DeviceTimeout,
+            504,
+        ),
+        (
+            Error::Invalid("Invalid Last-Event-ID abc".to_string()),
+            ErrorCode::InvalidRequest,
+            400,
+        ),
+        // The message no longer decides the code
+        (
+            Error::from("Not found events events.jsonl"),
+            ErrorCode::Internal,
+            500,
+        ),
+        (
+            Error::from("Failed to write events events.jsonl: disk full"),
+            ErrorCode::Internal,
+            500,
+        ),
+    ];
+    for (error, code, status) in cases {
+        let message = error.to_string();
+        let error = ApiError::from(error);
+        assert_eq!((error.code, error.status()), (code, status), "{}", message);
+        assert_eq!(error.message, message);
+    }
+
+    assert_eq!(ApiError::device_conflict("10.0.0.1").status(), 409);
+
+    // Input and payload errors are raised with the variant of their kind
+    let invalid = ApiError::from("10.0.0.1:0".parse::<Host>().unwrap_err());
+    assert_eq!(invalid.code, ErrorCode::InvalidRequest);
+    let malformed = ApiError::from(Link::from_value(&json!({}), "10.0.0.1").unwrap_err());
+    assert_eq!(
+        (malformed.code, malformed.message.as_str()),
+        (ErrorCode::DeviceBadResponse, "Not found link uuid")
+    );
+}
+
+/// # Test: `test_api_error_body`
+///
+/// This test checks the body of a validation error, with the field errors as details and
+/// the request id.
+#[test]
+fn test_api_error_body() {
+    let errors = Device::validate(&json!({"host": "10.0.0.1"}));
+    let request_id = RequestId::from_header(Some("req-1"));
+
+    assert_eq!(
+        to_value(ApiError::validation(&errors).body(&request_id)).unwrap(),
+        json!({
+            "code": "VALIDATION_FAILED",
+            "message": "Validation failed",
+            "details": [{"field": "auth", "message": "Auth body not found"}],
+            "request_id": "req-1"
+        })
+    );
+
+    assert_eq!(
+        to_value(ApiError::device_not_found("10.0.0.2").body(&request_id)).unwrap(),
+        json!({
+            "code": "DEVICE_NOT_FOUND",
+            "message": "Not found device 10.0.0.2",
+            "request_id": "req-1"
+        })
+    );
+}
@@ -0,0 +1,647 @@
+use backend::actions::{BulkAction, BulkReport, BulkRequest}; // Import the bulk actions
+use backend::audit::{AuditAction, AuditQuery}; // Import the audit log queries
+use backend::ingest::ObjectStore; // Import the object journal read after a restart
+use backend::jobs::{Job, JobStatus}; // Import the job statuses
+use backend::maintenance::{MaintenanceTarget, MaintenanceWindow}; // Import the maintenance windows
+use backend::models::topology::Topology; // Import the topology model
+use backend::notifications::routing::Sink; // Import the notification sinks
+use backend::setup::app::{App, AppHooks, AppSettings, CollectorPhase}; // Import the application orchestration
+use backend::setup::log_setup::{logging_init_stderr, TelemetryConfig}; // Import the logging setup
+use backend::tenant::DEFAULT_TENANT; // Import the tenant of the devices without one
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::Value;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Returns a fresh directory holding the files of a test
+fn app_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("app_test_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Waits until a job of the application is finished
+async fn finished(app: &App, id: &Uuid) -> Job {
+    for _ in 0..500 {
+        let job = app.job(id).unwrap();
+        if matches!(job.status, JobStatus::Done | JobStatus::Failed) {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("Job {} did not finish", id);
+}
+
+/// # Test: `test_app_start`
+///
+/// This test verifies that the application reports invalid startup files, and that it
+/// starts a collector per enabled device next to the notification, retention and
+/// latency tasks, which the scheduler state reports, with the notification spools next
+/// to the config.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_app_start() {
+    let directory = app_directory("start");
+    let settings = AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        events: Some(directory.join("events.jsonl")),
+        objects: Some(directory.join("objects.jsonl")),
+        candidates: None,
+        latency: None,
+        ..Default::default()
+    };
+    std::fs::write(&settings.config, "poll_interval = 60\n").unwrap();
+
+    // The device file is missing
+    match App::start(&settings) {
+        Err(Error::Custom(msg)) => assert!(msg.starts_with("Failed to read"), "{}", msg),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but the app started"),
+    }
+
+    std::fs::write(&settings.devices, r#"{"host": "127.0.0.1"}"#).unwrap();
+    match App::start(&settings) {
+        Err(Error::Custom(msg)) => {
+            assert_eq!(msg, "Device file must hold an array of devices")
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but the app started"),
+    }
+
+    std::fs::write(
+        &settings.devices,
+        r#"[
+            {"host": "127.0.0.1", "auth": {"username": "tapi", "password": "secret"}},
+            {"host": "127.0.0.2", "auth": {"username": "tapi", "password": "secret"}, "enabled": false}
+        ]"#,
+    )
+    .unwrap();
+    let app = App::start(&settings).expect("App cannot be started");
+    let names: Vec<String> = app.health().into_iter().map(|task| task.name).collect();
+    assert_eq!(
+        names,
+        [
+            "collector:127.0.0.1",
+            "compaction",
+            "latency",
+            "notifications",
+            "retention"
+        ]
+    );
+    assert_eq!(app.events(), 0);
+
+    // IP literals are polled at their own address, disabled devices have no collector
+    let devices = app.device_health();
+    assert_eq!(
+        devices[0].address.map(|address| address.to_string()),
+        Some("127.0.0.1".to_string())
+    );
+    assert!(devices[0].collector.is_some());
+    assert_eq!(devices[1].host, "127.0.0.2");
+    assert_eq!(devices[1].collector, None);
+
+    // One collector for the enabled device, the notifications consume the event bus
+    let scheduler = app.scheduler();
+    assert_eq!(scheduler.collectors, 1);
+    assert_eq!(scheduler.devices.len(), 2);
+    assert_eq!(scheduler.devices[1].phase, CollectorPhase::Disabled);
+    assert_eq!(scheduler.devices[1].next_run, None);
+    let queues: Vec<&str> = scheduler
+        .queues
+        .bus
+        .iter()
+        .map(|queue| queue.name.as_str())
+        .collect();
+    assert_eq!(queues, ["notifications"]);
+    let spools: Vec<Sink> = scheduler.queues.spools.keys().copied().collect();
+    assert_eq!(spools, [Sink::Email, Sink::Slack, Sink::Teams]);
+    // Without a spool directory, the spools are kept next to the config
+    assert!(directory.join("spool").is_dir());
+
+    // Nothing answers on 127.0.0.1, so no topology was validated
+    match app.violations("127.0.0.1") {
+        Err(Error::TopologyNotFound(msg)) => assert_eq!(msg, "Not found topology of 127.0.0.1"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    app.shutdown();
+}
+
+/// # Test: `test_app_collection_files`
+///
+/// This test verifies that the site mapping and the capture directory are read at
+/// startup, and that no statistics are served before a first collection.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_app_collection_files() {
+    let directory = app_directory("collection_files");
+    let settings = AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        sites: Some(directory.join("sites.json")),
+        captures: Some(directory.join("captures")),
+        ..Default::default()
+    };
+    std::fs::write(&settings.config, "poll_interval = 60\n").unwrap();
+    std::fs::write(&settings.devices, "[]").unwrap();
+
+    match App::start(&settings) {
+        Err(Error::Custom(msg)) => {
+            assert!(msg.starts_with("Failed to read site mapping"), "{}", msg)
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but the app started"),
+    }
+
+    std::fs::write(directory.join("sites.json"), r#"{"hosts": {}}"#).unwrap();
+    let app = App::start(&settings).expect("App cannot be started");
+    assert!(directory.join("captures").is_dir());
+    match app.stats("127.0.0.1", |stats| stats.cycles.len()) {
+        Err(Error::NotFound(msg)) => assert_eq!(msg, "Not found stats of 127.0.0.1"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    assert!(!app.prometheus().contains("127.0.0.1"));
+    assert!(app.custom_objects("otn-counters", None).is_empty());
+    app.shutdown();
+}
+
+/// # Test: `test_app_maintenance`
+///
+/// This test verifies that the maintenance windows of the application are saved to the
+/// file given at startup and loaded again by the next start, and that adding and
+/// removing a window is audited.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_app_maintenance() {
+    let directory = app_directory("maintenance");
+    let settings = AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        maintenance: Some(directory.join("maintenance.json")),
+        ..Default::default()
+    };
+    std::fs::write(&settings.config, "poll_interval = 60\n").unwrap();
+    std::fs::write(&settings.devices, "[]").unwrap();
+    let now = chrono::Local::now();
+    let window = MaintenanceWindow {
+        id: Uuid::new_v4(),
+        target: MaintenanceTarget::Device {
+            host: "10.0.0.1".to_string(),
+        },
+        start: now,
+        end: now + chrono::Duration::hours(2),
+        reason: Some("Software upgrade".to_string()),
+    };
+
+    let app = App::start(&settings).expect("App cannot be started");
+    assert!(app.maintenance().is_empty());
+    let id = app.add_maintenance(window.clone()).unwrap();
+    let entries = app.audit(&AuditQuery::default());
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].action, AuditAction::MaintenanceAdded);
+    assert_eq!(entries[0].actor, "local");
+    assert_eq!(entries[0].target, id.to_string());
+    app.shutdown();
+
+    let app = App::start(&settings).expect("App cannot be started");
+    assert_eq!(app.maintenance(), [window]);
+    app.remove_maintenance(&id).unwrap();
+    match app.remove_maintenance(&id) {
+        Err(Error::NotFound(msg)) => {
+            assert_eq!(msg, format!("Not found maintenance window {}", id))
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(()) => panic!("Expected an error, but got ()"),
+    }
+    let entries = app.audit(&AuditQuery::default());
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].action, AuditAction::MaintenanceRemoved);
+    assert_eq!(
+        entries[0].detail.as_ref().unwrap()["reason"],
+        "Software upgrade"
+    );
+    app.shutdown();
+}
+
+/// # Test: `test_app_log_level`
+///
+/// This test verifies that the log level of a reloaded config is applied to the
+/// logging system handed to the application, and that the reload is audited.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_app_log_level() {
+    let directory = app_directory("log_level");
+    let settings = AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        audit: Some(directory.join("audit.jsonl")),
+        ..Default::default()
+    };
+    std::fs::write(&settings.config, "log_level = \"info\"\n").unwrap();
+    std::fs::write(&settings.devices, "[]").unwrap();
+
+    let (filter, _telemetry) = logging_init_stderr("info", &TelemetryConfig::default())
+        .expect("Logging cannot be initialized");
+    let hooks = AppHooks {
+        log_filter: Some(filter),
+        ..Default::default()
+    };
+    let app = App::start_with(&settings, hooks).expect("App cannot be started");
+    assert!(!tracing::enabled!(tracing::Level::DEBUG));
+
+    std::fs::write(&settings.config, "log_level = \"debug\"\n").unwrap();
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while !tracing::enabled!(tracing::Level::DEBUG) {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "The log level was not reloaded"
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+
+    let query = AuditQuery {
+        action: Some(AuditAction::ConfigChanged),
+        ..Default::default()
+    };
+    while app.audit(&query).is_empty() {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "The config change was not audited"
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let entries = app.audit(&query);
+    assert_eq!(entries[0].target, "log_level");
+    assert_eq!(entries[0].actor, settings.config.display().to_string());
+    assert!(std::fs::read_to_string(directory.join("audit.jsonl"))
+        .unwrap()
+        .contains("config_changed"));
+    app.shutdown();
+}
+
+/// # Test: `test_app_bulk_actions`
+///
+/// This test verifies that a bulk action runs on every device of its tag as a job
+/// reporting each device, that paused or disabled devices cannot be polled, and that
+/// health checks skip devices not reached over HTTPS.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_app_bulk_actions() {
+    let directory = app_directory("bulk_actions");
+    let settings = AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        ..Default::default()
+    };
+    std::fs::write(
+        &settings.config,
+        "poll_interval = 60\n[devices.\"127.0.0.4\".netconf]\nport = 1\n",
+    )
+    .unwrap();
+    std::fs::write(
+        &settings.devices,
+        r#"[
+            {"host": "127.0.0.1", "auth": {"username": "tapi", "password": "secret"}, "tags": ["lab"]},
+            {"host": "127.0.0.2", "auth": {"username": "tapi", "password": "secret"}, "tags": ["lab"], "enabled": false},
+            {"host": "127.0.0.3", "auth": {"username": "tapi", "password": "secret"}},
+            {"host": "127.0.0.4", "auth": {"username": "tapi", "password": "secret"}, "tags": ["netconf"]}
+        ]"#,
+    )
+    .unwrap();
+    let app = App::start(&settings).expect("App cannot be started");
+    let run = |action: BulkAction| {
+        app.bulk(&BulkRequest {
+            tag: "lab".to_string(),
+            action,
+        })
+        .unwrap()
+    };
+    let report = |job: Job| -> BulkReport {
+        assert_eq!(job.status, JobStatus::Done);
+        serde_json::from_value(job.result.unwrap()).unwrap()
+    };
+
+    match app.bulk(&BulkRequest {
+        tag: "core".to_string(),
+        action: BulkAction::TriggerPoll,
+    }) {
+        Err(Error::NotFound(msg)) => assert_eq!(msg, "Not found devices tagged core"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+
+    let paused = report(finished(&app, &run(BulkAction::PausePolling)).await);
+    let hosts: Vec<&str> = paused
+        .devices
+        .iter()
+        .map(|device| device.host.as_str())
+        .collect();
+    assert_eq!(hosts, ["127.0.0.1", "127.0.0.2"]);
+    assert_eq!(paused.failed, 0);
+
+    let triggered = report(finished(&app, &run(BulkAction::TriggerPoll)).await);
+    assert_eq!(triggered.failed, 2);
+    let errors: Vec<Option<&str>> = triggered
+        .devices
+        .iter()
+        .map(|device| device.error.as_deref())
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            Some("Polling of 127.0.0.1 is paused"),
+            Some("Polling of 127.0.0.2 is disabled")
+        ]
+    );
+
+    let resumed = report(finished(&app, &run(BulkAction::Resume)).await);
+    assert_eq!(
+        resumed.devices[0].result,
+        Some(serde_json::json!({"paused": false, "resumed": true}))
+    );
+    let triggered = report(finished(&app, &run(BulkAction::TriggerPoll)).await);
+    assert!(triggered.devices[0].ok);
+    assert_eq!(triggered.failed, 1);
+
+    let checked = app
+        .bulk(&BulkRequest {
+            tag: "netconf".to_string(),
+            action: BulkAction::HealthCheck,
+        })
+        .unwrap();
+    let checked = report(finished(&app, &checked).await);
+    assert_eq!((checked.failed, checked.skipped), (0, 1));
+    assert_eq!(
+        checked.devices[0].skipped.as_deref(),
+        Some("Reached over NETCONF")
+    );
+    app.shutdown();
+}
+
+/// # Test: `test_app_read_through`
+///
+/// This test verifies that after a restart the topologies of a device are served from
+/// the object journal, read through on a request or loaded by the warm-load, which the
+/// readiness reports.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_app_read_through() {
+    let directory = app_directory("read_through");
+    let settings = AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        objects: Some(directory.join("objects.jsonl")),
+        ..Default::default()
+    };
+    std::fs::write(&settings.config, "poll_interval = 60\n").unwrap();
+    std::fs::write(
+        &settings.devices,
+        r#"[{"host": "10.0.0.1", "auth": {"username": "tapi", "password": "secret"}, "enabled": false}]"#,
+    )
+    .unwrap();
+    let topology = Topology::from_value(
+        &serde_json::json!({
+            "uuid": Uuid::from_u128(1),
+            "node": [{"uuid": Uuid::from_u128(2)}],
+            "link": [{
+                "uuid": Uuid::from_u128(3),
+                "node-edge-point": [{
+                    "node-uuid": Uuid::from_u128(2),
+                    "node-edge-point-uuid": Uuid::from_u128(4)
+                }]
+            }]
+        }),
+        "10.0.0.1",
+    )
+    .unwrap();
+    ObjectStore::open(directory.join("objects.jsonl"))
+        .unwrap()
+        .ingest("10.0.0.1", &[topology])
+        .unwrap();
+
+    let app = App::start(&settings).expect("App cannot be started");
+    let counts = |topology: &Topology| (topology.nodes.len(), topology.links.len());
+    assert_eq!(app.topologies("10.0.0.1", counts).await.unwrap(), [(1, 1)]);
+    assert_eq!(app.topologies("10.0.0.1", counts).await.unwrap(), [(1, 1)]);
+    // Whichever loaded the host first, the second read is a hit
+    let metrics = app.cache_metrics();
+    assert_eq!((metrics.hits >= 1, metrics.entries), (true, 1));
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+    while !app.readiness().ready {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "The topologies were not hydrated"
+        );
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert_eq!((app.readiness().hydrated, app.readiness().hosts), (1, 1));
+    match app.topologies("10.0.0.2", counts).await {
+        Err(Error::TopologyNotFound(msg)) => assert_eq!(msg, "Not found topology of 10.0.0.2"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    app.shutdown();
+}
+
+/// Mock incoming webhook refusing the first `refused` posts and accepting the next ones,
+/// forwarding the JSON bodies of every post
+async fn webhook(refused: usize) -> (String, mpsc::UnboundedReceiver<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        for attempt in 0.. {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 8192];
+            let mut request = String::new();
+            loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.push_str(&String::from_utf8_lossy(&buffer[..read]));
+                if let Some((headers, body)) = request.split_once("\r\n\r\n") {
+                    let length = headers
+                        .lines()
+                        .find_map(|line| {
+                            line.to_lowercase()
+                                .strip_prefix("content-length: ")
+                                .map(|length| length.parse::<usize>().unwrap())
+                        })
+                        .unwrap_or_default();
+                    if body.len() >= length {
+                        sender.send(serde_json::from_str(body).unwrap()).unwrap();
+                        break;
+                    }
+                }
+            }
+
+            let status = match attempt < refused {
+                true => "500 Internal Server Error",
+                false => "200 OK",
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                status
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (format!("http://{}/hook", address), receiver)
+}
+
+/// # Test: `test_app_notification_spool`
+///
+/// This test verifies that an alert a chat channel refuses is spooled for its sink, and
+/// delivered again ahead of the next alert once the channel recovers.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_app_notification_spool() {
+    let directory = app_directory("spool");
+    let (url, mut posts) = webhook(1).await;
+    let settings = AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        spool: Some(directory.join("spool")),
+        ..Default::default()
+    };
+    std::fs::write(
+        &settings.config,
+        format!(
+            "poll_interval = 60\n\
+             [notifications.thresholds]\n\
+             failed_polls = 1\n\
+             [[notifications.chat]]\n\
+             name = \"noc\"\n\
+             kind = \"slack\"\n\
+             webhook_url = \"{}\"\n",
+            url
+        ),
+    )
+    .unwrap();
+    // Nothing answers on either device, so both are reported down
+    std::fs::write(
+        &settings.devices,
+        r#"[
+            {"host": "127.0.0.1", "auth": {"username": "tapi", "password": "secret"}},
+            {"host": "127.0.0.2", "auth": {"username": "tapi", "password": "secret"}}
+        ]"#,
+    )
+    .unwrap();
+    let app = App::start(&settings).expect("App cannot be started");
+
+    let mut received = vec![];
+    while received.len() < 3 {
+        let post = tokio::time::timeout(Duration::from_secs(30), posts.recv())
+            .await
+            .expect("The alerts were not posted")
+            .unwrap();
+        received.push(post["text"].as_str().unwrap().to_string());
+    }
+    // The refused alert goes first once the channel takes the second one
+    assert_eq!(received[0], received[1]);
+    assert_ne!(received[1], received[2]);
+    let mut hosts = vec![received[1].clone(), received[2].clone()];
+    hosts.sort();
+    assert_eq!(
+        hosts,
+        ["Polls failing on 127.0.0.1", "Polls failing on 127.0.0.2"]
+    );
+
+    // The second alert queued behind the refused one, both left once posted
+    let mut spools = app.scheduler().queues.spools;
+    for _ in 0..100 {
+        if spools[&Sink::Slack].in_memory == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        spools = app.scheduler().queues.spools;
+    }
+    assert_eq!(spools[&Sink::Slack].in_memory, 0);
+    assert_eq!(spools[&Sink::Slack].delivered, 2);
+    assert_eq!(spools[&Sink::Teams].delivered, 0);
+    app.shutdown();
+}
+
+/// # Test: `test_app_alert_maintenance`
+///
+/// This test verifies that the threshold alerts of a device under maintenance are stored
+/// tagged `suppressed` and not posted, while the alerts of the other devices are.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_app_alert_maintenance() {
+    let directory = app_directory("alert_maintenance");
+    let (url, mut posts) = webhook(0).await;
+    let settings = AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        maintenance: Some(directory.join("maintenance.json")),
+        ..Default::default()
+    };
+    std::fs::write(
+        &settings.config,
+        format!(
+            "poll_interval = 60\n\
+             [notifications.thresholds]\n\
+             failed_polls = 1\n\
+             [[notifications.chat]]\n\
+             name = \"noc\"\n\
+             kind = \"slack\"\n\
+             webhook_url = \"{}\"\n",
+            url
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        &settings.devices,
+        r#"[
+            {"host": "127.0.0.1", "auth": {"username": "tapi", "password": "secret"}},
+            {"host": "127.0.0.2", "auth": {"username": "tapi", "password": "secret"}}
+        ]"#,
+    )
+    .unwrap();
+    let now = chrono::Local::now();
+    std::fs::write(
+        directory.join("maintenance.json"),
+        serde_json::to_string(&[MaintenanceWindow {
+            id: Uuid::new_v4(),
+            target: MaintenanceTarget::Device {
+                host: "127.0.0.1".to_string(),
+            },
+            start: now - chrono::Duration::hours(1),
+            end: now + chrono::Duration::hours(1),
+            reason: Some("Line card swap".to_string()),
+        }])
+        .unwrap(),
+    )
+    .unwrap();
+    let app = App::start(&settings).expect("App cannot be started");
+
+    // Nothing answers on either device, only the one out of maintenance is posted
+    let post = tokio::time::timeout(Duration::from_secs(30), posts.recv())
+        .await
+        .expect("The alert was not posted")
+        .unwrap();
+    assert_eq!(post["text"], "Polls failing on 127.0.0.2");
+    let mut alerts = app.alerts(DEFAULT_TENANT);
+    for _ in 0..100 {
+        if alerts.len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        alerts = app.alerts(DEFAULT_TENANT);
+    }
+    let mut suppressed: Vec<(String, bool)> = alerts
+        .iter()
+        .map(|stored| (stored.alert.host().to_string(), stored.suppressed))
+        .collect();
+    suppressed.sort();
+    assert_eq!(
+        suppressed,
+        [
+            ("127.0.0.1".to_string(), true),
+            ("127.0.0.2".to_string(), false)
+        ]
+    );
+    assert!(app.alerts("acme").is_empty());
+    assert!(posts.try_recv().is_err());
+    app.shutdown();
+}
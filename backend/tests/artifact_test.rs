@@ -0,0 +1,128 @@
+use backend::export::artifact::{ByteRange, ExportArtifact, ExportStore}; // Import the export artifacts
+use backend::jobs::{Job, JobQueue, JobStatus}; // Import the job queue
+use backend::Error; // Import the custom error type from the backend module
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Waits until a job is finished
+async fn finished(queue: &JobQueue, id: &Uuid) -> Job {
+    for _ in 0..100 {
+        let job = queue.get(id).unwrap();
+        if matches!(job.status, JobStatus::Done | JobStatus::Failed) {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("Job {} did not finish", id);
+}
+
+/// # Test: `test_byte_range`
+///
+/// This test verifies that the ranges of a download are parsed against the size of the
+/// artifact, and that ranges beyond it are rejected.
+#[test]
+fn test_byte_range() {
+    let range = ByteRange::parse("bytes=0-99", 1000).unwrap();
+    assert_eq!((range.start, range.end, range.len()), (0, 99, 100));
+    assert_eq!(range.content_range(1000), "bytes 0-99/1000");
+    // Resuming after the first 600 bytes
+    assert_eq!(
+        ByteRange::parse("bytes=600-", 1000).unwrap(),
+        ByteRange {
+            start: 600,
+            end: 999
+        }
+    );
+    // The last 100 bytes, and an end beyond the artifact
+    assert_eq!(
+        ByteRange::parse("bytes=-100", 1000).unwrap(),
+        ByteRange {
+            start: 900,
+            end: 999
+        }
+    );
+    assert_eq!(ByteRange::parse("bytes=900-5000", 1000).unwrap().end, 999);
+
+    match ByteRange::parse("bytes=1000-", 1000) {
+        Err(Error::RangeNotSatisfiable(msg)) => {
+            assert_eq!(msg, "Range not satisfiable: bytes=1000- of 1000 bytes")
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    for header in ["items=0-1", "bytes=a-b", "bytes=-", "bytes=0-1,5-6"] {
+        match ByteRange::parse(header, 1000) {
+            Err(Error::Invalid(_)) => {}
+            Err(err) => panic!("Unexpected error {:?}", err),
+            Ok(x) => panic!("Expected an error, but got {:?}", x),
+        }
+    }
+}
+
+/// # Test: `test_export_store`
+///
+/// This test verifies that an export job writes a stable artifact reported as its
+/// result, that a download resumes from a range, and that a changed `If-Range` gets
+/// the whole artifact.
+#[tokio::test]
+async fn test_export_store() {
+    let directory = std::env::temp_dir().join(format!("artifact_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    let store = ExportStore::open(&directory).unwrap();
+    let queue = JobQueue::new(1);
+    let content: Vec<u8> = (0..=255).cycle().take(10_000).collect();
+
+    let id = {
+        let content = content.clone();
+        store.submit(&queue, "topology.graphml", "application/xml", move || {
+            Ok(content)
+        })
+    };
+    let job = finished(&queue, &id).await;
+    assert_eq!(job.status, JobStatus::Done);
+    let artifact: ExportArtifact = serde_json::from_value(job.result.unwrap()).unwrap();
+    assert_eq!(artifact, store.artifact(&id).unwrap());
+    assert_eq!((artifact.id, artifact.size), (id, 10_000));
+
+    let chunk = store.read(&id, None, None).unwrap();
+    assert!(!chunk.partial);
+    assert_eq!(chunk.content, content);
+
+    // The link dropped after 4000 bytes
+    let chunk = store
+        .read(&id, Some("bytes=4000-"), Some(&artifact.etag))
+        .unwrap();
+    assert!(chunk.partial);
+    assert_eq!(
+        chunk.range.content_range(artifact.size),
+        "bytes 4000-9999/10000"
+    );
+    assert_eq!(chunk.content, content[4000..]);
+
+    let chunk = store
+        .read(&id, Some("bytes=4000-"), Some("\"another\""))
+        .unwrap();
+    assert!(!chunk.partial);
+    assert_eq!(chunk.content.len(), 10_000);
+
+    let failed = store.submit(&queue, "topology.csv", "text/csv", || {
+        Err(Error::from("Not found topology of 10.0.0.1"))
+    });
+    assert_eq!(finished(&queue, &failed).await.status, JobStatus::Failed);
+    match store.read(&failed, None, None) {
+        Err(Error::NotFound(msg)) => assert_eq!(msg, format!("Not found export {}", failed)),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+
+    // Artifacts expire with their jobs
+    assert_eq!(store.prune(chrono::Local::now()).unwrap(), 0);
+    assert_eq!(
+        store
+            .prune(chrono::Local::now() + chrono::Duration::days(8))
+            .unwrap(),
+        1
+    );
+    assert!(store.artifact(&id).is_err());
+    let _ = std::fs::remove_dir_all(&directory);
+}
@@ -0,0 +1,41 @@
+use backend::api::assets::{StaticAssets, IMMUTABLE, REVALIDATE}; // Import the frontend serving
+
+/// # Test: `test_static_assets`
+///
+/// This test verifies that files are served with their content type and cache headers,
+/// that application routes fall back to `index.html`, and that missing files and paths
+/// escaping the directory are not served.
+#[test]
+fn test_static_assets() {
+    let directory = std::env::temp_dir().join(format!("assets_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    for name in [
+        "index.html",
+        "frontend-4c2a81f0e1b3d9a7_bg.wasm",
+        "favicon.ico",
+    ] {
+        std::fs::write(directory.join(name), "").unwrap();
+    }
+    let assets = StaticAssets::new(&directory);
+
+    let wasm = assets
+        .resolve("/frontend-4c2a81f0e1b3d9a7_bg.wasm")
+        .unwrap();
+    assert_eq!(wasm.content_type, "application/wasm");
+    assert_eq!(wasm.cache_control, IMMUTABLE);
+
+    let favicon = assets.resolve("/favicon.ico").unwrap();
+    assert_eq!(favicon.cache_control, REVALIDATE);
+
+    // Routes of the application are answered with the index
+    let route = assets.resolve("/devices/10.0.0.1?tab=links").unwrap();
+    assert_eq!(route.path, directory.join("index.html"));
+    assert_eq!(route.content_type, "text/html; charset=utf-8");
+    assert_eq!(route.cache_control, REVALIDATE);
+
+    assert_eq!(assets.resolve("/missing.js"), None);
+    assert_eq!(assets.resolve("/../etc/passwd"), None);
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
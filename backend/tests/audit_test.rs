@@ -0,0 +1,134 @@
+use backend::audit::{AuditAction, AuditLog, AuditQuery}; // Import the audit log
+use backend::setup::config::ConfigChange;
+use chrono::{Duration, Local};
+use serde_json::json;
+
+/// # Test: `test_audit_record_and_query`
+///
+/// This test verifies that operations are recorded with their actor and filtered by
+/// actor, action and time range, most recent first.
+#[test]
+fn test_audit_record_and_query() {
+    let mut log = AuditLog::new();
+    let start = Local::now();
+    log.record("alice", AuditAction::DeviceAdded, "10.0.0.1", None)
+        .unwrap();
+    log.record(
+        "bob",
+        AuditAction::DeviceUpdated,
+        "10.0.0.1",
+        Some(json!({"enabled": false})),
+    )
+    .unwrap();
+    log.record("alice", AuditAction::PollTriggered, "10.0.0.1", None)
+        .unwrap();
+
+    let alice = log.query(&AuditQuery {
+        actor: Some("alice".to_string()),
+        ..Default::default()
+    });
+    assert_eq!(
+        alice.iter().map(|entry| entry.action).collect::<Vec<_>>(),
+        vec![AuditAction::PollTriggered, AuditAction::DeviceAdded]
+    );
+
+    let updates = log.query(&AuditQuery {
+        action: Some(AuditAction::DeviceUpdated),
+        ..Default::default()
+    });
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].detail, Some(json!({"enabled": false})));
+
+    let future = log.query(&AuditQuery {
+        from: Some(start + Duration::hours(1)),
+        ..Default::default()
+    });
+    assert!(future.is_empty());
+    let past = log.query(&AuditQuery {
+        from: Some(start - Duration::hours(1)),
+        to: Some(start + Duration::hours(1)),
+        ..Default::default()
+    });
+    assert_eq!(past.len(), 3);
+}
+
+/// # Test: `test_audit_persistence_and_config_changes`
+///
+/// This test checks that entries survive a restart and that config reloads are
+/// recorded one entry per setting.
+#[test]
+fn test_audit_persistence_and_config_changes() {
+    let path = std::env::temp_dir().join(format!("audit_test_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut log = AuditLog::open(&path).unwrap();
+    log.record_config_changes(
+        "config.toml",
+        &[ConfigChange {
+            setting: "log_level".to_string(),
+            from: Some("info".to_string()),
+            to: Some("debug".to_string()),
+        }],
+    )
+    .unwrap();
+    drop(log);
+
+    let mut log = AuditLog::open(&path).unwrap();
+    let entry = log
+        .record("alice", AuditAction::DeviceDeleted, "10.0.0.1", None)
+        .unwrap();
+    assert_eq!(entry.id, 2);
+
+    let changes = log.query(&AuditQuery {
+        action: Some(AuditAction::ConfigChanged),
+        ..Default::default()
+    });
+    assert_eq!(changes[0].target, "log_level");
+    assert_eq!(
+        changes[0].detail,
+        Some(json!({"from": "info", "to": "debug"}))
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// # Test: `test_audit_tenant_entries`
+///
+/// This test checks that a tenant only sees the entries recorded on its own targets,
+/// not those of another tenant nor the operations on the whole application.
+#[test]
+fn test_audit_tenant_entries() {
+    let mut log = AuditLog::new();
+    log.record_for("acme", "ops", AuditAction::PollingPaused, "10.0.0.1", None)
+        .unwrap();
+    log.record_for(
+        "globex",
+        "ops",
+        AuditAction::PollTriggered,
+        "10.0.0.2",
+        None,
+    )
+    .unwrap();
+    log.record(
+        "config.toml",
+        AuditAction::ConfigChanged,
+        "poll_interval",
+        None,
+    )
+    .unwrap();
+
+    let acme = log.query_tenant("acme", &AuditQuery::default());
+    assert_eq!(acme.len(), 1);
+    assert_eq!(acme[0].target, "10.0.0.1");
+    assert_eq!(acme[0].tenant_id.as_deref(), Some("acme"));
+    assert!(log
+        .query_tenant(
+            "acme",
+            &AuditQuery {
+                action: Some(AuditAction::PollTriggered),
+                ..Default::default()
+            }
+        )
+        .is_empty());
+    assert_eq!(log.query(&AuditQuery::default()).len(), 3);
+}
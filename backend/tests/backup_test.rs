@@ -0,0 +1,141 @@
+use backend::audit::{AuditAction, AuditLog, AuditQuery}; // Import the audit log
+use backend::backup::{backup, restore, DataFiles, SCHEMA_VERSION}; // Import the backup and restore of the data files
+use backend::Error;
+use serde_json::json;
+use std::fs;
+use std::path::PathBuf;
+
+/// Returns a fresh directory for the test `name`
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("backup_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// Returns the data files of `directory`, with devices and an audit log
+fn data_files(directory: &std::path::Path) -> DataFiles {
+    DataFiles {
+        devices: Some(directory.join("devices.json")),
+        events: Some(directory.join("events.jsonl")),
+        audit: Some(directory.join("audit.jsonl")),
+        ..Default::default()
+    }
+}
+
+fn devices(host: &str) -> String {
+    json!([{"host": host, "auth": {"username": "tapi", "password": "secret"}}]).to_string()
+}
+
+/// # Test: `test_backup_restore`
+///
+/// This test verifies that a backup holds the existing data files only, and that
+/// restoring it on another host brings back the devices and the audit log.
+#[test]
+fn test_backup_restore() {
+    let source = directory("source");
+    let files = data_files(&source);
+    fs::write(files.devices.as_ref().unwrap(), devices("10.0.0.1")).unwrap();
+    AuditLog::open(files.audit.as_ref().unwrap())
+        .unwrap()
+        .record("alice", AuditAction::DeviceAdded, "10.0.0.1", None)
+        .unwrap();
+
+    let archive = source.join("backup.tar.zst");
+    let manifest = backup(&files, &archive).unwrap();
+    assert_eq!(manifest.schema_version, SCHEMA_VERSION);
+    // The event log was never written, so it is not in the backup
+    assert_eq!(manifest.files, ["devices.json", "audit.jsonl"]);
+
+    let target = directory("target");
+    let restored = data_files(&target);
+    fs::write(restored.devices.as_ref().unwrap(), devices("10.9.9.9")).unwrap();
+    assert_eq!(restore(&archive, &restored).unwrap(), manifest);
+    assert_eq!(
+        fs::read_to_string(restored.devices.as_ref().unwrap()).unwrap(),
+        devices("10.0.0.1")
+    );
+    let audit = AuditLog::open(restored.audit.as_ref().unwrap()).unwrap();
+    assert_eq!(audit.query(&AuditQuery::default())[0].actor, "alice");
+    assert!(!target.join("devices.json.restore").exists());
+
+    fs::remove_dir_all(source).unwrap();
+    fs::remove_dir_all(target).unwrap();
+}
+
+/// # Test: `test_restore_refused`
+///
+/// This test checks that a backup of another schema version, with an invalid file or
+/// with a file lacking a destination is refused, leaving the current files untouched.
+#[test]
+fn test_restore_refused() {
+    let directory = directory("refused");
+    let files = data_files(&directory);
+    let devices_file = files.devices.clone().unwrap();
+
+    // Written by hand, as a future version would
+    let archive = directory.join("future.tar.zst");
+    {
+        let encoder = zstd::Encoder::new(fs::File::create(&archive).unwrap(), 3)
+            .unwrap()
+            .auto_finish();
+        let mut builder = tar::Builder::new(encoder);
+        let manifest = json!({
+            "schema_version": SCHEMA_VERSION + 1,
+            "created_at": "2024-05-24T00:00:00+00:00",
+            "files": []
+        })
+        .to_string();
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "manifest.json", manifest.as_bytes())
+            .unwrap();
+        builder.into_inner().unwrap();
+    }
+    match restore(&archive, &files) {
+        Err(Error::Custom(msg)) => assert_eq!(
+            msg,
+            format!(
+                "Unsupported backup schema version {}, expected {}",
+                SCHEMA_VERSION + 1,
+                SCHEMA_VERSION
+            )
+        ),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+
+    // A device without host cannot be loaded
+    fs::write(&devices_file, json!([{"port": 443}]).to_string()).unwrap();
+    let archive = directory.join("invalid.tar.zst");
+    backup(&files, &archive).unwrap();
+    fs::write(&devices_file, devices("10.0.0.1")).unwrap();
+    match restore(&archive, &files) {
+        Err(Error::Custom(msg)) => assert!(msg.starts_with("Invalid devices.json in backup")),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    assert_eq!(
+        fs::read_to_string(&devices_file).unwrap(),
+        devices("10.0.0.1")
+    );
+    assert!(!directory.join("devices.json.restore").exists());
+
+    let archive = directory.join("valid.tar.zst");
+    backup(&files, &archive).unwrap();
+    match restore(
+        &archive,
+        &DataFiles {
+            devices: None,
+            ..files
+        },
+    ) {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "Not found destination of devices.json"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,91 @@
+use backend::events::bus::{EventBus, OverflowPolicy}; // Import the event bus
+use std::sync::Arc;
+use std::time::Duration;
+
+/// # Test: `test_bus_drop_oldest`
+///
+/// This test verifies that a lagging `DropOldest` subscriber never slows publishing,
+/// loses its oldest events, and that the drops and queue depth are reported.
+#[tokio::test]
+async fn test_bus_drop_oldest() {
+    let bus = EventBus::new(4);
+    let mut slow = bus.subscribe("webhook", OverflowPolicy::DropOldest);
+
+    for event in 0..10 {
+        tokio::time::timeout(Duration::from_secs(1), bus.publish(event))
+            .await
+            .unwrap();
+    }
+    let metrics = bus.metrics();
+    assert_eq!(metrics.published, 10);
+    assert_eq!(metrics.subscribers[0].depth, 10);
+
+    // The 6 oldest events were overwritten
+    assert_eq!(slow.recv().await, Some(6));
+    let metrics = bus.metrics();
+    assert_eq!(metrics.subscribers[0].dropped, 6);
+    assert_eq!(metrics.subscribers[0].depth, 3);
+
+    let text = bus.prometheus();
+    assert!(text.contains("event_bus_queue_depth{subscriber=\"webhook\"} 3"));
+    assert!(text.contains("event_bus_dropped_total{subscriber=\"webhook\"} 6"));
+
+    drop(bus);
+    assert_eq!(slow.recv().await, Some(7));
+    assert_eq!(slow.recv().await, Some(8));
+    assert_eq!(slow.recv().await, Some(9));
+    assert_eq!(slow.recv().await, None);
+}
+
+/// # Test: `test_bus_block`
+///
+/// This test checks that publishing waits for a full `Block` subscriber, which then
+/// receives every event in order, while `DropOldest` subscribers are unaffected.
+#[tokio::test]
+async fn test_bus_block() {
+    let bus = Arc::new(EventBus::new(2));
+    let mut store = bus.subscribe("event_store", OverflowPolicy::Block);
+    let mut live = bus.subscribe("sse", OverflowPolicy::DropOldest);
+
+    bus.publish(1).await;
+    bus.publish(2).await;
+    // The store queue is full: the third event waits
+    assert!(
+        tokio::time::timeout(Duration::from_millis(100), bus.publish(3))
+            .await
+            .is_err()
+    );
+
+    let publisher = {
+        let bus = Arc::clone(&bus);
+        tokio::spawn(async move {
+            for event in 3..6 {
+                bus.publish(event).await;
+            }
+        })
+    };
+    let mut received = vec![];
+    while received.len() < 5 {
+        received.push(store.recv().await.unwrap());
+    }
+    publisher.await.unwrap();
+    // The timed out publish was cancelled before reaching the store
+    assert_eq!(received, vec![1, 2, 3, 4, 5]);
+    assert_eq!(bus.metrics().subscribers[0].dropped, 0);
+    assert!(live.recv().await.is_some());
+
+    // A dropped subscriber no longer blocks publishing
+    drop(store);
+    for event in 6..10 {
+        tokio::time::timeout(Duration::from_secs(1), bus.publish(event))
+            .await
+            .unwrap();
+    }
+    let names: Vec<String> = bus
+        .metrics()
+        .subscribers
+        .into_iter()
+        .map(|subscriber| subscriber.name)
+        .collect();
+    assert_eq!(names, vec!["sse"]);
+}
@@ -0,0 +1,98 @@
+use backend::cache::TopologyCache; // Import the topology cache
+use backend::models::topology::Topology; // Import the topology model
+use serde_json::{from_str, Value}; // Importing JSON deserialization utilities
+
+/// Parses a small topology `uuid` with one link for `host`
+fn topology(host: &str, uuid: &str) -> Topology {
+    let raw_topology = r#"
+        {
+            "uuid": "UUID",
+            "node": [
+                {"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"},
+                {"uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7"}
+            ],
+            "link": [
+                {
+                    "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+                    "node-edge-point": [
+                        {
+                            "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577",
+                            "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"
+                        },
+                        {
+                            "node-edge-point-uuid": "63366151-aeb4-3dfd-af66-d471b353aa1c",
+                            "node-uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7"
+                        }
+                    ]
+                }
+            ]
+        }"#;
+    let raw_value: Value = from_str(&raw_topology.replace("UUID", uuid)).unwrap_or_default();
+    Topology::from_value(&raw_value, host).unwrap()
+}
+
+/// # Test: `test_read_through`
+///
+/// This test verifies that a miss loads the topology from the store once, later reads
+/// are served from memory, and invalidation forces a new load.
+#[tokio::test]
+async fn test_read_through() {
+    let cache = TopologyCache::new();
+    let host = "127.0.0.1";
+
+    let loaded = cache
+        .get_or_load(host, |host| async move {
+            Ok(Some(vec![topology(
+                &host,
+                "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+            )]))
+        })
+        .await
+        .unwrap()
+        .expect("Topology not loaded");
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded[0].nodes.len(), 2);
+    assert_eq!(loaded[0].links.len(), 1);
+
+    // Served from memory: the loader must not run
+    let cached = cache
+        .get_or_load(host, |_| async { panic!("Store must not be hit") })
+        .await
+        .unwrap();
+    assert!(cached.is_some());
+
+    let metrics = cache.metrics();
+    assert_eq!((metrics.hits, metrics.misses, metrics.entries), (1, 1, 1));
+
+    // Invalidated by the collector: the next read goes to the store again
+    cache.invalidate(host);
+    let missing = cache
+        .get_or_load(host, |_| async { Ok(None) })
+        .await
+        .unwrap();
+    assert!(missing.is_none());
+    assert_eq!(cache.metrics().entries, 0);
+}
+
+/// # Test: `test_several_topologies`
+///
+/// This test checks that every topology of a controller exposing several of them is
+/// kept, a new collection replacing them together.
+#[test]
+fn test_several_topologies() {
+    let cache = TopologyCache::new();
+    let host = "127.0.0.1";
+    let first = "4e537278-79f8-39ad-804b-f0b553cb2ffb";
+    let second = "5f648389-8a09-4abe-915c-0c1c664dc30c";
+
+    cache.insert(host, vec![topology(host, first), topology(host, second)]);
+    cache.insert("127.0.0.2", vec![topology("127.0.0.2", first)]);
+    let cached = cache.get(host).expect("Topologies not cached");
+    let uuids: Vec<String> = cached.iter().map(|t| t.uuid.to_string()).collect();
+    assert_eq!(uuids, [first, second]);
+    assert_eq!(cache.snapshot().len(), 2);
+
+    cache.insert(host, vec![topology(host, second)]);
+    assert_eq!(cache.get(host).unwrap().len(), 1);
+    assert_eq!(cache.metrics().entries, 2);
+}
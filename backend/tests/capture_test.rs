@@ -0,0 +1,55 @@
+use backend::capture::ParseCapture; // Import the parse failure capture
+use backend::models::parse::{ParseMode, ParseOptions};
+use backend::models::topology::Topology;
+use backend::payloads::PayloadConfig;
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::json;
+use uuid::Uuid;
+
+/// # Test: `test_capture_parse_failure`
+///
+/// This test verifies that a link failing to parse is saved with its error, and that the
+/// returned error carries the id of the capture.
+#[test]
+fn test_capture_parse_failure() {
+    let directory = std::env::temp_dir().join(format!("capture_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    let capture = ParseCapture::open(&directory, PayloadConfig::default()).unwrap();
+
+    let link = json!({"uuid": "not-a-uuid", "node-edge-point": []});
+    let topology = json!({
+        "uuid": "14219539-9a9c-4e1b-8b0e-3f8a8b5f1a01",
+        "node": [],
+        "link": [link]
+    });
+
+    let options = ParseOptions {
+        mode: ParseMode::Strict,
+        capture: Some(&capture),
+        ..Default::default()
+    };
+    let id = match Topology::parse(&topology, "10.0.0.1", &options) {
+        Err(Error::Custom(msg)) => {
+            let (error, id) = msg.split_once(" (capture ").unwrap();
+            assert_eq!(error, "Not found link uuid");
+            Uuid::parse_str(id.trim_end_matches(')')).unwrap()
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok((topology, _)) => panic!("Expected an error, but got {:?}", topology),
+    };
+
+    let captured = capture.load(&id).unwrap();
+    assert_eq!(captured.host, "10.0.0.1");
+    assert_eq!(captured.object, "link");
+    assert_eq!(captured.error, "Not found link uuid");
+    assert_eq!(captured.fragment, link);
+
+    // Without a capture the error is left unchanged
+    match Topology::from_value(&topology, "10.0.0.1") {
+        Err(Error::BadResponse(msg)) => assert_eq!(msg, "Not found link uuid"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(topology) => panic!("Expected an error, but got {:?}", topology),
+    }
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
@@ -0,0 +1,274 @@
+use backend::events::{ChangeEvent, ChangeKind, ObjectKind}; // Import the change events
+use backend::notifications::chat::{ChatChannelConfig, ChatKind, ChatNotifier};
+use backend::notifications::templates::NotificationTemplates;
+use backend::notifications::{Alert, Severity};
+use backend::setup::config::Config;
+use backend::Error;
+use chrono::Local;
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Mock incoming webhook answering `status` to every post and forwarding the JSON bodies
+async fn webhook(status: &'static str) -> (String, mpsc::UnboundedReceiver<Value>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 8192];
+            let mut request = String::new();
+            // Read until the whole body announced by content-length is received
+            loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.push_str(&String::from_utf8_lossy(&buffer[..read]));
+                if let Some((headers, body)) = request.split_once("\r\n\r\n") {
+                    let length = headers
+                        .lines()
+                        .find_map(|line| {
+                            line.to_lowercase()
+                                .strip_prefix("content-length: ")
+                                .map(|length| length.parse::<usize>().unwrap())
+                        })
+                        .unwrap_or_default();
+                    if body.len() >= length {
+                        sender.send(serde_json::from_str(body).unwrap()).unwrap();
+                        break;
+                    }
+                }
+            }
+
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                status
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (format!("http://{}/hook", address), receiver)
+}
+
+/// Returns a chat channel posting to `url`
+fn channel(name: &str, kind: ChatKind, url: &str, tags: &[&str]) -> ChatChannelConfig {
+    ChatChannelConfig {
+        name: name.to_string(),
+        kind,
+        webhook_url: url.to_string(),
+        device_tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        min_severity: Severity::Info,
+    }
+}
+
+fn device_down(host: &str) -> Alert {
+    Alert::DeviceDown {
+        host: host.to_string(),
+        error: "Device timeout".to_string(),
+        since: Local::now(),
+    }
+}
+
+fn topology_change(host: &str) -> Alert {
+    Alert::TopologyChange {
+        event: ChangeEvent {
+            host: host.to_string(),
+            topology_uuid: Uuid::nil(),
+            object: ObjectKind::Link,
+            uuid: Uuid::nil(),
+            change: ChangeKind::Added,
+            date: Local::now(),
+        },
+    }
+}
+
+/// # Test: `test_chat_routing`
+///
+/// This test verifies that alerts are only posted to the channels of the chat services
+/// they were routed to, whose device tags match the device and whose minimum severity
+/// the alert reaches.
+#[tokio::test]
+async fn test_chat_routing() {
+    let (slack_url, mut slack) = webhook("200 OK").await;
+    let (teams_url, mut teams) = webhook("200 OK").await;
+    let mut critical = channel("teams-oncall", ChatKind::Teams, &teams_url, &[]);
+    critical.min_severity = Severity::Critical;
+    let notifier = ChatNotifier::new(
+        vec![
+            channel(
+                "slack-production",
+                ChatKind::Slack,
+                &slack_url,
+                &["production"],
+            ),
+            critical,
+        ],
+        NotificationTemplates::default(),
+    )
+    .unwrap();
+    let production = vec!["production".to_string()];
+    let lab = vec!["lab".to_string()];
+
+    let routed = |alert: &Alert, tags: &[String]| -> Vec<String> {
+        notifier
+            .routes(alert, tags, &[ChatKind::Slack, ChatKind::Teams])
+            .into_iter()
+            .map(|channel| channel.name.clone())
+            .collect()
+    };
+    assert_eq!(
+        routed(&topology_change("10.0.0.1"), &production),
+        vec!["slack-production"]
+    );
+    assert!(routed(&topology_change("10.0.0.1"), &lab).is_empty());
+    assert_eq!(routed(&device_down("10.0.0.1"), &lab), vec!["teams-oncall"]);
+
+    let notified = notifier
+        .notify(
+            &device_down("10.0.0.1"),
+            &production,
+            &[ChatKind::Slack, ChatKind::Teams],
+        )
+        .await
+        .unwrap();
+    assert_eq!(notified, vec!["slack-production", "teams-oncall"]);
+    assert_eq!(slack.recv().await.unwrap()["text"], "Device 10.0.0.1 down");
+    assert_eq!(teams.recv().await.unwrap()["title"], "Device 10.0.0.1 down");
+
+    // Only the chat services the alert was routed to are posted to
+    let notified = notifier
+        .notify(&device_down("10.0.0.2"), &production, &[ChatKind::Teams])
+        .await
+        .unwrap();
+    assert_eq!(notified, vec!["teams-oncall"]);
+    assert_eq!(teams.recv().await.unwrap()["title"], "Device 10.0.0.2 down");
+    assert!(slack.try_recv().is_err());
+}
+
+/// # Test: `test_chat_messages`
+///
+/// This test checks the Slack Block Kit and Teams card formatting of the alerts, topology
+/// changes being rendered with the channel templates.
+#[test]
+fn test_chat_messages() {
+    let notifier = ChatNotifier::new(vec![], NotificationTemplates::default()).unwrap();
+
+    let slack = notifier
+        .message(ChatKind::Slack, &topology_change("10.0.0.1"))
+        .unwrap();
+    assert_eq!(slack["text"], "Topology change on 10.0.0.1");
+    assert_eq!(slack["blocks"][0]["type"], "header");
+    assert_eq!(
+        slack["blocks"][1]["text"]["text"],
+        ":satellite_antenna: *10.0.0.1* link `00000000-0000-0000-0000-000000000000` added"
+    );
+    assert_eq!(
+        slack["blocks"][2]["elements"][0]["text"],
+        "Severity: *info* | Device Manager"
+    );
+
+    let alarm = Alert::Alarm {
+        host: "10.0.0.2".to_string(),
+        severity: Severity::Warning,
+        resource: "port-1/1".to_string(),
+        description: "Loss of signal".to_string(),
+        raised_at: Local::now(),
+    };
+    let teams = notifier.message(ChatKind::Teams, &alarm).unwrap();
+    assert_eq!(teams["@type"], "MessageCard");
+    assert_eq!(teams["themeColor"], "F9A825");
+    assert_eq!(teams["title"], "Alarm on 10.0.0.2");
+    assert!(teams["text"]
+        .as_str()
+        .unwrap()
+        .starts_with("**port-1/1**: Loss of signal"));
+
+    let down = notifier
+        .message(ChatKind::Teams, &device_down("10.0.0.3"))
+        .unwrap();
+    assert_eq!(down["themeColor"], "D32F2F");
+}
+
+/// # Test: `test_chat_failures`
+///
+/// This test ensures that a refusing webhook is reported without preventing the other
+/// channels from being notified, and that invalid webhook URLs are rejected.
+#[tokio::test]
+async fn test_chat_failures() {
+    let (broken_url, _broken) = webhook("500 Internal Server Error").await;
+    let (working_url, mut working) = webhook("200 OK").await;
+    let notifier = ChatNotifier::new(
+        vec![
+            channel("broken", ChatKind::Slack, &broken_url, &[]),
+            channel("working", ChatKind::Teams, &working_url, &[]),
+        ],
+        NotificationTemplates::default(),
+    )
+    .unwrap();
+
+    match notifier
+        .notify(
+            &device_down("10.0.0.1"),
+            &[],
+            &[ChatKind::Slack, ChatKind::Teams],
+        )
+        .await
+    {
+        Err(Error::Custom(msg)) => assert_eq!(
+            msg,
+            "Failed to notify chat channels: Failed to post to broken: 500 Internal Server Error"
+        ),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    assert_eq!(
+        working.recv().await.unwrap()["title"],
+        "Device 10.0.0.1 down"
+    );
+
+    match ChatNotifier::new(
+        vec![channel("bad", ChatKind::Slack, "not a url", &[])],
+        NotificationTemplates::default(),
+    ) {
+        Err(Error::Custom(msg)) => {
+            assert!(msg.starts_with("Invalid webhook URL of chat channel bad"))
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error"),
+    }
+}
+
+/// # Test: `test_chat_config`
+///
+/// This test verifies that chat channels are read from `[[notifications.chat]]`, and
+/// that their webhook URLs never appear in the config changes.
+#[test]
+fn test_chat_config() {
+    let previous = Config::parse("").unwrap();
+    let config = Config::parse(
+        r#"
+        [[notifications.chat]]
+        name = "noc"
+        kind = "teams"
+        webhook_url = "https://example.webhook.office.com/secret"
+        min_severity = "warning"
+        "#,
+    )
+    .unwrap();
+
+    let chat = &config.notifications.chat;
+    assert_eq!(chat.len(), 1);
+    assert_eq!(chat[0].kind, ChatKind::Teams);
+    assert_eq!(chat[0].min_severity, Severity::Warning);
+    assert!(chat[0].device_tags.is_empty());
+
+    let changes = config.changes(&previous);
+    let change = changes
+        .iter()
+        .find(|change| change.setting == "notifications.chat")
+        .unwrap();
+    assert!(!format!("{:?}", change).contains("secret"));
+}
@@ -0,0 +1,30 @@
+use backend::api::codes::{catalog, ErrorCode}; // Import the API error codes
+use serde_json::{json, to_value};
+use std::collections::HashSet;
+
+/// # Test: `test_error_code_catalog`
+///
+/// This test verifies that the catalog lists every code once, with the wire name used
+/// in serialization and its HTTP status.
+#[test]
+fn test_error_code_catalog() {
+    let catalog = catalog();
+    assert_eq!(catalog.len(), ErrorCode::ALL.len());
+
+    let codes: HashSet<&str> = catalog.iter().map(|info| info.code.as_str()).collect();
+    assert_eq!(codes.len(), catalog.len(), "Duplicated error code");
+
+    // The serialized code must match `as_str`, which is what clients match on
+    for info in &catalog {
+        assert_eq!(to_value(info.code).unwrap(), json!(info.code.as_str()));
+    }
+
+    assert_eq!(
+        to_value(&catalog[4]).unwrap(),
+        json!({
+            "code": "DEVICE_NOT_FOUND",
+            "http_status": 404,
+            "description": "No device is registered for the requested host"
+        })
+    );
+}
@@ -0,0 +1,73 @@
+use backend::collector::{CollectionStep, DeviceCycle, StepOutcome}; // Import the collection cycle
+use backend::Error; // Import the custom error type from the backend module
+use std::time::Duration;
+
+/// # Test: `test_cycle_within_budget`
+///
+/// This test verifies that every step runs when the cycle fits its budget, and that a
+/// failing step does not stop the following ones.
+#[tokio::test]
+async fn test_cycle_within_budget() {
+    let mut cycle = DeviceCycle::new(Duration::from_secs(5));
+
+    let report = cycle
+        .run("10.95.87.21", |step| async move {
+            match step {
+                CollectionStep::Inventory => Err(Error::from("Inventory not supported")),
+                _ => Ok(()),
+            }
+        })
+        .await;
+
+    assert!(!report.partial);
+    assert_eq!(
+        report.steps,
+        vec![
+            (CollectionStep::Topology, StepOutcome::Completed),
+            (
+                CollectionStep::Inventory,
+                StepOutcome::Failed {
+                    error: "Inventory not supported".to_string()
+                }
+            ),
+            (CollectionStep::Alarms, StepOutcome::Completed),
+        ]
+    );
+}
+
+/// # Test: `test_cycle_deadline`
+///
+/// This test checks that a step exceeding the budget is aborted, the remaining steps
+/// are skipped, and the next cycle runs the skipped steps first.
+#[tokio::test]
+async fn test_cycle_deadline() {
+    let mut cycle = DeviceCycle::new(Duration::from_millis(100));
+
+    let report = cycle
+        .run("10.95.87.21", |step| async move {
+            if step == CollectionStep::Inventory {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            Ok(())
+        })
+        .await;
+
+    assert!(report.partial);
+    assert!(report.elapsed < Duration::from_secs(5));
+    assert_eq!(
+        report.steps,
+        vec![
+            (CollectionStep::Topology, StepOutcome::Completed),
+            (CollectionStep::Inventory, StepOutcome::Aborted),
+            (CollectionStep::Alarms, StepOutcome::Skipped),
+        ]
+    );
+    assert_eq!(
+        cycle.scope(),
+        &[
+            CollectionStep::Alarms,
+            CollectionStep::Topology,
+            CollectionStep::Inventory
+        ]
+    );
+}
@@ -0,0 +1,85 @@
+use backend::correlation::Correlation; // Import the correlation layer
+use backend::models::{
+    // Import necessary model components
+    node::Node,
+    service_interface_point::ServiceInterfacePoint,
+};
+use serde_json::{from_str, Value}; // Importing JSON deserialization utilities
+use uuid::Uuid; // For handling UUIDs (universally unique identifiers)
+
+/// # Test: `test_service_interface_point_correlation`
+///
+/// This test verifies that service interface points are resolved to the owned node edge
+/// point mapping them and its parent node, and that unmapped ones are reported.
+#[test]
+fn test_service_interface_point_correlation() {
+    let host = "127.0.0.1";
+    let raw_node = r#"
+        {
+            "uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476",
+            "name": [{"value-name": "NODE_NAME", "value": "Barcelona"}],
+            "owned-node-edge-point": [
+                {
+                    "uuid": "65a39427-3055-3ba4-9e15-0ebed4974577",
+                    "layer-protocol-qualifier": "tapi-dsr:DIGITAL_SIGNAL_TYPE_10_GigE_LAN",
+                    "mapped-service-interface-point": [
+                        {"service-interface-point-uuid": "0b7e4c1d-8a3f-4f9e-b2d6-5c1a9e8f7d6c"}
+                    ],
+                    "tapi-equipment:supporting-access-port": {
+                        "access-port": {
+                            "device-uuid": "d5a1e8c2-6f3b-4c1e-9a0e-1b2c3d4e5f60",
+                            "access-port-uuid": "a1b2c3d4-e5f6-4a7b-8c9d-0e1f2a3b4c5d"
+                        }
+                    }
+                },
+                {
+                    "uuid": "63366151-aeb4-3dfd-af66-d471b353aa1c"
+                }
+            ]
+        }"#;
+    let raw_sips = r#"
+        [
+            {
+                "uuid": "0b7e4c1d-8a3f-4f9e-b2d6-5c1a9e8f7d6c",
+                "name": [{"value-name": "SIP_NAME", "value": "BCN-CLIENT-1"}],
+                "layer-protocol-name": "DSR"
+            },
+            {
+                "uuid": "3f2e1d0c-b9a8-4c7d-8e6f-5a4b3c2d1e0f",
+                "layer-protocol-name": "DSR"
+            }
+        ]"#;
+
+    let node_value: Value = from_str(raw_node).unwrap_or_default();
+    let node = Node::from_value(&node_value, host).unwrap();
+    assert_eq!(node.owned_node_edge_points.len(), 2);
+
+    let sips_value: Value = from_str(raw_sips).unwrap_or_default();
+    let sips: Vec<ServiceInterfacePoint> = sips_value
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|sip| ServiceInterfacePoint::from_value(sip, host).unwrap())
+        .collect();
+
+    let correlation = Correlation::build(&sips, &[node]);
+
+    let mapped_sip = Uuid::parse_str("0b7e4c1d-8a3f-4f9e-b2d6-5c1a9e8f7d6c").unwrap();
+    let endpoints = correlation.resolve(&mapped_sip);
+    assert_eq!(endpoints.len(), 1);
+    assert_eq!(endpoints[0].node_name.as_deref(), Some("Barcelona"));
+    assert_eq!(
+        endpoints[0].node_edge_point_uuid,
+        Uuid::parse_str("65a39427-3055-3ba4-9e15-0ebed4974577").unwrap()
+    );
+    assert_eq!(
+        endpoints[0].layer_protocol_qualifier.as_deref(),
+        Some("tapi-dsr:DIGITAL_SIGNAL_TYPE_10_GigE_LAN")
+    );
+    assert!(endpoints[0].supporting_access_port.is_some());
+
+    assert_eq!(
+        correlation.unmapped,
+        vec![Uuid::parse_str("3f2e1d0c-b9a8-4c7d-8e6f-5a4b3c2d1e0f").unwrap()]
+    );
+}
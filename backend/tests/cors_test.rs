@@ -0,0 +1,68 @@
+use backend::api::cors::CorsConfig; // Import the CORS policy
+use backend::setup::config::Config;
+
+/// # Test: `test_cors_profiles`
+///
+/// This test verifies that cross-origin requests are denied in production and allowed
+/// in the dev profile when no policy is configured.
+#[test]
+fn test_cors_profiles() {
+    let production = Config::parse("").unwrap().cors();
+    assert_eq!(production.response("http://localhost:8080"), None);
+    assert_eq!(
+        production.preflight("http://localhost:8080", "GET", None),
+        None
+    );
+
+    let dev = Config::parse(r#"profile = "dev""#).unwrap().cors();
+    assert_eq!(
+        dev.response("http://localhost:8080"),
+        Some(vec![
+            (
+                "access-control-allow-origin",
+                "http://localhost:8080".to_string()
+            ),
+            ("vary", "Origin".to_string()),
+            ("access-control-allow-credentials", "true".to_string()),
+        ])
+    );
+}
+
+/// # Test: `test_cors_configured_policy`
+///
+/// This test checks that a configured policy only allows its origins, methods and
+/// headers in preflight requests.
+#[test]
+fn test_cors_configured_policy() {
+    let config = Config::parse(
+        r#"
+        profile = "dev"
+
+        [cors]
+        allowed_origins = ["https://noc.example.com"]
+        allowed_methods = ["GET", "POST"]
+        "#,
+    )
+    .unwrap();
+    let cors: CorsConfig = config.cors();
+
+    let preflight = cors
+        .preflight("https://noc.example.com", "POST", Some("Content-Type"))
+        .unwrap();
+    assert!(preflight.contains(&(
+        "access-control-allow-origin",
+        "https://noc.example.com".to_string()
+    )));
+    assert!(preflight.contains(&("access-control-allow-methods", "GET, POST".to_string())));
+    assert!(preflight.contains(&("access-control-max-age", "600".to_string())));
+
+    assert_eq!(
+        cors.preflight("https://noc.example.com", "DELETE", None),
+        None
+    );
+    assert_eq!(
+        cors.preflight("https://noc.example.com", "GET", Some("x-custom")),
+        None
+    );
+    assert_eq!(cors.response("https://evil.example.com"), None);
+}
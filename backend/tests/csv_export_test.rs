@@ -0,0 +1,47 @@
+use backend::export::csv::{devices_to_csv, links_to_csv, nodes_to_csv}; // Import the CSV export
+use backend::models::{device::Device, topology::Topology}; // Import the models
+use serde_json::json;
+
+/// # Test: `test_csv_export`
+///
+/// This test verifies that links are flattened into endpoint columns, that fields with
+/// separators are quoted, and that device credentials are left out.
+#[test]
+fn test_csv_export() {
+    let value = json!({
+        "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+        "node": [
+            {"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "name": [{"value-name": "NODE_NAME", "value": "Madrid, \"core\""}]}
+        ],
+        "link": [{
+            "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+            "lifecycle-state": "INSTALLED",
+            "node-edge-point": [
+                {"node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577", "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"},
+                {"node-edge-point-uuid": "63366151-aeb4-3dfd-af66-d471b353aa1c", "node-uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7"}
+            ]
+        }]
+    });
+    let topology = Topology::from_value(&value, "127.0.0.1").unwrap();
+
+    let links = links_to_csv(&topology.links);
+    let lines: Vec<&str> = links.split("\r\n").collect();
+    assert_eq!(
+        lines[0],
+        "host,uuid,lifecycle_state,endpoint_1_node_uuid,endpoint_1_node_edge_point_uuid,endpoint_2_node_uuid,endpoint_2_node_edge_point_uuid,date"
+    );
+    assert!(lines[1].starts_with("127.0.0.1,14219539-208b-35f5-b7cf-35a58e083490,INSTALLED,62d11f13-db6c-3398-8a83-5fac0b2b7476,65a39427-3055-3ba4-9e15-0ebed4974577,7b0c973a-996a-3409-ad2f-d173354bfdb7,63366151-aeb4-3dfd-af66-d471b353aa1c,"));
+
+    let nodes = nodes_to_csv(&topology.nodes);
+    assert!(nodes
+        .contains("127.0.0.1,62d11f13-db6c-3398-8a83-5fac0b2b7476,\"Madrid, \"\"core\"\"\",,,0,"));
+
+    let device = Device::from_value(&json!({
+        "host": "10.95.87.21",
+        "port": 8443,
+        "auth": {"username": "tapi", "password": "secret"}
+    }))
+    .unwrap();
+    let devices = devices_to_csv(&[device]);
+    assert_eq!(devices, "host,port,auth\r\n10.95.87.21,8443,basic\r\n");
+}
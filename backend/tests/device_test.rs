@@ -1,8 +1,11 @@
 // Import the necessary structs and enums from your backend models
-use backend::models::device::{Auth, Device};
+use backend::models::device::{register, Auth, Device, DeviceFilter, Registration};
+use backend::Error; // Import the custom error type from the backend module
+use chrono::{Duration, Local};
+use std::collections::HashMap;
 
 // Import necessary modules from serde_json for JSON handling
-use serde_json::{from_str, Value};
+use serde_json::{from_str, json, Value};
 
 /// Test case for creating a `Device` with Basic Authentication
 #[test]
@@ -21,7 +24,7 @@ fn test_basic_device() {
 
     // Convert the JSON string into a serde_json::Value
     let json_value: Value =
-        from_str(&json_data).expect("Json test data cannot be transformed to Value type");
+        from_str(json_data).expect("Json test data cannot be transformed to Value type");
 
     // Create a `Device` instance from the JSON `Value`
     let device = Device::from_value(&json_value).expect("Device cannot be created");
@@ -29,11 +32,43 @@ fn test_basic_device() {
     // Match the type of authentication used in the device
     match device.auth {
         // Check if it uses Basic Authentication
-        Auth::BasicAuth(_) => assert!(true), // Pass if it's BasicAuth
+        Auth::BasicAuth(_) => {} // Pass if it's BasicAuth
         _ => panic!("There isn't Basic Authentication here"), // Fail if it's not BasicAuth
     }
 }
 
+/// # Test: `test_device_without_port`
+///
+/// This test verifies that a `null` port is read as no port, like an absent one, and that
+/// a device without a port is serialized without the field.
+#[test]
+fn test_device_without_port() {
+    let device = Device::from_value(&json!({
+        "host": "10.95.87.21",
+        "port": null,
+        "auth": {"username": "tapi", "password": "secret"}
+    }))
+    .unwrap();
+    assert_eq!(device.port, None);
+    assert!(Device::validate(&json!({
+        "host": "10.95.87.21",
+        "port": null,
+        "auth": {"username": "tapi", "password": "secret"}
+    }))
+    .is_empty());
+    assert!(serde_json::to_value(&device).unwrap().get("port").is_none());
+
+    match Device::from_value(&json!({
+        "host": "10.95.87.21",
+        "port": "http",
+        "auth": {"username": "tapi", "password": "secret"}
+    })) {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid port \"http\""),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
+
 /// Test case for creating a `Device` with Custom Authentication
 #[test]
 fn test_custom_device() {
@@ -53,7 +88,7 @@ fn test_custom_device() {
 
     // Convert the JSON string into a serde_json::Value
     let json_value: Value =
-        from_str(&json_data).expect("Json test data cannot be transformed to Value type");
+        from_str(json_data).expect("Json test data cannot be transformed to Value type");
 
     // Create a `Device` instance from the JSON `Value`
     let device = Device::from_value(&json_value).expect("Device cannot be created");
@@ -61,7 +96,7 @@ fn test_custom_device() {
     // Match the type of authentication used in the device
     match device.auth {
         // Check if it uses Custom Authentication
-        Auth::Custom(_) => assert!(true), // Pass if it's CustomAuth
+        Auth::Custom(_) => {} // Pass if it's CustomAuth
         _ => panic!("There isn't Custom Authentication here"), // Fail if it's not CustomAuth
     }
 }
@@ -84,7 +119,7 @@ fn test_oauth2_device() {
 
     // Convert the JSON string into a serde_json::Value
     let json_value: Value =
-        from_str(&json_data).expect("Json test data cannot be transformed to Value type");
+        from_str(json_data).expect("Json test data cannot be transformed to Value type");
 
     // Create a `Device` instance from the JSON `Value`
     let device = Device::from_value(&json_value).expect("Device cannot be created");
@@ -92,7 +127,213 @@ fn test_oauth2_device() {
     // Match the type of authentication used in the device
     match device.auth {
         // Check if it uses OAuth2 Authentication
-        Auth::Oauth2(_) => assert!(true), // Pass if it's Oauth2Auth
+        Auth::Oauth2(_) => {} // Pass if it's Oauth2Auth
         _ => panic!("There isn't OAuth2 Authentication here"), // Fail if it's not Oauth2Auth
     }
 }
+
+/// Test case for creating a `Device` with API key and Bearer token Authentication
+#[test]
+fn test_token_devices() {
+    // Example JSON data for API key Authentication, using the default header
+    let device = Device::from_value(&json!({
+        "host": "10.95.87.21",
+        "auth": {"key": "3f9a1c0b7d2e"}
+    }))
+    .expect("Device cannot be created");
+
+    // Check if it uses API key Authentication with the default header
+    match device.auth {
+        Auth::ApiKey(api_key) => assert_eq!(api_key.header_name, "X-API-Key"),
+        _ => panic!("There isn't API key Authentication here"),
+    }
+
+    // Example JSON data for Bearer token Authentication
+    let device = Device::from_value(&json!({
+        "host": "10.95.87.21",
+        "auth": {"token": "eyJhbGciOiJIUzI1NiJ9"}
+    }))
+    .expect("Device cannot be created");
+
+    // Check if it uses Bearer token Authentication
+    match device.auth {
+        Auth::BearerToken(_) => {} // Pass if it's BearerToken
+        _ => panic!("There isn't Bearer token Authentication here"),
+    }
+}
+
+/// # Test: `test_device_tags_and_metadata`
+///
+/// This test verifies that tags and metadata are read from the registry entry and that
+/// devices can be filtered on them.
+#[test]
+fn test_device_tags_and_metadata() {
+    let madrid = Device::from_value(&json!({
+        "host": "10.95.87.21",
+        "auth": {"username": "tapi", "password": "secret"},
+        "tags": ["production", "optical"],
+        "metadata": {"site": "MAD01", "vendor": "zte"}
+    }))
+    .unwrap();
+    let barcelona = Device::from_value(&json!({
+        "host": "10.95.87.22",
+        "auth": {"username": "tapi", "password": "secret"},
+        "tags": ["production"],
+        "metadata": {"site": "BCN01"}
+    }))
+    .unwrap();
+    let devices = vec![madrid, barcelona];
+
+    let filter = DeviceFilter {
+        tags: vec!["production".to_string()],
+        metadata: HashMap::from([("site".to_string(), "MAD01".to_string())]),
+    };
+    let matching = filter.apply(&devices);
+    assert_eq!(matching.len(), 1);
+    assert_eq!(matching[0].host, "10.95.87.21");
+    assert_eq!(DeviceFilter::default().apply(&devices).len(), 2);
+
+    match Device::from_value(&json!({
+        "host": "10.95.87.23",
+        "auth": {"username": "tapi", "password": "secret"},
+        "tags": ["production", 7]
+    })) {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid device tags".to_string()),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but got Ok"),
+    }
+}
+
+/// # Test: `test_device_maintenance`
+///
+/// This test verifies that devices are polled by default, and that alerts are suppressed
+/// during a maintenance window and while polling is paused.
+#[test]
+fn test_device_maintenance() {
+    let mut device = Device::from_value(&json!({
+        "host": "10.95.87.21",
+        "auth": {"username": "tapi", "password": "secret"}
+    }))
+    .unwrap();
+    let now = Local::now();
+    assert!(device.enabled);
+    assert!(device.alerts_enabled(now));
+
+    device.maintenance_until = Some(now + Duration::hours(2));
+    assert!(device.in_maintenance(now));
+    assert!(!device.alerts_enabled(now));
+    assert!(device.alerts_enabled(now + Duration::hours(3)));
+
+    device.enabled = false;
+    device.maintenance_until = None;
+    assert!(!device.in_maintenance(now));
+    assert!(!device.alerts_enabled(now));
+}
+
+/// # Test: `test_device_validation`
+///
+/// This test verifies that validation reports every problem of a registration at once,
+/// and accepts a valid one.
+#[test]
+fn test_device_validation() {
+    let errors = Device::validate(&json!({
+        "host": "bad host!",
+        "port": 70000,
+        "auth": {"username": "", "password": "secret", "grant_type": "client_credentials", "auth_url": "not a url"}
+    }));
+    let fields: Vec<&str> = errors.iter().map(|error| error.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        vec!["host", "port", "auth.username", "auth.auth_url"]
+    );
+
+    // Neither would be read as an option of `ssh`
+    let errors = Device::validate(&json!({
+        "host": "-oProxyCommand=id",
+        "auth": {"username": "-oProxyCommand=id", "password": "secret"}
+    }));
+    let messages: Vec<&str> = errors.iter().map(|error| error.message.as_str()).collect();
+    assert_eq!(
+        messages,
+        vec![
+            "Host must be an IP address or a hostname",
+            "Username must not start with '-'"
+        ]
+    );
+
+    match Device::from_value_validated(&json!({"port": 18010})) {
+        Err(errors) => {
+            assert_eq!(errors.len(), 2);
+            assert_eq!(errors[1].message, "Auth body not found");
+        }
+        Ok(device) => panic!("Expected errors, but got {:?}", device),
+    }
+
+    let device = Device::from_value_validated(&json!({
+        "host": "controller-01.example.net",
+        "port": 18010,
+        "auth": {"username": "tapi", "password": "secret", "grant_type": "password", "auth_url": "/tron/api/v1/tokens"}
+    }))
+    .unwrap();
+    assert!(matches!(device.auth, Auth::Oauth2(_)));
+}
+
+/// # Test: `test_device_duplicates`
+///
+/// This test verifies that registering a device whose hostname resolves to a registered
+/// IP is reported as a conflict, that it is merged on request without overwriting the
+/// existing metadata, and that other ports and tenants are distinct devices.
+#[test]
+fn test_device_duplicates() {
+    let device = |value: Value| Device::from_value(&value).unwrap();
+    let mut devices = vec![device(json!({
+        "host": "127.0.0.1",
+        "port": 8443,
+        "auth": {"username": "tapi", "password": "secret"},
+        "tags": ["production"],
+        "metadata": {"site": "madrid"}
+    }))];
+    let duplicate = device(json!({
+        "host": "localhost",
+        "port": 8443,
+        "auth": {"username": "tapi", "password": "secret"},
+        "tags": ["production", "core"],
+        "metadata": {"site": "paris", "role": "roadm"}
+    }));
+
+    assert_eq!(
+        register(&mut devices, duplicate.clone(), false),
+        Registration::Conflict(0)
+    );
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].tags, vec!["production"]);
+
+    assert_eq!(
+        register(&mut devices, duplicate, true),
+        Registration::Merged(0)
+    );
+    assert_eq!(devices[0].host, "127.0.0.1");
+    assert_eq!(devices[0].tags, vec!["production", "core"]);
+    assert_eq!(devices[0].metadata["site"], "madrid");
+    assert_eq!(devices[0].metadata["role"], "roadm");
+
+    let other_port = device(json!({
+        "host": "LOCALHOST",
+        "port": 9443,
+        "auth": {"username": "tapi", "password": "secret"}
+    }));
+    assert_eq!(
+        register(&mut devices, other_port, false),
+        Registration::Added(1)
+    );
+    let other_tenant = device(json!({
+        "tenant_id": "acme",
+        "host": "127.0.0.1",
+        "port": 8443,
+        "auth": {"username": "tapi", "password": "secret"}
+    }));
+    assert_eq!(
+        register(&mut devices, other_tenant, false),
+        Registration::Added(2)
+    );
+}
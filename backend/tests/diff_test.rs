@@ -0,0 +1,255 @@
+use backend::diff::{compare_topologies, diff_links, diff_topologies}; // Import the diff engine
+use backend::events::{ChangeKind, ObjectKind}; // Import the change event types
+use backend::models::{lifecycle_state::LifecycleState, topology::Topology}; // Import the topology models
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Builds a link payload between two fixed node edge points
+fn link(uuid: &str, lifecycle_state: &str) -> Value {
+    json!({
+        "uuid": uuid,
+        "lifecycle-state": lifecycle_state,
+        "node-edge-point": [
+            {
+                "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577",
+                "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"
+            },
+            {
+                "node-edge-point-uuid": "63366151-aeb4-3dfd-af66-d471b353aa1c",
+                "node-uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7"
+            }
+        ]
+    })
+}
+
+/// Parses a topology with the given nodes and links
+fn topology(nodes: Vec<Value>, links: Vec<Value>) -> Topology {
+    let value = json!({
+        "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+        "node": nodes,
+        "link": links
+    });
+    Topology::from_value(&value, "127.0.0.1").unwrap()
+}
+
+/// # Test: `test_lifecycle_parsing_and_filtering`
+///
+/// This test verifies that lifecycle states are parsed on links and nodes, can be
+/// filtered on, also by their API name, and that unknown values are rejected.
+#[test]
+fn test_lifecycle_parsing_and_filtering() {
+    let current = topology(
+        vec![
+            json!({"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "lifecycle-state": "INSTALLED"}),
+        ],
+        vec![
+            link("14219539-208b-35f5-b7cf-35a58e083490", "INSTALLED"),
+            link("2b1f6a3c-5d4e-4f7a-9b8c-1d2e3f4a5b6c", "PLANNED"),
+        ],
+    );
+
+    assert_eq!(current.nodes_in_state(LifecycleState::Installed).len(), 1);
+    assert_eq!(current.links_in_state(LifecycleState::Planned).len(), 1);
+    assert_eq!(
+        current.links_in_state(LifecycleState::PendingRemoval).len(),
+        0
+    );
+    let planned = current.in_state("PLANNED".parse().unwrap());
+    assert_eq!((planned.nodes.len(), planned.links.len()), (0, 1));
+    assert_eq!(
+        planned.links[0].uuid.to_string(),
+        "2b1f6a3c-5d4e-4f7a-9b8c-1d2e3f4a5b6c"
+    );
+    match "RETIRED".parse::<LifecycleState>() {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid lifecycle state RETIRED"),
+        other => panic!("Expected an invalid state, but got {:?}", other),
+    }
+
+    let value = json!({"uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb", "link": [link("14219539-208b-35f5-b7cf-35a58e083490", "RETIRED")]});
+    assert!(Topology::from_value(&value, "127.0.0.1").is_err());
+}
+
+/// # Test: `test_topology_diff`
+///
+/// This test checks that additions, removals, content changes and lifecycle
+/// transitions are reported between two collections.
+#[test]
+fn test_topology_diff() {
+    let previous = topology(
+        vec![json!({"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"})],
+        vec![
+            link("14219539-208b-35f5-b7cf-35a58e083490", "PLANNED"),
+            link("2b1f6a3c-5d4e-4f7a-9b8c-1d2e3f4a5b6c", "INSTALLED"),
+        ],
+    );
+    let current = topology(
+        vec![
+            json!({"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "name": [{"value-name": "NODE_NAME", "value": "Madrid"}]}),
+        ],
+        vec![
+            link("14219539-208b-35f5-b7cf-35a58e083490", "INSTALLED"),
+            link("9c8b7a6d-5e4f-4a3b-8c2d-1e0f9a8b7c6d", "PLANNED"),
+        ],
+    );
+
+    let events = diff_topologies(&previous, &current);
+    let changes: Vec<(ObjectKind, Uuid, ChangeKind)> = events
+        .into_iter()
+        .map(|event| (event.object, event.uuid, event.change))
+        .collect();
+
+    let uuid = |value: &str| Uuid::parse_str(value).unwrap();
+    assert_eq!(
+        changes,
+        vec![
+            (
+                ObjectKind::Node,
+                uuid("62d11f13-db6c-3398-8a83-5fac0b2b7476"),
+                ChangeKind::Changed
+            ),
+            (
+                ObjectKind::Link,
+                uuid("14219539-208b-35f5-b7cf-35a58e083490"),
+                ChangeKind::LifecycleTransition {
+                    from: Some(LifecycleState::Planned),
+                    to: Some(LifecycleState::Installed)
+                }
+            ),
+            (
+                ObjectKind::Link,
+                uuid("2b1f6a3c-5d4e-4f7a-9b8c-1d2e3f4a5b6c"),
+                ChangeKind::Removed
+            ),
+            (
+                ObjectKind::Link,
+                uuid("9c8b7a6d-5e4f-4a3b-8c2d-1e0f9a8b7c6d"),
+                ChangeKind::Added
+            ),
+        ]
+    );
+}
+
+/// # Test: `test_link_diff`
+///
+/// This test checks the link summary of `cli topology diff`: links are grouped as added,
+/// removed or changed, and every link of a topology missing on one side is reported.
+#[test]
+fn test_link_diff() {
+    let previous = topology(
+        vec![],
+        vec![
+            link("14219539-208b-35f5-b7cf-35a58e083490", "PLANNED"),
+            link("2b1f6a3c-5d4e-4f7a-9b8c-1d2e3f4a5b6c", "INSTALLED"),
+        ],
+    );
+    let mut current = topology(
+        vec![],
+        vec![
+            link("14219539-208b-35f5-b7cf-35a58e083490", "INSTALLED"),
+            link("3c2f6a3c-5d4e-4f7a-9b8c-1d2e3f4a5b6c", "INSTALLED"),
+        ],
+    );
+    current.links[1].name = Some("MAD-BCN".to_string());
+
+    let diff = diff_links(std::slice::from_ref(&previous), &[current]);
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.added[0].name.as_deref(), Some("MAD-BCN"));
+    assert_eq!(
+        diff.removed[0].uuid,
+        Uuid::parse_str("2b1f6a3c-5d4e-4f7a-9b8c-1d2e3f4a5b6c").unwrap()
+    );
+    assert_eq!(
+        diff.changed[0].change,
+        ChangeKind::LifecycleTransition {
+            from: Some(LifecycleState::Planned),
+            to: Some(LifecycleState::Installed),
+        }
+    );
+
+    // A topology no longer reported loses all its links
+    let diff = diff_links(std::slice::from_ref(&previous), &[]);
+    assert_eq!((diff.added.len(), diff.removed.len()), (0, 2));
+    let previous = [previous];
+    assert!(diff_links(&previous, &previous).is_empty());
+}
+
+/// # Test: `test_topology_compare`
+///
+/// This test checks that the objects of two controllers are aligned by UUID across
+/// topologies, then by unique name, and that the rest is reported per side.
+#[test]
+fn test_topology_compare() {
+    let named = |uuid: &str, name: &str| {
+        let mut value = link(uuid, "INSTALLED");
+        value["name"] = json!([{"value-name": "LINK_NAME", "value": name}]);
+        value
+    };
+    let left = topology(
+        vec![json!({"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"})],
+        vec![
+            link("14219539-208b-35f5-b7cf-35a58e083490", "INSTALLED"),
+            named("00000000-0000-0000-0000-000000000001", "MAD-BCN"),
+            named("00000000-0000-0000-0000-000000000002", "MAD-VLC"),
+            named("00000000-0000-0000-0000-000000000003", "MAD-VLC"),
+        ],
+    );
+    // Another controller names its topology differently
+    let right = Topology::from_value(
+        &json!({
+            "uuid": "00000000-0000-0000-0000-0000000000ff",
+            "node": [
+                {"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"},
+                {"uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7"}
+            ],
+            "link": [
+                link("14219539-208b-35f5-b7cf-35a58e083490", "INSTALLED"),
+                named("00000000-0000-0000-0000-00000000000a", "MAD-BCN"),
+                named("00000000-0000-0000-0000-00000000000b", "MAD-VLC"),
+            ]
+        }),
+        "10.0.0.2",
+    )
+    .unwrap();
+
+    let comparison = compare_topologies(("127.0.0.1", &[left]), ("10.0.0.2", &[right]));
+    assert_eq!(comparison.left, "127.0.0.1");
+    assert_eq!(comparison.matched_by_uuid, 2);
+    assert_eq!(comparison.matched_by_name, 1);
+    assert!(!comparison.is_empty());
+
+    let uuids = |objects: &[backend::diff::UnmatchedObject]| -> Vec<(ObjectKind, String)> {
+        objects
+            .iter()
+            .map(|object| (object.object, object.uuid.to_string()))
+            .collect()
+    };
+    // MAD-VLC is ambiguous on the left, so none of its links is aligned
+    assert_eq!(
+        uuids(&comparison.only_left),
+        vec![
+            (
+                ObjectKind::Link,
+                "00000000-0000-0000-0000-000000000002".to_string()
+            ),
+            (
+                ObjectKind::Link,
+                "00000000-0000-0000-0000-000000000003".to_string()
+            ),
+        ]
+    );
+    assert_eq!(
+        uuids(&comparison.only_right),
+        vec![
+            (
+                ObjectKind::Node,
+                "7b0c973a-996a-3409-ad2f-d173354bfdb7".to_string()
+            ),
+            (
+                ObjectKind::Link,
+                "00000000-0000-0000-0000-00000000000b".to_string()
+            ),
+        ]
+    );
+    assert_eq!(comparison.only_right[1].name.as_deref(), Some("MAD-VLC"));
+}
@@ -0,0 +1,335 @@
+use backend::discovery::scan::Cidr;
+use backend::discovery::{
+    candidates_from_value, discover, scan, CandidateStatus, CandidateStore, Fingerprint, ScanConfig,
+}; // Import the device discovery
+use backend::models::address::Port;
+use backend::models::device::{Device, Registration}; // Import the device model
+use backend::setup::config::Config;
+use backend::southbound::restconf::RestconfClient;
+use backend::testsupport::{MockAuth, MockDevice}; // Import the mock TAPI device
+use backend::Error;
+use chrono::Local;
+use serde_json::{json, Value};
+use std::fs;
+use std::net::IpAddr;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+/// Physical context of a controller managing two ROADMs and a device without address
+fn inventory() -> Value {
+    json!({"tapi-equipment:physical-context": {"device": [
+        {
+            "uuid": "00000000-0000-0000-0000-000000000001",
+            "name": [
+                {"value-name": "DEVICE_NAME", "value": "roadm-madrid"},
+                {"value-name": "IP_ADDRESS", "value": "10.0.1.1"}
+            ]
+        },
+        {
+            "uuid": "00000000-0000-0000-0000-000000000002",
+            "name": [{"value-name": "MANAGEMENT_IP", "value": "10.0.1.2"}]
+        },
+        {
+            "uuid": "00000000-0000-0000-0000-000000000003",
+            "name": [{"value-name": "DEVICE_NAME", "value": "shelf"}]
+        }
+    ]}})
+}
+
+fn controller(host: &str) -> Device {
+    Device::from_value(&json!({
+        "tenant_id": "acme",
+        "host": host,
+        "auth": {"username": "tapi", "password": "secret"},
+        "metadata": {"role": "controller"}
+    }))
+    .unwrap()
+}
+
+/// # Test: `test_discover`
+///
+/// This test verifies that the devices of a controller's physical context are read as
+/// candidates when they report a management address, and skipped otherwise.
+#[tokio::test]
+async fn test_discover() {
+    let mock = MockDevice::start(inventory(), MockAuth::None)
+        .await
+        .unwrap();
+    let base_url = format!("http://{}", mock.address());
+    let client = RestconfClient::with_base_url(controller("127.0.0.1"), &base_url).unwrap();
+
+    let candidates = discover(&client, "127.0.0.1").await.unwrap();
+    assert_eq!(candidates.len(), 2);
+    assert_eq!(candidates[0].host.as_str(), "10.0.1.1");
+    assert_eq!(candidates[0].name.as_deref(), Some("roadm-madrid"));
+    assert_eq!(candidates[0].controller.as_deref(), Some("127.0.0.1"));
+    assert_eq!(candidates[0].status, CandidateStatus::Pending);
+    assert_eq!(candidates[1].host.as_str(), "10.0.1.2");
+    assert_eq!(candidates[1].name, None);
+
+    match candidates_from_value(&json!({"device": [{"name": []}]}), "c", Local::now()) {
+        Err(Error::BadResponse(msg)) => assert_eq!(msg, "Not found physical device uuid"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
+
+/// # Test: `test_candidate_approval`
+///
+/// This test checks that registered and known devices are not proposed twice, that an
+/// approved candidate is registered with the controller's tenant and credentials, and
+/// that a rejected one is kept out of the pending list across reopenings.
+#[test]
+fn test_candidate_approval() {
+    let path = std::env::temp_dir().join(format!("candidates_{}.json", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let mut devices = vec![controller("10.0.0.1")];
+    let mut registered = controller("10.0.1.2");
+    registered.metadata.clear();
+    devices.push(registered);
+
+    let mut store = CandidateStore::open(&path).unwrap();
+    let candidates = candidates_from_value(&inventory(), "10.0.0.1", Local::now()).unwrap();
+    assert_eq!(store.propose(candidates.clone(), &devices).unwrap(), 1);
+    assert_eq!(store.propose(candidates, &devices).unwrap(), 0);
+    assert_eq!(store.pending().len(), 1);
+
+    match store.approve("10.0.1.1", None, &mut devices).unwrap() {
+        Registration::Added(index) => {
+            let device = &devices[index];
+            assert_eq!(device.tenant_id, "acme");
+            assert_eq!(device.auth, devices[0].auth);
+            assert_eq!(device.metadata["name"], "roadm-madrid");
+            assert_eq!(device.metadata["discovered_by"], "10.0.0.1");
+        }
+        registration => panic!("Expected an added device, but got {:?}", registration),
+    }
+    assert!(store.pending().is_empty());
+    match store.approve("10.0.1.1", None, &mut devices) {
+        Err(Error::NotFound(msg)) => assert_eq!(msg, "Not found candidate 10.0.1.1"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+
+    // Another controller reports a new device, which is rejected
+    let mut other = inventory();
+    other["tapi-equipment:physical-context"]["device"][1]["name"][0]["value"] = json!("10.0.2.2");
+    let candidates = candidates_from_value(&other, "10.0.0.9", Local::now()).unwrap();
+    assert_eq!(store.propose(candidates, &devices).unwrap(), 1);
+    let mut store = CandidateStore::open(&path).unwrap();
+    match store.approve("10.0.2.2", None, &mut devices) {
+        Err(Error::NotFound(msg)) => assert_eq!(msg, "Not found controller 10.0.0.9"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    store.reject("10.0.2.2").unwrap();
+    assert!(CandidateStore::open(&path).unwrap().pending().is_empty());
+
+    fs::remove_file(path).unwrap();
+}
+
+/// # Test: `test_cli_candidate_approval`
+///
+/// This test approves a candidate with the `cli discovery approve` command and checks that
+/// the registry written can be loaded again, and that a candidate duplicating a registered
+/// device stays pending.
+#[test]
+fn test_cli_candidate_approval() {
+    let directory = std::env::temp_dir().join(format!("cli_discovery_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    let registry = directory.join("devices.json");
+    let candidates = directory.join("candidates.json");
+    fs::write(
+        &registry,
+        json!([{
+            "tenant_id": "acme",
+            "host": "10.0.0.1",
+            "auth": {"username": "tapi", "password": "secret"},
+            "metadata": {"role": "controller"}
+        }])
+        .to_string(),
+    )
+    .unwrap();
+    let mut store = CandidateStore::open(&candidates).unwrap();
+    let proposed = candidates_from_value(&inventory(), "10.0.0.1", Local::now()).unwrap();
+    assert_eq!(
+        store.propose(proposed, &[controller("10.0.0.1")]).unwrap(),
+        2
+    );
+
+    let approve = |host: &str| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_cli"))
+            .args(["discovery", "approve", host, "--devices"])
+            .arg(&registry)
+            .arg("--candidates")
+            .arg(&candidates)
+            .output()
+            .unwrap()
+    };
+    let output = approve("10.0.1.1");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let entries: Value = serde_json::from_str(&fs::read_to_string(&registry).unwrap()).unwrap();
+    let devices = entries
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(Device::from_value)
+        .collect::<Result<Vec<Device>, Error>>()
+        .unwrap();
+    assert_eq!(devices.len(), 2);
+    assert_eq!(devices[1].host.as_str(), "10.0.1.1");
+    assert_eq!(devices[1].port, None);
+    assert_eq!(devices[1].auth, devices[0].auth);
+
+    // The second candidate duplicates a device registered meanwhile
+    let mut entries = entries;
+    entries.as_array_mut().unwrap().push(json!({
+        "tenant_id": "acme",
+        "host": "10.0.1.2",
+        "auth": {"username": "tapi", "password": "secret"}
+    }));
+    fs::write(&registry, entries.to_string()).unwrap();
+    assert!(!approve("10.0.1.2").status.success());
+    let store = CandidateStore::open(&candidates).unwrap();
+    assert_eq!(store.pending().len(), 1);
+    assert_eq!(store.pending()[0].host.as_str(), "10.0.1.2");
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+/// # Test: `test_cidr`
+///
+/// This test verifies that networks are parsed with their host bits cleared, that the
+/// network and broadcast addresses are not scanned, and that oversized or malformed
+/// ranges are refused.
+#[test]
+fn test_cidr() {
+    let cidr: Cidr = "10.0.1.7/30".parse().unwrap();
+    assert_eq!(cidr.to_string(), "10.0.1.4/30");
+    assert_eq!(cidr.size(), 4);
+    assert_eq!(
+        cidr.hosts(),
+        ["10.0.1.5", "10.0.1.6"]
+            .map(|address| address.parse::<IpAddr>().unwrap())
+            .to_vec()
+    );
+    assert_eq!("10.0.1.1/32".parse::<Cidr>().unwrap().hosts().len(), 1);
+    assert_eq!("fd00::/120".parse::<Cidr>().unwrap().hosts().len(), 256);
+
+    match "10.0.0.0/8".parse::<Cidr>() {
+        Err(Error::Invalid(msg)) => assert_eq!(
+            msg,
+            "Invalid network 10.0.0.0/8, at most 65536 addresses are scanned"
+        ),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    for value in ["10.0.1.0", "10.0.1.0/33", "roadm/24"] {
+        match value.parse::<Cidr>() {
+            Err(Error::Invalid(msg)) => assert_eq!(msg, format!("Invalid network {}", value)),
+            Err(err) => panic!("Unexpected error {:?}", err),
+            Ok(x) => panic!("Expected an error, but got {:?}", x),
+        }
+    }
+}
+
+/// # Test: `test_scan`
+///
+/// This test checks that a scan fingerprints a TAPI device and an SSH server, ignores
+/// closed ports, and proposes each host once on its most useful port.
+#[tokio::test]
+async fn test_scan() {
+    let mock = MockDevice::start(json!({"tapi-common:context": {}}), MockAuth::None)
+        .await
+        .unwrap();
+    let ssh = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let ssh_port = ssh.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = ssh.accept().await.unwrap();
+            let _ = socket.write_all(b"SSH-2.0-OpenSSH_9.6\r\n").await;
+        }
+    });
+    // Bound then dropped, so nothing listens there
+    let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let closed_port = closed.local_addr().unwrap().port();
+    drop(closed);
+
+    let config = |ports: &[u16]| ScanConfig {
+        ranges: vec!["127.0.0.1/32".parse().unwrap()],
+        ports: ports.iter().map(|port| Port::new(*port).unwrap()).collect(),
+        rate: 100,
+        timeout: 2_000,
+        interval: 3_600,
+    };
+
+    let candidates = scan(&config(&[ssh_port, closed_port])).await.unwrap();
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].fingerprint, Some(Fingerprint::Netconf));
+    assert_eq!(candidates[0].port, Port::new(ssh_port).ok());
+
+    let tapi_port = mock.address().port();
+    let candidates = scan(&config(&[ssh_port, tapi_port, closed_port]))
+        .await
+        .unwrap();
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].host.as_str(), "127.0.0.1");
+    assert_eq!(candidates[0].fingerprint, Some(Fingerprint::Tapi));
+    assert_eq!(candidates[0].port, Port::new(tapi_port).ok());
+    assert_eq!(candidates[0].controller, None);
+
+    // Scanned devices are only registered with credentials
+    let mut store = CandidateStore::new();
+    let mut devices = vec![];
+    assert_eq!(store.propose(candidates, &devices).unwrap(), 1);
+    match store.approve("127.0.0.1", None, &mut devices) {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Missing credentials for candidate 127.0.0.1"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    let auth = controller("10.0.0.1").auth;
+    assert_eq!(
+        store
+            .approve("127.0.0.1", Some(auth), &mut devices)
+            .unwrap(),
+        Registration::Added(0)
+    );
+    assert_eq!(devices[0].port, Port::new(tapi_port).ok());
+    assert_eq!(devices[0].tenant_id, "default");
+}
+
+/// # Test: `test_discovery_config`
+///
+/// This test ensures that the `[discovery]` section is off by default, parsed with its
+/// defaults when set, and refused with a zero rate or an invalid range.
+#[test]
+fn test_discovery_config() {
+    assert_eq!(Config::default().discovery, None);
+    let config = Config::parse("[discovery]\nranges = [\"10.0.1.0/24\"]").unwrap();
+    let discovery = config.discovery.unwrap();
+    assert_eq!(
+        discovery.ports,
+        [443, 8443, 830].map(|port| Port::new(port).unwrap())
+    );
+    assert_eq!(discovery.rate, 10);
+    assert_eq!(discovery.interval, 86_400);
+
+    match Config::parse("[discovery]\nranges = [\"10.0.1.0/24\"]\nrate = 0") {
+        Err(Error::Invalid(msg)) => assert_eq!(
+            msg,
+            "Invalid config: discovery.rate and discovery.interval must be positive"
+        ),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    match Config::parse("[discovery]\nranges = [\"10.0.0.0/8\"]") {
+        Err(Error::Invalid(msg)) => assert!(msg.starts_with("Invalid config"), "{}", msg),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
@@ -0,0 +1,70 @@
+use backend::models::device::Device; // Import the device model
+use backend::models::topology::TOPOLOGY_CONTEXT_PATH;
+use backend::southbound::dns::CachedResolver; // Import the DNS cache of the device clients
+use backend::southbound::restconf::RestconfClient;
+use backend::southbound::{FetchOptions, SouthboundProtocol}; // Import the RESTCONF driver
+use backend::testsupport::{MockAuth, MockDevice}; // Import the mock TAPI device
+use serde_json::json;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// # Test: `test_cached_resolver`
+///
+/// This test verifies that IPv4 and IPv6 literals, bracketed or not, are used as they
+/// are, and that a resolved hostname is cached and reported as the polled address.
+#[tokio::test]
+async fn test_cached_resolver() {
+    let resolver = CachedResolver::new();
+    let loopback = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    assert_eq!(resolver.resolve("127.0.0.1").await.unwrap(), [loopback]);
+    assert_eq!(
+        resolver.resolve("[::1]").await.unwrap(),
+        [IpAddr::V6(Ipv6Addr::LOCALHOST)]
+    );
+    assert_eq!(
+        resolver.address("::1"),
+        Some(IpAddr::V6(Ipv6Addr::LOCALHOST))
+    );
+
+    // A hostname has no address until it is resolved
+    assert_eq!(resolver.address("localhost"), None);
+    let addresses = resolver.resolve("localhost").await.unwrap();
+    assert!(addresses.iter().all(IpAddr::is_loopback), "{:?}", addresses);
+    assert_eq!(resolver.address("localhost"), addresses.first().copied());
+
+    // Clones share the cache
+    assert_eq!(
+        resolver.clone().resolve("localhost").await.unwrap(),
+        addresses
+    );
+}
+
+/// # Test: `test_client_address`
+///
+/// This test checks that a client reaching its device by hostname goes through the
+/// resolver and reports the address it polls.
+#[tokio::test]
+async fn test_client_address() {
+    let context = json!({"tapi-topology:topology-context": {"topology": []}});
+    let mock = MockDevice::start(context, MockAuth::None).await.unwrap();
+    let device = Device::from_value(&json!({
+        "host": "localhost",
+        "auth": {"username": "tapi", "password": "secret"}
+    }))
+    .unwrap();
+    let base_url = format!("http://localhost:{}", mock.address().port());
+    let client = RestconfClient::with_base_url(device, &base_url).unwrap();
+    assert_eq!(client.address(), None);
+
+    let context = client
+        .get(TOPOLOGY_CONTEXT_PATH, &FetchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        context["tapi-topology:topology-context"]["topology"],
+        json!([])
+    );
+    assert!(client
+        .address()
+        .is_some_and(|address| address.is_loopback()));
+}
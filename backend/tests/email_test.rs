@@ -0,0 +1,208 @@
+use backend::events::{ChangeEvent, ChangeKind, ObjectKind}; // Import the change events
+use backend::notifications::email::{Delivery, EmailConfig, EmailNotifier};
+use backend::notifications::templates::NotificationTemplates;
+use backend::notifications::thresholds::{Threshold, ThresholdState};
+use backend::notifications::Alert;
+use backend::setup::config::Config;
+use chrono::{Duration, Local};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Minimal SMTP server accepting every email and forwarding its data
+async fn smtp_server() -> (u16, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                let (read, mut write) = socket.into_split();
+                let mut lines = BufReader::new(read).lines();
+                write.write_all(b"220 mock ESMTP\r\n").await.unwrap();
+                let mut data: Option<String> = None;
+                while let Ok(Some(line)) = lines.next_line().await {
+                    if let Some(content) = data.as_mut() {
+                        if line == "." {
+                            sender.send(data.take().unwrap()).unwrap();
+                            write.write_all(b"250 queued\r\n").await.unwrap();
+                        } else {
+                            content.push_str(&line);
+                            content.push('\n');
+                        }
+                        continue;
+                    }
+                    let reply: &[u8] = match line.split(' ').next().unwrap_or_default() {
+                        "DATA" => {
+                            data = Some(String::new());
+                            b"354 go ahead\r\n"
+                        }
+                        "QUIT" => {
+                            write.write_all(b"221 bye\r\n").await.unwrap();
+                            return;
+                        }
+                        _ => b"250 ok\r\n",
+                    };
+                    write.write_all(reply).await.unwrap();
+                }
+            });
+        }
+    });
+
+    (port, receiver)
+}
+
+/// Returns a change event on the device at `host`
+fn event(host: &str, change: ChangeKind) -> ChangeEvent {
+    ChangeEvent {
+        host: host.to_string(),
+        topology_uuid: Uuid::nil(),
+        object: ObjectKind::Link,
+        uuid: Uuid::nil(),
+        change,
+        date: Local::now(),
+    }
+}
+
+/// Email settings sending to the mock server on `port`
+fn config(port: u16) -> EmailConfig {
+    let config = Config::parse(&format!(
+        r#"
+        [notifications.email]
+        smtp_host = "127.0.0.1"
+        smtp_port = {}
+        starttls = false
+        from = "Device Manager <noc@example.com>"
+        to = ["oncall@example.com"]
+        device_tags = ["production"]
+        digest = {{ window = 600, max_immediate = 2 }}
+        "#,
+        port
+    ))
+    .unwrap();
+    config.notifications.email.unwrap()
+}
+
+/// # Test: `test_email_filtering`
+///
+/// This test verifies that only events at or above the configured severity, from
+/// devices with one of the configured tags, are emailed.
+#[tokio::test]
+async fn test_email_filtering() {
+    let (port, mut emails) = smtp_server().await;
+    let mut notifier = EmailNotifier::new(config(port), NotificationTemplates::default()).unwrap();
+    let production = vec!["production".to_string()];
+
+    assert_eq!(
+        notifier
+            .notify(&event("10.0.0.1", ChangeKind::Changed), &production)
+            .await
+            .unwrap(),
+        Delivery::Filtered
+    );
+    assert_eq!(
+        notifier
+            .notify(
+                &event("10.0.0.1", ChangeKind::Removed),
+                &["lab".to_string()]
+            )
+            .await
+            .unwrap(),
+        Delivery::Filtered
+    );
+    assert_eq!(
+        notifier
+            .notify(&event("10.0.0.1", ChangeKind::Removed), &production)
+            .await
+            .unwrap(),
+        Delivery::Sent
+    );
+
+    let email = emails.recv().await.unwrap();
+    assert!(email.contains("Subject: [Device Manager] 10.0.0.1: link removed"));
+    assert!(email.contains("To: oncall@example.com"));
+    assert!(email.contains("Device 10.0.0.1: link 00000000-0000-0000-0000-000000000000 removed"));
+}
+
+/// # Test: `test_email_digest`
+///
+/// This test checks that a flapping device only sends `max_immediate` emails per
+/// window, and that its other events are sent in one digest when the window closes.
+#[tokio::test]
+async fn test_email_digest() {
+    let (port, mut emails) = smtp_server().await;
+    let mut notifier = EmailNotifier::new(config(port), NotificationTemplates::default()).unwrap();
+    let production = vec!["production".to_string()];
+
+    let mut deliveries = vec![];
+    for _ in 0..5 {
+        deliveries.push(
+            notifier
+                .notify(&event("10.0.0.1", ChangeKind::Removed), &production)
+                .await
+                .unwrap(),
+        );
+    }
+    assert_eq!(
+        deliveries,
+        vec![
+            Delivery::Sent,
+            Delivery::Sent,
+            Delivery::Digested,
+            Delivery::Digested,
+            Delivery::Digested
+        ]
+    );
+
+    assert_eq!(notifier.flush(Local::now()).await.unwrap(), 0);
+    assert_eq!(
+        notifier
+            .flush(Local::now() + Duration::minutes(11))
+            .await
+            .unwrap(),
+        1
+    );
+
+    emails.recv().await.unwrap();
+    emails.recv().await.unwrap();
+    let digest = emails.recv().await.unwrap();
+    assert!(digest.contains("Subject: [Device Manager] 10.0.0.1: 3 more events since"));
+    assert_eq!(digest.matches("removed").count(), 3);
+}
+
+/// # Test: `test_email_threshold_alert`
+///
+/// This test verifies that a threshold alert at or above the configured severity is
+/// emailed, and that a less severe one is filtered out.
+#[tokio::test]
+async fn test_email_threshold_alert() {
+    let (port, mut emails) = smtp_server().await;
+    let mut notifier = EmailNotifier::new(config(port), NotificationTemplates::default()).unwrap();
+    let production = vec!["production".to_string()];
+    let alert = |threshold: Threshold, detail: &str| Alert::Threshold {
+        host: "10.0.0.1".to_string(),
+        threshold,
+        state: ThresholdState::Raised,
+        detail: detail.to_string(),
+        at: Local::now(),
+    };
+
+    let latency = alert(Threshold::Latency, "latency 900 ms over 500 ms");
+    assert_eq!(
+        notifier.notify_alert(&latency, &production).await.unwrap(),
+        Delivery::Filtered
+    );
+    let failed = alert(Threshold::FailedPolls, "3 consecutive polls failed");
+    assert_eq!(
+        notifier.notify_alert(&failed, &production).await.unwrap(),
+        Delivery::Sent
+    );
+
+    let email = emails.recv().await.unwrap();
+    assert!(email.contains("Subject: [Device Manager] Polls failing on 10.0.0.1"));
+    assert!(email.contains("3 consecutive polls failed"));
+}
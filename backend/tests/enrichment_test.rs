@@ -0,0 +1,56 @@
+use backend::enrichment::{enrich, SiteMapping}; // Import the enrichment stage
+use backend::models::topology::Topology; // Import the topology model
+use serde_json::json;
+use std::fs;
+
+/// # Test: `test_site_enrichment`
+///
+/// This test verifies that a mapping file annotates the topology with the device site
+/// and each node with its own site, falling back to the device site.
+#[test]
+fn test_site_enrichment() {
+    let mapping_path = std::env::temp_dir().join("enrichment_test_sites.json");
+    fs::write(
+        &mapping_path,
+        json!({
+            "sites": {
+                "MAD01": {"id": "MAD01", "name": "Madrid", "latitude": 40.4168, "longitude": -3.7038},
+                "BCN01": {"id": "BCN01", "name": "Barcelona", "latitude": 41.3874, "longitude": 2.1686}
+            },
+            "hosts": {"10.95.87.21": "MAD01"},
+            "nodes": {"Barcelona": "BCN01"}
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let mapping = SiteMapping::from_file(&mapping_path).unwrap();
+    fs::remove_file(&mapping_path).ok();
+
+    let mut topology = Topology::from_value(
+        &json!({
+            "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+            "node": [
+                {"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "name": [{"value-name": "NODE_NAME", "value": "Barcelona"}]},
+                {"uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7"}
+            ]
+        }),
+        "10.95.87.21",
+    )
+    .unwrap();
+
+    enrich(&mut topology, &mapping);
+
+    assert_eq!(topology.site.as_ref().unwrap().id, "MAD01");
+    assert_eq!(topology.nodes[0].site.as_ref().unwrap().id, "BCN01");
+    assert_eq!(topology.nodes[1].site.as_ref().unwrap().id, "MAD01");
+
+    // Unknown hosts are left without annotations
+    let mut unknown = Topology::from_value(
+        &json!({"uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb", "node": [{"uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7"}]}),
+        "10.0.0.1",
+    )
+    .unwrap();
+    enrich(&mut unknown, &mapping);
+    assert!(unknown.site.is_none());
+    assert!(unknown.nodes[0].site.is_none());
+}
@@ -0,0 +1,110 @@
+use backend::models::equipment::{AccessPortRef, PhysicalContext}; // Import the equipment models
+use serde_json::{from_str, Value}; // Importing JSON deserialization utilities
+use uuid::Uuid; // For handling UUIDs (universally unique identifiers)
+
+/// Raw `tapi-equipment:physical-context` with one device, a card and a pluggable
+const RAW_PHYSICAL_CONTEXT: &str = r#"
+    {
+        "tapi-equipment:physical-context": {
+            "device": [
+                {
+                    "uuid": "d5a1e8c2-6f3b-4c1e-9a0e-1b2c3d4e5f60",
+                    "name": [{"value-name": "DEVICE_NAME", "value": "Barcelona"}],
+                    "equipment": [
+                        {
+                            "uuid": "0f9d5b6e-2a41-4f1c-8b57-6a1d2e3f4a5b",
+                            "name": [{"value-name": "EQUIPMENT_NAME", "value": "/ne=Barcelona/r=1/sh=3/sl=7"}],
+                            "category": "CIRCUIT_PACK",
+                            "actual-equipment": {
+                                "common-actual-properties": {"serial-number": "SN-0001"},
+                                "common-equipment-properties": {
+                                    "equipment-type-identifier": "PN-100G",
+                                    "manufacturer-name": "Ciena"
+                                }
+                            }
+                        },
+                        {
+                            "uuid": "7c3e1a2b-9d8f-4e6a-b5c4-d3e2f1a0b9c8",
+                            "category": "SMALL_FORMFACTOR_PLUGGABLE"
+                        }
+                    ],
+                    "access-port": [
+                        {
+                            "uuid": "a1b2c3d4-e5f6-4a7b-8c9d-0e1f2a3b4c5d",
+                            "connector-pin": [
+                                {"equipment-uuid": "0f9d5b6e-2a41-4f1c-8b57-6a1d2e3f4a5b", "connector-identification": "1"},
+                                {"equipment-uuid": "0f9d5b6e-2a41-4f1c-8b57-6a1d2e3f4a5b", "connector-identification": "2"}
+                            ]
+                        }
+                    ]
+                }
+            ]
+        }
+    }"#;
+
+/// # Test: `test_physical_context`
+///
+/// This test verifies that devices, equipment identifiers and access ports are parsed
+/// from a raw physical context.
+#[test]
+fn test_physical_context() {
+    let raw_value: Value = from_str(RAW_PHYSICAL_CONTEXT).unwrap_or_default();
+    let context = PhysicalContext::from_value(&raw_value, "127.0.0.1").unwrap();
+
+    assert_eq!(context.host, "127.0.0.1");
+    assert_eq!(context.devices.len(), 1);
+
+    let device = &context.devices[0];
+    assert_eq!(device.name.as_deref(), Some("Barcelona"));
+    assert_eq!(device.equipment.len(), 2);
+
+    let card = &device.equipment[0];
+    assert_eq!(card.category.as_deref(), Some("CIRCUIT_PACK"));
+    assert_eq!(card.serial_number.as_deref(), Some("SN-0001"));
+    assert_eq!(card.part_number.as_deref(), Some("PN-100G"));
+    assert_eq!(card.manufacturer.as_deref(), Some("Ciena"));
+
+    // Pins landing on the same equipment are collapsed
+    assert_eq!(device.access_ports[0].equipment_uuids, vec![card.uuid]);
+}
+
+/// # Test: `test_equipment_behind_node_edge_point`
+///
+/// This test resolves the hardware behind an owned node edge point through its
+/// supporting access port.
+#[test]
+fn test_equipment_behind_node_edge_point() {
+    let raw_value: Value = from_str(RAW_PHYSICAL_CONTEXT).unwrap_or_default();
+    let context = PhysicalContext::from_value(&raw_value, "127.0.0.1").unwrap();
+
+    let raw_node_edge_point = r#"
+        {
+            "uuid": "65a39427-3055-3ba4-9e15-0ebed4974577",
+            "tapi-equipment:supporting-access-port": {
+                "access-port": {
+                    "device-uuid": "d5a1e8c2-6f3b-4c1e-9a0e-1b2c3d4e5f60",
+                    "access-port-uuid": "a1b2c3d4-e5f6-4a7b-8c9d-0e1f2a3b4c5d"
+                }
+            }
+        }"#;
+    let node_edge_point_value: Value = from_str(raw_node_edge_point).unwrap_or_default();
+
+    let access_port = AccessPortRef::from_node_edge_point(&node_edge_point_value)
+        .unwrap()
+        .expect("Access port reference not found");
+    let equipment = context.equipment_for(&access_port);
+
+    assert_eq!(equipment.len(), 1);
+    assert_eq!(
+        equipment[0].uuid,
+        Uuid::parse_str("0f9d5b6e-2a41-4f1c-8b57-6a1d2e3f4a5b").unwrap()
+    );
+
+    // A node edge point without supporting access port has no hardware linkage
+    let bare_value: Value =
+        from_str(r#"{"uuid": "65a39427-3055-3ba4-9e15-0ebed4974577"}"#).unwrap();
+    assert_eq!(
+        AccessPortRef::from_node_edge_point(&bare_value).unwrap(),
+        None
+    );
+}
@@ -0,0 +1,108 @@
+use backend::events::store::EventStore; // Import the event log
+use backend::events::{ChangeEvent, ChangeKind, ObjectKind};
+use backend::pagination::CursorSigner;
+use backend::tenant::DEFAULT_TENANT;
+use backend::Error; // Import the custom error type from the backend module
+use chrono::{Duration, Local};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Returns a fresh event log path for a test
+fn events_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!(
+        "event_store_test_{}_{}.jsonl",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// Returns a change event dated `minutes_ago` minutes ago
+fn event(minutes_ago: i64) -> ChangeEvent {
+    ChangeEvent {
+        host: "127.0.0.1".to_string(),
+        topology_uuid: Uuid::nil(),
+        object: ObjectKind::Node,
+        uuid: Uuid::new_v4(),
+        change: ChangeKind::Added,
+        date: Local::now() - Duration::minutes(minutes_ago),
+    }
+}
+
+/// # Test: `test_event_store_replay_with_cursor`
+///
+/// This test verifies that a consumer pages through the log with cursors, picks up the
+/// events recorded while it was away, and that the log survives a restart.
+#[test]
+fn test_event_store_replay_with_cursor() {
+    let path = events_path("cursor");
+    let signer = CursorSigner::new("secret", Duration::minutes(5));
+
+    let mut store = EventStore::open(&path).unwrap();
+    for minutes_ago in [30, 20, 10] {
+        store.append(DEFAULT_TENANT, event(minutes_ago)).unwrap();
+    }
+
+    let first = store.replay(DEFAULT_TENANT, None, 2, &signer).unwrap();
+    assert_eq!(
+        first.events.iter().map(|e| e.id).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert!(first.has_more);
+
+    let cursor = first.next_cursor.unwrap();
+    let second = store
+        .replay(DEFAULT_TENANT, Some(&cursor), 2, &signer)
+        .unwrap();
+    assert_eq!(
+        second.events.iter().map(|e| e.id).collect::<Vec<_>>(),
+        vec![3]
+    );
+    assert!(!second.has_more);
+
+    // The consumer goes offline while a new event is recorded, then resumes
+    let cursor = second.next_cursor.unwrap();
+    drop(store);
+    let mut store = EventStore::open(&path).unwrap();
+    assert_eq!(store.len(), 3);
+    assert_eq!(store.append(DEFAULT_TENANT, event(0)).unwrap(), 4);
+
+    let resumed = store
+        .replay(DEFAULT_TENANT, Some(&cursor), 10, &signer)
+        .unwrap();
+    assert_eq!(
+        resumed.events.iter().map(|e| e.id).collect::<Vec<_>>(),
+        vec![4]
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// # Test: `test_event_store_replay_since_timestamp`
+///
+/// This test checks that a timestamp replays the events dated from that instant and
+/// that an invalid `since` is rejected.
+#[test]
+fn test_event_store_replay_since_timestamp() {
+    let signer = CursorSigner::new("secret", Duration::minutes(5));
+    let mut store = EventStore::new();
+    for minutes_ago in [30, 20, 10] {
+        store.append(DEFAULT_TENANT, event(minutes_ago)).unwrap();
+    }
+
+    let since = (Local::now() - Duration::minutes(25)).to_rfc3339();
+    let page = store
+        .replay(DEFAULT_TENANT, Some(&since), 10, &signer)
+        .unwrap();
+    assert_eq!(
+        page.events.iter().map(|e| e.id).collect::<Vec<_>>(),
+        vec![2, 3]
+    );
+
+    match store.replay(DEFAULT_TENANT, Some("yesterday"), 10, &signer) {
+        Err(Error::InvalidCursor(msg)) => assert_eq!(msg, "Invalid cursor"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(page) => panic!("Expected an error, but got {:?}", page),
+    }
+}
@@ -0,0 +1,65 @@
+use backend::enrichment::{enrich, SiteMapping}; // Import the enrichment stage
+use backend::export::geojson::to_geojson; // Import the exporters
+use backend::models::topology::Topology; // Import the topology model
+use serde_json::{from_value, json, Value};
+
+/// Parses a two-node, one-link topology
+fn topology() -> Topology {
+    Topology::from_value(
+        &json!({
+            "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+            "node": [
+                {"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "name": [{"value-name": "NODE_NAME", "value": "Madrid"}]},
+                {"uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7", "name": [{"value-name": "NODE_NAME", "value": "Barcelona"}]}
+            ],
+            "link": [{
+                "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+                "lifecycle-state": "INSTALLED",
+                "node-edge-point": [
+                    {"node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577", "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"},
+                    {"node-edge-point-uuid": "63366151-aeb4-3dfd-af66-d471b353aa1c", "node-uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7"}
+                ]
+            }]
+        }),
+        "10.95.87.21",
+    )
+    .unwrap()
+}
+
+/// # Test: `test_geojson_export`
+///
+/// This test verifies that enriched nodes are exported as points and links as lines
+/// between their endpoint sites.
+#[test]
+fn test_geojson_export() {
+    let mapping: SiteMapping = from_value(json!({
+        "sites": {
+            "MAD01": {"id": "MAD01", "name": "Madrid", "latitude": 40.4168, "longitude": -3.7038},
+            "BCN01": {"id": "BCN01", "name": "Barcelona", "latitude": 41.3874, "longitude": 2.1686}
+        },
+        "nodes": {"Madrid": "MAD01", "Barcelona": "BCN01"}
+    }))
+    .unwrap();
+
+    let mut enriched = topology();
+    enrich(&mut enriched, &mapping);
+
+    let geojson = to_geojson(&[enriched]);
+    assert_eq!(geojson["type"], "FeatureCollection");
+
+    let features = geojson["features"].as_array().unwrap();
+    assert_eq!(features.len(), 3);
+    assert_eq!(
+        features[0]["geometry"]["coordinates"],
+        json!([-3.7038, 40.4168])
+    );
+    assert_eq!(
+        features[2]["geometry"],
+        json!({"type": "LineString", "coordinates": [[-3.7038, 40.4168], [2.1686, 41.3874]]})
+    );
+    assert_eq!(features[2]["properties"]["lifecycle-state"], "INSTALLED");
+
+    // Without sites nothing can be placed on a map
+    let bare: Value = to_geojson(&[topology()]);
+    assert_eq!(bare["features"], json!([]));
+}
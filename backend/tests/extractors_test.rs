@@ -0,0 +1,48 @@
+use backend::extractors::{CustomObjectStore, ExtractorRegistry, ModelExtractor}; // Import the extractor plugins
+use backend::{Error, Result};
+use serde_json::{json, Value};
+
+/// Extracts the OTN performance counters of a payload
+struct OtnCounters;
+
+impl ModelExtractor for OtnCounters {
+    fn name(&self) -> &str {
+        "otn-counters"
+    }
+
+    fn extract(&self, _host: &str, payload: &Value) -> Result<Vec<Value>> {
+        payload
+            .get("otn-counters")
+            .and_then(Value::as_array)
+            .cloned()
+            .ok_or_else(|| Error::from("Not found otn counters"))
+    }
+}
+
+/// # Test: `test_extractor_registry`
+///
+/// This test verifies that registered extractors store their objects per device, that
+/// a failing extractor keeps its previous objects, and that names are unique.
+#[test]
+fn test_extractor_registry() {
+    let mut registry = ExtractorRegistry::new();
+    registry.register(OtnCounters).unwrap();
+    match registry.register(OtnCounters) {
+        Err(Error::Custom(msg)) => assert_eq!(msg, "Duplicated extractor otn-counters"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(()) => panic!("Expected an error, but got Ok"),
+    }
+
+    let mut store = CustomObjectStore::new();
+    let payload = json!({"otn-counters": [{"port": 1, "errored-seconds": 3}]});
+    assert_eq!(registry.run("10.0.0.1", &payload, &mut store).unwrap(), 1);
+    assert_eq!(registry.run("10.0.0.2", &payload, &mut store).unwrap(), 1);
+
+    // The failure is logged, the objects of the previous poll are kept
+    assert_eq!(registry.run("10.0.0.1", &json!({}), &mut store).unwrap(), 0);
+
+    let objects = store.query("otn-counters", Some("10.0.0.1"));
+    assert_eq!(objects.len(), 1);
+    assert_eq!(objects[0].object, json!({"port": 1, "errored-seconds": 3}));
+    assert_eq!(store.query("otn-counters", None).len(), 2);
+}
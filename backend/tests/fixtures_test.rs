@@ -0,0 +1,73 @@
+use backend::testsupport::fixtures::{check_golden, load_fixtures, parse_fixture}; // Import the fixture helpers
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+
+/// # Test: `test_fixtures_match_golden`
+///
+/// This test runs every vendor sample of `tests/fixtures` through the parsers and
+/// compares the normalized output with its golden file in `tests/golden`. New samples
+/// get their golden file by running the tests with `UPDATE_GOLDEN=1`.
+#[test]
+fn test_fixtures_match_golden() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let fixtures = load_fixtures(root.join("fixtures")).unwrap();
+    assert!(fixtures
+        .iter()
+        .any(|fixture| fixture.name == "generic/topology"));
+    assert_eq!(
+        fixtures
+            .iter()
+            .find(|fixture| fixture.name == "ciena/topology")
+            .and_then(|fixture| fixture.vendor.as_deref()),
+        Some("ciena")
+    );
+
+    for fixture in &fixtures {
+        let output = parse_fixture(fixture).unwrap();
+        if let Err(err) = check_golden(root.join("golden"), fixture, &output) {
+            panic!("{:?}", err);
+        }
+    }
+}
+
+/// # Test: `test_golden_mismatch`
+///
+/// This test checks that a missing golden file and an output that differs from its
+/// golden file are reported with the fixture name and the first differing line.
+#[test]
+fn test_golden_mismatch() {
+    let dir = std::env::temp_dir().join(format!("fixtures_test_{}", std::process::id()));
+    fs::create_dir_all(dir.join("fixtures/acme")).unwrap();
+    fs::write(
+        dir.join("fixtures/acme/sample.json"),
+        json!({"uuid": "00000000-0000-0000-0000-000000000001"}).to_string(),
+    )
+    .unwrap();
+    let fixtures = load_fixtures(dir.join("fixtures")).unwrap();
+    assert_eq!(fixtures.len(), 1);
+    let fixture = &fixtures[0];
+    assert_eq!(fixture.vendor.as_deref(), Some("acme"));
+
+    let output = parse_fixture(fixture).unwrap();
+    let golden = dir.join("golden");
+    match check_golden(&golden, fixture, &output) {
+        Err(Error::Custom(msg)) => assert!(msg.starts_with("Not found golden file")),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(()) => panic!("Expected an error, but got Ok"),
+    }
+
+    fs::create_dir_all(golden.join("acme")).unwrap();
+    fs::write(golden.join("acme/sample.json"), "[]\n").unwrap();
+    match check_golden(&golden, fixture, &output) {
+        Err(Error::Custom(msg)) => {
+            assert!(msg.starts_with("Fixture acme/sample differs from"));
+            assert!(msg.contains("at line 1: expected `[]`, got `[`"));
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(()) => panic!("Expected an error, but got Ok"),
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
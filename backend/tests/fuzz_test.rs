@@ -0,0 +1,150 @@
+use backend::models::connection::{Connection, ConnectivityService}; // Import the connection models
+use backend::models::device::Device; // Import the device model
+use backend::models::equipment::PhysicalContext; // Import the equipment model
+use backend::models::link::{Link, LinkRef}; // Import the link models
+use backend::models::node::Node; // Import the node model
+use backend::models::parse::{ParseMode, ParseOptions}; // Import the parse settings
+use backend::models::service_interface_point::ServiceInterfacePoint; // Import the SIP model
+use backend::models::topology::Topology; // Import the topology model
+use backend::models::vendor::VendorProfile; // Import the vendor profiles
+use backend::Error; // Import the custom error type from the backend module
+use proptest::prelude::*;
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+/// Field names of the TAPI payloads, so generated objects hit the parsers' branches
+const FIELDS: &[&str] = &[
+    "uuid",
+    "name",
+    "value",
+    "value-name",
+    "lifecycle-state",
+    "node",
+    "link",
+    "topology",
+    "tapi-topology:topology-context",
+    "node-edge-point",
+    "owned-node-edge-point",
+    "node-uuid",
+    "node-edge-point-uuid",
+    "topology-uuid",
+    "layer-protocol-qualifier",
+    "mapped-service-interface-point",
+    "service-interface-point-uuid",
+    "supporting-access-port",
+    "connection-end-point",
+    "route",
+    "lower-connection",
+    "connection-uuid",
+    "host",
+    "auth",
+    "username",
+    "password",
+    "tags",
+    "metadata",
+    "tapi-ciena-link-extensions:layer-protocol-qualifier",
+    "tapi-ciena-link-extensions:signal-content-type",
+];
+
+/// Generates scalars, biased towards UUIDs and lifecycle states
+fn scalar() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        any::<f64>().prop_map(Value::from),
+        ".{0,12}".prop_map(Value::String),
+        any::<u128>().prop_map(|uuid| Value::String(Uuid::from_u128(uuid).to_string())),
+        Just(Value::String("INSTALLED".to_string())),
+    ]
+}
+
+/// Generates TAPI-ish JSON: objects keyed by TAPI field names with missing fields,
+/// wrong types and nested extensions
+fn tapi_value() -> impl Strategy<Value = Value> {
+    scalar().prop_recursive(5, 64, 6, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+            prop::collection::vec((prop::sample::select(FIELDS), inner), 0..6).prop_map(|fields| {
+                Value::Object(
+                    fields
+                        .into_iter()
+                        .map(|(key, value)| (key.to_string(), value))
+                        .collect::<Map<String, Value>>(),
+                )
+            }),
+        ]
+    })
+}
+
+/// Fails unless `result` is a model or an error with a message
+fn assert_handled<T>(result: Result<T, Error>) -> Result<(), TestCaseError> {
+    if let Err(Error::Custom(msg)) = result {
+        prop_assert!(!msg.is_empty(), "Error without message");
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    /// # Test: `test_parsers_never_panic`
+    ///
+    /// This test feeds arbitrary TAPI-ish JSON to every object parser and checks that
+    /// each one returns either a model or a descriptive error instead of panicking.
+    #[test]
+    fn test_parsers_never_panic(value in tapi_value()) {
+        assert_handled(Node::from_value(&value, "10.0.0.1"))?;
+        assert_handled(Link::from_value(&value, "10.0.0.1"))?;
+        assert_handled(LinkRef::from_value(&value))?;
+        assert_handled(Topology::from_value(&value, "10.0.0.1"))?;
+        assert_handled(Topology::list_from_context(&value))?;
+        assert_handled(ServiceInterfacePoint::from_value(&value, "10.0.0.1"))?;
+        assert_handled(Connection::from_value(&value, "10.0.0.1"))?;
+        assert_handled(ConnectivityService::from_value(&value, "10.0.0.1"))?;
+        assert_handled(PhysicalContext::from_value(&value, "10.0.0.1"))?;
+        assert_handled(Device::from_value(&value))?;
+    }
+
+    /// # Test: `test_parse_modes_agree`
+    ///
+    /// This test checks on arbitrary topologies that a lenient parse never fails when a
+    /// strict parse succeeds, that it reports every skipped entry with a message, and that
+    /// a strict parse only succeeds when the lenient one had nothing to skip.
+    #[test]
+    fn test_parse_modes_agree(
+        uuid in any::<u128>(),
+        nodes in prop::collection::vec(tapi_value(), 0..4),
+        links in prop::collection::vec(tapi_value(), 0..4),
+    ) {
+        let topology = serde_json::json!({
+            "uuid": Uuid::from_u128(uuid),
+            "node": nodes,
+            "link": links,
+        });
+        let profiles = VendorProfile::builtin();
+        let lenient = ParseOptions {
+            profile: profiles.first(),
+            ..Default::default()
+        };
+        let strict = ParseOptions {
+            mode: ParseMode::Strict,
+            ..lenient
+        };
+
+        let (parsed, report) = match Topology::parse(&topology, "10.0.0.1", &lenient) {
+            Ok(parsed) => parsed,
+            Err(err) => return Err(TestCaseError::fail(format!("{:?}", err))),
+        };
+        prop_assert!(report.issues.iter().all(|issue| !issue.error.is_empty()));
+        prop_assert_eq!(
+            parsed.nodes.len() + parsed.links.len() + report.issues.len(),
+            nodes.len() + links.len()
+        );
+        match Topology::parse(&topology, "10.0.0.1", &strict) {
+            Ok(_) => prop_assert!(report.issues.is_empty()),
+            Err(Error::BadResponse(msg)) => prop_assert_eq!(&msg, &report.issues[0].error),
+            Err(err) => panic!("Unexpected error {:?}", err),
+        }
+    }
+}
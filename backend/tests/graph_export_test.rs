@@ -0,0 +1,73 @@
+use backend::export::graph::{render, to_json, GraphFormat}; // Import the graph export
+use backend::models::topology::Topology; // Import the topology model
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::json;
+
+/// Parses a topology of two nodes joined by one link
+fn topology() -> Topology {
+    let value = json!({
+        "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+        "node": [
+            {"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "name": [{"value-name": "NODE_NAME", "value": "Madrid \"core\""}]},
+            {"uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7", "name": [{"value-name": "NODE_NAME", "value": "R&D <lab>"}]}
+        ],
+        "link": [{
+            "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+            "node-edge-point": [
+                {"node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577", "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"},
+                {"node-edge-point-uuid": "63366151-aeb4-3dfd-af66-d471b353aa1c", "node-uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7"}
+            ]
+        }]
+    });
+    Topology::from_value(&value, "127.0.0.1").unwrap()
+}
+
+/// # Test: `test_graph_export_formats`
+///
+/// This test verifies that nodes and links are rendered in every format, with names
+/// escaped for DOT and GraphML.
+#[test]
+fn test_graph_export_formats() {
+    let topology = topology();
+
+    let dot = render(&topology, GraphFormat::Dot);
+    assert!(dot.starts_with("graph \"4e537278-79f8-39ad-804b-f0b553cb2ffb\" {"));
+    assert!(
+        dot.contains("\"62d11f13-db6c-3398-8a83-5fac0b2b7476\" [label=\"Madrid \\\"core\\\"\"];")
+    );
+    assert!(dot.contains(
+        "\"62d11f13-db6c-3398-8a83-5fac0b2b7476\" -- \"7b0c973a-996a-3409-ad2f-d173354bfdb7\""
+    ));
+
+    let graphml = render(&topology, GraphFormat::Graphml);
+    assert!(graphml.contains("<data key=\"name\">R&amp;D &lt;lab&gt;</data>"));
+    assert!(graphml.contains("<edge id=\"14219539-208b-35f5-b7cf-35a58e083490\" source=\"62d11f13-db6c-3398-8a83-5fac0b2b7476\" target=\"7b0c973a-996a-3409-ad2f-d173354bfdb7\"/>"));
+
+    let graph = to_json(&topology);
+    assert_eq!(graph["nodes"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        graph["links"][0],
+        json!({
+            "id": "14219539-208b-35f5-b7cf-35a58e083490",
+            "source": "62d11f13-db6c-3398-8a83-5fac0b2b7476",
+            "target": "7b0c973a-996a-3409-ad2f-d173354bfdb7"
+        })
+    );
+}
+
+/// # Test: `test_graph_format_parsing`
+///
+/// This test checks that the `format` parameter is parsed and unknown formats are rejected.
+#[test]
+fn test_graph_format_parsing() {
+    assert_eq!(
+        "graphml".parse::<GraphFormat>().unwrap(),
+        GraphFormat::Graphml
+    );
+
+    match "svg".parse::<GraphFormat>() {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid export format svg".to_string()),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(format) => panic!("Expected an error, but got {:?}", format),
+    }
+}
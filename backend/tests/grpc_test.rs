@@ -0,0 +1,230 @@
+use backend::api::auth::hash; // Import the hash of the configured API tokens
+use backend::api::grpc::proto::device_service_client::DeviceServiceClient; // Import the generated gRPC clients
+use backend::api::grpc::proto::event_service_client::EventServiceClient;
+use backend::api::grpc::proto::topology_service_client::TopologyServiceClient;
+use backend::api::grpc::proto::{
+    GetSchedulerRequest, GetTopologyRequest, ListDevicesRequest, SubscribeRequest,
+};
+use backend::api::grpc::{change_event, event_message, serve, topology_message};
+use backend::events::{ChangeEvent, ChangeKind, ObjectKind};
+use backend::models::lifecycle_state::LifecycleState;
+use backend::models::topology::Topology;
+use backend::setup::app::{App, AppSettings};
+use chrono::Local;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Wraps a message in a request authenticated with `token`
+fn authorized<T>(message: T, token: &str) -> tonic::Request<T> {
+    let mut request = tonic::Request::new(message);
+    request.metadata_mut().insert(
+        "authorization",
+        format!("Bearer {}", token).parse().unwrap(),
+    );
+    request
+}
+
+/// # Test: `test_grpc_api`
+///
+/// This test verifies that the gRPC API serves the devices of a running application to
+/// the tenant of the token of a call, that calls without a known token are answered
+/// `UNAUTHENTICATED`, that a device not collected yet or of another tenant is answered
+/// `NOT_FOUND`, and that the queue of an event stream goes away with the stream.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_grpc_api() {
+    let directory = std::env::temp_dir().join(format!("grpc_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let settings = AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        ..Default::default()
+    };
+    std::fs::write(
+        &settings.config,
+        format!(
+            "poll_interval = 60\n\n\
+             [[api.tokens]]\ntenant_id = \"default\"\nsha256 = \"{}\"\n\n\
+             [[api.tokens]]\ntenant_id = \"acme\"\nsha256 = \"{}\"\n",
+            hash("default-token"),
+            hash("acme-token")
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        &settings.devices,
+        r#"[
+            {"host": "127.0.0.1", "auth": {"username": "tapi", "password": "secret"}},
+            {"host": "127.0.0.2", "auth": {"username": "tapi", "password": "secret"}, "enabled": false},
+            {"host": "127.0.0.3", "auth": {"username": "tapi", "password": "secret"}, "tenant_id": "acme"}
+        ]"#,
+    )
+    .unwrap();
+    let app = Arc::new(App::start(&settings).unwrap());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(serve(Arc::clone(&app), listener));
+
+    let mut devices = DeviceServiceClient::connect(url.clone()).await.unwrap();
+    for request in [
+        tonic::Request::new(ListDevicesRequest {}),
+        authorized(ListDevicesRequest {}, "unknown-token"),
+    ] {
+        let status = devices.list_devices(request).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    }
+    let response = devices
+        .list_devices(authorized(ListDevicesRequest {}, "default-token"))
+        .await
+        .unwrap()
+        .into_inner();
+    let hosts: Vec<&str> = response
+        .devices
+        .iter()
+        .map(|device| device.host.as_str())
+        .collect();
+    assert_eq!(hosts, ["127.0.0.1", "127.0.0.2"]);
+    assert_eq!(response.devices[0].address.as_deref(), Some("127.0.0.1"));
+    // Disabled devices have no collector
+    assert_eq!(response.devices[1].collector_state, None);
+    let scheduler = devices
+        .get_scheduler(authorized(GetSchedulerRequest {}, "default-token"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(scheduler.collectors, 1);
+    assert_eq!(scheduler.devices.len(), 2);
+    assert_eq!(scheduler.devices[1].phase, "disabled");
+    // Another tenant only sees its own devices
+    let response = devices
+        .list_devices(authorized(ListDevicesRequest {}, "acme-token"))
+        .await
+        .unwrap()
+        .into_inner();
+    let hosts: Vec<&str> = response
+        .devices
+        .iter()
+        .map(|device| device.host.as_str())
+        .collect();
+    assert_eq!(hosts, ["127.0.0.3"]);
+    let scheduler = devices
+        .get_scheduler(authorized(GetSchedulerRequest {}, "acme-token"))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(scheduler.collectors, 1);
+    assert!(scheduler
+        .devices
+        .iter()
+        .all(|device| device.host == "127.0.0.3"));
+
+    let mut topology = TopologyServiceClient::connect(url.clone()).await.unwrap();
+    for (host, token) in [("127.0.0.1", "default-token"), ("127.0.0.1", "acme-token")] {
+        let request = GetTopologyRequest {
+            host: host.to_string(),
+            lifecycle_state: None,
+        };
+        let status = topology
+            .get_topology(authorized(request, token))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        assert_eq!(
+            status.message(),
+            "TOPOLOGY_NOT_FOUND: Not found topology of 127.0.0.1"
+        );
+    }
+
+    // Every stream has its own queue, removed once the client hangs up
+    let grpc_queues = || {
+        app.scheduler()
+            .queues
+            .bus
+            .into_iter()
+            .filter(|queue| queue.name.starts_with("grpc:"))
+            .count()
+    };
+    let mut events = EventServiceClient::connect(url).await.unwrap();
+    let status = events
+        .subscribe(SubscribeRequest { hosts: vec![] })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unauthenticated);
+    let first = events
+        .subscribe(authorized(SubscribeRequest { hosts: vec![] }, "acme-token"))
+        .await
+        .unwrap();
+    let second = events
+        .subscribe(authorized(SubscribeRequest { hosts: vec![] }, "acme-token"))
+        .await
+        .unwrap();
+    assert_eq!(grpc_queues(), 2);
+    drop(first);
+    drop(second);
+    for _ in 0..50 {
+        if grpc_queues() == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(grpc_queues(), 0);
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_grpc_messages`
+///
+/// This test checks that topologies and events are converted into messages with the
+/// values of the JSON API, and that event messages are read back into the same event.
+#[test]
+fn test_grpc_messages() {
+    let value = json!({
+        "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+        "node": [{"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "lifecycle-state": "INSTALLED"}],
+        "link": [{
+            "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+            "direction": "UNIDIRECTIONAL",
+            "node-edge-point": [
+                {"node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577"},
+                {"node-uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7", "node-edge-point-uuid": "63366151-aeb4-3dfd-af66-d471b353aa1c"}
+            ]
+        }]
+    });
+    let message = topology_message(&Topology::from_value(&value, "10.0.0.1").unwrap());
+    assert_eq!(message.uuid, "4e537278-79f8-39ad-804b-f0b553cb2ffb");
+    assert_eq!(
+        message.nodes[0].lifecycle_state.as_deref(),
+        Some("INSTALLED")
+    );
+    assert_eq!(
+        message.links[0].direction.as_deref(),
+        Some("UNIDIRECTIONAL")
+    );
+    assert_eq!(
+        message.links[0].node_edge_points[1].node_uuid,
+        "7b0c973a-996a-3409-ad2f-d173354bfdb7"
+    );
+
+    let event = ChangeEvent {
+        host: "10.0.0.1".to_string(),
+        topology_uuid: Uuid::nil(),
+        object: ObjectKind::Link,
+        uuid: Uuid::nil(),
+        change: ChangeKind::LifecycleTransition {
+            from: Some(LifecycleState::Planned),
+            to: Some(LifecycleState::Installed),
+        },
+        date: Local::now(),
+    };
+    let message = event_message(&event);
+    assert_eq!(message.object, "link");
+    assert_eq!(message.change, "lifecycle_transition");
+    assert_eq!(message.from.as_deref(), Some("PLANNED"));
+    assert_eq!(message.to.as_deref(), Some("INSTALLED"));
+    assert_eq!(message.rule, None);
+    // Clients read the event back
+    assert_eq!(change_event(&message).unwrap(), event);
+}
@@ -0,0 +1,64 @@
+use backend::models::{hash::ContentHash, link::Link}; // Import the content hash and a hashed model
+use serde_json::json;
+use sha2::{Digest, Sha256}; // Import SHA-256 to hash the serialized payload directly
+
+/// # Test: `test_content_hash_stable`
+///
+/// This test pins the hash of a known payload: the value is the truncated SHA-256 of the
+/// canonical payload, so it must never change between runs, processes or releases.
+#[test]
+fn test_content_hash_stable() {
+    let value = json!({"uuid": "14219539-208b-35f5-b7cf-35a58e083490", "node-edge-point": []});
+
+    let hash = ContentHash::of(&value);
+    assert_eq!(hash.get(), 0x67bf1a77919bf25b);
+    assert_eq!(hash.to_string(), "67bf1a77919bf25b");
+
+    // Models hash the whole payload the same way
+    let link = Link::from_value(&value, "127.0.0.1").unwrap();
+    assert_eq!(link.hash, hash);
+}
+
+/// # Test: `test_content_hash_serialization`
+///
+/// This test checks that the hash is persisted as a plain number and read back unchanged.
+#[test]
+fn test_content_hash_serialization() {
+    let hash = ContentHash::from(7475723007304659547);
+
+    assert_eq!(serde_json::to_string(&hash).unwrap(), "7475723007304659547");
+    assert_eq!(
+        serde_json::from_str::<ContentHash>("7475723007304659547").unwrap(),
+        hash
+    );
+}
+
+/// # Test: `test_content_hash_canonical`
+///
+/// This test pins the hash of a payload with unsorted keys, escaped strings and numbers,
+/// whose canonical form is written without building the sorted value first, and checks
+/// that a payload longer than the chunks it is hashed in matches the SHA-256 of its
+/// compact serialization.
+#[test]
+fn test_content_hash_canonical() {
+    let value = json!({
+        "uuid": "x",
+        "name": [{"value-name": "LINK_NAME", "value": "LINK \"A\"\n\\B"}],
+        "cost": [1.5, -3, 10, true, null],
+        "a": {"z": 1, "b": "é"}
+    });
+
+    assert_eq!(ContentHash::of(&value).to_string(), "2835d521ac6d4566");
+
+    // Sorted keys, so the compact serialization is the canonical form
+    let value = json!({
+        "a": "x".repeat(3000),
+        "b": (0..500).map(|index| json!({"index": index, "name": "LINK"})).collect::<Vec<_>>(),
+        "c": "y".repeat(700)
+    });
+    let digest = Sha256::digest(serde_json::to_vec(&value).unwrap());
+    assert_eq!(
+        ContentHash::of(&value).get(),
+        u64::from_be_bytes(digest[..8].try_into().unwrap())
+    );
+}
@@ -0,0 +1,1503 @@
+use backend::agents::AgentRegistry;
+use backend::api::auth::hash; // Import the hash of the configured API tokens
+use backend::api::codes::ErrorBody;
+use backend::api::http::serve; // Import the HTTP API served by the backend
+use backend::events::store::EventStore;
+use backend::events::{ChangeEvent, ChangeKind, ObjectKind};
+use backend::ingest::ObjectStore;
+use backend::latency::{LatencyProbe, LatencySample, LatencyStore};
+use backend::models::topology::Topology;
+use backend::setup::app::{App, AppSettings};
+use backend::stats::{CycleStats, StatsStore};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Serves an application with a device tagged `core` in the default tenant and another
+/// in `acme`, the token `ops-token` of the default tenant being named `ops`
+///
+/// # Returns
+/// The application, the URL it is served at, the server task and its directory
+async fn start(
+    name: &str,
+    settings: impl FnOnce(&Path) -> AppSettings,
+) -> (
+    Arc<App>,
+    String,
+    tokio::task::JoinHandle<backend::Result<()>>,
+    PathBuf,
+) {
+    let directory = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let settings = settings(&directory);
+    std::fs::write(
+        &settings.config,
+        format!(
+            "poll_interval = 60\n\n\
+             [[api.tokens]]\ntenant_id = \"default\"\nname = \"ops\"\nsha256 = \"{}\"\n\n\
+             [[api.tokens]]\ntenant_id = \"acme\"\nsha256 = \"{}\"\n",
+            hash("ops-token"),
+            hash("acme-token")
+        ),
+    )
+    .unwrap();
+    std::fs::write(
+        &settings.devices,
+        r#"[
+            {"host": "127.0.0.1", "auth": {"username": "tapi", "password": "secret"}, "tags": ["core"]},
+            {"host": "127.0.0.3", "auth": {"username": "tapi", "password": "secret"}, "tags": ["edge"], "tenant_id": "acme"}
+        ]"#,
+    )
+    .unwrap();
+    let app = Arc::new(App::start(&settings).unwrap());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(serve(Arc::clone(&app), listener));
+    (app, url, server, directory)
+}
+
+/// Returns the settings of an application in `directory`
+fn settings(directory: &Path) -> AppSettings {
+    AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        ..Default::default()
+    }
+}
+
+/// Stores a topology of `host` with two nodes joined by a link in the object journal of
+/// `directory`, served after the start
+fn store_topology(directory: &Path, host: &str) {
+    let topology = Topology::from_value(
+        &json!({
+            "uuid": Uuid::from_u128(1),
+            "node": [{"uuid": Uuid::from_u128(2)}, {"uuid": Uuid::from_u128(3)}],
+            "link": [{
+                "uuid": Uuid::from_u128(4),
+                "node-edge-point": [
+                    {"node-uuid": Uuid::from_u128(2), "node-edge-point-uuid": Uuid::from_u128(5)},
+                    {"node-uuid": Uuid::from_u128(3), "node-edge-point-uuid": Uuid::from_u128(6)}
+                ]
+            }]
+        }),
+        host,
+    )
+    .unwrap();
+    ObjectStore::open(directory.join("objects.jsonl"))
+        .unwrap()
+        .ingest(host, &[topology])
+        .unwrap();
+}
+
+/// Waits until the job at `location` is done and returns it
+async fn finished(client: &reqwest::Client, url: &str, location: &str, token: &str) -> Value {
+    for _ in 0..100 {
+        let job: Value = client
+            .get(format!("{}{}", url, location))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if job["status"] != "queued" && job["status"] != "running" {
+            return job;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("Job {} did not finish", location);
+}
+
+/// # Test: `test_http_api`
+///
+/// This test verifies that the HTTP API serves the devices of a running application to
+/// the tenant of the token of a request, that requests without a known token are
+/// answered `401`, that a device not collected yet or of another tenant is `404`, an
+/// unknown lifecycle state filter `400`, and that every response carries the request
+/// id, also written in error bodies.
+/// Cross-origin requests follow the `[cors]` section of the config, and other paths
+/// are answered with the frontend of the `[frontend]` section. The change events are
+/// streamed to the tenant as Server-Sent Events, and the readiness needs no token.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_api() {
+    let directory = std::env::temp_dir().join(format!("http_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let settings = AppSettings {
+        config: directory.join("config.toml"),
+        devices: directory.join("devices.json"),
+        ..Default::default()
+    };
+    std::fs::write(
+        &settings.config,
+        format!(
+            "poll_interval = 60\n\n\
+             [frontend]\ndirectory = {:?}\n\n\
+             [cors]\nallowed_origins = [\"https://noc.example.com\"]\n\n\
+             [[api.tokens]]\ntenant_id = \"default\"\nsha256 = \"{}\"\n\n\
+             [[api.tokens]]\ntenant_id = \"acme\"\nsha256 = \"{}\"\n",
+            directory.join("dist"),
+            hash("default-token"),
+            hash("acme-token")
+        ),
+    )
+    .unwrap();
+    std::fs::create_dir_all(directory.join("dist")).unwrap();
+    std::fs::write(directory.join("dist/index.html"), "<html></html>").unwrap();
+    std::fs::write(
+        directory.join("dist/frontend-4c2a81f0e1b3d9a7.js"),
+        "main();",
+    )
+    .unwrap();
+    std::fs::write(
+        &settings.devices,
+        r#"[
+            {"host": "127.0.0.1", "auth": {"username": "tapi", "password": "secret"}},
+            {"host": "127.0.0.3", "auth": {"username": "tapi", "password": "secret"}, "tenant_id": "acme"}
+        ]"#,
+    )
+    .unwrap();
+    let app = Arc::new(App::start(&settings).unwrap());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let server = tokio::spawn(serve(Arc::clone(&app), listener));
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{}/devices", url))
+        .header("x-request-id", "report-42")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    assert_eq!(response.headers()["x-request-id"], "report-42");
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.code.as_str(), "UNAUTHORIZED");
+    assert_eq!(body.message, "Missing API token");
+    assert_eq!(body.request_id.unwrap().as_str(), "report-42");
+
+    // Without a usable id, one is generated and sent back
+    let response = client
+        .get(format!("{}/devices/127.0.0.1/topology", url))
+        .header("x-request-id", "bad id")
+        .bearer_auth("default-token")
+        .send()
+        .await
+        .unwrap();
+    let request_id = response.headers()["x-request-id"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert_ne!(request_id, "bad id");
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.request_id.unwrap().as_str(), request_id);
+
+    for (token, expected) in [("default-token", "127.0.0.1"), ("acme-token", "127.0.0.3")] {
+        let devices: Vec<Value> = client
+            .get(format!("{}/devices", url))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let hosts: Vec<&str> = devices
+            .iter()
+            .filter_map(|device| device["host"].as_str())
+            .collect();
+        assert_eq!(hosts, [expected]);
+    }
+    let scheduler: Value = client
+        .get(format!("{}/admin/scheduler", url))
+        .bearer_auth("acme-token")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(scheduler["collectors"], 1);
+
+    // Another tenant's device is answered like a device not collected yet
+    for token in ["default-token", "acme-token"] {
+        let response = client
+            .get(format!("{}/devices/127.0.0.1/topology", url))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+        let body: ErrorBody = response.json().await.unwrap();
+        assert_eq!(body.message, "Not found topology of 127.0.0.1");
+    }
+
+    let response = client
+        .get(format!(
+            "{}/devices/127.0.0.1/topology?lifecycle_state=RETIRED",
+            url
+        ))
+        .bearer_auth("default-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.message, "Invalid lifecycle state RETIRED");
+
+    // Jobs are only seen by their tenant, none was queued yet
+    let jobs: Vec<Value> = client
+        .get(format!("{}/jobs", url))
+        .bearer_auth("acme-token")
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(jobs.is_empty());
+    for (id, status) in [
+        ("6b3c8a8e-0f4e-4a55-9d6e-2a7c1f0e9b11", 404),
+        ("first", 400),
+    ] {
+        let response = client
+            .get(format!("{}/jobs/{}", url, id))
+            .bearer_auth("acme-token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status);
+    }
+
+    let codes: Vec<Value> = client
+        .get(format!("{}/meta/error-codes", url))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(codes.len(), 12);
+
+    // Nothing was stored before the start, so there is nothing to warm-load
+    let response = client.get(format!("{}/ready", url)).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    let readiness: Value = response.json().await.unwrap();
+    assert_eq!(readiness["ready"], true);
+
+    // Preflights are answered with the policy headers for allowed origins only
+    for (origin, allowed) in [
+        ("https://noc.example.com", true),
+        ("https://evil.example.com", false),
+    ] {
+        let response = client
+            .request(reqwest::Method::OPTIONS, format!("{}/devices", url))
+            .header("origin", origin)
+            .header("access-control-request-method", "GET")
+            .header("access-control-request-headers", "authorization")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 204);
+        let headers = response.headers();
+        assert_eq!(
+            headers
+                .get("access-control-allow-origin")
+                .map(|value| value.to_str().unwrap()),
+            allowed.then_some(origin)
+        );
+        assert_eq!(
+            headers.contains_key("access-control-allow-methods"),
+            allowed
+        );
+    }
+    let response = client
+        .get(format!("{}/devices", url))
+        .header("origin", "https://noc.example.com")
+        .bearer_auth("default-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["access-control-allow-origin"],
+        "https://noc.example.com"
+    );
+    // Requests without an origin get no CORS headers
+    let response = client
+        .get(format!("{}/meta/error-codes", url))
+        .send()
+        .await
+        .unwrap();
+    assert!(!response
+        .headers()
+        .contains_key("access-control-allow-origin"));
+
+    let response = client
+        .get(format!("{}/events/stream", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    let response = client
+        .get(format!("{}/events/stream", url))
+        .header("last-event-id", "first")
+        .bearer_auth("acme-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.message, "Invalid Last-Event-ID first");
+    let response = client
+        .get(format!("{}/events/stream", url))
+        .header("last-event-id", "0")
+        .bearer_auth("acme-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    drop(response);
+
+    // The frontend is served with its cache headers, its routes get `index.html`
+    for (path, body, cache_control) in [
+        ("/", "<html></html>", "no-cache"),
+        ("/devices/10.0.0.1", "<html></html>", "no-cache"),
+        (
+            "/frontend-4c2a81f0e1b3d9a7.js",
+            "main();",
+            "public, max-age=31536000, immutable",
+        ),
+    ] {
+        let response = client.get(format!("{}{}", url, path)).send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["cache-control"], cache_control);
+        assert_eq!(response.text().await.unwrap(), body);
+    }
+    let response = client
+        .get(format!("{}/missing.js", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_bulk_actions`
+///
+/// This test verifies that `POST /actions/bulk` queues a job over the tagged devices of
+/// the tenant of the token only, answered `202` with the job to follow at its location,
+/// that every action is audited under the name of the token and listed by `GET /audit`
+/// to that tenant only, and that a malformed body is answered `400`.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_bulk_actions() {
+    let (app, url, server, _) = start("http_bulk_test", settings).await;
+    let client = reqwest::Client::new();
+
+    // The tag exists, but only on a device of another tenant
+    let response = client
+        .post(format!("{}/actions/bulk", url))
+        .bearer_auth("acme-token")
+        .json(&json!({"tag": "core", "action": "pause-polling"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.message, "Not found devices tagged core");
+
+    let response = client
+        .post(format!("{}/actions/bulk", url))
+        .bearer_auth("ops-token")
+        .json(&json!({"tag": "core", "action": "reboot"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = client
+        .post(format!("{}/actions/bulk", url))
+        .bearer_auth("ops-token")
+        .json(&json!({"tag": "core", "action": "pause-polling"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 202);
+    let location = response.headers()["location"].to_str().unwrap().to_string();
+    let job: Value = response.json().await.unwrap();
+    assert_eq!(location, format!("/jobs/{}", job["id"].as_str().unwrap()));
+
+    let job = finished(&client, &url, &location, "ops-token").await;
+    assert_eq!(job["status"], "done");
+    assert_eq!(job["result"]["devices"][0]["host"], "127.0.0.1");
+    let response = client
+        .get(format!("{}{}", url, location))
+        .bearer_auth("acme-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let entries = app.audit(&Default::default());
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].actor, "ops");
+
+    // The audit log is only seen by the tenant of the paused device
+    for (token, query, expected) in [
+        ("ops-token", "", 1),
+        ("ops-token", "?actor=ops&action=polling_paused", 1),
+        ("ops-token", "?action=poll_triggered", 0),
+        ("acme-token", "", 0),
+    ] {
+        let response = client
+            .get(format!("{}/audit{}", url, query))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let entries: Vec<Value> = response.json().await.unwrap();
+        assert_eq!(entries.len(), expected, "{} {}", token, query);
+        if let Some(entry) = entries.first() {
+            assert_eq!(entry["target"], "127.0.0.1");
+            assert_eq!(entry["action"], "polling_paused");
+        }
+    }
+    let response = client
+        .get(format!("{}/audit?action=reboot", url))
+        .bearer_auth("ops-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_event_replay`
+///
+/// This test verifies that `GET /events` replays the change events of the tenant of the
+/// token from a timestamp or page by page with the signed `next_cursor`, and that a
+/// cursor of another tenant or a forged one is answered `400`.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_event_replay() {
+    let (_, url, server, _) = start("http_replay_test", |directory| {
+        let mut events = EventStore::open(directory.join("events.jsonl")).unwrap();
+        for (tenant_id, host) in [
+            ("default", "127.0.0.1"),
+            ("acme", "127.0.0.3"),
+            ("default", "127.0.0.1"),
+            ("default", "127.0.0.1"),
+        ] {
+            let event = ChangeEvent {
+                host: host.to_string(),
+                topology_uuid: Uuid::nil(),
+                object: ObjectKind::Link,
+                uuid: Uuid::new_v4(),
+                change: ChangeKind::Added,
+                date: chrono::Local::now(),
+            };
+            events.append(tenant_id, event).unwrap();
+        }
+        AppSettings {
+            events: Some(directory.join("events.jsonl")),
+            ..settings(directory)
+        }
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let page = |token: &'static str, query: String| {
+        let request = client
+            .get(format!("{}/events{}", url, query))
+            .bearer_auth(token);
+        async move { request.send().await.unwrap() }
+    };
+
+    let first: Value = page("ops-token", "?limit=2".to_string())
+        .await
+        .json()
+        .await
+        .unwrap();
+    let ids: Vec<u64> = first["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, [1, 3]);
+    assert_eq!(first["has_more"], true);
+    let cursor = first["next_cursor"].as_str().unwrap();
+
+    let second: Value = page("ops-token", format!("?since={}", cursor))
+        .await
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(second["events"].as_array().unwrap().len(), 1);
+    assert_eq!(second["events"][0]["id"], 4);
+    assert_eq!(second["has_more"], false);
+
+    let since = (chrono::Local::now() - chrono::Duration::hours(1)).to_rfc3339();
+    let acme: Value = page(
+        "acme-token",
+        format!("?since={}", since.replace('+', "%2B")),
+    )
+    .await
+    .json()
+    .await
+    .unwrap();
+    assert_eq!(acme["events"].as_array().unwrap().len(), 1);
+    assert_eq!(acme["events"][0]["host"], "127.0.0.3");
+
+    for cursor in [cursor, "forged.cursor"] {
+        let response = page("acme-token", format!("?since={}", cursor)).await;
+        assert_eq!(response.status(), 400);
+        let body: ErrorBody = response.json().await.unwrap();
+        assert_eq!(body.code.as_str(), "INVALID_CURSOR");
+    }
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_stats_and_metrics`
+///
+/// This test verifies that the poll statistics and the latency of a device are served
+/// to its tenant only, and that `GET /metrics` renders the Prometheus metrics of the
+/// devices of the tenant of the token.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_stats_and_metrics() {
+    let (_, url, server, _) = start("http_stats_test", |directory| {
+        let mut stats = StatsStore::open(directory.join("stats.json")).unwrap();
+        let mut latency = LatencyStore::open(directory.join("latency.jsonl")).unwrap();
+        for host in ["127.0.0.1", "127.0.0.3"] {
+            let cycle = CycleStats {
+                collected_at: chrono::Local::now(),
+                parsed: 12,
+                skipped: 0,
+                duration_ms: 250,
+                payload_bytes: 4096,
+                http_latency_ms: 40,
+            };
+            stats.record(host, cycle).unwrap();
+            let sample = LatencySample {
+                at: chrono::Local::now() - chrono::Duration::hours(2),
+                probe: LatencyProbe::Tcp,
+                latency_ms: Some(4.0),
+            };
+            latency.record(host, sample).unwrap();
+        }
+        AppSettings {
+            stats: Some(directory.join("stats.json")),
+            latency: Some(directory.join("latency.jsonl")),
+            ..settings(directory)
+        }
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let get = |path: &str, token: &str| {
+        client
+            .get(format!("{}{}", url, path))
+            .bearer_auth(token)
+            .send()
+    };
+
+    let stats: Value = get("/devices/127.0.0.1/stats", "ops-token")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(stats["host"], "127.0.0.1");
+    assert_eq!(stats["cycles"][0]["parsed"], 12);
+    for path in ["/devices/127.0.0.3/stats", "/devices/127.0.0.3/latency"] {
+        let response = get(path, "ops-token").await.unwrap();
+        assert_eq!(response.status(), 404);
+    }
+
+    // The stored sample, and those probed since the start
+    let day: Value = get("/devices/127.0.0.1/latency", "ops-token")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(day["samples"][0]["latency_ms"], 4.0);
+    let hour: Value = get("/devices/127.0.0.1/latency?window=1h", "ops-token")
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let stored = &day["samples"][0]["at"];
+    assert!(hour["samples"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|sample| &sample["at"] != stored));
+    let response = get("/devices/127.0.0.1/latency?window=soon", "ops-token")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = get("/metrics", "acme-token").await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.headers()["content-type"]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+    let metrics = response.text().await.unwrap();
+    assert!(metrics.contains("poll_objects_parsed{host=\"127.0.0.3\"} 12"));
+    assert!(metrics.contains("# TYPE device_latency_seconds histogram"));
+    assert!(metrics.contains("event_bus_published_total"));
+    assert!(!metrics.contains("127.0.0.1"));
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_exports`
+///
+/// This test verifies that the export of a topology is queued as a job of the tenant,
+/// and that its artifact is downloaded whole, resumed from a `Range` while `If-Range`
+/// matches, answered `416` with its size beyond its end, and hidden from other tenants.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_exports() {
+    let (_, url, server, _) = start("http_exports_test", |directory| {
+        store_topology(directory, "127.0.0.1");
+        AppSettings {
+            objects: Some(directory.join("objects.jsonl")),
+            exports: Some(directory.join("exports")),
+            ..settings(directory)
+        }
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    for (host, format, status) in [
+        ("127.0.0.1", "png", 400),
+        ("127.0.0.3", "dot", 404),
+        ("127.0.0.1", "dot", 202),
+    ] {
+        let response = client
+            .post(format!("{}/devices/{}/export?format={}", url, host, format))
+            .bearer_auth("ops-token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status, "{} {}", host, format);
+    }
+    let response = client
+        .post(format!("{}/devices/127.0.0.1/export", url))
+        .bearer_auth("ops-token")
+        .send()
+        .await
+        .unwrap();
+    let location = response.headers()["location"].to_str().unwrap().to_string();
+    let job = finished(&client, &url, &location, "ops-token").await;
+    assert_eq!(job["status"], "done");
+    let size = job["result"]["size"].as_u64().unwrap();
+    let etag = job["result"]["etag"].as_str().unwrap().to_string();
+    let export = format!("{}/exports/{}", url, job["id"].as_str().unwrap());
+
+    let response = client
+        .get(&export)
+        .bearer_auth("ops-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/graphml+xml"
+    );
+    assert_eq!(response.headers()["accept-ranges"], "bytes");
+    assert_eq!(response.headers()["etag"], etag.as_str());
+    let whole = response.bytes().await.unwrap();
+    assert_eq!(whole.len() as u64, size);
+    assert!(String::from_utf8_lossy(&whole).contains("graphml"));
+
+    // The link dropped after 10 bytes
+    let response = client
+        .get(&export)
+        .bearer_auth("ops-token")
+        .header("range", "bytes=10-")
+        .header("if-range", etag.as_str())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 206);
+    assert_eq!(
+        response.headers()["content-range"],
+        format!("bytes 10-{}/{}", size - 1, size).as_str()
+    );
+    assert_eq!(response.bytes().await.unwrap(), whole[10..]);
+
+    let response = client
+        .get(&export)
+        .bearer_auth("ops-token")
+        .header("range", "bytes=10-")
+        .header("if-range", "\"another\"")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.bytes().await.unwrap().len() as u64, size);
+
+    let response = client
+        .get(&export)
+        .bearer_auth("ops-token")
+        .header("range", format!("bytes={}-", size))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 416);
+    assert_eq!(
+        response.headers()["content-range"],
+        format!("bytes */{}", size).as_str()
+    );
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.code.as_str(), "RANGE_NOT_SATISFIABLE");
+
+    let response = client
+        .get(&export)
+        .bearer_auth("acme-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// A valid provisioning request body
+fn service_body() -> Value {
+    json!({
+        "uuid": Uuid::from_u128(1),
+        "name": "MAD-BCN 100G",
+        "layer_protocol_name": "DSR",
+        "end_points": [
+            {"service_interface_point": Uuid::from_u128(10)},
+            {"service_interface_point": Uuid::from_u128(11)}
+        ],
+        "requested_capacity": {"value": 100, "unit": "GBPS"}
+    })
+}
+
+/// # Test: `test_http_create_connectivity_service`
+///
+/// This test verifies that a provisioning request lists every invalid field, that the
+/// devices of another tenant are not found, and that a service the device could not
+/// create is answered with the device error and not audited.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_create_connectivity_service() {
+    let (app, url, server, _) = start("http_create_service_test", settings).await;
+    let client = reqwest::Client::new();
+    let create = |host: &str, body: Value| {
+        client
+            .post(format!("{}/devices/{}/connectivity-services", url, host))
+            .bearer_auth("ops-token")
+            .json(&body)
+            .send()
+    };
+
+    let response = create(
+        "127.0.0.1",
+        json!({"layer_protocol_name": "SONET", "end_points": []}),
+    )
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 400);
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.code.as_str(), "VALIDATION_FAILED");
+    let fields: Vec<&str> = body
+        .details
+        .as_ref()
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|error| error["field"].as_str())
+        .collect();
+    assert!(fields.contains(&"layer_protocol_name"));
+
+    let response = create("127.0.0.3", service_body()).await.unwrap();
+    assert_eq!(response.status(), 404);
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.message, "Not found device 127.0.0.3");
+
+    // Nothing answers HTTPS on the device
+    let response = create("127.0.0.1", service_body()).await.unwrap();
+    assert_eq!(response.status(), 502);
+    assert!(app.audit(&Default::default()).is_empty());
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_change_connectivity_services`
+///
+/// This test verifies that deleting or changing the state of a connectivity service
+/// is answered `202 Accepted` with a provisioning job of the tenant, followed through
+/// `GET /jobs/{id}` until the device outcome, that another tenant's device is not found
+/// and that an unknown state is `400`.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_change_connectivity_services() {
+    let (app, url, server, _) = start("http_change_services_test", settings).await;
+    let client = reqwest::Client::new();
+    let service = |host: &str| {
+        format!(
+            "{}/devices/{}/connectivity-services/{}",
+            url,
+            host,
+            Uuid::from_u128(1)
+        )
+    };
+
+    let response = client
+        .delete(service("127.0.0.1"))
+        .bearer_auth("ops-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 202);
+    let location = response.headers()["location"].to_str().unwrap().to_string();
+    let job: Value = response.json().await.unwrap();
+    assert_eq!(job["kind"], "provisioning");
+    assert_eq!(location, format!("/jobs/{}", job["id"].as_str().unwrap()));
+    // Nothing answers HTTPS on the device
+    let job = finished(&client, &url, &location, "ops-token").await;
+    assert_eq!(job["status"], "failed");
+    assert!(job["error"].is_string());
+    let response = client
+        .get(format!("{}{}", url, location))
+        .bearer_auth("acme-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response = client
+        .patch(service("127.0.0.1"))
+        .bearer_auth("ops-token")
+        .json(&json!({"administrative_state": "LOCKED"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 202);
+    let location = response.headers()["location"].to_str().unwrap().to_string();
+    let job = finished(&client, &url, &location, "ops-token").await;
+    assert_eq!(job["status"], "failed");
+    assert!(app.audit(&Default::default()).is_empty());
+
+    let response = client
+        .patch(service("127.0.0.1"))
+        .bearer_auth("ops-token")
+        .json(&json!({"administrative_state": "PAUSED"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let body: ErrorBody = response.json().await.unwrap();
+    assert_eq!(body.message, "Invalid administrative state");
+
+    let response = client
+        .delete(service("127.0.0.3"))
+        .bearer_auth("ops-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+    let response = client
+        .delete(format!(
+            "{}/devices/127.0.0.1/connectivity-services/42",
+            url
+        ))
+        .bearer_auth("ops-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_validate_connectivity_service`
+///
+/// This test verifies that a provisioning request is checked against every cached
+/// topology of a device of the tenant, that its invalid fields are answered as
+/// violations, and that devices of other tenants are not found.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_validate_connectivity_service() {
+    let (_, url, server, _) = start("http_validate_service_test", |directory| {
+        // Each end point of the request is found in a different topology
+        let topologies: Vec<Topology> = [(1, 10), (2, 11)]
+            .into_iter()
+            .map(|(topology, sip)| {
+                Topology::from_value(
+                    &json!({
+                        "uuid": Uuid::from_u128(topology),
+                        "node": [{
+                            "uuid": Uuid::from_u128(topology + 100),
+                            "owned-node-edge-point": [{
+                                "uuid": Uuid::from_u128(topology + 200),
+                                "layer-protocol-qualifier": "tapi-dsr:DIGITAL_SIGNAL_TYPE_100_GigE",
+                                "mapped-service-interface-point": [
+                                    {"service-interface-point-uuid": Uuid::from_u128(sip)}
+                                ]
+                            }]
+                        }]
+                    }),
+                    "127.0.0.1",
+                )
+                .unwrap()
+            })
+            .collect();
+        ObjectStore::open(directory.join("objects.jsonl"))
+            .unwrap()
+            .ingest("127.0.0.1", &topologies)
+            .unwrap();
+        AppSettings {
+            objects: Some(directory.join("objects.jsonl")),
+            ..settings(directory)
+        }
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let validate = |host: &str, body: Value| {
+        client
+            .post(format!(
+                "{}/devices/{}/connectivity-services:validate",
+                url, host
+            ))
+            .bearer_auth("ops-token")
+            .json(&body)
+            .send()
+    };
+
+    let response = validate("127.0.0.1", service_body()).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let report: Value = response.json().await.unwrap();
+    assert_eq!(report, json!({"valid": true, "violations": []}));
+
+    let mut body = service_body();
+    body["end_points"][1]["service_interface_point"] = json!(Uuid::from_u128(12));
+    let report: Value = validate("127.0.0.1", body)
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report["valid"], false);
+    assert_eq!(
+        report["violations"][0]["field"],
+        "end_points.1.service_interface_point"
+    );
+
+    let report: Value = validate("127.0.0.1", json!({"layer_protocol_name": "SONET"}))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report["valid"], false);
+
+    let response = validate("127.0.0.3", service_body()).await.unwrap();
+    assert_eq!(response.status(), 404);
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_global_topology`
+///
+/// This test verifies that the global topology of a tenant holds the topologies of its
+/// devices only, so a link named alike on a device of another tenant is not stitched.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_global_topology() {
+    let (_, url, server, _) = start("http_global_topology_test", |directory| {
+        let mut objects = ObjectStore::open(directory.join("objects.jsonl")).unwrap();
+        for (index, host) in [(1, "127.0.0.1"), (2, "127.0.0.3")] {
+            let topology = Topology::from_value(
+                &json!({
+                    "uuid": Uuid::from_u128(index),
+                    "node": [{"uuid": Uuid::from_u128(index + 10)}],
+                    "link": [{
+                        "uuid": Uuid::from_u128(index + 20),
+                        "name": [{"value-name": "LINK_NAME", "value": "MAD-BCN"}],
+                        "node-edge-point": []
+                    }]
+                }),
+                host,
+            )
+            .unwrap();
+            objects.ingest(host, &[topology]).unwrap();
+        }
+        AppSettings {
+            objects: Some(directory.join("objects.jsonl")),
+            ..settings(directory)
+        }
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    for (token, host) in [("ops-token", "127.0.0.1"), ("acme-token", "127.0.0.3")] {
+        let response = client
+            .get(format!("{}/topology/global", url))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let global: Value = response.json().await.unwrap();
+        let domains = global["domains"].as_array().unwrap();
+        assert_eq!(domains.len(), 1);
+        assert_eq!(domains[0]["host"], host);
+        assert_eq!(domains[0]["links"][0]["name"], "MAD-BCN");
+        assert_eq!(global["stitched"], json!([]));
+    }
+
+    let response = client
+        .get(format!("{}/topology/global", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_link_impact`
+///
+/// This test verifies that the impact of a link is only analysed once the services of its
+/// device are collected, and that links of other tenants or unknown links are not found.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_link_impact() {
+    let (_, url, server, _) = start("http_link_impact_test", |directory| {
+        store_topology(directory, "127.0.0.1");
+        AppSettings {
+            objects: Some(directory.join("objects.jsonl")),
+            ..settings(directory)
+        }
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    for (token, link, status, message) in [
+        (
+            "ops-token",
+            Uuid::from_u128(4).to_string(),
+            404,
+            "Not found connectivity services of 127.0.0.1".to_string(),
+        ),
+        (
+            "acme-token",
+            Uuid::from_u128(4).to_string(),
+            404,
+            format!("Not found link {}", Uuid::from_u128(4)),
+        ),
+        (
+            "ops-token",
+            Uuid::from_u128(7).to_string(),
+            404,
+            format!("Not found link {}", Uuid::from_u128(7)),
+        ),
+        (
+            "ops-token",
+            "42".to_string(),
+            400,
+            "Invalid link id 42".to_string(),
+        ),
+    ] {
+        let response = client
+            .get(format!("{}/links/{}/impact", url, link))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status);
+        let body: ErrorBody = response.json().await.unwrap();
+        assert_eq!(body.message, message);
+    }
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_agents`
+///
+/// This test verifies that an edge agent enrolls once with a token issued to a tenant,
+/// that it is listed and revoked by that tenant only, and that the registry is saved to
+/// its file.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_agents() {
+    let (_, url, server, directory) = start("http_agents_test", |directory| AppSettings {
+        agents: Some(directory.join("agents.json")),
+        ..settings(directory)
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(format!("{}/agents/tokens", url))
+        .bearer_auth("ops-token")
+        .json(&json!({"label": "Madrid edge", "ttl": "forever"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let response = client
+        .post(format!("{}/agents/tokens", url))
+        .json(&json!({"label": "Madrid edge"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    let response = client
+        .post(format!("{}/agents/tokens", url))
+        .bearer_auth("ops-token")
+        .json(&json!({"label": "Madrid edge", "ttl": "1h"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    let token: Value = response.json().await.unwrap();
+    let token = token["token"].as_str().unwrap().to_string();
+
+    let enroll = || {
+        client
+            .post(format!("{}/agents/enroll", url))
+            .json(&json!({"token": token, "name": "edge-mad01"}))
+            .send()
+    };
+    let response = enroll().await.unwrap();
+    assert_eq!(response.status(), 201);
+    let credentials: Value = response.json().await.unwrap();
+    let agent_id = credentials["agent_id"].as_str().unwrap().to_string();
+    assert!(credentials["secret"].is_string());
+    let response = enroll().await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    for (token, count) in [("ops-token", 1), ("acme-token", 0)] {
+        let agents: Vec<Value> = client
+            .get(format!("{}/agents", url))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(agents.len(), count);
+    }
+    let saved = AgentRegistry::open(directory.join("agents.json")).unwrap();
+    assert_eq!(saved.agents("default")[0].name, "edge-mad01");
+
+    for (token, status) in [("acme-token", 404), ("ops-token", 200), ("ops-token", 404)] {
+        let response = client
+            .delete(format!("{}/agents/{}", url, agent_id))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), status);
+    }
+    let saved = AgentRegistry::open(directory.join("agents.json")).unwrap();
+    assert!(saved.agents("default").is_empty());
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_search`
+///
+/// This test verifies that a search only answers the devices and objects of the tenant
+/// of the token, and that an empty query is `400`.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_search() {
+    let (_, url, server, _) = start("http_search_test", settings).await;
+    let client = reqwest::Client::new();
+
+    for (token, host) in [("ops-token", "127.0.0.1"), ("acme-token", "127.0.0.3")] {
+        let response = client
+            .get(format!("{}/search?q=127.0.0", url))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        let hits: Value = response.json().await.unwrap();
+        let hits = hits.as_array().unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0]["kind"], "device");
+        assert_eq!(hits[0]["host"], host);
+    }
+
+    let response = client
+        .get(format!("{}/search?q=%20", url))
+        .bearer_auth("ops-token")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+    let error: ErrorBody = response.json().await.unwrap();
+    assert!(error.message.starts_with("Invalid search query"));
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_topology_compare`
+///
+/// This test verifies that two devices of the tenant are compared, that a device of
+/// another tenant is `404` like a device not collected yet, and that a comparison
+/// without both devices is `400`.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_topology_compare() {
+    let (_, url, server, _) = start("http_topology_compare_test", |directory| {
+        store_topology(directory, "127.0.0.1");
+        store_topology(directory, "127.0.0.3");
+        AppSettings {
+            objects: Some(directory.join("objects.jsonl")),
+            ..settings(directory)
+        }
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let compare = |query: &str, token: &str| {
+        client
+            .get(format!("{}/topology/compare?{}", url, query))
+            .bearer_auth(token)
+            .send()
+    };
+
+    let response = compare("left=127.0.0.1&right=127.0.0.1", "ops-token")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let comparison: Value = response.json().await.unwrap();
+    assert_eq!(comparison["matched_by_uuid"], 3);
+    assert_eq!(comparison["only_left"], json!([]));
+    assert_eq!(comparison["only_right"], json!([]));
+
+    for (query, status) in [
+        ("left=127.0.0.1&right=127.0.0.3", 404),
+        ("left=127.0.0.3&right=127.0.0.3", 404),
+        ("left=127.0.0.1", 400),
+    ] {
+        let response = compare(query, "ops-token").await.unwrap();
+        assert_eq!(response.status(), status, "{}", query);
+    }
+    let response = compare("left=127.0.0.3&right=127.0.0.3", "acme-token")
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_violations`
+///
+/// This test verifies that the violations of a device not validated yet and those of a
+/// device of another tenant are answered alike with `404`.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_violations() {
+    let (_, url, server, _) = start("http_violations_test", settings).await;
+    let client = reqwest::Client::new();
+
+    for host in ["127.0.0.1", "127.0.0.3"] {
+        let response = client
+            .get(format!("{}/topology/{}/violations", url, host))
+            .bearer_auth("ops-token")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 404);
+        let error: ErrorBody = response.json().await.unwrap();
+        assert_eq!(error.message, format!("Not found topology of {}", host));
+    }
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_geojson`
+///
+/// This test verifies that the GeoJSON export places the nodes of the devices of the
+/// tenant on their sites, leaving out those of the other tenants.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_geojson() {
+    let (_, url, server, _) = start("http_geojson_test", |directory| {
+        store_topology(directory, "127.0.0.1");
+        store_topology(directory, "127.0.0.3");
+        std::fs::write(
+            directory.join("sites.json"),
+            json!({
+                "sites": {"MAD01": {"id": "MAD01", "latitude": 40.41, "longitude": -3.70}},
+                "hosts": {"127.0.0.1": "MAD01", "127.0.0.3": "MAD01"}
+            })
+            .to_string(),
+        )
+        .unwrap();
+        AppSettings {
+            objects: Some(directory.join("objects.jsonl")),
+            sites: Some(directory.join("sites.json")),
+            ..settings(directory)
+        }
+    })
+    .await;
+    let client = reqwest::Client::new();
+
+    for (token, host) in [("ops-token", "127.0.0.1"), ("acme-token", "127.0.0.3")] {
+        let response = client
+            .get(format!("{}/export/topology.geojson", url))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "application/geo+json");
+        let collection: Value = response.json().await.unwrap();
+        assert_eq!(collection["type"], "FeatureCollection");
+        let features = collection["features"].as_array().unwrap();
+        assert!(!features.is_empty());
+        assert!(features
+            .iter()
+            .all(|feature| feature["properties"]["host"] == host));
+        assert_eq!(
+            features[0]["geometry"],
+            json!({"type": "Point", "coordinates": [-3.70, 40.41]})
+        );
+    }
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_links`
+///
+/// This test verifies that the links of a device of the tenant are served as JSON by
+/// default and as CSV with flattened endpoints, that a device of another tenant is `404`
+/// and an unknown format `400`.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_links() {
+    let (_, url, server, _) = start("http_links_test", |directory| {
+        store_topology(directory, "127.0.0.1");
+        store_topology(directory, "127.0.0.3");
+        AppSettings {
+            objects: Some(directory.join("objects.jsonl")),
+            ..settings(directory)
+        }
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let links = |path: &str| {
+        client
+            .get(format!("{}/topology/{}", url, path))
+            .bearer_auth("ops-token")
+            .send()
+    };
+
+    let response = links("127.0.0.1/links").await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body.as_array().unwrap().len(), 1);
+    assert_eq!(body[0]["uuid"], Uuid::from_u128(4).to_string());
+
+    let response = links("127.0.0.1/links?format=csv").await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/csv; charset=utf-8"
+    );
+    let csv = response.text().await.unwrap();
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows.len(), 2);
+    assert!(rows[0].starts_with(
+        "host,uuid,lifecycle_state,endpoint_1_node_uuid,endpoint_1_node_edge_point_uuid"
+    ));
+    assert!(rows[1].starts_with(&format!(
+        "127.0.0.1,{},,{},{}",
+        Uuid::from_u128(4),
+        Uuid::from_u128(2),
+        Uuid::from_u128(5)
+    )));
+
+    for (path, status) in [
+        ("127.0.0.3/links?format=csv", 404),
+        ("127.0.0.1/links?format=xlsx", 400),
+    ] {
+        assert_eq!(links(path).await.unwrap().status(), status, "{}", path);
+    }
+
+    server.abort();
+    let _ = server.await;
+}
+
+/// # Test: `test_http_render_topology`
+///
+/// This test verifies that a topology of a device of the tenant is rendered right away
+/// in the asked format, that a device of another tenant or an unknown topology is `404`
+/// and an unknown format `400`.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_http_render_topology() {
+    let (_, url, server, _) = start("http_render_topology_test", |directory| {
+        store_topology(directory, "127.0.0.1");
+        store_topology(directory, "127.0.0.3");
+        AppSettings {
+            objects: Some(directory.join("objects.jsonl")),
+            ..settings(directory)
+        }
+    })
+    .await;
+    let client = reqwest::Client::new();
+    let render = |path: &str| {
+        client
+            .get(format!("{}/topology/{}", url, path))
+            .bearer_auth("ops-token")
+            .send()
+    };
+
+    let response = render("127.0.0.1/export?format=dot").await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "text/vnd.graphviz");
+    let dot = response.text().await.unwrap();
+    assert!(dot.starts_with(&format!("graph \"{}\" {{", Uuid::from_u128(1))));
+    assert!(dot.contains(&format!(
+        "\"{}\" -- \"{}\"",
+        Uuid::from_u128(2),
+        Uuid::from_u128(3)
+    )));
+
+    let response = render("127.0.0.1/export").await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/graphml+xml"
+    );
+
+    for (path, status) in [
+        ("127.0.0.3/export?format=dot", 404),
+        (
+            &format!("127.0.0.1/export?topology={}", Uuid::from_u128(9)),
+            404,
+        ),
+        ("127.0.0.1/export?format=png", 400),
+    ] {
+        assert_eq!(render(path).await.unwrap().status(), status, "{}", path);
+    }
+
+    server.abort();
+    let _ = server.await;
+}
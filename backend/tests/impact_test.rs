@@ -0,0 +1,119 @@
+use backend::impact::LinkImpact; // Import the impact analysis
+use backend::models::{
+    connection::{Connection, ConnectivityContext, ConnectivityService},
+    link::Link,
+};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// A connection end point on node edge point `node_edge_point`
+fn end_point(node_edge_point: u128) -> Value {
+    json!({
+        "node-uuid": Uuid::from_u128(1),
+        "node-edge-point-uuid": Uuid::from_u128(node_edge_point),
+        "connection-end-point-uuid": Uuid::new_v4()
+    })
+}
+
+/// # Test: `test_link_impact`
+///
+/// This test verifies that the services routed over a link are found, including through
+/// lower layer connections, and that services routed elsewhere are not reported.
+#[test]
+fn test_link_impact() {
+    let host = "127.0.0.1";
+    let link = Link::builder()
+        .host(host)
+        .uuid(Uuid::from_u128(500))
+        .endpoint(Uuid::from_u128(1), Uuid::from_u128(21))
+        .endpoint(Uuid::from_u128(2), Uuid::from_u128(22))
+        .build()
+        .unwrap();
+
+    let connections: Vec<Connection> = [
+        // DSR connection of the first service, carried by the ODU connection below
+        json!({
+            "uuid": Uuid::from_u128(100),
+            "route": [{"local-id": "1", "connection-end-point": [end_point(10), end_point(11)]}],
+            "lower-connection": [{"connection-uuid": Uuid::from_u128(101)}]
+        }),
+        json!({
+            "uuid": Uuid::from_u128(101),
+            "route": [{"local-id": "1", "connection-end-point": [end_point(20), end_point(21), end_point(22), end_point(23)]}]
+        }),
+        // Connection of the second service, only touching one end of the link
+        json!({
+            "uuid": Uuid::from_u128(200),
+            "route": [{"connection-end-point": [end_point(21), end_point(30)]}]
+        }),
+    ]
+    .iter()
+    .map(|connection| Connection::from_value(connection, host).unwrap())
+    .collect();
+    assert_eq!(connections[0].lower_connections, vec![Uuid::from_u128(101)]);
+    assert_eq!(connections[1].routes[0].connection_end_points.len(), 4);
+
+    let services: Vec<ConnectivityService> = [
+        json!({
+            "uuid": Uuid::from_u128(1000),
+            "name": [{"value-name": "SERVICE_NAME", "value": "MAD-BCN 100G"}],
+            "connection": [{"connection-uuid": Uuid::from_u128(100)}]
+        }),
+        json!({
+            "uuid": Uuid::from_u128(2000),
+            "connection": [{"connection-uuid": Uuid::from_u128(200)}]
+        }),
+    ]
+    .iter()
+    .map(|service| ConnectivityService::from_value(service, host).unwrap())
+    .collect();
+
+    let impact = LinkImpact::analyse(&link, &services, &connections);
+    assert_eq!(impact.link, Uuid::from_u128(500));
+    assert_eq!(impact.services.len(), 1);
+    assert_eq!(impact.services[0].uuid, Uuid::from_u128(1000));
+    assert_eq!(impact.services[0].name.as_deref(), Some("MAD-BCN 100G"));
+    assert_eq!(impact.services[0].connections, vec![Uuid::from_u128(100)]);
+}
+
+/// # Test: `test_connectivity_context`
+///
+/// This test verifies that the services and connections of a whole
+/// `tapi-connectivity:connectivity-context` are parsed for the impact analysis, and that
+/// a context without services is empty.
+#[test]
+fn test_connectivity_context() {
+    let context = ConnectivityContext::from_value(
+        &json!({
+            "tapi-connectivity:connectivity-context": {
+                "connectivity-service": [{
+                    "uuid": Uuid::from_u128(1000),
+                    "connection": [{"connection-uuid": Uuid::from_u128(100)}]
+                }],
+                "connection": [{
+                    "uuid": Uuid::from_u128(100),
+                    "route": [{"connection-end-point": [end_point(21), end_point(22)]}]
+                }]
+            }
+        }),
+        "127.0.0.1",
+    )
+    .unwrap();
+    assert_eq!(context.host, "127.0.0.1");
+    assert_eq!(context.services[0].connections, vec![Uuid::from_u128(100)]);
+    assert_eq!(context.connections[0].host, "127.0.0.1");
+
+    let link = Link::builder()
+        .host("127.0.0.1")
+        .uuid(Uuid::from_u128(500))
+        .endpoint(Uuid::from_u128(1), Uuid::from_u128(21))
+        .endpoint(Uuid::from_u128(2), Uuid::from_u128(22))
+        .build()
+        .unwrap();
+    let impact = LinkImpact::analyse(&link, &context.services, &context.connections);
+    assert_eq!(impact.services[0].uuid, Uuid::from_u128(1000));
+
+    let empty = ConnectivityContext::from_value(&json!({}), "127.0.0.1").unwrap();
+    assert!(empty.services.is_empty() && empty.connections.is_empty());
+    assert!(ConnectivityContext::from_value(&json!({"connection": [{}]}), "127.0.0.1").is_err());
+}
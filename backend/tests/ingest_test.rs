@@ -0,0 +1,176 @@
+use backend::events::ObjectKind;
+use backend::ingest::ObjectStore; // Import the delta ingestion store
+use backend::models::key::ObjectKey;
+use backend::models::topology::Topology;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// Returns a link payload between two nodes, named `name`
+fn link(uuid: u128, name: &str) -> Value {
+    json!({
+        "uuid": Uuid::from_u128(uuid),
+        "name": [{"value-name": "LINK_NAME", "value": name}],
+        "node-edge-point": [{
+            "node-uuid": Uuid::from_u128(100),
+            "node-edge-point-uuid": Uuid::from_u128(101)
+        }]
+    })
+}
+
+/// Returns the topology of `host` made of `links`
+fn topology(host: &str, links: Vec<Value>) -> Topology {
+    Topology::from_value(&json!({"uuid": Uuid::from_u128(1), "link": links}), host).unwrap()
+}
+
+fn key(host: &str, uuid: u128) -> ObjectKey {
+    ObjectKey::new(host, Uuid::from_u128(1), Uuid::from_u128(uuid))
+}
+
+/// # Test: `test_delta_ingestion`
+///
+/// This test verifies that only inserted, changed and removed objects are written, that
+/// an identical collection writes nothing, and that other hosts are left untouched.
+#[test]
+fn test_delta_ingestion() {
+    let mut store = ObjectStore::new();
+    let first = topology("10.0.0.1", vec![link(2, "A"), link(3, "B"), link(4, "C")]);
+    let delta = store.ingest("10.0.0.1", &[first]).unwrap();
+    assert_eq!(delta.inserts.len(), 3);
+    assert_eq!(delta.writes(), 3);
+    store
+        .ingest("10.0.0.2", &[topology("10.0.0.2", vec![link(2, "A")])])
+        .unwrap();
+
+    let same = topology("10.0.0.1", vec![link(2, "A"), link(3, "B"), link(4, "C")]);
+    let delta = store.ingest("10.0.0.1", &[same]).unwrap();
+    assert!(delta.is_empty());
+    assert_eq!(delta.unchanged, 3);
+
+    let changed = topology("10.0.0.1", vec![link(2, "A"), link(3, "B2"), link(5, "D")]);
+    let delta = store.ingest("10.0.0.1", &[changed]).unwrap();
+    assert_eq!(delta.inserts, vec![(ObjectKind::Link, key("10.0.0.1", 5))]);
+    assert_eq!(delta.updates, vec![(ObjectKind::Link, key("10.0.0.1", 3))]);
+    assert_eq!(delta.deletes, vec![(ObjectKind::Link, key("10.0.0.1", 4))]);
+    assert_eq!(delta.unchanged, 1);
+    assert_eq!(
+        store
+            .get(ObjectKind::Link, &key("10.0.0.1", 3))
+            .unwrap()
+            .object["name"],
+        "B2"
+    );
+    assert!(store.get(ObjectKind::Link, &key("10.0.0.2", 2)).is_some());
+    assert_eq!(store.len(), 4);
+}
+
+/// # Test: `test_delta_ingestion_journal`
+///
+/// This test checks that the journal only grows by the writes of each collection, is
+/// replayed when the store is reopened, and is rewritten by `compact`. The topologies
+/// of a host are rebuilt from the replayed objects.
+#[test]
+fn test_delta_ingestion_journal() {
+    let path = std::env::temp_dir().join(format!("ingest_test_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let lines = || std::fs::read_to_string(&path).unwrap().lines().count();
+
+    let mut store = ObjectStore::open(&path).unwrap();
+    store
+        .ingest(
+            "10.0.0.1",
+            &[topology("10.0.0.1", vec![link(2, "A"), link(3, "B")])],
+        )
+        .unwrap();
+    store
+        .ingest(
+            "10.0.0.1",
+            &[topology("10.0.0.1", vec![link(2, "A"), link(3, "B")])],
+        )
+        .unwrap();
+    assert_eq!(lines(), 2);
+    store
+        .ingest("10.0.0.1", &[topology("10.0.0.1", vec![link(2, "A2")])])
+        .unwrap();
+    assert_eq!(lines(), 4);
+
+    let reopened = ObjectStore::open(&path).unwrap();
+    assert_eq!(reopened.len(), 1);
+    assert_eq!(
+        reopened.get(ObjectKind::Link, &key("10.0.0.1", 2)),
+        store.get(ObjectKind::Link, &key("10.0.0.1", 2))
+    );
+
+    let topologies = reopened.topologies("10.0.0.1").unwrap().unwrap();
+    assert_eq!(topologies.len(), 1);
+    assert_eq!(topologies[0].uuid, Uuid::from_u128(1));
+    assert_eq!(
+        topologies[0].links,
+        store.topologies("10.0.0.1").unwrap().unwrap()[0].links
+    );
+    assert_eq!(topologies[0].links[0].uuid, Uuid::from_u128(2));
+    assert_eq!(reopened.topologies("10.0.0.2").unwrap(), None);
+
+    reopened.compact().unwrap();
+    assert_eq!(lines(), 1);
+    assert_eq!(ObjectStore::open(&path).unwrap().len(), 1);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// # Test: `test_node_and_link_sharing_a_uuid`
+///
+/// This test verifies that a node and a link reported with the same UUID are stored
+/// apart, and that deletes of journals written without the object kind still apply.
+#[test]
+fn test_node_and_link_sharing_a_uuid() {
+    let path = std::env::temp_dir().join(format!("ingest_kind_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let topology = Topology::from_value(
+        &json!({
+            "uuid": Uuid::from_u128(1),
+            "node": [{"uuid": Uuid::from_u128(2)}],
+            "link": [link(2, "A")]
+        }),
+        "10.0.0.1",
+    )
+    .unwrap();
+
+    let mut store = ObjectStore::open(&path).unwrap();
+    let delta = store.ingest("10.0.0.1", &[topology]).unwrap();
+    assert_eq!(
+        delta.inserts,
+        vec![
+            (ObjectKind::Node, key("10.0.0.1", 2)),
+            (ObjectKind::Link, key("10.0.0.1", 2))
+        ]
+    );
+    assert_eq!(store.len(), 2);
+    assert_eq!(
+        store
+            .get(ObjectKind::Node, &key("10.0.0.1", 2))
+            .unwrap()
+            .kind,
+        ObjectKind::Node
+    );
+
+    // Only the link is gone
+    let without_link = Topology::from_value(
+        &json!({"uuid": Uuid::from_u128(1), "node": [{"uuid": Uuid::from_u128(2)}]}),
+        "10.0.0.1",
+    )
+    .unwrap();
+    let delta = store.ingest("10.0.0.1", &[without_link]).unwrap();
+    assert_eq!(delta.deletes, vec![(ObjectKind::Link, key("10.0.0.1", 2))]);
+    assert_eq!(ObjectStore::open(&path).unwrap().len(), 1);
+
+    // A delete without kind, from an older journal
+    let mut journal = std::fs::read_to_string(&path).unwrap();
+    journal.push_str(&format!(
+        "{}\n",
+        json!({"op": "delete", "key": key("10.0.0.1", 2)})
+    ));
+    std::fs::write(&path, journal).unwrap();
+    assert!(ObjectStore::open(&path).unwrap().is_empty());
+
+    let _ = std::fs::remove_file(&path);
+}
@@ -0,0 +1,111 @@
+use backend::jobs::{Job, JobKind, JobQueue, JobStatus, RETENTION_DAYS}; // Import the job queue
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::json;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Waits until a job is finished
+async fn finished(queue: &JobQueue, id: &Uuid) -> Job {
+    for _ in 0..100 {
+        let job = queue.get(id).unwrap();
+        if matches!(job.status, JobStatus::Done | JobStatus::Failed) {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("Job {} did not finish", id);
+}
+
+/// # Test: `test_job_queue`
+///
+/// This test verifies that jobs go from queued to done with their result, that
+/// failing jobs report their error, and that jobs keep the tenant they were queued for.
+#[tokio::test]
+async fn test_job_queue() {
+    let queue = JobQueue::new(2);
+
+    let export = queue.submit(JobKind::Export, |progress| async move {
+        progress.set(50);
+        Ok(json!({"rows": 42}))
+    });
+    let import = queue.submit(JobKind::Import, |_| async move {
+        Err(Error::from("Invalid import file"))
+    });
+
+    let job = finished(&queue, &export).await;
+    assert_eq!(job.status, JobStatus::Done);
+    assert_eq!(job.progress, 100);
+    assert_eq!(job.result, Some(json!({"rows": 42})));
+    assert_eq!(job.tenant_id, "default");
+
+    let job = finished(&queue, &import).await;
+    assert_eq!(job.status, JobStatus::Failed);
+    assert_eq!(job.error, Some("Invalid import file".to_string()));
+    assert_eq!(queue.list().len(), 2);
+
+    let poll = queue.submit_for("acme", JobKind::Poll, |_| async move { Ok(json!({})) });
+    assert_eq!(finished(&queue, &poll).await.tenant_id, "acme");
+}
+
+/// # Test: `test_job_queue_persistence`
+///
+/// This test checks that job statuses survive a restart and that jobs interrupted by
+/// the restart are reported as failed.
+#[tokio::test]
+async fn test_job_queue_persistence() {
+    let path = std::env::temp_dir().join(format!("jobs_test_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let queue = JobQueue::persistent(1, &path).unwrap();
+    let done = queue.submit(JobKind::Poll, |_| async move { Ok(json!(null)) });
+    finished(&queue, &done).await;
+    let interrupted = queue.submit(JobKind::Poll, |_| async move {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        Ok(json!(null))
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    queue.shutdown();
+
+    let queue = JobQueue::persistent(1, &path).unwrap();
+    assert_eq!(queue.get(&done).unwrap().status, JobStatus::Done);
+    let job = queue.get(&interrupted).unwrap();
+    assert_eq!(job.status, JobStatus::Failed);
+    assert_eq!(job.error, Some("Interrupted by a restart".to_string()));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// # Test: `test_job_retention`
+///
+/// This test checks that finished jobs older than the retention are forgotten when the
+/// queue is opened, while recent and unfinished jobs are kept.
+#[tokio::test]
+async fn test_job_retention() {
+    let path = std::env::temp_dir().join(format!("jobs_retention_{}.json", std::process::id()));
+    let now = chrono::Local::now();
+    let job = |status: JobStatus, days: i64| Job {
+        id: Uuid::new_v4(),
+        kind: JobKind::Export,
+        tenant_id: "default".to_string(),
+        status,
+        progress: 100,
+        result: None,
+        error: None,
+        created_at: now - chrono::Duration::days(days),
+        updated_at: now - chrono::Duration::days(days),
+    };
+    let old = job(JobStatus::Done, RETENTION_DAYS + 1);
+    let recent = job(JobStatus::Failed, 1);
+    let table: std::collections::HashMap<Uuid, Job> = [old.clone(), recent.clone()]
+        .into_iter()
+        .map(|job| (job.id, job))
+        .collect();
+    std::fs::write(&path, serde_json::to_string(&table).unwrap()).unwrap();
+
+    let queue = JobQueue::persistent(1, &path).unwrap();
+    assert_eq!(queue.get(&old.id), None);
+    assert_eq!(queue.get(&recent.id), Some(recent));
+    queue.shutdown();
+
+    let _ = std::fs::remove_file(&path);
+}
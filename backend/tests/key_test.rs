@@ -0,0 +1,90 @@
+use backend::diff::diff_topologies; // Import the diff engine
+use backend::enrichment::{enrich, SiteMapping}; // Import the enrichment stage
+use backend::events::ChangeKind; // Import the change event types
+use backend::models::{key::ObjectKey, site::Site, topology::Topology}; // Import the topology models
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::json;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const NODE: &str = "62d11f13-db6c-3398-8a83-5fac0b2b7476";
+
+/// Parses a topology with a single node reusing the same node UUID
+fn topology(host: &str, topology_uuid: &str) -> Topology {
+    let value = json!({"uuid": topology_uuid, "node": [{"uuid": NODE}]});
+    Topology::from_value(&value, host).unwrap()
+}
+
+/// # Test: `test_object_key_parsing`
+///
+/// This test verifies the storage form of object keys and the migration of legacy keys
+/// made of the bare object UUID.
+#[test]
+fn test_object_key_parsing() {
+    let topology_uuid = Uuid::parse_str("4e537278-79f8-39ad-804b-f0b553cb2ffb").unwrap();
+    let key = ObjectKey::new("10.95.87.21", topology_uuid, Uuid::parse_str(NODE).unwrap());
+
+    let stored = key.to_string();
+    assert_eq!(
+        stored,
+        format!("10.95.87.21/4e537278-79f8-39ad-804b-f0b553cb2ffb/{}", NODE)
+    );
+    assert_eq!(stored.parse::<ObjectKey>().unwrap(), key);
+    assert_eq!(
+        ObjectKey::migrate(NODE, "10.95.87.21", topology_uuid).unwrap(),
+        key
+    );
+    assert_eq!(
+        ObjectKey::migrate(&stored, "other", Uuid::nil()).unwrap(),
+        key
+    );
+
+    match "10.95.87.21/not-a-uuid".parse::<ObjectKey>() {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid object key 10.95.87.21/not-a-uuid"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(key) => panic!("Expected an error, but got {}", key),
+    }
+}
+
+/// # Test: `test_reused_uuids_are_scoped`
+///
+/// This test checks that the same node UUID in two topologies is treated as two objects
+/// by the diff and by site lookups.
+#[test]
+fn test_reused_uuids_are_scoped() {
+    let previous = topology("10.95.87.21", "4e537278-79f8-39ad-804b-f0b553cb2ffb");
+    let current = topology("10.95.87.21", "9c8b7a6d-5e4f-4a3b-8c2d-1e0f9a8b7c6d");
+    let changes: Vec<ChangeKind> = diff_topologies(&previous, &current)
+        .into_iter()
+        .map(|event| event.change)
+        .collect();
+    assert_eq!(changes, vec![ChangeKind::Removed, ChangeKind::Added]);
+
+    let site = |id: &str| Site {
+        id: id.to_string(),
+        name: None,
+        latitude: 0.0,
+        longitude: 0.0,
+    };
+    let mapping = SiteMapping {
+        sites: HashMap::from([
+            ("MAD01".to_string(), site("MAD01")),
+            ("BCN01".to_string(), site("BCN01")),
+        ]),
+        hosts: HashMap::new(),
+        nodes: HashMap::from([
+            (NODE.to_string(), "MAD01".to_string()),
+            (
+                format!("10.95.87.22/4e537278-79f8-39ad-804b-f0b553cb2ffb/{}", NODE),
+                "BCN01".to_string(),
+            ),
+        ]),
+    };
+
+    let mut madrid = topology("10.95.87.21", "4e537278-79f8-39ad-804b-f0b553cb2ffb");
+    let mut barcelona = topology("10.95.87.22", "4e537278-79f8-39ad-804b-f0b553cb2ffb");
+    enrich(&mut madrid, &mapping);
+    enrich(&mut barcelona, &mapping);
+    assert_eq!(madrid.nodes[0].site.as_ref().unwrap().id, "MAD01");
+    assert_eq!(barcelona.nodes[0].site.as_ref().unwrap().id, "BCN01");
+}
@@ -0,0 +1,155 @@
+use backend::latency::{measure, LatencyProbe, LatencySample, LatencyStore, RETENTION_DAYS}; // Import the latency monitoring
+use backend::models::device::Device; // Import the device model
+use backend::setup::config::Config;
+use backend::southbound::dns::CachedResolver;
+use backend::testsupport::{MockAuth, MockDevice}; // Import the mock TAPI device
+use backend::Error;
+use chrono::{Duration, Local};
+use serde_json::json;
+use tokio::net::TcpListener;
+
+fn sample(minutes_ago: i64, latency_ms: Option<f64>) -> LatencySample {
+    LatencySample {
+        at: Local::now() - Duration::minutes(minutes_ago),
+        probe: LatencyProbe::Tcp,
+        latency_ms,
+    }
+}
+
+fn local_device(port: u16) -> Device {
+    Device::from_value(&json!({
+        "host": "127.0.0.1",
+        "port": port,
+        "auth": {"username": "tapi", "password": "secret"}
+    }))
+    .unwrap()
+}
+
+/// # Test: `test_latency_series`
+///
+/// This test verifies that the samples of a window are returned with their statistics,
+/// that expired samples are dropped when the file is reopened, and that an unknown
+/// device is reported.
+#[test]
+fn test_latency_series() {
+    let path = std::env::temp_dir().join(format!("latency_test_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut store = LatencyStore::open(&path).unwrap();
+    store
+        .record("10.0.0.1", sample(RETENTION_DAYS * 24 * 60 + 1, Some(1.0)))
+        .unwrap();
+    store.record("10.0.0.1", sample(120, Some(40.0))).unwrap();
+    for latency_ms in 1..=20 {
+        store
+            .record("10.0.0.1", sample(30, Some(f64::from(latency_ms))))
+            .unwrap();
+    }
+    store.record("10.0.0.1", sample(10, None)).unwrap();
+
+    let store = LatencyStore::open(&path).unwrap();
+    let day = store
+        .series("10.0.0.1", "24h".parse().unwrap(), Local::now())
+        .unwrap();
+    assert_eq!(day.samples.len(), 22);
+    assert_eq!(day.failures, 1);
+    assert_eq!(day.min_ms, Some(1.0));
+    assert_eq!(day.p95_ms, Some(20.0));
+    assert_eq!(day.max_ms, Some(40.0));
+    assert_eq!(day.avg_ms, Some(250.0 / 21.0));
+    assert_eq!(store.last("10.0.0.1").unwrap().latency_ms, None);
+
+    let hour = store
+        .series("10.0.0.1", "1h".parse().unwrap(), Local::now())
+        .unwrap();
+    assert_eq!(hour.samples.len(), 21);
+    assert_eq!(hour.max_ms, Some(20.0));
+
+    // The expired sample was compacted away
+    let lines = std::fs::read_to_string(&path).unwrap().lines().count();
+    assert_eq!(lines, 22);
+
+    match store.series("10.0.0.2", "24h".parse().unwrap(), Local::now()) {
+        Err(Error::NotFound(msg)) => assert_eq!(msg, "Not found latency of 10.0.0.2"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// # Test: `test_latency_prometheus`
+///
+/// This test checks that answered probes are exported as cumulative histogram buckets
+/// with their sum and count, and that failed probes are left out.
+#[test]
+fn test_latency_prometheus() {
+    let mut store = LatencyStore::new();
+    store.record("10.0.0.1", sample(0, Some(3.0))).unwrap();
+    store.record("10.0.0.1", sample(0, Some(30.0))).unwrap();
+    store.record("10.0.0.1", sample(0, Some(7000.0))).unwrap();
+    store.record("10.0.0.1", sample(0, None)).unwrap();
+
+    let metrics = store.prometheus();
+    assert!(metrics.contains("# TYPE device_latency_seconds histogram\n"));
+    assert!(metrics.contains("device_latency_seconds_bucket{host=\"10.0.0.1\",le=\"0.005\"} 1\n"));
+    assert!(metrics.contains("device_latency_seconds_bucket{host=\"10.0.0.1\",le=\"0.025\"} 1\n"));
+    assert!(metrics.contains("device_latency_seconds_bucket{host=\"10.0.0.1\",le=\"0.05\"} 2\n"));
+    assert!(metrics.contains("device_latency_seconds_bucket{host=\"10.0.0.1\",le=\"5\"} 2\n"));
+    assert!(metrics.contains("device_latency_seconds_bucket{host=\"10.0.0.1\",le=\"+Inf\"} 3\n"));
+    assert!(metrics.contains("device_latency_seconds_sum{host=\"10.0.0.1\"} 7.033\n"));
+    assert!(metrics.contains("device_latency_seconds_count{host=\"10.0.0.1\"} 3\n"));
+}
+
+/// # Test: `test_latency_measure`
+///
+/// This test ensures that the TCP and HTTP probes time a listening device, and that a
+/// closed port is recorded as unanswered.
+#[tokio::test]
+async fn test_latency_measure() {
+    let resolver = CachedResolver::new();
+    let mock = MockDevice::start(json!({}), MockAuth::None).await.unwrap();
+    let device = local_device(mock.address().port());
+
+    let tcp = measure(&device, &mock.base_url(), LatencyProbe::Tcp, &resolver).await;
+    assert_eq!(tcp.probe, LatencyProbe::Tcp);
+    assert!(tcp.latency_ms.is_some_and(|latency| latency >= 0.0));
+    let http = measure(&device, &mock.base_url(), LatencyProbe::Http, &resolver).await;
+    assert_eq!(http.probe, LatencyProbe::Http);
+    assert!(http.latency_ms.is_some());
+    assert!(mock
+        .requests()
+        .iter()
+        .any(|request| request.method == "HEAD"));
+
+    // Bound then dropped, so nothing listens there
+    let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = closed.local_addr().unwrap().port();
+    drop(closed);
+    let base_url = format!("http://127.0.0.1:{}", port);
+    for probe in [LatencyProbe::Tcp, LatencyProbe::Http] {
+        let sample = measure(&local_device(port), &base_url, probe, &resolver).await;
+        assert_eq!(sample.latency_ms, None);
+    }
+}
+
+/// # Test: `test_latency_config`
+///
+/// This test verifies that latency is probed over TCP every minute by default, and that
+/// a zero interval is refused.
+#[test]
+fn test_latency_config() {
+    let config = Config::default();
+    assert_eq!(config.latency.probe, LatencyProbe::Tcp);
+    assert_eq!(config.latency.interval, 60);
+    let config = Config::parse("[latency]\nprobe = \"http\"").unwrap();
+    assert_eq!(config.latency.probe, LatencyProbe::Http);
+
+    match Config::parse("[latency]\ninterval = 0") {
+        Err(Error::Invalid(msg)) => {
+            assert_eq!(msg, "Invalid config: latency.interval must be positive")
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
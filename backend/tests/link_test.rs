@@ -1,6 +1,10 @@
 use backend::models::{
     // Import necessary model components
-    link::Link,
+    direction::Direction,
+    hash::ContentHash,
+    lifecycle_state::LifecycleState,
+    link::{Link, LinkRef, LinkWarning},
+    name::Name,
     node_edge_point::NodeEdgePoint,
 };
 use backend::Error; // Import the custom error type from the backend module
@@ -8,15 +12,10 @@ use chrono::Local; // For handling date and time
 use serde_json::{
     from_str,
     to_string,
+    Map,
     // Importing JSON serialization/deserialization utilities
     Value,
 };
-use std::hash::{
-    // Import hashing traits
-    DefaultHasher,
-    Hash,
-    Hasher,
-};
 use uuid::Uuid; // For handling UUIDs (universally unique identifiers)
 
 /// # Test: `test_raw_link`
@@ -66,7 +65,7 @@ fn test_raw_link() {
         }"#;
 
     // Deserialize raw JSON data into a `Value` type and unwrap safely
-    let raw_link_data_value: Value = from_str(&raw_link_data).unwrap_or_default();
+    let raw_link_data_value: Value = from_str(raw_link_data).unwrap_or_default();
     // Attempt to create a `Link` object from the `Value`
     let raw_link_object = Link::from_value(&raw_link_data_value, host).unwrap();
 
@@ -79,15 +78,28 @@ fn test_raw_link() {
                     .unwrap_or_default(),
                 node_uuid: Uuid::parse_str("62d11f13-db6c-3398-8a83-5fac0b2b7476")
                     .unwrap_or_default(),
+                topology_uuid: Some(
+                    Uuid::parse_str("4e537278-79f8-39ad-804b-f0b553cb2ffb").unwrap_or_default(),
+                ),
             },
             NodeEdgePoint {
                 node_edge_point_uuid: Uuid::parse_str("63366151-aeb4-3dfd-af66-d471b353aa1c")
                     .unwrap_or_default(),
                 node_uuid: Uuid::parse_str("7b0c973a-996a-3409-ad2f-d173354bfdb7")
                     .unwrap_or_default(),
+                topology_uuid: Some(
+                    Uuid::parse_str("4e537278-79f8-39ad-804b-f0b553cb2ffb").unwrap_or_default(),
+                ),
             },
         ],
         uuid: Uuid::parse_str("14219539-208b-35f5-b7cf-35a58e083490").unwrap_or_default(),
+        lifecycle_state: Some(LifecycleState::Installed),
+        direction: Some(Direction::Bidirectional),
+        name: None,
+        names: vec![],
+        extensions: Map::new(),
+        raw_uuids: vec![],
+        warnings: vec![],
         hash: raw_link_object.hash,
         date: raw_link_object.date,
     };
@@ -139,17 +151,16 @@ fn test_raw_link_error() {
             "uuid": "14219539-208b-35f5-b7cf-35a58e083490"
         }"#;
 
-    let raw_link_data_value: Value = from_str(&raw_link_data).unwrap_or_default();
+    let raw_link_data_value: Value = from_str(raw_link_data).unwrap_or_default();
 
     // Check for a custom error when certain required fields are missing
     match Link::from_value(&raw_link_data_value, host) {
-        Err(e) => {
-            match e {
-                Error::Custom(msg) => {
-                    assert_eq!(msg, "Not found node uuid".to_string());
-                } //_ => panic!("Expected an Error::Custom, but got other kind of error")
+        Err(e) => match e {
+            Error::BadResponse(msg) => {
+                assert_eq!(msg, "Not found node uuid".to_string());
             }
-        }
+            _ => panic!("Expected an Error::BadResponse, but got other kind of error"),
+        },
         Ok(_) => panic!("Expected an error, but got Ok"),
     }
 
@@ -190,15 +201,14 @@ fn test_raw_link_error() {
             "uuid": "14219539-208b-35f5-b7cf-35a58e083490"
         }"#;
 
-    let raw_link_data_value: Value = from_str(&raw_link_data).unwrap_or_default();
+    let raw_link_data_value: Value = from_str(raw_link_data).unwrap_or_default();
     match Link::from_value(&raw_link_data_value, host) {
-        Err(e) => {
-            match e {
-                Error::Custom(msg) => {
-                    assert_eq!(msg, "Not found node edge point uuid".to_string());
-                } //_ => panic!("Expected an Error::Custom, but got other kind of error")
+        Err(e) => match e {
+            Error::BadResponse(msg) => {
+                assert_eq!(msg, "Not found node edge point uuid".to_string());
             }
-        }
+            _ => panic!("Expected an Error::BadResponse, but got other kind of error"),
+        },
         Ok(_) => panic!("Expected an error, but got Ok"),
     }
 
@@ -237,15 +247,14 @@ fn test_raw_link_error() {
             "tapi-ciena-link-extensions:signal-content-type": "IP"
         }"#;
 
-    let raw_link_data_value: Value = from_str(&raw_link_data).unwrap_or_default();
+    let raw_link_data_value: Value = from_str(raw_link_data).unwrap_or_default();
     match Link::from_value(&raw_link_data_value, host) {
-        Err(e) => {
-            match e {
-                Error::Custom(msg) => {
-                    assert_eq!(msg, "Not found link uuid".to_string());
-                } //_ => panic!("Expected an Error::Custom, but got other kind of error")
+        Err(e) => match e {
+            Error::BadResponse(msg) => {
+                assert_eq!(msg, "Not found link uuid".to_string());
             }
-        }
+            _ => panic!("Expected an Error::BadResponse, but got other kind of error"),
+        },
         Ok(_) => panic!("Expected an error, but got Ok"),
     }
 
@@ -278,15 +287,14 @@ fn test_raw_link_error() {
             "uuid": "14219539-208b-35f5-b7cf-35a58e083490"
         }"#;
 
-    let raw_link_data_value: Value = from_str(&raw_link_data).unwrap_or_default();
+    let raw_link_data_value: Value = from_str(raw_link_data).unwrap_or_default();
     match Link::from_value(&raw_link_data_value, host) {
-        Err(e) => {
-            match e {
-                Error::Custom(msg) => {
-                    assert_eq!(msg, "Not found node edge points list".to_string());
-                } //_ => panic!("Expected an Error::Custom, but got other kind of error")
+        Err(e) => match e {
+            Error::BadResponse(msg) => {
+                assert_eq!(msg, "Not found node edge points list".to_string());
             }
-        }
+            _ => panic!("Expected an Error::BadResponse, but got other kind of error"),
+        },
         Ok(_) => panic!("Expected an error, but got Ok"),
     }
 }
@@ -314,8 +322,7 @@ fn test_controlled_link() {
         }"#;
 
     // Hashing and timestamp generation
-    let mut hasher = DefaultHasher::new();
-    String::from(link_data).hash(&mut hasher);
+    let hash = ContentHash::of(&from_str(link_data).unwrap());
     let now = Local::now();
 
     // Create a `Link` object
@@ -327,16 +334,25 @@ fn test_controlled_link() {
                     .unwrap_or_default(),
                 node_uuid: Uuid::parse_str("62d11f13-db6c-3398-8a83-5fac0b2b7476")
                     .unwrap_or_default(),
+                topology_uuid: None,
             },
             NodeEdgePoint {
                 node_edge_point_uuid: Uuid::parse_str("63366151-aeb4-3dfd-af66-d471b353aa1c")
                     .unwrap_or_default(),
                 node_uuid: Uuid::parse_str("7b0c973a-996a-3409-ad2f-d173354bfdb7")
                     .unwrap_or_default(),
+                topology_uuid: None,
             },
         ],
         uuid: Uuid::parse_str("14219539-208b-35f5-b7cf-35a58e083490").unwrap_or_default(),
-        hash: hasher.finish(),
+        lifecycle_state: None,
+        direction: None,
+        name: None,
+        names: vec![],
+        extensions: Map::new(),
+        raw_uuids: vec![],
+        warnings: vec![],
+        hash,
         date: now,
     };
 
@@ -359,8 +375,8 @@ fn test_controlled_link() {
         "hash":{},
         "date":"{}"
     }}"#,
-        host.to_string(),
-        hasher.finish(),
+        host,
+        hash.get(),
         now.to_rfc3339()
     );
 
@@ -372,3 +388,203 @@ fn test_controlled_link() {
     // Assert the reverse process: deserialization works correctl
     assert_eq!(from_str::<Link>(&link_data_formated).unwrap(), link_object);
 }
+
+/// # Test: `test_link_builder`
+///
+/// This test verifies that a `Link` built in code matches the same minimal payload parsed
+/// with `Link::from_value`, hash included, and that the UUID is mandatory.
+#[test]
+fn test_link_builder() {
+    let host = "127.0.0.1";
+    let topology_uuid = Uuid::parse_str("4e537278-79f8-39ad-804b-f0b553cb2ffb").unwrap();
+    let link_data = r#"
+        {
+            "lifecycle-state": "PLANNED",
+            "node-edge-point": [
+                {
+                    "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577",
+                    "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476",
+                    "topology-uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb"
+                },
+                {
+                    "node-edge-point-uuid": "63366151-aeb4-3dfd-af66-d471b353aa1c",
+                    "node-uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7",
+                    "topology-uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb"
+                }
+            ],
+            "uuid": "14219539-208b-35f5-b7cf-35a58e083490"
+        }"#;
+    let parsed = Link::from_value(&from_str(link_data).unwrap(), host).unwrap();
+
+    let built = Link::builder()
+        .host(host)
+        .uuid(Uuid::parse_str("14219539-208b-35f5-b7cf-35a58e083490").unwrap())
+        .topology(topology_uuid)
+        .endpoint(
+            Uuid::parse_str("62d11f13-db6c-3398-8a83-5fac0b2b7476").unwrap(),
+            Uuid::parse_str("65a39427-3055-3ba4-9e15-0ebed4974577").unwrap(),
+        )
+        .endpoint(
+            Uuid::parse_str("7b0c973a-996a-3409-ad2f-d173354bfdb7").unwrap(),
+            Uuid::parse_str("63366151-aeb4-3dfd-af66-d471b353aa1c").unwrap(),
+        )
+        .lifecycle_state(LifecycleState::Planned)
+        .build()
+        .unwrap();
+
+    assert_eq!(built.hash, parsed.hash);
+    assert_eq!(
+        Link {
+            date: parsed.date,
+            ..built
+        },
+        parsed
+    );
+
+    match Link::builder().host(host).build() {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Not found link uuid"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(link) => panic!("Expected an error, but got {:?}", link),
+    }
+}
+
+/// # Test: `test_link_hash_key_order`
+///
+/// This test checks that the hash only depends on the content of the payload, not on
+/// the order of its keys or its whitespace, while a content change still changes it.
+#[test]
+fn test_link_hash_key_order() {
+    let host = "127.0.0.1";
+    let link_data = r#"
+        {
+            "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+            "lifecycle-state": "INSTALLED",
+            "node-edge-point": [
+                {
+                    "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476",
+                    "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577"
+                }
+            ]
+        }"#;
+    let reordered_data = r#"{"node-edge-point":[{"node-edge-point-uuid":"65a39427-3055-3ba4-9e15-0ebed4974577","node-uuid":"62d11f13-db6c-3398-8a83-5fac0b2b7476"}],"lifecycle-state":"INSTALLED","uuid":"14219539-208b-35f5-b7cf-35a58e083490"}"#;
+    let changed_data = r#"{"node-edge-point":[{"node-edge-point-uuid":"65a39427-3055-3ba4-9e15-0ebed4974577","node-uuid":"62d11f13-db6c-3398-8a83-5fac0b2b7476"}],"lifecycle-state":"PLANNED","uuid":"14219539-208b-35f5-b7cf-35a58e083490"}"#;
+
+    let link = Link::from_value(&from_str(link_data).unwrap(), host).unwrap();
+    let reordered = Link::from_value(&from_str(reordered_data).unwrap(), host).unwrap();
+    let changed = Link::from_value(&from_str(changed_data).unwrap(), host).unwrap();
+
+    assert_eq!(link.hash, reordered.hash);
+    assert_ne!(link.hash, changed.hash);
+}
+
+/// # Test: `test_link_ref`
+///
+/// This test verifies that the borrowed view of a link borrows its strings from the
+/// payload, and converts into the same `Link` as `Link::from_value`.
+#[test]
+fn test_link_ref() {
+    let value: Value = from_str(
+        r#"{
+            "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+            "name": [
+                {"value-name": "LINK_NAME", "value": "LINK-1"},
+                {"value-name": "CIRCUIT_ID", "value": "MAD-BCN-100G-01"},
+                {"value-name": "USER_LABEL", "value": ""}
+            ],
+            "lifecycle-state": "PLANNED",
+            "node-edge-point": [{
+                "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577",
+                "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476",
+                "topology-uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb"
+            }]
+        }"#,
+    )
+    .unwrap();
+
+    let view = LinkRef::from_value(&value).unwrap();
+    assert_eq!(view.uuid, "14219539-208b-35f5-b7cf-35a58e083490");
+    assert_eq!(view.name(), Some("LINK-1"));
+    assert_eq!(
+        view.node_edge_points[0].node_uuid.as_ptr(),
+        value["node-edge-point"][0]["node-uuid"]
+            .as_str()
+            .unwrap()
+            .as_ptr()
+    );
+
+    let link = Link::from_value(&value, "127.0.0.1").unwrap();
+    // Every named entry is kept, empty ones are dropped
+    assert_eq!(
+        link.names,
+        vec![
+            Name {
+                value_name: Some("LINK_NAME".to_string()),
+                value: "LINK-1".to_string()
+            },
+            Name {
+                value_name: Some("CIRCUIT_ID".to_string()),
+                value: "MAD-BCN-100G-01".to_string()
+            },
+        ]
+    );
+    assert_eq!(
+        view.to_link("127.0.0.1", ContentHash::of(&value), link.date)
+            .unwrap(),
+        link
+    );
+
+    let mut bad = value.clone();
+    bad["node-edge-point"][0]["node-uuid"] = Value::from("bad");
+    match LinkRef::from_value(&bad).unwrap().to_link(
+        "127.0.0.1",
+        ContentHash::of(&bad),
+        Local::now(),
+    ) {
+        Err(Error::BadResponse(msg)) => assert_eq!(msg, "Not found node uuid"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    match LinkRef::from_value(&serde_json::json!({"uuid": 1})) {
+        Err(Error::BadResponse(msg)) => assert!(msg.starts_with("Invalid link:")),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
+
+/// # Test: `test_self_referential_link`
+///
+/// This test checks that a link whose endpoints name the same node edge point of the
+/// same node is accepted with a warning, by both parsing paths, and that the warning is
+/// serialized.
+#[test]
+fn test_self_referential_link() {
+    let endpoint = serde_json::json!({
+        "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577",
+        "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476",
+        "topology-uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb"
+    });
+    let mut value = serde_json::json!({
+        "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+        "node-edge-point": [endpoint.clone(), endpoint]
+    });
+
+    let link = Link::from_value(&value, "127.0.0.1").unwrap();
+    assert_eq!(link.warnings, vec![LinkWarning::SelfReferential]);
+    assert!(to_string(&link)
+        .unwrap()
+        .contains(r#""warnings":["self_referential"]"#));
+    let view = LinkRef::from_value(&value).unwrap();
+    assert_eq!(
+        view.to_link("127.0.0.1", ContentHash::of(&value), link.date)
+            .unwrap()
+            .warnings,
+        vec![LinkWarning::SelfReferential]
+    );
+
+    // Another edge point of the same node is a regular link
+    value["node-edge-point"][1]["node-edge-point-uuid"] =
+        Value::from("63366151-aeb4-3dfd-af66-d471b353aa1c");
+    let link = Link::from_value(&value, "127.0.0.1").unwrap();
+    assert!(link.warnings.is_empty());
+    assert!(!to_string(&link).unwrap().contains("warnings"));
+}
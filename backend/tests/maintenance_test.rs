@@ -0,0 +1,197 @@
+use backend::events::store::EventStore; // Import the event log
+use backend::events::{ChangeEvent, ChangeKind, ObjectKind};
+use backend::maintenance::{MaintenanceSchedule, MaintenanceTarget, MaintenanceWindow};
+use backend::notifications::history::AlertHistory;
+use backend::notifications::Alert;
+use backend::tenant::DEFAULT_TENANT;
+use backend::Error;
+use chrono::{DateTime, Duration, Local};
+use serde_json::json;
+use std::fs;
+use uuid::Uuid;
+
+/// Returns a change event on `object` of the device at `host`, emitted at `date`
+fn event(host: &str, object: ObjectKind, uuid: Uuid, date: DateTime<Local>) -> ChangeEvent {
+    ChangeEvent {
+        host: host.to_string(),
+        topology_uuid: Uuid::nil(),
+        object,
+        uuid,
+        change: ChangeKind::Changed,
+        date,
+    }
+}
+
+/// # Test: `test_maintenance_suppression`
+///
+/// This test verifies that events emitted during a window of their device or link are
+/// stored tagged `suppressed` and are not deliverable, while other events are.
+#[test]
+fn test_maintenance_suppression() {
+    let now = Local::now();
+    let link = Uuid::new_v4();
+    let mut schedule = MaintenanceSchedule::new();
+    schedule
+        .add(MaintenanceWindow {
+            id: Uuid::new_v4(),
+            target: MaintenanceTarget::Device {
+                host: "10.0.0.1".to_string(),
+            },
+            start: now - Duration::hours(1),
+            end: now + Duration::hours(1),
+            reason: None,
+        })
+        .unwrap();
+    let window: MaintenanceWindow = serde_json::from_value(json!({
+        "target": {"kind": "link", "host": "10.0.0.2", "uuid": link},
+        "start": now - Duration::hours(1),
+        "end": now + Duration::hours(1),
+        "reason": "Fiber repair"
+    }))
+    .unwrap();
+    schedule.add(window).unwrap();
+    assert_eq!(schedule.active(now).len(), 2);
+    assert!(schedule.active(now + Duration::hours(2)).is_empty());
+
+    let mut store = EventStore::new();
+    let events = [
+        event("10.0.0.1", ObjectKind::Node, Uuid::new_v4(), now),
+        event("10.0.0.2", ObjectKind::Link, link, now),
+        event("10.0.0.2", ObjectKind::Link, Uuid::new_v4(), now),
+        event(
+            "10.0.0.1",
+            ObjectKind::Node,
+            Uuid::new_v4(),
+            now + Duration::hours(2),
+        ),
+    ];
+    for event in events {
+        store
+            .append_scheduled(DEFAULT_TENANT, event, &schedule)
+            .unwrap();
+    }
+
+    let stored = store.after(DEFAULT_TENANT, 0);
+    assert_eq!(stored.len(), 4);
+    assert!(store.get(1).is_some_and(|event| event.suppressed));
+    assert_eq!(store.get(5), None);
+    let deliverable: Vec<bool> = stored.iter().map(|event| event.deliverable()).collect();
+    assert_eq!(deliverable, vec![false, false, true, true]);
+    assert_eq!(serde_json::to_value(stored[0]).unwrap()["suppressed"], true);
+    assert!(serde_json::to_value(stored[2])
+        .unwrap()
+        .get("suppressed")
+        .is_none());
+}
+
+/// # Test: `test_maintenance_alert_suppression`
+///
+/// This test checks that the threshold alerts of a device under maintenance are recorded
+/// tagged `suppressed`, that a link window does not cover the alerts of its device, and
+/// that the history lists the alerts of a tenant, most recent first.
+#[test]
+fn test_maintenance_alert_suppression() {
+    let now = Local::now();
+    let mut schedule = MaintenanceSchedule::new();
+    schedule
+        .add(MaintenanceWindow {
+            id: Uuid::new_v4(),
+            target: MaintenanceTarget::Device {
+                host: "10.0.0.1".to_string(),
+            },
+            start: now - Duration::hours(1),
+            end: now + Duration::hours(1),
+            reason: None,
+        })
+        .unwrap();
+    schedule
+        .add(MaintenanceWindow {
+            id: Uuid::new_v4(),
+            target: MaintenanceTarget::Link {
+                host: "10.0.0.2".to_string(),
+                uuid: Uuid::new_v4(),
+            },
+            start: now - Duration::hours(1),
+            end: now + Duration::hours(1),
+            reason: None,
+        })
+        .unwrap();
+    let down = |host: &str, since: DateTime<Local>| Alert::DeviceDown {
+        host: host.to_string(),
+        error: "Device timeout".to_string(),
+        since,
+    };
+
+    let mut history = AlertHistory::new();
+    for alert in [
+        down("10.0.0.1", now),
+        down("10.0.0.2", now),
+        down("10.0.0.1", now + Duration::hours(2)),
+    ] {
+        let suppressed = schedule.suppresses_alert(&alert);
+        history.record(DEFAULT_TENANT, alert, suppressed);
+    }
+    history.record("acme", down("10.0.0.3", now), false);
+
+    let alerts = history.list(DEFAULT_TENANT);
+    let suppressed: Vec<bool> = alerts.iter().map(|alert| alert.suppressed).collect();
+    assert_eq!(suppressed, vec![false, false, true]);
+    assert_eq!(alerts[2].alert, down("10.0.0.1", now));
+    let stored = serde_json::to_value(alerts[2]).unwrap();
+    assert_eq!(
+        (&stored["type"], &stored["suppressed"]),
+        (&json!("device_down"), &json!(true))
+    );
+    assert_eq!(history.list("acme").len(), 1);
+}
+
+/// # Test: `test_maintenance_schedule_persistence`
+///
+/// This test checks that windows survive a reopen, that removing an unknown window and
+/// adding a window ending before its start fail.
+#[test]
+fn test_maintenance_schedule_persistence() {
+    let path = std::env::temp_dir().join(format!("maintenance-{}.json", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let now = Local::now();
+    let window = MaintenanceWindow {
+        id: Uuid::new_v4(),
+        target: MaintenanceTarget::Device {
+            host: "10.0.0.1".to_string(),
+        },
+        start: now,
+        end: now + Duration::hours(4),
+        reason: Some("Software upgrade".to_string()),
+    };
+
+    let id = {
+        let mut schedule = MaintenanceSchedule::open(&path).unwrap();
+        schedule.add(window.clone()).unwrap()
+    };
+    let mut schedule = MaintenanceSchedule::open(&path).unwrap();
+    assert_eq!(schedule.list().len(), 1);
+    assert_eq!(schedule.list()[0], window);
+
+    match schedule.add(MaintenanceWindow {
+        end: now - Duration::hours(1),
+        ..window
+    }) {
+        Err(Error::Invalid(msg)) => {
+            assert_eq!(msg, "Invalid maintenance window: end must be after start")
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(id) => panic!("Expected an error, but got {:?}", id),
+    }
+
+    schedule.remove(&id).unwrap();
+    assert!(MaintenanceSchedule::open(&path).unwrap().list().is_empty());
+    match schedule.remove(&id) {
+        Err(Error::NotFound(msg)) => {
+            assert_eq!(msg, format!("Not found maintenance window {}", id))
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(()) => panic!("Expected an error, but got ()"),
+    }
+
+    fs::remove_file(&path).unwrap();
+}
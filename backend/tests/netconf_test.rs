@@ -0,0 +1,251 @@
+use backend::models::device::Device; // Import the device model
+use backend::models::topology::{Topology, TOPOLOGY_CONTEXT_PATH}; // Import the topology model
+use backend::setup::config::Config; // Import the runtime configuration
+use backend::southbound::netconf::{subtree_filter, Framing, NetconfClient, NetconfSession}; // Import the NETCONF driver
+use backend::southbound::restconf::Fetched;
+use backend::southbound::{FetchOptions, SouthboundProtocol};
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::json;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, DuplexStream};
+
+/// Data of a `<get>` reply holding one topology with one node
+const DATA: &str = r#"<data>
+  <context xmlns="urn:onf:otcc:yang:tapi-common">
+    <uuid>2b8e4e4a-3e5a-4fd9-8b06-8c3a8e1a9f10</uuid>
+    <topology-context xmlns="urn:onf:otcc:yang:tapi-topology">
+      <topology>
+        <uuid>4e537278-79f8-39ad-804b-f0b553cb2ffb</uuid>
+        <node>
+          <uuid>62d11f13-db6c-3398-8a83-5fac0b2b7476</uuid>
+          <name><value-name>NODE_NAME</value-name><value>Madrid</value></name>
+        </node>
+      </topology>
+    </topology-context>
+  </context>
+</data>"#;
+
+/// Server side of a NETCONF session, answering every request with the next reply
+async fn device(stream: DuplexStream, capabilities: &[&str], replies: Vec<String>) {
+    let mut stream = BufReader::new(stream);
+    let mut hello = vec![];
+    while !hello.ends_with(b"]]>]]>") {
+        stream.read_until(b'>', &mut hello).await.unwrap();
+    }
+    let capabilities: String = capabilities
+        .iter()
+        .map(|capability| format!("<capability>{}</capability>", capability))
+        .collect();
+    let hello = format!(
+        "<hello xmlns=\"urn:ietf:params:xml:ns:netconf:base:1.0\"><capabilities>{}</capabilities><session-id>4</session-id></hello>]]>]]>",
+        capabilities
+    );
+    stream.get_mut().write_all(hello.as_bytes()).await.unwrap();
+    let chunked = capabilities.contains("base:1.1");
+
+    for reply in replies {
+        let reply = format!(
+            "<rpc-reply xmlns=\"urn:ietf:params:xml:ns:netconf:base:1.0\">{}</rpc-reply>",
+            reply
+        );
+        if chunked {
+            // The request is a single chunk, the reply is sent in two
+            let mut header = String::new();
+            stream.read_line(&mut header).await.unwrap();
+            header.clear();
+            stream.read_line(&mut header).await.unwrap();
+            let size: usize = header.trim_start_matches('#').trim().parse().unwrap();
+            let mut request = vec![0; size + "\n##\n".len()];
+            stream.read_exact(&mut request).await.unwrap();
+            let (first, second) = reply.split_at(reply.len() / 2);
+            let framed = format!(
+                "\n#{}\n{}\n#{}\n{}\n##\n",
+                first.len(),
+                first,
+                second.len(),
+                second
+            );
+            stream.get_mut().write_all(framed.as_bytes()).await.unwrap();
+        } else {
+            let mut request = vec![];
+            while !request.ends_with(b"]]>]]>") {
+                stream.read_until(b'>', &mut request).await.unwrap();
+            }
+            let framed = format!("{}]]>]]>", reply);
+            stream.get_mut().write_all(framed.as_bytes()).await.unwrap();
+        }
+    }
+}
+
+/// Returns a device of the registry reached over NETCONF
+fn netconf_device() -> Device {
+    Device::from_value(&json!({
+        "host": "10.0.0.4",
+        "auth": {"username": "tapi", "password": "secret"}
+    }))
+    .unwrap()
+}
+
+/// # Test: `test_netconf_config`
+///
+/// This test verifies that the NETCONF options of a device are read from the config,
+/// that `ssh` opens the `netconf` subsystem as the user of the device without
+/// prompting, and that RESTCONF paths are turned into subtree filters.
+#[test]
+fn test_netconf_config() {
+    let config = Config::parse(
+        r#"
+        [devices."10.0.0.4".netconf]
+        identity_file = "/etc/device-manager/id_ed25519"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.netconf("10.0.0.1"), None);
+    let options = config.netconf("10.0.0.4").unwrap();
+    assert_eq!(options.port, 830);
+    assert_eq!(
+        options.ssh_args(&netconf_device()).unwrap(),
+        [
+            "-o",
+            "BatchMode=yes",
+            "-o",
+            "ServerAliveInterval=30",
+            "-p",
+            "830",
+            "-i",
+            "/etc/device-manager/id_ed25519",
+            "-s",
+            "--",
+            "tapi@10.0.0.4",
+            "netconf"
+        ]
+    );
+    let token =
+        Device::from_value(&json!({"host": "10.0.0.5", "auth": {"token": "secret"}})).unwrap();
+    match options.ssh_args(&token) {
+        Err(Error::Invalid(msg)) => {
+            assert_eq!(msg, "NETCONF of 10.0.0.5 needs a basic auth username")
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    // The user would be read as an option of `ssh`
+    let injected = Device::from_value(&json!({
+        "host": "10.0.0.6",
+        "auth": {"username": "-oProxyCommand=touch /tmp/pwned", "password": "secret"}
+    }))
+    .unwrap();
+    match options.ssh_args(&injected) {
+        Err(Error::Invalid(msg)) => {
+            assert_eq!(
+                msg,
+                "NETCONF of 10.0.0.6 has a user or host starting with '-'"
+            )
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+
+    assert_eq!(
+        subtree_filter(TOPOLOGY_CONTEXT_PATH).unwrap(),
+        "<filter type=\"subtree\"><context xmlns=\"urn:onf:otcc:yang:tapi-common\"><topology-context xmlns=\"urn:onf:otcc:yang:tapi-topology\"></topology-context></context></filter>"
+    );
+    assert_eq!(
+        subtree_filter("/restconf/data/tapi-common:context/tapi-topology:topology-context/topology=4e537278").unwrap(),
+        "<filter type=\"subtree\"><context xmlns=\"urn:onf:otcc:yang:tapi-common\"><topology-context xmlns=\"urn:onf:otcc:yang:tapi-topology\"><topology><uuid>4e537278</uuid></topology></topology-context></context></filter>"
+    );
+    match subtree_filter("/restconf/data/acme-topology:context") {
+        Err(Error::Invalid(msg)) => {
+            assert_eq!(msg, "Unknown namespace of YANG module acme-topology")
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
+
+/// # Test: `test_netconf_get`
+///
+/// This test verifies that a NETCONF 1.1 session reads chunked replies, that the
+/// topology context is answered as RESTCONF would and parses into the models, that an
+/// unchanged context is reported as such, and that `rpc-error`s fail the request.
+#[tokio::test]
+async fn test_netconf_get() {
+    let (client, server) = tokio::io::duplex(64 * 1024);
+    let error = "<rpc-error><error-type>application</error-type><error-tag>operation-not-supported</error-tag><error-message>get is not supported</error-message></rpc-error>";
+    let replies = vec![
+        DATA.to_string(),
+        DATA.to_string(),
+        DATA.to_string(),
+        error.to_string(),
+    ];
+    tokio::spawn(device(
+        server,
+        &[
+            "urn:ietf:params:netconf:base:1.0",
+            "urn:ietf:params:netconf:base:1.1",
+        ],
+        replies,
+    ));
+    let client = NetconfClient::over(netconf_device(), client).await.unwrap();
+
+    let context = client
+        .get(TOPOLOGY_CONTEXT_PATH, &FetchOptions::default())
+        .await
+        .unwrap();
+    let topologies = Topology::list_from_context(&context).unwrap();
+    let topology = Topology::from_value(&topologies[0], "10.0.0.4").unwrap();
+    assert_eq!(topology.nodes.len(), 1);
+    assert!(context["tapi-topology:topology-context"]["topology"].is_array());
+
+    assert!(matches!(
+        client.get_if_changed(TOPOLOGY_CONTEXT_PATH).await.unwrap(),
+        Fetched::Modified(_)
+    ));
+    assert_eq!(
+        client.get_if_changed(TOPOLOGY_CONTEXT_PATH).await.unwrap(),
+        Fetched::Unchanged
+    );
+    match client
+        .get(TOPOLOGY_CONTEXT_PATH, &FetchOptions::default())
+        .await
+    {
+        Err(Error::BadResponse(msg)) => assert_eq!(
+            msg,
+            "NETCONF error: operation-not-supported: get is not supported"
+        ),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
+
+/// # Test: `test_netconf_1_0`
+///
+/// This test verifies that a device speaking NETCONF 1.0 only gets `]]>]]>` delimited
+/// messages, and that a resource missing from the running configuration is not found.
+#[tokio::test]
+async fn test_netconf_1_0() {
+    let (client, server) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(device(
+        server,
+        &["urn:ietf:params:netconf:base:1.0"],
+        vec!["<ok/>".to_string()],
+    ));
+    let mut session = NetconfSession::open(client).await.unwrap();
+    assert_eq!(session.framing(), Framing::EndOfMessage);
+    session.close().await.unwrap();
+
+    let (client, server) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(device(
+        server,
+        &["urn:ietf:params:netconf:base:1.0"],
+        vec!["<data/>".to_string()],
+    ));
+    let client = NetconfClient::over(netconf_device(), client).await.unwrap();
+    match client.get_config(TOPOLOGY_CONTEXT_PATH).await {
+        Err(Error::NotFound(msg)) => assert_eq!(
+            msg,
+            format!("Not found {} on the device", TOPOLOGY_CONTEXT_PATH)
+        ),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
@@ -0,0 +1,63 @@
+use backend::models::node_edge_point::NodeEdgePoint; // Import the `NodeEdgePoint` model
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::{from_str, to_value, Value}; // Importing JSON serialization/deserialization utilities
+use uuid::Uuid; // For handling UUIDs (universally unique identifiers)
+
+/// # Test: `test_topology_uuid`
+///
+/// This test verifies that the `topology-uuid` of a node edge point is retained and
+/// serialized back.
+#[test]
+fn test_topology_uuid() {
+    let raw_data = r#"
+        {
+            "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577",
+            "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476",
+            "topology-uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb"
+        }"#;
+
+    let raw_value: Value = from_str(raw_data).unwrap_or_default();
+    let node_edge_point = NodeEdgePoint::from_value(&raw_value).unwrap();
+
+    let topology_uuid = Uuid::parse_str("4e537278-79f8-39ad-804b-f0b553cb2ffb").unwrap();
+    assert_eq!(node_edge_point.topology_uuid, Some(topology_uuid));
+
+    // The serialized form keeps the TAPI field names
+    assert_eq!(to_value(&node_edge_point).unwrap(), raw_value);
+}
+
+/// # Test: `test_without_topology_uuid`
+///
+/// This test checks that a missing `topology-uuid` is accepted and omitted on
+/// serialization, while an invalid one is rejected.
+#[test]
+fn test_without_topology_uuid() {
+    let raw_data = r#"
+        {
+            "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577",
+            "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"
+        }"#;
+
+    let raw_value: Value = from_str(raw_data).unwrap_or_default();
+    let node_edge_point = NodeEdgePoint::from_value(&raw_value).unwrap();
+
+    assert_eq!(node_edge_point.topology_uuid, None);
+    assert!(to_value(&node_edge_point)
+        .unwrap()
+        .get("topology-uuid")
+        .is_none());
+
+    let raw_data = r#"
+        {
+            "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577",
+            "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476",
+            "topology-uuid": "not-a-uuid"
+        }"#;
+
+    let raw_value: Value = from_str(raw_data).unwrap_or_default();
+    match NodeEdgePoint::from_value(&raw_value) {
+        Err(Error::BadResponse(msg)) => assert_eq!(msg, "Invalid topology uuid".to_string()),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but got Ok"),
+    }
+}
@@ -0,0 +1,96 @@
+use backend::models::direction::Direction; // Import the TAPI forwarding direction
+use backend::models::topology::Topology;
+use backend::normalization::pair_links; // Import the link normalization
+use backend::setup::config::Config;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+const NODE_A: &str = "00000000-0000-0000-0000-00000000000a";
+const NODE_B: &str = "00000000-0000-0000-0000-00000000000b";
+const NODE_C: &str = "00000000-0000-0000-0000-00000000000c";
+
+/// A link payload between two nodes, endpoints in order
+fn link(uuid: u128, from: &str, to: &str, direction: &str) -> Value {
+    json!({
+        "uuid": Uuid::from_u128(uuid),
+        "direction": direction,
+        "node-edge-point": [
+            {"node-uuid": from, "node-edge-point-uuid": from},
+            {"node-uuid": to, "node-edge-point-uuid": to}
+        ]
+    })
+}
+
+/// A topology holding the given links
+fn topology(links: Vec<Value>) -> Topology {
+    Topology::from_value(
+        &json!({"uuid": "00000000-0000-0000-0000-0000000000aa", "link": links}),
+        "10.0.0.1",
+    )
+    .unwrap()
+}
+
+/// # Test: `test_pair_links`
+///
+/// This test verifies that A→B and B→A links are merged into one bidirectional link
+/// referencing both raw UUIDs, that a change on either side changes its hash, and that
+/// links without a mate are kept as reported.
+#[test]
+fn test_pair_links() {
+    let mut b_to_a = link(2, NODE_B, NODE_A, "UNIDIRECTIONAL");
+    b_to_a["name"] = json!([{"value-name": "LINK_NAME", "value": "MAD-BCN"}]);
+    let links = vec![
+        link(7, NODE_A, NODE_B, "UNIDIRECTIONAL"),
+        link(3, NODE_A, NODE_C, "UNIDIRECTIONAL"),
+        b_to_a.clone(),
+        link(4, NODE_B, NODE_C, "BIDIRECTIONAL"),
+    ];
+    let mut paired = topology(links.clone());
+    assert_eq!(pair_links(&mut paired), 1);
+
+    let uuids: Vec<Uuid> = paired.links.iter().map(|link| link.uuid).collect();
+    assert_eq!(
+        uuids,
+        vec![Uuid::from_u128(2), Uuid::from_u128(3), Uuid::from_u128(4)]
+    );
+    let merged = &paired.links[0];
+    assert_eq!(
+        merged.raw_uuids,
+        vec![Uuid::from_u128(2), Uuid::from_u128(7)]
+    );
+    assert_eq!(merged.direction, Some(Direction::Bidirectional));
+    assert_eq!(merged.name.as_deref(), Some("MAD-BCN"));
+    assert_eq!(merged.node_edge_points[0].node_uuid.to_string(), NODE_B);
+    // Left without a mate
+    assert_eq!(paired.links[1].direction, Some(Direction::Unidirectional));
+    assert!(paired.links[1].raw_uuids.is_empty());
+
+    // A change of the side whose UUID was dropped still changes the merged link
+    let mut changed = links.clone();
+    changed[0]["lifecycle-state"] = json!("PENDING_REMOVAL");
+    let mut changed = topology(changed);
+    pair_links(&mut changed);
+    assert_ne!(changed.links[0].hash, merged.hash);
+
+    let mut unchanged = topology(links);
+    assert_eq!(pair_links(&mut unchanged), 1);
+    assert_eq!(unchanged.links[0].hash, merged.hash);
+}
+
+/// # Test: `test_pair_links_config`
+///
+/// This test checks that links are only paired for the devices enabling it, and that
+/// the setting is reported when the config is reloaded.
+#[test]
+fn test_pair_links_config() {
+    let previous = Config::default();
+    assert!(!previous.pair_links("10.0.0.1"));
+    let config = Config::parse("[devices.\"10.0.0.1\"]\npair_links = true").unwrap();
+    assert!(config.pair_links("10.0.0.1"));
+    assert!(!config.pair_links("10.0.0.2"));
+
+    let changes = config.changes(&previous);
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].setting, "devices.10.0.0.1.pair_links");
+    assert_eq!(changes[0].to.as_deref(), Some("true"));
+}
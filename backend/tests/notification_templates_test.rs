@@ -0,0 +1,68 @@
+use backend::events::{ChangeEvent, ChangeKind, ObjectKind}; // Import the change event types
+use backend::models::lifecycle_state::LifecycleState; // Import the lifecycle states
+use backend::notifications::templates::{preview, validate, NotificationTemplates}; // Import the templates
+use backend::notifications::Channel; // Import the notification channels
+use backend::Error; // Import the custom error type from the backend module
+use chrono::Local;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// # Test: `test_notification_templates_render`
+///
+/// This test verifies that operator templates override the built-in ones and can use
+/// every field of the change event.
+#[test]
+fn test_notification_templates_render() {
+    let overrides = HashMap::from([(
+        Channel::Slack,
+        "{{ host }}: {{ object }} {{ change.type }} {{ change.from }} -> {{ change.to }}"
+            .to_string(),
+    )]);
+    let templates = NotificationTemplates::new(&overrides).unwrap();
+
+    let event = ChangeEvent {
+        host: "10.95.87.21".to_string(),
+        topology_uuid: Uuid::parse_str("4e537278-79f8-39ad-804b-f0b553cb2ffb").unwrap(),
+        object: ObjectKind::Link,
+        uuid: Uuid::parse_str("14219539-208b-35f5-b7cf-35a58e083490").unwrap(),
+        change: ChangeKind::LifecycleTransition {
+            from: Some(LifecycleState::Planned),
+            to: Some(LifecycleState::Installed),
+        },
+        date: Local::now(),
+    };
+
+    assert_eq!(
+        templates.render(Channel::Slack, &event).unwrap(),
+        "10.95.87.21: link lifecycle_transition PLANNED -> INSTALLED"
+    );
+    assert!(templates
+        .render(Channel::Email, &event)
+        .unwrap()
+        .starts_with("Device 10.95.87.21: link 14219539-208b-35f5-b7cf-35a58e083490"));
+}
+
+/// # Test: `test_notification_template_validation`
+///
+/// This test checks that syntax errors and unknown variables are rejected, and that a
+/// valid template is previewed with the sample event.
+#[test]
+fn test_notification_template_validation() {
+    assert_eq!(
+        preview("{{ object }} on {{ host }}").unwrap(),
+        "link on 10.95.87.21"
+    );
+
+    match validate("{{ host ") {
+        Err(Error::Custom(msg)) => assert!(msg.starts_with("Invalid template")),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but got Ok"),
+    }
+
+    let mut templates = NotificationTemplates::default();
+    match templates.set(Channel::Webhook, "{{ hostname }}") {
+        Err(Error::Custom(msg)) => assert!(msg.contains("hostname")),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but got Ok"),
+    }
+}
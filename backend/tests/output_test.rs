@@ -0,0 +1,46 @@
+use backend::export::output::{render_output, OutputFormat}; // Import the CLI output formatter
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::json;
+
+/// # Test: `test_render_output`
+///
+/// This test verifies that records are rendered as aligned table columns, as JSON and
+/// as YAML, and that unknown output formats are rejected.
+#[test]
+fn test_render_output() {
+    let devices = json!([
+        {"host": "10.0.0.1", "port": null, "tags": ["core"], "enabled": true},
+        {"host": "10.0.0.22", "port": 8443, "tags": [], "enabled": false}
+    ]);
+
+    let table = render_output(&devices, OutputFormat::Table).unwrap();
+    assert_eq!(
+        table,
+        concat!(
+            "HOST       PORT  TAGS      ENABLED\n",
+            "10.0.0.1         [\"core\"]  true\n",
+            "10.0.0.22  8443  []        false\n"
+        )
+    );
+    let table = render_output(&json!({"deleted": 3, "left": 10}), OutputFormat::Table).unwrap();
+    assert_eq!(table, "deleted  3\nleft     10\n");
+    assert_eq!(render_output(&json!([]), OutputFormat::Table).unwrap(), "");
+
+    let output = render_output(&devices, OutputFormat::Json).unwrap();
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&output).unwrap(),
+        devices
+    );
+    let output = render_output(&devices, OutputFormat::Yaml).unwrap();
+    assert_eq!(
+        serde_yaml::from_str::<serde_json::Value>(&output).unwrap(),
+        devices
+    );
+
+    assert_eq!("yaml".parse::<OutputFormat>().unwrap(), OutputFormat::Yaml);
+    match "xml".parse::<OutputFormat>() {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid output format xml"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(format) => panic!("Expected an error, but got {:?}", format),
+    }
+}
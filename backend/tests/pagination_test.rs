@@ -0,0 +1,94 @@
+use backend::pagination::CursorSigner; // Import the cursor signer
+use backend::Error; // Import the custom error type from the backend module
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+/// Filters of a paginated link listing
+#[derive(Serialize)]
+struct LinkQuery {
+    host: &'static str,
+    lifecycle_state: Option<&'static str>,
+}
+
+/// Sort key of a paginated link listing
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct LinkKey {
+    date: i64,
+    uuid: String,
+}
+
+/// Returns the message of an expected error
+fn error_message(result: Result<LinkKey, Error>) -> String {
+    match result {
+        Err(Error::InvalidCursor(msg)) => msg,
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but got Ok"),
+    }
+}
+
+/// # Test: `test_cursor_round_trip`
+///
+/// This test verifies that a cursor decodes back to its sort key for the same query.
+#[test]
+fn test_cursor_round_trip() {
+    let signer = CursorSigner::new("secret", Duration::minutes(5));
+    let query = LinkQuery {
+        host: "127.0.0.1",
+        lifecycle_state: Some("INSTALLED"),
+    };
+    let key = LinkKey {
+        date: 1_729_000_000,
+        uuid: "14219539-208b-35f5-b7cf-35a58e083490".to_string(),
+    };
+
+    let cursor = signer.encode(&key, &query).unwrap();
+    assert_eq!(signer.decode::<LinkKey, _>(&cursor, &query).unwrap(), key);
+}
+
+/// # Test: `test_cursor_rejections`
+///
+/// This test checks that forged, reused and expired cursors are rejected.
+#[test]
+fn test_cursor_rejections() {
+    let signer = CursorSigner::new("secret", Duration::minutes(5));
+    let query = LinkQuery {
+        host: "127.0.0.1",
+        lifecycle_state: None,
+    };
+    let key = LinkKey {
+        date: 1_729_000_000,
+        uuid: "14219539-208b-35f5-b7cf-35a58e083490".to_string(),
+    };
+    let cursor = signer.encode(&key, &query).unwrap();
+
+    // Signed with another key
+    let other_signer = CursorSigner::new("other secret", Duration::minutes(5));
+    assert_eq!(
+        error_message(other_signer.decode(&cursor, &query)),
+        "Invalid cursor signature"
+    );
+
+    // Garbage
+    assert_eq!(
+        error_message(signer.decode("not-a-cursor", &query)),
+        "Invalid cursor"
+    );
+
+    // Issued for another query
+    let other_query = LinkQuery {
+        host: "127.0.0.2",
+        lifecycle_state: None,
+    };
+    assert_eq!(
+        error_message(signer.decode(&cursor, &other_query)),
+        "Cursor does not match the query"
+    );
+
+    // Expired
+    let expired_signer = CursorSigner::new("secret", Duration::minutes(-1));
+    let expired_cursor = expired_signer.encode(&key, &query).unwrap();
+    assert_eq!(
+        error_message(signer.decode(&expired_cursor, &query)),
+        "Expired cursor"
+    );
+}
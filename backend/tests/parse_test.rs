@@ -0,0 +1,148 @@
+use backend::models::parse::{ParseIssue, ParseMode, ParseOptions, PARALLEL_THRESHOLD}; // Import the parse settings
+use backend::models::topology::Topology;
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::json;
+use uuid::Uuid;
+
+/// # Test: `test_lenient_parse`
+///
+/// This test verifies that a lenient parse skips and reports a link with a malformed
+/// node-edge-point while keeping the valid links, and that a strict parse fails.
+#[test]
+fn test_lenient_parse() {
+    let valid = json!({
+        "uuid": Uuid::from_u128(2),
+        "node-edge-point": [{
+            "node-uuid": Uuid::from_u128(10),
+            "node-edge-point-uuid": Uuid::from_u128(11),
+            "topology-uuid": Uuid::from_u128(1)
+        }]
+    });
+    let malformed = json!({
+        "uuid": Uuid::from_u128(3).to_string(),
+        "node-edge-point": [{"node-uuid": "bad"}]
+    });
+    let topology = json!({"uuid": Uuid::from_u128(1), "link": [malformed, valid]});
+
+    let (parsed, report) =
+        Topology::parse(&topology, "10.0.0.1", &ParseOptions::default()).unwrap();
+    assert_eq!(parsed.links.len(), 1);
+    assert_eq!(parsed.links[0].uuid, Uuid::from_u128(2));
+    assert_eq!(report.issues.len(), 1);
+    let ParseIssue {
+        object,
+        index,
+        uuid,
+        ..
+    } = &report.issues[0];
+    assert_eq!(
+        (object.as_str(), *index, uuid.clone()),
+        ("link", 0, Some(Uuid::from_u128(3).to_string()))
+    );
+
+    let strict = ParseOptions {
+        mode: ParseMode::Strict,
+        ..Default::default()
+    };
+    match Topology::parse(&topology, "10.0.0.1", &strict) {
+        Err(Error::BadResponse(msg)) => assert_eq!(msg, report.issues[0].error),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok((topology, _)) => panic!("Expected an error, but got {:?}", topology),
+    }
+
+    match "loose".parse::<ParseMode>() {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid parse mode loose"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(mode) => panic!("Expected an error, but got {:?}", mode),
+    }
+}
+
+/// # Test: `test_parallel_parse`
+///
+/// This test checks that a link list large enough to be parsed in parallel keeps the
+/// links in order and reports the bad ones at their index, a strict parse failing on the
+/// first of them.
+#[test]
+fn test_parallel_parse() {
+    let links: Vec<_> = (0..PARALLEL_THRESHOLD as u128 * 2)
+        .map(|index| match index {
+            // Every 500th link has a malformed uuid
+            index if index % 500 == 7 => {
+                json!({"uuid": format!("bad-{}", index), "node-edge-point": []})
+            }
+            index => json!({"uuid": Uuid::from_u128(index), "node-edge-point": []}),
+        })
+        .collect();
+    let topology = json!({"uuid": Uuid::from_u128(1), "link": links});
+
+    let (parsed, report) =
+        Topology::parse(&topology, "10.0.0.1", &ParseOptions::default()).unwrap();
+    let bad: Vec<usize> = report.issues.iter().map(|issue| issue.index).collect();
+    assert_eq!(bad, vec![7, 507, 1007, 1507]);
+    assert_eq!(report.issues[1].uuid.as_deref(), Some("bad-507"));
+    assert_eq!(parsed.links.len(), PARALLEL_THRESHOLD * 2 - 4);
+    assert!(parsed
+        .links
+        .windows(2)
+        .all(|pair| pair[0].uuid.as_u128() < pair[1].uuid.as_u128()));
+
+    let strict = ParseOptions {
+        mode: ParseMode::Strict,
+        ..Default::default()
+    };
+    match Topology::parse(&topology, "10.0.0.1", &strict) {
+        Err(Error::BadResponse(msg)) => assert_eq!(msg, report.issues[0].error),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok((topology, _)) => panic!("Expected an error, but got {:?}", topology),
+    }
+}
+
+/// # Test: `test_parse_with_previous`
+///
+/// This test checks that a parse given the previous topologies of the host reuses the
+/// links whose content hash is unchanged, and builds the new and changed ones.
+#[test]
+fn test_parse_with_previous() {
+    let link = |uuid: u128, name: &str| {
+        json!({
+            "uuid": Uuid::from_u128(uuid),
+            "name": [{"value-name": "LINK_NAME", "value": name}],
+            "node-edge-point": [{
+                "node-edge-point-uuid": Uuid::from_u128(100 + uuid),
+                "node-uuid": Uuid::from_u128(200 + uuid)
+            }]
+        })
+    };
+    let before = json!({"uuid": Uuid::from_u128(1), "link": [link(1, "a"), link(2, "b")]});
+    let mut previous = Topology::from_value(&before, "10.0.0.1").unwrap();
+    // Only a reused link keeps this name, a rebuilt one reads it from its payload
+    previous.links[0].name = Some("reused".to_string());
+    previous.links[0]
+        .extensions
+        .insert("stale".to_string(), json!(true));
+
+    let after = json!({
+        "uuid": Uuid::from_u128(1),
+        "link": [link(1, "a"), link(2, "c"), link(3, "d")]
+    });
+    let options = ParseOptions {
+        previous: Some(std::slice::from_ref(&previous)),
+        ..Default::default()
+    };
+    let (topology, report) = Topology::parse(&after, "10.0.0.1", &options).unwrap();
+    assert!(report.is_empty());
+    let names: Vec<Option<&str>> = topology
+        .links
+        .iter()
+        .map(|link| link.name.as_deref())
+        .collect();
+    assert_eq!(names, [Some("reused"), Some("c"), Some("d")]);
+    assert_eq!(topology.links[0].hash, previous.links[0].hash);
+    assert!(topology.links[0].date >= previous.links[0].date);
+    // Extensions come from the current vendor profile, none here
+    assert!(topology.links[0].extensions.is_empty());
+
+    // The links of another host are not reused
+    let (topology, _) = Topology::parse(&after, "10.0.0.2", &options).unwrap();
+    assert_eq!(topology.links[0].name.as_deref(), Some("a"));
+}
@@ -0,0 +1,48 @@
+use backend::payloads::{PayloadConfig, PayloadStore}; // Import the raw payload store
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// # Test: `test_payload_compression`
+///
+/// This test verifies that payloads are stored compressed, are read back unchanged, and
+/// that payloads stored before compression was switched on remain readable.
+#[test]
+fn test_payload_compression() {
+    let directory = std::env::temp_dir().join(format!("payloads_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+
+    // A big, repetitive context like the ones of large networks
+    let nodes: Vec<Value> = (0..500)
+        .map(|index| json!({"uuid": Uuid::nil(), "name": [{"value-name": "NODE_NAME", "value": format!("node-{}", index)}]}))
+        .collect();
+    let payload = json!({"tapi-topology:topology": [{"node": nodes}]});
+
+    let plain = PayloadStore::open(
+        &directory,
+        PayloadConfig {
+            compress: false,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let plain_id = plain.save(&payload).unwrap();
+
+    let compressed = PayloadStore::open(&directory, PayloadConfig::default()).unwrap();
+    let compressed_id = compressed.save(&payload).unwrap();
+
+    assert_eq!(compressed.load(&compressed_id).unwrap(), payload);
+    assert_eq!(compressed.load(&plain_id).unwrap(), payload);
+
+    let size = |name: String| std::fs::metadata(directory.join(name)).unwrap().len();
+    assert!(size(format!("{}.json.zst", compressed_id)) * 10 < size(format!("{}.json", plain_id)));
+
+    let missing = Uuid::nil();
+    match compressed.load(&missing) {
+        Err(Error::NotFound(msg)) => assert_eq!(msg, format!("Not found payload {}", missing)),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(payload) => panic!("Expected an error, but got {:?}", payload),
+    }
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
@@ -0,0 +1,56 @@
+use backend::persist::{read_json, write_atomic, write_json}; // Import the store file helpers
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::{json, Value};
+
+/// # Test: `test_persist_round_trip`
+///
+/// This test verifies that a store file is written through a temporary file renamed over
+/// it, that nothing is left behind, and that a missing file reads as `None`.
+#[test]
+fn test_persist_round_trip() {
+    let directory = std::env::temp_dir().join(format!("persist_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("store.json");
+
+    assert_eq!(read_json::<Value>(&path, "store").unwrap(), None);
+
+    write_json(&path, &json!({"version": 1}), "store").unwrap();
+    write_json(&path, &json!({"version": 2}), "store").unwrap();
+    assert_eq!(
+        read_json::<Value>(&path, "store").unwrap(),
+        Some(json!({"version": 2}))
+    );
+    let files: Vec<_> = std::fs::read_dir(&directory).unwrap().collect();
+    assert_eq!(files.len(), 1);
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+/// # Test: `test_persist_errors`
+///
+/// This test checks that a corrupted file and an unwritable destination are reported
+/// with what the file holds.
+#[test]
+fn test_persist_errors() {
+    let directory = std::env::temp_dir().join(format!("persist_errors_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("jobs.json");
+    std::fs::write(&path, "{\"truncated").unwrap();
+
+    match read_json::<Value>(&path, "jobs") {
+        Err(Error::Custom(msg)) => assert!(msg.starts_with("Invalid jobs file"), "{}", msg),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(value) => panic!("Expected an error, but got {:?}", value),
+    }
+
+    let missing = directory.join("missing").join("jobs.json");
+    match write_atomic(&missing, b"{}", "jobs") {
+        Err(Error::Custom(msg)) => assert!(msg.starts_with("Failed to write jobs"), "{}", msg),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(()) => panic!("Expected an error, but got ()"),
+    }
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
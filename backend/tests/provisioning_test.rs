@@ -0,0 +1,382 @@
+use backend::jobs::{JobQueue, JobStatus};
+use backend::models::connectivity_service::{ConnectivityServiceRequest, ServiceStateChange}; // Import the provisioning requests
+use backend::models::device::Device;
+use backend::models::topology::Topology;
+use backend::provisioning::{
+    change_connectivity_service_state, create_connectivity_service, delete_connectivity_service,
+    dry_run, Confirmation,
+};
+use backend::southbound::restconf::RestconfClient;
+use backend::tenant::DEFAULT_TENANT;
+use backend::Error;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+/// Serves a single canned HTTP response and forwards the raw request it received
+async fn serve_once(status: &'static str, body: &'static str) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = mpsc::channel(1);
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buffer = vec![0; 8192];
+        let read = socket.read(&mut buffer).await.unwrap();
+        sender
+            .send(String::from_utf8_lossy(&buffer[..read]).to_string())
+            .await
+            .unwrap();
+
+        let response = format!(
+            "HTTP/1.1 {}\r\ncontent-type: application/yang-data+json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+    });
+
+    (format!("http://{}", address), receiver)
+}
+
+/// Serves canned HTTP responses in order, one per connection, forwarding the request lines
+async fn serve_sequence(
+    responses: Vec<(&'static str, &'static str)>,
+) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = mpsc::channel(responses.len());
+
+    tokio::spawn(async move {
+        for (status, body) in responses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 8192];
+            let read = socket.read(&mut buffer).await.unwrap();
+            let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+            let line = request.lines().next().unwrap_or_default().to_string();
+            sender.send(line).await.unwrap();
+
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: application/yang-data+json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (format!("http://{}", address), receiver)
+}
+
+/// A client for the mock device at `base_url`
+fn client(base_url: &str) -> Arc<RestconfClient> {
+    let device = Device::from_value(&json!({
+        "host": "127.0.0.1",
+        "auth": {"username": "tapi", "password": "secret"}
+    }))
+    .unwrap();
+    Arc::new(RestconfClient::with_base_url(device, base_url).unwrap())
+}
+
+/// Waits until a job is finished
+async fn finished(queue: &JobQueue, id: &uuid::Uuid) -> backend::jobs::Job {
+    for _ in 0..200 {
+        let job = queue.get(id).unwrap();
+        if matches!(job.status, JobStatus::Done | JobStatus::Failed) {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("Job {} did not finish", id);
+}
+
+/// Confirmation settings fast enough for tests
+const FAST: Confirmation = Confirmation {
+    interval: Duration::from_millis(10),
+    attempts: 3,
+};
+
+/// A valid provisioning request body
+fn request_body() -> Value {
+    json!({
+        "uuid": Uuid::from_u128(1),
+        "name": "MAD-BCN 100G",
+        "layer_protocol_name": "DSR",
+        "end_points": [
+            {"service_interface_point": Uuid::from_u128(10)},
+            {"service_interface_point": Uuid::from_u128(11)}
+        ],
+        "requested_capacity": {"value": 100, "unit": "GBPS"}
+    })
+}
+
+/// # Test: `test_connectivity_service_validation`
+///
+/// This test verifies that every problem of a provisioning request is reported.
+#[test]
+fn test_connectivity_service_validation() {
+    assert!(ConnectivityServiceRequest::validate(&request_body()).is_empty());
+
+    let invalid = json!({
+        "layer_protocol_name": "SONET",
+        "end_points": [
+            {"service_interface_point": Uuid::from_u128(10)},
+            {"service_interface_point": Uuid::from_u128(10)}
+        ],
+        "requested_capacity": {"value": 0, "unit": "GBPS"}
+    });
+    let fields: Vec<String> = ConnectivityServiceRequest::validate(&invalid)
+        .into_iter()
+        .map(|error| error.field)
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            "layer_protocol_name",
+            "end_points.1.service_interface_point",
+            "requested_capacity.value"
+        ]
+    );
+
+    match ConnectivityServiceRequest::from_value(&json!({"layer_protocol_name": "DSR"})) {
+        Err(Error::Invalid(msg)) => assert_eq!(
+            msg,
+            "Invalid connectivity service request: end_points: End points not found"
+        ),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(request) => panic!("Expected an error, but got {:?}", request),
+    }
+}
+
+/// # Test: `test_create_connectivity_service`
+///
+/// This test checks that the service is forwarded to the TAPI connectivity context of
+/// the device, and that its uuid is returned when the device answers `201` without body.
+#[tokio::test]
+async fn test_create_connectivity_service() {
+    let (base_url, mut requests) = serve_once("201 Created", "").await;
+    let device = Device::from_value(&json!({
+        "host": "127.0.0.1",
+        "auth": {"username": "tapi", "password": "secret"}
+    }))
+    .unwrap();
+    let client = RestconfClient::with_base_url(device, &base_url).unwrap();
+    let request = ConnectivityServiceRequest::from_value(&request_body()).unwrap();
+
+    let created = create_connectivity_service(&client, &request)
+        .await
+        .unwrap();
+    assert_eq!(created.uuid, Uuid::from_u128(1));
+
+    let raw = requests.recv().await.unwrap();
+    assert!(raw.starts_with(
+        "POST /restconf/data/tapi-common:context/tapi-connectivity:connectivity-context "
+    ));
+    assert!(raw
+        .to_lowercase()
+        .contains("content-type: application/yang-data+json"));
+    let body: Value = serde_json::from_str(raw.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+    let service = &body["tapi-connectivity:connectivity-service"][0];
+    assert_eq!(
+        service["end-point"][1]["service-interface-point"]["service-interface-point-uuid"],
+        json!(Uuid::from_u128(11))
+    );
+    assert_eq!(
+        service["requested-capacity"],
+        json!({"total-size": {"value": 100, "unit": "GBPS"}})
+    );
+}
+
+/// # Test: `test_delete_connectivity_service`
+///
+/// This test checks that a deletion job sends the `DELETE` and is only done once the
+/// device stops reporting the service.
+#[tokio::test]
+async fn test_delete_connectivity_service() {
+    let (base_url, mut requests) = serve_sequence(vec![
+        ("204 No Content", ""),
+        (
+            "200 OK",
+            r#"{"tapi-connectivity:connectivity-service":[{"uuid":"x"}]}"#,
+        ),
+        ("404 Not Found", ""),
+    ])
+    .await;
+    let queue = JobQueue::new(1);
+    let uuid = Uuid::from_u128(1);
+
+    let id = delete_connectivity_service(&queue, DEFAULT_TENANT, client(&base_url), uuid, FAST);
+    let job = finished(&queue, &id).await;
+    assert_eq!(job.status, JobStatus::Done);
+    assert_eq!(job.result, Some(json!({"uuid": uuid, "deleted": true})));
+
+    let path = format!(
+        "/restconf/data/tapi-common:context/tapi-connectivity:connectivity-context/connectivity-service={}",
+        uuid
+    );
+    assert_eq!(
+        requests.recv().await.unwrap(),
+        format!("DELETE {} HTTP/1.1", path)
+    );
+    assert_eq!(
+        requests.recv().await.unwrap(),
+        format!("GET {} HTTP/1.1", path)
+    );
+    assert_eq!(
+        requests.recv().await.unwrap(),
+        format!("GET {} HTTP/1.1", path)
+    );
+}
+
+/// # Test: `test_change_connectivity_service_state`
+///
+/// This test checks that a state change job fails with a timeout when the device never
+/// reports the requested administrative state, and succeeds when it does.
+#[tokio::test]
+async fn test_change_connectivity_service_state() {
+    let unlocked =
+        r#"{"tapi-connectivity:connectivity-service":[{"administrative-state":"UNLOCKED"}]}"#;
+    let locked =
+        r#"{"tapi-connectivity:connectivity-service":[{"administrative-state":"LOCKED"}]}"#;
+    let change =
+        ServiceStateChange::from_value(&json!({"administrative_state": "LOCKED"})).unwrap();
+    let queue = JobQueue::new(1);
+    let uuid = Uuid::from_u128(1);
+
+    let (base_url, mut requests) = serve_sequence(vec![
+        ("204 No Content", ""),
+        ("200 OK", unlocked),
+        ("200 OK", locked),
+    ])
+    .await;
+    let id = change_connectivity_service_state(
+        &queue,
+        DEFAULT_TENANT,
+        client(&base_url),
+        uuid,
+        change,
+        FAST,
+    );
+    let job = finished(&queue, &id).await;
+    assert_eq!(job.status, JobStatus::Done);
+    assert_eq!(
+        job.result,
+        Some(json!({"uuid": uuid, "administrative_state": "LOCKED"}))
+    );
+    assert!(requests.recv().await.unwrap().starts_with("PATCH "));
+
+    let (base_url, _requests) = serve_sequence(vec![
+        ("204 No Content", ""),
+        ("200 OK", unlocked),
+        ("200 OK", unlocked),
+        ("200 OK", unlocked),
+    ])
+    .await;
+    let id = change_connectivity_service_state(
+        &queue,
+        DEFAULT_TENANT,
+        client(&base_url),
+        uuid,
+        change,
+        FAST,
+    );
+    let job = finished(&queue, &id).await;
+    assert_eq!(job.status, JobStatus::Failed);
+    assert_eq!(
+        job.error.unwrap(),
+        format!(
+            "Device timeout confirming administrative state of connectivity service {}",
+            uuid
+        )
+    );
+
+    match ServiceStateChange::from_value(&json!({"administrative_state": "SHUTDOWN"})) {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid administrative state"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(change) => panic!("Expected an error, but got {:?}", change),
+    }
+}
+
+/// # Test: `test_connectivity_service_dry_run`
+///
+/// This test verifies that a request is checked against the cached topology: unknown
+/// SIPs, end points on another layer and capacities above the end point rate.
+#[test]
+fn test_connectivity_service_dry_run() {
+    let sip = |n: u128| Uuid::from_u128(n).to_string();
+    let topology = Topology::from_value(
+        &json!({
+            "uuid": Uuid::from_u128(100),
+            "node": [{
+                "uuid": Uuid::from_u128(200),
+                "owned-node-edge-point": [
+                    {
+                        "uuid": Uuid::from_u128(300),
+                        "layer-protocol-qualifier": "tapi-dsr:DIGITAL_SIGNAL_TYPE_10_GigE_LAN",
+                        "mapped-service-interface-point": [{"service-interface-point-uuid": sip(10)}]
+                    },
+                    {
+                        "uuid": Uuid::from_u128(301),
+                        "layer-protocol-qualifier": "tapi-dsr:DIGITAL_SIGNAL_TYPE_100_GigE",
+                        "mapped-service-interface-point": [{"service-interface-point-uuid": sip(11)}]
+                    },
+                    {
+                        "uuid": Uuid::from_u128(302),
+                        "layer-protocol-qualifier": "tapi-odu:ODU_TYPE_ODU4",
+                        "mapped-service-interface-point": [{"service-interface-point-uuid": sip(12)}]
+                    }
+                ]
+            }]
+        }),
+        "127.0.0.1",
+    )
+    .unwrap();
+
+    let mut body = request_body();
+    body["requested_capacity"]["value"] = json!(10);
+    let report = dry_run(&body, &topology);
+    assert!(report.valid);
+    assert!(report.violations.is_empty());
+
+    let report = dry_run(&request_body(), &topology);
+    assert!(!report.valid);
+    assert_eq!(report.violations.len(), 1);
+    assert_eq!(report.violations[0].field, "requested_capacity.value");
+    assert_eq!(
+        report.violations[0].message,
+        "Capacity exceeds the 10 Gbps of end point 0"
+    );
+
+    body["end_points"] = json!([
+        {"service_interface_point": sip(12)},
+        {"service_interface_point": sip(13)}
+    ]);
+    let report = dry_run(&body, &topology);
+    let messages: Vec<(String, String)> = report
+        .violations
+        .into_iter()
+        .map(|violation| (violation.field, violation.message))
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            (
+                "end_points.0.service_interface_point".to_string(),
+                "Service interface point is on a ODU_TYPE_ODU4 end point, not DSR".to_string()
+            ),
+            (
+                "end_points.1.service_interface_point".to_string(),
+                "Service interface point not found in the topology".to_string()
+            )
+        ]
+    );
+
+    let report = dry_run(&json!({"layer_protocol_name": "DSR"}), &topology);
+    assert_eq!(report.violations[0].field, "end_points");
+}
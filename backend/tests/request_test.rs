@@ -0,0 +1,36 @@
+use backend::api::codes::{ErrorBody, ErrorCode};
+use backend::api::request::{RequestId, RequestSpan}; // Import the request correlation
+use serde_json::{json, to_value};
+
+/// # Test: `test_request_id_propagation`
+///
+/// This test verifies that a valid `x-request-id` is propagated, that missing or unsafe
+/// ones are replaced, and that the id is included in error bodies.
+#[test]
+fn test_request_id_propagation() {
+    let id = RequestId::from_header(Some("lb-7f3a.42"));
+    assert_eq!(id.as_str(), "lb-7f3a.42");
+
+    let generated = RequestId::from_header(None);
+    assert_eq!(generated.as_str().len(), 36);
+    assert_ne!(generated, RequestId::from_header(None));
+
+    let forged = RequestId::from_header(Some("abc\nlevel=ERROR"));
+    assert_ne!(forged.as_str(), "abc\nlevel=ERROR");
+    let oversized = "a".repeat(RequestId::MAX_LENGTH + 1);
+    assert_ne!(RequestId::from_header(Some(&oversized)).as_str(), oversized);
+
+    let span = RequestSpan::start("GET", "/devices/10.0.0.1", &id);
+    span.span().in_scope(|| tracing::debug!("Handling request"));
+    span.finish(404);
+
+    let body = ErrorBody::new(ErrorCode::DeviceNotFound, "Not found device 10.0.0.1", &id);
+    assert_eq!(
+        to_value(&body).unwrap(),
+        json!({
+            "code": "DEVICE_NOT_FOUND",
+            "message": "Not found device 10.0.0.1",
+            "request_id": "lb-7f3a.42"
+        })
+    );
+}
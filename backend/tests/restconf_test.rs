@@ -0,0 +1,418 @@
+use backend::models::device::Device; // Import the device model
+use backend::models::topology::TOPOLOGY_CONTEXT_PATH;
+use backend::southbound::restconf::{Fetched, RestconfClient};
+use backend::southbound::{FetchOptions, SouthboundProtocol}; // Import the RESTCONF driver
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+/// Serves a single canned HTTP response and forwards the raw request it received
+async fn serve_once(status: &'static str, body: &'static str) -> (String, mpsc::Receiver<String>) {
+    serve_once_as(status, "application/yang-data+json", body).await
+}
+
+/// Serves a single canned HTTP response of the given media type
+async fn serve_once_as(
+    status: &'static str,
+    content_type: &'static str,
+    body: &'static str,
+) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = mpsc::channel(1);
+
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buffer = vec![0; 4096];
+        let read = socket.read(&mut buffer).await.unwrap();
+        sender
+            .send(String::from_utf8_lossy(&buffer[..read]).to_string())
+            .await
+            .unwrap();
+
+        let response = format!(
+            "HTTP/1.1 {}\r\ncontent-type: {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+    });
+
+    (format!("http://{}", address), receiver)
+}
+
+/// Device with Basic Authentication used by the tests
+fn basic_device() -> Device {
+    Device::from_value(&json!({
+        "host": "127.0.0.1",
+        "auth": {"username": "tapi", "password": "secret"}
+    }))
+    .unwrap()
+}
+
+/// # Test: `test_restconf_get`
+///
+/// This test verifies that the driver asks for `application/yang-data+json`, forwards
+/// the depth and fields options, authenticates and returns the JSON body.
+#[tokio::test]
+async fn test_restconf_get() {
+    let (base_url, mut requests) = serve_once("200 OK", r#"{"tapi-topology:topology": []}"#).await;
+    let client = RestconfClient::with_base_url(basic_device(), &base_url).unwrap();
+
+    let options = FetchOptions {
+        depth: Some(2),
+        fields: Some("uuid;name".to_string()),
+    };
+    let value: Value = client
+        .get("/restconf/data/tapi-common:context", &options)
+        .await
+        .unwrap();
+    assert_eq!(value, json!({"tapi-topology:topology": []}));
+
+    let request = requests.recv().await.unwrap().to_lowercase();
+    assert!(
+        request.starts_with("get /restconf/data/tapi-common:context?depth=2&fields=uuid%3bname")
+    );
+    assert!(request.contains("accept: application/yang-data+json"));
+    assert!(request.contains("authorization: basic"));
+}
+
+/// # Test: `test_restconf_error_envelope`
+///
+/// This test checks that a RESTCONF error envelope is reported with its tag and message.
+#[tokio::test]
+async fn test_restconf_error_envelope() {
+    let (base_url, _requests) = serve_once(
+        "404 Not Found",
+        r#"{"ietf-restconf:errors": {"error": [{"error-type": "application", "error-tag": "invalid-value", "error-message": "Uri keypath not found"}]}}"#,
+    )
+    .await;
+    let client = RestconfClient::with_base_url(basic_device(), &base_url).unwrap();
+
+    match client
+        .get(
+            "/restconf/data/tapi-common:context",
+            &FetchOptions::default(),
+        )
+        .await
+    {
+        Err(Error::BadResponse(msg)) => assert_eq!(
+            msg,
+            "RESTCONF error: invalid-value: Uri keypath not found".to_string()
+        ),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but got Ok"),
+    }
+}
+
+/// # Test: `test_restconf_api_key`
+///
+/// This test checks that API key devices send their key in the configured header.
+#[tokio::test]
+async fn test_restconf_api_key() {
+    let (base_url, mut requests) = serve_once("200 OK", "{}").await;
+    let device = Device::from_value(&json!({
+        "host": "127.0.0.1",
+        "auth": {"header_name": "X-Auth-Key", "key": "3f9a1c0b7d2e"}
+    }))
+    .unwrap();
+    let client = RestconfClient::with_base_url(device, &base_url).unwrap();
+
+    client
+        .get(
+            "/restconf/data/tapi-common:context",
+            &FetchOptions::default(),
+        )
+        .await
+        .unwrap();
+
+    let request = requests.recv().await.unwrap().to_lowercase();
+    assert!(request.contains("x-auth-key: 3f9a1c0b7d2e"));
+    assert!(!request.contains("authorization:"));
+}
+
+/// Serves canned HTTP responses, one connection each, forwarding the raw requests
+async fn serve_sequence(
+    responses: Vec<(&'static str, &'static str, &'static str)>,
+) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = mpsc::channel(responses.len());
+
+    tokio::spawn(async move {
+        for (status, headers, body) in responses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = vec![0; 4096];
+            let read = socket.read(&mut buffer).await.unwrap();
+            sender
+                .send(String::from_utf8_lossy(&buffer[..read]).to_string())
+                .await
+                .unwrap();
+
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: application/json\r\n{}content-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                headers,
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (format!("http://{}", address), receiver)
+}
+
+/// # Test: `test_restconf_custom_session`
+///
+/// This test verifies that custom authentication logs in with the configured body,
+/// reuses the session cookie, and logs in again when the device answers 401.
+#[tokio::test]
+async fn test_restconf_custom_session() {
+    let (base_url, mut requests) = serve_sequence(vec![
+        (
+            "200 OK",
+            "set-cookie: JSESSIONID=first; Path=/; HttpOnly\r\n",
+            "{}",
+        ),
+        ("401 Unauthorized", "", "{}"),
+        ("200 OK", "set-cookie: JSESSIONID=second; Path=/\r\n", "{}"),
+        ("200 OK", "", r#"{"tapi-common:context": {}}"#),
+    ])
+    .await;
+    let device = Device::from_value(&json!({
+        "host": "127.0.0.1",
+        "auth": {"auth_body": {"username": "tapi", "password": "secret"}, "auth_url": "/tron/api/v1/tokens"}
+    }))
+    .unwrap();
+    let client = RestconfClient::with_base_url(device, &base_url).unwrap();
+
+    let value = client
+        .get(
+            "/restconf/data/tapi-common:context",
+            &FetchOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(value, json!({"tapi-common:context": {}}));
+
+    let login = requests.recv().await.unwrap().to_lowercase();
+    assert!(login.starts_with("post /tron/api/v1/tokens"));
+    let expired = requests.recv().await.unwrap().to_lowercase();
+    assert!(expired.contains("cookie: jsessionid=first"));
+    let relogin = requests.recv().await.unwrap().to_lowercase();
+    assert!(relogin.starts_with("post /tron/api/v1/tokens"));
+    let retried = requests.recv().await.unwrap().to_lowercase();
+    assert!(retried.contains("cookie: jsessionid=second"));
+}
+
+/// # Test: `test_restconf_custom_token_pointer`
+///
+/// This test checks that a token read at the configured JSON pointer is sent in the
+/// configured header.
+#[tokio::test]
+async fn test_restconf_custom_token_pointer() {
+    let (base_url, mut requests) = serve_sequence(vec![
+        ("200 OK", "", r#"{"data": {"token": "3f9a1c0b7d2e"}}"#),
+        ("200 OK", "", "{}"),
+    ])
+    .await;
+    let device = Device::from_value(&json!({
+        "host": "127.0.0.1",
+        "auth": {
+            "auth_body": {"username": "tapi", "password": "secret"},
+            "auth_url": "/api/login",
+            "token_pointer": "/data/token",
+            "token_header": "X-Auth-Token"
+        }
+    }))
+    .unwrap();
+    let client = RestconfClient::with_base_url(device, &base_url).unwrap();
+
+    client
+        .get(
+            "/restconf/data/tapi-common:context",
+            &FetchOptions::default(),
+        )
+        .await
+        .unwrap();
+
+    requests.recv().await.unwrap();
+    let request = requests.recv().await.unwrap().to_lowercase();
+    assert!(request.contains("x-auth-token: 3f9a1c0b7d2e"));
+}
+
+/// # Test: `test_restconf_get_if_changed`
+///
+/// This test verifies that conditional fetches send back the `ETag` and `Last-Modified`
+/// of the previous answer and report `304` as unchanged, and that devices without them
+/// are compared on the content hash.
+#[tokio::test]
+async fn test_restconf_get_if_changed() {
+    let (base_url, mut requests) = serve_sequence(vec![
+        (
+            "200 OK",
+            "etag: \"v1\"\r\nlast-modified: Sat, 17 Oct 2026 08:00:00 GMT\r\n",
+            r#"{"topology": []}"#,
+        ),
+        ("304 Not Modified", "", ""),
+    ])
+    .await;
+    let client = RestconfClient::with_base_url(basic_device(), &base_url).unwrap();
+    let options = FetchOptions::default();
+
+    assert_eq!(
+        client.get_if_changed("/context", &options).await.unwrap(),
+        Fetched::Modified(json!({"topology": []}))
+    );
+    assert!(!requests
+        .recv()
+        .await
+        .unwrap()
+        .to_lowercase()
+        .contains("if-none-match"));
+    assert_eq!(
+        client.get_if_changed("/context", &options).await.unwrap(),
+        Fetched::Unchanged
+    );
+    let request = requests.recv().await.unwrap().to_lowercase();
+    assert!(request.contains("if-none-match: \"v1\""));
+    assert!(request.contains("if-modified-since: sat, 17 oct 2026 08:00:00 gmt"));
+
+    // Without validators, the same content is recognized by its hash
+    let (base_url, _requests) = serve_sequence(vec![
+        ("200 OK", "", r#"{"topology": [], "uuid": "a"}"#),
+        ("200 OK", "", r#"{"uuid": "a", "topology": []}"#),
+        ("200 OK", "", r#"{"uuid": "b", "topology": []}"#),
+    ])
+    .await;
+    let client = RestconfClient::with_base_url(basic_device(), &base_url).unwrap();
+    assert!(matches!(
+        client.get_if_changed("/context", &options).await.unwrap(),
+        Fetched::Modified(_)
+    ));
+    assert_eq!(
+        client.get_if_changed("/context", &options).await.unwrap(),
+        Fetched::Unchanged
+    );
+    assert_eq!(
+        client.get_if_changed("/context", &options).await.unwrap(),
+        Fetched::Modified(json!({"uuid": "b", "topology": []}))
+    );
+}
+
+/// # Test: `test_restconf_proxy`
+///
+/// This test checks that a device with a proxy is reached through it, the request
+/// carrying the absolute URL of the device and the proxy credentials.
+#[tokio::test]
+async fn test_restconf_proxy() {
+    let (proxy_url, mut requests) = serve_once("200 OK", r#"{"uuid": "context"}"#).await;
+    let device = Device::from_value(&json!({
+        "host": "device.example.com",
+        "auth": {"username": "tapi", "password": "secret"},
+        "proxy_url": proxy_url.replace("http://", "http://squid:pass@")
+    }))
+    .unwrap();
+    let client = RestconfClient::with_base_url(device, "http://device.example.com:8080").unwrap();
+
+    let value = client
+        .get(
+            "/restconf/data/tapi-common:context",
+            &FetchOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(value["uuid"], "context");
+
+    let request = requests.recv().await.unwrap();
+    assert!(
+        request.starts_with(
+            "GET http://device.example.com:8080/restconf/data/tapi-common:context HTTP/1.1"
+        ),
+        "{}",
+        request
+    );
+    // Basic credentials of `squid:pass`
+    assert!(
+        request.contains("proxy-authorization: Basic c3F1aWQ6cGFzcw=="),
+        "{}",
+        request
+    );
+}
+
+/// # Test: `test_restconf_xml`
+///
+/// This test verifies that the driver also accepts XML, that an XML answer is returned
+/// in the JSON encoding, and that an XML error envelope is reported like a JSON one.
+#[tokio::test]
+async fn test_restconf_xml() {
+    let (base_url, mut requests) = serve_once_as(
+        "200 OK",
+        "application/yang-data+xml",
+        r#"<topology-context xmlns="urn:onf:otcc:yang:tapi-topology"><topology><uuid>4e537278-79f8-39ad-804b-f0b553cb2ffb</uuid></topology></topology-context>"#,
+    )
+    .await;
+    let client = RestconfClient::with_base_url(basic_device(), &base_url).unwrap();
+    let value = client
+        .get(TOPOLOGY_CONTEXT_PATH, &FetchOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        value,
+        json!({"tapi-topology:topology-context": {"topology": [{"uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb"}]}})
+    );
+    let request = requests.recv().await.unwrap().to_lowercase();
+    assert!(request.contains("application/yang-data+xml;q=0.5"));
+
+    let (base_url, _requests) = serve_once_as(
+        "404 Not Found",
+        "application/yang-data+xml",
+        r#"<errors xmlns="urn:ietf:params:xml:ns:yang:ietf-restconf"><error><error-type>application</error-type><error-tag>invalid-value</error-tag><error-message>Uri keypath not found</error-message></error></errors>"#,
+    )
+    .await;
+    let client = RestconfClient::with_base_url(basic_device(), &base_url).unwrap();
+    match client
+        .get(TOPOLOGY_CONTEXT_PATH, &FetchOptions::default())
+        .await
+    {
+        Err(Error::BadResponse(msg)) => {
+            assert_eq!(msg, "RESTCONF error: invalid-value: Uri keypath not found")
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
+
+/// # Test: `test_restconf_timeout`
+///
+/// This test checks that a device accepting the connection but never answering fails
+/// the request with a timeout instead of blocking the collector.
+#[tokio::test]
+async fn test_restconf_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buffer = vec![0; 4096];
+        let _ = socket.read(&mut buffer).await;
+        // Hold the connection open without answering
+        tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+    });
+
+    let client = RestconfClient::with_base_url(basic_device(), &format!("http://{}", address))
+        .unwrap()
+        .with_timeout(std::time::Duration::from_millis(200));
+    match client
+        .get(TOPOLOGY_CONTEXT_PATH, &FetchOptions::default())
+        .await
+    {
+        Err(Error::Timeout(msg)) => assert!(msg.starts_with("Device timeout"), "{}", msg),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
@@ -0,0 +1,119 @@
+use backend::events::store::EventStore; // Import the stores under retention
+use backend::events::{ChangeEvent, ChangeKind, ObjectKind};
+use backend::retention::{prune, Age, PruneReport, RetentionPolicy};
+use backend::setup::config::Config;
+use backend::sync::{Snapshot, SnapshotStore};
+use backend::tenant::DEFAULT_TENANT;
+use backend::Error; // Import the custom error type from the backend module
+use chrono::{Duration, Local};
+use uuid::Uuid;
+
+/// Returns a change event of `host` dated `days_ago` days ago
+fn event(host: &str, days_ago: i64) -> ChangeEvent {
+    ChangeEvent {
+        host: host.to_string(),
+        topology_uuid: Uuid::nil(),
+        object: ObjectKind::Node,
+        uuid: Uuid::new_v4(),
+        change: ChangeKind::Changed,
+        date: Local::now() - Duration::days(days_ago),
+    }
+}
+
+/// # Test: `test_retention_age`
+///
+/// This test verifies that ages are read with their unit, printed in the largest whole
+/// unit and that malformed ages are rejected.
+#[test]
+fn test_retention_age() {
+    let age: Age = "30d".parse().unwrap();
+    assert_eq!(age.duration(), Duration::days(30));
+    assert_eq!("14d".parse::<Age>().unwrap().to_string(), "2w");
+    assert_eq!("90m".parse::<Age>().unwrap().to_string(), "90m");
+
+    for invalid in ["", "d", "30", "-1d", "0h", "3y"] {
+        match invalid.parse::<Age>() {
+            Err(Error::Invalid(msg)) => assert_eq!(msg, format!("Invalid age {}", invalid)),
+            Err(err) => panic!("Unexpected error {:?}", err),
+            Ok(age) => panic!("Expected an error, but got {:?}", age),
+        }
+    }
+
+    let config =
+        Config::parse("[retention]\nmax_age = \"30d\"\nmax_events_per_device = 100").unwrap();
+    assert_eq!(config.retention.max_age, Some(age));
+    assert_eq!(config.retention.max_events_per_device, Some(100));
+}
+
+/// # Test: `test_retention_prune`
+///
+/// This test checks that old snapshots and events, and events beyond the per-device
+/// limit, are deleted, and that event ids are not reused after pruning.
+#[test]
+fn test_retention_prune() {
+    let path = std::env::temp_dir().join(format!("retention_test_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut events = EventStore::open(&path).unwrap();
+    for days_ago in [40, 20, 10, 5] {
+        events
+            .append(DEFAULT_TENANT, event("10.0.0.1", days_ago))
+            .unwrap();
+    }
+    events.append(DEFAULT_TENANT, event("10.0.0.2", 1)).unwrap();
+
+    let mut snapshots = SnapshotStore::new();
+    for (sequence, (host, days_ago)) in [("10.0.0.1", 1), ("10.0.0.2", 60)].into_iter().enumerate()
+    {
+        let snapshot = Snapshot {
+            agent_id: Uuid::new_v4(),
+            sequence: sequence as u64,
+            host: host.to_string(),
+            collected_at: Local::now() - Duration::days(days_ago),
+            topologies: vec![],
+            events: vec![],
+        };
+        snapshots.merge(DEFAULT_TENANT, snapshot);
+    }
+
+    let policy = RetentionPolicy {
+        max_age: Some("30d".parse().unwrap()),
+        max_events_per_device: Some(2),
+    };
+    let report = prune(&mut events, &mut snapshots, &policy, Local::now()).unwrap();
+    assert_eq!(
+        report,
+        PruneReport {
+            snapshots: 1,
+            events: 2
+        }
+    );
+    assert!(snapshots.latest(DEFAULT_TENANT, "10.0.0.1").is_some());
+    assert!(snapshots.latest(DEFAULT_TENANT, "10.0.0.2").is_none());
+
+    // The pruned log is what a restart loads, and ids keep increasing
+    drop(events);
+    let mut events = EventStore::open(&path).unwrap();
+    assert_eq!(
+        events
+            .after(DEFAULT_TENANT, 0)
+            .iter()
+            .map(|e| e.id)
+            .collect::<Vec<_>>(),
+        vec![3, 4, 5]
+    );
+    let everything = RetentionPolicy {
+        max_age: Some("1s".parse().unwrap()),
+        max_events_per_device: None,
+    };
+    assert_eq!(events.prune(&everything, Local::now()).unwrap(), 3);
+    drop(events);
+    let mut events = EventStore::open(&path).unwrap();
+    assert!(events.is_empty());
+    assert_eq!(
+        events.append(DEFAULT_TENANT, event("10.0.0.1", 0)).unwrap(),
+        6
+    );
+
+    let _ = std::fs::remove_file(&path);
+}
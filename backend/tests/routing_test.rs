@@ -0,0 +1,222 @@
+use backend::events::{ChangeEvent, ChangeKind, ObjectKind}; // Import the change events
+use backend::models::topology::Topology;
+use backend::notifications::routing::{RoutingContext, RoutingRules, RoutingWatcher, Sink};
+use backend::notifications::Alert;
+use backend::Error;
+use chrono::Local;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+const RULES: &str = r#"
+default: [email]
+rules:
+  - name: optical-outages
+    match:
+      min_severity: critical
+      device_tags: [production]
+      layer_protocols: [PHOTONIC_MEDIA]
+    sinks: [email, slack]
+    stop: true
+  - name: devices-down
+    match:
+      event_types: [device_down]
+    sinks: [teams]
+  - name: production-to-slack
+    match:
+      device_tags: [production]
+    sinks: [slack]
+"#;
+
+/// Returns a topology change on `host`
+fn change(host: &str, change: ChangeKind) -> Alert {
+    Alert::TopologyChange {
+        event: ChangeEvent {
+            host: host.to_string(),
+            topology_uuid: Uuid::nil(),
+            object: ObjectKind::Link,
+            uuid: Uuid::nil(),
+            change,
+            date: Local::now(),
+        },
+    }
+}
+
+fn sinks(list: &[Sink]) -> BTreeSet<Sink> {
+    list.iter().copied().collect()
+}
+
+/// # Test: `test_routing_rules`
+///
+/// This test verifies that the sinks of every matching rule are combined, that a
+/// matching `stop` rule ends the evaluation, and that unmatched events go to the default
+/// sinks.
+#[test]
+fn test_routing_rules() {
+    let rules = RoutingRules::parse(RULES).unwrap();
+    let production = vec!["production".to_string()];
+    let photonic = RoutingContext {
+        device_tags: &production,
+        layer_protocol: Some("PHOTONIC_MEDIA"),
+    };
+    let ethernet = RoutingContext {
+        device_tags: &production,
+        layer_protocol: Some("ETH"),
+    };
+
+    assert_eq!(
+        rules.sinks(&change("10.0.0.1", ChangeKind::Removed), &photonic),
+        sinks(&[Sink::Email, Sink::Slack])
+    );
+    assert_eq!(
+        rules.sinks(&change("10.0.0.1", ChangeKind::Removed), &ethernet),
+        sinks(&[Sink::Slack])
+    );
+
+    let down = Alert::DeviceDown {
+        host: "10.0.0.1".to_string(),
+        error: "Device timeout".to_string(),
+        since: Local::now(),
+    };
+    assert_eq!(
+        rules.sinks(&down, &ethernet),
+        sinks(&[Sink::Teams, Sink::Slack])
+    );
+    assert_eq!(
+        rules.sinks(
+            &change("10.0.0.2", ChangeKind::Added),
+            &RoutingContext::default()
+        ),
+        sinks(&[Sink::Email])
+    );
+}
+
+/// # Test: `test_routing_rules_validation`
+///
+/// This test ensures that rules without sinks, sinks the app does not deliver to,
+/// unknown layer protocols and unknown fields are rejected.
+#[test]
+fn test_routing_rules_validation() {
+    match RoutingRules::parse("rules:\n  - name: empty\n    sinks: []\n") {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid routing rule empty: no sink"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    match RoutingRules::parse("rules:\n  - name: stream\n    sinks: [email, kafka]\n") {
+        Err(Error::Invalid(msg)) => {
+            assert_eq!(
+                msg,
+                "Invalid routing rule stream: no delivery for sink Kafka"
+            )
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    match RoutingRules::parse("default: [webhook]\n") {
+        Err(Error::Invalid(msg)) => assert_eq!(
+            msg,
+            "Invalid routing rules: no delivery for default sink Webhook"
+        ),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    match RoutingRules::parse(
+        "rules:\n  - name: otn\n    match:\n      layer_protocols: [OTN]\n    sinks: [email]\n",
+    ) {
+        Err(Error::Invalid(msg)) => {
+            assert_eq!(msg, "Invalid routing rule otn: unknown layer protocol OTN")
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+    match RoutingRules::parse("rules:\n  - name: typo\n    sink: [email]\n") {
+        Err(Error::Invalid(msg)) => assert!(msg.starts_with("Invalid routing rules:")),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
+
+/// # Test: `test_routing_rules_reload`
+///
+/// This test checks that the watcher applies a new version of the rules file without a
+/// restart, and keeps the previous rules when the new file is invalid.
+#[test]
+fn test_routing_rules_reload() {
+    let directory =
+        std::env::temp_dir().join(format!("routing_test_reload_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let path: PathBuf = directory.join("routing.yaml");
+    std::fs::write(&path, "default: [email]\n").unwrap();
+
+    let watcher = RoutingWatcher::start(&path).unwrap();
+    let alert = change("10.0.0.1", ChangeKind::Added);
+    let context = RoutingContext::default();
+    assert_eq!(watcher.sinks(&alert, &context), sinks(&[Sink::Email]));
+
+    std::fs::write(&path, "default: [teams]\n").unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while watcher.current().default != vec![Sink::Teams] && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(watcher.sinks(&alert, &context), sinks(&[Sink::Teams]));
+
+    // A broken file is reported and ignored
+    std::fs::write(&path, "default: [pager]\n").unwrap();
+    assert!(watcher.reload().is_err());
+    assert_eq!(watcher.current().default, vec![Sink::Teams]);
+
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+/// # Test: `test_object_layer_protocol`
+///
+/// This test verifies that the layer protocol the rules match is told by the node edge
+/// points of a node, or of the nodes a link joins, and is unknown without a qualifier.
+#[test]
+fn test_object_layer_protocol() {
+    let topology = serde_json::json!({
+        "uuid": Uuid::from_u128(1),
+        "node": [
+            {
+                "uuid": Uuid::from_u128(10),
+                "owned-node-edge-point": [{
+                    "uuid": Uuid::from_u128(11),
+                    "layer-protocol-qualifier": "tapi-photonic-media:PHOTONIC_LAYER_QUALIFIER_OMS"
+                }]
+            },
+            {
+                "uuid": Uuid::from_u128(20),
+                "owned-node-edge-point": [{"uuid": Uuid::from_u128(21)}]
+            }
+        ],
+        "link": [{
+            "uuid": Uuid::from_u128(100),
+            "node-edge-point": [
+                {
+                    "topology-uuid": Uuid::from_u128(1),
+                    "node-uuid": Uuid::from_u128(20),
+                    "node-edge-point-uuid": Uuid::from_u128(21)
+                },
+                {
+                    "topology-uuid": Uuid::from_u128(1),
+                    "node-uuid": Uuid::from_u128(10),
+                    "node-edge-point-uuid": Uuid::from_u128(11)
+                }
+            ]
+        }]
+    });
+    let topology = Topology::from_value(&topology, "10.0.0.1").unwrap();
+
+    assert_eq!(
+        topology.layer_protocol(&Uuid::from_u128(10)),
+        Some("PHOTONIC_MEDIA")
+    );
+    assert_eq!(
+        topology.layer_protocol(&Uuid::from_u128(100)),
+        Some("PHOTONIC_MEDIA")
+    );
+    assert_eq!(topology.layer_protocol(&Uuid::from_u128(20)), None);
+    assert_eq!(topology.layer_protocol(&Uuid::from_u128(999)), None);
+}
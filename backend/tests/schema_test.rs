@@ -0,0 +1,172 @@
+use backend::models::parse::ParseOptions;
+use backend::models::topology::Topology;
+use backend::schema::{adapter, validate_context, SchemaViolation, TapiVersion}; // Import the TAPI schema validation
+use backend::setup::config::Config;
+use backend::Error;
+use serde_json::{json, Value};
+
+/// A topology context holding `topology`, as answered by RESTCONF
+fn context(topology: Value) -> Value {
+    json!({"tapi-topology:topology-context": {"topology": [topology]}})
+}
+
+/// Returns the pointers of the violations
+fn pointers(violations: &[SchemaViolation]) -> Vec<&str> {
+    violations
+        .iter()
+        .map(|violation| violation.pointer.as_str())
+        .collect()
+}
+
+/// # Test: `test_schema_conforming_payload`
+///
+/// This test verifies that the bundled schemas are valid and accept a conforming
+/// topology carrying vendor augmentations.
+#[test]
+fn test_schema_conforming_payload() {
+    let payload = context(json!({
+        "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+        "node": [{
+            "uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476",
+            "name": [{"value-name": "NODE_NAME", "value": "MAD-1"}],
+            "lifecycle-state": "INSTALLED",
+            "owned-node-edge-point": [{"uuid": "65a39427-3055-3ba4-9e15-0ebed4974577", "layer-protocol-name": "ETH"}]
+        }],
+        "link": [{
+            "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+            "direction": "BIDIRECTIONAL",
+            "layer-protocol-name": ["ETH"],
+            "tapi-ciena-link-extensions:signal-content-type": "IP",
+            "node-edge-point": [
+                {"node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577"},
+                {"node-uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7", "node-edge-point-uuid": "63366151-aeb4-3dfd-af66-d471b353aa1c"}
+            ]
+        }]
+    }));
+
+    for version in [TapiVersion::V2_1_3, TapiVersion::V2_4] {
+        assert!(version.schema().is_ok());
+        assert_eq!(validate_context(&payload, version).unwrap(), vec![]);
+    }
+}
+
+/// # Test: `test_schema_violations`
+///
+/// This test checks that each nonconformant value is reported with the JSON pointer
+/// of its place in the payload, and that the releases differ on layer protocols.
+#[test]
+fn test_schema_violations() {
+    let payload = context(json!({
+        "uuid": "not-a-uuid",
+        "node": [{"lifecycle-state": "RETIRED"}],
+        "link": [{
+            "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+            "layer-protocol-name": ["tapi-common:LAYER_PROTOCOL_NAME_TYPE_ETH"],
+            "node-edge-point": [{"node-uuid": 7, "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577"}]
+        }]
+    }));
+
+    let violations = validate_context(&payload, TapiVersion::V2_1_3).unwrap();
+    let root = "/tapi-topology:topology-context/topology/0";
+    assert_eq!(
+        pointers(&violations),
+        vec![
+            format!("{}/uuid", root),
+            format!("{}/node/0", root),
+            format!("{}/node/0/lifecycle-state", root),
+            format!("{}/link/0/layer-protocol-name/0", root),
+            format!("{}/link/0/node-edge-point", root),
+            format!("{}/link/0/node-edge-point/0/node-uuid", root),
+        ]
+    );
+    assert_eq!(violations[0].message, "Invalid uuid \"not-a-uuid\"");
+    assert_eq!(violations[1].message, "Missing required property uuid");
+    assert!(violations[2]
+        .message
+        .starts_with("Invalid value \"RETIRED\", expected one of \"PLANNED\""));
+    assert_eq!(violations[4].message, "Expected at least 2 items, found 1");
+    assert_eq!(violations[5].message, "Expected string, found integer");
+
+    // Layer protocols are identities since TAPI 2.4
+    let violations = validate_context(&payload, TapiVersion::V2_4).unwrap();
+    assert_eq!(violations.len(), 5);
+
+    let violations = validate_context(&json!({"uuid": "x"}), TapiVersion::V2_4).unwrap();
+    assert_eq!(pointers(&violations), vec![""]);
+    assert_eq!(violations[0].message, "Missing topology list");
+}
+
+/// # Test: `test_schema_config`
+///
+/// This test ensures that payloads are not checked by default, and that the release is
+/// read from the `[validation]` section.
+#[test]
+fn test_schema_config() {
+    assert_eq!(Config::default().validation.schema, None);
+    let config = Config::parse("[validation]\nschema = \"2.4\"").unwrap();
+    assert_eq!(config.validation.schema, Some(TapiVersion::V2_4));
+    assert!(Config::parse("[validation]\nschema = \"3.0\"").is_err());
+
+    match "3.0".parse::<TapiVersion>() {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid TAPI version 3.0"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
+
+/// # Test: `test_tapi_adapter`
+///
+/// This test verifies that the TAPI release is detected from the layer protocols, and
+/// that a TAPI 2.4 payload is parsed into the same topology, hashes included, as its
+/// TAPI 2.1.3 counterpart.
+#[test]
+fn test_tapi_adapter() {
+    let topology = |layer: &str| {
+        json!({
+            "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+            "node": [{
+                "uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476",
+                "layer-protocol-name": [layer],
+                "owned-node-edge-point": [{"uuid": "65a39427-3055-3ba4-9e15-0ebed4974577", "layer-protocol-name": layer}]
+            }],
+            "link": [{
+                "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+                "layer-protocol-name": [layer],
+                "node-edge-point": [
+                    {"node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577"},
+                    {"node-uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7", "node-edge-point-uuid": "63366151-aeb4-3dfd-af66-d471b353aa1c"}
+                ]
+            }]
+        })
+    };
+    let v2_1_3 = topology("ETH");
+    let v2_4 = topology("tapi-common:LAYER_PROTOCOL_NAME_TYPE_ETH");
+    assert_eq!(adapter::detect(&v2_1_3), TapiVersion::V2_1_3);
+    assert_eq!(adapter::detect(&v2_4), TapiVersion::V2_4);
+    assert_eq!(
+        adapter::normalize(&v2_4, TapiVersion::V2_4).as_ref(),
+        &v2_1_3
+    );
+
+    let parse = |value: &Value, version: Option<TapiVersion>| {
+        let options = ParseOptions {
+            version,
+            ..Default::default()
+        };
+        Topology::parse(value, "10.0.0.1", &options).unwrap().0
+    };
+    let expected = parse(&v2_1_3, None);
+    let detected = parse(&v2_4, None);
+    assert_eq!(detected.links[0].hash, expected.links[0].hash);
+    assert_eq!(detected.nodes[0].hash, expected.nodes[0].hash);
+    let configured = parse(&v2_4, Some(TapiVersion::V2_4));
+    assert_eq!(configured.links[0].hash, expected.links[0].hash);
+
+    let config = Config::parse("[devices.\"10.0.0.1\"]\ntapi = \"2.4\"").unwrap();
+    assert_eq!(config.tapi_version("10.0.0.1"), Some(TapiVersion::V2_4));
+    assert_eq!(config.tapi_version("10.0.0.2"), None);
+    let changes = config.changes(&Config::default());
+    assert_eq!(changes.len(), 1);
+    assert_eq!(changes[0].setting, "devices.10.0.0.1.tapi");
+    assert_eq!(changes[0].to.as_deref(), Some("2.4"));
+}
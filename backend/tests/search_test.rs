@@ -0,0 +1,187 @@
+use backend::ingest::ObjectStore;
+use backend::models::device::Device;
+use backend::models::name::Name;
+use backend::models::service_interface_point::ServiceInterfacePoint;
+use backend::models::topology::Topology;
+use backend::search::{tokenize, HitKind, SearchIndex}; // Import the cross-device search index
+use backend::Error;
+use serde_json::json;
+use uuid::Uuid;
+
+/// Returns the topology of `host` with a node owning an edge point and a link
+fn topology(host: &str, node_name: &str, link_name: &str) -> Topology {
+    Topology::from_value(
+        &json!({
+            "uuid": Uuid::from_u128(1),
+            "node": [{
+                "uuid": Uuid::from_u128(10),
+                "name": [{"value-name": "NODE_NAME", "value": node_name}],
+                "owned-node-edge-point": [{"uuid": Uuid::from_u128(11)}]
+            }],
+            "link": [{
+                "uuid": Uuid::from_u128(20),
+                "name": [{"value-name": "LINK_NAME", "value": link_name}],
+                "node-edge-point": [{
+                    "node-uuid": Uuid::from_u128(10),
+                    "node-edge-point-uuid": Uuid::from_u128(11)
+                }]
+            }]
+        }),
+        host,
+    )
+    .unwrap()
+}
+
+fn device(host: &str, name: &str) -> Device {
+    Device::from_value(&json!({
+        "host": host,
+        "auth": {"username": "tapi", "password": "secret"},
+        "metadata": {"name": name}
+    }))
+    .unwrap()
+}
+
+/// Returns an index of two devices collected with the same UUIDs and other names
+fn index() -> SearchIndex {
+    let mut store = ObjectStore::new();
+    store
+        .ingest(
+            "10.0.0.1",
+            &[topology("10.0.0.1", "ROADM-PARIS", "PARIS-LYON")],
+        )
+        .unwrap();
+    store
+        .ingest(
+            "10.0.0.2",
+            &[topology("10.0.0.2", "ROADM-LYON", "LYON-NICE")],
+        )
+        .unwrap();
+    SearchIndex::build(
+        &[
+            device("10.0.0.1", "paris-ctrl"),
+            device("10.0.0.2", "lyon-ctrl"),
+        ],
+        &store,
+    )
+}
+
+/// # Test: `test_search_exact`
+///
+/// This test verifies that a full UUID returns the object on every device holding it,
+/// with its owning host, and that an IP returns the device itself.
+#[test]
+fn test_search_exact() {
+    let index = index();
+    assert_eq!(index.len(), 8);
+
+    let hits = index.search(&Uuid::from_u128(11).to_string(), 10).unwrap();
+    assert_eq!(hits.len(), 2);
+    assert!(hits.iter().all(
+        |hit| hit.kind == HitKind::NodeEdgePoint && hit.node_uuid == Some(Uuid::from_u128(10))
+    ));
+    assert_eq!(hits[0].host, "10.0.0.1");
+    assert_eq!(hits[1].host, "10.0.0.2");
+
+    let hits = index.search("10.0.0.2", 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].kind, HitKind::Device);
+    assert_eq!(hits[0].name.as_deref(), Some("lyon-ctrl"));
+
+    // Names match regardless of case
+    let hits = index.search("roadm-paris", 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].kind, HitKind::Node);
+    assert_eq!(hits[0].topology_uuid, Some(Uuid::from_u128(1)));
+}
+
+/// # Test: `test_search_partial`
+///
+/// This test checks that partial queries rank prefix matches before substring matches,
+/// honour the limit, and that an empty query is refused.
+#[test]
+fn test_search_partial() {
+    let index = index();
+
+    let hits = index.search("lyon", 10).unwrap();
+    let names: Vec<&str> = hits.iter().filter_map(|hit| hit.name.as_deref()).collect();
+    assert_eq!(
+        names,
+        ["lyon-ctrl", "LYON-NICE", "PARIS-LYON", "ROADM-LYON"]
+    );
+    assert_eq!(index.search("lyon", 2).unwrap().len(), 2);
+    assert!(index.search("marseille", 10).unwrap().is_empty());
+
+    match index.search("  ", 10) {
+        Err(Error::Invalid(msg)) => assert!(msg.starts_with("Invalid search query")),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(hits) => panic!("Expected an error, but got {:?}", hits),
+    }
+}
+
+/// # Test: `test_search_update`
+///
+/// This test ensures that updating a device replaces its hits, indexes its service
+/// interface points, and that removing it leaves the other devices searchable.
+#[test]
+fn test_search_update() {
+    let mut index = index();
+    let sip = ServiceInterfacePoint::from_value(
+        &json!({
+            "uuid": Uuid::from_u128(30),
+            "name": [{"value-name": "SIP_NAME", "value": "CLIENT-1"}]
+        }),
+        "10.0.0.1",
+    )
+    .unwrap();
+
+    let mut store = ObjectStore::new();
+    store
+        .ingest(
+            "10.0.0.1",
+            &[topology("10.0.0.1", "ROADM-ROUEN", "ROUEN-LYON")],
+        )
+        .unwrap();
+    index.update("10.0.0.1", None, &store, &[sip]);
+    assert!(index.search("roadm-paris", 10).unwrap().is_empty());
+    assert!(index.search("10.0.0.1", 10).unwrap().is_empty());
+    let hits = index.search("client-1", 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].kind, HitKind::ServiceInterfacePoint);
+    assert_eq!(hits[0].uuid, Some(Uuid::from_u128(30)));
+
+    index.remove("10.0.0.1");
+    assert!(index.search("rouen", 10).unwrap().is_empty());
+    assert_eq!(index.search("roadm-lyon", 10).unwrap().len(), 1);
+}
+
+/// # Test: `test_search_full_text`
+///
+/// This test verifies that every TAPI name and text extension of a link is indexed, so
+/// a circuit name finds the link in full, by its words or by a fragment.
+#[test]
+fn test_search_full_text() {
+    assert_eq!(tokenize("MAD-BCN-100G-01"), ["mad", "bcn", "100g", "01"]);
+
+    let mut topology = topology("10.0.0.1", "ROADM-MADRID", "LINK-7");
+    topology.links[0].names.push(Name {
+        value_name: Some("CIRCUIT_ID".to_string()),
+        value: "MAD-BCN-100G-01".to_string(),
+    });
+    topology.links[0]
+        .extensions
+        .insert("ticket".to_string(), json!("CHG-4711"));
+    let mut store = ObjectStore::new();
+    store.ingest("10.0.0.1", &[topology]).unwrap();
+    let index = SearchIndex::build(&[], &store);
+
+    for query in ["MAD-BCN-100G-01", "bcn 100g", "100G-0", "chg-4711"] {
+        let hits = index.search(query, 10).unwrap();
+        assert_eq!(hits.len(), 1, "{}", query);
+        assert_eq!(hits[0].kind, HitKind::Link);
+        assert_eq!(hits[0].uuid, Some(Uuid::from_u128(20)));
+        assert_eq!(hits[0].name.as_deref(), Some("LINK-7"));
+        assert_eq!(hits[0].labels, ["MAD-BCN-100G-01", "CHG-4711"]);
+    }
+    // Every word must match
+    assert!(index.search("bcn 400g", 10).unwrap().is_empty());
+}
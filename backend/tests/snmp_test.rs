@@ -0,0 +1,68 @@
+use backend::southbound::snmp::{probe, SnmpAuthProtocol, SnmpCredentials, SnmpInfo}; // Import the SNMP probe
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::json;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+/// # Test: `test_snmp_credentials_and_metadata`
+///
+/// This test verifies that v2c and v3 credentials are read from JSON and that the
+/// probed information is exposed as device metadata.
+#[test]
+fn test_snmp_credentials_and_metadata() {
+    let v2c: SnmpCredentials =
+        serde_json::from_value(json!({"version": "v2c", "community": "public"})).unwrap();
+    assert_eq!(
+        v2c,
+        SnmpCredentials::V2c {
+            community: "public".to_string()
+        }
+    );
+
+    let v3: SnmpCredentials = serde_json::from_value(
+        json!({"version": "v3", "username": "admin", "auth_password": "authpass123"}),
+    )
+    .unwrap();
+    match v3 {
+        SnmpCredentials::V3 {
+            auth_protocol,
+            privacy_password,
+            ..
+        } => {
+            assert_eq!(auth_protocol, SnmpAuthProtocol::Sha1);
+            assert_eq!(privacy_password, None);
+        }
+        other => panic!("Expected v3 credentials, but got {:?}", other),
+    }
+
+    let info = SnmpInfo {
+        sys_name: Some("router-01".to_string()),
+        sys_descr: None,
+        interface_count: Some(24),
+    };
+    let metadata = info.metadata();
+    assert_eq!(metadata.len(), 2);
+    assert_eq!(metadata["snmp.sys_name"], "router-01");
+    assert_eq!(metadata["snmp.interface_count"], "24");
+}
+
+/// # Test: `test_snmp_probe_timeout`
+///
+/// This test checks that an agent that never answers is reported as a timeout.
+#[tokio::test]
+async fn test_snmp_probe_timeout() {
+    // A bound socket that swallows every request
+    let agent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let port = agent.local_addr().unwrap().port();
+
+    let credentials = SnmpCredentials::V2c {
+        community: "public".to_string(),
+    };
+    match probe("127.0.0.1", port, &credentials, Duration::from_millis(200)).await {
+        Err(Error::Timeout(msg)) => {
+            assert_eq!(msg, "Device timeout: no SNMP answer from 127.0.0.1")
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(info) => panic!("Expected an error, but got {:?}", info),
+    }
+}
@@ -0,0 +1,93 @@
+use backend::notifications::spool::{DropPolicy, SpoolConfig, SpoolQueue}; // Import the spool queue
+use backend::Error; // Import the custom error type from the backend module
+use std::path::PathBuf;
+
+/// Returns a fresh spool file path for a test
+fn spool_path(name: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("spool_test_{}_{}.jsonl", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+/// # Test: `test_spool_spills_and_drains_in_order`
+///
+/// This test verifies that events beyond the memory capacity are spilled to disk, are
+/// delivered in order once the target recovers, and survive a restart.
+#[test]
+fn test_spool_spills_and_drains_in_order() {
+    let path = spool_path("order");
+    let config = SpoolConfig {
+        memory_capacity: 2,
+        disk_capacity: 10,
+        drop_policy: DropPolicy::DropNewest,
+    };
+
+    let mut queue = SpoolQueue::open(&path, config.clone()).unwrap();
+    for event in 1..=5 {
+        queue.push(event).unwrap();
+    }
+    let metrics = queue.metrics();
+    assert_eq!(
+        (metrics.in_memory, metrics.on_disk, metrics.spilled),
+        (2, 3, 3)
+    );
+
+    // The target is down: nothing is delivered and the head is kept
+    let delivered = queue
+        .drain(|_| Err(Error::from("Webhook target unreachable")))
+        .unwrap();
+    assert_eq!(delivered, 0);
+    drop(queue);
+
+    // After a restart only the spooled events remain
+    let mut queue = SpoolQueue::<i32>::open(&path, config).unwrap();
+    assert_eq!(queue.len(), 3);
+    let mut received = vec![];
+    queue
+        .drain(|event| {
+            received.push(*event);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(received, vec![3, 4, 5]);
+    assert!(queue.is_empty());
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// # Test: `test_spool_drop_policy`
+///
+/// This test checks that a full spool drops the oldest or the newest event depending on
+/// its policy, and counts the drops.
+#[test]
+fn test_spool_drop_policy() {
+    for (policy, expected) in [
+        (DropPolicy::DropOldest, vec![2, 3, 4, 5]),
+        (DropPolicy::DropNewest, vec![1, 2, 3, 4]),
+    ] {
+        let path = spool_path(&format!("{:?}", policy));
+        let config = SpoolConfig {
+            memory_capacity: 2,
+            disk_capacity: 2,
+            drop_policy: policy,
+        };
+        let mut queue = SpoolQueue::open(&path, config).unwrap();
+        for event in 1..=5 {
+            queue.push(event).unwrap();
+        }
+        let metrics = queue.metrics();
+        assert_eq!((metrics.on_disk, metrics.dropped), (2, 1));
+
+        let mut received = vec![];
+        queue
+            .drain(|event| {
+                received.push(*event);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(received, expected);
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,93 @@
+use backend::events::sse::{frame, parse_last_event_id, EventStream, HEARTBEAT}; // Import the SSE stream
+use backend::events::store::EventStore;
+use backend::events::{ChangeEvent, ChangeKind, ObjectKind};
+use backend::tenant::DEFAULT_TENANT;
+use backend::Error; // Import the custom error type from the backend module
+use chrono::Local;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Returns a change event on the node `uuid`
+fn event(uuid: Uuid) -> ChangeEvent {
+    ChangeEvent {
+        host: "127.0.0.1".to_string(),
+        topology_uuid: Uuid::nil(),
+        object: ObjectKind::Node,
+        uuid,
+        change: ChangeKind::Removed,
+        date: Local::now(),
+    }
+}
+
+/// # Test: `test_sse_frame_and_last_event_id`
+///
+/// This test verifies the wire format of an event and the parsing of `Last-Event-ID`.
+#[test]
+fn test_sse_frame_and_last_event_id() {
+    let mut store = EventStore::new();
+    store.append(DEFAULT_TENANT, event(Uuid::nil())).unwrap();
+
+    let message = frame(store.after(DEFAULT_TENANT, 0)[0]).unwrap();
+    assert!(message.starts_with("id: 1\nevent: change\ndata: {\"host\":\"127.0.0.1\""));
+    assert!(message.ends_with("}\n\n"));
+    assert_eq!(message.matches('\n').count(), 4);
+
+    assert_eq!(parse_last_event_id(None).unwrap(), None);
+    assert_eq!(parse_last_event_id(Some("42")).unwrap(), Some(42));
+    match parse_last_event_id(Some("abc")) {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid Last-Event-ID abc"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(id) => panic!("Expected an error, but got {:?}", id),
+    }
+}
+
+/// # Test: `test_sse_stream_resume_and_heartbeat`
+///
+/// This test checks that a reconnecting client gets the events it missed, then new
+/// events as they are recorded, and heartbeats while nothing happens.
+#[tokio::test]
+async fn test_sse_stream_resume_and_heartbeat() {
+    let store = Arc::new(Mutex::new(EventStore::new()));
+    let uuids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+    for uuid in &uuids[..2] {
+        store
+            .lock()
+            .unwrap()
+            .append(DEFAULT_TENANT, event(*uuid))
+            .unwrap();
+    }
+
+    // The client already received the first event
+    let mut stream = EventStream::new(
+        Arc::clone(&store),
+        DEFAULT_TENANT,
+        Some("1"),
+        Duration::from_millis(300),
+    )
+    .unwrap();
+    let chunk = stream.next_chunk().await.unwrap();
+    assert!(chunk.starts_with("id: 2\n"));
+    assert!(chunk.contains(&uuids[1].to_string()));
+
+    // Nothing new: a heartbeat keeps the connection alive
+    assert_eq!(stream.next_chunk().await.unwrap(), HEARTBEAT);
+
+    store
+        .lock()
+        .unwrap()
+        .append(DEFAULT_TENANT, event(uuids[2]))
+        .unwrap();
+    let chunk = stream.next_chunk().await.unwrap();
+    assert!(chunk.starts_with("id: 3\n"));
+
+    // A new client without `Last-Event-ID` only gets new events
+    let mut fresh = EventStream::new(
+        Arc::clone(&store),
+        DEFAULT_TENANT,
+        None,
+        Duration::from_millis(100),
+    )
+    .unwrap();
+    assert_eq!(fresh.next_chunk().await.unwrap(), HEARTBEAT);
+}
@@ -0,0 +1,62 @@
+use backend::stats::{CycleStats, StatsStore, HISTORY}; // Import the poll statistics
+use backend::Error; // Import the custom error type from the backend module
+use chrono::Local;
+
+/// Returns the statistics of a cycle that parsed `parsed` objects
+fn cycle(parsed: usize) -> CycleStats {
+    CycleStats {
+        collected_at: Local::now(),
+        parsed,
+        skipped: 1,
+        duration_ms: 1500,
+        payload_bytes: 2048,
+        http_latency_ms: 250,
+    }
+}
+
+/// # Test: `test_stats_history`
+///
+/// This test verifies that the cycles of a device are kept up to `HISTORY`, survive a
+/// restart, and that an unknown device is reported.
+#[test]
+fn test_stats_history() {
+    let path = std::env::temp_dir().join(format!("stats_test_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let mut store = StatsStore::open(&path).unwrap();
+    for parsed in 0..HISTORY + 5 {
+        store.record("10.0.0.1", cycle(parsed)).unwrap();
+    }
+
+    let store = StatsStore::open(&path).unwrap();
+    let stats = store.device("10.0.0.1").unwrap();
+    assert_eq!(stats.cycles.len(), HISTORY);
+    assert_eq!(stats.cycles[0].parsed, 5);
+    assert_eq!(stats.cycles[HISTORY - 1].parsed, HISTORY + 4);
+
+    match store.device("10.0.0.2") {
+        Err(Error::NotFound(msg)) => assert_eq!(msg, "Not found stats of 10.0.0.2"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(stats) => panic!("Expected an error, but got {:?}", stats),
+    }
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// # Test: `test_stats_prometheus`
+///
+/// This test checks that the latest cycle of every device is exported as gauges.
+#[test]
+fn test_stats_prometheus() {
+    let mut store = StatsStore::new();
+    store.record("10.0.0.1", cycle(10)).unwrap();
+    store.record("10.0.0.1", cycle(12)).unwrap();
+    store.record("10.0.0.2", cycle(3)).unwrap();
+
+    let metrics = store.prometheus();
+    assert!(metrics.contains("# TYPE poll_objects_parsed gauge\n"));
+    assert!(metrics.contains("poll_objects_parsed{host=\"10.0.0.1\"} 12\n"));
+    assert!(metrics.contains("poll_objects_parsed{host=\"10.0.0.2\"} 3\n"));
+    assert!(metrics.contains("poll_duration_seconds{host=\"10.0.0.1\"} 1.5\n"));
+    assert!(metrics.contains("poll_http_latency_seconds{host=\"10.0.0.2\"} 0.25\n"));
+}
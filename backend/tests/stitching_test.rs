@@ -0,0 +1,106 @@
+use backend::models::{link::Link, topology::Topology}; // Import the topology models
+use backend::setup::config::Config;
+use backend::stitching::{GlobalTopology, LinkEnd, StitchMatch};
+use serde_json::json;
+use uuid::Uuid;
+
+/// A topology of `host` with the given links
+fn topology(host: &str, links: Vec<Link>) -> Topology {
+    Topology {
+        host: host.to_string(),
+        uuid: Uuid::new_v4(),
+        nodes: vec![],
+        links,
+        site: None,
+    }
+}
+
+/// A link of `host`, optionally named
+fn link(host: &str, uuid: u128, name: Option<&str>) -> Link {
+    let builder = Link::builder().host(host).uuid(Uuid::from_u128(uuid));
+    match name {
+        Some(name) => builder.name(name),
+        None => builder,
+    }
+    .build()
+    .unwrap()
+}
+
+/// # Test: `test_link_stitching`
+///
+/// This test verifies that links reported by two devices are stitched by configured
+/// mapping, label and name, in that order, and that ambiguous names are not stitched.
+#[test]
+fn test_link_stitching() {
+    let config = Config::parse(
+        r#"
+        [stitching]
+        labels = ["circuit-id"]
+
+        [[stitching.mappings]]
+        a = { host = "10.0.0.1", link = "00000000-0000-0000-0000-000000000001" }
+        b = { host = "10.0.0.2", link = "00000000-0000-0000-0000-000000000009" }
+        "#,
+    )
+    .unwrap();
+
+    let mut labelled_a = link("10.0.0.1", 2, None);
+    labelled_a
+        .extensions
+        .insert("circuit-id".to_string(), json!("MAD-BCN-7"));
+    let mut labelled_b = link("10.0.0.2", 8, None);
+    labelled_b
+        .extensions
+        .insert("circuit-id".to_string(), json!("MAD-BCN-7"));
+
+    let first = topology(
+        "10.0.0.1",
+        vec![
+            link("10.0.0.1", 1, Some("MAD-BCN-1")),
+            labelled_a,
+            link("10.0.0.1", 3, Some("MAD-VLC-1")),
+            link("10.0.0.1", 4, Some("MAD-SEV-1")),
+            link("10.0.0.1", 5, Some("MAD-SEV-1")),
+        ],
+    );
+    let second = topology(
+        "10.0.0.2",
+        vec![
+            link("10.0.0.2", 9, Some("MAD-BCN-1")),
+            labelled_b,
+            link("10.0.0.2", 7, Some("MAD-VLC-1")),
+            link("10.0.0.2", 6, Some("MAD-SEV-1")),
+        ],
+    );
+
+    let global = GlobalTopology::stitch(&[&first, &second], &config.stitching);
+    assert_eq!(global.domains.len(), 2);
+
+    let pairs: Vec<(u128, u128, StitchMatch)> = global
+        .stitched
+        .iter()
+        .map(|stitched| {
+            (
+                stitched.a.link.as_u128(),
+                stitched.b.link.as_u128(),
+                stitched.matched_by.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        pairs,
+        vec![
+            (1, 9, StitchMatch::Mapping),
+            (2, 8, StitchMatch::Label("circuit-id".to_string())),
+            (3, 7, StitchMatch::Name),
+        ]
+    );
+    assert_eq!(global.stitched[0].name.as_deref(), Some("MAD-BCN-1"));
+    assert_eq!(
+        global.stitched[2].b,
+        LinkEnd {
+            host: "10.0.0.2".to_string(),
+            link: Uuid::from_u128(7)
+        }
+    );
+}
@@ -0,0 +1,90 @@
+use backend::supervisor::{RestartPolicy, Supervisor, TaskHealth, TaskState}; // Import the task supervisor
+use backend::Error; // Import the custom error type from the backend module
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Restart policy with short delays so the tests run quickly
+fn fast_policy(max_restarts: Option<u32>) -> RestartPolicy {
+    RestartPolicy {
+        initial_backoff: Duration::from_millis(5),
+        max_backoff: Duration::from_millis(20),
+        max_restarts,
+    }
+}
+
+/// Waits until the only task of `supervisor` is in `state`, returning its health
+///
+/// Restarts take longer than their backoff when a panic is symbolized
+/// (`RUST_BACKTRACE=1`), so the state is polled instead of expected after a fixed delay.
+async fn wait_for_state(supervisor: &Supervisor, state: TaskState) -> Vec<TaskHealth> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+    loop {
+        let health = supervisor.health();
+        if health.first().is_some_and(|task| task.state == state) {
+            return health;
+        }
+        assert!(
+            tokio::time::Instant::now() < deadline,
+            "Task never reached {:?}: {:?}",
+            state,
+            health
+        );
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+/// # Test: `test_restart_until_success`
+///
+/// This test verifies that a task failing (by error and by panic) is restarted and that
+/// the restart count and final state are reported.
+#[tokio::test]
+async fn test_restart_until_success() {
+    let mut supervisor = Supervisor::new();
+    let attempts = Arc::new(AtomicU32::new(0));
+
+    let task_attempts = Arc::clone(&attempts);
+    supervisor.spawn("poller", fast_policy(None), move || {
+        let attempts = Arc::clone(&task_attempts);
+        async move {
+            match attempts.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(Error::from("Device unreachable")),
+                1 => panic!("Unexpected payload"),
+                _ => Ok(()),
+            }
+        }
+    });
+
+    let health = wait_for_state(&supervisor, TaskState::Finished).await;
+    assert_eq!(health.len(), 1);
+    assert_eq!(health[0].restarts, 2);
+    assert!(health[0]
+        .last_error
+        .as_deref()
+        .unwrap()
+        .contains("panicked"));
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+    supervisor.shutdown();
+}
+
+/// # Test: `test_restart_budget`
+///
+/// This test checks that a task is marked as failed once its restart budget is exhausted.
+#[tokio::test]
+async fn test_restart_budget() {
+    let mut supervisor = Supervisor::new();
+
+    supervisor.spawn("notifier", fast_policy(Some(2)), || async {
+        Err(Error::from("Webhook rejected the event"))
+    });
+
+    let health = wait_for_state(&supervisor, TaskState::Failed).await;
+    assert_eq!(health[0].restarts, 2);
+    assert_eq!(
+        health[0].last_error.as_deref(),
+        Some("Webhook rejected the event")
+    );
+
+    supervisor.shutdown();
+}
@@ -0,0 +1,78 @@
+use backend::models::topology::Topology; // Import the topology model
+use backend::sync::{MergeOutcome, Snapshot, SnapshotStore}; // Import the agent snapshot merge
+use backend::tenant::DEFAULT_TENANT;
+use chrono::{Duration, Local};
+use serde_json::json;
+use uuid::Uuid;
+
+/// Builds a snapshot of a host collected `age` minutes ago
+fn snapshot(agent_id: Uuid, sequence: u64, age: i64) -> Snapshot {
+    Snapshot {
+        agent_id,
+        sequence,
+        host: "10.95.87.21".to_string(),
+        collected_at: Local::now() - Duration::minutes(age),
+        topologies: vec![json!({"uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb"})],
+        events: vec![],
+    }
+}
+
+/// # Test: `test_snapshot_merge`
+///
+/// This test verifies that late snapshots never overwrite newer ones and that retried
+/// snapshots are recognized as duplicates.
+#[test]
+fn test_snapshot_merge() {
+    let agent_id = Uuid::new_v4();
+    let mut store = SnapshotStore::new();
+
+    assert_eq!(
+        store.merge(DEFAULT_TENANT, snapshot(agent_id, 2, 5)),
+        MergeOutcome::Applied
+    );
+    // Delivered late, after the newer one
+    assert_eq!(
+        store.merge(DEFAULT_TENANT, snapshot(agent_id, 1, 10)),
+        MergeOutcome::Stale
+    );
+    // Retried after a lost acknowledgement
+    assert_eq!(
+        store.merge(DEFAULT_TENANT, snapshot(agent_id, 2, 5)),
+        MergeOutcome::Duplicate
+    );
+    assert_eq!(
+        store
+            .latest(DEFAULT_TENANT, "10.95.87.21")
+            .unwrap()
+            .sequence,
+        2
+    );
+
+    assert_eq!(
+        store.merge(DEFAULT_TENANT, snapshot(agent_id, 3, 0)),
+        MergeOutcome::Applied
+    );
+    assert_eq!(
+        store
+            .latest(DEFAULT_TENANT, "10.95.87.21")
+            .unwrap()
+            .sequence,
+        3
+    );
+}
+
+/// # Test: `test_topology_context_listing`
+///
+/// This test checks that the topologies of a topology context are extracted, with or
+/// without the module-qualified wrapper.
+#[test]
+fn test_topology_context_listing() {
+    let context = json!({
+        "tapi-topology:topology-context": {
+            "topology": [{"uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb"}, {"uuid": "9c8b7a6d-5e4f-4a3b-8c2d-1e0f9a8b7c6d"}]
+        }
+    });
+    assert_eq!(Topology::list_from_context(&context).unwrap().len(), 2);
+    assert_eq!(Topology::list_from_context(&json!({})).unwrap().len(), 0);
+    assert!(Topology::list_from_context(&json!({"topology": {}})).is_err());
+}
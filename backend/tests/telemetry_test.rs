@@ -0,0 +1,40 @@
+use backend::setup::config::Config;
+use backend::setup::log_setup::{logging_init_telemetry, TelemetryConfig}; // Import the trace export setup
+use backend::Error;
+
+/// # Test: `test_telemetry_config`
+///
+/// This test verifies that the span export is off by default, that the endpoint is
+/// read from the `[telemetry]` section, and that an invalid endpoint is rejected before
+/// the logging system is initialized.
+#[test]
+fn test_telemetry_config() {
+    assert_eq!(
+        Config::parse("").unwrap().telemetry,
+        TelemetryConfig::default()
+    );
+    assert_eq!(TelemetryConfig::default().otlp_endpoint, None);
+
+    let config = Config::parse(
+        r#"
+        [telemetry]
+        otlp_endpoint = "http://tempo:4318"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.telemetry.otlp_endpoint.as_deref(),
+        Some("http://tempo:4318")
+    );
+    assert_eq!(config.telemetry.service_name, "device-manager-backend");
+
+    let invalid = TelemetryConfig {
+        otlp_endpoint: Some("not an endpoint".to_string()),
+        ..Default::default()
+    };
+    match logging_init_telemetry("telemetry_test.log", "info", &invalid) {
+        Err(Error::Invalid(msg)) => assert!(msg.starts_with("Invalid OTLP endpoint")),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(_) => panic!("Expected an error, but got the logging guards"),
+    }
+}
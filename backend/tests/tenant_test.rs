@@ -0,0 +1,170 @@
+use backend::agents::AgentRegistry; // Import the stores scoped by tenant
+use backend::api::auth::{hash, ApiConfig, ApiToken};
+use backend::events::store::EventStore;
+use backend::events::{ChangeEvent, ChangeKind, ObjectKind};
+use backend::models::device::{Device, DeviceFilter};
+use backend::pagination::CursorSigner;
+use backend::sync::{MergeOutcome, Snapshot, SnapshotStore};
+use backend::tenant::DEFAULT_TENANT;
+use backend::Error; // Import the custom error type from the backend module
+use chrono::{Duration, Local};
+use serde_json::json;
+use uuid::Uuid;
+
+/// Returns a change event on the device at `host`
+fn event(host: &str) -> ChangeEvent {
+    ChangeEvent {
+        host: host.to_string(),
+        topology_uuid: Uuid::nil(),
+        object: ObjectKind::Link,
+        uuid: Uuid::new_v4(),
+        change: ChangeKind::Changed,
+        date: Local::now(),
+    }
+}
+
+/// # Test: `test_tenant_devices`
+///
+/// This test verifies that devices are registered under their tenant, the default one
+/// when none is given, and that a tenant only lists its own devices.
+#[test]
+fn test_tenant_devices() {
+    let auth = json!({"username": "admin", "password": "admin"});
+    let devices = vec![
+        Device::from_value(&json!({"host": "10.0.0.1", "auth": auth})).unwrap(),
+        Device::from_value(&json!({"host": "10.0.0.2", "auth": auth, "tenant_id": "acme"}))
+            .unwrap(),
+        Device::from_value(&json!({"host": "10.0.0.3", "auth": auth, "tenant_id": "globex"}))
+            .unwrap(),
+    ];
+    assert_eq!(devices[0].tenant_id, DEFAULT_TENANT);
+
+    let acme = DeviceFilter::default().apply_for_tenant("acme", &devices);
+    assert_eq!(acme.len(), 1);
+    assert_eq!(acme[0].host, "10.0.0.2");
+
+    match Device::from_value(&json!({"host": "10.0.0.4", "auth": auth, "tenant_id": ""})) {
+        Err(Error::Invalid(msg)) => assert_eq!(msg, "Invalid device tenant"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(device) => panic!("Expected an error, but got {:?}", device),
+    }
+}
+
+/// # Test: `test_tenant_agents_and_snapshots`
+///
+/// This test checks that an agent inherits the tenant of its enrollment token and that
+/// snapshots and events of the same host stay separate between tenants.
+#[test]
+fn test_tenant_agents_and_snapshots() {
+    let mut registry = AgentRegistry::new();
+    let token = registry
+        .issue_token("acme", "Madrid edge", Duration::minutes(10))
+        .unwrap();
+    let credentials = registry.enroll(&token, "edge-mad01").unwrap();
+    assert_eq!(registry.agents("acme").len(), 1);
+    assert!(registry.agents("globex").is_empty());
+
+    let (tenant_id, _) = registry
+        .ingest(
+            &credentials.agent_id,
+            &credentials.secret,
+            "10.0.0.1",
+            &json!({"uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb"}),
+        )
+        .unwrap();
+    assert_eq!(tenant_id, "acme");
+
+    let mut store = SnapshotStore::new();
+    let snapshot = Snapshot {
+        agent_id: credentials.agent_id,
+        sequence: 1,
+        host: "10.0.0.1".to_string(),
+        collected_at: Local::now(),
+        topologies: vec![],
+        events: vec![event("10.0.0.1")],
+    };
+    assert_eq!(store.merge(&tenant_id, snapshot), MergeOutcome::Applied);
+    assert!(store.latest("acme", "10.0.0.1").is_some());
+    assert!(store.latest("globex", "10.0.0.1").is_none());
+    assert_eq!(store.events("acme").len(), 1);
+    assert!(store.events("globex").is_empty());
+}
+
+/// # Test: `test_tenant_event_replay`
+///
+/// This test checks that a tenant only replays its own events and cannot use the
+/// cursor issued to another tenant.
+#[test]
+fn test_tenant_event_replay() {
+    let signer = CursorSigner::new("secret", Duration::minutes(5));
+    let mut store = EventStore::new();
+    store.append("acme", event("10.0.0.1")).unwrap();
+    store.append("globex", event("10.0.0.1")).unwrap();
+    store.append("acme", event("10.0.0.2")).unwrap();
+
+    let page = store.replay("acme", None, 1, &signer).unwrap();
+    assert_eq!(page.events[0].id, 1);
+    let cursor = page.next_cursor.unwrap();
+
+    let next = store.replay("acme", Some(&cursor), 10, &signer).unwrap();
+    assert_eq!(
+        next.events.iter().map(|e| e.id).collect::<Vec<_>>(),
+        vec![3]
+    );
+
+    match store.replay("globex", Some(&cursor), 10, &signer) {
+        Err(Error::InvalidCursor(msg)) => assert_eq!(msg, "Cursor does not match the query"),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(page) => panic!("Expected an error, but got {:?}", page),
+    }
+}
+
+/// # Test: `test_tenant_api_tokens`
+///
+/// This test verifies that an API request is scoped to the tenant of its bearer token,
+/// that its operations are audited under the name of the token, and that a missing or
+/// unknown token is rejected.
+#[test]
+fn test_tenant_api_tokens() {
+    let config = ApiConfig {
+        tokens: vec![
+            ApiToken {
+                tenant_id: DEFAULT_TENANT.to_string(),
+                name: Some("ci".to_string()),
+                sha256: hash("default-token"),
+            },
+            ApiToken {
+                tenant_id: "acme".to_string(),
+                name: None,
+                sha256: hash("acme-token").to_uppercase(),
+            },
+        ],
+        cursor_secret: None,
+    };
+    assert_eq!(
+        config.tenant(Some("Bearer default-token")).unwrap(),
+        DEFAULT_TENANT
+    );
+    assert_eq!(config.tenant(Some("Bearer acme-token")).unwrap(), "acme");
+    assert_eq!(
+        config.caller(Some("Bearer default-token")).unwrap().actor,
+        "ci"
+    );
+    assert_eq!(
+        config.caller(Some("Bearer acme-token")).unwrap().actor,
+        format!("acme:{}", &hash("acme-token")[..8])
+    );
+
+    for (authorization, expected) in [
+        (None, "Missing API token"),
+        (Some("acme-token"), "Missing API token"),
+        (Some("Bearer "), "Missing API token"),
+        (Some("Bearer other-token"), "Invalid API token"),
+    ] {
+        match config.tenant(authorization) {
+            Err(Error::Unauthorized(msg)) => assert_eq!(msg, expected),
+            Err(err) => panic!("Unexpected error {:?}", err),
+            Ok(tenant) => panic!("Expected an error, but got {:?}", tenant),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use backend::models::device::Device; // Import the device model
+use backend::models::topology::{Topology, TOPOLOGY_CONTEXT_PATH}; // Import the topology model
+use backend::southbound::restconf::RestconfClient;
+use backend::southbound::{FetchOptions, SouthboundProtocol}; // Import the RESTCONF driver
+use backend::testsupport::{Fault, MockAuth, MockDevice}; // Import the mock TAPI device
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+/// Returns a context with one topology holding one link
+fn context() -> Value {
+    json!({
+        "uuid": "0b1c2d3e-4f5a-4b6c-8d7e-9f0a1b2c3d4e",
+        "tapi-topology:topology-context": {
+            "topology": [{
+                "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+                "node": [],
+                "link": [{
+                    "uuid": "14219539-208b-35f5-b7cf-35a58e083490",
+                    "node-edge-point": [
+                        {"node-edge-point-uuid": "65a39427-3055-3ba4-9e15-0ebed4974577", "node-uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476"},
+                        {"node-edge-point-uuid": "63366151-aeb4-3dfd-af66-d471b353aa1c", "node-uuid": "7b0c973a-996a-3409-ad2f-d173354bfdb7"}
+                    ]
+                }]
+            }]
+        }
+    })
+}
+
+/// Returns a RESTCONF client of the mock device with the given authentication
+fn client(mock: &MockDevice, auth: Value) -> RestconfClient {
+    let device = Device::from_value(&json!({"host": "127.0.0.1", "auth": auth})).unwrap();
+    RestconfClient::with_base_url(device, &mock.base_url()).unwrap()
+}
+
+/// Fetches and parses the topologies of the mock device
+async fn topologies(client: &RestconfClient) -> Result<Vec<Topology>, Error> {
+    let context = client
+        .get(TOPOLOGY_CONTEXT_PATH, &FetchOptions::default())
+        .await?;
+    Topology::list_from_context(&context)?
+        .iter()
+        .map(|value| Topology::from_value(value, "127.0.0.1"))
+        .collect()
+}
+
+/// # Test: `test_mock_device_auth`
+///
+/// This test verifies that the mock device serves the topology context to clients
+/// using Basic, OAuth2 and custom login authentication, and rejects wrong credentials.
+#[tokio::test]
+async fn test_mock_device_auth() {
+    let basic = MockAuth::Basic {
+        username: "tapi".to_string(),
+        password: "secret".to_string(),
+    };
+    let mock = MockDevice::start(context(), basic).await.unwrap();
+    let collected = topologies(&client(
+        &mock,
+        json!({"username": "tapi", "password": "secret"}),
+    ))
+    .await
+    .unwrap();
+    assert_eq!(collected[0].links.len(), 1);
+    match topologies(&client(
+        &mock,
+        json!({"username": "tapi", "password": "wrong"}),
+    ))
+    .await
+    {
+        Err(Error::BadResponse(msg)) => {
+            assert_eq!(
+                msg,
+                "RESTCONF error: access-denied: Authentication required"
+            )
+        }
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(topologies) => panic!("Expected an error, but got {:?}", topologies),
+    }
+    // Subtrees are wrapped in their name, list elements are selected by UUID
+    let link = client(&mock, json!({"username": "tapi", "password": "secret"}))
+        .get(
+            &format!(
+                "{}/topology=4e537278-79f8-39ad-804b-f0b553cb2ffb/link=14219539-208b-35f5-b7cf-35a58e083490",
+                TOPOLOGY_CONTEXT_PATH
+            ),
+            &FetchOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(link["link"]["uuid"], "14219539-208b-35f5-b7cf-35a58e083490");
+
+    let oauth2 = MockAuth::Oauth2 {
+        token_path: "/oauth/token".to_string(),
+        username: "client".to_string(),
+        password: "secret".to_string(),
+        token: "eyJhbGciOiJIUzI1NiJ9".to_string(),
+    };
+    let mock = MockDevice::start(context(), oauth2).await.unwrap();
+    let auth = json!({"username": "client", "password": "secret", "grant_type": "client_credentials", "auth_url": "/oauth/token"});
+    assert_eq!(topologies(&client(&mock, auth)).await.unwrap().len(), 1);
+    let requests = mock.requests();
+    assert_eq!(requests[0].path, "/oauth/token");
+    assert_eq!(
+        requests[1].header("Authorization"),
+        Some("Bearer eyJhbGciOiJIUzI1NiJ9")
+    );
+
+    let custom = MockAuth::Custom {
+        login_path: "/api/login".to_string(),
+        body: json!({"user": "tapi", "pass": "secret"}),
+        token: "a1b2c3".to_string(),
+    };
+    let mock = MockDevice::start(context(), custom).await.unwrap();
+    let cookie = json!({"auth_body": {"user": "tapi", "pass": "secret"}, "auth_url": "/api/login"});
+    assert_eq!(topologies(&client(&mock, cookie)).await.unwrap().len(), 1);
+    let token = json!({"auth_body": {"user": "tapi", "pass": "secret"}, "auth_url": "/api/login", "token_pointer": "/token"});
+    assert_eq!(topologies(&client(&mock, token)).await.unwrap().len(), 1);
+    let wrong = json!({"auth_body": {"user": "tapi", "pass": "wrong"}, "auth_url": "/api/login"});
+    assert!(topologies(&client(&mock, wrong)).await.is_err());
+}
+
+/// # Test: `test_mock_device_faults`
+///
+/// This test checks the injected faults: server errors on the next requests, latency,
+/// and malformed fields rejected by the parsers.
+#[tokio::test]
+async fn test_mock_device_faults() {
+    let mock = MockDevice::start(context(), MockAuth::None).await.unwrap();
+    let client = client(&mock, json!({"token": "unused"}));
+
+    mock.inject(Fault::ServerErrors(2));
+    for _ in 0..2 {
+        match topologies(&client).await {
+            Err(Error::BadResponse(msg)) => {
+                assert_eq!(msg, "Device answered with status 500 Internal Server Error")
+            }
+            Err(err) => panic!("Unexpected error {:?}", err),
+            Ok(topologies) => panic!("Expected an error, but got {:?}", topologies),
+        }
+    }
+    assert_eq!(topologies(&client).await.unwrap().len(), 1);
+
+    mock.inject(Fault::Latency(Duration::from_millis(200)));
+    let start = Instant::now();
+    topologies(&client).await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    mock.inject(Fault::Latency(Duration::ZERO));
+
+    mock.inject(Fault::Malformed(
+        "/tapi-topology:topology-context/topology/0/link/0/uuid".to_string(),
+    ));
+    assert!(topologies(&client).await.is_err());
+    assert_eq!(mock.requests().len(), 5);
+}
@@ -0,0 +1,162 @@
+use backend::latency::{LatencyProbe, LatencySample};
+use backend::notifications::routing::EventType;
+use backend::notifications::thresholds::{
+    Threshold, ThresholdConfig, ThresholdMonitor, ThresholdState,
+}; // Import the threshold alerting
+use backend::notifications::{Alert, Severity}; // Import the alerts of the notification pipeline
+use backend::setup::config::Config;
+use backend::Error;
+use chrono::{Duration, Local};
+
+fn config() -> ThresholdConfig {
+    ThresholdConfig {
+        failed_polls: Some(3),
+        latency_ms: Some(500.0),
+        latency_minutes: 5,
+    }
+}
+
+/// Returns the state of a threshold alert, panicking on any other alert
+fn state(alert: Option<Alert>) -> Option<ThresholdState> {
+    alert.map(|alert| match alert {
+        Alert::Threshold { state, .. } => state,
+        alert => panic!("Expected a threshold alert, but got {:?}", alert),
+    })
+}
+
+/// # Test: `test_failed_polls_threshold`
+///
+/// This test verifies that an alert is raised once the consecutive failed polls reach
+/// the threshold, is not repeated while the device keeps failing, and is cleared by the
+/// next successful poll.
+#[test]
+fn test_failed_polls_threshold() {
+    let mut monitor = ThresholdMonitor::new();
+    let now = Local::now();
+
+    // A success without alert clears nothing
+    assert_eq!(monitor.poll_succeeded("10.0.0.1", now), None);
+    assert_eq!(
+        monitor.poll_failed("10.0.0.1", "timeout", &config(), now),
+        None
+    );
+    assert_eq!(
+        monitor.poll_failed("10.0.0.1", "timeout", &config(), now),
+        None
+    );
+
+    let alert = monitor
+        .poll_failed("10.0.0.1", "Device timeout", &config(), now)
+        .unwrap();
+    assert_eq!(alert.host(), "10.0.0.1");
+    assert_eq!(alert.severity(), Severity::Critical);
+    assert_eq!(EventType::of(&alert), EventType::Threshold);
+    match &alert {
+        Alert::Threshold {
+            threshold, detail, ..
+        } => {
+            assert_eq!(*threshold, Threshold::FailedPolls);
+            assert_eq!(detail, "3 consecutive polls failed, last: Device timeout");
+        }
+        alert => panic!("Expected a threshold alert, but got {:?}", alert),
+    }
+    assert!(monitor.is_raised("10.0.0.1", Threshold::FailedPolls));
+    assert_eq!(
+        monitor.poll_failed("10.0.0.1", "timeout", &config(), now),
+        None
+    );
+
+    let clear = monitor.poll_succeeded("10.0.0.1", now).unwrap();
+    assert_eq!(clear.severity(), Severity::Info);
+    assert_eq!(state(Some(clear)), Some(ThresholdState::Cleared));
+    assert!(!monitor.is_raised("10.0.0.1", Threshold::FailedPolls));
+
+    // Without threshold, failures raise nothing
+    let off = ThresholdConfig::default();
+    for _ in 0..10 {
+        assert_eq!(monitor.poll_failed("10.0.0.2", "timeout", &off, now), None);
+    }
+}
+
+/// # Test: `test_latency_threshold`
+///
+/// This test checks that a high latency raises an alert only once it lasted the
+/// configured minutes, that unanswered probes change nothing, and that a latency back
+/// below the threshold clears it.
+#[test]
+fn test_latency_threshold() {
+    let mut monitor = ThresholdMonitor::new();
+    let start = Local::now();
+    let sample = |minutes: i64, latency_ms: Option<f64>| LatencySample {
+        at: start + Duration::minutes(minutes),
+        probe: LatencyProbe::Tcp,
+        latency_ms,
+    };
+
+    assert_eq!(
+        monitor.latency("10.0.0.1", &sample(0, Some(800.0)), &config()),
+        None
+    );
+    assert_eq!(
+        monitor.latency("10.0.0.1", &sample(4, Some(900.0)), &config()),
+        None
+    );
+    assert_eq!(
+        monitor.latency("10.0.0.1", &sample(5, None), &config()),
+        None
+    );
+    let alert = monitor.latency("10.0.0.1", &sample(5, Some(700.0)), &config());
+    assert_eq!(alert.as_ref().map(Alert::severity), Some(Severity::Warning));
+    assert_eq!(state(alert), Some(ThresholdState::Raised));
+    assert_eq!(
+        monitor.latency("10.0.0.1", &sample(6, Some(700.0)), &config()),
+        None
+    );
+
+    let clear = monitor.latency("10.0.0.1", &sample(7, Some(20.0)), &config());
+    assert_eq!(state(clear), Some(ThresholdState::Cleared));
+
+    // A dip below the threshold restarts the slow period
+    assert_eq!(
+        monitor.latency("10.0.0.1", &sample(8, Some(800.0)), &config()),
+        None
+    );
+    assert_eq!(
+        monitor.latency("10.0.0.1", &sample(10, Some(20.0)), &config()),
+        None
+    );
+    assert_eq!(
+        monitor.latency("10.0.0.1", &sample(11, Some(800.0)), &config()),
+        None
+    );
+    assert_eq!(
+        monitor.latency("10.0.0.1", &sample(14, Some(800.0)), &config()),
+        None
+    );
+}
+
+/// # Test: `test_thresholds_config`
+///
+/// This test ensures that thresholds are off by default, parsed from the
+/// `[notifications.thresholds]` section, and refused when not positive.
+#[test]
+fn test_thresholds_config() {
+    assert_eq!(
+        Config::default().notifications.thresholds,
+        ThresholdConfig::default()
+    );
+    let config =
+        Config::parse("[notifications.thresholds]\nfailed_polls = 3\nlatency_ms = 500").unwrap();
+    assert_eq!(config.notifications.thresholds.failed_polls, Some(3));
+    assert_eq!(config.notifications.thresholds.latency_ms, Some(500.0));
+    assert_eq!(config.notifications.thresholds.latency_minutes, 5);
+
+    match Config::parse("[notifications.thresholds]\nfailed_polls = 0") {
+        Err(Error::Invalid(msg)) => assert_eq!(
+            msg,
+            "Invalid config: notifications.thresholds must be positive"
+        ),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(x) => panic!("Expected an error, but got {:?}", x),
+    }
+}
@@ -0,0 +1,123 @@
+use backend::models::device::Device; // Import the device model
+use backend::setup::config::Config; // Import the runtime configuration
+use backend::sync::Snapshot; // Import the agent snapshots
+use backend::tui::{view, Action, Browser, DeviceStatus, ObjectTab, Panel}; // Import the terminal UI
+use chrono::{Duration, Local};
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::Terminal;
+use serde_json::json;
+use uuid::Uuid;
+
+/// Builds a device of the registry
+fn device(host: &str, tags: &[&str], enabled: bool) -> Device {
+    Device::from_value(&json!({
+        "host": host,
+        "auth": {"username": "tapi", "password": "secret"},
+        "tags": tags,
+        "enabled": enabled
+    }))
+    .unwrap()
+}
+
+/// # Test: `test_tui_browser`
+///
+/// This test verifies the device statuses, the search of the device list, the
+/// drill-down into the links and nodes of a device, and that the screen renders.
+#[test]
+fn test_tui_browser() {
+    let now = Local::now();
+    let devices = vec![
+        device("10.0.0.1", &["core"], true),
+        device("10.0.0.2", &["edge"], true),
+        device("10.0.0.3", &["edge"], false),
+        device("10.0.0.4", &[], true),
+    ];
+    let mut browser = Browser::new(devices, Config::default());
+
+    let snapshot = |host: &str, age: Duration| Snapshot {
+        agent_id: Uuid::nil(),
+        sequence: 1,
+        host: host.to_string(),
+        collected_at: now - age,
+        topologies: vec![json!({
+            "uuid": "4e537278-79f8-39ad-804b-f0b553cb2ffb",
+            "node": [{"uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476", "name": [{"value-name": "NODE_NAME", "value": "Madrid"}]}],
+            "link": [
+                {"uuid": "14219539-208b-35f5-b7cf-35a58e083490", "name": [{"value-name": "LINK_NAME", "value": "MAD-BCN"}], "node-edge-point": []},
+                {"uuid": "2b1f6a3c-5d4e-4f7a-9b8c-1d2e3f4a5b6c", "name": [{"value-name": "LINK_NAME", "value": "MAD-SEV"}], "node-edge-point": []}
+            ]
+        })],
+        events: vec![],
+    };
+    browser.load_snapshots(
+        &[
+            snapshot("10.0.0.1", Duration::minutes(1)),
+            snapshot("10.0.0.2", Duration::hours(1)),
+        ],
+        now,
+    );
+    let statuses: Vec<DeviceStatus> = browser.devices.iter().map(|entry| entry.status).collect();
+    assert_eq!(
+        statuses,
+        [
+            DeviceStatus::Healthy,
+            DeviceStatus::Stale, // Older than three default poll intervals
+            DeviceStatus::Disabled,
+            DeviceStatus::NoData
+        ]
+    );
+
+    // Search devices by tag, then open the first match
+    for key in [
+        KeyCode::Char('/'),
+        KeyCode::Char('e'),
+        KeyCode::Char('d'),
+        KeyCode::Enter,
+    ] {
+        assert_eq!(browser.handle(key), Action::None);
+    }
+    assert_eq!(browser.visible_devices().len(), 2);
+    browser.handle(KeyCode::Enter);
+    assert_eq!(browser.panel, Panel::Objects);
+    assert_eq!(
+        browser.selected().unwrap().device.host.to_string(),
+        "10.0.0.2"
+    );
+    assert_eq!(browser.rows().len(), 2);
+
+    // Search links by name, then switch to the nodes
+    for key in [
+        KeyCode::Char('/'),
+        KeyCode::Char('s'),
+        KeyCode::Char('e'),
+        KeyCode::Char('v'),
+    ] {
+        browser.handle(key);
+    }
+    assert_eq!(browser.rows()[0].name, "MAD-SEV");
+    assert_eq!(browser.rows().len(), 1);
+    browser.handle(KeyCode::Esc);
+    browser.handle(KeyCode::Tab);
+    assert_eq!(browser.tab, ObjectTab::Nodes);
+    assert_eq!(browser.rows()[0].name, "Madrid");
+
+    let mut terminal = Terminal::new(TestBackend::new(140, 20)).unwrap();
+    terminal
+        .draw(|frame| view::render(frame, &browser))
+        .unwrap();
+    let screen: String = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect();
+    assert!(screen.contains("Devices (4)"));
+    assert!(screen.contains("10.0.0.2"));
+    assert!(screen.contains("Madrid"));
+
+    browser.handle(KeyCode::Esc);
+    assert_eq!(browser.panel, Panel::Devices);
+    assert_eq!(browser.handle(KeyCode::Char('q')), Action::Quit);
+}
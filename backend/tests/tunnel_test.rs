@@ -0,0 +1,130 @@
+use backend::models::device::Device; // Import the device model
+use backend::setup::config::Config; // Import the runtime configuration
+use backend::southbound::dns::CachedResolver;
+use backend::southbound::restconf::RestconfClient;
+use backend::southbound::tunnel::SshTunnel; // Import the SSH transport
+use backend::Error; // Import the custom error type from the backend module
+use serde_json::json;
+use std::net::SocketAddr;
+use std::path::Path;
+
+/// # Test: `test_jump_host_config`
+///
+/// This test verifies that a jump host is read from the device settings of the config,
+/// that its master session never prompts, and that the channels opened through it
+/// target the device port, IPv6 addresses bracketed, with the destination after `--`.
+#[test]
+fn test_jump_host_config() {
+    let config = Config::parse(
+        r#"
+        [devices."2001:db8::1".jump_host]
+        host = "bastion.example.com"
+        user = "ops"
+        identity_file = "/etc/device-manager/id_ed25519"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(config.jump_host("10.0.0.1"), None);
+    let jump = config.jump_host("2001:db8::1").unwrap();
+    assert_eq!(jump.port, 22);
+
+    let control = Path::new("/tmp/device-manager-ssh/master");
+    let args = jump.ssh_args(control).unwrap();
+    assert_eq!(
+        args[..4],
+        ["-N", "-M", "-S", "/tmp/device-manager-ssh/master"][..]
+    );
+    assert!(args.contains(&"BatchMode=yes".to_string()));
+
+    let args = jump.forward_args(control, "2001:db8::1", 443).unwrap();
+    assert_eq!(
+        args[args.len() - 8..],
+        [
+            "-W",
+            "[2001:db8::1]:443",
+            "-p",
+            "22",
+            "-i",
+            "/etc/device-manager/id_ed25519",
+            "--",
+            "ops@bastion.example.com",
+        ][..]
+    );
+    assert!(args.contains(&"ControlMaster=no".to_string()));
+
+    // A user read as an option is refused
+    let mut injected = jump.clone();
+    injected.user = "-oProxyCommand=touch /tmp/pwned".to_string();
+    match injected.forward_args(control, "2001:db8::1", 443) {
+        Err(Error::Invalid(msg)) => assert!(msg.contains("starting with '-'"), "{}", msg),
+        other => panic!("Expected an invalid jump host, but got {:?}", other),
+    }
+}
+
+/// # Test: `test_tunnel_client`
+///
+/// This test checks that a client of a tunneled device keeps addressing it by name on
+/// the local port of the tunnel, and that an IP device is addressed at the local end.
+#[tokio::test]
+async fn test_tunnel_client() {
+    let local: SocketAddr = "127.0.0.1:40001".parse().unwrap();
+    let device = |host: &str| {
+        Device::from_value(&json!({
+            "host": host,
+            "auth": {"username": "tapi", "password": "secret"}
+        }))
+        .unwrap()
+    };
+
+    let client = RestconfClient::with_tunnel(
+        device("controller.example.com"),
+        CachedResolver::new(),
+        local,
+    )
+    .unwrap();
+    assert_eq!(
+        client.url("/restconf/data"),
+        "https://controller.example.com:40001/restconf/data"
+    );
+
+    let client =
+        RestconfClient::with_tunnel(device("10.0.0.1"), CachedResolver::new(), local).unwrap();
+    assert_eq!(
+        client.url("/restconf/data"),
+        "https://127.0.0.1:40001/restconf/data"
+    );
+}
+
+/// # Test: `test_tunnel_failure`
+///
+/// This test verifies that a jump host refusing the SSH connection fails the tunnel
+/// with the `ssh` error instead of waiting for the forward.
+#[tokio::test]
+async fn test_tunnel_failure() {
+    // A port no one listens on
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+
+    let config = Config::parse(&format!(
+        r#"
+        [devices."10.0.0.1".jump_host]
+        host = "127.0.0.1"
+        port = {}
+        user = "ops"
+        "#,
+        port
+    ))
+    .unwrap();
+    let jump = config.jump_host("10.0.0.1").unwrap();
+
+    match SshTunnel::open(jump, "10.0.0.1", 443).await {
+        Err(Error::Unreachable(msg)) => assert!(
+            msg.starts_with("Device unreachable: SSH tunnel through 127.0.0.1 exited"),
+            "{}",
+            msg
+        ),
+        Err(err) => panic!("Unexpected error {:?}", err),
+        Ok(tunnel) => panic!("Expected an error, but got {}", tunnel.local_address()),
+    }
+}
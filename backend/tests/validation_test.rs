@@ -0,0 +1,111 @@
+use backend::models::direction::Direction; // Import the TAPI forwarding direction
+use backend::models::topology::Topology;
+use backend::setup::config::Config;
+use backend::validation::{validate, Rule}; // Import the topology validation
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const TOPOLOGY: &str = "00000000-0000-0000-0000-0000000000aa";
+const NODE_A: &str = "00000000-0000-0000-0000-00000000000a";
+const NODE_B: &str = "00000000-0000-0000-0000-00000000000b";
+const NODE_C: &str = "00000000-0000-0000-0000-00000000000c";
+const NODE_GONE: &str = "00000000-0000-0000-0000-00000000000f";
+
+/// A link payload between two nodes, endpoints in order
+fn link(uuid: u128, from: &str, to: &str, direction: &str) -> Value {
+    json!({
+        "uuid": Uuid::from_u128(uuid),
+        "direction": direction,
+        "node-edge-point": [
+            {"topology-uuid": TOPOLOGY, "node-uuid": from, "node-edge-point-uuid": from},
+            {"topology-uuid": TOPOLOGY, "node-uuid": to, "node-edge-point-uuid": to}
+        ]
+    })
+}
+
+/// A topology of `host` holding nodes A, B and C and the given links
+fn topology(host: &str, links: Vec<Value>) -> Topology {
+    Topology::from_value(
+        &json!({
+            "uuid": TOPOLOGY,
+            "node": [{"uuid": NODE_A}, {"uuid": NODE_B}, {"uuid": NODE_C}],
+            "link": links
+        }),
+        host,
+    )
+    .unwrap()
+}
+
+/// # Test: `test_validation_rules`
+///
+/// This test verifies that links to unknown nodes, unidirectional links without their
+/// mate and link UUIDs reported by two devices are found, and that only the selected
+/// rules run.
+#[test]
+fn test_validation_rules() {
+    let mut snapshots = HashMap::new();
+    snapshots.insert(
+        "10.0.0.1".to_string(),
+        vec![topology(
+            "10.0.0.1",
+            vec![
+                link(1, NODE_A, NODE_B, "BIDIRECTIONAL"),
+                link(2, NODE_A, NODE_GONE, "BIDIRECTIONAL"),
+                link(3, NODE_A, NODE_B, "UNIDIRECTIONAL"),
+                link(4, NODE_B, NODE_A, "UNIDIRECTIONAL"),
+                link(5, NODE_B, NODE_C, "UNIDIRECTIONAL"),
+                link(6, NODE_A, NODE_B, "BIDIRECTIONAL"),
+            ],
+        )],
+    );
+    snapshots.insert(
+        "10.0.0.2".to_string(),
+        vec![topology(
+            "10.0.0.2",
+            vec![link(6, NODE_A, NODE_B, "BIDIRECTIONAL")],
+        )],
+    );
+    assert_eq!(
+        snapshots["10.0.0.1"][0].links[2].direction,
+        Some(Direction::Unidirectional)
+    );
+
+    let violations = validate("10.0.0.1", &snapshots, &Rule::ALL);
+    let found: Vec<(Rule, Uuid)> = violations
+        .iter()
+        .map(|violation| (violation.rule, violation.link_uuid))
+        .collect();
+    // Link 3 and 4 are mates, 5 is left without one
+    assert_eq!(
+        found,
+        vec![
+            (Rule::UnknownNode, Uuid::from_u128(2)),
+            (Rule::MissingMate, Uuid::from_u128(5)),
+            (Rule::DuplicateLink, Uuid::from_u128(6)),
+        ]
+    );
+    assert_eq!(
+        violations[0].detail,
+        format!("Not found node {} in topology {}", NODE_GONE, TOPOLOGY)
+    );
+    assert_eq!(violations[2].detail, "Link uuid also reported by 10.0.0.2");
+    assert_eq!(violations[2].host, "10.0.0.1");
+
+    let only = validate("10.0.0.1", &snapshots, &[Rule::MissingMate]);
+    assert_eq!(only.len(), 1);
+    assert!(validate("10.0.0.1", &snapshots, &[]).is_empty());
+    assert!(validate("10.0.0.3", &snapshots, &Rule::ALL).is_empty());
+}
+
+/// # Test: `test_validation_config`
+///
+/// This test checks that every rule runs by default and that the `[validation]`
+/// section selects them.
+#[test]
+fn test_validation_config() {
+    assert_eq!(Config::default().validation.rules, Rule::ALL.to_vec());
+    let config = Config::parse("[validation]\nrules = [\"duplicate_link\"]").unwrap();
+    assert_eq!(config.validation.rules, vec![Rule::DuplicateLink]);
+    assert!(Config::parse("[validation]\nrules = [\"unknown\"]").is_err());
+}
@@ -0,0 +1,53 @@
+use backend::models::device::Device;
+use backend::models::parse::ParseOptions;
+use backend::models::topology::Topology;
+use backend::models::vendor::VendorProfile; // Import the vendor profiles
+use serde_json::json;
+use uuid::Uuid;
+
+/// # Test: `test_vendor_extensions`
+///
+/// This test verifies that the profile of a device is selected by its `vendor` metadata
+/// and that its fields are extracted into the `extensions` of the links.
+#[test]
+fn test_vendor_extensions() {
+    let profiles = VendorProfile::builtin();
+    let device = Device::from_value(&json!({
+        "host": "10.0.0.1",
+        "auth": {"username": "admin", "password": "admin"},
+        "metadata": {"vendor": "Ciena"}
+    }))
+    .unwrap();
+    let profile = VendorProfile::for_device(&device, &profiles);
+    assert_eq!(
+        profile.map(|profile| profile.vendor.as_str()),
+        Some("ciena")
+    );
+
+    let topology = json!({
+        "uuid": Uuid::from_u128(1),
+        "link": [{
+            "uuid": Uuid::from_u128(2),
+            "node-edge-point": [],
+            "tapi-ciena-link-extensions:layer-protocol-qualifier": "tapi-ciena-protocol-extensions:ETHERNET",
+            "tapi-ciena-link-extensions:signal-content-type": "IP"
+        }]
+    });
+
+    let options = ParseOptions {
+        profile,
+        ..Default::default()
+    };
+    let (parsed, _) = Topology::parse(&topology, "10.0.0.1", &options).unwrap();
+    assert_eq!(
+        json!(parsed.links[0].extensions),
+        json!({
+            "layer-protocol-qualifier": "tapi-ciena-protocol-extensions:ETHERNET",
+            "signal-content-type": "IP"
+        })
+    );
+
+    // Without a profile the vendor fields are left out
+    let (parsed, _) = Topology::parse(&topology, "10.0.0.1", &ParseOptions::default()).unwrap();
+    assert!(parsed.links[0].extensions.is_empty());
+}
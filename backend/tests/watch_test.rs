@@ -0,0 +1,63 @@
+use backend::setup::watch::FileWatcher; // Import the shared file watcher
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// # Test: `test_file_watchers_share_a_directory`
+///
+/// This test verifies that files of the same directory are reloaded independently, and
+/// that dropping the watcher of one of them keeps the other one watched.
+#[test]
+fn test_file_watchers_share_a_directory() {
+    let directory = std::env::temp_dir().join(format!("watch_test_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let (first, second) = (directory.join("first.toml"), directory.join("second.yaml"));
+    std::fs::write(&first, "a").unwrap();
+    std::fs::write(&second, "b").unwrap();
+
+    let reloads: Arc<Mutex<Vec<String>>> = Arc::default();
+    let watcher = |name: &'static str| {
+        let reloads = Arc::clone(&reloads);
+        move |content: &str| {
+            reloads
+                .lock()
+                .unwrap()
+                .push(format!("{}={}", name, content));
+            Ok(())
+        }
+    };
+    let first_watcher = FileWatcher::start(&first, "first", watcher("first")).unwrap();
+    let second_watcher = FileWatcher::start(&second, "second", watcher("second")).unwrap();
+
+    let wait_for = |expected: &str| {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !reloads
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|reload| reload == expected)
+        {
+            assert!(Instant::now() < deadline, "{} was not reloaded", expected);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    };
+    std::fs::write(&first, "c").unwrap();
+    wait_for("first=c");
+    assert!(reloads
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|reload| reload.starts_with("first=")));
+
+    // The directory stays watched for the second file, once the reloads of the
+    // previous write are over
+    std::thread::sleep(Duration::from_millis(500));
+    drop(first_watcher);
+    std::fs::write(&first, "d").unwrap();
+    std::fs::write(&second, "e").unwrap();
+    wait_for("second=e");
+    assert!(!reloads.lock().unwrap().contains(&"first=d".to_string()));
+
+    drop(second_watcher);
+    let _ = std::fs::remove_dir_all(&directory);
+}
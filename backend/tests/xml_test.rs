@@ -0,0 +1,83 @@
+use backend::models::topology::Topology; // Import the topology model
+use backend::southbound::xml::to_json; // Import the XML payload conversion
+use backend::Error;
+use serde_json::json;
+
+/// A topology context as answered in `application/yang-data+xml`
+const CONTEXT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<topology-context xmlns="urn:onf:otcc:yang:tapi-topology">
+  <topology>
+    <uuid>4e537278-79f8-39ad-804b-f0b553cb2ffb</uuid>
+    <node>
+      <uuid>62d11f13-db6c-3398-8a83-5fac0b2b7476</uuid>
+      <name><value-name>NODE_NAME</value-name><value>MAD &amp; BCN</value></name>
+      <layer-protocol-name>ETH</layer-protocol-name>
+      <owned-node-edge-point>
+        <uuid>65a39427-3055-3ba4-9e15-0ebed4974577</uuid>
+        <layer-protocol-name>ETH</layer-protocol-name>
+      </owned-node-edge-point>
+    </node>
+    <link>
+      <uuid>14219539-208b-35f5-b7cf-35a58e083490</uuid>
+      <direction>BIDIRECTIONAL</direction>
+      <node-edge-point>
+        <node-uuid>62d11f13-db6c-3398-8a83-5fac0b2b7476</node-uuid>
+        <node-edge-point-uuid>65a39427-3055-3ba4-9e15-0ebed4974577</node-edge-point-uuid>
+      </node-edge-point>
+      <node-edge-point>
+        <node-uuid>7b0c973a-996a-3409-ad2f-d173354bfdb7</node-uuid>
+        <node-edge-point-uuid>63366151-aeb4-3dfd-af66-d471b353aa1c</node-edge-point-uuid>
+      </node-edge-point>
+      <signal-content-type xmlns="urn:ciena:params:xml:ns:yang:tapi-ciena-link-extensions">IP</signal-content-type>
+    </link>
+  </topology>
+</topology-context>"#;
+
+/// # Test: `test_xml_to_json`
+///
+/// This test verifies that an XML topology context is converted into its JSON encoding:
+/// lists of a single entry are arrays, leaves are strings, and members of another module
+/// are qualified by it.
+#[test]
+fn test_xml_to_json() {
+    let value = to_json(CONTEXT).unwrap();
+    let topology = &value["tapi-topology:topology-context"]["topology"][0];
+    assert_eq!(
+        topology["node"][0],
+        json!({
+            "uuid": "62d11f13-db6c-3398-8a83-5fac0b2b7476",
+            "name": [{"value-name": "NODE_NAME", "value": "MAD & BCN"}],
+            "layer-protocol-name": ["ETH"],
+            "owned-node-edge-point": [{"uuid": "65a39427-3055-3ba4-9e15-0ebed4974577", "layer-protocol-name": "ETH"}]
+        })
+    );
+    let link = &topology["link"][0];
+    assert_eq!(link["node-edge-point"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        link["tapi-ciena-link-extensions:signal-content-type"],
+        json!("IP")
+    );
+
+    // Parsed like a JSON payload
+    let topologies = Topology::list_from_context(&value).unwrap();
+    let topology = Topology::from_value(&topologies[0], "10.0.0.1").unwrap();
+    assert_eq!(topology.nodes.len(), 1);
+    assert_eq!(topology.links.len(), 1);
+    assert_eq!(topology.links[0].node_edge_points.len(), 2);
+}
+
+/// # Test: `test_xml_to_json_invalid`
+///
+/// This test checks that malformed documents are rejected.
+#[test]
+fn test_xml_to_json_invalid() {
+    for xml in ["", "<topology><uuid>x</topology>", "<a:topology/>"] {
+        match to_json(xml) {
+            Err(Error::BadResponse(msg)) => {
+                assert!(msg.starts_with("Invalid XML payload"), "{}", msg)
+            }
+            Err(err) => panic!("Unexpected error {:?}", err),
+            Ok(x) => panic!("Expected an error, but got {:?}", x),
+        }
+    }
+}